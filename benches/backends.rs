@@ -0,0 +1,120 @@
+//! Compares the backends shipped with the crate on the workloads of
+//! `oscoin_graph_api::bench`, at the graph sizes of `bench::SIZES`.
+//!
+//! ```text
+//! cargo bench --bench backends
+//! cargo bench --bench backends --features sled-storage
+//! ```
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::hint::black_box;
+
+use oscoin_graph_api::bench::{Workload, SIZES};
+use oscoin_graph_api::mem::{ArenaGraph, EdgeListGraph};
+use oscoin_graph_api::snapshot::compact::{self, CompactGraph, Encoding};
+
+fn workloads() -> Vec<(usize, Workload)> {
+    SIZES
+        .iter()
+        .map(|&n| (n, Workload::random(n, Workload::DEFAULT_DEGREE, 0)))
+        .collect()
+}
+
+fn compact(graph: &EdgeListGraph, encoding: Encoding) -> CompactGraph<Vec<u8>> {
+    let mut bytes = Vec::new();
+    compact::write_with(graph, &mut bytes, encoding).expect("workloads have no custom edges");
+    CompactGraph::from_bytes(bytes).expect("written snapshots are valid")
+}
+
+#[cfg(feature = "sled-storage")]
+fn sled(workload: &Workload) -> oscoin_graph_api::storage::sled::SledGraph {
+    use oscoin_graph_api::storage::sled::{SledConfig, SledGraph};
+    use oscoin_graph_api::storage::PersistentGraph;
+
+    let path = std::env::temp_dir().join(format!("oscoin-bench-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&path);
+    let mut graph = SledGraph::open(SledConfig::new(path)).expect("the database can be opened");
+    workload.insert(&mut graph);
+    graph
+}
+
+/// Benchmark a read-only workload on every backend.
+macro_rules! bench_reads {
+    ($c:expr, $name:expr, $run:ident) => {{
+        let mut group = $c.benchmark_group($name);
+        for (n, workload) in workloads() {
+            let mem: EdgeListGraph = workload.build();
+            group.bench_with_input(BenchmarkId::new("mem", n), &mem, |b, g| {
+                b.iter(|| black_box(workload.$run(g)))
+            });
+            let arena: ArenaGraph = workload.build();
+            group.bench_with_input(BenchmarkId::new("arena", n), &arena, |b, g| {
+                b.iter(|| black_box(workload.$run(g)))
+            });
+            let snapshot = compact(&mem, Encoding::Plain);
+            group.bench_with_input(BenchmarkId::new("compact", n), &snapshot, |b, g| {
+                b.iter(|| black_box(workload.$run(g)))
+            });
+            let encoding = Encoding::Compressed {
+                edge_dictionary: true,
+            };
+            let snapshot = compact(&mem, encoding);
+            group.bench_with_input(BenchmarkId::new("compressed", n), &snapshot, |b, g| {
+                b.iter(|| black_box(workload.$run(g)))
+            });
+            #[cfg(feature = "sled-storage")]
+            {
+                let db = sled(&workload);
+                group.bench_with_input(BenchmarkId::new("sled", n), &db, |b, g| {
+                    b.iter(|| black_box(workload.$run(g)))
+                });
+            }
+        }
+        group.finish();
+    }};
+}
+
+fn neighbors(c: &mut Criterion) {
+    bench_reads!(c, "neighbors", neighbors);
+}
+
+fn edges_directed(c: &mut Criterion) {
+    bench_reads!(c, "edges_directed", edges_directed);
+}
+
+fn subgraphs(c: &mut Criterion) {
+    bench_reads!(c, "subgraph", subgraphs);
+}
+
+fn rank(c: &mut Criterion) {
+    bench_reads!(c, "rank", rank);
+}
+
+/// Snapshots are read-only: only the writable backends insert.
+fn bulk_insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bulk_insert");
+    group.sample_size(10);
+    for (n, workload) in workloads() {
+        group.bench_with_input(BenchmarkId::new("mem", n), &workload, |b, w| {
+            b.iter(|| black_box(w.build::<EdgeListGraph>()))
+        });
+        group.bench_with_input(BenchmarkId::new("arena", n), &workload, |b, w| {
+            b.iter(|| black_box(w.build::<ArenaGraph>()))
+        });
+        #[cfg(feature = "sled-storage")]
+        group.bench_with_input(BenchmarkId::new("sled", n), &workload, |b, w| {
+            b.iter(|| black_box(sled(w)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    neighbors,
+    edges_directed,
+    bulk_insert,
+    subgraphs,
+    rank
+);
+criterion_main!(benches);
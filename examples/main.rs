@@ -11,9 +11,8 @@ type Id = u64;
 /// in the `NodeType`.
 type NodeData = types::NodeType;
 
-/// Data stored in edges. For the sake of this example, we are interested only
-/// in the `EdgeType`.
-type EdgeData = types::EdgeType;
+/// Data stored in edges: the `EdgeType` and the weight of the edge.
+type EdgeData = types::EdgeData<f64>;
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct Node {
@@ -33,14 +32,9 @@ pub struct Edge {
     from: Id,
     to: Id,
     data: EdgeData,
-    weight: f64,
 }
 
 impl oscoin::Edge<f64, Id, EdgeData> for Edge {
-    fn weight(&self) -> f64 {
-        self.weight
-    }
-
     fn source(&self) -> &Id {
         &self.from
     }
@@ -50,7 +44,7 @@ impl oscoin::Edge<f64, Id, EdgeData> for Edge {
     }
 
     fn edge_type(&self) -> &types::EdgeType {
-        &self.data
+        &self.data.edge_type
     }
 }
 
@@ -88,20 +82,12 @@ impl oscoin::GraphObject for Node {
     }
 }
 
+#[derive(Default)]
 struct Network {
     edges: BTreeMap<Id, Edge>,
     nodes: BTreeMap<Id, Node>,
 }
 
-impl Default for Network {
-    fn default() -> Self {
-        Self {
-            edges: BTreeMap::new(),
-            nodes: BTreeMap::new(),
-        }
-    }
-}
-
 impl oscoin::Graph for Network {
     type Node = self::Node;
     type Edge = self::Edge;
@@ -119,7 +105,7 @@ impl oscoin::Graph for Network {
         self.edges.get(id)
     }
 
-    fn nodes(&self) -> oscoin::Nodes<Self::Node> {
+    fn nodes(&self) -> oscoin::Nodes<'_, Self::Node> {
         let vec: Vec<&Node> = self.nodes.values().collect();
         oscoin::Nodes {
             range: vec.into_iter(),
@@ -129,7 +115,7 @@ impl oscoin::Graph for Network {
     fn neighbors(
         &self,
         node: &<Self::Node as oscoin::GraphObject>::Id,
-    ) -> oscoin::Nodes<Self::Node> {
+    ) -> oscoin::Nodes<'_, Self::Node> {
         let mut ns: Vec<&Node> = Vec::new();
 
         for e in self.edges.values() {
@@ -145,7 +131,10 @@ impl oscoin::Graph for Network {
         }
     }
 
-    fn edges(&self, node: &<Self::Node as oscoin::GraphObject>::Id) -> oscoin::Edges<Self::Edge> {
+    fn edges(
+        &self,
+        node: &<Self::Node as oscoin::GraphObject>::Id,
+    ) -> oscoin::Edges<'_, Self::Edge> {
         let mut edges = Vec::new();
 
         for e in self.edges.values() {
@@ -162,23 +151,16 @@ impl oscoin::Graph for Network {
         &self,
         node: &<Self::Node as oscoin::GraphObject>::Id,
         dir: oscoin::Direction,
-    ) -> oscoin::EdgeRefs<oscoin::Id<Self::Node>, oscoin::Id<Self::Edge>> {
+    ) -> oscoin::EdgeRefs<'_, oscoin::Id<Self::Node>, oscoin::Id<Self::Edge>, self::EdgeData> {
         let mut refs = Vec::new();
 
         for e in self.edges.values() {
-            if dir == oscoin::Direction::Outgoing && e.from == *node {
+            if dir.matches(node, &e.from, &e.to) {
                 refs.push(oscoin::EdgeRef {
                     from: &e.from,
                     to: &e.to,
                     id: &e.id,
-                    edge_type: &e.data,
-                })
-            } else if dir == oscoin::Direction::Incoming && e.to == *node {
-                refs.push(oscoin::EdgeRef {
-                    from: &e.from,
-                    to: &e.to,
-                    id: &e.id,
-                    edge_type: &e.data,
+                    data: &e.data,
                 })
             }
         }
@@ -205,15 +187,12 @@ impl oscoin::GraphWriter for Network {
         to: &oscoin::Id<Node>,
         data: EdgeData,
     ) {
-        // In this example we are modelling the `EdgeData` as a bytes blob,
-        // but in practice we should be able to extract a weight out of that.
         self.edges.insert(
             id,
             Edge {
                 id,
                 from: *from,
                 to: *to,
-                weight: 0.0,
                 data,
             },
         );
@@ -223,12 +202,19 @@ impl oscoin::GraphWriter for Network {
         self.edges.remove(&id);
     }
 
-    fn nodes_mut(&mut self) -> oscoin::NodesMut<Self::Node> {
+    fn nodes_mut(&mut self) -> oscoin::NodesMut<'_, Self::Node> {
         let vec: Vec<&mut Node> = self.nodes.values_mut().collect();
         oscoin::NodesMut {
             range: vec.into_iter(),
         }
     }
+
+    fn edges_mut(&mut self) -> oscoin::EdgesMut<'_, Self::Edge> {
+        let vec: Vec<&mut Edge> = self.edges.values_mut().collect();
+        oscoin::EdgesMut {
+            range: vec.into_iter(),
+        }
+    }
 }
 
 impl oscoin::GraphDataWriter for Network {
@@ -247,8 +233,27 @@ impl oscoin::GraphDataWriter for Network {
     }
 }
 
+/// `Network` accepts every valid checkpoint.
+impl oscoin::ledger::LedgerGraph for Network {}
+
+impl oscoin::IntoGraphParts for Network {
+    fn into_parts(self) -> (oscoin::IntoNodes<Node>, oscoin::IntoEdges<Edge>) {
+        let nodes: Vec<Node> = self.nodes.into_values().collect();
+        let edges: Vec<Edge> = self.edges.into_values().collect();
+
+        (
+            oscoin::IntoNodes {
+                range: nodes.into_iter(),
+            },
+            oscoin::IntoEdges {
+                range: edges.into_iter(),
+            },
+        )
+    }
+}
+
 fn main() {
-    use oscoin::{Graph, GraphDataWriter, GraphWriter};
+    use oscoin::{Graph, GraphDataWriter, GraphExt, GraphWriter};
 
     let mut g = Network::default();
     g.add_node(
@@ -263,7 +268,17 @@ fn main() {
             contributions_from_all_users: 1,
         },
     );
-    g.add_edge(0x3, &0x1, &0x2, types::EdgeType::Dependency);
+    g.add_edge(
+        0x3,
+        &0x1,
+        &0x2,
+        types::EdgeData {
+            edge_type: types::EdgeType::Dependency,
+            weight: 1.0,
+            history: Default::default(),
+            timestamps: Default::default(),
+        },
+    );
 
     assert_eq!(
         g.neighbors(&0x1).collect::<Vec<&Node>>(),
@@ -278,6 +293,26 @@ fn main() {
         vec![g.get_edge(&0x3)]
     );
 
+    // A parallel edge lists the neighbor twice, unless asked for distinct
+    // neighbors.
+    g.add_edge(
+        0x4,
+        &0x1,
+        &0x2,
+        types::EdgeData {
+            edge_type: types::EdgeType::Dependency,
+            weight: 1.0,
+            history: Default::default(),
+            timestamps: Default::default(),
+        },
+    );
+    assert_eq!(g.neighbors(&0x1).count(), 2);
+    assert_eq!(
+        g.distinct_neighbors(&0x1, oscoin::Direction::Both)
+            .collect::<Vec<&Node>>(),
+        vec![g.get_node(&0x2).unwrap()]
+    );
+
     *g.node_data_mut(&0x1).unwrap() = types::NodeType::Project {
         contributions_from_all_users: 2,
     };
@@ -287,100 +322,50 @@ fn main() {
             contributions_from_all_users: 2
         }
     );
-}
-
-mod ledger {
-    use oscoin_graph_api as oscoin;
-    use oscoin_graph_api::types;
-    use oscoin_graph_api::{GraphDataWriter, GraphWriter};
-
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
-
-    type H256 = [u8; 32];
 
-    struct Dep {
-        node_id: super::Id,
-        is_added: bool,
-    }
+    ledger();
+}
 
-    struct Contrib {
-        node_id: super::Id,
-        /// How many contributions.
-        contributions: u32,
-    }
+/// Apply a checkpoint to the osrank layer.
+fn ledger() {
+    use oscoin::ledger::{Checkpoint, CheckpointApplier, Contrib, Dep};
+    use oscoin::{Graph, GraphAPI, GraphWriter, Layer, LayerInfo, LayeredGraphs};
 
-    // An example ledger implementation that uses the APIs defined.
-    struct Ledger<T> {
-        api: T,
-    }
-
-    impl<T> Ledger<T>
-    where
-        T: oscoin::GraphAPI<Graph = super::Network>,
-    {
-        fn checkpoint(
-            &mut self,
-            id: super::Id,
-            _version: &[u8],
-            _hash: H256,
-            deps: &[Dep],
-            contribs: &[Contrib],
-        ) {
-            // The node-id must be unique for all (project-id, project-hash) pairs.
-            // TODO
-            let node_id = id;
-
-            // Get a mutable ref to the osrank graph.
-            let graph = self.api.graph_mut(&oscoin::Layer("osrank")).unwrap();
-
-            // Add the new checkpoint node to the graph.
-            graph.add_node(
-                node_id,
-                types::NodeType::Project {
-                    contributions_from_all_users: 0,
-                },
-            );
-
-            for d in deps.iter() {
-                let edge_id = self::edge_id(node_id, d.node_id);
-
-                // If we're adding a dependency, add a `project -> project` link.
-                // If we're removing one, remove the link.
-                if d.is_added {
-                    graph.add_edge(edge_id, &node_id, &d.node_id, types::EdgeType::Dependency);
-                } else {
-                    graph.remove_edge(edge_id);
-                }
-            }
+    let osrank = Layer::from_static("osrank");
+    let mut api = LayeredGraphs::new();
+    let mut g = Network::default();
+    g.add_node(
+        0x2,
+        types::NodeType::Project {
+            contributions_from_all_users: 0,
+        },
+    );
+    api.add_layer(osrank.clone(), LayerInfo::new(0, "osrank"), g);
+
+    let checkpoint = Checkpoint {
+        project: 0x1,
+        version: b"0.1.0".to_vec(),
+        hash: types::Hash32::default(),
+        deps: vec![Dep {
+            node_id: 0x2,
+            is_added: true,
+        }],
+        contributions: vec![Contrib {
+            node_id: 0x3,
+            contributions: 5,
+        }],
+    };
+    let ids = types::SipIdGenerator::default();
+    api.apply_checkpoint(&osrank, &checkpoint, &ids).unwrap();
+    // Applying the same checkpoint twice would duplicate its edges.
+    assert!(api.apply_checkpoint(&osrank, &checkpoint, &ids).is_err());
 
-            for c in contribs.iter() {
-                // Add `project -> contribution` link.
-                graph.add_edge(
-                    self::edge_id(node_id, c.node_id),
-                    &node_id,
-                    &c.node_id,
-                    types::EdgeType::ProjectToUserContribution(c.contributions),
-                );
-                // Add `contribution -> project` link.
-                graph.add_edge(
-                    self::edge_id(node_id, c.node_id),
-                    &c.node_id,
-                    &node_id,
-                    types::EdgeType::UserToProjectContribution(c.contributions),
-                );
-
-                // increment the total contributions.
-                let node_type = graph.node_data_mut(&node_id).unwrap();
-                node_type.add_contributions(c.contributions);
-            }
+    let g = api.graph(&osrank).unwrap();
+    assert_eq!(g.nodes().count(), 3);
+    assert_eq!(
+        g.get_node(&0x1).unwrap().data,
+        types::NodeType::Project {
+            contributions_from_all_users: 5
         }
-    }
-
-    fn edge_id(from: super::Id, to: super::Id) -> super::Id {
-        let mut hasher = DefaultHasher::new();
-        from.hash(&mut hasher);
-        to.hash(&mut hasher);
-        hasher.finish()
-    }
+    );
 }
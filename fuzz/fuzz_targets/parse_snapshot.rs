@@ -0,0 +1,6 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use oscoin_graph_api::fuzz::fuzz_parse_snapshot;
+
+fuzz_target!(|data: &[u8]| fuzz_parse_snapshot(data));
@@ -0,0 +1,50 @@
+/* C declarations of the `ffi` module of oscoin-graph-api, built with the
+ * `ffi` feature. See `src/ffi.rs` for the documentation of every function. */
+
+#ifndef OSCOIN_GRAPH_H
+#define OSCOIN_GRAPH_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#define OSC_OK 0
+#define OSC_NULL_POINTER -1
+#define OSC_INVALID_TYPE -2
+#define OSC_NOT_FOUND -3
+#define OSC_RANK_FAILED -4
+
+/* Node type codes. */
+#define OSC_NODE_USER 0
+#define OSC_NODE_PROJECT 1
+#define OSC_NODE_ORG 2
+#define OSC_NODE_CHECKPOINT 3
+
+/* Edge type codes. */
+#define OSC_EDGE_PROJECT_TO_USER_CONTRIBUTION 0
+#define OSC_EDGE_USER_TO_PROJECT_CONTRIBUTION 1
+#define OSC_EDGE_PROJECT_TO_USER_MEMBERSHIP 2
+#define OSC_EDGE_USER_TO_PROJECT_MEMBERSHIP 3
+#define OSC_EDGE_DEPENDENCY 4
+#define OSC_EDGE_ORG_TO_PROJECT_OWNERSHIP 5
+#define OSC_EDGE_PROJECT_TO_ORG_OWNERSHIP 6
+#define OSC_EDGE_DONATION 7
+
+typedef struct OscGraph OscGraph;
+
+OscGraph *osc_graph_new(void);
+void osc_graph_free(OscGraph *graph);
+
+size_t osc_graph_node_count(const OscGraph *graph);
+size_t osc_graph_edge_count(const OscGraph *graph);
+
+int32_t osc_graph_add_node(OscGraph *graph, uint64_t id, uint8_t node_type,
+                           uint32_t contributions);
+int32_t osc_graph_add_edge(OscGraph *graph, uint64_t id, uint64_t from,
+                           uint64_t to, uint8_t edge_type, uint32_t value,
+                           double weight);
+
+int32_t osc_graph_rank(OscGraph *graph, double project_damping,
+                       double account_damping, uint32_t steps);
+int32_t osc_graph_node_rank(const OscGraph *graph, uint64_t id, double *rank);
+
+#endif /* OSCOIN_GRAPH_H */
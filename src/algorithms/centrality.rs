@@ -0,0 +1,209 @@
+//! Classical centrality measures, as baselines to compare osrank against.
+//!
+//! Like the rank algorithms, they annotate every node with its score as a
+//! `NodeRank`.
+
+use num_traits::Float;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+use crate::cancel::CancellationToken;
+use crate::types::{AlgorithmError, NodeRank};
+use crate::{Direction, Graph, GraphAlgorithm, GraphAnnotator, GraphObject, Id};
+
+/// The scores of the nodes of a graph.
+type Scores<G> = HashMap<Id<<G as Graph>::Node>, f64>;
+
+/// Annotate every node with its score, in id order, so that every node
+/// emits the same annotations for the same graph.
+fn annotate<G, A, W>(scores: &Scores<G>, annotator: &mut A)
+where
+    G: Graph,
+    Id<G::Node>: Clone + Ord,
+    A: GraphAnnotator<Annotation = (Id<G::Node>, NodeRank<W>)>,
+    W: Float,
+{
+    let mut sorted: Vec<_> = scores.iter().collect();
+    sorted.sort_by(|a, b| a.0.cmp(b.0));
+    for (id, score) in sorted {
+        let rank = W::from(*score).unwrap_or_else(W::zero);
+        annotator.annotate_graph((id.clone(), NodeRank { rank }));
+    }
+}
+
+/// Betweenness centrality, computed with Brandes' algorithm: the score of a
+/// node is the number of shortest paths between other nodes going through
+/// it, following edges in their direction. Edge weights are ignored.
+#[derive(Clone, Debug, Default)]
+pub struct Betweenness {
+    /// Whether to divide scores by `(n - 1)(n - 2)`, the number of pairs of
+    /// other nodes, so that they fall in `[0, 1]`.
+    pub normalized: bool,
+}
+
+impl Betweenness {
+    fn run<G>(&self, graph: &G, token: &CancellationToken) -> Result<Scores<G>, AlgorithmError>
+    where
+        G: Graph,
+        Id<G::Node>: Clone + Eq + Hash,
+    {
+        let ids: Vec<&Id<G::Node>> = graph.nodes().map(|n| n.id()).collect();
+        let mut scores: HashMap<&Id<G::Node>, f64> = ids.iter().map(|id| (*id, 0.0)).collect();
+        trace_span!(
+            "betweenness",
+            nodes = ids.len(),
+            normalized = self.normalized
+        );
+
+        for source in ids.iter().copied() {
+            token.check()?;
+
+            // Single-source shortest paths, by breadth-first search.
+            let mut stack = Vec::new();
+            let mut predecessors: HashMap<&Id<G::Node>, Vec<&Id<G::Node>>> = HashMap::new();
+            let mut paths: HashMap<&Id<G::Node>, f64> = HashMap::new();
+            let mut distance: HashMap<&Id<G::Node>, usize> = HashMap::new();
+            let mut queue = VecDeque::new();
+
+            paths.insert(source, 1.0);
+            distance.insert(source, 0);
+            queue.push_back(source);
+
+            while let Some(v) = queue.pop_front() {
+                stack.push(v);
+                let d = distance[v];
+
+                for e in graph.edges_directed(v, Direction::Outgoing) {
+                    let w = e.to;
+                    if graph.get_node(w).is_none() {
+                        continue;
+                    }
+                    if !distance.contains_key(w) {
+                        distance.insert(w, d + 1);
+                        queue.push_back(w);
+                    }
+                    if distance[w] == d + 1 {
+                        *paths.entry(w).or_insert(0.0) += paths[v];
+                        predecessors.entry(w).or_default().push(v);
+                    }
+                }
+            }
+
+            // Accumulate dependencies, from the farthest nodes back.
+            let mut dependency: HashMap<&Id<G::Node>, f64> = HashMap::new();
+            while let Some(w) = stack.pop() {
+                let delta = dependency.get(w).copied().unwrap_or(0.0);
+                for v in predecessors.get(w).into_iter().flatten() {
+                    *dependency.entry(v).or_insert(0.0) += paths[v] / paths[w] * (1.0 + delta);
+                }
+                if w != source {
+                    *scores.entry(w).or_insert(0.0) += delta;
+                }
+            }
+        }
+
+        let n = ids.len() as f64;
+        let scale = if self.normalized && n > 2.0 {
+            1.0 / ((n - 1.0) * (n - 2.0))
+        } else {
+            1.0
+        };
+        Ok(scores
+            .into_iter()
+            .map(|(id, score)| (id.clone(), score * scale))
+            .collect())
+    }
+}
+
+impl<G, A, W> GraphAlgorithm<G, A> for Betweenness
+where
+    G: Graph,
+    Id<G::Node>: Clone + Ord + Hash,
+    A: GraphAnnotator<Annotation = (Id<G::Node>, NodeRank<W>)>,
+    W: Float,
+{
+    type Context = ();
+    /// The score of every node.
+    type Output = Scores<G>;
+    type Error = AlgorithmError;
+    /// The algorithm is deterministic and ignores its seed.
+    type RngSeed = u64;
+    type Annotation = (Id<G::Node>, NodeRank<W>);
+
+    fn execute(
+        &self,
+        context: &mut (),
+        graph: &G,
+        annotator: &mut A,
+        seed: u64,
+    ) -> Result<Self::Output, Self::Error> {
+        self.execute_with(context, graph, annotator, seed, &CancellationToken::new())
+    }
+
+    fn execute_with(
+        &self,
+        _context: &mut (),
+        graph: &G,
+        annotator: &mut A,
+        _seed: u64,
+        token: &CancellationToken,
+    ) -> Result<Self::Output, Self::Error> {
+        let scores = self.run(graph, token)?;
+        annotate::<G, A, W>(&scores, annotator);
+        Ok(scores)
+    }
+}
+
+/// Degree centrality: the score of a node is its number of edges, divided
+/// by `n - 1` if normalized.
+#[derive(Debug, Default)]
+pub struct DegreeCentrality {
+    /// The edges counted, or all the edges of the node if `None`.
+    pub direction: Option<Direction>,
+    /// Whether to divide scores by `n - 1`.
+    pub normalized: bool,
+}
+
+impl<G, A, W> GraphAlgorithm<G, A> for DegreeCentrality
+where
+    G: Graph,
+    Id<G::Node>: Clone + Ord + Hash,
+    A: GraphAnnotator<Annotation = (Id<G::Node>, NodeRank<W>)>,
+    W: Float,
+{
+    type Context = ();
+    /// The score of every node.
+    type Output = Scores<G>;
+    type Error = AlgorithmError;
+    /// The algorithm is deterministic and ignores its seed.
+    type RngSeed = u64;
+    type Annotation = (Id<G::Node>, NodeRank<W>);
+
+    fn execute(
+        &self,
+        _context: &mut (),
+        graph: &G,
+        annotator: &mut A,
+        _seed: u64,
+    ) -> Result<Self::Output, Self::Error> {
+        let n = graph.nodes().count() as f64;
+        let scale = if self.normalized && n > 1.0 {
+            1.0 / (n - 1.0)
+        } else {
+            1.0
+        };
+        let scores: Scores<G> = graph
+            .nodes()
+            .map(|node| {
+                let degree = match self.direction {
+                    Some(dir) => graph.edges_directed(node.id(), dir).len(),
+                    None => graph.edges(node.id()).count(),
+                };
+                (node.id().clone(), degree as f64 * scale)
+            })
+            .collect();
+
+        annotate::<G, A, W>(&scores, annotator);
+        Ok(scores)
+    }
+}
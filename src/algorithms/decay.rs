@@ -0,0 +1,103 @@
+//! Aging ranks between executions.
+//!
+//! Ranks stored on the graph would otherwise keep their value until the next
+//! full ranking, however long ago their node last received contributions.
+//! `DecayRanks` makes them decay exponentially, so that stale projects
+//! gradually lose their standing.
+
+use num_traits::Float;
+use std::convert::TryFrom;
+
+use crate::cancel::CancellationToken;
+use crate::types::{AlgorithmError, HyperParameters, NodeData, NodeRank};
+use crate::{Graph, GraphAlgorithm, GraphAnnotator, GraphObject, Id};
+
+/// Exponential decay of the stored ranks, as a `GraphAlgorithm`, annotating
+/// every node with its decayed rank.
+///
+/// Ranks are multiplied by `HyperParameters::rank_decay` once per elapsed
+/// epoch. Nothing is annotated if ranks don't decay. Unlike
+/// `GraphAPI::advance_epoch`, the graph is left untouched: the decayed ranks
+/// are written by the annotator, eg. along with the output of a ranking.
+#[derive(Clone, Debug)]
+pub struct DecayRanks<W> {
+    /// The hyperparameters, of which `rank_decay` is used.
+    pub hyperparams: HyperParameters<W>,
+    /// The number of epochs elapsed since ranks last decayed.
+    pub epochs: u32,
+}
+
+impl<W> DecayRanks<W> {
+    /// Decay ranks by a single epoch.
+    pub fn new(hyperparams: HyperParameters<W>) -> Self {
+        DecayRanks {
+            hyperparams,
+            epochs: 1,
+        }
+    }
+
+    /// Decay ranks by `epochs` epochs.
+    pub fn epochs(mut self, epochs: u32) -> Self {
+        self.epochs = epochs;
+        self
+    }
+}
+
+impl<W: Float> DecayRanks<W> {
+    /// The factor ranks are multiplied by, if they decay.
+    pub fn factor(&self) -> Option<W> {
+        let epochs = i32::try_from(self.epochs).unwrap_or(i32::MAX);
+        self.hyperparams.rank_decay.map(|decay| decay.powi(epochs))
+    }
+}
+
+impl<G, A, W> GraphAlgorithm<G, A> for DecayRanks<W>
+where
+    G: Graph<NodeData = NodeData<W>>,
+    Id<G::Node>: Clone,
+    A: GraphAnnotator<Annotation = (Id<G::Node>, NodeRank<W>)>,
+    W: Float,
+{
+    type Context = ();
+    /// The number of nodes annotated.
+    type Output = usize;
+    type Error = AlgorithmError;
+    /// The algorithm is deterministic and ignores its seed.
+    type RngSeed = u64;
+    type Annotation = (Id<G::Node>, NodeRank<W>);
+
+    fn execute(
+        &self,
+        context: &mut (),
+        graph: &G,
+        annotator: &mut A,
+        seed: u64,
+    ) -> Result<usize, AlgorithmError> {
+        self.execute_with(context, graph, annotator, seed, &CancellationToken::new())
+    }
+
+    fn execute_with(
+        &self,
+        _context: &mut (),
+        graph: &G,
+        annotator: &mut A,
+        _seed: u64,
+        token: &CancellationToken,
+    ) -> Result<usize, AlgorithmError> {
+        let factor = match self.factor() {
+            Some(factor) => factor,
+            None => return Ok(0),
+        };
+        trace_span!("decay_ranks", epochs = self.epochs);
+
+        let mut annotated = 0;
+        for node in graph.nodes() {
+            token.check()?;
+            let mut rank = node.data().rank.clone();
+            rank.decay(factor);
+            annotator.annotate_graph((node.id().clone(), rank));
+            annotated += 1;
+        }
+        Ok(annotated)
+    }
+}
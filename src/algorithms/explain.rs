@@ -0,0 +1,113 @@
+//! Explaining ranks by where the rank mass of nodes comes from.
+//!
+//! The rank of a node is made of the walks starting on it, and of the rank
+//! mass flowing along its incoming edges, from the nodes walks come from.
+//! Algorithms record these flows while they run, and turn them into a
+//! `RankExplanation` per node, eg. to tell why the rank of a project dropped.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::sum_by_id;
+
+/// A node some of the rank of another node comes from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankSource<NodeId> {
+    /// The node the rank mass comes from, along its edges to the explained
+    /// node.
+    pub source: NodeId,
+    /// The share of the rank of the explained node coming from `source`.
+    pub share: f64,
+}
+
+/// The breakdown of the rank of a node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankExplanation<NodeId> {
+    /// The explained node.
+    pub node: NodeId,
+    /// The rank of the node.
+    pub rank: f64,
+    /// The share of the rank coming from walks starting, or restarting, on
+    /// the node.
+    pub own: f64,
+    /// The sources of the rest of the rank with the largest shares, by
+    /// decreasing share, then increasing id. The shares of all the sources,
+    /// including those left out, sum to `1.0` along with `own`.
+    pub sources: Vec<RankSource<NodeId>>,
+}
+
+/// The rank mass flowing into every node, recorded during an execution.
+#[derive(Debug)]
+pub(crate) struct Flows<NodeId> {
+    own: HashMap<NodeId, f64>,
+    inflows: HashMap<NodeId, HashMap<NodeId, f64>>,
+}
+
+impl<NodeId> Flows<NodeId>
+where
+    NodeId: Clone + Ord + Hash,
+{
+    pub(crate) fn new() -> Self {
+        Flows {
+            own: HashMap::new(),
+            inflows: HashMap::new(),
+        }
+    }
+
+    /// Record mass from walks starting on `node`.
+    pub(crate) fn add_own(&mut self, node: &NodeId, mass: f64) {
+        *self.own.entry(node.clone()).or_insert(0.0) += mass;
+    }
+
+    /// Record mass flowing from `from` to `to`.
+    pub(crate) fn add(&mut self, from: &NodeId, to: &NodeId, mass: f64) {
+        *self
+            .inflows
+            .entry(to.clone())
+            .or_default()
+            .entry(from.clone())
+            .or_insert(0.0) += mass;
+    }
+
+    /// The explanation of every ranked node, by increasing id, with the
+    /// `top` sources of each.
+    pub(crate) fn explain(
+        &self,
+        ranks: &HashMap<NodeId, f64>,
+        top: usize,
+    ) -> Vec<RankExplanation<NodeId>> {
+        let mut explanations: Vec<RankExplanation<NodeId>> = ranks
+            .iter()
+            .map(|(node, rank)| {
+                let own = self.own.get(node).cloned().unwrap_or(0.0);
+                let inflows = self.inflows.get(node);
+                let total = own + inflows.map_or(0.0, sum_by_id);
+                let share = |mass: f64| if total > 0.0 { mass / total } else { 0.0 };
+
+                let mut sources: Vec<RankSource<NodeId>> = inflows
+                    .into_iter()
+                    .flatten()
+                    .map(|(source, mass)| RankSource {
+                        source: source.clone(),
+                        share: share(*mass),
+                    })
+                    .collect();
+                sources.sort_by(|a, b| {
+                    b.share
+                        .total_cmp(&a.share)
+                        .then_with(|| a.source.cmp(&b.source))
+                });
+                sources.truncate(top);
+
+                RankExplanation {
+                    node: node.clone(),
+                    rank: *rank,
+                    own: share(own),
+                    sources,
+                }
+            })
+            .collect();
+        explanations.sort_by(|a, b| a.node.cmp(&b.node));
+        explanations
+    }
+}
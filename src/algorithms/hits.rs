@@ -0,0 +1,220 @@
+//! HITS: hubs and authorities.
+//!
+//! A good hub points to good authorities, and a good authority is pointed to
+//! by good hubs. On the bipartite user/project structure of the registry,
+//! users contributing to many valuable projects are hubs, and projects
+//! receiving contributions from valuable users are authorities.
+
+use num_traits::Float;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::cancel::CancellationToken;
+use crate::progress::{ExecutionStats, NoProgress, ProgressSink, Stopwatch};
+use crate::types::{AlgorithmError, HyperParameters};
+use crate::{Direction, Edge, Graph, GraphAlgorithm, GraphAnnotator, GraphObject, Id};
+
+/// The HITS scores of a node.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HitsScore<W> {
+    /// How well the node points to authorities.
+    pub hub: W,
+    /// How well the node is pointed to by hubs.
+    pub authority: W,
+}
+
+/// The HITS algorithm, as a `GraphAlgorithm`, annotating every node with its
+/// `HitsScore`.
+///
+/// Edges are weighted by the weight of their type in the hyperparameters.
+/// Scores are normalized so that hub and authority scores each sum to `1.0`.
+#[derive(Clone, Debug)]
+pub struct Hits<W> {
+    /// The hyperparameters, of which `edge_weights` are used.
+    pub hyperparams: HyperParameters<W>,
+    /// The maximum number of iterations.
+    pub max_iterations: usize,
+    /// The iterations stop once no score changes by more than this.
+    pub tolerance: f64,
+}
+
+impl<W> Hits<W> {
+    /// The default maximum number of iterations.
+    pub const DEFAULT_MAX_ITERATIONS: usize = 100;
+
+    /// The default tolerance.
+    pub const DEFAULT_TOLERANCE: f64 = 1e-8;
+
+    /// Create an instance with the default number of iterations and
+    /// tolerance.
+    pub fn new(hyperparams: HyperParameters<W>) -> Self {
+        Hits {
+            hyperparams,
+            max_iterations: Self::DEFAULT_MAX_ITERATIONS,
+            tolerance: Self::DEFAULT_TOLERANCE,
+        }
+    }
+}
+
+/// The scores of the nodes of a graph.
+type Scores<G, W> = HashMap<Id<<G as Graph>::Node>, HitsScore<W>>;
+
+/// Normalize scores so that they sum to `1.0`, unless they're all zero.
+fn normalize(scores: &mut [f64]) {
+    let total: f64 = scores.iter().sum();
+    if total > 0.0 {
+        for s in scores.iter_mut() {
+            *s /= total;
+        }
+    }
+}
+
+impl<G, A, W> GraphAlgorithm<G, A> for Hits<W>
+where
+    G: Graph,
+    Id<G::Node>: Clone + Ord + Hash,
+    A: GraphAnnotator<Annotation = (Id<G::Node>, HitsScore<W>)>,
+    W: Float,
+{
+    type Context = ();
+    /// The scores of every node.
+    type Output = Scores<G, W>;
+    type Error = AlgorithmError;
+    /// The algorithm is deterministic and ignores its seed.
+    type RngSeed = u64;
+    type Annotation = (Id<G::Node>, HitsScore<W>);
+
+    fn execute(
+        &self,
+        _context: &mut (),
+        graph: &G,
+        annotator: &mut A,
+        _seed: u64,
+    ) -> Result<Self::Output, Self::Error> {
+        self.run(graph, annotator, &mut NoProgress, &CancellationToken::new())
+    }
+
+    fn execute_with_progress(
+        &self,
+        _context: &mut (),
+        graph: &G,
+        annotator: &mut A,
+        _seed: u64,
+        progress: &mut dyn ProgressSink,
+    ) -> Result<Self::Output, Self::Error> {
+        self.run(graph, annotator, progress, &CancellationToken::new())
+    }
+
+    fn execute_with(
+        &self,
+        _context: &mut (),
+        graph: &G,
+        annotator: &mut A,
+        _seed: u64,
+        token: &CancellationToken,
+    ) -> Result<Self::Output, Self::Error> {
+        self.run(graph, annotator, &mut NoProgress, token)
+    }
+}
+
+impl<W: Float> Hits<W> {
+    fn run<G, A>(
+        &self,
+        graph: &G,
+        annotator: &mut A,
+        progress: &mut dyn ProgressSink,
+        token: &CancellationToken,
+    ) -> Result<Scores<G, W>, AlgorithmError>
+    where
+        G: Graph,
+        Id<G::Node>: Clone + Ord + Hash,
+        A: GraphAnnotator<Annotation = (Id<G::Node>, HitsScore<W>)>,
+    {
+        let start = Stopwatch::start(progress);
+        trace_span!(
+            "hits",
+            max_iterations = self.max_iterations,
+            tolerance = self.tolerance
+        );
+        // Sorted, so that scores are summed and annotated in the same order
+        // on every node.
+        let mut ids: Vec<&Id<G::Node>> = graph.nodes().map(|n| n.id()).collect();
+        ids.sort();
+        let index: HashMap<&Id<G::Node>, usize> =
+            ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+
+        // The weighted edges, as pairs of node indexes.
+        let mut edges = Vec::new();
+        for (i, id) in ids.iter().enumerate() {
+            for e in graph.edges_directed(id, Direction::Outgoing) {
+                let (j, edge) = match (index.get(e.to), graph.get_edge(e.id)) {
+                    (Some(j), Some(edge)) => (*j, edge),
+                    _ => continue,
+                };
+                let weight = self.hyperparams.try_get_param(&edge.edge_type().to_tag())?;
+                edges.push((i, j, weight.to_f64().unwrap_or(0.0)));
+            }
+        }
+
+        let n = ids.len();
+        let mut hubs = vec![1.0 / n as f64; n];
+        let mut authorities = vec![1.0 / n as f64; n];
+        let mut iterations = 0;
+
+        loop {
+            token.check()?;
+            iterations += 1;
+
+            let mut next_authorities = vec![0.0; n];
+            for &(i, j, w) in &edges {
+                next_authorities[j] += w * hubs[i];
+            }
+            normalize(&mut next_authorities);
+
+            let mut next_hubs = vec![0.0; n];
+            for &(i, j, w) in &edges {
+                next_hubs[i] += w * next_authorities[j];
+            }
+            normalize(&mut next_hubs);
+
+            let residual = hubs
+                .iter()
+                .zip(&next_hubs)
+                .chain(authorities.iter().zip(&next_authorities))
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0, f64::max);
+            hubs = next_hubs;
+            authorities = next_authorities;
+            progress.on_progress(iterations as u64, Some(self.max_iterations as u64));
+            trace_step!(iterations, residual, "hits iteration");
+
+            if residual <= self.tolerance {
+                trace_event!(iterations, residual, "hits converged");
+                break;
+            }
+            if iterations >= self.max_iterations {
+                trace_event!(iterations, residual, "hits did not converge");
+                return Err(AlgorithmError::NotConverged {
+                    iterations: iterations as u64,
+                    residual,
+                });
+            }
+        }
+
+        let mut scores: Scores<G, W> = HashMap::with_capacity(n);
+        for (i, id) in ids.iter().enumerate() {
+            let score = HitsScore {
+                hub: W::from(hubs[i]).unwrap_or_else(W::zero),
+                authority: W::from(authorities[i]).unwrap_or_else(W::zero),
+            };
+            annotator.annotate_graph(((*id).clone(), score));
+            scores.insert((*id).clone(), score);
+        }
+        progress.on_stats(&ExecutionStats {
+            iterations: iterations as u64,
+            elapsed: start.elapsed(),
+            ..Default::default()
+        });
+        Ok(scores)
+    }
+}
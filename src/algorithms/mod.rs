@@ -0,0 +1,169 @@
+//! Graph algorithms shipped with the crate.
+
+pub mod centrality;
+pub mod decay;
+pub mod explain;
+pub mod hits;
+pub mod monte_carlo;
+pub mod paths;
+pub mod prune;
+
+pub use self::centrality::{Betweenness, DegreeCentrality};
+pub use self::decay::DecayRanks;
+pub use self::explain::{RankExplanation, RankSource};
+pub use self::hits::{Hits, HitsScore};
+pub use self::monte_carlo::MonteCarlo;
+pub use self::paths::{is_reachable, shortest_path, Path};
+pub use self::prune::Prune;
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use self::explain::Flows;
+use super::cancel::{CancellationToken, Cancelled};
+use super::progress::ProgressSink;
+use super::types::{HyperParameters, NodeType};
+use super::{Direction, Graph, GraphObject, Id, Node};
+
+/// Compute an approximate osrank for every node of the graph, in expectation
+/// rather than by simulating random walks.
+///
+/// One walk is started from every node. At each step, a walk on a project
+/// (resp. user) node continues with probability
+/// `damping_factors.project` (resp. `account`), to one of the node's
+/// outgoing neighbors picked proportionally to edge weights. The rank of a
+/// node is its expected number of visits, for walks up to `steps` long,
+/// normalized so that all ranks sum to `1.0`.
+///
+/// The mass of the walks is recorded in `flows`, if given.
+pub(crate) fn expected_visits<G, W>(
+    graph: &G,
+    hyperparams: &HyperParameters<W>,
+    steps: usize,
+    progress: &mut dyn ProgressSink,
+    token: &CancellationToken,
+    mut flows: Option<&mut Flows<Id<G::Node>>>,
+) -> Result<HashMap<Id<G::Node>, f64>, Cancelled>
+where
+    G: Graph,
+    G::Weight: Into<f64>,
+    Id<G::Node>: Clone + Ord + Hash,
+{
+    let n = graph.nodes().count();
+    trace_span!("expected_visits", nodes = n, steps);
+    if n == 0 {
+        return Ok(HashMap::new());
+    }
+
+    let mut walk = ExpectedVisits::start(graph, flows.as_deref_mut());
+    for step in 0..steps {
+        token.check()?;
+        walk.step(graph, hyperparams, flows.as_deref_mut());
+        progress.on_progress(step as u64 + 1, Some(steps as u64));
+    }
+    Ok(walk.finish())
+}
+
+/// The state of `expected_visits` between steps, for executions driving the
+/// steps themselves, eg. to yield between them.
+pub(crate) struct ExpectedVisits<G: Graph> {
+    /// The mass of the walks on every node after the last step.
+    current: HashMap<Id<G::Node>, f64>,
+    /// The expected visits of every node so far.
+    visits: HashMap<Id<G::Node>, f64>,
+    /// The number of steps taken.
+    steps: usize,
+}
+
+impl<G> ExpectedVisits<G>
+where
+    G: Graph,
+    G::Weight: Into<f64>,
+    Id<G::Node>: Clone + Ord + Hash,
+{
+    /// Start one walk from every node.
+    pub(crate) fn start(graph: &G, flows: Option<&mut Flows<Id<G::Node>>>) -> Self {
+        let n = graph.nodes().count();
+        let current: HashMap<Id<G::Node>, f64> = graph
+            .nodes()
+            .map(|node| (node.id().clone(), 1.0 / n as f64))
+            .collect();
+        if let Some(flows) = flows {
+            for (id, mass) in current.iter() {
+                flows.add_own(id, *mass);
+            }
+        }
+        ExpectedVisits {
+            visits: current.clone(),
+            current,
+            steps: 0,
+        }
+    }
+
+    /// Move the walks one step further.
+    pub(crate) fn step<W>(
+        &mut self,
+        graph: &G,
+        hyperparams: &HyperParameters<W>,
+        mut flows: Option<&mut Flows<Id<G::Node>>>,
+    ) {
+        let mut next: HashMap<Id<G::Node>, f64> = HashMap::with_capacity(self.current.len());
+
+        // Mass is spread by increasing node id, and then target id, so that
+        // the sums don't depend on the iteration order of the backend.
+        let mut nodes: Vec<&G::Node> = graph.nodes().collect();
+        nodes.sort_unstable_by(|a, b| a.id().cmp(b.id()));
+
+        for node in nodes {
+            let mass = self.current.get(node.id()).cloned().unwrap_or(0.0);
+            if mass == 0.0 {
+                continue;
+            }
+            let damping = match node.node_type() {
+                NodeType::User { .. } => hyperparams.damping_factors.account,
+                _ => hyperparams.damping_factors.project,
+            };
+            let mut out: Vec<(&Id<G::Node>, f64)> = graph
+                .edges_directed(node.id(), Direction::Outgoing)
+                .into_iter()
+                .map(|e| (e.to, e.weight().into()))
+                .filter(|(_, w)| *w > 0.0)
+                .collect();
+            out.sort_unstable_by(|a, b| a.0.cmp(b.0).then(a.1.total_cmp(&b.1)));
+            let total: f64 = out.iter().map(|(_, w)| w).sum();
+
+            for (target, w) in out {
+                let flow = mass * damping * w / total;
+                *next.entry(target.clone()).or_insert(0.0) += flow;
+                if let Some(flows) = flows.as_mut() {
+                    flows.add(node.id(), target, flow);
+                }
+            }
+        }
+        for (id, mass) in next.iter() {
+            *self.visits.entry(id.clone()).or_insert(0.0) += mass;
+        }
+        self.steps += 1;
+        trace_step!(step = self.steps, active = next.len(), "walk step");
+        self.current = next;
+    }
+
+    /// The expected visits of every node, normalized to sum to `1.0`.
+    pub(crate) fn finish(mut self) -> HashMap<Id<G::Node>, f64> {
+        let total = sum_by_id(&self.visits);
+        if total > 0.0 {
+            for v in self.visits.values_mut() {
+                *v /= total;
+            }
+        }
+        self.visits
+    }
+}
+
+/// The sum of the masses of every node, added by increasing id, so that it
+/// doesn't depend on the iteration order of the map.
+pub(crate) fn sum_by_id<NodeId: Ord>(masses: &HashMap<NodeId, f64>) -> f64 {
+    let mut masses: Vec<(&NodeId, &f64)> = masses.iter().collect();
+    masses.sort_unstable_by(|a, b| a.0.cmp(b.0));
+    masses.into_iter().map(|(_, mass)| mass).sum()
+}
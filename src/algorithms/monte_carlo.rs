@@ -0,0 +1,168 @@
+//! Osrank by simulating random walks, possibly on remote executors.
+//!
+//! Unlike the osrank in expectation computed by other algorithms, ranks are
+//! estimated from the visits of `r_value` random walks started from every
+//! node. The walks can be distributed over the shards of a partitioned graph
+//! with `MonteCarlo::rank_on`, which yields the very same ranks as a local
+//! execution with the same seed.
+
+use num_traits::Float;
+use std::hash::Hash;
+
+use crate::cancel::CancellationToken;
+use crate::distributed::{
+    LocalWalkExecutor, RemoteWalkExecutor, WalkDriver, WalkError, WalkRequest,
+};
+use crate::partition::Assignment;
+use crate::types::{AlgorithmError, HyperParameters, NodeRank};
+use crate::{Graph, GraphAlgorithm, GraphAnnotator, GraphObject, Id};
+
+type Ranks<NodeId, W> = Vec<(NodeId, NodeRank<W>)>;
+
+/// Monte Carlo osrank, as a `GraphAlgorithm`, annotating every node with its
+/// rank.
+///
+/// `HyperParameters::r_value` walks start from every node, and continue
+/// with the probability given by `damping_factors` for the type of their
+/// node, to an outgoing neighbor picked proportionally to edge weights. The
+/// rank of a node is its share of all visits.
+#[derive(Clone, Debug)]
+pub struct MonteCarlo<W> {
+    /// The hyperparameters, of which `damping_factors` and `r_value` are
+    /// used.
+    pub hyperparams: HyperParameters<W>,
+    /// The maximum number of steps of a walk.
+    pub max_steps: u32,
+    /// The maximum number of walks submitted to an executor at once.
+    pub batch_size: usize,
+}
+
+impl<W> MonteCarlo<W> {
+    /// The default maximum number of steps of a walk.
+    pub const DEFAULT_MAX_STEPS: u32 = 100;
+    /// The default maximum number of walks per batch.
+    pub const DEFAULT_BATCH_SIZE: usize = 10_000;
+
+    /// Monte Carlo osrank with the default limits.
+    pub fn new(hyperparams: HyperParameters<W>) -> Self {
+        MonteCarlo {
+            hyperparams,
+            max_steps: Self::DEFAULT_MAX_STEPS,
+            batch_size: Self::DEFAULT_BATCH_SIZE,
+        }
+    }
+
+    /// Cut walks after `max_steps` steps.
+    pub fn max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Submit at most `batch_size` walks to an executor at once.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// The driver of the walks, seeded with `seed`.
+    pub fn driver(&self, seed: u64) -> WalkDriver {
+        WalkDriver {
+            seed,
+            damping_factors: self.hyperparams.damping_factors.clone(),
+            max_steps: self.max_steps,
+            batch_size: self.batch_size,
+        }
+    }
+
+    /// Rank the nodes of `assignment`, simulating the walks on `executors`,
+    /// one per shard. Ranks are sorted by ascending id.
+    ///
+    /// Walks are numbered by their source, in ascending id order, so ranks
+    /// only depend on the seed and the graph: neither on the partitioning
+    /// nor on the executors.
+    pub fn rank_on<NodeId, E>(
+        &self,
+        assignment: &Assignment<NodeId>,
+        executors: &mut [E],
+        seed: u64,
+        token: &CancellationToken,
+    ) -> Result<Ranks<NodeId, W>, WalkError<E::Error>>
+    where
+        NodeId: Clone + Ord,
+        E: RemoteWalkExecutor<NodeId>,
+        W: Float,
+    {
+        let r = u64::from(self.hyperparams.r_value);
+        trace_span!("monte_carlo", walks = r, steps = self.max_steps);
+        let walks = assignment
+            .iter()
+            .enumerate()
+            .flat_map(|(i, (id, _))| {
+                (0..r).map(move |j| WalkRequest::start(i as u64 * r + j, id.clone()))
+            })
+            .collect();
+        let visits = self.driver(seed).run(executors, assignment, walks, token)?;
+
+        let total = visits.values().sum::<u64>().max(1) as f64;
+        Ok(assignment
+            .iter()
+            .map(|(id, _)| {
+                let n = visits.get(id).copied().unwrap_or(0);
+                let rank = num_traits::cast(n as f64 / total).unwrap_or_else(W::zero);
+                (id.clone(), NodeRank { rank })
+            })
+            .collect())
+    }
+}
+
+impl<G, A, W> GraphAlgorithm<G, A> for MonteCarlo<W>
+where
+    G: Graph,
+    G::Weight: Into<f64>,
+    Id<G::Node>: Clone + Ord + Hash,
+    Id<G::Edge>: Clone + Ord,
+    A: GraphAnnotator<Annotation = (Id<G::Node>, NodeRank<W>)>,
+    W: Float,
+{
+    type Context = ();
+    /// The number of nodes annotated.
+    type Output = usize;
+    type Error = AlgorithmError;
+    type RngSeed = u64;
+    type Annotation = (Id<G::Node>, NodeRank<W>);
+
+    fn execute(
+        &self,
+        context: &mut (),
+        graph: &G,
+        annotator: &mut A,
+        seed: u64,
+    ) -> Result<usize, AlgorithmError> {
+        self.execute_with(context, graph, annotator, seed, &CancellationToken::new())
+    }
+
+    /// Simulates the walks in process, as a single shard.
+    fn execute_with(
+        &self,
+        _context: &mut (),
+        graph: &G,
+        annotator: &mut A,
+        seed: u64,
+        token: &CancellationToken,
+    ) -> Result<usize, AlgorithmError> {
+        let assignment = Assignment::new(1, graph.nodes().map(|node| (node.id().clone(), 0)));
+        let mut executors = [LocalWalkExecutor::new(graph)];
+        let ranks = self
+            .rank_on(&assignment, &mut executors, seed, token)
+            .map_err(|e| match e {
+                WalkError::Cancelled => AlgorithmError::Cancelled,
+                e => AlgorithmError::InvariantViolated(e.to_string()),
+            })?;
+
+        let annotated = ranks.len();
+        for note in ranks {
+            annotator.annotate_graph(note);
+        }
+        Ok(annotated)
+    }
+}
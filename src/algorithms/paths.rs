@@ -0,0 +1,161 @@
+//! Reachability and shortest paths, following edges in their direction.
+
+use num_traits::Zero;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::ops::Add;
+
+use crate::{Direction, Edge, Graph, GraphObject, Id};
+
+/// A path between two nodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<NodeId, EdgeId, W> {
+    /// The nodes of the path, from the start to the end.
+    pub nodes: Vec<NodeId>,
+    /// The edges of the path, one less than the nodes.
+    pub edges: Vec<EdgeId>,
+    /// The sum of the weights of the edges.
+    pub cost: W,
+}
+
+/// A path in a given graph type.
+type GraphPath<G> = Path<Id<<G as Graph>::Node>, Id<<G as Graph>::Edge>, <G as Graph>::Weight>;
+
+/// The cost of the cheapest known path to a node, and the edge it ends with.
+type Best<'a, G> =
+    HashMap<&'a Id<<G as Graph>::Node>, (<G as Graph>::Weight, Option<&'a Id<<G as Graph>::Edge>>)>;
+
+/// Whether `to` can be reached from `from` by following outgoing edges for
+/// which `edge_filter` returns `true`. A node reaches itself.
+pub fn is_reachable<G, F>(
+    graph: &G,
+    from: &Id<G::Node>,
+    to: &Id<G::Node>,
+    mut edge_filter: F,
+) -> bool
+where
+    G: Graph,
+    F: FnMut(&G::Edge) -> bool,
+    Id<G::Node>: Eq + Hash,
+{
+    let start = match graph.get_node(from) {
+        Some(node) => node.id(),
+        None => return false,
+    };
+    if graph.get_node(to).is_none() {
+        return false;
+    }
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::new();
+
+    seen.insert(start);
+    queue.push_back(start);
+
+    while let Some(id) = queue.pop_front() {
+        if id == to {
+            return true;
+        }
+        for e in graph.edges_directed(id, Direction::Outgoing) {
+            if seen.contains(e.to) || !graph.get_edge(e.id).is_some_and(&mut edge_filter) {
+                continue;
+            }
+            seen.insert(e.to);
+            queue.push_back(e.to);
+        }
+    }
+    false
+}
+
+/// A node to visit, ordered by cost only.
+struct Visit<'a, NodeId, W> {
+    cost: W,
+    node: &'a NodeId,
+}
+
+impl<'a, NodeId, W: PartialEq> PartialEq for Visit<'a, NodeId, W> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl<'a, NodeId, W: Eq> Eq for Visit<'a, NodeId, W> {}
+
+impl<'a, NodeId, W: Ord> PartialOrd for Visit<'a, NodeId, W> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a, NodeId, W: Ord> Ord for Visit<'a, NodeId, W> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cost.cmp(&other.cost)
+    }
+}
+
+/// The cheapest path from `from` to `to` following outgoing edges for which
+/// `edge_filter` returns `true`, with Dijkstra's algorithm. Weights must not
+/// be negative. Returns `None` if `to` can't be reached.
+pub fn shortest_path<G, F>(
+    graph: &G,
+    from: &Id<G::Node>,
+    to: &Id<G::Node>,
+    mut edge_filter: F,
+) -> Option<GraphPath<G>>
+where
+    G: Graph,
+    G::Weight: Ord + Add<Output = G::Weight> + Zero + Clone,
+    F: FnMut(&G::Edge) -> bool,
+    Id<G::Node>: Clone + Eq + Hash,
+    Id<G::Edge>: Clone,
+{
+    let start = graph.get_node(from)?.id();
+    let mut best: Best<'_, G> = HashMap::new();
+    let mut done = HashSet::new();
+    let mut heap = BinaryHeap::new();
+
+    best.insert(start, (G::Weight::zero(), None));
+    heap.push(Reverse(Visit {
+        cost: G::Weight::zero(),
+        node: start,
+    }));
+
+    while let Some(Reverse(Visit { cost, node })) = heap.pop() {
+        if !done.insert(node) {
+            continue;
+        }
+        if node == to {
+            break;
+        }
+        for e in graph.edges_directed(node, Direction::Outgoing) {
+            let edge = match graph.get_edge(e.id) {
+                Some(edge) if edge_filter(edge) => edge,
+                _ => continue,
+            };
+            let next = cost.clone() + edge.weight();
+            let improves = best.get(e.to).is_none_or(|(known, _)| next < *known);
+            if improves {
+                best.insert(e.to, (next.clone(), Some(e.id)));
+                heap.push(Reverse(Visit {
+                    cost: next,
+                    node: e.to,
+                }));
+            }
+        }
+    }
+
+    let (cost, _) = best.get(to)?.clone();
+    let mut nodes = vec![to.clone()];
+    let mut edges = Vec::new();
+    let mut current = to;
+
+    while let Some((_, Some(edge))) = best.get(current) {
+        current = graph.get_edge(edge)?.source();
+        edges.push((*edge).clone());
+        nodes.push(current.clone());
+    }
+    nodes.reverse();
+    edges.reverse();
+
+    Some(Path { nodes, edges, cost })
+}
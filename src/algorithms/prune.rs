@@ -0,0 +1,327 @@
+//! Phase 1 of osrank: pruning the graph.
+//!
+//! Running the full osrank algorithm on every node is expensive, while most
+//! nodes end up with a negligible rank. The first phase of the algorithm
+//! computes a cheap approximate rank, and discards the nodes whose rank falls
+//! below the `tau` threshold (`HyperParameters::pruning_threshold`) before
+//! the second phase runs on what remains.
+
+use num_traits::Float;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::mem;
+
+use super::explain::{Flows, RankExplanation};
+use super::{expected_visits, ExpectedVisits};
+use crate::asynchronous::{yield_now, AsyncError, AsyncGraphAlgorithm, AsyncGraphAnnotator};
+use crate::cancel::CancellationToken;
+use crate::progress::{ExecutionStats, NoProgress, ProgressSink, Stopwatch};
+use crate::types::{AlgorithmError, HyperParameters, NodeRank};
+use crate::{Direction, Graph, GraphAlgorithm, GraphAnnotator, Id};
+
+/// The pruning phase of osrank, as a `GraphAlgorithm`.
+///
+/// Every pruned node is annotated with its approximate rank.
+#[derive(Clone, Debug)]
+pub struct Prune<W> {
+    /// The hyperparameters, of which `pruning_threshold` and
+    /// `damping_factors` are used.
+    pub hyperparams: HyperParameters<W>,
+    /// The maximum length of the walks considered by the approximate rank.
+    pub steps: usize,
+    /// Whether to return the pruned graph in the output.
+    pub emit_subgraph: bool,
+    /// The number of sources to return in the explanation of the rank of
+    /// every node, if explanations are returned in the output.
+    pub explain: Option<usize>,
+}
+
+impl<W> Prune<W> {
+    /// The default maximum length of walks.
+    pub const DEFAULT_STEPS: usize = 10;
+
+    /// Create a pruning pass with the default number of steps, not emitting
+    /// the pruned graph.
+    pub fn new(hyperparams: HyperParameters<W>) -> Self {
+        Prune {
+            hyperparams,
+            steps: Self::DEFAULT_STEPS,
+            emit_subgraph: false,
+            explain: None,
+        }
+    }
+
+    /// Return the pruned graph in the output.
+    pub fn emit_subgraph(mut self) -> Self {
+        self.emit_subgraph = true;
+        self
+    }
+
+    /// Return the explanation of the rank of every node in the output, with
+    /// its `top` sources.
+    pub fn explain(mut self, top: usize) -> Self {
+        self.explain = Some(top);
+        self
+    }
+}
+
+/// The context of the pruning pass.
+#[derive(Debug, Clone)]
+pub struct PruneContext<NodeId> {
+    /// The approximate ranks computed by the last run.
+    pub ranks: HashMap<NodeId, f64>,
+}
+
+impl<NodeId: Eq + Hash> Default for PruneContext<NodeId> {
+    fn default() -> Self {
+        PruneContext {
+            ranks: HashMap::new(),
+        }
+    }
+}
+
+/// The nodes and edges remaining after pruning.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrunedGraph<NodeId, EdgeId> {
+    /// The nodes that were kept, sorted.
+    pub nodes: Vec<NodeId>,
+    /// The edges between kept nodes.
+    pub edges: Vec<EdgeId>,
+}
+
+/// The output of the pruning pass.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PruneOutput<NodeId, EdgeId> {
+    /// The nodes whose rank is at or above the threshold, sorted.
+    pub kept: Vec<NodeId>,
+    /// The nodes whose rank is below the threshold, sorted.
+    pub pruned: Vec<NodeId>,
+    /// The pruned graph, if `Prune::emit_subgraph` is set.
+    pub subgraph: Option<PrunedGraph<NodeId, EdgeId>>,
+    /// The explanation of the approximate rank of every node, by increasing
+    /// id, if `Prune::explain` is set.
+    pub explanations: Option<Vec<RankExplanation<NodeId>>>,
+}
+
+/// The output of the pruning pass over a given graph type.
+type GraphPruneOutput<G> = PruneOutput<Id<<G as Graph>::Node>, Id<<G as Graph>::Edge>>;
+
+impl<G, A, W> GraphAlgorithm<G, A> for Prune<W>
+where
+    G: Graph,
+    G::Weight: Into<f64>,
+    Id<G::Node>: Clone + Ord + Hash,
+    Id<G::Edge>: Clone,
+    A: GraphAnnotator<Annotation = (Id<G::Node>, NodeRank<W>)>,
+    W: Float,
+{
+    type Context = PruneContext<Id<G::Node>>;
+    type Output = GraphPruneOutput<G>;
+    type Error = AlgorithmError;
+    /// The pruning pass is deterministic and ignores its seed.
+    type RngSeed = u64;
+    type Annotation = (Id<G::Node>, NodeRank<W>);
+
+    fn execute(
+        &self,
+        context: &mut Self::Context,
+        graph: &G,
+        annotator: &mut A,
+        _seed: u64,
+    ) -> Result<Self::Output, Self::Error> {
+        self.run(
+            context,
+            graph,
+            annotator,
+            &mut NoProgress,
+            &CancellationToken::new(),
+        )
+    }
+
+    fn execute_with_progress(
+        &self,
+        context: &mut Self::Context,
+        graph: &G,
+        annotator: &mut A,
+        _seed: u64,
+        progress: &mut dyn ProgressSink,
+    ) -> Result<Self::Output, Self::Error> {
+        self.run(
+            context,
+            graph,
+            annotator,
+            progress,
+            &CancellationToken::new(),
+        )
+    }
+
+    fn execute_with(
+        &self,
+        context: &mut Self::Context,
+        graph: &G,
+        annotator: &mut A,
+        _seed: u64,
+        token: &CancellationToken,
+    ) -> Result<Self::Output, Self::Error> {
+        self.run(context, graph, annotator, &mut NoProgress, token)
+    }
+}
+
+impl<W: Float> Prune<W> {
+    fn run<G, A>(
+        &self,
+        context: &mut PruneContext<Id<G::Node>>,
+        graph: &G,
+        annotator: &mut A,
+        progress: &mut dyn ProgressSink,
+        token: &CancellationToken,
+    ) -> Result<GraphPruneOutput<G>, AlgorithmError>
+    where
+        G: Graph,
+        G::Weight: Into<f64>,
+        Id<G::Node>: Clone + Ord + Hash,
+        Id<G::Edge>: Clone,
+        A: GraphAnnotator<Annotation = (Id<G::Node>, NodeRank<W>)>,
+    {
+        let start = Stopwatch::start(progress);
+        trace_span!(
+            "prune",
+            steps = self.steps,
+            emit_subgraph = self.emit_subgraph
+        );
+        let mut flows = self.explain.map(|_| Flows::new());
+        let ranks = expected_visits(
+            graph,
+            &self.hyperparams,
+            self.steps,
+            progress,
+            token,
+            flows.as_mut(),
+        )?;
+        // Past this point, the execution can't be cancelled anymore, so that
+        // annotations are made for all the pruned nodes or none at all.
+        Ok(self.finish(context, graph, annotator, progress, start, ranks, flows))
+    }
+
+    /// Split the nodes by their approximate rank, annotating the pruned ones.
+    #[allow(clippy::too_many_arguments)]
+    fn finish<G, A>(
+        &self,
+        context: &mut PruneContext<Id<G::Node>>,
+        graph: &G,
+        annotator: &mut A,
+        progress: &mut dyn ProgressSink,
+        start: Stopwatch,
+        ranks: HashMap<Id<G::Node>, f64>,
+        flows: Option<Flows<Id<G::Node>>>,
+    ) -> GraphPruneOutput<G>
+    where
+        G: Graph,
+        Id<G::Node>: Clone + Ord + Hash,
+        Id<G::Edge>: Clone,
+        A: GraphAnnotator<Annotation = (Id<G::Node>, NodeRank<W>)>,
+    {
+        let mut ids: Vec<&Id<G::Node>> = ranks.keys().collect();
+        ids.sort();
+
+        let mut kept = Vec::new();
+        let mut pruned = Vec::new();
+        for id in ids {
+            let rank = W::from(ranks[id]).unwrap_or_else(W::zero);
+
+            if rank < self.hyperparams.pruning_threshold {
+                annotator.annotate_graph((id.clone(), NodeRank { rank }));
+                pruned.push(id.clone());
+            } else {
+                kept.push(id.clone());
+            }
+        }
+
+        let subgraph = if self.emit_subgraph {
+            let edges = kept
+                .iter()
+                .flat_map(|id| graph.edges_directed(id, Direction::Outgoing))
+                .filter(|e| kept.binary_search(e.to).is_ok())
+                .map(|e| e.id.clone())
+                .collect();
+
+            Some(PrunedGraph {
+                nodes: kept.clone(),
+                edges,
+            })
+        } else {
+            None
+        };
+        trace_event!(kept = kept.len(), pruned = pruned.len(), "pruned graph");
+        progress.on_stats(&ExecutionStats {
+            // One walk is accounted for per node.
+            walks: ranks.len() as u64,
+            iterations: self.steps as u64,
+            elapsed: start.elapsed(),
+            // The visits, the current and next walk distributions.
+            peak_memory: 3 * ranks.len() * mem::size_of::<(Id<G::Node>, f64)>(),
+        });
+        let explanations = flows
+            .zip(self.explain)
+            .map(|(flows, top)| flows.explain(&ranks, top));
+        context.ranks = ranks;
+
+        PruneOutput {
+            kept,
+            pruned,
+            subgraph,
+            explanations,
+        }
+    }
+}
+
+/// Runs natively, yielding between the steps of the walks. Pruned nodes are
+/// annotated once all the steps are done.
+impl<G, A, W> AsyncGraphAlgorithm<G, A> for Prune<W>
+where
+    G: Graph + Sync,
+    G::Weight: Into<f64>,
+    Id<G::Node>: Clone + Ord + Hash + Send + Sync,
+    Id<G::Edge>: Clone + Send,
+    A: AsyncGraphAnnotator<Annotation = (Id<G::Node>, NodeRank<W>)> + Send,
+    W: Float + Send + Sync,
+{
+    type Context = PruneContext<Id<G::Node>>;
+    type Output = GraphPruneOutput<G>;
+    type Error = AsyncError<AlgorithmError, A::Error>;
+    type RngSeed = u64;
+    type Annotation = (Id<G::Node>, NodeRank<W>);
+
+    async fn execute(
+        &self,
+        context: &mut Self::Context,
+        graph: &G,
+        annotator: &mut A,
+        _seed: u64,
+    ) -> Result<Self::Output, Self::Error> {
+        let mut flows = self.explain.map(|_| Flows::new());
+        let mut walk = ExpectedVisits::start(graph, flows.as_mut());
+        for _ in 0..self.steps {
+            yield_now().await;
+            walk.step(graph, &self.hyperparams, flows.as_mut());
+        }
+
+        let mut notes = Vec::new();
+        let output = self.finish(
+            context,
+            graph,
+            &mut notes,
+            &mut NoProgress,
+            Stopwatch::start(&NoProgress),
+            walk.finish(),
+            flows,
+        );
+        for note in notes {
+            annotator
+                .annotate_graph(note)
+                .await
+                .map_err(AsyncError::Annotator)?;
+        }
+        Ok(output)
+    }
+}
@@ -0,0 +1,278 @@
+//! Adapters adding behaviour to any `GraphAnnotator`, and the merging of
+//! annotations made by parallel shards of an execution.
+
+use alloc::collections::btree_map::{BTreeMap, Entry};
+use alloc::vec::Vec;
+
+use super::{GraphAnnotator, TransactionalAnnotator};
+
+/// Makes an annotator transactional: annotations made within a transaction
+/// are buffered, and only written to the inner annotator once committed.
+/// Annotations made outside of a transaction are written right away.
+///
+/// Transactions don't nest: `begin` has no effect within a transaction.
+#[derive(Debug, Clone)]
+pub struct Transaction<A: GraphAnnotator> {
+    inner: A,
+    pending: Vec<A::Annotation>,
+    active: bool,
+}
+
+impl<A: GraphAnnotator> Transaction<A> {
+    /// Wrap `inner`, outside of a transaction.
+    pub fn new(inner: A) -> Self {
+        Transaction {
+            inner,
+            pending: Vec::new(),
+            active: false,
+        }
+    }
+
+    /// The inner annotator, holding the committed annotations.
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+
+    /// The inner annotator. Pending annotations are discarded.
+    pub fn into_inner(self) -> A {
+        self.inner
+    }
+
+    /// Whether a transaction is in progress.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// The number of annotations waiting to be committed.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+impl<A: GraphAnnotator + Default> Default for Transaction<A> {
+    fn default() -> Self {
+        Transaction::new(A::default())
+    }
+}
+
+/// Flushing writes the committed annotations, not the pending ones.
+impl<A: GraphAnnotator> GraphAnnotator for Transaction<A> {
+    type Annotation = A::Annotation;
+    type Error = A::Error;
+
+    fn annotate_graph(&mut self, note: A::Annotation) {
+        if self.active {
+            self.pending.push(note);
+        } else {
+            self.inner.annotate_graph(note);
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), A::Error> {
+        self.inner.flush()
+    }
+}
+
+impl<A: GraphAnnotator> TransactionalAnnotator for Transaction<A> {
+    fn begin(&mut self) {
+        self.active = true;
+    }
+
+    fn commit(&mut self) {
+        for note in self.pending.drain(..) {
+            self.inner.annotate_graph(note);
+        }
+        self.active = false;
+    }
+
+    fn rollback(&mut self) {
+        self.pending.clear();
+        self.active = false;
+    }
+}
+
+/// Batches annotations for an annotator taking them in batches, eg. to write
+/// them to storage in a single transaction. Annotations are handed to the
+/// inner annotator every `chunk_size` annotations, and when flushed.
+///
+/// Annotations still buffered when the adapter is dropped are lost: it must
+/// be flushed once the annotations are made.
+#[derive(Debug, Clone)]
+pub struct BufferedAnnotator<A, T> {
+    inner: A,
+    buffer: Vec<T>,
+    chunk_size: usize,
+}
+
+impl<A, T> BufferedAnnotator<A, T>
+where
+    A: GraphAnnotator<Annotation = Vec<T>>,
+{
+    /// The default number of annotations per batch.
+    pub const DEFAULT_CHUNK_SIZE: usize = 4096;
+
+    /// Batch the annotations for `inner` in chunks of `DEFAULT_CHUNK_SIZE`.
+    pub fn new(inner: A) -> Self {
+        Self::with_chunk_size(inner, Self::DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Batch the annotations for `inner` in chunks of `chunk_size`, which is
+    /// at least one.
+    pub fn with_chunk_size(inner: A, chunk_size: usize) -> Self {
+        let chunk_size = chunk_size.max(1);
+        BufferedAnnotator {
+            inner,
+            buffer: Vec::with_capacity(chunk_size),
+            chunk_size,
+        }
+    }
+
+    /// The inner annotator.
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+
+    /// Flush, then return the inner annotator.
+    pub fn into_inner(mut self) -> Result<A, A::Error> {
+        self.flush()?;
+        Ok(self.inner)
+    }
+
+    /// The number of annotations not yet handed to the inner annotator.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    fn write_batch(&mut self) {
+        if !self.buffer.is_empty() {
+            let batch = core::mem::replace(&mut self.buffer, Vec::with_capacity(self.chunk_size));
+            self.inner.annotate_graph(batch);
+        }
+    }
+}
+
+impl<A, T> GraphAnnotator for BufferedAnnotator<A, T>
+where
+    A: GraphAnnotator<Annotation = Vec<T>>,
+{
+    type Annotation = T;
+    type Error = A::Error;
+
+    fn annotate_graph(&mut self, note: T) {
+        self.buffer.push(note);
+        if self.buffer.len() >= self.chunk_size {
+            self.write_batch();
+        }
+    }
+
+    /// Hand the buffered annotations to the inner annotator, then flush it.
+    fn flush(&mut self) -> Result<(), A::Error> {
+        self.write_batch();
+        self.inner.flush()
+    }
+}
+
+/// Annotation sets which can be combined, eg. the annotations made by the
+/// shards of a parallel execution. `merge` must be associative, so that the
+/// result doesn't depend on how shards are grouped.
+pub trait MergeAnnotations {
+    /// Add the annotations of `other` to `self`.
+    fn merge(&mut self, other: Self);
+}
+
+/// Appends `other`.
+impl<T> MergeAnnotations for Vec<T> {
+    fn merge(&mut self, mut other: Self) {
+        self.append(&mut other);
+    }
+}
+
+/// Merges the values of the keys present in both maps.
+impl<K: Ord, V: MergeAnnotations> MergeAnnotations for BTreeMap<K, V> {
+    fn merge(&mut self, other: Self) {
+        for (key, value) in other {
+            match self.entry(key) {
+                Entry::Occupied(mut entry) => entry.get_mut().merge(value),
+                Entry::Vacant(entry) => {
+                    entry.insert(value);
+                }
+            }
+        }
+    }
+}
+
+macro_rules! merge_by_sum {
+    ($($t:ty),*) => {$(
+        /// Adds `other`.
+        impl MergeAnnotations for $t {
+            fn merge(&mut self, other: Self) {
+                *self += other;
+            }
+        }
+    )*};
+}
+
+merge_by_sum!(u32, u64, usize, f32, f64);
+
+/// Annotators for the shards of a parallel execution, each of which can be
+/// handed to a different thread, merged once the execution is over.
+///
+/// ```ignore
+/// let mut sharded = ShardedAnnotator::<Vec<_>>::new(threads);
+/// std::thread::scope(|s| {
+///     for (i, shard) in sharded.shards_mut().iter_mut().enumerate() {
+///         s.spawn(move || walk(i, shard));
+///     }
+/// });
+/// sharded.finish(&mut annotator);
+/// ```
+///
+/// Shards are merged in order, whichever finished first, so that the result
+/// is deterministic even when merging isn't commutative.
+#[derive(Debug, Clone)]
+pub struct ShardedAnnotator<S> {
+    shards: Vec<S>,
+}
+
+impl<S: Default> ShardedAnnotator<S> {
+    /// `n` empty shards, at least one.
+    pub fn new(n: usize) -> Self {
+        ShardedAnnotator {
+            shards: (0..n.max(1)).map(|_| S::default()).collect(),
+        }
+    }
+}
+
+impl<S> ShardedAnnotator<S> {
+    /// The shards, by index.
+    pub fn shards(&self) -> &[S] {
+        &self.shards
+    }
+
+    /// The shards, to annotate them from different threads.
+    pub fn shards_mut(&mut self) -> &mut [S] {
+        &mut self.shards
+    }
+
+    /// The annotations of all shards, merged in order.
+    pub fn merge(self) -> S
+    where
+        S: MergeAnnotations,
+    {
+        let mut shards = self.shards.into_iter();
+        let mut merged = shards.next().expect("there is at least one shard");
+        for shard in shards {
+            merged.merge(shard);
+        }
+        merged
+    }
+
+    /// Merge the shards, and annotate `annotator` with the result.
+    pub fn finish<A>(self, annotator: &mut A)
+    where
+        S: MergeAnnotations,
+        A: GraphAnnotator<Annotation = S>,
+    {
+        annotator.annotate_graph(self.merge());
+    }
+}
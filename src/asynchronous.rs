@@ -0,0 +1,196 @@
+//! Async variants of `GraphAlgorithm` and `GraphAnnotator`, for nodes writing
+//! annotations to async storage, eg. a database or over RPC.
+//!
+//! The traits don't depend on any runtime. Synchronous algorithms and
+//! annotators are adapted with `BlockingAlgorithm` and `BlockingAnnotator`,
+//! and long computations give control back to the executor between steps
+//! with `yield_now`. `algorithms::Prune` and `linalg::PowerIteration`
+//! implement `AsyncGraphAlgorithm` natively, yielding between iterations.
+
+use alloc::vec::Vec;
+use core::convert::Infallible;
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use super::{rng, Graph, GraphAlgorithm, GraphAnnotator};
+
+/// An annotator writing annotations asynchronously.
+pub trait AsyncGraphAnnotator {
+    type Annotation;
+
+    /// A failure to write an annotation.
+    type Error;
+
+    /// Annotate the graph with some data, see
+    /// `GraphAnnotator::annotate_graph`.
+    fn annotate_graph(
+        &mut self,
+        note: Self::Annotation,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+}
+
+/// A graph algorithm over a graph, running asynchronously. See
+/// `GraphAlgorithm` for the meaning of the associated types.
+pub trait AsyncGraphAlgorithm<G, A>
+where
+    G: Graph,
+    A: AsyncGraphAnnotator<Annotation = Self::Annotation>,
+{
+    type Context: Default;
+    type Output;
+    type Error;
+    type RngSeed: rng::AlgorithmSeed;
+    type Annotation;
+
+    /// Execute an algorithm over a context and graph. Implementations should
+    /// `yield_now` regularly if they compute for long.
+    fn execute(
+        &self,
+        context: &mut Self::Context,
+        graph: &G,
+        annotator: &mut A,
+        seed: Self::RngSeed,
+    ) -> impl Future<Output = Result<Self::Output, Self::Error>> + Send;
+}
+
+/// An error of an async execution.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AsyncError<E, A> {
+    /// The algorithm failed.
+    Algorithm(E),
+    /// The annotator failed to write an annotation.
+    Annotator(A),
+}
+
+impl<E: fmt::Display, A: fmt::Display> fmt::Display for AsyncError<E, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AsyncError::Algorithm(err) => write!(f, "algorithm failed: {}", err),
+            AsyncError::Annotator(err) => write!(f, "annotator failed: {}", err),
+        }
+    }
+}
+
+impl<E, A> core::error::Error for AsyncError<E, A>
+where
+    E: fmt::Debug + fmt::Display,
+    A: fmt::Debug + fmt::Display,
+{
+}
+
+/// Runs a synchronous algorithm as an `AsyncGraphAlgorithm`.
+///
+/// The algorithm runs to completion without yielding, annotating a buffer,
+/// and the annotations are then written to the async annotator, yielding
+/// every `yield_every` annotations. Long computations should still be moved
+/// off the executor, eg. with `spawn_blocking`, unless the algorithm
+/// implements `AsyncGraphAlgorithm` natively.
+#[derive(Debug, Clone)]
+pub struct BlockingAlgorithm<Alg> {
+    /// The synchronous algorithm.
+    pub algorithm: Alg,
+    /// Number of annotations written between yields. Never yields if zero.
+    pub yield_every: usize,
+}
+
+impl<Alg> BlockingAlgorithm<Alg> {
+    /// The default number of annotations written between yields.
+    pub const DEFAULT_YIELD_EVERY: usize = 1024;
+
+    /// Adapt `algorithm`, yielding every `DEFAULT_YIELD_EVERY` annotations.
+    pub fn new(algorithm: Alg) -> Self {
+        BlockingAlgorithm {
+            algorithm,
+            yield_every: Self::DEFAULT_YIELD_EVERY,
+        }
+    }
+}
+
+impl<G, A, Alg> AsyncGraphAlgorithm<G, A> for BlockingAlgorithm<Alg>
+where
+    G: Graph + Sync,
+    A: AsyncGraphAnnotator + Send,
+    A::Annotation: Send,
+    Alg: GraphAlgorithm<G, Vec<A::Annotation>, Annotation = A::Annotation> + Sync,
+    Alg::Context: Send,
+    Alg::Output: Send,
+    Alg::Error: Send,
+    Alg::RngSeed: Send,
+{
+    type Context = Alg::Context;
+    type Output = Alg::Output;
+    type Error = AsyncError<Alg::Error, A::Error>;
+    type RngSeed = Alg::RngSeed;
+    type Annotation = A::Annotation;
+
+    async fn execute(
+        &self,
+        context: &mut Self::Context,
+        graph: &G,
+        annotator: &mut A,
+        seed: Self::RngSeed,
+    ) -> Result<Self::Output, Self::Error> {
+        let mut buffer = Vec::new();
+        let output = self
+            .algorithm
+            .execute(context, graph, &mut buffer, seed)
+            .map_err(AsyncError::Algorithm)?;
+
+        for (i, note) in buffer.into_iter().enumerate() {
+            if self.yield_every > 0 && i > 0 && i % self.yield_every == 0 {
+                yield_now().await;
+            }
+            annotator
+                .annotate_graph(note)
+                .await
+                .map_err(AsyncError::Annotator)?;
+        }
+        Ok(output)
+    }
+}
+
+/// Adapts a synchronous annotator as an `AsyncGraphAnnotator` which never
+/// fails.
+#[derive(Debug, Clone, Default)]
+pub struct BlockingAnnotator<A>(pub A);
+
+impl<A> AsyncGraphAnnotator for BlockingAnnotator<A>
+where
+    A: GraphAnnotator + Send,
+    A::Annotation: Send,
+{
+    type Annotation = A::Annotation;
+    type Error = Infallible;
+
+    async fn annotate_graph(&mut self, note: A::Annotation) -> Result<(), Infallible> {
+        self.0.annotate_graph(note);
+        Ok(())
+    }
+}
+
+/// Give control back to the executor once, letting other tasks run.
+pub fn yield_now() -> YieldNow {
+    YieldNow { yielded: false }
+}
+
+/// The future returned by `yield_now`.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless awaited"]
+pub struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
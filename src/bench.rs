@@ -0,0 +1,203 @@
+//! Workloads to benchmark graph backends against each other.
+//!
+//! `benches/backends.rs` runs them on the backends shipped with the crate.
+//! Backend authors can run the same workloads from their own benchmarks, on
+//! the same generated graphs, for numbers that compare:
+//!
+//! ```ignore
+//! let workload = Workload::random(10_000, Workload::DEFAULT_DEGREE, 0);
+//! let graph: MyGraph = workload.build();
+//! c.bench_function("neighbors", |b| b.iter(|| workload.neighbors(&graph)));
+//! ```
+
+use std::hash::Hash;
+
+use super::algorithms::prune::{Prune, PruneContext};
+use super::epochs::EdgeHistory;
+use super::rng::{AlgorithmSeed, SeedableAlgorithmRng};
+use super::subgraph;
+use super::types::{
+    ContributionLedger, EdgeData, EdgeType, HyperParameters, NodeData, NodeRank, NodeType,
+    Timestamps,
+};
+use super::{Direction, Graph, GraphAlgorithm, GraphObject, GraphWriter, Id};
+
+/// The graph sizes, in nodes, the shipped benchmarks run at.
+pub const SIZES: [usize; 3] = [100, 1_000, 10_000];
+
+/// The number of nodes the lookup workloads visit.
+pub const LOOKUPS: usize = 100;
+
+/// A generated graph, and the nodes the workloads look up.
+///
+/// A tenth of the nodes are users, contributing to projects, and projects
+/// depend on other projects. Edges go to random nodes, so that there are no
+/// hubs.
+#[derive(Debug, Clone)]
+pub struct Workload {
+    /// The nodes, by increasing id.
+    pub nodes: Vec<(u64, NodeData<f64>)>,
+    /// The edges `(id, from, to, data)`, by increasing id.
+    pub edges: Vec<(u64, u64, u64, EdgeData<f64>)>,
+    /// The nodes looked up, `LOOKUPS` of them at most.
+    pub lookups: Vec<u64>,
+}
+
+impl Workload {
+    /// The default number of outgoing edges per node.
+    pub const DEFAULT_DEGREE: usize = 8;
+
+    /// A graph of `nodes` nodes, with `degree` outgoing edges each,
+    /// generated from `seed`.
+    pub fn random(nodes: usize, degree: usize, seed: u64) -> Self {
+        let mut rng = seed.into_rng();
+        let n = nodes as u64;
+        let is_user = |id: u64| id.is_multiple_of(10);
+
+        let nodes = (0..n)
+            .map(|id| {
+                let node_type = if is_user(id) {
+                    NodeType::User {
+                        contributions_to_all_projects: 0,
+                    }
+                } else {
+                    NodeType::Project {
+                        contributions_from_all_users: 0,
+                    }
+                };
+                (id, node_data(node_type))
+            })
+            .collect();
+
+        let mut edges = Vec::with_capacity(n as usize * degree);
+        for from in 0..n {
+            for _ in 0..degree {
+                let to = rng.next_u64() % n;
+                let edge_type = if is_user(from) {
+                    EdgeType::UserToProjectContribution(1 + rng.next_u32() % 10)
+                } else {
+                    EdgeType::Dependency
+                };
+                let id = edges.len() as u64;
+                edges.push((id, from, to, edge_data(edge_type, rng.next_f64())));
+            }
+        }
+
+        let lookups = (0..LOOKUPS.min(n as usize))
+            .map(|_| rng.next_u64() % n)
+            .collect();
+
+        Workload {
+            nodes,
+            edges,
+            lookups,
+        }
+    }
+
+    /// Insert the nodes, then the edges, into `graph`.
+    pub fn insert<G>(&self, graph: &mut G)
+    where
+        G: GraphWriter<NodeData = NodeData<f64>, EdgeData = EdgeData<f64>>,
+        G::Node: GraphObject<Id = u64>,
+        G::Edge: GraphObject<Id = u64>,
+    {
+        for (id, data) in &self.nodes {
+            graph.add_node(*id, data.clone());
+        }
+        for (id, from, to, data) in &self.edges {
+            graph.add_edge(*id, from, to, data.clone());
+        }
+    }
+
+    /// A new graph with the nodes and edges of the workload.
+    pub fn build<G>(&self) -> G
+    where
+        G: GraphWriter<NodeData = NodeData<f64>, EdgeData = EdgeData<f64>> + Default,
+        G::Node: GraphObject<Id = u64>,
+        G::Edge: GraphObject<Id = u64>,
+    {
+        let mut graph = G::default();
+        self.insert(&mut graph);
+        graph
+    }
+
+    /// List the neighbors of the looked up nodes, returning their number.
+    pub fn neighbors<G>(&self, graph: &G) -> usize
+    where
+        G: Graph,
+        G::Node: GraphObject<Id = u64>,
+    {
+        self.lookups
+            .iter()
+            .map(|id| graph.neighbors(id).count())
+            .sum()
+    }
+
+    /// List the edges of the looked up nodes in both directions, returning
+    /// their number.
+    pub fn edges_directed<G>(&self, graph: &G) -> usize
+    where
+        G: Graph,
+        G::Node: GraphObject<Id = u64>,
+    {
+        self.lookups
+            .iter()
+            .map(|id| {
+                graph.edges_directed(id, Direction::Outgoing).len()
+                    + graph.edges_directed(id, Direction::Incoming).len()
+            })
+            .sum()
+    }
+
+    /// Extract the 2-hop outgoing neighborhood of the looked up nodes,
+    /// returning the total number of nodes extracted.
+    pub fn subgraphs<G>(&self, graph: &G) -> usize
+    where
+        G: Graph,
+        G::Node: GraphObject<Id = u64>,
+        Id<G::Edge>: Eq + Hash,
+    {
+        self.lookups
+            .iter()
+            .map(|id| {
+                subgraph::neighborhood(graph, id, 2, Direction::Outgoing, |_| true).node_count()
+            })
+            .sum()
+    }
+
+    /// Rank the graph with the reference algorithm, the approximate osrank of
+    /// `Prune`, returning the number of nodes ranked.
+    pub fn rank<G>(&self, graph: &G) -> usize
+    where
+        G: Graph,
+        G::Node: GraphObject<Id = u64>,
+        G::Weight: Into<f64>,
+        Id<G::Edge>: Clone,
+    {
+        let prune = Prune::new(HyperParameters::osrank_paper_defaults());
+        // Nothing is pruned without a threshold, so nothing is annotated.
+        let mut ranks: Vec<(u64, NodeRank<f64>)> = Vec::new();
+        let output = prune
+            .execute(&mut PruneContext::default(), graph, &mut ranks, 0)
+            .expect("ranking without a threshold can't fail");
+        output.kept.len() + output.pruned.len()
+    }
+}
+
+fn node_data(node_type: NodeType) -> NodeData<f64> {
+    NodeData {
+        node_type,
+        rank: NodeRank { rank: 0.0 },
+        contributions: ContributionLedger::new(),
+        timestamps: Timestamps::default(),
+    }
+}
+
+fn edge_data(edge_type: EdgeType, weight: f64) -> EdgeData<f64> {
+    EdgeData {
+        edge_type,
+        weight,
+        history: EdgeHistory::new(),
+        timestamps: Timestamps::default(),
+    }
+}
@@ -0,0 +1,53 @@
+//! Cooperative cancellation of long-running algorithms.
+
+use alloc::sync::Arc;
+use core::fmt;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// A token used to request the cancellation of an algorithm execution, eg.
+/// when a reorg invalidates the graph it is running against.
+///
+/// Clones of a token share its state: cancelling any of them cancels all of
+/// them. Cancellation is cooperative, algorithms check the token at regular
+/// intervals and stop with an error as soon as they notice it was cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Create a token which isn't cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst)
+    }
+
+    /// Whether cancellation was requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Return `Err(Cancelled)` if cancellation was requested. Handy to bail
+    /// out with `?`.
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// The error of an execution that was cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "execution was cancelled")
+    }
+}
+
+impl core::error::Error for Cancelled {}
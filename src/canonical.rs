@@ -0,0 +1,134 @@
+//! A canonical representation of graphs, independent of the backend storing
+//! them.
+//!
+//! Backends iterate over nodes and edges in different orders, and keep
+//! bookkeeping data, such as timestamps, that others don't. `canonicalize`
+//! lists nodes and edges sorted by id, with normalized data, so that graphs
+//! holding the same state compare equal and serialize to the same bytes.
+
+use alloc::vec::Vec;
+
+use super::types::{EdgeData, NodeData, NodeRank, Timestamps};
+use super::{Data, Direction, Edge, Graph, GraphObject, Id};
+
+/// Data with a canonical value, independent of how it was stored.
+pub trait Canonical {
+    /// The canonical value of the data.
+    fn canonical(&self) -> Self;
+}
+
+macro_rules! canonical_float {
+    ($($t:ty),*) => {$(
+        /// Negative zero is made positive, and every NaN is the same NaN.
+        impl Canonical for $t {
+            fn canonical(&self) -> Self {
+                if self.is_nan() {
+                    <$t>::NAN
+                } else if *self == 0.0 {
+                    0.0
+                } else {
+                    *self
+                }
+            }
+        }
+    )*};
+}
+
+macro_rules! canonical_int {
+    ($($t:ty),*) => {$(
+        impl Canonical for $t {
+            fn canonical(&self) -> Self {
+                *self
+            }
+        }
+    )*};
+}
+
+canonical_float!(f32, f64);
+canonical_int!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// Timestamps are dropped, as only some backends maintain them.
+impl<W: Canonical> Canonical for NodeData<W> {
+    fn canonical(&self) -> Self {
+        NodeData {
+            node_type: self.node_type.clone(),
+            rank: NodeRank {
+                rank: self.rank.rank.canonical(),
+            },
+            contributions: self.contributions.clone(),
+            timestamps: Timestamps::default(),
+        }
+    }
+}
+
+/// Timestamps are dropped, as only some backends maintain them.
+impl<W: Canonical> Canonical for EdgeData<W> {
+    fn canonical(&self) -> Self {
+        EdgeData {
+            edge_type: self.edge_type.clone(),
+            weight: self.weight.canonical(),
+            history: self.history.clone(),
+            timestamps: Timestamps::default(),
+        }
+    }
+}
+
+/// An edge of a `CanonicalForm`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CanonicalEdge<NodeId, EdgeId, E> {
+    pub id: EdgeId,
+    pub source: NodeId,
+    pub target: NodeId,
+    pub data: E,
+}
+
+/// The nodes and edges of a graph, sorted by id, with canonical data.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CanonicalForm<NodeId, EdgeId, N, E> {
+    /// The nodes, by increasing id.
+    pub nodes: Vec<(NodeId, N)>,
+    /// The edges, by increasing id.
+    pub edges: Vec<CanonicalEdge<NodeId, EdgeId, E>>,
+}
+
+/// The `CanonicalForm` of a graph.
+pub type CanonicalGraph<G> = CanonicalForm<
+    Id<<G as Graph>::Node>,
+    Id<<G as Graph>::Edge>,
+    Data<<G as Graph>::Node>,
+    Data<<G as Graph>::Edge>,
+>;
+
+/// The canonical form of `graph`.
+pub fn canonicalize<G>(graph: &G) -> CanonicalGraph<G>
+where
+    G: Graph,
+    Id<G::Node>: Clone + Ord,
+    Id<G::Edge>: Clone + Ord,
+    G::NodeData: Canonical,
+    G::EdgeData: Canonical,
+{
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+
+    for node in graph.nodes() {
+        nodes.push((node.id().clone(), node.data().canonical()));
+
+        for e in graph.edges_directed(node.id(), Direction::Outgoing) {
+            if let Some(edge) = graph.get_edge(e.id) {
+                edges.push(CanonicalEdge {
+                    id: edge.id().clone(),
+                    source: edge.source().clone(),
+                    target: edge.target().clone(),
+                    data: edge.data().canonical(),
+                });
+            }
+        }
+    }
+    nodes.sort_by(|(a, _), (b, _)| a.cmp(b));
+    edges.sort_by(|a, b| a.id.cmp(&b.id));
+
+    CanonicalForm { nodes, edges }
+}
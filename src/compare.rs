@@ -0,0 +1,280 @@
+//! Comparing graphs, eg. a graph replayed by a follower against the leader's.
+//!
+//! `eq_by_ids` checks that two graphs hold the same nodes and edges under the
+//! same ids, in time linear in their size. `is_isomorphic` ignores the ids and
+//! searches for a mapping between the nodes of the graphs, which takes
+//! exponential time in the worst case, so it is limited to graphs of at most
+//! `MAX_ISOMORPHISM_NODES` nodes.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
+
+use super::{Data, Direction, Edge, Graph, GraphObject, Id};
+
+/// The maximum number of nodes of the graphs compared by `is_isomorphic`.
+pub const MAX_ISOMORPHISM_NODES: usize = 64;
+
+/// Graphs too large to be checked for isomorphism.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TooLarge {
+    /// The number of nodes of the larger graph.
+    pub nodes: usize,
+}
+
+impl fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "graph has {} nodes, isomorphism is checked for at most {}",
+            self.nodes, MAX_ISOMORPHISM_NODES
+        )
+    }
+}
+
+impl std::error::Error for TooLarge {}
+
+/// Whether `a` and `b` have the same nodes and edges: the same node ids with
+/// the same data, and the same edge ids with the same endpoints and data.
+pub fn eq_by_ids<G, H>(a: &G, b: &H) -> bool
+where
+    G: Graph,
+    H: Graph<NodeData = G::NodeData, EdgeData = G::EdgeData, Weight = G::Weight>,
+    H::Node: GraphObject<Id = Id<G::Node>>,
+    H::Edge: GraphObject<Id = Id<G::Edge>>,
+    Id<G::Node>: PartialEq,
+    Id<G::Edge>: Eq + Hash,
+    G::NodeData: PartialEq,
+    G::EdgeData: PartialEq,
+{
+    if a.nodes().count() != b.nodes().count() {
+        return false;
+    }
+    let mut edges = HashSet::new();
+
+    for node in a.nodes() {
+        if b.node_data(node.id()) != Some(node.data()) {
+            return false;
+        }
+        for e in a.edges_directed(node.id(), Direction::Outgoing) {
+            let same = match (a.get_edge(e.id), b.get_edge(e.id)) {
+                (Some(x), Some(y)) => {
+                    x.source() == y.source() && x.target() == y.target() && x.data() == y.data()
+                }
+                _ => false,
+            };
+            if !same {
+                return false;
+            }
+            edges.insert(e.id);
+        }
+    }
+    // Every edge of `a` is in `b`: they are equal if `b` has no other edge.
+    let count: usize = b
+        .nodes()
+        .map(|n| b.edges_directed(n.id(), Direction::Outgoing).len())
+        .sum();
+    count == edges.len()
+}
+
+/// Whether there is a bijection between the nodes of `a` and `b` preserving
+/// the edges, ignoring ids and data. Parallel edges are counted.
+pub fn is_isomorphic<G, H>(a: &G, b: &H) -> Result<bool, TooLarge>
+where
+    G: Graph,
+    H: Graph,
+    Id<G::Node>: Eq + Hash,
+    Id<H::Node>: Eq + Hash,
+{
+    is_isomorphic_matching(a, b, |_, _| true, |_, _| true)
+}
+
+/// Like `is_isomorphic`, but nodes are only mapped to nodes whose data
+/// `node_match`, and edges to edges whose data `edge_match`. Both must be
+/// equivalence relations, eg. equality of the node types.
+pub fn is_isomorphic_matching<G, H, NM, EM>(
+    a: &G,
+    b: &H,
+    mut node_match: NM,
+    mut edge_match: EM,
+) -> Result<bool, TooLarge>
+where
+    G: Graph,
+    H: Graph,
+    Id<G::Node>: Eq + Hash,
+    Id<H::Node>: Eq + Hash,
+    NM: FnMut(&Data<G::Node>, &Data<H::Node>) -> bool,
+    EM: FnMut(&Data<G::Edge>, &Data<H::Edge>) -> bool,
+{
+    let x = Indexed::new(a);
+    let y = Indexed::new(b);
+    let nodes = x.nodes.len().max(y.nodes.len());
+
+    if nodes > MAX_ISOMORPHISM_NODES {
+        return Err(TooLarge { nodes });
+    }
+    if x.nodes.len() != y.nodes.len() || x.edges != y.edges {
+        return Ok(false);
+    }
+
+    // The nodes of `b` each node of `a` can be mapped to, on their own.
+    let mut candidates = Vec::with_capacity(x.nodes.len());
+    for (i, data) in x.nodes.iter().enumerate() {
+        let mut to = Vec::new();
+        for (j, other) in y.nodes.iter().enumerate() {
+            if x.degrees[i] == y.degrees[j]
+                && node_match(data, other)
+                && edges_match(x.between(i, i), y.between(j, j), &mut edge_match)
+            {
+                to.push(j);
+            }
+        }
+        if to.is_empty() {
+            return Ok(false);
+        }
+        candidates.push(to);
+    }
+
+    let mut search = Search {
+        x: &x,
+        y: &y,
+        candidates: &candidates,
+        mapping: vec![None; x.nodes.len()],
+        used: vec![false; y.nodes.len()],
+        edge_match,
+    };
+    Ok(search.extend(0))
+}
+
+/// Adjacency of a graph, with nodes indexed from zero.
+struct Indexed<'a, N, E> {
+    nodes: Vec<&'a N>,
+    /// The outgoing and incoming degrees of every node.
+    degrees: Vec<(usize, usize)>,
+    /// The data of the edges between two nodes, in their direction.
+    adjacency: HashMap<(usize, usize), Vec<&'a E>>,
+    edges: usize,
+}
+
+impl<'a, N, E> Indexed<'a, N, E> {
+    fn new<G>(graph: &'a G) -> Self
+    where
+        G: Graph<NodeData = N, EdgeData = E>,
+        Id<G::Node>: Eq + Hash,
+    {
+        let mut index = HashMap::new();
+        let mut nodes = Vec::new();
+        for node in graph.nodes() {
+            index.insert(node.id(), nodes.len());
+            nodes.push(node.data());
+        }
+
+        let mut degrees = vec![(0, 0); nodes.len()];
+        let mut adjacency: HashMap<_, Vec<_>> = HashMap::new();
+        let mut edges = 0;
+        for (&id, &from) in index.iter() {
+            for e in graph.edges_directed(id, Direction::Outgoing) {
+                let (to, edge) = match (index.get(e.to), graph.get_edge(e.id)) {
+                    (Some(&to), Some(edge)) => (to, edge),
+                    _ => continue,
+                };
+                degrees[from].0 += 1;
+                degrees[to].1 += 1;
+                adjacency.entry((from, to)).or_default().push(edge.data());
+                edges += 1;
+            }
+        }
+
+        Indexed {
+            nodes,
+            degrees,
+            adjacency,
+            edges,
+        }
+    }
+
+    fn between(&self, from: usize, to: usize) -> &[&'a E] {
+        self.adjacency
+            .get(&(from, to))
+            .map(|edges| edges.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+/// Whether the edges of `xs` can be paired with the edges of `ys`.
+fn edges_match<E, F, EM>(xs: &[&E], ys: &[&F], edge_match: &mut EM) -> bool
+where
+    EM: FnMut(&E, &F) -> bool,
+{
+    if xs.len() != ys.len() {
+        return false;
+    }
+    // As `edge_match` is an equivalence, pairing greedily finds a pairing if
+    // there is one.
+    let mut paired = vec![false; ys.len()];
+    xs.iter().all(|x| {
+        let found = (0..ys.len()).find(|&j| !paired[j] && edge_match(x, ys[j]));
+        if let Some(j) = found {
+            paired[j] = true;
+        }
+        found.is_some()
+    })
+}
+
+/// A backtracking search for an isomorphism, mapping the nodes of `x` in
+/// order.
+struct Search<'s, 'a, 'b, N, E, M, F, EM> {
+    x: &'s Indexed<'a, N, E>,
+    y: &'s Indexed<'b, M, F>,
+    candidates: &'s [Vec<usize>],
+    mapping: Vec<Option<usize>>,
+    used: Vec<bool>,
+    edge_match: EM,
+}
+
+impl<N, E, M, F, EM> Search<'_, '_, '_, N, E, M, F, EM>
+where
+    EM: FnMut(&E, &F) -> bool,
+{
+    fn extend(&mut self, i: usize) -> bool {
+        if i == self.mapping.len() {
+            return true;
+        }
+        for &j in &self.candidates[i] {
+            if self.used[j] || !self.consistent(i, j) {
+                continue;
+            }
+            self.mapping[i] = Some(j);
+            self.used[j] = true;
+            if self.extend(i + 1) {
+                return true;
+            }
+            self.mapping[i] = None;
+            self.used[j] = false;
+        }
+        false
+    }
+
+    /// Whether mapping `i` to `j` preserves the edges to the nodes already
+    /// mapped.
+    fn consistent(&mut self, i: usize, j: usize) -> bool {
+        for k in 0..i {
+            let l = match self.mapping[k] {
+                Some(l) => l,
+                None => continue,
+            };
+            if !edges_match(
+                self.x.between(i, k),
+                self.y.between(j, l),
+                &mut self.edge_match,
+            ) || !edges_match(
+                self.x.between(k, i),
+                self.y.between(l, j),
+                &mut self.edge_match,
+            ) {
+                return false;
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,254 @@
+//! Loading `HyperParameters` from TOML or JSON configuration files.
+//!
+//! A configuration starts from an optional named preset, whose values the
+//! other fields override. Edge weights are keyed by `EdgeTypeTag` name:
+//!
+//! ```toml
+//! preset = "paper"
+//! r_value = 100
+//!
+//! [damping_factors]
+//! account = 0.9
+//!
+//! [edge_weights]
+//! dependency = 0.5
+//! "custom:code_review" = 0.25
+//! ```
+//!
+//! Without a preset, the damping factors and `r_value` are required.
+//! Loaded parameters are validated, see `validate`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::types::{DampingFactors, HyperParameters, ParseTypeError};
+
+/// The names of the presets.
+pub const PRESETS: [&str; 2] = ["paper", "conservative"];
+
+/// The hyperparameters of the preset with the given name, if any.
+///
+/// * `paper`: `HyperParameters::osrank_paper_defaults`.
+/// * `conservative`: `HyperParameters::conservative`.
+pub fn preset(name: &str) -> Option<HyperParameters<f64>> {
+    match name {
+        "paper" => Some(HyperParameters::osrank_paper_defaults()),
+        "conservative" => Some(HyperParameters::conservative()),
+        _ => None,
+    }
+}
+
+/// A failure to load hyperparameters.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file couldn't be read.
+    Io(io::Error),
+    /// The file isn't valid TOML or JSON, or has unknown fields.
+    Parse(String),
+    /// The file extension is neither `toml` nor `json`.
+    UnknownFormat(String),
+    /// No preset has this name.
+    UnknownPreset(String),
+    /// An edge weight has an unknown edge type.
+    EdgeType(ParseTypeError),
+    /// A required field is missing, and not set by a preset.
+    MissingField(&'static str),
+    /// A parameter is out of its range.
+    Invalid {
+        /// The parameter, eg. `damping_factors.project`.
+        field: String,
+        /// Its value.
+        value: f64,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "i/o error: {}", err),
+            ConfigError::Parse(msg) => write!(f, "parse error: {}", msg),
+            ConfigError::UnknownFormat(ext) => write!(f, "unknown config format {:?}", ext),
+            ConfigError::UnknownPreset(name) => write!(f, "unknown preset {:?}", name),
+            ConfigError::EdgeType(err) => write!(f, "invalid edge weight: {}", err),
+            ConfigError::MissingField(field) => write!(f, "missing field {}", field),
+            ConfigError::Invalid { field, value } => {
+                write!(f, "invalid value {} for {}", value, field)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io(err) => Some(err),
+            ConfigError::EdgeType(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(err: io::Error) -> Self {
+        ConfigError::Io(err)
+    }
+}
+
+impl From<ParseTypeError> for ConfigError {
+    fn from(err: ParseTypeError) -> Self {
+        ConfigError::EdgeType(err)
+    }
+}
+
+/// Damping factors, of which either can be left to the preset.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DampingConfig {
+    pub project: Option<f64>,
+    pub account: Option<f64>,
+}
+
+/// The contents of a configuration file. Every field is optional, and
+/// overrides the preset.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The preset the configuration starts from, see `PRESETS`.
+    pub preset: Option<String>,
+    pub pruning_threshold: Option<f64>,
+    pub damping_factors: DampingConfig,
+    pub r_value: Option<u32>,
+    /// Weights by edge type tag, eg. `dependency`. They are added to the
+    /// weights of the preset.
+    pub edge_weights: BTreeMap<String, f64>,
+    pub max_edge_weight_share: Option<f64>,
+    pub max_user_rank: Option<f64>,
+    pub rank_decay: Option<f64>,
+}
+
+impl Config {
+    /// Parse a TOML configuration.
+    pub fn from_toml(s: &str) -> Result<Self, ConfigError> {
+        toml::from_str(s).map_err(|e| ConfigError::Parse(e.to_string()))
+    }
+
+    /// Parse a JSON configuration.
+    pub fn from_json(s: &str) -> Result<Self, ConfigError> {
+        serde_json::from_str(s).map_err(|e| ConfigError::Parse(e.to_string()))
+    }
+
+    /// The validated hyperparameters of the configuration.
+    pub fn hyperparameters(&self) -> Result<HyperParameters<f64>, ConfigError> {
+        let mut params = match &self.preset {
+            Some(name) => preset(name).ok_or_else(|| ConfigError::UnknownPreset(name.clone()))?,
+            None => HyperParameters {
+                pruning_threshold: 0.0,
+                damping_factors: DampingFactors {
+                    project: required(self.damping_factors.project, "damping_factors.project")?,
+                    account: required(self.damping_factors.account, "damping_factors.account")?,
+                },
+                r_value: required(self.r_value, "r_value")?,
+                edge_weights: BTreeMap::new(),
+                max_edge_weight_share: None,
+                max_user_rank: None,
+                rank_decay: None,
+            },
+        };
+
+        if let Some(project) = self.damping_factors.project {
+            params.damping_factors.project = project;
+        }
+        if let Some(account) = self.damping_factors.account {
+            params.damping_factors.account = account;
+        }
+        if let Some(r_value) = self.r_value {
+            params.r_value = r_value;
+        }
+        if let Some(threshold) = self.pruning_threshold {
+            params.pruning_threshold = threshold;
+        }
+        for (tag, weight) in &self.edge_weights {
+            params.edge_weights.insert(tag.parse()?, *weight);
+        }
+        params.max_edge_weight_share = self.max_edge_weight_share.or(params.max_edge_weight_share);
+        params.max_user_rank = self.max_user_rank.or(params.max_user_rank);
+        params.rank_decay = self.rank_decay.or(params.rank_decay);
+
+        validate(&params)?;
+        Ok(params)
+    }
+}
+
+fn required<T>(value: Option<T>, field: &'static str) -> Result<T, ConfigError> {
+    value.ok_or(ConfigError::MissingField(field))
+}
+
+/// Load hyperparameters from a TOML configuration.
+pub fn from_toml(s: &str) -> Result<HyperParameters<f64>, ConfigError> {
+    Config::from_toml(s)?.hyperparameters()
+}
+
+/// Load hyperparameters from a JSON configuration.
+pub fn from_json(s: &str) -> Result<HyperParameters<f64>, ConfigError> {
+    Config::from_json(s)?.hyperparameters()
+}
+
+/// Load hyperparameters from a `.toml` or `.json` file.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<HyperParameters<f64>, ConfigError> {
+    let path = path.as_ref();
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    match ext.as_str() {
+        "toml" => from_toml(&fs::read_to_string(path)?),
+        "json" => from_json(&fs::read_to_string(path)?),
+        _ => Err(ConfigError::UnknownFormat(ext)),
+    }
+}
+
+/// Check that the hyperparameters are in range: probabilities, shares and
+/// decay factors in `[0, 1]`, and finite, non-negative weights and pruning
+/// threshold.
+pub fn validate(params: &HyperParameters<f64>) -> Result<(), ConfigError> {
+    let check = |field: &str, value: f64, max: f64| {
+        if (0.0..=max).contains(&value) {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid {
+                field: field.to_owned(),
+                value,
+            })
+        }
+    };
+
+    check("pruning_threshold", params.pruning_threshold, f64::MAX)?;
+    check(
+        "damping_factors.project",
+        params.damping_factors.project,
+        1.0,
+    )?;
+    check(
+        "damping_factors.account",
+        params.damping_factors.account,
+        1.0,
+    )?;
+    for (tag, weight) in &params.edge_weights {
+        check(&format!("edge_weights.{}", tag), *weight, f64::MAX)?;
+    }
+    if let Some(share) = params.max_edge_weight_share {
+        check("max_edge_weight_share", share, 1.0)?;
+    }
+    if let Some(rank) = params.max_user_rank {
+        check("max_user_rank", rank, 1.0)?;
+    }
+    if let Some(decay) = params.rank_decay {
+        check("rank_decay", decay, 1.0)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,67 @@
+//! Human-readable listings of graphs, for debugging.
+//!
+//! `dump` lists every node, sorted by id, with its type, followed by its
+//! outgoing edges with their type and weight:
+//!
+//! ```text
+//! 1 user(3)
+//!   -> 2 user_to_project_contribution(3) weight=0.75 #10
+//! 2 project(3)
+//! ```
+
+use std::fmt;
+use std::hash::Hash;
+
+use super::subgraph;
+use super::{Direction, Edge, Graph, GraphObject, Id, Node};
+
+/// Write the adjacency listing of `graph` to `out`.
+pub fn dump<G, W>(graph: &G, out: &mut W) -> fmt::Result
+where
+    G: Graph,
+    G::Weight: fmt::Debug,
+    Id<G::Node>: Ord + fmt::Debug,
+    Id<G::Edge>: Ord + fmt::Debug,
+    W: fmt::Write,
+{
+    let mut nodes: Vec<&G::Node> = graph.nodes().collect();
+    nodes.sort_by(|a, b| a.id().cmp(b.id()));
+
+    for node in nodes {
+        writeln!(out, "{:?} {}", node.id(), node.node_type())?;
+
+        let mut edges: Vec<&G::Edge> = graph
+            .edges_directed(node.id(), Direction::Outgoing)
+            .into_iter()
+            .filter_map(|e| graph.get_edge(e.id))
+            .collect();
+        edges.sort_by(|a, b| (a.target(), a.id()).cmp(&(b.target(), b.id())));
+
+        for edge in edges {
+            writeln!(
+                out,
+                "  -> {:?} {} weight={:?} #{:?}",
+                edge.target(),
+                edge.edge_type(),
+                edge.weight(),
+                edge.id()
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Write the adjacency listing of the nodes at most `k` hops away from
+/// `root`, in either direction, and of the edges between them. Nothing is
+/// written if `root` isn't in the graph.
+pub fn dump_neighborhood<G, W>(graph: &G, root: &Id<G::Node>, k: usize, out: &mut W) -> fmt::Result
+where
+    G: Graph,
+    G::Weight: fmt::Debug,
+    Id<G::Node>: Ord + Hash + fmt::Debug,
+    Id<G::Edge>: Ord + Hash + fmt::Debug,
+    W: fmt::Write,
+{
+    let view = subgraph::neighborhood(graph, root, k, Direction::Both, |_| true);
+    dump(&view, out)
+}
@@ -0,0 +1,271 @@
+//! Operations on sequences of `GraphDiffOwned`.
+//!
+//! `compact` shortens a diff log without changing its effect, and `invert`
+//! computes the diffs undoing a log, to roll back a rejected checkpoint with
+//! `apply`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+use super::types::GraphDiffOwned;
+use super::{Data, Graph, GraphWriter, Id};
+
+/// A failure to invert a diff log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffError {
+    /// The diff at the given index updates a node that was neither added by
+    /// an earlier diff nor found in the base graph, so its previous data is
+    /// unknown.
+    UnknownNode(usize),
+    /// The diff at the given index updates an edge that was neither added by
+    /// an earlier diff nor found in the base graph.
+    UnknownEdge(usize),
+}
+
+impl fmt::Display for DiffError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiffError::UnknownNode(index) => {
+                write!(f, "diff {} updates an unknown node", index)
+            }
+            DiffError::UnknownEdge(index) => {
+                write!(f, "diff {} updates an unknown edge", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DiffError {}
+
+/// Shorten a diff log without changing its effect on a graph.
+///
+/// Nodes and edges added then removed are dropped altogether, along with the
+/// edges of such nodes. Updates of a node added in the log are merged into
+/// the addition, and repeated updates of a node are merged into the last one.
+/// Updates of a node followed by its removal are dropped. The same goes for
+/// edges.
+pub fn compact<G>(diffs: Vec<GraphDiffOwned<G>>) -> Vec<GraphDiffOwned<G>>
+where
+    G: Graph,
+    Id<G::Node>: Clone + Eq + Hash,
+    Id<G::Edge>: Clone + Eq + Hash,
+{
+    let mut out: Vec<Option<GraphDiffOwned<G>>> = Vec::with_capacity(diffs.len());
+    // Indexes in `out` of the pending additions and updates.
+    let mut added_nodes: HashMap<Id<G::Node>, usize> = HashMap::new();
+    let mut updated_nodes: HashMap<Id<G::Node>, usize> = HashMap::new();
+    let mut added_edges: HashMap<Id<G::Edge>, usize> = HashMap::new();
+    let mut updated_edges: HashMap<Id<G::Edge>, usize> = HashMap::new();
+
+    for diff in diffs {
+        match diff {
+            GraphDiffOwned::NodeAdded { ref id, .. } => {
+                added_nodes.insert(id.clone(), out.len());
+            }
+            GraphDiffOwned::NodeUpdated { id, data } => {
+                let pending = added_nodes.get(&id).or_else(|| updated_nodes.get(&id));
+                if let Some(&index) = pending {
+                    match &mut out[index] {
+                        Some(GraphDiffOwned::NodeAdded { data: d, .. })
+                        | Some(GraphDiffOwned::NodeUpdated { data: d, .. }) => *d = data,
+                        _ => unreachable!("pending diffs are node additions or updates"),
+                    }
+                } else {
+                    updated_nodes.insert(id.clone(), out.len());
+                    out.push(Some(GraphDiffOwned::NodeUpdated { id, data }));
+                }
+                continue;
+            }
+            GraphDiffOwned::NodeDeleted { ref id, .. } => {
+                if let Some(index) = updated_nodes.remove(id) {
+                    out[index] = None;
+                }
+                if let Some(index) = added_nodes.remove(id) {
+                    out[index] = None;
+                    // The node's edges were all added in the log as well.
+                    added_edges.retain(|_, &mut index| {
+                        let incident = match &out[index] {
+                            Some(GraphDiffOwned::EdgeAdded { source, target, .. }) => {
+                                source == id || target == id
+                            }
+                            _ => false,
+                        };
+                        if incident {
+                            out[index] = None;
+                        }
+                        !incident
+                    });
+                    continue;
+                }
+            }
+            GraphDiffOwned::EdgeAdded { ref id, .. } => {
+                added_edges.insert(id.clone(), out.len());
+            }
+            GraphDiffOwned::EdgeUpdated {
+                id,
+                source,
+                target,
+                data,
+            } => {
+                let pending = added_edges.get(&id).or_else(|| updated_edges.get(&id));
+                if let Some(&index) = pending {
+                    match &mut out[index] {
+                        Some(GraphDiffOwned::EdgeAdded { data: d, .. })
+                        | Some(GraphDiffOwned::EdgeUpdated { data: d, .. }) => *d = data,
+                        _ => unreachable!("pending diffs are edge additions or updates"),
+                    }
+                } else {
+                    updated_edges.insert(id.clone(), out.len());
+                    out.push(Some(GraphDiffOwned::EdgeUpdated {
+                        id,
+                        source,
+                        target,
+                        data,
+                    }));
+                }
+                continue;
+            }
+            GraphDiffOwned::EdgeDeleted { ref id, .. } => {
+                if let Some(index) = updated_edges.remove(id) {
+                    out[index] = None;
+                }
+                if let Some(index) = added_edges.remove(id) {
+                    out[index] = None;
+                    continue;
+                }
+            }
+        }
+        out.push(Some(diff));
+    }
+    out.into_iter().flatten().collect()
+}
+
+/// The diffs undoing `diffs`, in the order they must be applied.
+///
+/// `base` is the graph before `diffs` are applied: the previous data of the
+/// nodes and edges updated by the log is looked up there, unless they were
+/// added or updated by an earlier diff. Nodes removed by the log must have their edges
+/// removed by an explicit `EdgeDeleted` in the log, or the edges won't be
+/// restored.
+pub fn invert<G>(diffs: &[GraphDiffOwned<G>], base: &G) -> Result<Vec<GraphDiffOwned<G>>, DiffError>
+where
+    G: Graph,
+    Id<G::Node>: Clone + Eq + Hash,
+    Id<G::Edge>: Clone + Eq + Hash,
+    Data<G::Node>: Clone,
+    Data<G::Edge>: Clone,
+{
+    // The data of the nodes and edges modified so far.
+    let mut current: HashMap<&Id<G::Node>, &Data<G::Node>> = HashMap::new();
+    let mut current_edges: HashMap<&Id<G::Edge>, &Data<G::Edge>> = HashMap::new();
+    let mut inverse = Vec::with_capacity(diffs.len());
+
+    for (index, diff) in diffs.iter().enumerate() {
+        let undo = match diff {
+            GraphDiffOwned::NodeAdded { id, data } => {
+                current.insert(id, data);
+                GraphDiffOwned::NodeDeleted {
+                    id: id.clone(),
+                    data: data.clone(),
+                }
+            }
+            GraphDiffOwned::NodeDeleted { id, data } => {
+                current.remove(id);
+                GraphDiffOwned::NodeAdded {
+                    id: id.clone(),
+                    data: data.clone(),
+                }
+            }
+            GraphDiffOwned::NodeUpdated { id, data } => {
+                let previous = current
+                    .insert(id, data)
+                    .or_else(|| base.node_data(id))
+                    .ok_or(DiffError::UnknownNode(index))?;
+                GraphDiffOwned::NodeUpdated {
+                    id: id.clone(),
+                    data: previous.clone(),
+                }
+            }
+            GraphDiffOwned::EdgeAdded {
+                id,
+                source,
+                target,
+                data,
+            } => {
+                current_edges.insert(id, data);
+                GraphDiffOwned::EdgeDeleted {
+                    id: id.clone(),
+                    source: source.clone(),
+                    target: target.clone(),
+                    data: data.clone(),
+                }
+            }
+            GraphDiffOwned::EdgeDeleted {
+                id,
+                source,
+                target,
+                data,
+            } => {
+                current_edges.remove(id);
+                GraphDiffOwned::EdgeAdded {
+                    id: id.clone(),
+                    source: source.clone(),
+                    target: target.clone(),
+                    data: data.clone(),
+                }
+            }
+            GraphDiffOwned::EdgeUpdated {
+                id,
+                source,
+                target,
+                data,
+            } => {
+                let previous = current_edges
+                    .insert(id, data)
+                    .or_else(|| base.edge_data(id))
+                    .ok_or(DiffError::UnknownEdge(index))?;
+                GraphDiffOwned::EdgeUpdated {
+                    id: id.clone(),
+                    source: source.clone(),
+                    target: target.clone(),
+                    data: previous.clone(),
+                }
+            }
+        };
+        inverse.push(undo);
+    }
+    inverse.reverse();
+    Ok(inverse)
+}
+
+/// Apply diffs to `graph`, in order.
+pub fn apply<G, I>(graph: &mut G, diffs: I)
+where
+    G: GraphWriter,
+    I: IntoIterator<Item = GraphDiffOwned<G>>,
+{
+    for diff in diffs {
+        match diff {
+            GraphDiffOwned::NodeAdded { id, data } => graph.add_node(id, data),
+            GraphDiffOwned::NodeDeleted { id, .. } => graph.remove_node(id),
+            GraphDiffOwned::NodeUpdated { id, data } => {
+                if let Some(d) = graph.node_data_mut(&id) {
+                    *d = data;
+                }
+            }
+            GraphDiffOwned::EdgeAdded {
+                id,
+                source,
+                target,
+                data,
+            } => graph.add_edge(id, &source, &target, data),
+            GraphDiffOwned::EdgeDeleted { id, .. } => graph.remove_edge(id),
+            GraphDiffOwned::EdgeUpdated { id, data, .. } => {
+                if let Some(d) = graph.edge_data_mut(&id) {
+                    *d = data;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,366 @@
+//! Offloading the random walks of Monte Carlo osrank to executors, eg. on
+//! other machines.
+//!
+//! Walks are distributed over the shards of a `partition::Assignment`: the
+//! executor of every shard simulates the walks on its nodes, and hands the
+//! walks leaving its shard back to the `WalkDriver`, which forwards them to
+//! the executor of their next shard. Transports are left to implementors of
+//! `RemoteWalkExecutor`, and `LocalWalkExecutor` simulates walks in process.
+//!
+//! Every walk draws its random numbers from its own stream of the seed, and
+//! carries the number of them drawn so far, so that walks, and the visits
+//! they count, depend neither on how the graph is sharded nor on the order
+//! in which executors reply.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::convert::Infallible;
+use std::fmt;
+use std::hash::Hash;
+use std::mem;
+
+use super::cancel::{CancellationToken, Cancelled};
+use super::partition::Assignment;
+use super::rng::{AlgorithmSeed, SeedableAlgorithmRng};
+use super::sampling::WeightedNeighbors;
+use super::types::{DampingFactors, NodeType};
+use super::{Direction, Graph, Id, Node};
+
+/// A walk to simulate on the shard of its node, from its source or from
+/// where another shard handed it off.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WalkRequest<NodeId> {
+    /// The index of the walk, and of its stream of random numbers.
+    pub walk: u64,
+    /// The node the walk is on, whose visit is yet to be counted.
+    pub node: NodeId,
+    /// The number of steps taken so far.
+    pub steps: u32,
+    /// The number of random numbers drawn so far.
+    pub draws: u64,
+}
+
+impl<NodeId> WalkRequest<NodeId> {
+    /// A walk starting from `source`.
+    pub fn start(walk: u64, source: NodeId) -> Self {
+        WalkRequest {
+            walk,
+            node: source,
+            steps: 0,
+            draws: 0,
+        }
+    }
+}
+
+/// Walks to simulate on a shard, along with the parameters of the
+/// simulation.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WalkBatch<NodeId> {
+    /// The seed the streams of random numbers of walks are split from.
+    pub seed: u64,
+    /// The probabilities that walks continue, by node type.
+    pub damping_factors: DampingFactors,
+    /// The maximum number of steps of a walk.
+    pub max_steps: u32,
+    /// The walks, on nodes of the shard.
+    pub walks: Vec<WalkRequest<NodeId>>,
+}
+
+/// The outcome of a `WalkBatch`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WalkResults<NodeId> {
+    /// The number of visits of the walks to nodes of the shard.
+    pub visits: Vec<(NodeId, u64)>,
+    /// The walks which left the shard, to resume on the shard of their node.
+    pub handoffs: Vec<WalkRequest<NodeId>>,
+}
+
+/// An executor simulating walks on a shard of the graph, wherever it runs.
+///
+/// Results are received in the order batches were submitted. The driver
+/// submits batches to every executor before receiving any results, so that
+/// executors can simulate walks concurrently.
+pub trait RemoteWalkExecutor<NodeId> {
+    /// A failure of the executor, or of its transport.
+    type Error;
+
+    /// Submit a batch of walks on nodes of the shard.
+    fn submit(&mut self, batch: WalkBatch<NodeId>) -> Result<(), Self::Error>;
+
+    /// Receive the results of the oldest batch whose results weren't
+    /// received yet.
+    fn receive(&mut self) -> Result<WalkResults<NodeId>, Self::Error>;
+}
+
+/// An executor simulating walks in process, on a graph holding the nodes of
+/// its shard along with all their outgoing edges, eg. the whole graph or a
+/// `partition::ShardedGraph`, but not the subgraph of the shard alone.
+pub struct LocalWalkExecutor<'a, G: Graph> {
+    graph: &'a G,
+    /// The assignment and shard of the executor, unless it owns every node.
+    shard: Option<(&'a Assignment<Id<G::Node>>, usize)>,
+    neighbors: WeightedNeighbors<G>,
+    results: VecDeque<WalkResults<Id<G::Node>>>,
+}
+
+impl<'a, G> LocalWalkExecutor<'a, G>
+where
+    G: Graph,
+    G::Weight: Into<f64>,
+    Id<G::Node>: Clone + Ord + Hash,
+    Id<G::Edge>: Clone + Ord,
+{
+    /// An executor of walks on every node of `graph`.
+    pub fn new(graph: &'a G) -> Self {
+        LocalWalkExecutor {
+            graph,
+            shard: None,
+            neighbors: WeightedNeighbors::new(Direction::Outgoing),
+            results: VecDeque::new(),
+        }
+    }
+
+    /// An executor of walks on the nodes of shard `shard` of `assignment`.
+    /// Walks reaching other nodes are handed off.
+    pub fn for_shard(graph: &'a G, assignment: &'a Assignment<Id<G::Node>>, shard: usize) -> Self {
+        LocalWalkExecutor {
+            shard: Some((assignment, shard)),
+            ..Self::new(graph)
+        }
+    }
+
+    fn owns(&self, node: &Id<G::Node>) -> bool {
+        match self.shard {
+            Some((assignment, shard)) => assignment.shard_of(node) == Some(shard),
+            None => true,
+        }
+    }
+
+    /// Simulate the walks of a batch until they end or leave the shard.
+    pub fn simulate(&mut self, batch: WalkBatch<Id<G::Node>>) -> WalkResults<Id<G::Node>> {
+        let streams = batch.seed.into_rng();
+        let mut visits: BTreeMap<Id<G::Node>, u64> = BTreeMap::new();
+        let mut handoffs = Vec::new();
+
+        for mut request in batch.walks {
+            let mut rng = streams.split(request.walk);
+            rng.jump(request.draws);
+
+            loop {
+                if !self.owns(&request.node) {
+                    handoffs.push(request);
+                    break;
+                }
+                let damping = match self.graph.get_node(&request.node) {
+                    Some(node) => match node.node_type() {
+                        NodeType::User { .. } => batch.damping_factors.account,
+                        _ => batch.damping_factors.project,
+                    },
+                    None => break,
+                };
+                *visits.entry(request.node.clone()).or_insert(0) += 1;
+                if request.steps >= batch.max_steps {
+                    break;
+                }
+
+                request.draws += 1;
+                if rng.next_f64() >= damping {
+                    break;
+                }
+                request.draws += 1;
+                match self
+                    .neighbors
+                    .sample_with(self.graph, &request.node, &mut rng)
+                {
+                    Some((next, _)) => request.node = next.clone(),
+                    None => break,
+                }
+                request.steps += 1;
+            }
+        }
+
+        WalkResults {
+            visits: visits.into_iter().collect(),
+            handoffs,
+        }
+    }
+}
+
+impl<'a, G> RemoteWalkExecutor<Id<G::Node>> for LocalWalkExecutor<'a, G>
+where
+    G: Graph,
+    G::Weight: Into<f64>,
+    Id<G::Node>: Clone + Ord + Hash,
+    Id<G::Edge>: Clone + Ord,
+{
+    type Error = Infallible;
+
+    /// Simulates the walks right away.
+    fn submit(&mut self, batch: WalkBatch<Id<G::Node>>) -> Result<(), Infallible> {
+        let results = self.simulate(batch);
+        self.results.push_back(results);
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<WalkResults<Id<G::Node>>, Infallible> {
+        Ok(self.results.pop_front().unwrap_or(WalkResults {
+            visits: Vec::new(),
+            handoffs: Vec::new(),
+        }))
+    }
+}
+
+/// A failure to simulate walks on executors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalkError<E> {
+    /// There must be exactly one executor per shard.
+    ShardCount {
+        /// The number of shards.
+        shards: usize,
+        /// The number of executors.
+        executors: usize,
+    },
+    /// The executor of a shard failed.
+    Executor {
+        /// The shard.
+        shard: usize,
+        /// The failure.
+        error: E,
+    },
+    /// The execution was cancelled.
+    Cancelled,
+}
+
+impl<E: fmt::Display> fmt::Display for WalkError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WalkError::ShardCount { shards, executors } => {
+                write!(f, "{} executors for {} shards", executors, shards)
+            }
+            WalkError::Executor { shard, error } => {
+                write!(f, "executor of shard {} failed: {}", shard, error)
+            }
+            WalkError::Cancelled => write!(f, "execution was cancelled"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for WalkError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WalkError::Executor { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl<E> From<Cancelled> for WalkError<E> {
+    fn from(_: Cancelled) -> Self {
+        WalkError::Cancelled
+    }
+}
+
+/// Simulates walks on the executors of the shards of a graph.
+#[derive(Debug, Clone)]
+pub struct WalkDriver {
+    /// The seed the streams of random numbers of walks are split from.
+    pub seed: u64,
+    /// The probabilities that walks continue, by node type.
+    pub damping_factors: DampingFactors,
+    /// The maximum number of steps of a walk.
+    pub max_steps: u32,
+    /// The maximum number of walks per batch.
+    pub batch_size: usize,
+}
+
+impl WalkDriver {
+    /// Simulate `walks` on `executors`, where executor `i` simulates the
+    /// walks on the nodes of shard `i` of `assignment`, forwarding the walks
+    /// handed off between shards until all of them end. Returns the number of
+    /// visits of every node.
+    ///
+    /// Walks reaching nodes which aren't assigned end there, unvisited.
+    /// Walks are handed off at most once per step, so any walk still handed
+    /// off after `max_steps` rounds is dropped: only a faulty executor would
+    /// do that.
+    pub fn run<NodeId, E>(
+        &self,
+        executors: &mut [E],
+        assignment: &Assignment<NodeId>,
+        walks: Vec<WalkRequest<NodeId>>,
+        token: &CancellationToken,
+    ) -> Result<BTreeMap<NodeId, u64>, WalkError<E::Error>>
+    where
+        NodeId: Ord,
+        E: RemoteWalkExecutor<NodeId>,
+    {
+        let shards = assignment.shards();
+        if executors.len() != shards {
+            return Err(WalkError::ShardCount {
+                shards,
+                executors: executors.len(),
+            });
+        }
+
+        let mut visits = BTreeMap::new();
+        let mut pending = route(assignment, walks);
+        let mut round = 0;
+        while round <= self.max_steps && pending.iter().any(|walks| !walks.is_empty()) {
+            token.check()?;
+            round += 1;
+
+            // Submit every batch before receiving any results.
+            let mut batches = vec![0; shards];
+            for (shard, mut walks) in mem::take(&mut pending).into_iter().enumerate() {
+                while !walks.is_empty() {
+                    let rest = walks.split_off(walks.len().min(self.batch_size.max(1)));
+                    let batch = WalkBatch {
+                        seed: self.seed,
+                        damping_factors: self.damping_factors.clone(),
+                        max_steps: self.max_steps,
+                        walks: mem::replace(&mut walks, rest),
+                    };
+                    executors[shard]
+                        .submit(batch)
+                        .map_err(|error| WalkError::Executor { shard, error })?;
+                    batches[shard] += 1;
+                }
+            }
+
+            let mut handoffs = Vec::new();
+            for (shard, count) in batches.into_iter().enumerate() {
+                for _ in 0..count {
+                    let results = executors[shard]
+                        .receive()
+                        .map_err(|error| WalkError::Executor { shard, error })?;
+                    for (node, n) in results.visits {
+                        *visits.entry(node).or_insert(0) += n;
+                    }
+                    handoffs.extend(results.handoffs);
+                }
+            }
+            pending = route(assignment, handoffs);
+        }
+        Ok(visits)
+    }
+}
+
+/// Sort walks by the shard of their node, then by index.
+fn route<NodeId: Ord>(
+    assignment: &Assignment<NodeId>,
+    walks: Vec<WalkRequest<NodeId>>,
+) -> Vec<Vec<WalkRequest<NodeId>>> {
+    let mut shards: Vec<Vec<WalkRequest<NodeId>>> =
+        (0..assignment.shards()).map(|_| Vec::new()).collect();
+    for walk in walks {
+        if let Some(shard) = assignment.shard_of(&walk.node) {
+            shards[shard].push(walk);
+        }
+    }
+    for walks in shards.iter_mut() {
+        walks.sort_by_key(|w| w.walk);
+    }
+    shards
+}
@@ -0,0 +1,208 @@
+//! An object-safe, read-only facade over graphs.
+//!
+//! The `Graph` trait can't be used as a trait object, because of its
+//! associated types. `DynGraph` erases them, so that graphs of different
+//! backends can be stored side by side, eg. in a `Vec<Box<dyn DynGraph>>`.
+//! Every `Graph` whose ids can be erased implements `DynGraph`.
+//!
+//! Some methods share their name with methods of `Graph`: on concrete graph
+//! types, importing both traits requires disambiguating the calls.
+
+use std::any::Any;
+
+use super::types::{EdgeType, NodeType, TypedId};
+use super::{Direction, Edge, Graph, GraphObject, Id, Node};
+
+/// An id with its type erased, as bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ErasedId(pub Vec<u8>);
+
+impl From<u64> for ErasedId {
+    fn from(id: u64) -> Self {
+        id.erase()
+    }
+}
+
+/// An id which can be converted to and from bytes.
+pub trait ErasableId: Sized {
+    /// Convert the id to bytes.
+    fn erase(&self) -> ErasedId;
+
+    /// Convert bytes back to an id. Returns `None` if the bytes don't
+    /// represent an id of this type.
+    fn unerase(id: &ErasedId) -> Option<Self>;
+}
+
+macro_rules! erasable_int {
+    ($($t:ty),*) => {
+        $(
+            impl ErasableId for $t {
+                fn erase(&self) -> ErasedId {
+                    ErasedId(self.to_be_bytes().to_vec())
+                }
+
+                fn unerase(id: &ErasedId) -> Option<Self> {
+                    let mut bytes = [0; std::mem::size_of::<$t>()];
+                    if id.0.len() != bytes.len() {
+                        return None;
+                    }
+                    bytes.copy_from_slice(&id.0);
+
+                    Some(<$t>::from_be_bytes(bytes))
+                }
+            }
+        )*
+    };
+}
+
+erasable_int!(u16, u32, u64, u128);
+
+impl ErasableId for [u8; 32] {
+    fn erase(&self) -> ErasedId {
+        ErasedId(self.to_vec())
+    }
+
+    fn unerase(id: &ErasedId) -> Option<Self> {
+        let mut bytes = [0; 32];
+        if id.0.len() != bytes.len() {
+            return None;
+        }
+        bytes.copy_from_slice(&id.0);
+
+        Some(bytes)
+    }
+}
+
+impl<Tag, Raw: ErasableId> ErasableId for TypedId<Tag, Raw> {
+    fn erase(&self) -> ErasedId {
+        self.raw().erase()
+    }
+
+    fn unerase(id: &ErasedId) -> Option<Self> {
+        Raw::unerase(id).map(TypedId::new)
+    }
+}
+
+/// An edge with its types erased.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynEdge {
+    /// The edge id.
+    pub id: ErasedId,
+    /// The source node.
+    pub from: ErasedId,
+    /// The target node.
+    pub to: ErasedId,
+    /// The type of the edge.
+    pub edge_type: EdgeType,
+    /// The weight of the edge.
+    pub weight: f64,
+}
+
+/// An object-safe read-only graph.
+pub trait DynGraph {
+    /// The number of nodes.
+    fn node_count(&self) -> usize;
+
+    /// The ids of all nodes.
+    fn node_ids(&self) -> Vec<ErasedId>;
+
+    /// Whether the node exists.
+    fn contains_node(&self, id: &ErasedId) -> bool;
+
+    /// The type of a node.
+    fn node_type(&self, id: &ErasedId) -> Option<&NodeType>;
+
+    /// The data of a node, to be downcast to the concrete data type.
+    fn node_data(&self, id: &ErasedId) -> Option<&dyn Any>;
+
+    /// An edge.
+    fn edge(&self, id: &ErasedId) -> Option<DynEdge>;
+
+    /// The data of an edge, to be downcast to the concrete data type.
+    fn edge_data(&self, id: &ErasedId) -> Option<&dyn Any>;
+
+    /// The neighbors of a node.
+    fn neighbors(&self, id: &ErasedId) -> Vec<ErasedId>;
+
+    /// The edges of a node, in the given direction.
+    fn edges_directed(&self, id: &ErasedId, dir: Direction) -> Vec<DynEdge>;
+}
+
+fn dyn_edge<G>(edge: &G::Edge) -> DynEdge
+where
+    G: Graph,
+    G::Weight: Into<f64>,
+    Id<G::Node>: ErasableId,
+    Id<G::Edge>: ErasableId,
+{
+    DynEdge {
+        id: edge.id().erase(),
+        from: edge.source().erase(),
+        to: edge.target().erase(),
+        edge_type: edge.edge_type().clone(),
+        weight: edge.weight().into(),
+    }
+}
+
+impl<G> DynGraph for G
+where
+    G: Graph,
+    G::NodeData: Any,
+    G::EdgeData: Any,
+    G::Weight: Into<f64>,
+    Id<G::Node>: ErasableId,
+    Id<G::Edge>: ErasableId,
+{
+    fn node_count(&self) -> usize {
+        self.nodes().count()
+    }
+
+    fn node_ids(&self) -> Vec<ErasedId> {
+        self.nodes().map(|n| n.id().erase()).collect()
+    }
+
+    fn contains_node(&self, id: &ErasedId) -> bool {
+        Id::<G::Node>::unerase(id).is_some_and(|id| self.get_node(&id).is_some())
+    }
+
+    fn node_type(&self, id: &ErasedId) -> Option<&NodeType> {
+        let id = Id::<G::Node>::unerase(id)?;
+        self.get_node(&id).map(|n| n.node_type())
+    }
+
+    fn node_data(&self, id: &ErasedId) -> Option<&dyn Any> {
+        let id = Id::<G::Node>::unerase(id)?;
+        self.get_node(&id).map(|n| n.data() as &dyn Any)
+    }
+
+    fn edge(&self, id: &ErasedId) -> Option<DynEdge> {
+        let id = Id::<G::Edge>::unerase(id)?;
+        self.get_edge(&id).map(dyn_edge::<G>)
+    }
+
+    fn edge_data(&self, id: &ErasedId) -> Option<&dyn Any> {
+        let id = Id::<G::Edge>::unerase(id)?;
+        self.get_edge(&id).map(|e| e.data() as &dyn Any)
+    }
+
+    fn neighbors(&self, id: &ErasedId) -> Vec<ErasedId> {
+        match Id::<G::Node>::unerase(id) {
+            Some(id) => Graph::neighbors(self, &id)
+                .map(|n| n.id().erase())
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn edges_directed(&self, id: &ErasedId, dir: Direction) -> Vec<DynEdge> {
+        let id = match Id::<G::Node>::unerase(id) {
+            Some(id) => id,
+            None => return Vec::new(),
+        };
+        Graph::edges_directed(self, &id, dir)
+            .into_iter()
+            .filter_map(|e| self.get_edge(e.id))
+            .map(dyn_edge::<G>)
+            .collect()
+    }
+}
@@ -0,0 +1,306 @@
+//! Epochs and the decay of graph data between them.
+//!
+//! Osrank should reflect recent activity rather than all-time totals. At the
+//! end of every epoch, contribution counts and node ranks are scaled down by
+//! a decay factor, so that data which isn't refreshed by new checkpoints
+//! gradually loses its weight.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::fmt;
+use num_traits::Float;
+
+use super::types::{
+    ContributionLedger, EdgeData, EdgeType, GraphDiff, NodeData, NodeRank, NodeType,
+};
+use super::{Edge, GraphObject, GraphWriter, Id};
+
+/// An epoch number.
+pub type EpochId = u64;
+
+/// Decay factors applied when advancing to the next epoch. A factor of `1.0`
+/// leaves the data untouched, while a factor of `0.0` resets it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpochDecay {
+    /// Factor applied to contribution counts, on nodes and edges.
+    pub contributions: f64,
+    /// Factor applied to node ranks.
+    pub ranks: f64,
+}
+
+impl EpochDecay {
+    /// No decay at all.
+    pub fn none() -> Self {
+        EpochDecay {
+            contributions: 1.0,
+            ranks: 1.0,
+        }
+    }
+
+    /// Exponential decay where all data loses half its weight after
+    /// `half_life` epochs, which must be positive.
+    pub fn exponential(half_life: f64) -> Result<Self, DecayError> {
+        if half_life.is_nan() || half_life <= 0.0 {
+            return Err(DecayError::InvalidHalfLife(half_life));
+        }
+        let factor = 0.5f64.powf(1.0 / half_life);
+
+        Ok(EpochDecay {
+            contributions: factor,
+            ranks: factor,
+        })
+    }
+}
+
+/// An invalid `EpochDecay`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DecayError {
+    /// The half-life isn't a positive number.
+    InvalidHalfLife(f64),
+}
+
+impl fmt::Display for DecayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecayError::InvalidHalfLife(h) => write!(f, "invalid half-life {}", h),
+        }
+    }
+}
+
+impl core::error::Error for DecayError {}
+
+/// The contributions made along an edge, by epoch.
+///
+/// Unlike the decayed count of an `EdgeType`, the history keeps the raw
+/// contributions of every epoch, so that they can be weighted by recency.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EdgeHistory {
+    /// The contributions of every epoch, sorted by epoch, without zeros.
+    entries: Vec<(EpochId, u32)>,
+}
+
+impl EdgeHistory {
+    /// An empty history.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record contributions made during the given epoch, saturating at
+    /// `u32::MAX`.
+    pub fn append(&mut self, epoch: EpochId, contributions: u32) {
+        if contributions == 0 {
+            return;
+        }
+        match self.entries.binary_search_by_key(&epoch, |(e, _)| *e) {
+            Ok(i) => self.entries[i].1 = self.entries[i].1.saturating_add(contributions),
+            Err(i) => self.entries.insert(i, (epoch, contributions)),
+        }
+    }
+
+    /// The contributions of every epoch, sorted by epoch.
+    pub fn entries(&self) -> &[(EpochId, u32)] {
+        &self.entries
+    }
+
+    /// Whether no contributions were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The contributions made from epoch `from` until epoch `to`, both
+    /// included, saturating at `u32::MAX`.
+    pub fn window(&self, from: EpochId, to: EpochId) -> u32 {
+        self.entries
+            .iter()
+            .filter(|(e, _)| (from..=to).contains(e))
+            .fold(0, |total, (_, c)| total.saturating_add(*c))
+    }
+
+    /// The contributions made up to epoch `current`, where contributions
+    /// made `k` epochs earlier are scaled by `decay.contributions` to the
+    /// power `k`.
+    pub fn decayed(&self, current: EpochId, decay: &EpochDecay) -> f64 {
+        self.entries
+            .iter()
+            .filter(|(e, _)| *e <= current)
+            .map(|(e, c)| f64::from(*c) * decay.contributions.powf((current - e) as f64))
+            .sum()
+    }
+
+    /// Forget the contributions made before the given epoch.
+    pub fn truncate_before(&mut self, epoch: EpochId) {
+        self.entries.retain(|(e, _)| *e >= epoch);
+    }
+}
+
+/// Data that decays between epochs.
+pub trait Decay {
+    /// Apply the decay, returning whether the data changed.
+    fn decay(&mut self, decay: &EpochDecay) -> bool;
+}
+
+/// Node data whose contributions are those of the node's contribution
+/// edges.
+///
+/// Decaying the edges and their nodes separately would round them down
+/// separately, so that totals no longer add up: `decay_graph` recounts the
+/// contributions of nodes from their decayed edges instead.
+pub trait Recount<NodeId> {
+    /// Replace the contributions with the given ones, by counterpart,
+    /// returning whether the data changed.
+    fn recount(&mut self, contributions: &ContributionLedger<NodeId>) -> bool;
+}
+
+impl<NodeId: Ord> Recount<NodeId> for NodeType {
+    fn recount(&mut self, contributions: &ContributionLedger<NodeId>) -> bool {
+        let before = self.total_contributions();
+        self.set_contributions(contributions.total());
+
+        self.total_contributions() != before
+    }
+}
+
+/// The breakdown of contributions is replaced too.
+impl<W> Recount<u64> for NodeData<W> {
+    fn recount(&mut self, contributions: &ContributionLedger<u64>) -> bool {
+        let node_type = self.node_type.recount(contributions);
+        let breakdown = self.contributions != *contributions;
+        self.contributions = contributions.clone();
+
+        node_type || breakdown
+    }
+}
+
+/// The contributions credited to the source of an edge of the given type:
+/// those of the `project -> user` and `user -> project` contribution edges,
+/// as added by checkpoints and importers.
+pub fn credited_contributions(edge_type: &EdgeType) -> Option<u32> {
+    match edge_type {
+        EdgeType::ProjectToUserContribution(c) | EdgeType::UserToProjectContribution(c) => Some(*c),
+        _ => None,
+    }
+}
+
+/// Scale a contribution count, rounding down so that stale counts eventually
+/// reach zero.
+fn decay_contributions(c: u32, factor: f64) -> u32 {
+    (f64::from(c) * factor).floor() as u32
+}
+
+impl Decay for NodeType {
+    fn decay(&mut self, decay: &EpochDecay) -> bool {
+        let before = self.total_contributions();
+        self.set_contributions(decay_contributions(before, decay.contributions));
+
+        self.total_contributions() != before
+    }
+}
+
+impl Decay for EdgeType {
+    fn decay(&mut self, decay: &EpochDecay) -> bool {
+        match self {
+            EdgeType::ProjectToUserContribution(c)
+            | EdgeType::UserToProjectContribution(c)
+            | EdgeType::ProjectToUserMembership(c)
+            | EdgeType::UserToProjectMembership(c)
+            | EdgeType::Custom {
+                contributions: c, ..
+            } => {
+                let before = *c;
+                *c = decay_contributions(before, decay.contributions);
+
+                *c != before
+            }
+            // Donations are decided once, they don't go stale.
+            EdgeType::Dependency
+            | EdgeType::OrgToProjectOwnership
+            | EdgeType::ProjectToOrgOwnership
+            | EdgeType::Donation(_) => false,
+        }
+    }
+}
+
+impl<W: Float> Decay for NodeRank<W> {
+    fn decay(&mut self, decay: &EpochDecay) -> bool {
+        let before = self.rank;
+        NodeRank::decay(self, W::from(decay.ranks).unwrap_or_else(W::one));
+
+        self.rank != before
+    }
+}
+
+impl<W: Float> Decay for NodeData<W> {
+    fn decay(&mut self, decay: &EpochDecay) -> bool {
+        // Don't short-circuit: all fields must decay.
+        let node_type = self.node_type.decay(decay);
+        let rank = Decay::decay(&mut self.rank, decay);
+        let before = self.contributions.clone();
+        self.contributions
+            .scale(|c| decay_contributions(c, decay.contributions));
+
+        node_type || rank || self.contributions != before
+    }
+}
+
+impl<W> Decay for EdgeData<W> {
+    fn decay(&mut self, decay: &EpochDecay) -> bool {
+        self.edge_type.decay(decay)
+    }
+}
+
+/// Apply the decay to every node and edge of the graph, returning a
+/// `GraphDiff::NodeUpdated` for every node whose data changed, followed by a
+/// `GraphDiff::EdgeUpdated` for every edge whose data changed.
+///
+/// The contributions of nodes with outgoing contribution edges are then
+/// recounted from these edges, so that they keep adding up. Other nodes
+/// decay their contributions on their own.
+pub fn decay_graph<'a, G>(graph: &'a mut G, decay: &EpochDecay) -> Vec<GraphDiff<'a, G>>
+where
+    G: GraphWriter,
+    G::NodeData: Decay + Recount<Id<G::Node>>,
+    G::EdgeData: Decay,
+    Id<G::Node>: Clone + Ord,
+    Id<G::Edge>: Clone,
+{
+    let mut updated_edges = Vec::new();
+    let mut recounts: BTreeMap<Id<G::Node>, ContributionLedger<Id<G::Node>>> = BTreeMap::new();
+    for edge in graph.edges_mut() {
+        if edge.data_mut().decay(decay) {
+            updated_edges.push(edge.id().clone());
+        }
+        if let Some(c) = credited_contributions(edge.edge_type()) {
+            recounts
+                .entry(edge.source().clone())
+                .or_default()
+                .add(edge.target().clone(), c);
+        }
+    }
+
+    let mut updated = Vec::new();
+    for node in graph.nodes_mut() {
+        let mut changed = node.data_mut().decay(decay);
+        if let Some(contributions) = recounts.get(node.id()) {
+            changed |= node.data_mut().recount(contributions);
+        }
+        if changed {
+            updated.push(node.id().clone());
+        }
+    }
+
+    let graph: &'a G = graph;
+    let nodes = updated
+        .iter()
+        .filter_map(|id| graph.get_node(id))
+        .map(|n| GraphDiff::NodeUpdated(n.id()));
+    let edges = updated_edges
+        .iter()
+        .filter_map(|id| graph.get_edge(id))
+        .map(|e| GraphDiff::EdgeUpdated {
+            id: e.id(),
+            source: e.source(),
+            target: e.target(),
+        });
+    nodes.chain(edges).collect()
+}
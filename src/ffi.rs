@@ -0,0 +1,239 @@
+//! A C ABI over the reference graph implementation, for services embedding
+//! osrank outside of Rust. See `include/oscoin_graph.h` for the matching C
+//! declarations.
+//!
+//! Graphs are opaque `OscGraph` pointers created with `osc_graph_new` and
+//! released with `osc_graph_free`. Node and edge types are passed as their
+//! numeric codes, see `NodeType::code` and `EdgeType::code`. Fallible
+//! functions return `OSC_OK` or a negative status code.
+//!
+//! The library is built for linking with eg.
+//! `cargo rustc --release --features ffi --crate-type cdylib`.
+
+use std::convert::TryFrom;
+use std::ptr;
+
+use super::algorithms::prune::{Prune, PruneContext};
+use super::mem::EdgeListGraph;
+use super::types::{
+    ContributionLedger, DampingFactors, EdgeData, EdgeType, HyperParameters, NodeData, NodeRank,
+    NodeType,
+};
+use super::{Graph, GraphAlgorithm, GraphAnnotator, GraphDataWriter, GraphWriter};
+
+/// Success.
+pub const OSC_OK: i32 = 0;
+/// A pointer argument is null.
+pub const OSC_NULL_POINTER: i32 = -1;
+/// A node or edge type code is unknown.
+pub const OSC_INVALID_TYPE: i32 = -2;
+/// A node doesn't exist.
+pub const OSC_NOT_FOUND: i32 = -3;
+/// The rank algorithm failed.
+pub const OSC_RANK_FAILED: i32 = -4;
+
+/// An opaque graph handle.
+pub struct OscGraph {
+    graph: EdgeListGraph<u64, f64>,
+}
+
+/// Ignores annotations: ranks are read from the algorithm's context instead.
+struct Discard;
+
+impl GraphAnnotator for Discard {
+    type Annotation = (u64, NodeRank<f64>);
+    type Error = std::convert::Infallible;
+
+    fn annotate_graph(&mut self, _note: Self::Annotation) {}
+}
+
+/// Create an empty graph. It must be released with `osc_graph_free`.
+#[no_mangle]
+pub extern "C" fn osc_graph_new() -> *mut OscGraph {
+    Box::into_raw(Box::new(OscGraph {
+        graph: EdgeListGraph::new(),
+    }))
+}
+
+/// Release a graph. Does nothing if `graph` is null.
+///
+/// # Safety
+///
+/// `graph` must be null or returned by `osc_graph_new`, and not released
+/// already.
+#[no_mangle]
+pub unsafe extern "C" fn osc_graph_free(graph: *mut OscGraph) {
+    if !graph.is_null() {
+        drop(Box::from_raw(graph));
+    }
+}
+
+/// The number of nodes of a graph, or zero if `graph` is null.
+///
+/// # Safety
+///
+/// `graph` must be null or a live graph.
+#[no_mangle]
+pub unsafe extern "C" fn osc_graph_node_count(graph: *const OscGraph) -> usize {
+    graph.as_ref().map_or(0, |g| g.graph.node_count())
+}
+
+/// The number of edges of a graph, or zero if `graph` is null.
+///
+/// # Safety
+///
+/// `graph` must be null or a live graph.
+#[no_mangle]
+pub unsafe extern "C" fn osc_graph_edge_count(graph: *const OscGraph) -> usize {
+    graph.as_ref().map_or(0, |g| g.graph.edge_count())
+}
+
+/// Add a node of the given type code and contributions, replacing any node
+/// with the same id. Its rank is zero until `osc_graph_rank` is called.
+///
+/// # Safety
+///
+/// `graph` must be null or a live graph.
+#[no_mangle]
+pub unsafe extern "C" fn osc_graph_add_node(
+    graph: *mut OscGraph,
+    id: u64,
+    node_type: u8,
+    contributions: u32,
+) -> i32 {
+    let graph = match graph.as_mut() {
+        Some(g) => &mut g.graph,
+        None => return OSC_NULL_POINTER,
+    };
+    let node_type = match NodeType::try_from((node_type, contributions)) {
+        Ok(t) => t,
+        Err(_) => return OSC_INVALID_TYPE,
+    };
+    graph.add_node(
+        id,
+        NodeData {
+            node_type,
+            rank: NodeRank { rank: 0.0 },
+            contributions: ContributionLedger::new(),
+            timestamps: Default::default(),
+        },
+    );
+    OSC_OK
+}
+
+/// Add an edge of the given type code, value and weight between two
+/// existing nodes, replacing any edge with the same id. The value is the
+/// number of contributions, or the amount of a donation.
+///
+/// # Safety
+///
+/// `graph` must be null or a live graph.
+#[no_mangle]
+pub unsafe extern "C" fn osc_graph_add_edge(
+    graph: *mut OscGraph,
+    id: u64,
+    from: u64,
+    to: u64,
+    edge_type: u8,
+    value: u32,
+    weight: f64,
+) -> i32 {
+    let graph = match graph.as_mut() {
+        Some(g) => &mut g.graph,
+        None => return OSC_NULL_POINTER,
+    };
+    let edge_type = match EdgeType::try_from((edge_type, value)) {
+        Ok(t) => t,
+        Err(_) => return OSC_INVALID_TYPE,
+    };
+    if graph.get_node(&from).is_none() || graph.get_node(&to).is_none() {
+        return OSC_NOT_FOUND;
+    }
+    graph.add_edge(
+        id,
+        &from,
+        &to,
+        EdgeData {
+            edge_type,
+            weight,
+            history: Default::default(),
+            timestamps: Default::default(),
+        },
+    );
+    OSC_OK
+}
+
+/// Rank every node of the graph with the reference algorithm, the
+/// approximate osrank of the pruning phase, over walks of up to `steps`
+/// steps. Ranks are stored on the nodes, and sum to `1.0`.
+///
+/// # Safety
+///
+/// `graph` must be null or a live graph.
+#[no_mangle]
+pub unsafe extern "C" fn osc_graph_rank(
+    graph: *mut OscGraph,
+    project_damping: f64,
+    account_damping: f64,
+    steps: u32,
+) -> i32 {
+    let graph = match graph.as_mut() {
+        Some(g) => &mut g.graph,
+        None => return OSC_NULL_POINTER,
+    };
+    let mut prune = Prune::new(HyperParameters {
+        pruning_threshold: 0.0,
+        damping_factors: DampingFactors {
+            project: project_damping,
+            account: account_damping,
+        },
+        r_value: 0,
+        edge_weights: Default::default(),
+        max_edge_weight_share: None,
+        max_user_rank: None,
+        rank_decay: None,
+    });
+    prune.steps = steps as usize;
+
+    let mut context = PruneContext::default();
+    if prune
+        .execute(&mut context, &*graph, &mut Discard, 0)
+        .is_err()
+    {
+        return OSC_RANK_FAILED;
+    }
+    for (id, rank) in context.ranks {
+        if let Some(data) = graph.node_data_mut(&id) {
+            data.rank.rank = rank;
+        }
+    }
+    OSC_OK
+}
+
+/// Write the rank of a node to `rank`.
+///
+/// # Safety
+///
+/// `graph` must be null or a live graph, and `rank` null or valid for
+/// writes.
+#[no_mangle]
+pub unsafe extern "C" fn osc_graph_node_rank(
+    graph: *const OscGraph,
+    id: u64,
+    rank: *mut f64,
+) -> i32 {
+    let graph = match graph.as_ref() {
+        Some(g) => &g.graph,
+        None => return OSC_NULL_POINTER,
+    };
+    if rank.is_null() {
+        return OSC_NULL_POINTER;
+    }
+    match graph.node_data(&id) {
+        Some(data) => {
+            ptr::write(rank, data.rank.rank);
+            OSC_OK
+        }
+        None => OSC_NOT_FOUND,
+    }
+}
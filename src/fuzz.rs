@@ -0,0 +1,205 @@
+//! Entry points for coverage-guided fuzzing of the untrusted inputs of the
+//! graph: checkpoints from the registry, and snapshots.
+//!
+//! Each function takes the raw bytes of the fuzzer, and panics if an
+//! invariant is broken. The bytes are decoded into structured inputs with
+//! `arbitrary`, with small node ids so that checkpoints hit existing nodes
+//! and collide with each other. The targets of `fuzz/` call them:
+//!
+//! ```text
+//! cargo fuzz run apply_checkpoint
+//! cargo fuzz run parse_snapshot
+//! ```
+
+use arbitrary::{Arbitrary, Unstructured};
+
+use super::compare::eq_by_ids;
+use super::ledger::{
+    Checkpoint, CheckpointApplier, CheckpointError, Contrib, Dep, LedgerEdgeData, LedgerNodeData,
+};
+use super::mem::EdgeListGraph;
+use super::snapshot::compact::{self, CompactGraph, Encoding};
+use super::types::{EdgeData, EdgeType, NodeData, NodeType, SipIdGenerator};
+use super::{
+    Direction, Graph, GraphAPI, GraphObject, GraphWriter, Layer, LayerInfo, LayeredGraphs,
+};
+
+/// A node of a generated graph.
+#[derive(Debug, Clone, Arbitrary)]
+pub struct FuzzNode {
+    pub id: u8,
+    /// Whether the node is a user, or a project.
+    pub user: bool,
+    pub contributions: u8,
+}
+
+impl FuzzNode {
+    fn data(&self) -> NodeData<f64> {
+        let contributions = u32::from(self.contributions);
+        let node_type = if self.user {
+            NodeType::User {
+                contributions_to_all_projects: contributions,
+            }
+        } else {
+            NodeType::Project {
+                contributions_from_all_users: contributions,
+            }
+        };
+        NodeData::from_node_type(node_type)
+    }
+}
+
+/// A graph, and checkpoints to apply to it in order.
+#[derive(Debug, Clone, Arbitrary)]
+pub struct CheckpointInput {
+    pub nodes: Vec<FuzzNode>,
+    pub checkpoints: Vec<Checkpoint<u8>>,
+}
+
+/// A compact snapshot, written from a generated graph then corrupted.
+#[derive(Debug, Clone, Arbitrary)]
+pub struct SnapshotInput {
+    pub nodes: Vec<FuzzNode>,
+    /// Dependencies, between nodes by id. Edges to missing nodes are
+    /// skipped.
+    pub edges: Vec<(u8, u8)>,
+    pub encoding: Encoding,
+    /// Bytes overwritten in the snapshot, at offsets modulo its length.
+    pub corruptions: Vec<(u16, u8)>,
+}
+
+impl SnapshotInput {
+    /// The bytes of the snapshot.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut graph = graph(&self.nodes);
+        for (i, (from, to)) in self.edges.iter().enumerate() {
+            let (from, to) = (u64::from(*from), u64::from(*to));
+            if graph.get_node(&from).is_some() && graph.get_node(&to).is_some() {
+                let data = EdgeData::from_edge_type(EdgeType::Dependency);
+                graph.add_edge(i as u64, &from, &to, data);
+            }
+        }
+
+        let mut bytes = Vec::new();
+        compact::write_with(&graph, &mut bytes, self.encoding)
+            .expect("dependencies can be written");
+        let len = bytes.len();
+        for (offset, byte) in &self.corruptions {
+            bytes[usize::from(*offset) % len] = *byte;
+        }
+        bytes
+    }
+}
+
+fn graph(nodes: &[FuzzNode]) -> EdgeListGraph<u64, f64> {
+    let mut graph = EdgeListGraph::new();
+    for node in nodes {
+        graph.add_node(u64::from(node.id), node.data());
+    }
+    graph
+}
+
+fn widen(checkpoint: &Checkpoint<u8>) -> Checkpoint<u64> {
+    Checkpoint {
+        project: u64::from(checkpoint.project),
+        version: checkpoint.version.clone(),
+        hash: checkpoint.hash,
+        deps: checkpoint
+            .deps
+            .iter()
+            .map(|d| Dep {
+                node_id: u64::from(d.node_id),
+                is_added: d.is_added,
+            })
+            .collect(),
+        contributions: checkpoint
+            .contributions
+            .iter()
+            .map(|c| Contrib {
+                node_id: u64::from(c.node_id),
+                contributions: c.contributions,
+            })
+            .collect(),
+    }
+}
+
+/// Apply checkpoints to a graph. A rejected checkpoint must leave the graph
+/// untouched, and an applied one must create its project and contributors.
+pub fn fuzz_apply_checkpoint(bytes: &[u8]) {
+    let input = match CheckpointInput::arbitrary_take_rest(Unstructured::new(bytes)) {
+        Ok(input) => input,
+        Err(_) => return,
+    };
+    let layer = Layer::from_static("fuzz");
+    let ids = SipIdGenerator::default();
+    let mut api = LayeredGraphs::new();
+    api.add_layer(
+        layer.clone(),
+        LayerInfo::new(0, "fuzz"),
+        graph(&input.nodes),
+    );
+
+    for checkpoint in input.checkpoints.iter().map(widen) {
+        let before = api.graph(&layer).expect("the layer exists").clone();
+        let result = api.apply_checkpoint(&layer, &checkpoint, &ids);
+        let after = api.graph(&layer).expect("the layer exists");
+
+        match result {
+            Ok(()) => {
+                assert!(after.get_node(&checkpoint.project).is_some());
+                for contrib in &checkpoint.contributions {
+                    assert!(after.get_node(&contrib.node_id).is_some());
+                }
+            }
+            Err(CheckpointError::Invalid(violations)) => {
+                assert!(!violations.is_empty());
+                assert!(
+                    eq_by_ids(&before, after),
+                    "rejected checkpoint modified the graph"
+                );
+            }
+            Err(CheckpointError::Layer(err)) => panic!("layer error: {}", err),
+            Err(CheckpointError::Limit(err)) => {
+                panic!("unlimited graph refused checkpoint: {}", err)
+            }
+        }
+    }
+}
+
+/// Parse the bytes as a compact snapshot, then a snapshot generated from
+/// the bytes. Neither parsing nor reading must panic, and every edge listed
+/// by a valid snapshot must be readable. Snapshots that aren't corrupted
+/// must be valid.
+pub fn fuzz_parse_snapshot(bytes: &[u8]) {
+    read_snapshot(bytes);
+
+    let input = match SnapshotInput::arbitrary_take_rest(Unstructured::new(bytes)) {
+        Ok(input) => input,
+        Err(_) => return,
+    };
+    let valid = read_snapshot(&input.to_bytes());
+    if input.corruptions.is_empty() {
+        assert!(valid, "valid snapshot was rejected");
+    }
+}
+
+/// Read every node and edge of a snapshot, returning whether it's valid.
+fn read_snapshot(bytes: &[u8]) -> bool {
+    let graph = match CompactGraph::from_bytes(bytes) {
+        Ok(graph) => graph,
+        Err(_) => return false,
+    };
+    let valid = graph.validate().is_ok();
+    for node in graph.nodes() {
+        for dir in [Direction::Outgoing, Direction::Incoming] {
+            for e in graph.edges_directed(node.id(), dir) {
+                let found = graph.get_edge(e.id).is_some();
+                assert!(found || !valid, "dangling edge {}", e.id);
+            }
+        }
+    }
+    if valid {
+        assert_eq!(graph.nodes().count(), graph.node_count());
+    }
+    valid
+}
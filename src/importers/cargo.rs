@@ -0,0 +1,219 @@
+//! Importing Rust dependency graphs, from the output of `cargo metadata` or
+//! from a crates.io database dump.
+//!
+//! Every crate becomes a `Project` node, named after the crate, and every
+//! dependency a `Dependency` edge from the dependent crate. All versions of a
+//! crate are merged into a single node.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+
+use serde_json::Value;
+
+use super::{import_edge, import_node, ImportError, ImportMapping, ImportSummary};
+use crate::io::csv::split;
+use crate::types::{EdgeType, NodeType};
+use crate::{GraphWriter, Id};
+
+/// Which kinds of dependencies to import. Normal dependencies are always
+/// imported.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CargoOptions {
+    /// Import development dependencies.
+    pub dev_dependencies: bool,
+    /// Import build dependencies.
+    pub build_dependencies: bool,
+}
+
+impl CargoOptions {
+    fn accepts(&self, kind: DependencyKind) -> bool {
+        match kind {
+            DependencyKind::Normal => true,
+            DependencyKind::Build => self.build_dependencies,
+            DependencyKind::Dev => self.dev_dependencies,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DependencyKind {
+    Normal,
+    Build,
+    Dev,
+}
+
+/// Import the JSON output of `cargo metadata --format-version 1`.
+/// Dependencies which aren't part of the listed packages, eg. disabled
+/// optional dependencies, are skipped.
+pub fn import_metadata<G, M, R>(
+    graph: &mut G,
+    mapping: &mut M,
+    reader: R,
+    options: &CargoOptions,
+) -> Result<ImportSummary, ImportError>
+where
+    G: GraphWriter,
+    M: ImportMapping<G>,
+    R: Read,
+    Id<G::Node>: Clone,
+{
+    let metadata: Value =
+        serde_json::from_reader(reader).map_err(|e| ImportError::Parse(e.to_string()))?;
+    let packages = metadata["packages"]
+        .as_array()
+        .ok_or_else(|| ImportError::Parse("missing `packages` array".to_owned()))?;
+
+    let mut summary = ImportSummary::default();
+    let mut ids = HashMap::new();
+
+    for package in packages {
+        let name = str_field(package, "name")?;
+        let id = import_node(graph, mapping, &mut summary, name, project());
+        ids.insert(name, id);
+    }
+
+    for package in packages {
+        let from = &ids[str_field(package, "name")?];
+        let deps = package["dependencies"].as_array().into_iter().flatten();
+
+        for dep in deps {
+            let kind = match dep["kind"].as_str() {
+                None | Some("normal") => DependencyKind::Normal,
+                Some("build") => DependencyKind::Build,
+                Some("dev") => DependencyKind::Dev,
+                Some(other) => {
+                    return Err(ImportError::Parse(format!(
+                        "unknown dependency kind `{}`",
+                        other
+                    )))
+                }
+            };
+            if !options.accepts(kind) {
+                continue;
+            }
+            if let Some(to) = ids.get(str_field(dep, "name")?) {
+                import_edge(graph, mapping, &mut summary, from, to, EdgeType::Dependency);
+            }
+        }
+    }
+    Ok(summary)
+}
+
+/// Import a crates.io database dump, from its `crates.csv`, `versions.csv`
+/// and `dependencies.csv` tables. Columns are looked up by their header.
+pub fn import_crates_io_dump<G, M, C, V, D>(
+    graph: &mut G,
+    mapping: &mut M,
+    crates: C,
+    versions: V,
+    dependencies: D,
+    options: &CargoOptions,
+) -> Result<ImportSummary, ImportError>
+where
+    G: GraphWriter,
+    M: ImportMapping<G>,
+    C: Read,
+    V: Read,
+    D: Read,
+    Id<G::Node>: Clone,
+{
+    let mut summary = ImportSummary::default();
+
+    // Crate id -> node id.
+    let mut nodes = HashMap::new();
+    read_table(crates, &["id", "name"], |row| {
+        let id = import_node(graph, mapping, &mut summary, &row[1], project());
+        nodes.insert(row[0].clone(), id);
+        Ok(())
+    })?;
+
+    // Version id -> crate id.
+    let mut version_crates = HashMap::new();
+    read_table(versions, &["id", "crate_id"], |row| {
+        version_crates.insert(row[0].clone(), row[1].clone());
+        Ok(())
+    })?;
+
+    read_table(dependencies, &["version_id", "crate_id", "kind"], |row| {
+        let kind = match row[2].as_str() {
+            "0" => DependencyKind::Normal,
+            "1" => DependencyKind::Build,
+            "2" => DependencyKind::Dev,
+            other => {
+                return Err(ImportError::Parse(format!(
+                    "unknown dependency kind `{}`",
+                    other
+                )))
+            }
+        };
+        if !options.accepts(kind) {
+            return Ok(());
+        }
+        let from = version_crates
+            .get(&row[0])
+            .and_then(|c| nodes.get(c))
+            .ok_or_else(|| ImportError::Parse(format!("unknown version `{}`", row[0])))?;
+        let to = nodes
+            .get(&row[1])
+            .ok_or_else(|| ImportError::Parse(format!("unknown crate `{}`", row[1])))?;
+
+        import_edge(graph, mapping, &mut summary, from, to, EdgeType::Dependency);
+        Ok(())
+    })?;
+
+    Ok(summary)
+}
+
+fn project() -> NodeType {
+    NodeType::Project {
+        contributions_from_all_users: 0,
+    }
+}
+
+fn str_field<'a>(value: &'a Value, field: &str) -> Result<&'a str, ImportError> {
+    value[field]
+        .as_str()
+        .ok_or_else(|| ImportError::Parse(format!("missing `{}` field", field)))
+}
+
+/// Call `f` with the given columns of every row of a CSV table with a header.
+/// Quoted fields may span several lines.
+fn read_table<R, F>(reader: R, columns: &[&str], mut f: F) -> Result<(), ImportError>
+where
+    R: Read,
+    F: FnMut(&[String]) -> Result<(), ImportError>,
+{
+    let mut lines = BufReader::new(reader).lines();
+    let header = match lines.next() {
+        Some(line) => split(&line?, ','),
+        None => return Ok(()),
+    };
+    let positions = columns
+        .iter()
+        .map(|col| {
+            header
+                .iter()
+                .position(|h| h == col)
+                .ok_or_else(|| ImportError::Parse(format!("missing `{}` column", col)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut record = String::new();
+    for line in lines {
+        record.push_str(&line?);
+        if record.matches('"').count() % 2 == 1 {
+            record.push('\n');
+            continue;
+        }
+        let fields = split(&std::mem::take(&mut record), ',');
+        if fields.len() == 1 && fields[0].trim().is_empty() {
+            continue;
+        }
+        let row = positions
+            .iter()
+            .map(|&i| fields.get(i).cloned().unwrap_or_default())
+            .collect::<Vec<_>>();
+        f(&row)?;
+    }
+    Ok(())
+}
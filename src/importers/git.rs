@@ -0,0 +1,210 @@
+//! Importing contributions, from git commit logs or from aggregated
+//! contribution lists, eg. extracted from a GitHub archive export.
+//!
+//! Every contributor becomes a `User` node and every repository a `Project`
+//! node. A user who contributed to a project is linked to it in both
+//! directions, with `UserToProjectContribution` and
+//! `ProjectToUserContribution` edges carrying the number of commits. Node
+//! contributions are the totals over their contribution edges.
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read};
+
+use super::{import_node, ImportError, ImportMapping, ImportSummary};
+use crate::epochs::credited_contributions;
+use crate::io::csv::split;
+use crate::stats::NodeKind;
+use crate::types::{EdgeType, NodeType};
+use crate::{Direction, Edge, GraphDataWriter, GraphObject, GraphWriter, Id};
+
+/// Commit counts, by project and contributor.
+pub type Contributions = BTreeMap<(String, String), u32>;
+
+/// Count the commits of a single project's log, with one contributor per
+/// line, eg. the output of `git log --format=%ae`. Blank lines are skipped.
+pub fn read_git_log<R: Read>(
+    project: &str,
+    reader: R,
+    contributions: &mut Contributions,
+) -> Result<(), ImportError> {
+    for line in BufReader::new(reader).lines() {
+        let line = line?;
+        let author = line.trim();
+        if author.is_empty() {
+            continue;
+        }
+        count(contributions, project, author, 1)?;
+    }
+    Ok(())
+}
+
+/// Read a contribution list, with one `project,contributor,commits` record
+/// per line and no header. Counts of repeated pairs are added up.
+pub fn read_contributions<R: Read>(
+    reader: R,
+    contributions: &mut Contributions,
+) -> Result<(), ImportError> {
+    for (i, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = split(&line, ',');
+        let (project, user, commits) = match fields.as_slice() {
+            [project, user, commits] => (project.trim(), user.trim(), commits.trim()),
+            _ => {
+                return Err(ImportError::Parse(format!(
+                    "line {}: expected 3 fields, found {}",
+                    i + 1,
+                    fields.len()
+                )))
+            }
+        };
+        let commits: u32 = commits.parse().map_err(|_| {
+            ImportError::Parse(format!(
+                "line {}: invalid commit count {:?}",
+                i + 1,
+                commits
+            ))
+        })?;
+        count(contributions, project, user, commits)?;
+    }
+    Ok(())
+}
+
+/// Add commits to the count of a project and contributor.
+fn count(
+    contributions: &mut Contributions,
+    project: &str,
+    user: &str,
+    commits: u32,
+) -> Result<(), ImportError> {
+    let count = contributions
+        .entry((project.to_owned(), user.to_owned()))
+        .or_insert(0);
+    *count = count.checked_add(commits).ok_or_else(|| {
+        ImportError::Parse(format!("too many commits by {:?} to {:?}", user, project))
+    })?;
+    Ok(())
+}
+
+/// Add the given contributions to `graph`.
+///
+/// The counts are those of the whole history of the projects: the
+/// contribution edges which already exist are updated with them, through
+/// `ImportMapping::set_edge_type`, so that importing the same data again
+/// changes nothing. The contributions of the nodes which already exist are
+/// then recounted from their edges, through
+/// `ImportMapping::set_contributions`, saturating on overflow.
+pub fn import_contributions<G, M>(
+    graph: &mut G,
+    mapping: &mut M,
+    contributions: &Contributions,
+) -> Result<ImportSummary, ImportError>
+where
+    G: GraphWriter + GraphDataWriter,
+    M: ImportMapping<G>,
+    Id<G::Node>: Clone + PartialEq,
+    Id<G::Edge>: Clone,
+{
+    let mut summary = ImportSummary::default();
+    let mut totals: BTreeMap<(NodeKind, &str), u32> = BTreeMap::new();
+
+    for ((project, user), &commits) in contributions {
+        for key in [
+            (NodeKind::Project, project.as_str()),
+            (NodeKind::User, user),
+        ] {
+            let total = totals.entry(key).or_insert(0);
+            *total = total
+                .checked_add(commits)
+                .ok_or_else(|| ImportError::Parse(format!("too many commits for {:?}", key.1)))?;
+        }
+    }
+
+    let mut ids = BTreeMap::new();
+    let mut existing = Vec::new();
+    for (&(kind, name), &total) in totals.iter() {
+        let id = mapping.node_id(kind, name);
+
+        if graph.get_node(&id).is_some() {
+            existing.push(id.clone());
+        } else {
+            let node_type = match kind {
+                NodeKind::User => NodeType::User {
+                    contributions_to_all_projects: total,
+                },
+                // Only users and projects are counted.
+                _ => NodeType::Project {
+                    contributions_from_all_users: total,
+                },
+            };
+            import_node(graph, mapping, &mut summary, name, node_type);
+        }
+        ids.insert((kind, name), id);
+    }
+
+    for ((project, user), &commits) in contributions {
+        let p = &ids[&(NodeKind::Project, project.as_str())];
+        let u = &ids[&(NodeKind::User, user.as_str())];
+
+        let edge_type = EdgeType::UserToProjectContribution(commits);
+        upsert_edge(graph, mapping, &mut summary, u, p, edge_type);
+        let edge_type = EdgeType::ProjectToUserContribution(commits);
+        upsert_edge(graph, mapping, &mut summary, p, u, edge_type);
+    }
+
+    for id in existing {
+        let total = graph
+            .edges_directed(&id, Direction::Outgoing)
+            .into_iter()
+            .filter_map(|e| graph.get_edge(e.id))
+            .filter_map(|edge| credited_contributions(edge.edge_type()))
+            .fold(0u32, u32::saturating_add);
+        if let Some(data) = graph.node_data_mut(&id) {
+            mapping.set_contributions(data, total);
+        }
+    }
+    Ok(summary)
+}
+
+/// Add a contribution edge, or update the existing edge of the same type
+/// between the same nodes.
+fn upsert_edge<G, M>(
+    graph: &mut G,
+    mapping: &mut M,
+    summary: &mut ImportSummary,
+    from: &Id<G::Node>,
+    to: &Id<G::Node>,
+    edge_type: EdgeType,
+) where
+    G: GraphWriter + GraphDataWriter,
+    M: ImportMapping<G>,
+    Id<G::Node>: PartialEq,
+    Id<G::Edge>: Clone,
+{
+    let tag = edge_type.to_tag();
+    let existing = graph
+        .edges_directed(from, Direction::Outgoing)
+        .into_iter()
+        .filter(|e| e.to == to)
+        .filter_map(|e| graph.get_edge(e.id))
+        .find(|edge| edge.edge_type().to_tag() == tag)
+        .map(|edge| (edge.id().clone(), edge.edge_type() == &edge_type));
+
+    match existing {
+        Some((_, true)) => {}
+        Some((id, false)) => {
+            if let Some(data) = graph.edge_data_mut(&id) {
+                mapping.set_edge_type(data, edge_type);
+                summary.updated_edges += 1;
+            }
+        }
+        None => {
+            let id = mapping.edge_id(from, to, &edge_type);
+            let data = mapping.edge_data(edge_type);
+            graph.add_edge(id, from, to, data);
+            summary.edges += 1;
+        }
+    }
+}
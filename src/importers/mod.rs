@@ -0,0 +1,203 @@
+//! Building graphs from external data sources, eg. package registries or
+//! version control history.
+//!
+//! Importers identify nodes by name, eg. a crate name or a contributor
+//! email. An `ImportMapping` turns these names into graph ids and data, so
+//! that importers work with any `GraphWriter`.
+
+#[cfg(feature = "cargo-importer")]
+pub mod cargo;
+pub mod git;
+
+use std::fmt;
+use std::io;
+
+use num_traits::Zero;
+
+use super::epochs::EdgeHistory;
+use super::stats::NodeKind;
+use super::types::{
+    ContributionLedger, EdgeData, EdgeType, IdGenerator, NodeData, NodeRank, NodeType,
+    SipIdGenerator, Timestamps,
+};
+use super::{Data, Graph, GraphObject, GraphWriter, Id};
+
+/// How imported names and types map to graph ids and data.
+pub trait ImportMapping<G: Graph> {
+    /// The id of the node with the given name.
+    fn node_id(&mut self, kind: NodeKind, name: &str) -> Id<G::Node>;
+
+    /// The id of an edge of type `edge_type` between two nodes.
+    fn edge_id(
+        &mut self,
+        from: &Id<G::Node>,
+        to: &Id<G::Node>,
+        edge_type: &EdgeType,
+    ) -> Id<G::Edge>;
+
+    /// The data of a new node.
+    fn node_data(&mut self, name: &str, node_type: NodeType) -> Data<G::Node>;
+
+    /// The data of a new edge.
+    fn edge_data(&mut self, edge_type: EdgeType) -> Data<G::Edge>;
+
+    /// Set the total contributions on the data of an existing node. Does
+    /// nothing by default.
+    fn set_contributions(&mut self, _data: &mut Data<G::Node>, _contributions: u32) {}
+
+    /// Replace the type on the data of an existing edge, eg. to update its
+    /// contributions. Does nothing by default.
+    fn set_edge_type(&mut self, _data: &mut Data<G::Edge>, _edge_type: EdgeType) {}
+}
+
+/// An `ImportMapping` for graphs with `u64` ids and the standard data types,
+/// deriving ids with a `SipIdGenerator`. Edge weights are set to zero, to be
+/// assigned afterwards, eg. with `weights::assign_weights`.
+#[derive(Debug, Clone, Default)]
+pub struct SipMapping {
+    ids: SipIdGenerator,
+}
+
+impl SipMapping {
+    /// Create a mapping deriving ids with the given generator.
+    pub fn new(ids: SipIdGenerator) -> Self {
+        SipMapping { ids }
+    }
+}
+
+impl<G, W> ImportMapping<G> for SipMapping
+where
+    G: Graph<NodeData = NodeData<W>, EdgeData = EdgeData<W>>,
+    G::Node: GraphObject<Id = u64>,
+    G::Edge: GraphObject<Id = u64>,
+    W: Zero,
+{
+    fn node_id(&mut self, kind: NodeKind, name: &str) -> u64 {
+        let namespace = match kind {
+            NodeKind::User => "user",
+            NodeKind::Project => "project",
+            NodeKind::Org => "org",
+            NodeKind::Checkpoint => "checkpoint",
+        };
+        self.ids.name_id(namespace, name)
+    }
+
+    fn edge_id(&mut self, from: &u64, to: &u64, edge_type: &EdgeType) -> u64 {
+        self.ids.edge_id(from, to, edge_type, &[])
+    }
+
+    fn node_data(&mut self, _name: &str, node_type: NodeType) -> NodeData<W> {
+        NodeData {
+            node_type,
+            rank: NodeRank { rank: W::zero() },
+            contributions: ContributionLedger::new(),
+            timestamps: Timestamps::default(),
+        }
+    }
+
+    fn edge_data(&mut self, edge_type: EdgeType) -> EdgeData<W> {
+        EdgeData {
+            edge_type,
+            weight: W::zero(),
+            history: EdgeHistory::new(),
+            timestamps: Timestamps::default(),
+        }
+    }
+
+    fn set_contributions(&mut self, data: &mut NodeData<W>, contributions: u32) {
+        data.node_type.set_contributions(contributions);
+    }
+
+    fn set_edge_type(&mut self, data: &mut EdgeData<W>, edge_type: EdgeType) {
+        data.edge_type = edge_type;
+    }
+}
+
+/// What an import added to the graph.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// Number of nodes added.
+    pub nodes: usize,
+    /// Number of edges added.
+    pub edges: usize,
+    /// Number of existing edges updated.
+    pub updated_edges: usize,
+}
+
+/// Add a node unless it already exists, returning its id.
+fn import_node<G, M>(
+    graph: &mut G,
+    mapping: &mut M,
+    summary: &mut ImportSummary,
+    name: &str,
+    node_type: NodeType,
+) -> Id<G::Node>
+where
+    G: GraphWriter,
+    M: ImportMapping<G>,
+    Id<G::Node>: Clone,
+{
+    let id = mapping.node_id(NodeKind::from(&node_type), name);
+
+    if graph.get_node(&id).is_none() {
+        let data = mapping.node_data(name, node_type);
+        graph.add_node(id.clone(), data);
+        summary.nodes += 1;
+    }
+    id
+}
+
+/// Add an edge unless it already exists.
+#[cfg(feature = "cargo-importer")]
+fn import_edge<G, M>(
+    graph: &mut G,
+    mapping: &mut M,
+    summary: &mut ImportSummary,
+    from: &Id<G::Node>,
+    to: &Id<G::Node>,
+    edge_type: EdgeType,
+) where
+    G: GraphWriter,
+    M: ImportMapping<G>,
+{
+    let id = mapping.edge_id(from, to, &edge_type);
+
+    if graph.get_edge(&id).is_none() {
+        let data = mapping.edge_data(edge_type);
+        graph.add_edge(id, from, to, data);
+        summary.edges += 1;
+    }
+}
+
+/// An error while importing a graph.
+#[derive(Debug)]
+pub enum ImportError {
+    /// The source couldn't be read.
+    Io(io::Error),
+    /// The source is malformed.
+    Parse(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImportError::Io(err) => write!(f, "i/o error: {}", err),
+            ImportError::Parse(msg) => write!(f, "parse error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ImportError::Io(err) => Some(err),
+            ImportError::Parse(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for ImportError {
+    fn from(err: io::Error) -> Self {
+        ImportError::Io(err)
+    }
+}
@@ -0,0 +1,510 @@
+//! Node and edge lists as CSV files.
+//!
+//! Nodes are stored one per line, with an id, a type (`user`, `project`,
+//! `org` or `checkpoint`) and a number of contributions. Edges are stored
+//! one per line, with an id, source and target node ids, a type, a number of
+//! contributions, or the amount of donations, and a weight. Edge types are
+//! written in snake case, eg. `dependency` or `user_to_project_contribution`,
+//! and custom ones with a `custom:` prefix. The position of every column is
+//! configurable, and other columns are ignored.
+
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::str::FromStr;
+
+use crate::types::{EdgeType, EdgeTypeTag, NodeType};
+use crate::{Data, Direction, Edge, Graph, GraphObject, GraphWriter, Id, Node};
+
+/// The position of the node columns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeColumns {
+    /// The node id.
+    pub id: usize,
+    /// The node type.
+    pub node_type: usize,
+    /// The number of contributions.
+    pub contributions: usize,
+}
+
+impl Default for NodeColumns {
+    fn default() -> Self {
+        NodeColumns {
+            id: 0,
+            node_type: 1,
+            contributions: 2,
+        }
+    }
+}
+
+/// The position of the edge columns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdgeColumns {
+    /// The edge id.
+    pub id: usize,
+    /// The source node id.
+    pub from: usize,
+    /// The target node id.
+    pub to: usize,
+    /// The edge type.
+    pub edge_type: usize,
+    /// The number of contributions, or the amount of donations. Ignored for
+    /// dependencies and ownership.
+    pub contributions: usize,
+    /// The edge weight.
+    pub weight: usize,
+}
+
+impl Default for EdgeColumns {
+    fn default() -> Self {
+        EdgeColumns {
+            id: 0,
+            from: 1,
+            to: 2,
+            edge_type: 3,
+            contributions: 4,
+            weight: 5,
+        }
+    }
+}
+
+/// The layout of CSV files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvFormat {
+    /// The field delimiter.
+    pub delimiter: char,
+    /// Whether the first line is a header. It is skipped when reading, and
+    /// written when writing.
+    pub header: bool,
+    /// The node columns.
+    pub nodes: NodeColumns,
+    /// The edge columns.
+    pub edges: EdgeColumns,
+}
+
+impl Default for CsvFormat {
+    fn default() -> Self {
+        CsvFormat {
+            delimiter: ',',
+            header: true,
+            nodes: NodeColumns::default(),
+            edges: EdgeColumns::default(),
+        }
+    }
+}
+
+impl CsvFormat {
+    /// Check that the delimiter isn't a quote or a line break, and that no
+    /// two node columns, nor two edge columns, share a position.
+    pub fn validate(&self) -> Result<(), CsvError> {
+        if matches!(self.delimiter, '"' | '\n' | '\r') {
+            return Err(CsvError::InvalidDelimiter(self.delimiter));
+        }
+        let nodes = &self.nodes;
+        let edges = &self.edges;
+        let mut nodes = [nodes.id, nodes.node_type, nodes.contributions];
+        let mut edges = [
+            edges.id,
+            edges.from,
+            edges.to,
+            edges.edge_type,
+            edges.contributions,
+            edges.weight,
+        ];
+        for columns in [&mut nodes[..], &mut edges[..]] {
+            columns.sort_unstable();
+            if let Some(pair) = columns.windows(2).find(|pair| pair[0] == pair[1]) {
+                return Err(CsvError::DuplicateColumn(pair[0]));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A node, as read from a CSV file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeRecord<Id> {
+    /// The node id.
+    pub id: Id,
+    /// The node type, with its contributions.
+    pub node_type: NodeType,
+}
+
+/// An edge, as read from a CSV file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdgeRecord<NodeId, EdgeId> {
+    /// The edge id.
+    pub id: EdgeId,
+    /// The source node id.
+    pub from: NodeId,
+    /// The target node id.
+    pub to: NodeId,
+    /// The edge type, with its contributions.
+    pub edge_type: EdgeType,
+    /// The edge weight.
+    pub weight: f64,
+}
+
+/// An error while reading or writing CSV files.
+#[derive(Debug)]
+pub enum CsvError {
+    /// The underlying reader or writer failed.
+    Io(io::Error),
+    /// A line has fewer fields than required.
+    MissingColumn {
+        /// The line number, starting from 1.
+        line: usize,
+        /// The missing column.
+        column: usize,
+    },
+    /// A field couldn't be parsed.
+    InvalidValue {
+        /// The line number, starting from 1.
+        line: usize,
+        /// The column of the field.
+        column: usize,
+        /// The field.
+        value: String,
+    },
+    /// A quoted field isn't closed by the end of the file.
+    UnterminatedQuote {
+        /// The line number of the record, starting from 1.
+        line: usize,
+    },
+    /// Several fields of the format are at the same column.
+    DuplicateColumn(usize),
+    /// The delimiter of the format can't delimit fields.
+    InvalidDelimiter(char),
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CsvError::Io(err) => write!(f, "i/o error: {}", err),
+            CsvError::MissingColumn { line, column } => {
+                write!(f, "line {}: missing column {}", line, column)
+            }
+            CsvError::InvalidValue {
+                line,
+                column,
+                value,
+            } => write!(
+                f,
+                "line {}, column {}: invalid value {:?}",
+                line, column, value
+            ),
+            CsvError::UnterminatedQuote { line } => write!(f, "line {}: unterminated quote", line),
+            CsvError::DuplicateColumn(column) => {
+                write!(f, "several fields at column {}", column)
+            }
+            CsvError::InvalidDelimiter(c) => write!(f, "invalid delimiter {:?}", c),
+        }
+    }
+}
+
+impl std::error::Error for CsvError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CsvError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CsvError {
+    fn from(err: io::Error) -> Self {
+        CsvError::Io(err)
+    }
+}
+
+/// Read nodes into `graph`, converting every record to node data with
+/// `node_data`. Returns the number of nodes read.
+pub fn read_nodes<G, R, F>(
+    graph: &mut G,
+    reader: R,
+    format: &CsvFormat,
+    mut node_data: F,
+) -> Result<usize, CsvError>
+where
+    G: GraphWriter,
+    R: Read,
+    F: FnMut(&NodeRecord<Id<G::Node>>) -> Data<G::Node>,
+    Id<G::Node>: FromStr,
+{
+    format.validate()?;
+    let cols = &format.nodes;
+
+    read_records(reader, format, |line, fields| {
+        let kind = field(fields, line, cols.node_type)?;
+        let contributions = contributions(fields, line, cols.contributions)?;
+        let node_type = match kind {
+            "user" => NodeType::User {
+                contributions_to_all_projects: contributions,
+            },
+            "project" => NodeType::Project {
+                contributions_from_all_users: contributions,
+            },
+            "org" => NodeType::Org {
+                contributions_to_owned_projects: contributions,
+            },
+            "checkpoint" => NodeType::Checkpoint { contributions },
+            _ => return Err(invalid(line, cols.node_type, kind)),
+        };
+        let record = NodeRecord {
+            id: parse(fields, line, cols.id)?,
+            node_type,
+        };
+        let data = node_data(&record);
+
+        graph.add_node(record.id, data);
+        Ok(())
+    })
+}
+
+/// Read edges into `graph`, converting every record to edge data with
+/// `edge_data`. Nodes are expected to exist. Returns the number of edges read.
+pub fn read_edges<G, R, F>(
+    graph: &mut G,
+    reader: R,
+    format: &CsvFormat,
+    mut edge_data: F,
+) -> Result<usize, CsvError>
+where
+    G: GraphWriter,
+    R: Read,
+    F: FnMut(&EdgeRecord<Id<G::Node>, Id<G::Edge>>) -> Data<G::Edge>,
+    Id<G::Node>: FromStr,
+    Id<G::Edge>: FromStr,
+{
+    format.validate()?;
+    let cols = &format.edges;
+
+    read_records(reader, format, |line, fields| {
+        let kind = field(fields, line, cols.edge_type)?;
+        let tag: EdgeTypeTag = kind
+            .parse()
+            .map_err(|_| invalid(line, cols.edge_type, kind))?;
+        let value = if tag.has_value() {
+            contributions(fields, line, cols.contributions)?
+        } else {
+            0
+        };
+        let edge_type = EdgeType::from_tag(tag, value);
+        let record = EdgeRecord {
+            id: parse(fields, line, cols.id)?,
+            from: parse(fields, line, cols.from)?,
+            to: parse(fields, line, cols.to)?,
+            edge_type,
+            weight: parse(fields, line, cols.weight)?,
+        };
+        let data = edge_data(&record);
+
+        graph.add_edge(record.id, &record.from, &record.to, data);
+        Ok(())
+    })
+}
+
+/// Write all nodes of `graph`, in iteration order.
+pub fn write_nodes<G, W>(graph: &G, writer: W, format: &CsvFormat) -> Result<(), CsvError>
+where
+    G: Graph,
+    W: Write,
+    Id<G::Node>: fmt::Display,
+{
+    format.validate()?;
+    let cols = &format.nodes;
+    let mut out = RecordWriter::new(writer, format.delimiter);
+
+    if format.header {
+        out.write(&[
+            (cols.id, "id".to_owned()),
+            (cols.node_type, "type".to_owned()),
+            (cols.contributions, "contributions".to_owned()),
+        ])?;
+    }
+    for node in graph.nodes() {
+        let node_type = node.node_type();
+        out.write(&[
+            (cols.id, node.id().to_string()),
+            (cols.node_type, node_type.name().to_owned()),
+            (
+                cols.contributions,
+                node_type.total_contributions().to_string(),
+            ),
+        ])?;
+    }
+    out.flush()
+}
+
+/// Write all edges of `graph`, grouped by source node in iteration order.
+pub fn write_edges<G, W>(graph: &G, writer: W, format: &CsvFormat) -> Result<(), CsvError>
+where
+    G: Graph,
+    W: Write,
+    G::Weight: Into<f64>,
+    Id<G::Node>: fmt::Display,
+    Id<G::Edge>: fmt::Display,
+{
+    format.validate()?;
+    let cols = &format.edges;
+    let mut out = RecordWriter::new(writer, format.delimiter);
+
+    if format.header {
+        out.write(&[
+            (cols.id, "id".to_owned()),
+            (cols.from, "from".to_owned()),
+            (cols.to, "to".to_owned()),
+            (cols.edge_type, "type".to_owned()),
+            (cols.contributions, "contributions".to_owned()),
+            (cols.weight, "weight".to_owned()),
+        ])?;
+    }
+    for node in graph.nodes() {
+        for e in graph.edges_directed(node.id(), Direction::Outgoing) {
+            let edge = match graph.get_edge(e.id) {
+                Some(edge) => edge,
+                None => continue,
+            };
+            let edge_type = edge.edge_type();
+            out.write(&[
+                (cols.id, edge.id().to_string()),
+                (cols.from, edge.source().to_string()),
+                (cols.to, edge.target().to_string()),
+                (cols.edge_type, edge_type.to_tag().to_string()),
+                (cols.contributions, edge_type.value().to_string()),
+                (cols.weight, edge.weight().into().to_string()),
+            ])?;
+        }
+    }
+    out.flush()
+}
+
+/// Call `f` with the fields of every non-empty record, after the header,
+/// along with the line the record starts at. Records span several lines
+/// when quoted fields contain line breaks. Returns the number of records.
+fn read_records<R, F>(reader: R, format: &CsvFormat, mut f: F) -> Result<usize, CsvError>
+where
+    R: Read,
+    F: FnMut(usize, &[String]) -> Result<(), CsvError>,
+{
+    let mut count = 0;
+    let mut lines = BufReader::new(reader).lines().enumerate();
+
+    while let Some((i, line)) = lines.next() {
+        let mut record = line?;
+        while open_quote(&record) {
+            let (_, line) = lines
+                .next()
+                .ok_or(CsvError::UnterminatedQuote { line: i + 1 })?;
+            record.push('\n');
+            record.push_str(&line?);
+        }
+        if (i == 0 && format.header) || record.trim().is_empty() {
+            continue;
+        }
+        f(i + 1, &split(&record, format.delimiter))?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Whether a quoted field is left open at the end of `record`. Escaped
+/// quotes come in pairs, and don't change the outcome.
+fn open_quote(record: &str) -> bool {
+    record.chars().filter(|c| *c == '"').count() % 2 == 1
+}
+
+/// Split a line into fields, handling double-quoted fields.
+pub(crate) fn split(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    fields.push(current);
+    fields
+}
+
+fn field(fields: &[String], line: usize, column: usize) -> Result<&str, CsvError> {
+    fields
+        .get(column)
+        .map(|f| f.trim())
+        .ok_or(CsvError::MissingColumn { line, column })
+}
+
+fn parse<T: FromStr>(fields: &[String], line: usize, column: usize) -> Result<T, CsvError> {
+    let value = field(fields, line, column)?;
+    value.parse().map_err(|_| invalid(line, column, value))
+}
+
+/// Parse a number of contributions, where an empty field means zero.
+fn contributions(fields: &[String], line: usize, column: usize) -> Result<u32, CsvError> {
+    match fields.get(column).map(|f| f.trim()) {
+        None | Some("") => Ok(0),
+        Some(_) => parse(fields, line, column),
+    }
+}
+
+fn invalid(line: usize, column: usize, value: &str) -> CsvError {
+    CsvError::InvalidValue {
+        line,
+        column,
+        value: value.to_owned(),
+    }
+}
+
+/// Writes records whose fields are given by column.
+struct RecordWriter<W> {
+    writer: W,
+    delimiter: char,
+}
+
+impl<W: Write> RecordWriter<W> {
+    fn new(writer: W, delimiter: char) -> Self {
+        RecordWriter { writer, delimiter }
+    }
+
+    /// Write a record. Columns not given are left empty.
+    fn write(&mut self, fields: &[(usize, String)]) -> Result<(), CsvError> {
+        let len = fields.iter().map(|(col, _)| col + 1).max().unwrap_or(0);
+        let mut row = vec![""; len];
+        for (col, value) in fields {
+            row[*col] = value;
+        }
+
+        let mut line = String::new();
+        for (i, value) in row.into_iter().enumerate() {
+            if i > 0 {
+                line.push(self.delimiter);
+            }
+            if value.contains(self.delimiter)
+                || value.contains('"')
+                || value.contains('\n')
+                || value.contains('\r')
+            {
+                line.push('"');
+                line.push_str(&value.replace('"', "\"\""));
+                line.push('"');
+            } else {
+                line.push_str(value);
+            }
+        }
+        writeln!(self.writer, "{}", line)?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), CsvError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,3 @@
+//! Importing and exporting graphs.
+
+pub mod csv;
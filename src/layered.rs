@@ -0,0 +1,236 @@
+//! A generic `GraphAPI` implementation keeping one graph per layer.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::{
+    Direction, Graph, GraphAPI, GraphBuilder, GraphObject, GraphWriter, Id, Layer, LayerError,
+    LayerInfo, LayerRemoval,
+};
+
+/// A handy type alias.
+type NodeId<G> = Id<<G as Graph>::Node>;
+
+/// Node correspondences between layers: `from -> to -> (id in from -> id in to)`.
+type Links<Id> = HashMap<Layer, HashMap<Layer, HashMap<Id, Id>>>;
+
+/// A graph and its layer metadata.
+struct Entry<G> {
+    graph: G,
+    info: LayerInfo,
+}
+
+/// A `GraphAPI` implementation over a map of layers, each holding a graph of
+/// type `G`.
+pub struct LayeredGraphs<G: GraphWriter> {
+    layers: HashMap<Layer, Entry<G>>,
+    links: Links<NodeId<G>>,
+}
+
+impl<G: GraphWriter> LayeredGraphs<G> {
+    /// Create an instance without any layers.
+    pub fn new() -> Self {
+        LayeredGraphs {
+            layers: HashMap::new(),
+            links: HashMap::new(),
+        }
+    }
+
+    /// Iterator over the existing layers, in no particular order.
+    pub fn layers(&self) -> impl Iterator<Item = &Layer> {
+        self.layers.keys()
+    }
+
+    /// Whether the given layer exists.
+    pub fn contains_layer(&self, layer: &Layer) -> bool {
+        self.layers.contains_key(layer)
+    }
+}
+
+impl<G: GraphWriter> Default for LayeredGraphs<G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<G> LayeredGraphs<G>
+where
+    G: GraphWriter,
+    NodeId<G>: Clone + Eq + Hash,
+{
+    /// Record a one-way link, dropping the reverse of the link it replaces.
+    fn insert_link(&mut self, from: &Layer, id: &NodeId<G>, to: &Layer, counterpart: &NodeId<G>) {
+        let previous = self
+            .links
+            .entry(from.clone())
+            .or_default()
+            .entry(to.clone())
+            .or_default()
+            .insert(id.clone(), counterpart.clone());
+
+        if let Some(previous) = previous {
+            if let Some(reverse) = self.links.get_mut(to).and_then(|m| m.get_mut(from)) {
+                if reverse.get(&previous) == Some(id) {
+                    reverse.remove(&previous);
+                }
+            }
+        }
+    }
+
+    /// Drop all links from and to the given layer.
+    fn remove_links(&mut self, layer: &Layer) {
+        self.links.remove(layer);
+        for targets in self.links.values_mut() {
+            targets.remove(layer);
+        }
+    }
+}
+
+/// The number of nodes and edges of a graph.
+fn contents<G: Graph>(graph: &G) -> LayerRemoval {
+    let mut removal = LayerRemoval::default();
+    for node in graph.nodes() {
+        removal.nodes += 1;
+        // Every edge is outgoing from exactly one node.
+        removal.edges += graph.edges_directed(node.id(), Direction::Outgoing).len();
+    }
+    removal
+}
+
+impl<G> GraphAPI for LayeredGraphs<G>
+where
+    G: GraphWriter,
+    NodeId<G>: Clone + Eq + Hash,
+{
+    type Graph = G;
+
+    /// Add a graph layer. Adding a layer that already exists has no effect.
+    fn add_layer<B>(&mut self, layer: Layer, info: LayerInfo, builder: B)
+    where
+        B: GraphBuilder<Graph = G>,
+    {
+        trace_event!(layer = %layer, "adding layer");
+        self.layers.entry(layer).or_insert_with(|| Entry {
+            graph: builder.build(),
+            info,
+        });
+    }
+
+    fn layer_info(&self, layer: &Layer) -> Option<&LayerInfo> {
+        self.layers.get(layer).map(|e| &e.info)
+    }
+
+    fn freeze_layer(&mut self, layer: &Layer) -> Result<(), LayerError> {
+        let entry = self
+            .layers
+            .get_mut(layer)
+            .ok_or_else(|| LayerError::NotFound(layer.clone()))?;
+
+        entry.info.read_only = true;
+        trace_event!(layer = %layer, "froze layer");
+        Ok(())
+    }
+
+    fn remove_layer(&mut self, layer: &Layer) -> Result<(), LayerError> {
+        let entry = self
+            .layers
+            .get(layer)
+            .ok_or_else(|| LayerError::NotFound(layer.clone()))?;
+        if entry.info.read_only {
+            return Err(LayerError::ReadOnly(layer.clone()));
+        }
+
+        let nodes = entry.graph.nodes().count();
+        if nodes > 0 {
+            return Err(LayerError::NotEmpty {
+                layer: layer.clone(),
+                nodes,
+            });
+        }
+        self.layers.remove(layer);
+        self.remove_links(layer);
+        trace_event!(layer = %layer, "removed layer");
+
+        Ok(())
+    }
+
+    fn remove_layer_cascade(&mut self, layer: &Layer) -> Result<LayerRemoval, LayerError> {
+        match self.layers.get(layer) {
+            None => return Err(LayerError::NotFound(layer.clone())),
+            Some(entry) if entry.info.read_only => return Err(LayerError::ReadOnly(layer.clone())),
+            Some(_) => {}
+        }
+        let entry = self
+            .layers
+            .remove(layer)
+            .ok_or_else(|| LayerError::NotFound(layer.clone()))?;
+        self.remove_links(layer);
+
+        let removal = contents(&entry.graph);
+        trace_event!(
+            layer = %layer,
+            nodes = removal.nodes,
+            edges = removal.edges,
+            "removed layer and its contents"
+        );
+        Ok(removal)
+    }
+
+    fn clear_layer(&mut self, layer: &Layer) -> Result<LayerRemoval, LayerError> {
+        let entry = self
+            .layers
+            .get_mut(layer)
+            .ok_or_else(|| LayerError::NotFound(layer.clone()))?;
+        if entry.info.read_only {
+            return Err(LayerError::ReadOnly(layer.clone()));
+        }
+
+        let removal = contents(&entry.graph);
+        entry.graph.clear();
+        self.remove_links(layer);
+        trace_event!(
+            layer = %layer,
+            nodes = removal.nodes,
+            edges = removal.edges,
+            "cleared layer"
+        );
+        Ok(removal)
+    }
+
+    fn graph(&self, layer: &Layer) -> Option<&G> {
+        self.layers.get(layer).map(|e| &e.graph)
+    }
+
+    fn graph_mut(&mut self, layer: &Layer) -> Option<&mut G> {
+        self.layers
+            .get_mut(layer)
+            .filter(|e| !e.info.read_only)
+            .map(|e| &mut e.graph)
+    }
+
+    fn link_across(
+        &mut self,
+        layer_a: &Layer,
+        id_a: &NodeId<G>,
+        layer_b: &Layer,
+        id_b: &NodeId<G>,
+    ) -> Result<(), LayerError> {
+        for (layer, id) in &[(layer_a, id_a), (layer_b, id_b)] {
+            let graph = self
+                .graph(layer)
+                .ok_or_else(|| LayerError::NotFound((*layer).clone()))?;
+
+            if graph.get_node(id).is_none() {
+                return Err(LayerError::NodeNotFound((*layer).clone()));
+            }
+        }
+        self.insert_link(layer_a, id_a, layer_b, id_b);
+        self.insert_link(layer_b, id_b, layer_a, id_a);
+
+        Ok(())
+    }
+
+    fn linked(&self, from: &Layer, id: &NodeId<G>, to: &Layer) -> Option<&NodeId<G>> {
+        self.links.get(from)?.get(to)?.get(id)
+    }
+}
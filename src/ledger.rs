@@ -0,0 +1,460 @@
+//! Applying project checkpoints to the osrank graph.
+//!
+//! A checkpoint records a new version of a project on the registry: the
+//! dependencies it adds or removes, and the contributions made to it. The
+//! registry and osrank both apply checkpoints through `CheckpointApplier`,
+//! so that replaying the same checkpoints yields the same graph.
+
+use super::epochs::EdgeHistory;
+use super::limits::LimitError;
+use super::types::{
+    ContributionLedger, EdgeData, EdgeType, Hash32, IdGenerator, NodeData, NodeRank, NodeType,
+    Timestamps,
+};
+use super::{
+    Data, Direction, Edge, Graph, GraphAPI, GraphDataWriter, GraphWriter, Id, Layer, LayerError,
+    Node,
+};
+
+use num_traits::Zero;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::Hash;
+
+/// A dependency added or removed by a checkpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub struct Dep<NodeId> {
+    /// The project depended upon.
+    pub node_id: NodeId,
+    /// Whether the dependency is added, or removed.
+    pub is_added: bool,
+}
+
+/// Contributions made by a user to the checkpointed version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub struct Contrib<NodeId> {
+    /// The contributing user.
+    pub node_id: NodeId,
+    /// How many contributions.
+    pub contributions: u32,
+}
+
+/// A new version of a project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub struct Checkpoint<NodeId> {
+    /// The project.
+    pub project: NodeId,
+    /// The version, which ids of the edges introduced are derived from.
+    pub version: Vec<u8>,
+    /// The hash of the version.
+    pub hash: Hash32,
+    /// The dependencies added or removed since the previous version.
+    pub deps: Vec<Dep<NodeId>>,
+    /// The contributions made since the previous version.
+    pub contributions: Vec<Contrib<NodeId>>,
+}
+
+/// A graph checkpoints can be applied to.
+///
+/// Graphs may refuse checkpoints exceeding their resources, eg. `Limited`
+/// graphs. The check is made before the checkpoint is applied, so a refused
+/// checkpoint leaves the graph untouched. Other graphs accept every valid
+/// checkpoint, with the default `check_limits`.
+pub trait LedgerGraph: Graph {
+    /// Check that the checkpoint can be applied within the limits of the
+    /// graph.
+    fn check_limits(&self, _checkpoint: &Checkpoint<Id<Self::Node>>) -> Result<(), LimitError> {
+        Ok(())
+    }
+}
+
+/// Node data that checkpoints can create and count contributions on.
+pub trait LedgerNodeData<NodeId> {
+    /// The data of a node created by a checkpoint.
+    fn from_node_type(node_type: NodeType) -> Self;
+
+    /// Increment the contributions of the node, made with `counterpart`.
+    fn add_contributions(&mut self, counterpart: &NodeId, contributions: u32);
+}
+
+/// Only the total of contributions is kept.
+impl<NodeId> LedgerNodeData<NodeId> for NodeType {
+    fn from_node_type(node_type: NodeType) -> Self {
+        node_type
+    }
+
+    fn add_contributions(&mut self, _counterpart: &NodeId, contributions: u32) {
+        NodeType::add_contributions(self, contributions)
+    }
+}
+
+/// Contributions are also recorded in the breakdown of the node.
+impl<W: Zero> LedgerNodeData<u64> for NodeData<W> {
+    fn from_node_type(node_type: NodeType) -> Self {
+        NodeData {
+            node_type,
+            rank: NodeRank { rank: W::zero() },
+            contributions: ContributionLedger::new(),
+            timestamps: Timestamps::default(),
+        }
+    }
+
+    fn add_contributions(&mut self, counterpart: &u64, contributions: u32) {
+        self.node_type.add_contributions(contributions);
+        self.contributions.add(*counterpart, contributions);
+    }
+}
+
+/// Edge data that checkpoints can create.
+pub trait LedgerEdgeData {
+    /// The data of an edge created by a checkpoint.
+    fn from_edge_type(edge_type: EdgeType) -> Self;
+}
+
+impl LedgerEdgeData for EdgeType {
+    fn from_edge_type(edge_type: EdgeType) -> Self {
+        edge_type
+    }
+}
+
+/// Edge weights are set to zero, to be assigned afterwards, eg. with
+/// `weights::assign_weights`.
+impl<W: Zero> LedgerEdgeData for EdgeData<W> {
+    fn from_edge_type(edge_type: EdgeType) -> Self {
+        EdgeData {
+            edge_type,
+            weight: W::zero(),
+            history: EdgeHistory::new(),
+            timestamps: Timestamps::default(),
+        }
+    }
+}
+
+/// A reason a checkpoint can't be applied to a graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckpointViolation<NodeId, EdgeId> {
+    /// An added dependency isn't in the graph.
+    UnknownDependency(NodeId),
+    /// A removed dependency isn't a dependency of the project.
+    UnknownRemoval(NodeId),
+    /// A user is credited with zero contributions.
+    ZeroContributions(NodeId),
+    /// An edge id is already in the graph, or produced twice by the
+    /// checkpoint.
+    DuplicateEdge(EdgeId),
+    /// The total contributions of a node would exceed `u32::MAX`.
+    ContributionOverflow(NodeId),
+}
+
+impl<NodeId: fmt::Debug, EdgeId: fmt::Debug> fmt::Display for CheckpointViolation<NodeId, EdgeId> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckpointViolation::UnknownDependency(id) => {
+                write!(f, "dependency {:?} not found", id)
+            }
+            CheckpointViolation::UnknownRemoval(id) => {
+                write!(f, "removed dependency {:?} is not a dependency", id)
+            }
+            CheckpointViolation::ZeroContributions(id) => {
+                write!(f, "user {:?} has zero contributions", id)
+            }
+            CheckpointViolation::DuplicateEdge(id) => write!(f, "duplicate edge {:?}", id),
+            CheckpointViolation::ContributionOverflow(id) => {
+                write!(f, "contributions of {:?} overflow", id)
+            }
+        }
+    }
+}
+
+/// A failure to apply a checkpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckpointError<NodeId, EdgeId> {
+    /// The layer is missing or read-only.
+    Layer(LayerError),
+    /// The checkpoint doesn't validate against the graph, which was left
+    /// untouched.
+    Invalid(Vec<CheckpointViolation<NodeId, EdgeId>>),
+    /// The checkpoint exceeds the limits of the graph, which was left
+    /// untouched.
+    Limit(LimitError),
+}
+
+impl<NodeId: fmt::Debug, EdgeId: fmt::Debug> fmt::Display for CheckpointError<NodeId, EdgeId> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckpointError::Layer(e) => write!(f, "{}", e),
+            CheckpointError::Invalid(violations) => {
+                write!(f, "invalid checkpoint")?;
+                for (i, v) in violations.iter().enumerate() {
+                    write!(f, "{} {}", if i == 0 { ":" } else { "," }, v)?;
+                }
+                Ok(())
+            }
+            CheckpointError::Limit(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl<NodeId: fmt::Debug, EdgeId: fmt::Debug> std::error::Error for CheckpointError<NodeId, EdgeId> {}
+
+impl<NodeId, EdgeId> From<LayerError> for CheckpointError<NodeId, EdgeId> {
+    fn from(e: LayerError) -> Self {
+        CheckpointError::Layer(e)
+    }
+}
+
+impl<NodeId, EdgeId> From<LimitError> for CheckpointError<NodeId, EdgeId> {
+    fn from(e: LimitError) -> Self {
+        CheckpointError::Limit(e)
+    }
+}
+
+/// The violations of a checkpoint of the graph.
+type Violations<G> = Vec<CheckpointViolation<Id<<G as Graph>::Node>, Id<<G as Graph>::Edge>>>;
+
+/// Check that a checkpoint can be applied to a graph: added dependencies
+/// exist, removed ones are dependencies of the project, contributions are
+/// non-zero and the totals of nodes don't overflow, and the edges added
+/// don't collide with existing edges or with each other. Returns all the
+/// violations found.
+pub fn validate_checkpoint<G, I>(
+    graph: &G,
+    checkpoint: &Checkpoint<Id<G::Node>>,
+    ids: &I,
+) -> Result<(), Violations<G>>
+where
+    G: Graph,
+    Id<G::Node>: Clone + PartialEq,
+    Id<G::Edge>: Clone + Eq + Hash,
+    I: IdGenerator<Id<G::Node>, Id = Id<G::Edge>>,
+{
+    let project = &checkpoint.project;
+    let version = &checkpoint.version[..];
+    let mut violations = Vec::new();
+    let mut added = HashSet::new();
+    let mut check_edge = |id: Id<G::Edge>, violations: &mut Violations<G>| {
+        if graph.get_edge(&id).is_some() || !added.insert(id.clone()) {
+            violations.push(CheckpointViolation::DuplicateEdge(id));
+        }
+    };
+
+    for dep in &checkpoint.deps {
+        if dep.is_added {
+            if graph.get_node(&dep.node_id).is_none() {
+                violations.push(CheckpointViolation::UnknownDependency(dep.node_id.clone()));
+            }
+            let id = ids.edge_id(project, &dep.node_id, &EdgeType::Dependency, version);
+            check_edge(id, &mut violations);
+        } else if dependency_edges(graph, project, &dep.node_id).is_empty() {
+            violations.push(CheckpointViolation::UnknownRemoval(dep.node_id.clone()));
+        }
+    }
+
+    // The totals of the project and of its contributors once applied.
+    let total = |id: &Id<G::Node>| {
+        graph
+            .get_node(id)
+            .map_or(0, |n| u64::from(n.node_type().total_contributions()))
+    };
+    let mut totals = vec![(project, total(project))];
+    for contrib in &checkpoint.contributions {
+        for node in [project, &contrib.node_id] {
+            let c = u64::from(contrib.contributions);
+            match totals.iter_mut().find(|(id, _)| *id == node) {
+                Some((_, sum)) => *sum += c,
+                None => totals.push((node, total(node) + c)),
+            }
+        }
+    }
+    for (id, sum) in totals {
+        if sum > u64::from(u32::MAX) {
+            violations.push(CheckpointViolation::ContributionOverflow(id.clone()));
+        }
+    }
+
+    for contrib in &checkpoint.contributions {
+        let user = &contrib.node_id;
+        if contrib.contributions == 0 {
+            violations.push(CheckpointViolation::ZeroContributions(user.clone()));
+        }
+        let edge_type = EdgeType::ProjectToUserContribution(contrib.contributions);
+        check_edge(
+            ids.edge_id(project, user, &edge_type, version),
+            &mut violations,
+        );
+        let edge_type = EdgeType::UserToProjectContribution(contrib.contributions);
+        check_edge(
+            ids.edge_id(user, project, &edge_type, version),
+            &mut violations,
+        );
+    }
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
+
+/// The dependency edges from `project` to `dep`.
+fn dependency_edges<G>(graph: &G, project: &Id<G::Node>, dep: &Id<G::Node>) -> Vec<Id<G::Edge>>
+where
+    G: Graph,
+    Id<G::Node>: PartialEq,
+    Id<G::Edge>: Clone,
+{
+    if graph.get_node(project).is_none() {
+        return Vec::new();
+    }
+    graph
+        .edges_directed(project, Direction::Outgoing)
+        .into_iter()
+        .filter(|e| {
+            e.to == dep
+                && graph
+                    .get_edge(e.id)
+                    .is_some_and(|edge| *edge.edge_type() == EdgeType::Dependency)
+        })
+        .map(|e| e.id.clone())
+        .collect()
+}
+
+/// Something checkpoints can be applied to.
+pub trait CheckpointApplier<NodeId, EdgeId> {
+    /// Apply a checkpoint to the graph of the given layer, deriving the ids
+    /// of new edges with `ids`. The checkpoint is validated first, with
+    /// `validate_checkpoint`, and only applied if valid and within the limits
+    /// of the graph.
+    fn apply_checkpoint<I>(
+        &mut self,
+        layer: &Layer,
+        checkpoint: &Checkpoint<NodeId>,
+        ids: &I,
+    ) -> Result<(), CheckpointError<NodeId, EdgeId>>
+    where
+        I: IdGenerator<NodeId, Id = EdgeId>;
+}
+
+/// The error applying a checkpoint to a graph.
+type EdgeCheckpointError<G> = CheckpointError<Id<<G as Graph>::Node>, Id<<G as Graph>::Edge>>;
+
+/// The default implementation, for any `GraphAPI`.
+///
+/// The project node, as well as the contributing user nodes, are created if
+/// missing. Added dependencies become `project -> dependency` edges, and
+/// removing a dependency removes all these edges, whichever version added
+/// them. Every contribution adds a `project -> user` and a
+/// `user -> project` edge, and increments the contributions of both nodes.
+impl<T> CheckpointApplier<Id<<T::Graph as Graph>::Node>, Id<<T::Graph as Graph>::Edge>> for T
+where
+    T: GraphAPI,
+    T::Graph: GraphDataWriter + LedgerGraph,
+    Data<<T::Graph as Graph>::Node>: LedgerNodeData<Id<<T::Graph as Graph>::Node>>,
+    Data<<T::Graph as Graph>::Edge>: LedgerEdgeData,
+    Id<<T::Graph as Graph>::Node>: Clone + PartialEq,
+    Id<<T::Graph as Graph>::Edge>: Clone + Eq + Hash,
+{
+    fn apply_checkpoint<I>(
+        &mut self,
+        layer: &Layer,
+        checkpoint: &Checkpoint<Id<<T::Graph as Graph>::Node>>,
+        ids: &I,
+    ) -> Result<(), EdgeCheckpointError<T::Graph>>
+    where
+        I: IdGenerator<Id<<T::Graph as Graph>::Node>, Id = Id<<T::Graph as Graph>::Edge>>,
+    {
+        trace_span!(
+            "apply_checkpoint",
+            layer = %layer,
+            hash = %checkpoint.hash,
+            deps = checkpoint.deps.len(),
+            contributions = checkpoint.contributions.len()
+        );
+
+        match self.layer_info(layer) {
+            None => return Err(LayerError::NotFound(layer.clone()).into()),
+            Some(info) if info.read_only => return Err(LayerError::ReadOnly(layer.clone()).into()),
+            Some(_) => {}
+        }
+        let graph = self
+            .graph_mut(layer)
+            .ok_or_else(|| LayerError::NotFound(layer.clone()))?;
+        validate_checkpoint(&*graph, checkpoint, ids).map_err(|violations| {
+            trace_event!(violations = violations.len(), "rejected invalid checkpoint");
+            CheckpointError::Invalid(violations)
+        })?;
+        graph.check_limits(checkpoint).map_err(|err| {
+            trace_event!(error = %err, "rejected checkpoint exceeding limits");
+            CheckpointError::Limit(err)
+        })?;
+        let project = &checkpoint.project;
+        let version = &checkpoint.version[..];
+
+        ensure_node(
+            graph,
+            project,
+            NodeType::Project {
+                contributions_from_all_users: 0,
+            },
+        );
+
+        for dep in &checkpoint.deps {
+            if dep.is_added {
+                let edge_type = EdgeType::Dependency;
+                let id = ids.edge_id(project, &dep.node_id, &edge_type, version);
+                graph.add_edge(id, project, &dep.node_id, from_edge_type(edge_type));
+            } else {
+                for id in dependency_edges(&*graph, project, &dep.node_id) {
+                    graph.remove_edge(id);
+                }
+            }
+        }
+
+        for contrib in &checkpoint.contributions {
+            let user = &contrib.node_id;
+            ensure_node(
+                graph,
+                user,
+                NodeType::User {
+                    contributions_to_all_projects: 0,
+                },
+            );
+
+            let edge_type = EdgeType::ProjectToUserContribution(contrib.contributions);
+            let id = ids.edge_id(project, user, &edge_type, version);
+            graph.add_edge(id, project, user, from_edge_type(edge_type));
+
+            let edge_type = EdgeType::UserToProjectContribution(contrib.contributions);
+            let id = ids.edge_id(user, project, &edge_type, version);
+            graph.add_edge(id, user, project, from_edge_type(edge_type));
+
+            for (node, counterpart) in [(project, user), (user, project)] {
+                if let Some(data) = graph.node_data_mut(node) {
+                    data.add_contributions(counterpart, contrib.contributions);
+                }
+            }
+        }
+        trace_event!("applied checkpoint");
+        Ok(())
+    }
+}
+
+/// Add a node unless it already exists.
+fn ensure_node<G>(graph: &mut G, id: &Id<G::Node>, node_type: NodeType)
+where
+    G: GraphWriter,
+    Data<G::Node>: LedgerNodeData<Id<G::Node>>,
+    Id<G::Node>: Clone,
+{
+    if graph.get_node(id).is_none() {
+        graph.add_node(id.clone(), LedgerNodeData::from_node_type(node_type));
+    }
+}
+
+/// The data of a new edge.
+fn from_edge_type<D: LedgerEdgeData>(edge_type: EdgeType) -> D {
+    D::from_edge_type(edge_type)
+}
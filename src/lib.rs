@@ -1,18 +1,239 @@
-#[deny(clippy::all)]
-///! Graph API Traits
+//! Graph API Traits
+//!
+//! Without the default `std` feature, the crate is `no_std` and only needs
+//! `alloc`: the traits and the `types` are available, along with the few
+//! modules they build on, while the graph implementations, algorithms and
+//! I/O require `std`.
+#![cfg_attr(not(feature = "std"), no_std)]
+#![deny(clippy::all)]
+
+extern crate alloc;
+
+#[macro_use]
+mod trace;
+
+#[cfg(feature = "std")]
+pub mod algorithms;
+pub mod annotators;
+pub mod asynchronous;
+#[cfg(feature = "std")]
+pub mod bench;
+pub mod cancel;
+pub mod canonical;
+#[cfg(feature = "std")]
+pub mod compare;
+#[cfg(feature = "config")]
+pub mod config;
+#[cfg(feature = "std")]
+pub mod debug;
+#[cfg(feature = "std")]
+pub mod diff;
+#[cfg(feature = "std")]
+pub mod distributed;
+#[cfg(feature = "std")]
+pub mod dyn_graph;
+pub mod epochs;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
+#[cfg(feature = "std")]
+pub mod importers;
+#[cfg(feature = "std")]
+pub mod io;
+#[cfg(feature = "std")]
+pub mod layered;
+#[cfg(feature = "std")]
+pub mod ledger;
+#[cfg(feature = "std")]
+pub mod limits;
+#[cfg(feature = "linalg")]
+pub mod linalg;
+#[cfg(feature = "std")]
+pub mod mem;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod migrate;
+#[cfg(feature = "std")]
+pub mod partition;
+#[cfg(feature = "std")]
+pub mod pipeline;
+pub mod progress;
+pub mod properties;
+#[cfg(feature = "proto")]
+pub mod proto;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "std")]
+pub mod query;
+#[cfg(feature = "std")]
+pub mod ranking;
+#[cfg(feature = "replication")]
+pub mod replication;
+#[cfg(feature = "std")]
+pub mod rewards;
+pub mod rng;
+#[cfg(feature = "std")]
+pub mod sampling;
+#[cfg(feature = "std")]
+pub mod snapshot;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod storage;
+#[cfg(feature = "std")]
+pub mod subgraph;
+#[cfg(feature = "std")]
+pub mod testing;
+pub mod transform;
 pub mod types;
+#[cfg(feature = "wal")]
+pub mod wal;
+#[cfg(feature = "std")]
+pub mod walks;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "std")]
+pub mod watch;
+#[cfg(feature = "std")]
+pub mod weights;
+
+#[cfg(feature = "std")]
+pub use crate::layered::LayeredGraphs;
 
-use crate::types::EdgeType;
+use crate::types::EdgeTypeTag;
+
+use alloc::borrow::Cow;
+use alloc::borrow::ToOwned;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
 
 /// Specifies a direction for an edge.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Direction {
     Outgoing,
     Incoming,
+    /// Both outgoing and incoming edges. Self-loops are listed once.
+    Both,
+}
+
+impl Direction {
+    /// The reverse direction. `Both` is its own opposite.
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Outgoing => Direction::Incoming,
+            Direction::Incoming => Direction::Outgoing,
+            Direction::Both => Direction::Both,
+        }
+    }
+
+    /// Whether an edge from `from` to `to` leaves or enters `node` in this
+    /// direction.
+    pub fn matches<NodeId: PartialEq>(self, node: &NodeId, from: &NodeId, to: &NodeId) -> bool {
+        match self {
+            Direction::Outgoing => from == node,
+            Direction::Incoming => to == node,
+            Direction::Both => from == node || to == node,
+        }
+    }
 }
 
 /// A graph layer name.
-pub struct Layer(pub &'static str);
+///
+/// Layer names are non-empty, at most `Layer::MAX_LEN` bytes long, and only
+/// contain lowercase ASCII letters, digits, `-`, `_` and `.`, eg.
+/// `"osrank-epoch-42"`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Layer(Cow<'static, str>);
+
+impl Layer {
+    /// Maximum length of a layer name, in bytes.
+    pub const MAX_LEN: usize = 64;
+
+    /// Create a layer from a static name, without validating it. Meant for
+    /// well-known layers such as `Layer::from_static("osrank")`.
+    pub const fn from_static(name: &'static str) -> Self {
+        Layer(Cow::Borrowed(name))
+    }
+
+    /// Create a layer from a name constructed at runtime, checking that it is
+    /// a valid layer name.
+    pub fn new<S: Into<Cow<'static, str>>>(name: S) -> Result<Self, LayerNameError> {
+        let name = name.into();
+
+        if name.is_empty() {
+            return Err(LayerNameError::Empty);
+        }
+        if name.len() > Self::MAX_LEN {
+            return Err(LayerNameError::TooLong(name.len()));
+        }
+        if let Some(c) = name
+            .chars()
+            .find(|c| !matches!(c, 'a'..='z' | '0'..='9' | '-' | '_' | '.'))
+        {
+            return Err(LayerNameError::InvalidChar(c));
+        }
+        Ok(Layer(name))
+    }
+
+    /// The layer name.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for Layer {
+    type Err = LayerNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Layer::new(s.to_owned())
+    }
+}
+
+impl AsRef<str> for Layer {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// An invalid layer name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayerNameError {
+    /// The name is empty.
+    Empty,
+    /// The name is longer than `Layer::MAX_LEN` bytes.
+    TooLong(usize),
+    /// The name contains a character that isn't allowed.
+    InvalidChar(char),
+}
+
+impl fmt::Display for LayerNameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LayerNameError::Empty => write!(f, "layer name is empty"),
+            LayerNameError::TooLong(len) => write!(
+                f,
+                "layer name is {} bytes long, the maximum is {}",
+                len,
+                Layer::MAX_LEN
+            ),
+            LayerNameError::InvalidChar(c) => {
+                write!(f, "layer name contains invalid character {:?}", c)
+            }
+        }
+    }
+}
+
+impl core::error::Error for LayerNameError {}
 
 /// A handy type alias.
 pub type Id<T> = <T as GraphObject>::Id;
@@ -53,34 +274,264 @@ pub trait Node<N>: GraphObject<Data = N> {
 }
 
 /// A graph edge between two nodes.
-pub trait Edge<W, NodeId, E>: GraphObject<Data = E> {
+///
+/// The source and target ids can have different types, which allows edges
+/// between different kinds of nodes to be typed precisely (eg. a contribution
+/// from a `types::UserId` to a `types::ProjectId`). By default both endpoints
+/// share the same id type.
+///
+/// The weight of an edge is the one carried by its data, so that it reads
+/// back what `GraphWriter::add_edge` wrote.
+pub trait Edge<W, NodeId, E, TargetId = NodeId>: GraphObject<Data = E>
+where
+    E: HasWeight<W>,
+{
     /// The source node.
     fn source(&self) -> &NodeId;
     /// The target node
-    fn target(&self) -> &NodeId;
+    fn target(&self) -> &TargetId;
     /// Get the edge weight.
-    fn weight(&self) -> W;
+    fn weight(&self) -> W {
+        self.data().weight()
+    }
     /// Returns the type of this edge.
     // TODO(adinapoli) Same considerations as per `Node::node_type` apply.
     fn edge_type(&self) -> &types::EdgeType;
 }
 
+/// Metadata attached to a graph layer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerInfo {
+    /// Block height at which the layer was created.
+    pub created_at: u64,
+    /// Human-readable description of the layer's contents.
+    pub description: String,
+    /// Whether the layer is frozen. Frozen layers can only be read from.
+    pub read_only: bool,
+}
+
+impl LayerInfo {
+    /// Metadata for a new, writable layer.
+    pub fn new<S: Into<String>>(created_at: u64, description: S) -> Self {
+        LayerInfo {
+            created_at,
+            description: description.into(),
+            read_only: false,
+        }
+    }
+}
+
+/// The contents dropped by `GraphAPI::remove_layer_cascade` or
+/// `GraphAPI::clear_layer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LayerRemoval {
+    /// Number of nodes removed.
+    pub nodes: usize,
+    /// Number of edges removed.
+    pub edges: usize,
+}
+
+/// An error operating on a graph layer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayerError {
+    /// The layer doesn't exist.
+    NotFound(Layer),
+    /// The layer still contains nodes.
+    NotEmpty { layer: Layer, nodes: usize },
+    /// A node referenced in the operation doesn't exist in the layer.
+    NodeNotFound(Layer),
+    /// The layer is frozen and can't be written to.
+    ReadOnly(Layer),
+}
+
+impl fmt::Display for LayerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LayerError::NotFound(layer) => write!(f, "layer {} not found", layer),
+            LayerError::NotEmpty { layer, nodes } => {
+                write!(f, "layer {} still contains {} node(s)", layer, nodes)
+            }
+            LayerError::NodeNotFound(layer) => write!(f, "node not found in layer {}", layer),
+            LayerError::ReadOnly(layer) => write!(f, "layer {} is read-only", layer),
+        }
+    }
+}
+
+impl core::error::Error for LayerError {}
+
+/// A builder for graphs, for implementations which require configuration to
+/// be constructed.
+pub trait GraphBuilder {
+    /// The graph built.
+    type Graph: Graph;
+
+    /// Hint at the number of nodes and edges the graph is expected to hold.
+    /// Ignored by default.
+    fn with_capacity(self, _nodes: usize, _edges: usize) -> Self
+    where
+        Self: Sized,
+    {
+        self
+    }
+
+    /// Build the graph.
+    fn build(self) -> Self::Graph;
+}
+
+/// A pre-built graph is its own builder.
+impl<G: Graph> GraphBuilder for G {
+    type Graph = G;
+
+    fn build(self) -> G {
+        self
+    }
+}
+
+/// A builder for graphs implementing `Default`.
+pub struct DefaultBuilder<G>(core::marker::PhantomData<G>);
+
+impl<G> DefaultBuilder<G> {
+    /// Create a builder.
+    pub fn new() -> Self {
+        DefaultBuilder(core::marker::PhantomData)
+    }
+}
+
+impl<G> Default for DefaultBuilder<G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<G: Graph + Default> GraphBuilder for DefaultBuilder<G> {
+    type Graph = G;
+
+    fn build(self) -> G {
+        G::default()
+    }
+}
+
 /// The Graph API
 pub trait GraphAPI {
     /// The underlying graph.
     type Graph: GraphWriter;
 
-    /// Add a graph layer.
-    fn add_layer(&mut self, layer: Layer);
+    /// Add a graph layer, with the given metadata. The layer's graph is
+    /// created by the given builder, or is the given graph itself.
+    fn add_layer<B>(&mut self, layer: Layer, info: LayerInfo, builder: B)
+    where
+        B: GraphBuilder<Graph = Self::Graph>;
+
+    /// Return the metadata of the given layer.
+    fn layer_info(&self, layer: &Layer) -> Option<&LayerInfo>;
+
+    /// Freeze a layer, making it read-only: once frozen, `graph_mut` returns
+    /// `None` for this layer.
+    fn freeze_layer(&mut self, layer: &Layer) -> Result<(), LayerError>;
 
-    /// Remove a graph layer. The layer must not contain any nodes.
-    fn remove_layer(&mut self, layer: &Layer);
+    /// Remove a graph layer. The layer must not contain any nodes, otherwise
+    /// `LayerError::NotEmpty` is returned and the layer is left untouched.
+    /// Fails if the layer is frozen.
+    fn remove_layer(&mut self, layer: &Layer) -> Result<(), LayerError>;
+
+    /// Remove a graph layer along with all its nodes and edges, returning
+    /// how many of each were dropped. Fails if the layer is frozen.
+    fn remove_layer_cascade(&mut self, layer: &Layer) -> Result<LayerRemoval, LayerError>;
+
+    /// Remove all the nodes and edges of a layer, keeping the layer itself,
+    /// and return how many of each were dropped. Links to and from the
+    /// layer are dropped too. Fails if the layer is frozen.
+    fn clear_layer(&mut self, layer: &Layer) -> Result<LayerRemoval, LayerError>;
 
     /// Return an immutable graph of the given layer.
     fn graph(&self, layer: &Layer) -> Option<&Self::Graph>;
 
-    /// Return the mutable graph of the given layer.
+    /// Return the mutable graph of the given layer, unless it is frozen.
     fn graph_mut(&mut self, layer: &Layer) -> Option<&mut Self::Graph>;
+
+    /// Subscribe to the mutations of the given layer made from now on. The
+    /// subscription ends when the layer is removed.
+    #[cfg(feature = "std")]
+    fn subscribe(&self, layer: &Layer) -> Result<watch::GraphSubscription<Self::Graph>, LayerError>
+    where
+        Self::Graph: watch::Observable,
+    {
+        self.graph(layer)
+            .map(watch::Observable::subscribe)
+            .ok_or_else(|| LayerError::NotFound(layer.clone()))
+    }
+
+    /// Advance the given layer to the next epoch, decaying contribution
+    /// counts and node ranks. Returns the diffs describing the changes.
+    fn advance_epoch(
+        &mut self,
+        layer: &Layer,
+        decay: &epochs::EpochDecay,
+    ) -> Result<Vec<types::GraphDiff<'_, Self::Graph>>, LayerError>
+    where
+        <Self::Graph as Graph>::NodeData:
+            epochs::Decay + epochs::Recount<Id<<Self::Graph as Graph>::Node>>,
+        <Self::Graph as Graph>::EdgeData: epochs::Decay,
+        Id<<Self::Graph as Graph>::Node>: Clone + Ord,
+        Id<<Self::Graph as Graph>::Edge>: Clone,
+    {
+        trace_span!("advance_epoch", layer = %layer);
+
+        match self.layer_info(layer) {
+            None => return Err(LayerError::NotFound(layer.clone())),
+            Some(info) if info.read_only => return Err(LayerError::ReadOnly(layer.clone())),
+            Some(_) => {}
+        }
+        let graph = self
+            .graph_mut(layer)
+            .ok_or_else(|| LayerError::NotFound(layer.clone()))?;
+
+        Ok(epochs::decay_graph(graph, decay))
+    }
+
+    /// Declare that node `id_a` of `layer_a` and node `id_b` of `layer_b`
+    /// represent the same entity. Links are symmetric, and a node has at most
+    /// one counterpart in any other layer: linking it again replaces the
+    /// previous link. Both nodes must exist.
+    fn link_across(
+        &mut self,
+        layer_a: &Layer,
+        id_a: &Id<<Self::Graph as Graph>::Node>,
+        layer_b: &Layer,
+        id_b: &Id<<Self::Graph as Graph>::Node>,
+    ) -> Result<(), LayerError>;
+
+    /// Return the counterpart in layer `to` of node `id` of layer `from`, if
+    /// the two were linked with `link_across`. The counterpart may have been
+    /// removed from its graph since.
+    fn linked(
+        &self,
+        from: &Layer,
+        id: &Id<<Self::Graph as Graph>::Node>,
+        to: &Layer,
+    ) -> Option<&Id<<Self::Graph as Graph>::Node>>;
+
+    /// Project a set of nodes of layer `from` into layer `to`, returning the
+    /// counterparts that still exist in `to`, in input order. Nodes without a
+    /// counterpart are skipped.
+    fn project<'a, I>(
+        &'a self,
+        from: &Layer,
+        ids: I,
+        to: &Layer,
+    ) -> Vec<&'a Id<<Self::Graph as Graph>::Node>>
+    where
+        I: IntoIterator<Item = &'a Id<<Self::Graph as Graph>::Node>>,
+    {
+        let graph = match self.graph(to) {
+            Some(graph) => graph,
+            None => return Vec::new(),
+        };
+        ids.into_iter()
+            .filter_map(|id| self.linked(from, id, to))
+            .filter(|id| graph.get_node(id).is_some())
+            .collect()
+    }
 }
 
 pub trait GraphWriter: Graph + GraphDataWriter {
@@ -90,7 +541,8 @@ pub trait GraphWriter: Graph + GraphDataWriter {
     /// Remove a node from the graph.
     fn remove_node(&mut self, id: Id<Self::Node>);
 
-    /// Link two nodes.
+    /// Link two nodes, which must both exist: an edge to a missing node is
+    /// ignored, so that every backend ends up with the same graph.
     fn add_edge(
         &mut self,
         id: Id<Self::Edge>,
@@ -102,18 +554,145 @@ pub trait GraphWriter: Graph + GraphDataWriter {
     /// Unlink two nodes.
     fn remove_edge(&mut self, id: Id<Self::Edge>);
 
-    /// Mutable iterator over nodes.
-    fn nodes_mut(&mut self) -> NodesMut<Self::Node>;
+    /// Insert an edge, or update the edge with the same id. If that edge
+    /// links the same nodes, its data is replaced in place, so that
+    /// implementations may keep anything else attached to it; otherwise it's
+    /// removed, and the new edge added. Like `add_edge`, nothing changes if
+    /// either node is missing. Returns whether the edge existed.
+    fn upsert_edge(
+        &mut self,
+        id: Id<Self::Edge>,
+        from: &Id<Self::Node>,
+        to: &Id<Self::Node>,
+        data: Data<Self::Edge>,
+    ) -> bool
+    where
+        Self: Sized,
+        Id<Self::Node>: PartialEq,
+        Id<Self::Edge>: Clone,
+    {
+        if self.get_node(from).is_none() || self.get_node(to).is_none() {
+            return self.get_edge(&id).is_some();
+        }
+        let same_nodes = match self.get_edge(&id) {
+            Some(e) => e.source() == from && e.target() == to,
+            None => {
+                self.add_edge(id, from, to, data);
+                return false;
+            }
+        };
+        if same_nodes {
+            if let Some(d) = self.edge_data_mut(&id) {
+                *d = data;
+            }
+        } else {
+            self.remove_edge(id.clone());
+            self.add_edge(id, from, to, data);
+        }
+        true
+    }
+
+    /// Mutable iterator over nodes. Implementations tracking when objects
+    /// are updated, as with `node_data_mut`, count every node yielded as
+    /// updated.
+    fn nodes_mut(&mut self) -> NodesMut<'_, Self::Node>;
+
+    /// Mutable iterator over all the edges, to modify their data. Every edge
+    /// yielded counts as updated, as with `nodes_mut`.
+    fn edges_mut(&mut self) -> EdgesMut<'_, Self::Edge>;
+
+    /// Keep only the nodes for which `keep` returns `true`, removing the
+    /// others along with their edges.
+    ///
+    /// By default, the ids of the nodes to remove are collected, then the
+    /// nodes removed one at a time.
+    fn retain_nodes<F>(&mut self, mut keep: F)
+    where
+        Self: Sized,
+        F: FnMut(&Self::Node) -> bool,
+        Id<Self::Node>: Clone,
+    {
+        let removed: Vec<Id<Self::Node>> = self
+            .nodes()
+            .filter(|n| !keep(n))
+            .map(|n| n.id().clone())
+            .collect();
+        for id in removed {
+            self.remove_node(id);
+        }
+    }
+
+    /// Remove every node and edge.
+    ///
+    /// By default, the nodes are removed one at a time, with `retain_nodes`.
+    fn clear(&mut self)
+    where
+        Self: Sized,
+        Id<Self::Node>: Clone,
+    {
+        self.retain_nodes(|_| false);
+    }
+
+    /// Keep only the edges for which `keep` returns `true`.
+    ///
+    /// By default, the ids of the edges to remove are collected, then the
+    /// edges removed one at a time.
+    fn retain_edges<F>(&mut self, mut keep: F)
+    where
+        Self: Sized,
+        F: FnMut(&Self::Edge) -> bool,
+        Id<Self::Edge>: Clone,
+    {
+        let removed: Vec<Id<Self::Edge>> = self
+            .nodes()
+            .flat_map(|n| self.edges_directed(n.id(), Direction::Outgoing))
+            .filter_map(|e| self.get_edge(e.id))
+            .filter(|e| !keep(e))
+            .map(|e| e.id().clone())
+            .collect();
+        for id in removed {
+            self.remove_edge(id);
+        }
+    }
+
+    /// Change the id of a node, keeping its data and edges. Edge ids are
+    /// preserved. Returns `false`, leaving the graph untouched, if `old`
+    /// doesn't exist or `new` already does.
+    ///
+    /// By default, the node and its edges are removed, then added back.
+    fn relabel_node(&mut self, old: &Id<Self::Node>, new: Id<Self::Node>) -> bool
+    where
+        Self: Sized,
+        Id<Self::Node>: Clone + PartialEq,
+        Id<Self::Edge>: Clone,
+        Data<Self::Node>: Clone,
+        Data<Self::Edge>: Clone,
+    {
+        if self.get_node(&new).is_some() {
+            return false;
+        }
+        let (data, edges) = match transform::take_node(self, old) {
+            Some(taken) => taken,
+            None => return false,
+        };
+        self.add_node(new.clone(), data);
+
+        for (id, from, to, data) in edges {
+            let from = if from == *old { new.clone() } else { from };
+            let to = if to == *old { new.clone() } else { to };
+            self.add_edge(id, &from, &to, data);
+        }
+        true
+    }
 }
 
 /// A graph with read-only access to edge and node data.
-pub trait GraphDataReader: Graph {
-    /// Return an immutable reference to an edge's data.
-    fn edge_data(&self, id: &Id<Self::Edge>) -> Option<&Data<Self::Edge>>;
+///
+/// Data access is part of `Graph` itself, so this trait is implemented for
+/// every graph. It is kept so that bounds can keep naming it.
+pub trait GraphDataReader: Graph {}
 
-    /// Return an immutable reference to a node's data.
-    fn node_data(&self, id: &Id<Self::Node>) -> Option<&Data<Self::Node>>;
-}
+impl<G: Graph> GraphDataReader for G {}
 
 /// A graph with mutable access to edge and node data.
 pub trait GraphDataWriter: Graph {
@@ -122,21 +701,84 @@ pub trait GraphDataWriter: Graph {
 
     /// Return a mutable reference to a node's data, to annotate the node.
     fn node_data_mut(&mut self, id: &Id<Self::Node>) -> Option<&mut Data<Self::Node>>;
+
+    /// Set the weight of an edge, without knowing the layout of its data.
+    /// Returns `false` if the edge doesn't exist.
+    fn set_edge_weight(&mut self, id: &Id<Self::Edge>, weight: Self::Weight) -> bool
+    where
+        Data<Self::Edge>: HasWeight<Self::Weight>,
+    {
+        match self.edge_data_mut(id) {
+            Some(data) => {
+                data.set_weight(weight);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Edge data carrying a weight.
+pub trait HasWeight<W> {
+    /// The weight of the edge.
+    fn weight(&self) -> W;
+
+    /// Set the weight of the edge.
+    fn set_weight(&mut self, weight: W);
 }
 
 /// An annotator for graphs.
+///
+/// Annotators may buffer annotations, eg. to write them to storage in
+/// batches: annotations are only guaranteed to be written once `flush`
+/// succeeds. Algorithms don't flush, it's up to their caller once the
+/// execution is over. See `annotators::BufferedAnnotator` to batch the
+/// annotations given to an annotator.
 pub trait GraphAnnotator {
     type Annotation;
 
+    /// A failure to write annotations.
+    type Error;
+
     /// Annotate the graph with some data. This is left intentionally abstract,
     /// for the implementation to decide whether to expose a key/value like
     /// interface with eg. `Annotation = (Key, Val)` or a batched interface
     /// like `Annotation = Vec<(Key, Val)>`.
     fn annotate_graph(&mut self, note: Self::Annotation);
+
+    /// Write the buffered annotations. Does nothing by default.
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// An annotator whose annotations only become visible once committed, so
+/// that a failed or cancelled execution leaves no partial annotations
+/// behind. See `annotators::Transaction` to make any annotator transactional,
+/// and `GraphAlgorithm::execute_atomically`.
+pub trait TransactionalAnnotator: GraphAnnotator {
+    /// Start a transaction: annotations are pending until committed.
+    fn begin(&mut self);
+
+    /// Make the pending annotations visible, and end the transaction.
+    fn commit(&mut self);
+
+    /// Discard the pending annotations, and end the transaction.
+    fn rollback(&mut self);
+}
+
+/// Collects annotations, in order.
+impl<T> GraphAnnotator for alloc::vec::Vec<T> {
+    type Annotation = T;
+    type Error = core::convert::Infallible;
+
+    fn annotate_graph(&mut self, note: T) {
+        self.push(note);
+    }
 }
 
 /// A read-only graph of nodes and edges.
-pub trait Graph: Default {
+pub trait Graph {
     /// A graph node.
     type Node: Node<Self::NodeData>;
 
@@ -146,8 +788,8 @@ pub trait Graph: Default {
     /// Data stored in graph nodes.
     type NodeData;
 
-    /// Data stored in graph edges.
-    type EdgeData;
+    /// Data stored in graph edges, including their weight.
+    type EdgeData: HasWeight<Self::Weight>;
 
     /// An edge weight.
     type Weight;
@@ -158,24 +800,165 @@ pub trait Graph: Default {
     /// Get an edge.
     fn get_edge(&self, id: &Id<Self::Edge>) -> Option<&Self::Edge>;
 
+    /// Return an immutable reference to a node's data.
+    fn node_data(&self, id: &Id<Self::Node>) -> Option<&Data<Self::Node>> {
+        self.get_node(id).map(|n| n.data())
+    }
+
+    /// Return an immutable reference to an edge's data.
+    fn edge_data(&self, id: &Id<Self::Edge>) -> Option<&Data<Self::Edge>> {
+        self.get_edge(id).map(|e| e.data())
+    }
+
     /// Iterator over nodes.
-    fn nodes(&self) -> Nodes<Self::Node>;
+    fn nodes(&self) -> Nodes<'_, Self::Node>;
 
-    /// Get a node's neighbors.
-    fn neighbors(&self, node: &Id<Self::Node>) -> Nodes<Self::Node>;
+    /// Get a node's neighbors. A neighbor is listed once per edge joining it
+    /// to `node`, so it may be listed several times in a multigraph, see
+    /// `GraphExt::distinct_neighbors`.
+    fn neighbors(&self, node: &Id<Self::Node>) -> Nodes<'_, Self::Node>;
 
     /// Get a node's inbound and outbound edges.
-    fn edges(&self, node: &Id<Self::Node>) -> Edges<Self::Edge>;
+    fn edges(&self, node: &Id<Self::Node>) -> Edges<'_, Self::Edge>;
 
     /// Get a node's *directed* edges by passing a `Direction` as input.
     /// This is a slightly more specialised version of `edges`.
-    fn edges_directed(
+    fn edges_directed(&self, node: &Id<Self::Node>, dir: Direction) -> EdgeRefsOf<'_, Self>;
+
+    /// The number of edges of every type of a node, in the given direction.
+    /// Types without edges are left out. Implementations should override it
+    /// to count edges without listing them.
+    fn degree_by_edge_type(
         &self,
         node: &Id<Self::Node>,
         dir: Direction,
-    ) -> EdgeRefs<Id<Self::Node>, Id<Self::Edge>>;
+    ) -> BTreeMap<EdgeTypeTag, usize> {
+        let mut degrees = BTreeMap::new();
+        for e in self.edges_directed(node, dir) {
+            if let Some(edge) = self.get_edge(e.id) {
+                *degrees.entry(edge.edge_type().to_tag()).or_insert(0) += 1;
+            }
+        }
+        degrees
+    }
 }
 
+/// Convenience methods available on every graph, built on top of the core
+/// `Graph` methods.
+pub trait GraphExt: Graph
+where
+    Id<Self::Node>: PartialEq,
+{
+    /// The nodes reached by an outgoing edge of `node`.
+    fn out_neighbors(&self, node: &Id<Self::Node>) -> Nodes<'_, Self::Node> {
+        self.directed_neighbors(node, Direction::Outgoing)
+    }
+
+    /// The nodes with an edge towards `node`.
+    fn in_neighbors(&self, node: &Id<Self::Node>) -> Nodes<'_, Self::Node> {
+        self.directed_neighbors(node, Direction::Incoming)
+    }
+
+    /// The neighbors of `node`, following edges in the given direction, once
+    /// per edge.
+    fn directed_neighbors(&self, node: &Id<Self::Node>, dir: Direction) -> Nodes<'_, Self::Node> {
+        let nodes: Vec<&Self::Node> = self
+            .edges_directed(node, dir)
+            .into_iter()
+            .filter_map(|e| self.get_node(if e.from == node { e.to } else { e.from }))
+            .collect();
+
+        Nodes {
+            range: nodes.into_iter(),
+        }
+    }
+
+    /// The neighbors of `node`, following edges in the given direction, each
+    /// listed exactly once however many edges join it to `node`, in the order
+    /// they're first reached.
+    fn distinct_neighbors(&self, node: &Id<Self::Node>, dir: Direction) -> Nodes<'_, Self::Node>
+    where
+        Id<Self::Node>: Ord,
+    {
+        let mut seen = BTreeSet::new();
+        let nodes: Vec<&Self::Node> = self
+            .edges_directed(node, dir)
+            .into_iter()
+            .map(|e| if e.from == node { e.to } else { e.from })
+            .filter(|id| seen.insert(*id))
+            .filter_map(|id| self.get_node(id))
+            .collect();
+
+        Nodes {
+            range: nodes.into_iter(),
+        }
+    }
+
+    /// The neighbors of `node`, following edges in the given direction, with
+    /// the weight of the edge leading to them, by decreasing weight. Ties are
+    /// broken by increasing neighbor id, then edge id, so that the order is
+    /// deterministic. Neighbors are listed once per edge, and those with a
+    /// weight not comparable to itself, eg. NaN, are listed last.
+    fn neighbors_by_weight(
+        &self,
+        node: &Id<Self::Node>,
+        dir: Direction,
+    ) -> Vec<(&Self::Node, Self::Weight)>
+    where
+        Self::Weight: PartialOrd,
+        Id<Self::Node>: Ord,
+        Id<Self::Edge>: Ord,
+    {
+        let mut weighted: Vec<_> = self
+            .edges_directed(node, dir)
+            .into_iter()
+            .filter_map(|e| {
+                let neighbor = self.get_node(if e.from == node { e.to } else { e.from })?;
+                Some((neighbor, e.id, e.weight()))
+            })
+            .collect();
+        // Ordering NaN-like weights apart keeps the comparison a total order.
+        let comparable = |w: &Self::Weight| w.partial_cmp(w).is_some();
+        weighted.sort_by(|(n1, e1, w1), (n2, e2, w2)| {
+            comparable(w2)
+                .cmp(&comparable(w1))
+                .then_with(|| w2.partial_cmp(w1).unwrap_or(core::cmp::Ordering::Equal))
+                .then_with(|| n1.id().cmp(n2.id()))
+                .then_with(|| e1.cmp(e2))
+        });
+        weighted.into_iter().map(|(n, _, w)| (n, w)).collect()
+    }
+
+    /// An edge going from `from` to `to`, if any. If there are several, which
+    /// one is returned is unspecified.
+    fn edge_between(&self, from: &Id<Self::Node>, to: &Id<Self::Node>) -> Option<&Self::Edge> {
+        self.edges_directed(from, Direction::Outgoing)
+            .into_iter()
+            .find(|e| e.to == to)
+            .and_then(|e| self.get_edge(e.id))
+    }
+
+    /// Whether there is an edge going from `from` to `to`.
+    fn contains_edge_between(&self, from: &Id<Self::Node>, to: &Id<Self::Node>) -> bool {
+        self.edges_directed(from, Direction::Outgoing)
+            .into_iter()
+            .any(|e| e.to == to)
+    }
+
+    /// The ids of all nodes. Unlike `dyn_graph::DynGraph::node_ids`, the ids
+    /// aren't erased.
+    fn all_node_ids(&self) -> Vec<&Id<Self::Node>> {
+        self.nodes().map(|n| n.id()).collect()
+    }
+
+    /// Whether the graph has no nodes.
+    fn is_empty(&self) -> bool {
+        self.nodes().next().is_none()
+    }
+}
+
+impl<G: Graph> GraphExt for G where Id<G::Node>: PartialEq {}
+
 /// A graph algorithm over a graph.
 pub trait GraphAlgorithm<G, A>
 where
@@ -195,7 +978,7 @@ where
     type Error;
 
     /// A seed suitable for an RNG.
-    type RngSeed;
+    type RngSeed: rng::AlgorithmSeed;
 
     /// The type of annotation the algorithm will make
     /// on the graph.
@@ -211,11 +994,113 @@ where
         annotator: &mut A,
         seed: Self::RngSeed,
     ) -> Result<Self::Output, Self::Error>;
+
+    /// Like `execute`, reporting progress and statistics to the given sink.
+    /// By default, only the elapsed time is reported, at the end of the
+    /// execution, where there is a clock.
+    fn execute_with_progress(
+        &self,
+        context: &mut Self::Context,
+        graph: &G,
+        annotator: &mut A,
+        seed: Self::RngSeed,
+        progress: &mut dyn progress::ProgressSink,
+    ) -> Result<Self::Output, Self::Error> {
+        let start = progress::Stopwatch::start(progress);
+        let result = self.execute(context, graph, annotator, seed);
+
+        progress.on_stats(&progress::ExecutionStats {
+            elapsed: start.elapsed(),
+            ..Default::default()
+        });
+        result
+    }
+
+    /// Like `execute`, giving up as soon as possible once the token is
+    /// cancelled. Algorithms that support cancellation return an error in that
+    /// case. By default, the token is ignored.
+    fn execute_with(
+        &self,
+        context: &mut Self::Context,
+        graph: &G,
+        annotator: &mut A,
+        seed: Self::RngSeed,
+        _token: &cancel::CancellationToken,
+    ) -> Result<Self::Output, Self::Error> {
+        self.execute(context, graph, annotator, seed)
+    }
+
+    /// Like `execute_with`, within a transaction of the annotator: the
+    /// annotations are committed if the execution succeeds, and rolled back
+    /// if it fails or is cancelled.
+    fn execute_atomically(
+        &self,
+        context: &mut Self::Context,
+        graph: &G,
+        annotator: &mut A,
+        seed: Self::RngSeed,
+        token: &cancel::CancellationToken,
+    ) -> Result<Self::Output, Self::Error>
+    where
+        A: TransactionalAnnotator,
+    {
+        annotator.begin();
+        let result = self.execute_with(context, graph, annotator, seed, token);
+        match result {
+            Ok(_) => annotator.commit(),
+            Err(_) => annotator.rollback(),
+        }
+        result
+    }
+}
+
+/// The result of `DryRun::execute_dry_run`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DryRunOutput<O, N> {
+    /// The output of the execution.
+    pub output: O,
+    /// The annotations the execution would have made, in order.
+    pub annotations: Vec<N>,
+}
+
+/// Running an algorithm without side effects, to preview its annotations,
+/// eg. the rank changes a checkpoint would cause. Available on every
+/// algorithm accepting any annotator.
+pub trait DryRun<G, N>: GraphAlgorithm<G, Vec<N>, Annotation = N>
+where
+    G: Graph,
+{
+    /// Execute the algorithm over a copy of `context`, collecting its
+    /// annotations instead of writing them.
+    fn execute_dry_run(
+        &self,
+        context: &Self::Context,
+        graph: &G,
+        seed: Self::RngSeed,
+    ) -> Result<DryRunOutput<Self::Output, N>, Self::Error>
+    where
+        Self::Context: Clone,
+    {
+        let mut annotations = Vec::new();
+        let output = self.execute(&mut context.clone(), graph, &mut annotations, seed)?;
+
+        Ok(DryRunOutput {
+            output,
+            annotations,
+        })
+    }
+}
+
+impl<G, N, Alg> DryRun<G, N> for Alg
+where
+    G: Graph,
+    Alg: GraphAlgorithm<G, Vec<N>, Annotation = N>,
+{
 }
 
 /// Iterator over edges.
 pub struct Edges<'a, E: 'a> {
-    pub range: std::vec::IntoIter<&'a E>,
+    pub range: alloc::vec::IntoIter<&'a E>,
 }
 
 impl<'a, N: 'a> Iterator for Edges<'a, N> {
@@ -228,12 +1113,25 @@ impl<'a, N: 'a> Iterator for Edges<'a, N> {
 
 /// Iterator over nodes.
 pub struct Nodes<'a, N: 'a> {
-    pub range: std::vec::IntoIter<&'a N>,
+    pub range: alloc::vec::IntoIter<&'a N>,
 }
 
 /// Iterator over mutable nodes.
 pub struct NodesMut<'a, N: 'a> {
-    pub range: std::vec::IntoIter<&'a mut N>,
+    pub range: alloc::vec::IntoIter<&'a mut N>,
+}
+
+/// Iterator over mutable edges.
+pub struct EdgesMut<'a, E: 'a> {
+    pub range: alloc::vec::IntoIter<&'a mut E>,
+}
+
+impl<'a, E: 'a> Iterator for EdgesMut<'a, E> {
+    type Item = &'a mut E;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.range.next()
+    }
 }
 
 impl<'a, N: 'a> Iterator for Nodes<'a, N> {
@@ -252,23 +1150,72 @@ impl<'a, N: 'a> Iterator for NodesMut<'a, N> {
     }
 }
 
+/// A graph which can be decomposed into its nodes and edges, without cloning
+/// them.
+pub trait IntoGraphParts: Graph + Sized {
+    /// Consume the graph, returning its nodes and edges.
+    fn into_parts(self) -> (IntoNodes<Self::Node>, IntoEdges<Self::Edge>);
+
+    /// Consume the graph, returning its nodes.
+    fn into_nodes(self) -> IntoNodes<Self::Node> {
+        self.into_parts().0
+    }
+
+    /// Consume the graph, returning its edges.
+    fn into_edges(self) -> IntoEdges<Self::Edge> {
+        self.into_parts().1
+    }
+}
+
+/// Iterator over owned nodes.
+pub struct IntoNodes<N> {
+    pub range: alloc::vec::IntoIter<N>,
+}
+
+impl<N> Iterator for IntoNodes<N> {
+    type Item = N;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.range.next()
+    }
+}
+
+/// Iterator over owned edges.
+pub struct IntoEdges<E> {
+    pub range: alloc::vec::IntoIter<E>,
+}
+
+impl<E> Iterator for IntoEdges<E> {
+    type Item = E;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.range.next()
+    }
+}
+
 /// Iterator over edge _references_, which keep track of the source and
-/// target.
+/// target, along with the edge's data.
 #[derive(Debug)]
-pub struct EdgeRef<'a, NodeId, EdgeId> {
+pub struct EdgeRef<'a, NodeId, EdgeId, EData> {
     pub from: &'a NodeId,
     pub to: &'a NodeId,
     pub id: &'a EdgeId,
+    pub data: &'a EData,
+}
 
-    // TODO(adinapoli) Here we are using the *concrete* `EdgeType`, because
-    // it's handy to get hold of the type of the edge for library consumers,
-    // without having to assume anything about the underlying structure of the
-    // edge's data. In this design we cannot use `G::EdgeData` directly, because
-    // we don't have a `G` in scope. A perhaps better design would be either to
-    // make `EdgeRef` polymorphic in `G` and use `Id<G::Node>` and `Id<G::Edge>`
-    // and have `edge_data: &'a G::EdgeData` or simply parameterise the `EdgeRef`
-    // from an additional `EdgeData/EdgeType` parameter.
-    pub edge_type: &'a EdgeType,
+impl<NodeId, EdgeId, EData> EdgeRef<'_, NodeId, EdgeId, EData> {
+    /// The weight of the edge, read from its data without looking the edge
+    /// up.
+    pub fn weight<W>(&self) -> W
+    where
+        EData: HasWeight<W>,
+    {
+        self.data.weight()
+    }
 }
 
-pub type EdgeRefs<'a, N, E> = Vec<EdgeRef<'a, N, E>>;
+pub type EdgeRefs<'a, N, E, D> = Vec<EdgeRef<'a, N, E, D>>;
+
+/// The `EdgeRefs` of a graph.
+pub type EdgeRefsOf<'a, G> =
+    EdgeRefs<'a, Id<<G as Graph>::Node>, Id<<G as Graph>::Edge>, Data<<G as Graph>::Edge>>;
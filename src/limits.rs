@@ -0,0 +1,365 @@
+//! Enforcing resource limits on graphs fed from untrusted inputs.
+//!
+//! Checkpoints are submitted by users, so a graph layer applying them must
+//! defend itself against checkpoints crafted to exhaust its resources.
+//! `Limited` wraps a graph and rejects the mutations exceeding its `Limits`.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
+
+use super::ledger::{Checkpoint, LedgerGraph};
+use super::types::{EdgeData, EdgeType, EdgeTypeTag};
+use super::{
+    Data, Direction, EdgeRefsOf, Edges, EdgesMut, Graph, GraphDataWriter, GraphWriter, Id, Nodes,
+    NodesMut,
+};
+
+/// Resource limits. A limit of `None` is not enforced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Limits {
+    /// Maximum number of nodes in the graph.
+    pub max_nodes: Option<usize>,
+    /// Maximum number of edges of any node, inbound and outbound.
+    pub max_edges_per_node: Option<usize>,
+    /// Maximum number of edges a checkpoint can add.
+    pub max_edges_per_checkpoint: Option<usize>,
+    /// Maximum contribution count of any edge.
+    pub max_edge_contributions: Option<u32>,
+}
+
+/// A mutation rejected for exceeding a limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitError {
+    /// The graph already has the maximum number of nodes.
+    TooManyNodes { max: usize },
+    /// A node already has the maximum number of edges.
+    TooManyEdges { max: usize },
+    /// A checkpoint adds more edges than allowed.
+    CheckpointTooLarge { edges: usize, max: usize },
+    /// An edge carries more contributions than allowed.
+    TooManyContributions { contributions: u32, max: u32 },
+}
+
+impl fmt::Display for LimitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LimitError::TooManyNodes { max } => write!(f, "graph is limited to {} nodes", max),
+            LimitError::TooManyEdges { max } => {
+                write!(f, "nodes are limited to {} edges", max)
+            }
+            LimitError::CheckpointTooLarge { edges, max } => {
+                write!(f, "checkpoint adds {} edges, the maximum is {}", edges, max)
+            }
+            LimitError::TooManyContributions { contributions, max } => write!(
+                f,
+                "edge carries {} contributions, the maximum is {}",
+                contributions, max
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LimitError {}
+
+/// Edge data carrying a contribution count.
+pub trait EdgeContributions {
+    /// The contributions, or amount, carried by the edge.
+    fn contributions(&self) -> u32;
+}
+
+impl EdgeContributions for EdgeType {
+    fn contributions(&self) -> u32 {
+        self.value()
+    }
+}
+
+impl<W> EdgeContributions for EdgeData<W> {
+    fn contributions(&self) -> u32 {
+        self.edge_type.value()
+    }
+}
+
+/// A graph wrapper rejecting the mutations exceeding its limits.
+///
+/// The `try_` methods return the limit exceeded. `GraphWriter` methods can't
+/// fail, so the mutations they reject are dropped and the first error is
+/// kept, to be returned by `take_error`. Limits are only checked when nodes
+/// and edges are added: data modified through `GraphDataWriter` or
+/// `nodes_mut` isn't checked.
+///
+/// Checkpoints applied through `ledger::CheckpointApplier` are checked as a
+/// whole with `check_checkpoint` first, and refused without being applied
+/// if they'd exceed the limits.
+pub struct Limited<G: Graph> {
+    graph: G,
+    limits: Limits,
+    nodes: usize,
+    error: Option<LimitError>,
+}
+
+impl<G: Graph> Limited<G> {
+    /// Enforce `limits` on the mutations of `graph` made from now on. Nodes
+    /// and edges already in the graph are kept, even beyond the limits.
+    pub fn new(graph: G, limits: Limits) -> Self {
+        Limited {
+            nodes: graph.nodes().count(),
+            graph,
+            limits,
+            error: None,
+        }
+    }
+
+    /// The limits enforced.
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// The wrapped graph.
+    pub fn graph(&self) -> &G {
+        &self.graph
+    }
+
+    /// Unwrap the graph.
+    pub fn into_inner(self) -> G {
+        self.graph
+    }
+
+    /// The first mutation rejected by a `GraphWriter` method since the last
+    /// call, if any.
+    pub fn take_error(&mut self) -> Option<LimitError> {
+        self.error.take()
+    }
+
+    /// Check that a node can be added. Replacing a node is always allowed.
+    pub fn check_node(&self, id: &Id<G::Node>) -> Result<(), LimitError> {
+        match self.limits.max_nodes {
+            Some(max) if self.nodes >= max && self.graph.get_node(id).is_none() => {
+                Err(LimitError::TooManyNodes { max })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that an edge can be added between `from` and `to`.
+    pub fn check_edge(
+        &self,
+        from: &Id<G::Node>,
+        to: &Id<G::Node>,
+        data: &Data<G::Edge>,
+    ) -> Result<(), LimitError>
+    where
+        Data<G::Edge>: EdgeContributions,
+    {
+        if let Some(max) = self.limits.max_edge_contributions {
+            let contributions = data.contributions();
+            if contributions > max {
+                return Err(LimitError::TooManyContributions { contributions, max });
+            }
+        }
+        if let Some(max) = self.limits.max_edges_per_node {
+            if [from, to]
+                .iter()
+                .any(|node| self.graph.edges(node).count() >= max)
+            {
+                return Err(LimitError::TooManyEdges { max });
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that a checkpoint can be applied without exceeding the limits,
+    /// counting the nodes and edges it would add.
+    pub fn check_checkpoint(&self, checkpoint: &Checkpoint<Id<G::Node>>) -> Result<(), LimitError>
+    where
+        Id<G::Node>: Eq + Hash,
+    {
+        let deps = checkpoint.deps.iter().filter(|d| d.is_added).count();
+        let edges = deps + 2 * checkpoint.contributions.len();
+
+        if let Some(max) = self.limits.max_edges_per_checkpoint {
+            if edges > max {
+                return Err(LimitError::CheckpointTooLarge { edges, max });
+            }
+        }
+        if let Some(max) = self.limits.max_edge_contributions {
+            if let Some(c) = checkpoint
+                .contributions
+                .iter()
+                .find(|c| c.contributions > max)
+            {
+                return Err(LimitError::TooManyContributions {
+                    contributions: c.contributions,
+                    max,
+                });
+            }
+        }
+        if let Some(max) = self.limits.max_nodes {
+            let new: HashSet<&Id<G::Node>> = std::iter::once(&checkpoint.project)
+                .chain(checkpoint.contributions.iter().map(|c| &c.node_id))
+                .filter(|id| self.graph.get_node(id).is_none())
+                .collect();
+            if self.nodes + new.len() > max {
+                return Err(LimitError::TooManyNodes { max });
+            }
+        }
+        if let Some(max) = self.limits.max_edges_per_node {
+            // Edges removed by the checkpoint aren't subtracted, so a
+            // checkpoint passing the check never has an edge rejected.
+            let mut added: HashMap<&Id<G::Node>, usize> = HashMap::new();
+            let project = &checkpoint.project;
+            let targets = checkpoint
+                .deps
+                .iter()
+                .filter(|d| d.is_added)
+                .map(|d| (&d.node_id, 1))
+                .chain(checkpoint.contributions.iter().map(|c| (&c.node_id, 2)));
+            for (node, edges) in targets {
+                *added.entry(project).or_insert(0) += edges;
+                *added.entry(node).or_insert(0) += edges;
+            }
+            for (node, edges) in added {
+                if self.graph.edges(node).count() + edges > max {
+                    return Err(LimitError::TooManyEdges { max });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<G: GraphWriter> Limited<G> {
+    /// Add a node, unless the graph is full.
+    pub fn try_add_node(&mut self, id: Id<G::Node>, data: Data<G::Node>) -> Result<(), LimitError> {
+        self.check_node(&id)?;
+        if self.graph.get_node(&id).is_none() {
+            self.nodes += 1;
+        }
+        self.graph.add_node(id, data);
+        Ok(())
+    }
+
+    /// Add an edge, unless it carries too many contributions or either node
+    /// has too many edges.
+    pub fn try_add_edge(
+        &mut self,
+        id: Id<G::Edge>,
+        from: &Id<G::Node>,
+        to: &Id<G::Node>,
+        data: Data<G::Edge>,
+    ) -> Result<(), LimitError>
+    where
+        Data<G::Edge>: EdgeContributions,
+    {
+        self.check_edge(from, to, &data)?;
+        self.graph.add_edge(id, from, to, data);
+        Ok(())
+    }
+
+    fn rejected(&mut self, result: Result<(), LimitError>) {
+        if let Err(err) = result {
+            trace_event!(error = %err, "rejected mutation");
+            self.error.get_or_insert(err);
+        }
+    }
+}
+
+impl<G: Graph> Graph for Limited<G> {
+    type Node = G::Node;
+    type Edge = G::Edge;
+    type NodeData = G::NodeData;
+    type EdgeData = G::EdgeData;
+    type Weight = G::Weight;
+
+    fn get_node(&self, id: &Id<G::Node>) -> Option<&G::Node> {
+        self.graph.get_node(id)
+    }
+
+    fn get_edge(&self, id: &Id<G::Edge>) -> Option<&G::Edge> {
+        self.graph.get_edge(id)
+    }
+
+    fn nodes(&self) -> Nodes<'_, G::Node> {
+        self.graph.nodes()
+    }
+
+    fn neighbors(&self, node: &Id<G::Node>) -> Nodes<'_, G::Node> {
+        self.graph.neighbors(node)
+    }
+
+    fn edges(&self, node: &Id<G::Node>) -> Edges<'_, G::Edge> {
+        self.graph.edges(node)
+    }
+
+    fn edges_directed(&self, node: &Id<G::Node>, dir: Direction) -> EdgeRefsOf<'_, G> {
+        self.graph.edges_directed(node, dir)
+    }
+
+    fn degree_by_edge_type(
+        &self,
+        node: &Id<G::Node>,
+        dir: Direction,
+    ) -> BTreeMap<EdgeTypeTag, usize> {
+        self.graph.degree_by_edge_type(node, dir)
+    }
+}
+
+impl<G> GraphWriter for Limited<G>
+where
+    G: GraphWriter,
+    Data<G::Edge>: EdgeContributions,
+{
+    fn add_node(&mut self, id: Id<G::Node>, data: Data<G::Node>) {
+        let result = self.try_add_node(id, data);
+        self.rejected(result);
+    }
+
+    fn remove_node(&mut self, id: Id<G::Node>) {
+        if self.graph.get_node(&id).is_some() {
+            self.nodes -= 1;
+        }
+        self.graph.remove_node(id);
+    }
+
+    fn add_edge(
+        &mut self,
+        id: Id<G::Edge>,
+        from: &Id<G::Node>,
+        to: &Id<G::Node>,
+        data: Data<G::Edge>,
+    ) {
+        let result = self.try_add_edge(id, from, to, data);
+        self.rejected(result);
+    }
+
+    fn remove_edge(&mut self, id: Id<G::Edge>) {
+        self.graph.remove_edge(id);
+    }
+
+    fn nodes_mut(&mut self) -> NodesMut<'_, G::Node> {
+        self.graph.nodes_mut()
+    }
+
+    fn edges_mut(&mut self) -> EdgesMut<'_, G::Edge> {
+        self.graph.edges_mut()
+    }
+}
+
+impl<G: GraphDataWriter> GraphDataWriter for Limited<G> {
+    fn edge_data_mut(&mut self, id: &Id<G::Edge>) -> Option<&mut Data<G::Edge>> {
+        self.graph.edge_data_mut(id)
+    }
+
+    fn node_data_mut(&mut self, id: &Id<G::Node>) -> Option<&mut Data<G::Node>> {
+        self.graph.node_data_mut(id)
+    }
+}
+
+impl<G: Graph> LedgerGraph for Limited<G>
+where
+    Id<G::Node>: Eq + Hash,
+{
+    fn check_limits(&self, checkpoint: &Checkpoint<Id<G::Node>>) -> Result<(), LimitError> {
+        self.check_checkpoint(checkpoint)
+    }
+}
@@ -0,0 +1,478 @@
+//! Ranks computed by power iteration over a sparse transition matrix.
+//!
+//! For graphs fitting in memory, iterating over the transition matrix is
+//! faster than simulating random walks, and deterministic, which makes it
+//! handy to cross-validate the results of walk-based algorithms.
+//!
+//! The iterations themselves can be offloaded to an accelerator, eg. a GPU,
+//! by implementing `AcceleratedRankBackend` over the arrays of the
+//! transition matrix.
+
+use num_traits::Float;
+use sprs::{CsMat, TriMat};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::algorithms::explain::{Flows, RankExplanation};
+use crate::asynchronous::{yield_now, AsyncError, AsyncGraphAlgorithm, AsyncGraphAnnotator};
+use crate::cancel::CancellationToken;
+use crate::progress::{ExecutionStats, NoProgress, ProgressSink, Stopwatch};
+use crate::types::{AlgorithmError, HyperParameters, NodeRank, NodeType};
+use crate::{Direction, Graph, GraphAlgorithm, GraphAnnotator, GraphObject, Id, Node};
+
+/// The transition matrix of a graph, along with the node of every row and
+/// column.
+pub struct TransitionMatrix<'a, NodeId> {
+    /// The nodes, in the order of the rows and columns of the matrix.
+    pub nodes: Vec<&'a NodeId>,
+    /// The probability to go from node `i` to node `j` at row `i` and
+    /// column `j`, in compressed sparse row format. The rows of nodes
+    /// without outgoing edges are empty, the others sum to `1.0`.
+    pub matrix: CsMat<f64>,
+}
+
+/// Build the transition matrix of a graph, where a walk follows an outgoing
+/// edge with a probability proportional to its weight. Edges without a
+/// positive weight are ignored.
+///
+/// Nodes are numbered by increasing id, and the weights of their edges are
+/// summed by increasing target, so that the matrix doesn't depend on the
+/// iteration order of the backend.
+pub fn transition_matrix<G>(graph: &G) -> TransitionMatrix<'_, Id<G::Node>>
+where
+    G: Graph,
+    G::Weight: Into<f64>,
+    Id<G::Node>: Ord + Hash,
+{
+    let mut nodes: Vec<&Id<G::Node>> = graph.nodes().map(|n| n.id()).collect();
+    nodes.sort_unstable();
+    let index: HashMap<&Id<G::Node>, usize> =
+        nodes.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+    let n = nodes.len();
+    let mut triplets = TriMat::new((n, n));
+
+    for (i, id) in nodes.iter().enumerate() {
+        let mut out: Vec<(usize, f64)> = graph
+            .edges_directed(id, Direction::Outgoing)
+            .into_iter()
+            .filter_map(|e| Some((*index.get(e.to)?, e.weight().into())))
+            .filter(|(_, w)| *w > 0.0)
+            .collect();
+        out.sort_unstable_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+        let total: f64 = out.iter().map(|(_, w)| w).sum();
+
+        for (j, w) in out {
+            triplets.add_triplet(i, j, w / total);
+        }
+    }
+    TransitionMatrix {
+        nodes,
+        matrix: triplets.to_csr(),
+    }
+}
+
+/// The transition matrix of a graph as compressed sparse row arrays, along
+/// with the parameters of the iterations, as handed to an
+/// `AcceleratedRankBackend`. Nodes are numbered by their row.
+#[derive(Clone, Copy, Debug)]
+pub struct RankProblem<'a> {
+    /// The outgoing edges of node `i` are at `offsets[i]..offsets[i + 1]`
+    /// in `targets` and `weights`.
+    pub offsets: &'a [usize],
+    /// The target node of every edge.
+    pub targets: &'a [usize],
+    /// The transition probability of every edge. The probabilities of the
+    /// edges of a node sum to `1.0`.
+    pub weights: &'a [f64],
+    /// The damping factor of every node.
+    pub damping: &'a [f64],
+    /// The maximum number of iterations.
+    pub max_iterations: usize,
+    /// The iterations stop once the ranks change by less than this, in L1
+    /// norm.
+    pub tolerance: f64,
+}
+
+impl<'a> RankProblem<'a> {
+    /// The number of nodes.
+    pub fn nodes(&self) -> usize {
+        self.offsets.len().saturating_sub(1)
+    }
+}
+
+/// The ranks computed by an `AcceleratedRankBackend`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RankVector {
+    /// The rank of every node.
+    pub ranks: Vec<f64>,
+    /// The number of iterations run.
+    pub iterations: usize,
+}
+
+/// A backend running the iterations of `PowerIteration`, eg. on a GPU.
+pub trait AcceleratedRankBackend {
+    /// Compute the ranks of the nodes of `problem`, as described by
+    /// `PowerIteration`: starting from uniform ranks, iterate until they
+    /// change by at most `tolerance`, or fail with
+    /// `AlgorithmError::NotConverged` after `max_iterations`. Backends
+    /// should check `token`, and report to `progress`, between iterations.
+    fn rank(
+        &self,
+        problem: &RankProblem<'_>,
+        progress: &mut dyn ProgressSink,
+        token: &CancellationToken,
+    ) -> Result<RankVector, AlgorithmError>;
+}
+
+/// The default backend, iterating on the CPU.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CpuBackend;
+
+impl AcceleratedRankBackend for CpuBackend {
+    fn rank(
+        &self,
+        problem: &RankProblem<'_>,
+        progress: &mut dyn ProgressSink,
+        token: &CancellationToken,
+    ) -> Result<RankVector, AlgorithmError> {
+        let n = problem.nodes();
+        let mut ranks = vec![1.0 / n as f64; n];
+        let mut iterations = 0;
+
+        loop {
+            token.check()?;
+            iterations += 1;
+
+            let (next, residual) = iterate(problem, &ranks);
+            ranks = next;
+            progress.on_progress(iterations as u64, Some(problem.max_iterations as u64));
+
+            if converged(problem, iterations, residual)? {
+                return Ok(RankVector { ranks, iterations });
+            }
+        }
+    }
+}
+
+/// One iteration of `CpuBackend`: the ranks following `ranks`, and their
+/// distance to `ranks`, in L1 norm.
+fn iterate(problem: &RankProblem<'_>, ranks: &[f64]) -> (Vec<f64>, f64) {
+    let n = problem.nodes();
+    let mut next = vec![0.0; n];
+    let mut continued = 0.0;
+    for (i, edges) in problem.offsets.windows(2).enumerate() {
+        if edges[0] == edges[1] {
+            continue;
+        }
+        let mass = ranks[i] * problem.damping[i];
+        continued += mass;
+        let targets = &problem.targets[edges[0]..edges[1]];
+        let weights = &problem.weights[edges[0]..edges[1]];
+        for (j, p) in targets.iter().zip(weights) {
+            next[*j] += mass * p;
+        }
+    }
+    let restart = (1.0 - continued) / n as f64;
+    for r in next.iter_mut() {
+        *r += restart;
+    }
+
+    let residual: f64 = ranks.iter().zip(&next).map(|(a, b)| (a - b).abs()).sum();
+    (next, residual)
+}
+
+/// Whether the iterations converged after `iterations` with the given
+/// residual, failing once `max_iterations` are run without converging.
+fn converged(
+    problem: &RankProblem<'_>,
+    iterations: usize,
+    residual: f64,
+) -> Result<bool, AlgorithmError> {
+    if residual <= problem.tolerance {
+        Ok(true)
+    } else if iterations >= problem.max_iterations {
+        Err(AlgorithmError::NotConverged {
+            iterations: iterations as u64,
+            residual,
+        })
+    } else {
+        Ok(false)
+    }
+}
+
+/// Osrank computed by power iteration, as a `GraphAlgorithm`, annotating
+/// every node with its rank.
+///
+/// A walk on a project (resp. user) node continues with probability
+/// `damping_factors.project` (resp. `account`) along the transition matrix,
+/// and otherwise restarts from a node picked uniformly. Walks on nodes
+/// without outgoing edges always restart. Ranks are the stationary
+/// distribution of such walks, and sum to `1.0`.
+///
+/// The iterations are delegated to the backend `B`, on the CPU by default.
+#[derive(Clone, Debug)]
+pub struct PowerIteration<W, B = CpuBackend> {
+    /// The hyperparameters, of which `damping_factors` are used.
+    pub hyperparams: HyperParameters<W>,
+    /// The maximum number of iterations.
+    pub max_iterations: usize,
+    /// The iterations stop once the ranks change by less than this, in L1
+    /// norm.
+    pub tolerance: f64,
+    /// The backend running the iterations.
+    pub backend: B,
+}
+
+impl<W> PowerIteration<W> {
+    /// The default maximum number of iterations.
+    pub const DEFAULT_MAX_ITERATIONS: usize = 100;
+
+    /// The default tolerance.
+    pub const DEFAULT_TOLERANCE: f64 = 1e-10;
+
+    /// Create an instance with the default number of iterations and
+    /// tolerance, iterating on the CPU.
+    pub fn new(hyperparams: HyperParameters<W>) -> Self {
+        PowerIteration {
+            hyperparams,
+            max_iterations: Self::DEFAULT_MAX_ITERATIONS,
+            tolerance: Self::DEFAULT_TOLERANCE,
+            backend: CpuBackend,
+        }
+    }
+}
+
+impl<W, B> PowerIteration<W, B> {
+    /// Delegate the iterations to `backend`.
+    pub fn backend<C: AcceleratedRankBackend>(self, backend: C) -> PowerIteration<W, C> {
+        PowerIteration {
+            hyperparams: self.hyperparams,
+            max_iterations: self.max_iterations,
+            tolerance: self.tolerance,
+            backend,
+        }
+    }
+}
+
+/// The ranks of the nodes of a graph.
+type Ranks<G> = HashMap<Id<<G as Graph>::Node>, f64>;
+
+impl<G, A, W, B> GraphAlgorithm<G, A> for PowerIteration<W, B>
+where
+    G: Graph,
+    G::Weight: Into<f64>,
+    Id<G::Node>: Clone + Ord + Hash,
+    A: GraphAnnotator<Annotation = (Id<G::Node>, NodeRank<W>)>,
+    W: Float,
+    B: AcceleratedRankBackend,
+{
+    type Context = ();
+    /// The rank of every node.
+    type Output = Ranks<G>;
+    type Error = AlgorithmError;
+    /// The algorithm is deterministic and ignores its seed.
+    type RngSeed = u64;
+    type Annotation = (Id<G::Node>, NodeRank<W>);
+
+    fn execute(
+        &self,
+        _context: &mut (),
+        graph: &G,
+        annotator: &mut A,
+        _seed: u64,
+    ) -> Result<Self::Output, Self::Error> {
+        self.run(graph, annotator, &mut NoProgress, &CancellationToken::new())
+    }
+
+    fn execute_with_progress(
+        &self,
+        _context: &mut (),
+        graph: &G,
+        annotator: &mut A,
+        _seed: u64,
+        progress: &mut dyn ProgressSink,
+    ) -> Result<Self::Output, Self::Error> {
+        self.run(graph, annotator, progress, &CancellationToken::new())
+    }
+
+    fn execute_with(
+        &self,
+        _context: &mut (),
+        graph: &G,
+        annotator: &mut A,
+        _seed: u64,
+        token: &CancellationToken,
+    ) -> Result<Self::Output, Self::Error> {
+        self.run(graph, annotator, &mut NoProgress, token)
+    }
+}
+
+/// Runs natively on the CPU, yielding between iterations. Nodes are
+/// annotated once the iterations converge.
+impl<G, A, W> AsyncGraphAlgorithm<G, A> for PowerIteration<W, CpuBackend>
+where
+    G: Graph + Sync,
+    G::Weight: Into<f64>,
+    Id<G::Node>: Clone + Ord + Hash + Send + Sync,
+    A: AsyncGraphAnnotator<Annotation = (Id<G::Node>, NodeRank<W>)> + Send,
+    W: Float + Send + Sync,
+{
+    type Context = ();
+    /// The rank of every node.
+    type Output = Ranks<G>;
+    type Error = AsyncError<AlgorithmError, A::Error>;
+    type RngSeed = u64;
+    type Annotation = (Id<G::Node>, NodeRank<W>);
+
+    async fn execute(
+        &self,
+        _context: &mut (),
+        graph: &G,
+        annotator: &mut A,
+        _seed: u64,
+    ) -> Result<Self::Output, Self::Error> {
+        let TransitionMatrix { nodes, matrix } = transition_matrix(graph);
+        let n = nodes.len();
+        if n == 0 {
+            return Ok(HashMap::new());
+        }
+        let damping = self.damping(graph, &nodes);
+
+        let offsets = matrix.proper_indptr();
+        let problem = RankProblem {
+            offsets: &offsets,
+            targets: matrix.indices(),
+            weights: matrix.data(),
+            damping: &damping,
+            max_iterations: self.max_iterations,
+            tolerance: self.tolerance,
+        };
+        let mut ranks = vec![1.0 / n as f64; n];
+        let mut iterations = 0;
+        loop {
+            iterations += 1;
+            let (next, residual) = iterate(&problem, &ranks);
+            ranks = next;
+            if converged(&problem, iterations, residual).map_err(AsyncError::Algorithm)? {
+                break;
+            }
+            yield_now().await;
+        }
+
+        // Annotated by increasing id, the order of the rows.
+        for (id, rank) in nodes.iter().zip(&ranks) {
+            let rank = W::from(*rank).unwrap_or_else(W::zero);
+            annotator
+                .annotate_graph(((*id).clone(), NodeRank { rank }))
+                .await
+                .map_err(AsyncError::Annotator)?;
+        }
+        Ok(nodes.into_iter().cloned().zip(ranks).collect())
+    }
+}
+
+impl<W: Float, B> PowerIteration<W, B> {
+    fn run<G, A>(
+        &self,
+        graph: &G,
+        annotator: &mut A,
+        progress: &mut dyn ProgressSink,
+        token: &CancellationToken,
+    ) -> Result<Ranks<G>, AlgorithmError>
+    where
+        G: Graph,
+        G::Weight: Into<f64>,
+        Id<G::Node>: Clone + Ord + Hash,
+        A: GraphAnnotator<Annotation = (Id<G::Node>, NodeRank<W>)>,
+        B: AcceleratedRankBackend,
+    {
+        let start = Stopwatch::start(progress);
+        let TransitionMatrix { nodes, matrix } = transition_matrix(graph);
+        let n = nodes.len();
+        if n == 0 {
+            return Ok(HashMap::new());
+        }
+        let damping = self.damping(graph, &nodes);
+
+        let offsets = matrix.proper_indptr();
+        let problem = RankProblem {
+            offsets: &offsets,
+            targets: matrix.indices(),
+            weights: matrix.data(),
+            damping: &damping,
+            max_iterations: self.max_iterations,
+            tolerance: self.tolerance,
+        };
+        let RankVector { ranks, iterations } = self.backend.rank(&problem, progress, token)?;
+        if ranks.len() != n {
+            return Err(AlgorithmError::InvariantViolated(format!(
+                "the backend returned {} ranks for {} nodes",
+                ranks.len(),
+                n
+            )));
+        }
+
+        // Annotated by increasing id, the order of the rows.
+        for (id, rank) in nodes.iter().zip(&ranks) {
+            let rank = W::from(*rank).unwrap_or_else(W::zero);
+            annotator.annotate_graph(((*id).clone(), NodeRank { rank }));
+        }
+        let ranks: Ranks<G> = nodes.into_iter().cloned().zip(ranks).collect();
+        progress.on_stats(&ExecutionStats {
+            iterations: iterations as u64,
+            elapsed: start.elapsed(),
+            peak_memory: matrix.nnz() * (std::mem::size_of::<f64>() + std::mem::size_of::<usize>())
+                + 3 * n * std::mem::size_of::<f64>(),
+            ..Default::default()
+        });
+        Ok(ranks)
+    }
+
+    /// The damping factor of every node.
+    fn damping<G: Graph>(&self, graph: &G, nodes: &[&Id<G::Node>]) -> Vec<f64> {
+        nodes
+            .iter()
+            .map(|id| match graph.get_node(id).map(|n| n.node_type()) {
+                Some(NodeType::User { .. }) => self.hyperparams.damping_factors.account,
+                _ => self.hyperparams.damping_factors.project,
+            })
+            .collect()
+    }
+
+    /// Explain the ranks computed by an execution over `graph`, with the
+    /// `top` sources of every node, by increasing node id. At the stationary
+    /// distribution, the rank flowing from a node to another is the share of
+    /// the walks on the first node continuing to the second.
+    pub fn explain<G>(
+        &self,
+        graph: &G,
+        ranks: &Ranks<G>,
+        top: usize,
+    ) -> Vec<RankExplanation<Id<G::Node>>>
+    where
+        G: Graph,
+        G::Weight: Into<f64>,
+        Id<G::Node>: Clone + Ord + Hash,
+    {
+        let TransitionMatrix { nodes, matrix } = transition_matrix(graph);
+        let damping = self.damping(graph, &nodes);
+        let mut flows = Flows::new();
+        let mut continued = 0.0;
+
+        for (i, row) in matrix.outer_iterator().enumerate() {
+            let mass = ranks.get(nodes[i]).cloned().unwrap_or(0.0) * damping[i];
+            if row.nnz() == 0 {
+                continue;
+            }
+            continued += mass;
+            for (j, p) in row.iter() {
+                flows.add(nodes[i], nodes[j], mass * p);
+            }
+        }
+        let restart = (1.0 - continued) / nodes.len() as f64;
+        for id in nodes.iter() {
+            flows.add_own(id, restart);
+        }
+        flows.explain(ranks, top)
+    }
+}
@@ -0,0 +1,1129 @@
+//! In-memory reference implementations of the graph traits.
+
+use num_traits::Zero;
+use std::collections::{BTreeMap, BTreeSet};
+use std::iter::FromIterator;
+
+use super::epochs::EpochId;
+use super::ledger::LedgerGraph;
+use super::properties::{HasProperties, Properties};
+use super::types::{
+    ContributionLedger, EdgeData, EdgeType, EdgeTypeTag, NodeData, NodeType, Timestamps,
+};
+use super::{
+    Data, Direction, EdgeRef, EdgeRefs, Edges, EdgesMut, Graph, GraphBuilder, GraphDataWriter,
+    GraphObject, GraphWriter, Id, IntoEdges, IntoGraphParts, IntoNodes, Nodes, NodesMut,
+};
+
+/// The id of edges in the in-memory graphs.
+pub type EdgeId = u64;
+
+/// Build a graph of any backend from a list of `(from, to, data)` edges. Nodes
+/// are created on first use, with a clone of `node_data`, and edge ids are
+/// obtained from `edge_id`, which is given the index of the edge in the list.
+pub fn from_edges<B, I, F>(
+    builder: B,
+    edges: I,
+    node_data: Data<<B::Graph as Graph>::Node>,
+    mut edge_id: F,
+) -> B::Graph
+where
+    B: GraphBuilder,
+    B::Graph: GraphWriter,
+    I: IntoIterator<
+        Item = (
+            GraphNodeId<B::Graph>,
+            GraphNodeId<B::Graph>,
+            Data<<B::Graph as Graph>::Edge>,
+        ),
+    >,
+    F: FnMut(usize) -> Id<<B::Graph as Graph>::Edge>,
+    GraphNodeId<B::Graph>: Clone,
+    Data<<B::Graph as Graph>::Node>: Clone,
+{
+    let mut graph = builder.build();
+
+    for (i, (from, to, data)) in edges.into_iter().enumerate() {
+        for id in [&from, &to] {
+            if graph.get_node(id).is_none() {
+                graph.add_node(id.clone(), node_data.clone());
+            }
+        }
+        graph.add_edge(edge_id(i), &from, &to, data);
+    }
+    graph
+}
+
+type GraphNodeId<G> = Id<<G as Graph>::Node>;
+
+/// Stamp the timestamps of an object added at the current epoch, if set,
+/// keeping the creation time of the object it replaces, if any.
+fn stamp(epoch: Option<EpochId>, timestamps: &mut Timestamps, replaced: Option<&Timestamps>) {
+    if let Some(now) = epoch {
+        timestamps.created_at = match replaced {
+            Some(t) => t.created_at,
+            None => timestamps.created_at.or(Some(now)),
+        };
+        timestamps.touch(now);
+    }
+}
+
+/// A node of an in-memory graph.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemNode<NodeId, W> {
+    id: NodeId,
+    data: NodeData<W>,
+    properties: Properties,
+}
+
+impl<NodeId, W> MemNode<NodeId, W> {
+    pub(crate) fn new(id: NodeId, data: NodeData<W>) -> Self {
+        MemNode {
+            id,
+            data,
+            properties: Properties::new(),
+        }
+    }
+}
+
+impl<NodeId, W> GraphObject for MemNode<NodeId, W> {
+    type Id = NodeId;
+    type Data = NodeData<W>;
+
+    fn id(&self) -> &NodeId {
+        &self.id
+    }
+
+    fn data(&self) -> &NodeData<W> {
+        &self.data
+    }
+
+    fn data_mut(&mut self) -> &mut NodeData<W> {
+        &mut self.data
+    }
+}
+
+impl<NodeId, W> super::Node<NodeData<W>> for MemNode<NodeId, W> {
+    fn node_type(&self) -> &NodeType {
+        &self.data.node_type
+    }
+}
+
+/// Properties aren't persisted by the snapshot formats or storage backends.
+impl<NodeId, W> HasProperties for MemNode<NodeId, W> {
+    type Properties = Properties;
+
+    fn properties(&self) -> &Properties {
+        &self.properties
+    }
+
+    fn properties_mut(&mut self) -> &mut Properties {
+        &mut self.properties
+    }
+}
+
+/// An edge of an in-memory graph. Its weight is the one stored in its data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemEdge<NodeId, W> {
+    id: EdgeId,
+    from: NodeId,
+    to: NodeId,
+    data: EdgeData<W>,
+    properties: Properties,
+}
+
+impl<NodeId, W> MemEdge<NodeId, W> {
+    pub(crate) fn new(id: EdgeId, from: NodeId, to: NodeId, data: EdgeData<W>) -> Self {
+        MemEdge {
+            id,
+            from,
+            to,
+            data,
+            properties: Properties::new(),
+        }
+    }
+}
+
+impl<NodeId, W> GraphObject for MemEdge<NodeId, W> {
+    type Id = EdgeId;
+    type Data = EdgeData<W>;
+
+    fn id(&self) -> &EdgeId {
+        &self.id
+    }
+
+    fn data(&self) -> &EdgeData<W> {
+        &self.data
+    }
+
+    fn data_mut(&mut self) -> &mut EdgeData<W> {
+        &mut self.data
+    }
+}
+
+impl<NodeId, W: Clone> super::Edge<W, NodeId, EdgeData<W>> for MemEdge<NodeId, W> {
+    fn source(&self) -> &NodeId {
+        &self.from
+    }
+
+    fn target(&self) -> &NodeId {
+        &self.to
+    }
+
+    fn edge_type(&self) -> &EdgeType {
+        &self.data.edge_type
+    }
+}
+
+/// Properties aren't persisted by the snapshot formats or storage backends.
+impl<NodeId, W> HasProperties for MemEdge<NodeId, W> {
+    type Properties = Properties;
+
+    fn properties(&self) -> &Properties {
+        &self.properties
+    }
+
+    fn properties_mut(&mut self) -> &mut Properties {
+        &mut self.properties
+    }
+}
+
+/// A simple in-memory graph, storing nodes and edges in ordered maps along
+/// with adjacency lists. Iteration order is always by ascending id.
+///
+/// Once the current epoch is set with `set_epoch`, the timestamps of the
+/// nodes and edges added, replaced, or accessed through `GraphDataWriter`
+/// are maintained. Creation times survive replacements.
+#[derive(Debug, Clone)]
+pub struct EdgeListGraph<NodeId = u64, W = f64> {
+    nodes: BTreeMap<NodeId, MemNode<NodeId, W>>,
+    edges: BTreeMap<EdgeId, MemEdge<NodeId, W>>,
+    outgoing: BTreeMap<NodeId, BTreeSet<EdgeId>>,
+    incoming: BTreeMap<NodeId, BTreeSet<EdgeId>>,
+    next_edge_id: EdgeId,
+    epoch: Option<EpochId>,
+}
+
+impl<NodeId: Ord, W> Default for EdgeListGraph<NodeId, W> {
+    fn default() -> Self {
+        EdgeListGraph {
+            nodes: BTreeMap::new(),
+            edges: BTreeMap::new(),
+            outgoing: BTreeMap::new(),
+            incoming: BTreeMap::new(),
+            next_edge_id: 0,
+            epoch: None,
+        }
+    }
+}
+
+impl<NodeId, W> EdgeListGraph<NodeId, W>
+where
+    NodeId: Ord + Clone,
+    W: Clone,
+{
+    /// Create an empty graph.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The current epoch, if set.
+    pub fn epoch(&self) -> Option<EpochId> {
+        self.epoch
+    }
+
+    /// Set the current epoch, stamped on the nodes and edges modified from
+    /// now on.
+    pub fn set_epoch(&mut self, epoch: EpochId) {
+        self.epoch = Some(epoch);
+    }
+
+    /// Build a graph from a list of `(from, to, data)` edges. Nodes are created
+    /// on first use, with a clone of `node_data`. Edges are given sequential
+    /// ids, starting from zero, in iteration order.
+    pub fn from_edges<I>(edges: I, node_data: NodeData<W>) -> Self
+    where
+        I: IntoIterator<Item = (NodeId, NodeId, EdgeData<W>)>,
+    {
+        let mut graph = Self::new();
+        graph.extend_edges(edges, node_data);
+        graph
+    }
+
+    /// Add a list of `(from, to, data)` edges to the graph, with the same
+    /// semantics as `from_edges`. Ids of new edges follow the ones already
+    /// allocated.
+    pub fn extend_edges<I>(&mut self, edges: I, node_data: NodeData<W>)
+    where
+        I: IntoIterator<Item = (NodeId, NodeId, EdgeData<W>)>,
+    {
+        for (from, to, data) in edges {
+            for id in [&from, &to] {
+                if !self.nodes.contains_key(id) {
+                    self.add_node(id.clone(), node_data.clone());
+                }
+            }
+            let id = self.next_edge_id;
+            self.add_edge(id, &from, &to, data);
+        }
+    }
+
+    /// The number of nodes.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// The number of edges.
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Iterator over all edges, by ascending id.
+    pub fn all_edges(&self) -> Edges<'_, MemEdge<NodeId, W>> {
+        Edges {
+            range: self.edges.values().collect::<Vec<_>>().into_iter(),
+        }
+    }
+
+    /// Drop the removed edges from the adjacency lists.
+    fn prune_adjacency(&mut self) {
+        let edges = &self.edges;
+        for ids in self.outgoing.values_mut().chain(self.incoming.values_mut()) {
+            ids.retain(|id| edges.contains_key(id));
+        }
+    }
+
+    fn adjacent(&self, node: &NodeId, dir: Direction) -> impl Iterator<Item = &MemEdge<NodeId, W>> {
+        let ids = |index: &BTreeMap<NodeId, BTreeSet<EdgeId>>| {
+            index.get(node).into_iter().flatten().copied().collect()
+        };
+        let ids: BTreeSet<EdgeId> = match dir {
+            Direction::Outgoing => ids(&self.outgoing),
+            Direction::Incoming => ids(&self.incoming),
+            Direction::Both => &ids(&self.outgoing) | &ids(&self.incoming),
+        };
+        ids.into_iter().filter_map(move |id| self.edges.get(&id))
+    }
+}
+
+impl<NodeId, W> FromIterator<(NodeId, NodeId, EdgeData<W>)> for EdgeListGraph<NodeId, W>
+where
+    NodeId: Ord + Clone,
+    W: Clone + Zero,
+{
+    /// Build a graph from a list of edges, creating project nodes with no
+    /// contributions and a zero rank. See `EdgeListGraph::from_edges`.
+    fn from_iter<I: IntoIterator<Item = (NodeId, NodeId, EdgeData<W>)>>(edges: I) -> Self {
+        let node_data = NodeData {
+            node_type: NodeType::Project {
+                contributions_from_all_users: 0,
+            },
+            rank: Zero::zero(),
+            contributions: ContributionLedger::new(),
+            timestamps: Timestamps::default(),
+        };
+        Self::from_edges(edges, node_data)
+    }
+}
+
+impl<NodeId, W> Graph for EdgeListGraph<NodeId, W>
+where
+    NodeId: Ord + Clone,
+    W: Clone,
+{
+    type Node = MemNode<NodeId, W>;
+    type Edge = MemEdge<NodeId, W>;
+    type NodeData = NodeData<W>;
+    type EdgeData = EdgeData<W>;
+    type Weight = W;
+
+    fn get_node(&self, id: &NodeId) -> Option<&Self::Node> {
+        self.nodes.get(id)
+    }
+
+    fn get_edge(&self, id: &EdgeId) -> Option<&Self::Edge> {
+        self.edges.get(id)
+    }
+
+    fn nodes(&self) -> Nodes<'_, Self::Node> {
+        Nodes {
+            range: self.nodes.values().collect::<Vec<_>>().into_iter(),
+        }
+    }
+
+    fn neighbors(&self, node: &NodeId) -> Nodes<'_, Self::Node> {
+        let ns: Vec<&Self::Node> = self
+            .adjacent(node, Direction::Outgoing)
+            .filter_map(|e| self.nodes.get(&e.to))
+            .chain(
+                self.adjacent(node, Direction::Incoming)
+                    .filter_map(|e| self.nodes.get(&e.from)),
+            )
+            .collect();
+
+        Nodes {
+            range: ns.into_iter(),
+        }
+    }
+
+    fn edges(&self, node: &NodeId) -> Edges<'_, Self::Edge> {
+        let mut ids: BTreeSet<EdgeId> = BTreeSet::new();
+        ids.extend(self.outgoing.get(node).into_iter().flatten());
+        ids.extend(self.incoming.get(node).into_iter().flatten());
+
+        Edges {
+            range: ids
+                .iter()
+                .filter_map(|id| self.edges.get(id))
+                .collect::<Vec<_>>()
+                .into_iter(),
+        }
+    }
+
+    fn edges_directed(
+        &self,
+        node: &NodeId,
+        dir: Direction,
+    ) -> EdgeRefs<'_, NodeId, EdgeId, EdgeData<W>> {
+        self.adjacent(node, dir)
+            .map(|e| EdgeRef {
+                from: &e.from,
+                to: &e.to,
+                id: &e.id,
+                data: &e.data,
+            })
+            .collect()
+    }
+
+    fn degree_by_edge_type(&self, node: &NodeId, dir: Direction) -> BTreeMap<EdgeTypeTag, usize> {
+        let mut degrees = BTreeMap::new();
+        let mut count = |index: &BTreeMap<NodeId, BTreeSet<EdgeId>>, skip_loops: bool| {
+            for edge in index
+                .get(node)
+                .into_iter()
+                .flatten()
+                .filter_map(|id| self.edges.get(id))
+                .filter(|e| !(skip_loops && e.from == e.to))
+            {
+                *degrees.entry(edge.data.edge_type.to_tag()).or_insert(0) += 1;
+            }
+        };
+        match dir {
+            Direction::Outgoing => count(&self.outgoing, false),
+            Direction::Incoming => count(&self.incoming, false),
+            // Self-loops are both outgoing and incoming, and counted once.
+            Direction::Both => {
+                count(&self.outgoing, false);
+                count(&self.incoming, true);
+            }
+        }
+        degrees
+    }
+}
+
+impl<NodeId, W> GraphWriter for EdgeListGraph<NodeId, W>
+where
+    NodeId: Ord + Clone,
+    W: Clone,
+{
+    /// Add a node, replacing the data of any existing node with the same id
+    /// and keeping its properties.
+    fn add_node(&mut self, id: NodeId, mut data: NodeData<W>) {
+        let replaced = self.nodes.get(&id).map(|n| &n.data.timestamps);
+        stamp(self.epoch, &mut data.timestamps, replaced);
+
+        match self.nodes.get_mut(&id) {
+            Some(node) => node.data = data,
+            None => {
+                self.nodes.insert(id.clone(), MemNode::new(id, data));
+            }
+        }
+    }
+
+    /// Remove a node along with all its edges.
+    fn remove_node(&mut self, id: NodeId) {
+        if self.nodes.remove(&id).is_none() {
+            return;
+        }
+        let edges: Vec<EdgeId> = self
+            .outgoing
+            .remove(&id)
+            .into_iter()
+            .flatten()
+            .chain(self.incoming.remove(&id).into_iter().flatten())
+            .collect();
+
+        for e in edges {
+            self.remove_edge(e);
+        }
+    }
+
+    /// Link two nodes, replacing any existing edge with the same id along
+    /// with its properties. Edges to missing nodes are ignored.
+    fn add_edge(&mut self, id: EdgeId, from: &NodeId, to: &NodeId, mut data: EdgeData<W>) {
+        if !self.nodes.contains_key(from) || !self.nodes.contains_key(to) {
+            return;
+        }
+        let replaced = self.edges.get(&id).map(|e| &e.data.timestamps);
+        stamp(self.epoch, &mut data.timestamps, replaced);
+        self.remove_edge(id);
+
+        self.outgoing.entry(from.clone()).or_default().insert(id);
+        self.incoming.entry(to.clone()).or_default().insert(id);
+        self.edges
+            .insert(id, MemEdge::new(id, from.clone(), to.clone(), data));
+        self.next_edge_id = self.next_edge_id.max(id.saturating_add(1));
+    }
+
+    fn remove_edge(&mut self, id: EdgeId) {
+        if let Some(e) = self.edges.remove(&id) {
+            if let Some(out) = self.outgoing.get_mut(&e.from) {
+                out.remove(&id);
+            }
+            if let Some(inc) = self.incoming.get_mut(&e.to) {
+                inc.remove(&id);
+            }
+        }
+    }
+
+    /// Every node is stamped as updated at the current epoch, if set.
+    fn nodes_mut(&mut self) -> NodesMut<'_, Self::Node> {
+        let mut nodes: Vec<_> = self.nodes.values_mut().collect();
+        if let Some(now) = self.epoch {
+            for n in nodes.iter_mut() {
+                n.data.timestamps.touch(now);
+            }
+        }
+        NodesMut {
+            range: nodes.into_iter(),
+        }
+    }
+
+    /// Drops the internal storage at once. Edge ids allocated so far aren't
+    /// reused.
+    fn clear(&mut self) {
+        self.nodes.clear();
+        self.edges.clear();
+        self.outgoing.clear();
+        self.incoming.clear();
+    }
+
+    /// Removes the nodes and their edges in place, in a single pass.
+    fn retain_nodes<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&Self::Node) -> bool,
+    {
+        self.nodes.retain(|_, n| keep(n));
+
+        let nodes = &self.nodes;
+        self.edges
+            .retain(|_, e| nodes.contains_key(&e.from) && nodes.contains_key(&e.to));
+        self.outgoing.retain(|id, _| nodes.contains_key(id));
+        self.incoming.retain(|id, _| nodes.contains_key(id));
+        self.prune_adjacency();
+    }
+
+    /// Removes the edges in place, in a single pass.
+    fn retain_edges<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&Self::Edge) -> bool,
+    {
+        self.edges.retain(|_, e| keep(e));
+        self.prune_adjacency();
+    }
+
+    /// Every edge is stamped as updated at the current epoch, if set.
+    fn edges_mut(&mut self) -> EdgesMut<'_, Self::Edge> {
+        let mut edges: Vec<_> = self.edges.values_mut().collect();
+        if let Some(now) = self.epoch {
+            for e in edges.iter_mut() {
+                e.data.timestamps.touch(now);
+            }
+        }
+        EdgesMut {
+            range: edges.into_iter(),
+        }
+    }
+}
+
+impl<NodeId, W> GraphDataWriter for EdgeListGraph<NodeId, W>
+where
+    NodeId: Ord + Clone,
+    W: Clone,
+{
+    fn edge_data_mut(&mut self, id: &EdgeId) -> Option<&mut EdgeData<W>> {
+        let epoch = self.epoch;
+        self.edges.get_mut(id).map(|e| {
+            if let Some(now) = epoch {
+                e.data.timestamps.touch(now);
+            }
+            &mut e.data
+        })
+    }
+
+    fn node_data_mut(&mut self, id: &NodeId) -> Option<&mut NodeData<W>> {
+        let epoch = self.epoch;
+        self.nodes.get_mut(id).map(|n| {
+            if let Some(now) = epoch {
+                n.data.timestamps.touch(now);
+            }
+            &mut n.data
+        })
+    }
+}
+
+impl<NodeId: Ord + Clone, W: Clone> LedgerGraph for EdgeListGraph<NodeId, W> {}
+
+impl<NodeId: Ord, W> EdgeListGraph<NodeId, W> {
+    /// The properties of a node, to modify them.
+    pub fn node_properties_mut(&mut self, id: &NodeId) -> Option<&mut Properties> {
+        self.nodes.get_mut(id).map(|n| &mut n.properties)
+    }
+
+    /// The properties of an edge, to modify them.
+    pub fn edge_properties_mut(&mut self, id: &EdgeId) -> Option<&mut Properties> {
+        self.edges.get_mut(id).map(|e| &mut e.properties)
+    }
+}
+
+impl<NodeId, W> IntoGraphParts for EdgeListGraph<NodeId, W>
+where
+    NodeId: Ord + Clone,
+    W: Clone,
+{
+    fn into_parts(self) -> (IntoNodes<Self::Node>, IntoEdges<Self::Edge>) {
+        (
+            IntoNodes {
+                range: self.nodes.into_values().collect::<Vec<_>>().into_iter(),
+            },
+            IntoEdges {
+                range: self.edges.into_values().collect::<Vec<_>>().into_iter(),
+            },
+        )
+    }
+}
+
+/// An index into an `Arena`: a slot, and the generation of the object stored
+/// in it, so that the index of a removed object doesn't resolve to the one
+/// reusing its slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Index {
+    slot: usize,
+    generation: u32,
+}
+
+#[derive(Debug, Clone)]
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+/// A slab of objects in contiguous storage, reusing the slots of removed
+/// objects.
+#[derive(Debug, Clone)]
+struct Arena<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<usize>,
+    len: usize,
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Arena {
+            slots: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> Arena<T> {
+    fn insert(&mut self, value: T) -> Index {
+        self.len += 1;
+        match self.free.pop() {
+            Some(slot) => {
+                let s = &mut self.slots[slot];
+                s.value = Some(value);
+                Index {
+                    slot,
+                    generation: s.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    value: Some(value),
+                });
+                Index {
+                    slot: self.slots.len() - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    fn remove(&mut self, index: Index) -> Option<T> {
+        let s = self
+            .slots
+            .get_mut(index.slot)
+            .filter(|s| s.generation == index.generation)?;
+        let value = s.value.take()?;
+        s.generation = s.generation.wrapping_add(1);
+        self.free.push(index.slot);
+        self.len -= 1;
+        Some(value)
+    }
+
+    fn get(&self, index: Index) -> Option<&T> {
+        self.slots
+            .get(index.slot)
+            .filter(|s| s.generation == index.generation)
+            .and_then(|s| s.value.as_ref())
+    }
+
+    fn get_mut(&mut self, index: Index) -> Option<&mut T> {
+        self.slots
+            .get_mut(index.slot)
+            .filter(|s| s.generation == index.generation)
+            .and_then(|s| s.value.as_mut())
+    }
+
+    fn values(&self) -> impl Iterator<Item = &T> {
+        self.slots.iter().filter_map(|s| s.value.as_ref())
+    }
+
+    fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.slots.iter_mut().filter_map(|s| s.value.as_mut())
+    }
+
+    fn into_values(self) -> impl Iterator<Item = T> {
+        self.slots.into_iter().filter_map(|s| s.value)
+    }
+
+    fn clear(&mut self) {
+        self.slots.clear();
+        self.free.clear();
+        self.len = 0;
+    }
+}
+
+/// A node of an `ArenaGraph`, with the indices of its edges.
+#[derive(Debug, Clone)]
+struct ArenaNode<NodeId, W> {
+    node: MemNode<NodeId, W>,
+    outgoing: Vec<Index>,
+    incoming: Vec<Index>,
+}
+
+/// An edge of an `ArenaGraph`, with the indices of its nodes.
+#[derive(Debug, Clone)]
+struct ArenaEdge<NodeId, W> {
+    edge: MemEdge<NodeId, W>,
+    from: Index,
+    to: Index,
+}
+
+/// An in-memory graph storing nodes and edges in slab arenas, with
+/// adjacency lists of arena indices. Ids are only looked up in an index
+/// table to find the first node of a traversal, which then follows indices
+/// through contiguous storage.
+///
+/// Compared to `EdgeListGraph`, walks and neighborhood queries are faster,
+/// and removing an edge costs linear time in the degree of its nodes.
+/// Iteration order is by storage slot: insertion order, until slots of
+/// removed objects are reused. Timestamps are maintained as in
+/// `EdgeListGraph`, once the epoch is set.
+#[derive(Debug, Clone)]
+pub struct ArenaGraph<NodeId = u64, W = f64> {
+    nodes: Arena<ArenaNode<NodeId, W>>,
+    edges: Arena<ArenaEdge<NodeId, W>>,
+    node_index: BTreeMap<NodeId, Index>,
+    edge_index: BTreeMap<EdgeId, Index>,
+    epoch: Option<EpochId>,
+}
+
+impl<NodeId: Ord, W> Default for ArenaGraph<NodeId, W> {
+    fn default() -> Self {
+        ArenaGraph {
+            nodes: Arena::default(),
+            edges: Arena::default(),
+            node_index: BTreeMap::new(),
+            edge_index: BTreeMap::new(),
+            epoch: None,
+        }
+    }
+}
+
+impl<NodeId, W> ArenaGraph<NodeId, W>
+where
+    NodeId: Ord + Clone,
+    W: Clone,
+{
+    /// Create an empty graph.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The current epoch, if set.
+    pub fn epoch(&self) -> Option<EpochId> {
+        self.epoch
+    }
+
+    /// Set the current epoch, stamped on the nodes and edges modified from
+    /// now on.
+    pub fn set_epoch(&mut self, epoch: EpochId) {
+        self.epoch = Some(epoch);
+    }
+
+    /// The number of nodes.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len
+    }
+
+    /// The number of edges.
+    pub fn edge_count(&self) -> usize {
+        self.edges.len
+    }
+
+    /// Iterator over all edges, by storage slot.
+    pub fn all_edges(&self) -> Edges<'_, MemEdge<NodeId, W>> {
+        Edges {
+            range: self
+                .edges
+                .values()
+                .map(|e| &e.edge)
+                .collect::<Vec<_>>()
+                .into_iter(),
+        }
+    }
+
+    fn adjacent(
+        &self,
+        node: &NodeId,
+        dir: Direction,
+    ) -> impl Iterator<Item = &ArenaEdge<NodeId, W>> {
+        let node = self.node_index.get(node).and_then(|i| self.nodes.get(*i));
+        let (outgoing, incoming) = match (node, dir) {
+            (None, _) => (&[][..], &[][..]),
+            (Some(n), Direction::Outgoing) => (&n.outgoing[..], &[][..]),
+            (Some(n), Direction::Incoming) => (&[][..], &n.incoming[..]),
+            (Some(n), Direction::Both) => (&n.outgoing[..], &n.incoming[..]),
+        };
+        let skip_loops = dir == Direction::Both;
+
+        outgoing
+            .iter()
+            .filter_map(move |i| self.edges.get(*i))
+            .chain(
+                incoming
+                    .iter()
+                    .filter_map(move |i| self.edges.get(*i))
+                    // Self-loops are both outgoing and incoming, and listed once.
+                    .filter(move |e| !(skip_loops && e.from == e.to)),
+            )
+    }
+
+    /// Drop an edge index from the adjacency list of a node.
+    fn unlink(list: &mut Vec<Index>, edge: Index) {
+        if let Some(pos) = list.iter().position(|i| *i == edge) {
+            list.swap_remove(pos);
+        }
+    }
+}
+
+impl<NodeId, W> Graph for ArenaGraph<NodeId, W>
+where
+    NodeId: Ord + Clone,
+    W: Clone,
+{
+    type Node = MemNode<NodeId, W>;
+    type Edge = MemEdge<NodeId, W>;
+    type NodeData = NodeData<W>;
+    type EdgeData = EdgeData<W>;
+    type Weight = W;
+
+    fn get_node(&self, id: &NodeId) -> Option<&Self::Node> {
+        let index = self.node_index.get(id)?;
+        self.nodes.get(*index).map(|n| &n.node)
+    }
+
+    fn get_edge(&self, id: &EdgeId) -> Option<&Self::Edge> {
+        let index = self.edge_index.get(id)?;
+        self.edges.get(*index).map(|e| &e.edge)
+    }
+
+    fn nodes(&self) -> Nodes<'_, Self::Node> {
+        Nodes {
+            range: self
+                .nodes
+                .values()
+                .map(|n| &n.node)
+                .collect::<Vec<_>>()
+                .into_iter(),
+        }
+    }
+
+    fn neighbors(&self, node: &NodeId) -> Nodes<'_, Self::Node> {
+        let ns: Vec<&Self::Node> = self
+            .adjacent(node, Direction::Outgoing)
+            .filter_map(|e| self.nodes.get(e.to))
+            .chain(
+                self.adjacent(node, Direction::Incoming)
+                    .filter_map(|e| self.nodes.get(e.from)),
+            )
+            .map(|n| &n.node)
+            .collect();
+
+        Nodes {
+            range: ns.into_iter(),
+        }
+    }
+
+    fn edges(&self, node: &NodeId) -> Edges<'_, Self::Edge> {
+        Edges {
+            range: self
+                .adjacent(node, Direction::Both)
+                .map(|e| &e.edge)
+                .collect::<Vec<_>>()
+                .into_iter(),
+        }
+    }
+
+    fn edges_directed(
+        &self,
+        node: &NodeId,
+        dir: Direction,
+    ) -> EdgeRefs<'_, NodeId, EdgeId, EdgeData<W>> {
+        self.adjacent(node, dir)
+            .map(|e| EdgeRef {
+                from: &e.edge.from,
+                to: &e.edge.to,
+                id: &e.edge.id,
+                data: &e.edge.data,
+            })
+            .collect()
+    }
+
+    fn degree_by_edge_type(&self, node: &NodeId, dir: Direction) -> BTreeMap<EdgeTypeTag, usize> {
+        let mut degrees = BTreeMap::new();
+        for e in self.adjacent(node, dir) {
+            *degrees.entry(e.edge.data.edge_type.to_tag()).or_insert(0) += 1;
+        }
+        degrees
+    }
+}
+
+impl<NodeId, W> GraphWriter for ArenaGraph<NodeId, W>
+where
+    NodeId: Ord + Clone,
+    W: Clone,
+{
+    /// Add a node, replacing the data of any existing node with the same id
+    /// and keeping its properties.
+    fn add_node(&mut self, id: NodeId, mut data: NodeData<W>) {
+        let index = self.node_index.get(&id).copied();
+        let replaced = index
+            .and_then(|i| self.nodes.get(i))
+            .map(|n| &n.node.data.timestamps);
+        stamp(self.epoch, &mut data.timestamps, replaced);
+
+        match index.and_then(|i| self.nodes.get_mut(i)) {
+            Some(n) => n.node.data = data,
+            None => {
+                let index = self.nodes.insert(ArenaNode {
+                    node: MemNode::new(id.clone(), data),
+                    outgoing: Vec::new(),
+                    incoming: Vec::new(),
+                });
+                self.node_index.insert(id, index);
+            }
+        }
+    }
+
+    /// Remove a node along with all its edges.
+    fn remove_node(&mut self, id: NodeId) {
+        let node = match self
+            .node_index
+            .remove(&id)
+            .and_then(|i| self.nodes.remove(i))
+        {
+            Some(node) => node,
+            None => return,
+        };
+
+        for index in node.outgoing.into_iter().chain(node.incoming) {
+            // Self-loops are listed twice, and already gone the second time.
+            if let Some(e) = self.edges.remove(index) {
+                self.edge_index.remove(&e.edge.id);
+                if let Some(to) = self.nodes.get_mut(e.to) {
+                    Self::unlink(&mut to.incoming, index);
+                }
+                if let Some(from) = self.nodes.get_mut(e.from) {
+                    Self::unlink(&mut from.outgoing, index);
+                }
+            }
+        }
+    }
+
+    /// Link two nodes, replacing any existing edge with the same id along
+    /// with its properties. Edges to missing nodes are ignored.
+    fn add_edge(&mut self, id: EdgeId, from: &NodeId, to: &NodeId, mut data: EdgeData<W>) {
+        let (from_index, to_index) = match (self.node_index.get(from), self.node_index.get(to)) {
+            (Some(f), Some(t)) => (*f, *t),
+            _ => return,
+        };
+        let replaced = self
+            .edge_index
+            .get(&id)
+            .and_then(|i| self.edges.get(*i))
+            .map(|e| &e.edge.data.timestamps);
+        stamp(self.epoch, &mut data.timestamps, replaced);
+        self.remove_edge(id);
+
+        let index = self.edges.insert(ArenaEdge {
+            edge: MemEdge::new(id, from.clone(), to.clone(), data),
+            from: from_index,
+            to: to_index,
+        });
+        self.edge_index.insert(id, index);
+        if let Some(n) = self.nodes.get_mut(from_index) {
+            n.outgoing.push(index);
+        }
+        if let Some(n) = self.nodes.get_mut(to_index) {
+            n.incoming.push(index);
+        }
+    }
+
+    /// Remove an edge, in linear time in the degree of its nodes.
+    fn remove_edge(&mut self, id: EdgeId) {
+        let (index, e) = match self
+            .edge_index
+            .remove(&id)
+            .and_then(|i| self.edges.remove(i).map(|e| (i, e)))
+        {
+            Some(removed) => removed,
+            None => return,
+        };
+        if let Some(from) = self.nodes.get_mut(e.from) {
+            Self::unlink(&mut from.outgoing, index);
+        }
+        if let Some(to) = self.nodes.get_mut(e.to) {
+            Self::unlink(&mut to.incoming, index);
+        }
+    }
+
+    /// Every node is stamped as updated at the current epoch, if set.
+    fn nodes_mut(&mut self) -> NodesMut<'_, Self::Node> {
+        let epoch = self.epoch;
+        NodesMut {
+            range: self
+                .nodes
+                .values_mut()
+                .map(|n| {
+                    if let Some(now) = epoch {
+                        n.node.data.timestamps.touch(now);
+                    }
+                    &mut n.node
+                })
+                .collect::<Vec<_>>()
+                .into_iter(),
+        }
+    }
+
+    /// Drops the internal storage at once.
+    fn clear(&mut self) {
+        self.nodes.clear();
+        self.edges.clear();
+        self.node_index.clear();
+        self.edge_index.clear();
+    }
+
+    /// Every edge is stamped as updated at the current epoch, if set.
+    fn edges_mut(&mut self) -> EdgesMut<'_, Self::Edge> {
+        let epoch = self.epoch;
+        EdgesMut {
+            range: self
+                .edges
+                .values_mut()
+                .map(|e| {
+                    if let Some(now) = epoch {
+                        e.edge.data.timestamps.touch(now);
+                    }
+                    &mut e.edge
+                })
+                .collect::<Vec<_>>()
+                .into_iter(),
+        }
+    }
+}
+
+impl<NodeId, W> GraphDataWriter for ArenaGraph<NodeId, W>
+where
+    NodeId: Ord + Clone,
+    W: Clone,
+{
+    fn edge_data_mut(&mut self, id: &EdgeId) -> Option<&mut EdgeData<W>> {
+        let epoch = self.epoch;
+        let index = self.edge_index.get(id)?;
+        self.edges.get_mut(*index).map(|e| {
+            if let Some(now) = epoch {
+                e.edge.data.timestamps.touch(now);
+            }
+            &mut e.edge.data
+        })
+    }
+
+    fn node_data_mut(&mut self, id: &NodeId) -> Option<&mut NodeData<W>> {
+        let epoch = self.epoch;
+        let index = self.node_index.get(id)?;
+        self.nodes.get_mut(*index).map(|n| {
+            if let Some(now) = epoch {
+                n.node.data.timestamps.touch(now);
+            }
+            &mut n.node.data
+        })
+    }
+}
+
+impl<NodeId: Ord + Clone, W: Clone> LedgerGraph for ArenaGraph<NodeId, W> {}
+
+impl<NodeId: Ord, W> ArenaGraph<NodeId, W> {
+    /// The properties of a node, to modify them.
+    pub fn node_properties_mut(&mut self, id: &NodeId) -> Option<&mut Properties> {
+        let index = self.node_index.get(id)?;
+        self.nodes.get_mut(*index).map(|n| &mut n.node.properties)
+    }
+
+    /// The properties of an edge, to modify them.
+    pub fn edge_properties_mut(&mut self, id: &EdgeId) -> Option<&mut Properties> {
+        let index = self.edge_index.get(id)?;
+        self.edges.get_mut(*index).map(|e| &mut e.edge.properties)
+    }
+}
+
+impl<NodeId, W> IntoGraphParts for ArenaGraph<NodeId, W>
+where
+    NodeId: Ord + Clone,
+    W: Clone,
+{
+    fn into_parts(self) -> (IntoNodes<Self::Node>, IntoEdges<Self::Edge>) {
+        (
+            IntoNodes {
+                range: self
+                    .nodes
+                    .into_values()
+                    .map(|n| n.node)
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            },
+            IntoEdges {
+                range: self
+                    .edges
+                    .into_values()
+                    .map(|e| e.edge)
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            },
+        )
+    }
+}
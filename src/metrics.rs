@@ -0,0 +1,85 @@
+//! Exporting graph and algorithm metrics through the `metrics` facade, to be
+//! scraped by eg. a Prometheus exporter installed by the application.
+//!
+//! Metrics are labelled with the layer they describe. Counters are totals:
+//! rates, such as mutations per second, are left to the metrics backend.
+
+use ::metrics::{counter, gauge, histogram};
+
+use super::progress::{ExecutionStats, ProgressSink};
+use super::watch::{GraphEvent, Subscription};
+use super::{Direction, Graph, GraphObject, Layer};
+
+/// Gauge of the number of nodes.
+pub const NODES: &str = "oscoin_graph_nodes";
+/// Gauge of the number of edges.
+pub const EDGES: &str = "oscoin_graph_edges";
+/// Counter of mutations, labelled by `kind`, eg. `node_added`.
+pub const MUTATIONS: &str = "oscoin_graph_mutations_total";
+/// Histogram of algorithm execution durations, in seconds.
+pub const ALGORITHM_DURATION: &str = "oscoin_graph_algorithm_duration_seconds";
+/// Counter of random walks simulated by algorithms.
+pub const WALKS: &str = "oscoin_graph_walks_total";
+/// Counter of algorithm iterations.
+pub const ITERATIONS: &str = "oscoin_graph_iterations_total";
+
+/// Records the metrics of a graph layer.
+///
+/// Mutations are counted from the events of a `watch::Subscription`, graph
+/// sizes are sampled with `record_graph`, eg. after every checkpoint, and
+/// algorithm executions are recorded by passing the observer as their
+/// `ProgressSink`.
+#[derive(Debug, Clone)]
+pub struct MetricsObserver {
+    layer: String,
+}
+
+impl MetricsObserver {
+    /// Record metrics labelled with the given layer.
+    pub fn new(layer: &Layer) -> Self {
+        MetricsObserver {
+            layer: layer.to_string(),
+        }
+    }
+
+    /// Count a mutation.
+    pub fn record_event<NodeId, EdgeId>(&self, event: &GraphEvent<NodeId, EdgeId>) {
+        let kind = match event {
+            GraphEvent::NodeAdded(_) => "node_added",
+            GraphEvent::NodeRemoved(_) => "node_removed",
+            GraphEvent::NodeUpdated(_) => "node_updated",
+            GraphEvent::EdgeAdded { .. } => "edge_added",
+            GraphEvent::EdgeRemoved(_) => "edge_removed",
+            GraphEvent::EdgeUpdated(_) => "edge_updated",
+        };
+        counter!(MUTATIONS, "layer" => self.layer.clone(), "kind" => kind).increment(1);
+    }
+
+    /// Count the pending events of a subscription, without blocking.
+    /// Returns the number of events counted.
+    pub fn drain<NodeId, EdgeId>(&self, subscription: &Subscription<NodeId, EdgeId>) -> usize {
+        subscription
+            .pending()
+            .map(|event| self.record_event(&event))
+            .count()
+    }
+
+    /// Sample the number of nodes and edges of the graph.
+    pub fn record_graph<G: Graph>(&self, graph: &G) {
+        let (nodes, edges) = graph.nodes().fold((0, 0), |(nodes, edges), n| {
+            let out = graph.edges_directed(n.id(), Direction::Outgoing).len();
+            (nodes + 1, edges + out)
+        });
+        gauge!(NODES, "layer" => self.layer.clone()).set(nodes as f64);
+        gauge!(EDGES, "layer" => self.layer.clone()).set(edges as f64);
+    }
+}
+
+impl ProgressSink for MetricsObserver {
+    fn on_stats(&mut self, stats: &ExecutionStats) {
+        histogram!(ALGORITHM_DURATION, "layer" => self.layer.clone())
+            .record(stats.elapsed.as_secs_f64());
+        counter!(WALKS, "layer" => self.layer.clone()).increment(stats.walks);
+        counter!(ITERATIONS, "layer" => self.layer.clone()).increment(stats.iterations);
+    }
+}
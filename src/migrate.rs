@@ -0,0 +1,204 @@
+//! Upgrading persisted graphs across changes of `types::SCHEMA_VERSION`.
+//!
+//! Each `Migration` upgrades a graph from one schema version to a later one,
+//! in place. A `Migrator` chains the registered migrations to bring a graph
+//! loaded from an old snapshot or store up to the current schema, eg.
+//!
+//! ```ignore
+//! let graph = map_graph(&snapshot, DefaultBuilder::new(), |n| ..., |e| ...);
+//! migrator.migrate(&mut graph, snapshot.schema_version())?;
+//! ```
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::types::SCHEMA_VERSION;
+use super::GraphWriter;
+
+/// An in place upgrade of graphs from one schema version to another.
+pub trait Migration<G: GraphWriter> {
+    /// The schema version of the graphs this migration applies to.
+    #[allow(clippy::wrong_self_convention)]
+    fn from_version(&self) -> u32;
+
+    /// The schema version of the graphs once migrated.
+    fn to_version(&self) -> u32;
+
+    /// Upgrade the graph. On failure, returns the reason, and the graph may
+    /// have been partially upgraded.
+    fn apply(&self, graph: &mut G) -> Result<(), String>;
+}
+
+/// A migration running a function.
+pub struct FnMigration<F> {
+    from: u32,
+    to: u32,
+    f: F,
+}
+
+impl<F> FnMigration<F> {
+    /// A migration from schema version `from` to `to`, applying `f`.
+    pub fn new(from: u32, to: u32, f: F) -> Self {
+        FnMigration { from, to, f }
+    }
+}
+
+impl<G, F> Migration<G> for FnMigration<F>
+where
+    G: GraphWriter,
+    F: Fn(&mut G) -> Result<(), String>,
+{
+    fn from_version(&self) -> u32 {
+        self.from
+    }
+
+    fn to_version(&self) -> u32 {
+        self.to
+    }
+
+    fn apply(&self, graph: &mut G) -> Result<(), String> {
+        (self.f)(graph)
+    }
+}
+
+/// A failure to migrate a graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationError {
+    /// The graph has a newer schema than the target version; graphs are
+    /// never downgraded.
+    Newer {
+        /// The schema version of the graph.
+        version: u32,
+        /// The target schema version.
+        target: u32,
+    },
+    /// No registered migration upgrades graphs of the given version.
+    NoPath {
+        /// The version no migration starts from.
+        version: u32,
+        /// The target schema version.
+        target: u32,
+    },
+    /// A migration failed. The graph is left at version `from`, possibly
+    /// partially upgraded to `to`.
+    Failed { from: u32, to: u32, reason: String },
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MigrationError::Newer { version, target } => write!(
+                f,
+                "graph schema version {} is newer than {}",
+                version, target
+            ),
+            MigrationError::NoPath { version, target } => write!(
+                f,
+                "no migration from schema version {} towards {}",
+                version, target
+            ),
+            MigrationError::Failed { from, to, reason } => write!(
+                f,
+                "migration from schema version {} to {} failed: {}",
+                from, to, reason
+            ),
+        }
+    }
+}
+
+impl core::error::Error for MigrationError {}
+
+/// Runs a chain of migrations to upgrade graphs to a schema version.
+pub struct Migrator<G> {
+    migrations: Vec<Box<dyn Migration<G>>>,
+}
+
+impl<G: GraphWriter> Migrator<G> {
+    /// A migrator without migrations.
+    pub fn new() -> Self {
+        Migrator {
+            migrations: Vec::new(),
+        }
+    }
+
+    /// Register a migration.
+    pub fn with<M: Migration<G> + 'static>(mut self, migration: M) -> Self {
+        self.register(migration);
+        self
+    }
+
+    /// Register a migration. Migrations that don't upgrade, ie. whose target
+    /// version isn't above their source version, are ignored.
+    pub fn register<M: Migration<G> + 'static>(&mut self, migration: M) {
+        if migration.to_version() > migration.from_version() {
+            self.migrations.push(Box::new(migration));
+        }
+    }
+
+    /// The migrations upgrading graphs from `version` to `target`, in the
+    /// order they apply. At every step, the migration reaching the furthest
+    /// without going past `target` is picked.
+    pub fn plan(
+        &self,
+        version: u32,
+        target: u32,
+    ) -> Result<Vec<&dyn Migration<G>>, MigrationError> {
+        if version > target {
+            return Err(MigrationError::Newer { version, target });
+        }
+        let mut plan = Vec::new();
+        let mut current = version;
+
+        while current < target {
+            let next = self
+                .migrations
+                .iter()
+                .filter(|m| m.from_version() == current && m.to_version() <= target)
+                .max_by_key(|m| m.to_version())
+                .ok_or(MigrationError::NoPath {
+                    version: current,
+                    target,
+                })?;
+
+            current = next.to_version();
+            plan.push(next.as_ref());
+        }
+        Ok(plan)
+    }
+
+    /// Upgrade a graph of schema `version` to `target`, returning the number
+    /// of migrations applied. Nothing is applied unless a full chain of
+    /// migrations exists.
+    pub fn migrate_to(
+        &self,
+        graph: &mut G,
+        version: u32,
+        target: u32,
+    ) -> Result<usize, MigrationError> {
+        let plan = self.plan(version, target)?;
+
+        for migration in plan.iter() {
+            migration
+                .apply(graph)
+                .map_err(|reason| MigrationError::Failed {
+                    from: migration.from_version(),
+                    to: migration.to_version(),
+                    reason,
+                })?;
+        }
+        Ok(plan.len())
+    }
+
+    /// Upgrade a graph of schema `version` to the current `SCHEMA_VERSION`.
+    pub fn migrate(&self, graph: &mut G, version: u32) -> Result<usize, MigrationError> {
+        self.migrate_to(graph, version, SCHEMA_VERSION)
+    }
+}
+
+impl<G: GraphWriter> Default for Migrator<G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
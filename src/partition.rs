@@ -0,0 +1,457 @@
+//! Splitting a graph into shards, to distribute walk simulation across
+//! machines.
+//!
+//! Nodes are first assigned to shards, by hash of their id or in balanced
+//! shards of connected nodes. The graph is then split into the subgraph of
+//! every shard and the cut edges between shards, which a `ShardedGraph`
+//! stitches back together:
+//!
+//! ```ignore
+//! let assignment = partition::assign(&graph, 4, Strategy::balanced());
+//! let parts: Partition<EdgeListGraph> = partition::split(&graph, &assignment, EdgeListGraph::new);
+//! let view = ShardedGraph::new(parts);
+//! ```
+
+use siphasher::sip::SipHasher24;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::hash::{Hash, Hasher};
+use std::iter;
+
+use super::{
+    Data, Direction, EdgeRefsOf, Edges, Graph, GraphBuilder, GraphObject, GraphWriter, Id, Nodes,
+};
+
+/// How nodes are assigned to shards.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strategy {
+    /// By hash of the node id, see `hash_shard`. Shards are balanced on
+    /// average and the shard of a node is known without the assignment, but
+    /// most edges are cut.
+    Hash,
+    /// Shards of equal size, grown from the lowest unassigned ids by adding
+    /// the node with the most neighbors in the shard, then refined by
+    /// greedily moving nodes to the shard of most of their neighbors, as the
+    /// initial partitioning and refinement phases of METIS do.
+    Balanced {
+        /// How much larger than the average shards may grow during
+        /// refinement, eg. `0.05` for 5%.
+        imbalance: f64,
+        /// The maximum number of refinement passes over the nodes.
+        passes: usize,
+    },
+}
+
+impl Strategy {
+    /// Balanced shards, within 5% of the average size.
+    pub fn balanced() -> Self {
+        Strategy::Balanced {
+            imbalance: 0.05,
+            passes: 8,
+        }
+    }
+}
+
+/// The shard of the node with the given id, among `shards`, by SipHash-2-4
+/// of the id. The shard is stable across runs, so that every machine
+/// computes the same one. Panics if `shards` is zero.
+pub fn hash_shard<NodeId: Hash>(id: &NodeId, shards: usize) -> usize {
+    assert!(shards > 0, "there must be at least one shard");
+    let mut hasher = SipHasher24::new();
+    id.hash(&mut hasher);
+    (hasher.finish() % shards as u64) as usize
+}
+
+/// The shard of every node of a graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assignment<NodeId> {
+    shards: usize,
+    nodes: BTreeMap<NodeId, usize>,
+}
+
+impl<NodeId: Ord> Assignment<NodeId> {
+    /// An assignment of the given nodes to shards. Panics if a shard is out
+    /// of range.
+    pub fn new<I: IntoIterator<Item = (NodeId, usize)>>(shards: usize, nodes: I) -> Self {
+        let nodes: BTreeMap<NodeId, usize> = nodes.into_iter().collect();
+        assert!(nodes.values().all(|s| *s < shards), "shard out of range");
+        Assignment { shards, nodes }
+    }
+
+    /// The number of shards.
+    pub fn shards(&self) -> usize {
+        self.shards
+    }
+
+    /// The shard of a node, if assigned.
+    pub fn shard_of(&self, id: &NodeId) -> Option<usize> {
+        self.nodes.get(id).copied()
+    }
+
+    /// The nodes and their shard, by ascending id.
+    pub fn iter(&self) -> impl Iterator<Item = (&NodeId, usize)> {
+        self.nodes.iter().map(|(id, s)| (id, *s))
+    }
+
+    /// The nodes of a shard, by ascending id.
+    pub fn nodes_of(&self, shard: usize) -> impl Iterator<Item = &NodeId> {
+        self.nodes
+            .iter()
+            .filter(move |(_, s)| **s == shard)
+            .map(|(id, _)| id)
+    }
+
+    /// The number of nodes of every shard.
+    pub fn sizes(&self) -> Vec<usize> {
+        let mut sizes = vec![0; self.shards];
+        for s in self.nodes.values() {
+            sizes[*s] += 1;
+        }
+        sizes
+    }
+
+    /// The number of edges of `graph` between nodes of different shards.
+    pub fn cut_size<G>(&self, graph: &G) -> usize
+    where
+        G: Graph,
+        G::Node: GraphObject<Id = NodeId>,
+    {
+        graph
+            .nodes()
+            .filter_map(|n| Some((n.id(), self.shard_of(n.id())?)))
+            .map(|(id, from)| {
+                graph
+                    .edges_directed(id, Direction::Outgoing)
+                    .iter()
+                    .filter(|e| self.shard_of(e.to).is_some_and(|to| to != from))
+                    .count()
+            })
+            .sum()
+    }
+}
+
+/// Assign the nodes of `graph` to `shards` shards. Panics if `shards` is
+/// zero.
+pub fn assign<G>(graph: &G, shards: usize, strategy: Strategy) -> Assignment<Id<G::Node>>
+where
+    G: Graph,
+    Id<G::Node>: Ord + Clone + Hash,
+{
+    assert!(shards > 0, "there must be at least one shard");
+    match strategy {
+        Strategy::Hash => Assignment::new(
+            shards,
+            graph
+                .nodes()
+                .map(|n| (n.id().clone(), hash_shard(n.id(), shards))),
+        ),
+        Strategy::Balanced { imbalance, passes } => balanced(graph, shards, imbalance, passes),
+    }
+}
+
+fn balanced<G>(graph: &G, shards: usize, imbalance: f64, passes: usize) -> Assignment<Id<G::Node>>
+where
+    G: Graph,
+    Id<G::Node>: Ord + Clone,
+{
+    const UNASSIGNED: usize = usize::MAX;
+
+    let mut ids: Vec<&Id<G::Node>> = graph.nodes().map(|n| n.id()).collect();
+    ids.sort();
+    let n = ids.len();
+
+    // Undirected adjacency by node index, without self-loops.
+    let adjacency: Vec<Vec<usize>> = ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| {
+            graph
+                .edges_directed(id, Direction::Both)
+                .iter()
+                .map(|e| if e.from == *id { e.to } else { e.from })
+                .filter_map(|other| ids.binary_search(&other).ok())
+                .filter(|j| *j != i)
+                .collect()
+        })
+        .collect();
+
+    // Grow every shard up to its share of the nodes, adding the frontier
+    // node with the most neighbors in the shard, or the lowest unassigned
+    // node once the frontier is exhausted.
+    let mut shard_of = vec![UNASSIGNED; n];
+    let mut sizes = vec![0; shards];
+    let mut seed = 0;
+    let mut gains = vec![0usize; n];
+    for (s, size) in sizes.iter_mut().enumerate() {
+        let share = n / shards + usize::from(s < n % shards);
+        let mut frontier = BinaryHeap::new();
+        while *size < share {
+            let i = match frontier.pop() {
+                // Stale entries are skipped, the node was pushed again with
+                // a higher gain.
+                Some((gain, Reverse(i))) if gain == gains[i] => i,
+                Some(_) => continue,
+                None => {
+                    while seed < n && shard_of[seed] != UNASSIGNED {
+                        seed += 1;
+                    }
+                    seed
+                }
+            };
+            if shard_of[i] != UNASSIGNED {
+                continue;
+            }
+            shard_of[i] = s;
+            *size += 1;
+            for j in &adjacency[i] {
+                if shard_of[*j] == UNASSIGNED {
+                    gains[*j] += 1;
+                    frontier.push((gains[*j], Reverse(*j)));
+                }
+            }
+        }
+        for i in frontier.into_iter().map(|(_, Reverse(i))| i) {
+            gains[i] = 0;
+        }
+    }
+
+    // Move nodes to the shard of most of their neighbors, as long as it
+    // reduces the cut and the shard has room.
+    // The largest size within the imbalance, allowing for the rounding of
+    // eg. `100.0 * 1.1`, but no less than the initial shares.
+    let allowed = (n as f64 / shards as f64) * (1.0 + imbalance.max(0.0));
+    let max_size = ((allowed + 1e-9).floor() as usize).max(n.div_ceil(shards));
+    let mut neighbors = vec![0usize; shards];
+    for _ in 0..passes {
+        let mut moved = false;
+        for i in 0..n {
+            for j in &adjacency[i] {
+                neighbors[shard_of[*j]] += 1;
+            }
+            let current = shard_of[i];
+            let best = (0..shards)
+                .filter(|s| *s != current && sizes[*s] < max_size)
+                .max_by_key(|s| (neighbors[*s], Reverse(*s)));
+            if let Some(best) = best.filter(|s| neighbors[*s] > neighbors[current]) {
+                sizes[current] -= 1;
+                sizes[best] += 1;
+                shard_of[i] = best;
+                moved = true;
+            }
+            for j in &adjacency[i] {
+                neighbors[shard_of[*j]] = 0;
+            }
+            neighbors[current] = 0;
+        }
+        if !moved {
+            break;
+        }
+    }
+
+    Assignment::new(shards, ids.into_iter().cloned().zip(shard_of))
+}
+
+/// A graph split into shards.
+pub struct Partition<S: Graph> {
+    /// The shard of every node.
+    pub assignment: Assignment<Id<S::Node>>,
+    /// The subgraph of every shard: its nodes, and the edges between them.
+    pub shards: Vec<S>,
+    /// The cut edges, between nodes of different shards, along with copies
+    /// of their nodes.
+    pub cut: S,
+}
+
+impl<S> Partition<S>
+where
+    S: Graph,
+    Id<S::Node>: Ord,
+{
+    /// The cut edges.
+    pub fn cut_edges(&self) -> Edges<'_, S::Edge> {
+        let edges: Vec<&S::Edge> = self
+            .cut
+            .nodes()
+            .flat_map(|n| self.cut.edges_directed(n.id(), Direction::Outgoing))
+            .filter_map(|e| self.cut.get_edge(e.id))
+            .collect();
+
+        Edges {
+            range: edges.into_iter(),
+        }
+    }
+}
+
+/// Split `graph` into the subgraph of every shard and the cut edges, in
+/// graphs built by the builders returned by `builder`. Nodes which aren't
+/// assigned are dropped, along with their edges.
+pub fn split<G, S, B, F>(
+    graph: &G,
+    assignment: &Assignment<Id<G::Node>>,
+    mut builder: F,
+) -> Partition<S>
+where
+    G: Graph,
+    S: GraphWriter,
+    S::Node: GraphObject<Id = Id<G::Node>, Data = Data<G::Node>>,
+    S::Edge: GraphObject<Id = Id<G::Edge>, Data = Data<G::Edge>>,
+    B: GraphBuilder<Graph = S>,
+    F: FnMut() -> B,
+    Id<G::Node>: Ord + Clone,
+    Id<G::Edge>: Clone,
+    Data<G::Node>: Clone,
+    Data<G::Edge>: Clone,
+{
+    let mut shards: Vec<S> = (0..assignment.shards())
+        .map(|_| builder().build())
+        .collect();
+    let mut cut = builder().build();
+
+    for node in graph.nodes() {
+        if let Some(s) = assignment.shard_of(node.id()) {
+            shards[s].add_node(node.id().clone(), node.data().clone());
+        }
+    }
+    for node in graph.nodes() {
+        let from = match assignment.shard_of(node.id()) {
+            Some(s) => s,
+            None => continue,
+        };
+        for e in graph.edges_directed(node.id(), Direction::Outgoing) {
+            match assignment.shard_of(e.to) {
+                Some(to) if to == from => {
+                    shards[from].add_edge(e.id.clone(), e.from, e.to, e.data.clone());
+                }
+                Some(_) => {
+                    for id in [e.from, e.to] {
+                        if cut.get_node(id).is_none() {
+                            if let Some(n) = graph.get_node(id) {
+                                cut.add_node(id.clone(), n.data().clone());
+                            }
+                        }
+                    }
+                    cut.add_edge(e.id.clone(), e.from, e.to, e.data.clone());
+                }
+                None => {}
+            }
+        }
+    }
+
+    Partition {
+        assignment: assignment.clone(),
+        shards,
+        cut,
+    }
+}
+
+/// A read-only view of a partitioned graph, as the graph it was split from.
+///
+/// Nodes are looked up in their shard, and their edges in their shard and
+/// among the cut edges. Edges are looked up in every shard in turn.
+pub struct ShardedGraph<S: Graph> {
+    partition: Partition<S>,
+}
+
+impl<S> ShardedGraph<S>
+where
+    S: Graph,
+    Id<S::Node>: Ord,
+{
+    /// Stitch the shards of a partition back together.
+    pub fn new(partition: Partition<S>) -> Self {
+        ShardedGraph { partition }
+    }
+
+    /// The partition viewed.
+    pub fn partition(&self) -> &Partition<S> {
+        &self.partition
+    }
+
+    /// Consume the view, returning the partition.
+    pub fn into_partition(self) -> Partition<S> {
+        self.partition
+    }
+
+    /// The shard of a node.
+    fn home(&self, id: &Id<S::Node>) -> Option<&S> {
+        let shard = self.partition.assignment.shard_of(id)?;
+        self.partition.shards.get(shard)
+    }
+}
+
+impl<S> Graph for ShardedGraph<S>
+where
+    S: Graph,
+    Id<S::Node>: Ord,
+{
+    type Node = S::Node;
+    type Edge = S::Edge;
+    type NodeData = S::NodeData;
+    type EdgeData = S::EdgeData;
+    type Weight = S::Weight;
+
+    fn get_node(&self, id: &Id<S::Node>) -> Option<&S::Node> {
+        self.home(id)?.get_node(id)
+    }
+
+    fn get_edge(&self, id: &Id<S::Edge>) -> Option<&S::Edge> {
+        self.partition
+            .shards
+            .iter()
+            .chain(iter::once(&self.partition.cut))
+            .find_map(|g| g.get_edge(id))
+    }
+
+    fn nodes(&self) -> Nodes<'_, S::Node> {
+        let nodes: Vec<&S::Node> = self
+            .partition
+            .shards
+            .iter()
+            .flat_map(|g| g.nodes())
+            .collect();
+
+        Nodes {
+            range: nodes.into_iter(),
+        }
+    }
+
+    fn neighbors(&self, node: &Id<S::Node>) -> Nodes<'_, S::Node> {
+        let outgoing = self.edges_directed(node, Direction::Outgoing);
+        let incoming = self.edges_directed(node, Direction::Incoming);
+        let nodes: Vec<&S::Node> = outgoing
+            .into_iter()
+            .map(|e| e.to)
+            .chain(incoming.into_iter().map(|e| e.from))
+            .filter_map(|id| self.get_node(id))
+            .collect();
+
+        Nodes {
+            range: nodes.into_iter(),
+        }
+    }
+
+    fn edges(&self, node: &Id<S::Node>) -> Edges<'_, S::Edge> {
+        let edges: Vec<&S::Edge> = match self.home(node) {
+            Some(home) => home
+                .edges(node)
+                .chain(self.partition.cut.edges(node))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        Edges {
+            range: edges.into_iter(),
+        }
+    }
+
+    fn edges_directed(&self, node: &Id<S::Node>, dir: Direction) -> EdgeRefsOf<'_, S> {
+        match self.home(node) {
+            Some(home) => {
+                let mut edges = home.edges_directed(node, dir);
+                edges.extend(self.partition.cut.edges_directed(node, dir));
+                edges
+            }
+            None => Vec::new(),
+        }
+    }
+}
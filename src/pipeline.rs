@@ -0,0 +1,226 @@
+//! Composition of graph algorithms.
+//!
+//! Osrank is naturally made of several phases, eg. pruning the graph and then
+//! ranking what remains. The combinators in this module chain algorithms into
+//! a single `GraphAlgorithm`: both stages share the annotator, draw from
+//! their own stream of the seed, and their contexts are kept side by side
+//! across executions.
+
+use std::fmt;
+
+use super::cancel::CancellationToken;
+use super::progress::ProgressSink;
+use super::rng::AlgorithmSeed;
+use super::{Graph, GraphAlgorithm, GraphAnnotator};
+
+/// Two algorithms run one after the other. Created with `AlgorithmExt::then`.
+#[derive(Debug, Clone)]
+pub struct Pipeline<First, Second> {
+    first: First,
+    second: Second,
+}
+
+/// Two algorithms run one after the other, where the second one is built
+/// from the output of the first. Created with `AlgorithmExt::then_with`.
+#[derive(Debug, Clone)]
+pub struct PipelineWith<First, F> {
+    first: First,
+    second: F,
+}
+
+/// The error of a two-stage pipeline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PipelineError<E1, E2> {
+    /// The first stage failed, and the second one wasn't run.
+    First(E1),
+    /// The second stage failed.
+    Second(E2),
+}
+
+impl<E1: fmt::Display, E2: fmt::Display> fmt::Display for PipelineError<E1, E2> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PipelineError::First(e) => write!(f, "first stage failed: {}", e),
+            PipelineError::Second(e) => write!(f, "second stage failed: {}", e),
+        }
+    }
+}
+
+impl<E1, E2> std::error::Error for PipelineError<E1, E2>
+where
+    E1: std::error::Error,
+    E2: std::error::Error,
+{
+}
+
+/// Combinators available on all algorithms.
+pub trait AlgorithmExt: Sized {
+    /// Run `next` after this algorithm. The output of the pipeline is the
+    /// pair of both outputs.
+    fn then<Next>(self, next: Next) -> Pipeline<Self, Next> {
+        Pipeline {
+            first: self,
+            second: next,
+        }
+    }
+
+    /// Run the algorithm returned by `next` after this algorithm, passing it
+    /// this algorithm's output. The output of the pipeline is the pair of
+    /// both outputs.
+    fn then_with<F>(self, next: F) -> PipelineWith<Self, F> {
+        PipelineWith {
+            first: self,
+            second: next,
+        }
+    }
+}
+
+impl<T> AlgorithmExt for T {}
+
+impl<G, A, First, Second> GraphAlgorithm<G, A> for Pipeline<First, Second>
+where
+    G: Graph,
+    A: GraphAnnotator<Annotation = First::Annotation>,
+    First: GraphAlgorithm<G, A>,
+    Second: GraphAlgorithm<G, A, RngSeed = First::RngSeed, Annotation = First::Annotation>,
+{
+    type Context = (First::Context, Second::Context);
+    type Output = (First::Output, Second::Output);
+    type Error = PipelineError<First::Error, Second::Error>;
+    /// The stages are run with streams 0 and 1 of the seed, as split by
+    /// `AlgorithmSeed::split`.
+    type RngSeed = First::RngSeed;
+    type Annotation = First::Annotation;
+
+    fn execute(
+        &self,
+        context: &mut Self::Context,
+        graph: &G,
+        annotator: &mut A,
+        seed: Self::RngSeed,
+    ) -> Result<Self::Output, Self::Error> {
+        let first = self
+            .first
+            .execute(&mut context.0, graph, annotator, seed.split(0))
+            .map_err(PipelineError::First)?;
+        let second = self
+            .second
+            .execute(&mut context.1, graph, annotator, seed.split(1))
+            .map_err(PipelineError::Second)?;
+
+        Ok((first, second))
+    }
+
+    fn execute_with_progress(
+        &self,
+        context: &mut Self::Context,
+        graph: &G,
+        annotator: &mut A,
+        seed: Self::RngSeed,
+        progress: &mut dyn ProgressSink,
+    ) -> Result<Self::Output, Self::Error> {
+        let first = self
+            .first
+            .execute_with_progress(&mut context.0, graph, annotator, seed.split(0), progress)
+            .map_err(PipelineError::First)?;
+        let second = self
+            .second
+            .execute_with_progress(&mut context.1, graph, annotator, seed.split(1), progress)
+            .map_err(PipelineError::Second)?;
+
+        Ok((first, second))
+    }
+
+    fn execute_with(
+        &self,
+        context: &mut Self::Context,
+        graph: &G,
+        annotator: &mut A,
+        seed: Self::RngSeed,
+        token: &CancellationToken,
+    ) -> Result<Self::Output, Self::Error> {
+        let first = self
+            .first
+            .execute_with(&mut context.0, graph, annotator, seed.split(0), token)
+            .map_err(PipelineError::First)?;
+        let second = self
+            .second
+            .execute_with(&mut context.1, graph, annotator, seed.split(1), token)
+            .map_err(PipelineError::Second)?;
+
+        Ok((first, second))
+    }
+}
+
+impl<G, A, First, Second, F> GraphAlgorithm<G, A> for PipelineWith<First, F>
+where
+    G: Graph,
+    A: GraphAnnotator<Annotation = First::Annotation>,
+    First: GraphAlgorithm<G, A>,
+    Second: GraphAlgorithm<G, A, RngSeed = First::RngSeed, Annotation = First::Annotation>,
+    F: Fn(&First::Output) -> Second,
+{
+    type Context = (First::Context, Second::Context);
+    type Output = (First::Output, Second::Output);
+    type Error = PipelineError<First::Error, Second::Error>;
+    /// The stages are run with streams 0 and 1 of the seed, as split by
+    /// `AlgorithmSeed::split`.
+    type RngSeed = First::RngSeed;
+    type Annotation = First::Annotation;
+
+    fn execute(
+        &self,
+        context: &mut Self::Context,
+        graph: &G,
+        annotator: &mut A,
+        seed: Self::RngSeed,
+    ) -> Result<Self::Output, Self::Error> {
+        let first = self
+            .first
+            .execute(&mut context.0, graph, annotator, seed.split(0))
+            .map_err(PipelineError::First)?;
+        let second = (self.second)(&first)
+            .execute(&mut context.1, graph, annotator, seed.split(1))
+            .map_err(PipelineError::Second)?;
+
+        Ok((first, second))
+    }
+
+    fn execute_with_progress(
+        &self,
+        context: &mut Self::Context,
+        graph: &G,
+        annotator: &mut A,
+        seed: Self::RngSeed,
+        progress: &mut dyn ProgressSink,
+    ) -> Result<Self::Output, Self::Error> {
+        let first = self
+            .first
+            .execute_with_progress(&mut context.0, graph, annotator, seed.split(0), progress)
+            .map_err(PipelineError::First)?;
+        let second = (self.second)(&first)
+            .execute_with_progress(&mut context.1, graph, annotator, seed.split(1), progress)
+            .map_err(PipelineError::Second)?;
+
+        Ok((first, second))
+    }
+
+    fn execute_with(
+        &self,
+        context: &mut Self::Context,
+        graph: &G,
+        annotator: &mut A,
+        seed: Self::RngSeed,
+        token: &CancellationToken,
+    ) -> Result<Self::Output, Self::Error> {
+        let first = self
+            .first
+            .execute_with(&mut context.0, graph, annotator, seed.split(0), token)
+            .map_err(PipelineError::First)?;
+        let second = (self.second)(&first)
+            .execute_with(&mut context.1, graph, annotator, seed.split(1), token)
+            .map_err(PipelineError::Second)?;
+
+        Ok((first, second))
+    }
+}
@@ -0,0 +1,126 @@
+//! Progress reporting for long-running algorithms.
+
+use core::time::Duration;
+
+/// Statistics about an algorithm execution.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExecutionStats {
+    /// Number of random walks simulated, or accounted for.
+    pub walks: u64,
+    /// Number of iterations performed.
+    pub iterations: u64,
+    /// Time spent in the execution.
+    pub elapsed: Duration,
+    /// Estimate of the peak memory used by the execution, in bytes, not
+    /// counting the graph itself.
+    pub peak_memory: usize,
+}
+
+/// A receiver of progress reports from an algorithm execution.
+///
+/// Both methods do nothing by default, so that implementations can only pick
+/// the reports they are interested in.
+pub trait ProgressSink {
+    /// Called as the execution progresses, with the number of steps done out
+    /// of the total number of steps, if known. The meaning of a step depends on
+    /// the algorithm.
+    fn on_progress(&mut self, _step: u64, _total: Option<u64>) {}
+
+    /// Called once at the end of the execution.
+    fn on_stats(&mut self, _stats: &ExecutionStats) {}
+
+    /// Whether `on_stats` is interested in the statistics, so that the ones
+    /// which are costly or unavailable everywhere, like the elapsed time,
+    /// are only collected when needed. True by default.
+    fn wants_stats(&self) -> bool {
+        true
+    }
+}
+
+/// A `ProgressSink` which ignores all reports.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn wants_stats(&self) -> bool {
+        false
+    }
+}
+
+/// Closures only receive progress reports.
+impl<F> ProgressSink for F
+where
+    F: FnMut(u64, Option<u64>),
+{
+    fn on_progress(&mut self, step: u64, total: Option<u64>) {
+        self(step, total)
+    }
+
+    fn wants_stats(&self) -> bool {
+        false
+    }
+}
+
+/// Measures the time spent in an execution, for `ExecutionStats::elapsed`.
+///
+/// The clock is only read for sinks which want statistics, and where there
+/// is one: not without `std`, nor on `wasm32-unknown-unknown`, where reading
+/// it panics. The elapsed time is zero otherwise.
+pub(crate) struct Stopwatch(Option<Instant>);
+
+impl Stopwatch {
+    /// Start measuring the time of an execution reporting to `progress`.
+    pub(crate) fn start(progress: &dyn ProgressSink) -> Self {
+        Stopwatch(if progress.wants_stats() { now() } else { None })
+    }
+
+    /// The time elapsed since the start.
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.0.as_ref().map(elapsed).unwrap_or_default()
+    }
+}
+
+#[cfg(all(
+    feature = "std",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+use std::time::Instant;
+
+#[cfg(all(
+    feature = "std",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+fn now() -> Option<Instant> {
+    Some(Instant::now())
+}
+
+#[cfg(all(
+    feature = "std",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+fn elapsed(start: &Instant) -> Duration {
+    start.elapsed()
+}
+
+/// Stands for `std::time::Instant` where there is no clock.
+#[cfg(not(all(
+    feature = "std",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+)))]
+enum Instant {}
+
+#[cfg(not(all(
+    feature = "std",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+)))]
+fn now() -> Option<Instant> {
+    None
+}
+
+#[cfg(not(all(
+    feature = "std",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+)))]
+fn elapsed(start: &Instant) -> Duration {
+    match *start {}
+}
@@ -0,0 +1,195 @@
+//! Ad-hoc typed properties attached to graph objects, eg. a display name or
+//! the last epoch a node was seen, alongside their `Data`.
+//!
+//! Properties are looked up by `PropertyKey`, which carries the type of the
+//! value, so that keys are usually declared once as constants:
+//!
+//! ```ignore
+//! const LAST_SEEN: PropertyKey<u64> = PropertyKey::new("last_seen");
+//!
+//! node.properties_mut().set(&LAST_SEEN, epoch);
+//! ```
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use core::any::Any;
+use core::fmt;
+use core::marker::PhantomData;
+
+use super::GraphObject;
+
+/// The key of a property holding values of type `T`.
+pub struct PropertyKey<T> {
+    name: &'static str,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> PropertyKey<T> {
+    /// A key with the given name. Names are unique within a store: a value
+    /// set with a key replaces any value set with another key of the same
+    /// name.
+    pub const fn new(name: &'static str) -> Self {
+        PropertyKey {
+            name,
+            marker: PhantomData,
+        }
+    }
+
+    /// The name of the key.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl<T> Clone for PropertyKey<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for PropertyKey<T> {}
+
+impl<T> fmt::Debug for PropertyKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PropertyKey({:?})", self.name)
+    }
+}
+
+/// A value which can be stored as a property.
+pub trait PropertyValue: Any + fmt::Debug + Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+    fn clone_box(&self) -> Box<dyn PropertyValue>;
+    fn eq_dyn(&self, other: &dyn PropertyValue) -> bool;
+}
+
+impl<T> PropertyValue for T
+where
+    T: Any + Clone + PartialEq + fmt::Debug + Send + Sync,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn PropertyValue> {
+        Box::new(self.clone())
+    }
+
+    fn eq_dyn(&self, other: &dyn PropertyValue) -> bool {
+        other.as_any().downcast_ref::<T>() == Some(self)
+    }
+}
+
+/// A store of typed properties.
+pub trait PropertyStore {
+    /// The value of a property, if set with a key of the same type.
+    fn get<T: PropertyValue>(&self, key: &PropertyKey<T>) -> Option<&T>;
+
+    /// A mutable reference to the value of a property, if set with a key of
+    /// the same type.
+    fn get_mut<T: PropertyValue>(&mut self, key: &PropertyKey<T>) -> Option<&mut T>;
+
+    /// Set a property, returning its previous value if it had the same type.
+    fn set<T: PropertyValue>(&mut self, key: &PropertyKey<T>, value: T) -> Option<T>;
+
+    /// Remove a property, returning its value if it had the same type.
+    fn remove<T: PropertyValue>(&mut self, key: &PropertyKey<T>) -> Option<T>;
+}
+
+/// The default `PropertyStore`, a map from key names to boxed values.
+#[derive(Default)]
+pub struct Properties {
+    values: BTreeMap<&'static str, Box<dyn PropertyValue>>,
+}
+
+impl Properties {
+    /// An empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of properties set.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Whether no property is set.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The names of the properties set, in order.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.values.keys().copied()
+    }
+}
+
+impl PropertyStore for Properties {
+    fn get<T: PropertyValue>(&self, key: &PropertyKey<T>) -> Option<&T> {
+        self.values.get(key.name)?.as_any().downcast_ref()
+    }
+
+    fn get_mut<T: PropertyValue>(&mut self, key: &PropertyKey<T>) -> Option<&mut T> {
+        self.values.get_mut(key.name)?.as_any_mut().downcast_mut()
+    }
+
+    fn set<T: PropertyValue>(&mut self, key: &PropertyKey<T>, value: T) -> Option<T> {
+        let old = self.values.insert(key.name, Box::new(value))?;
+        old.into_any().downcast().ok().map(|v| *v)
+    }
+
+    fn remove<T: PropertyValue>(&mut self, key: &PropertyKey<T>) -> Option<T> {
+        let old = self.values.remove(key.name)?;
+        old.into_any().downcast().ok().map(|v| *v)
+    }
+}
+
+impl Clone for Properties {
+    fn clone(&self) -> Self {
+        Properties {
+            values: self
+                .values
+                .iter()
+                .map(|(name, value)| (*name, value.clone_box()))
+                .collect(),
+        }
+    }
+}
+
+impl PartialEq for Properties {
+    fn eq(&self, other: &Self) -> bool {
+        self.values.len() == other.values.len()
+            && self
+                .values
+                .iter()
+                .zip(other.values.iter())
+                .all(|((n1, v1), (n2, v2))| n1 == n2 && v1.eq_dyn(v2.as_ref()))
+    }
+}
+
+impl fmt::Debug for Properties {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.values.iter()).finish()
+    }
+}
+
+/// A graph object carrying properties besides its data.
+pub trait HasProperties: GraphObject {
+    /// The store holding the properties.
+    type Properties: PropertyStore;
+
+    /// The properties of the object.
+    fn properties(&self) -> &Self::Properties;
+
+    /// The properties of the object, to modify them.
+    fn properties_mut(&mut self) -> &mut Self::Properties;
+}
@@ -0,0 +1,488 @@
+//! Protocol buffer encodings of the graph types, for exchanging graph
+//! fragments and rank results, eg. over gRPC.
+//!
+//! The schema is in `proto/graph.proto`. Field tags are stable: fields may be
+//! added, but never renumbered or reused. Conversions from the crate types
+//! are infallible, while conversions back check that required fields are set
+//! and that enumerations are known.
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+
+use super::types::{self, EdgeTypeTag};
+use super::{Direction, Edge as _, Graph, GraphObject, Layer, Node as _};
+
+/// The version of the schema, sent in every `GraphChunk`.
+pub const VERSION: u32 = 1;
+
+/// A user node.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct User {
+    /// Contributions to all projects.
+    #[prost(uint32, tag = "1")]
+    pub contributions: u32,
+}
+
+/// A project node.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Project {
+    /// Contributions from all users.
+    #[prost(uint32, tag = "1")]
+    pub contributions: u32,
+}
+
+/// An organization node.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Org {
+    /// Contributions to the projects owned.
+    #[prost(uint32, tag = "1")]
+    pub contributions: u32,
+}
+
+/// A checkpoint node.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Checkpoint {
+    /// Contributions to the checkpointed version.
+    #[prost(uint32, tag = "1")]
+    pub contributions: u32,
+}
+
+/// The type of a node.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct NodeType {
+    /// The node kind, with its payload.
+    #[prost(oneof = "NodeKind", tags = "1, 2, 3, 4")]
+    pub kind: Option<NodeKind>,
+}
+
+/// The kinds of nodes.
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum NodeKind {
+    /// A user.
+    #[prost(message, tag = "1")]
+    User(User),
+    /// A project.
+    #[prost(message, tag = "2")]
+    Project(Project),
+    /// An organization.
+    #[prost(message, tag = "3")]
+    Org(Org),
+    /// A checkpoint.
+    #[prost(message, tag = "4")]
+    Checkpoint(Checkpoint),
+}
+
+/// The kinds of edges.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum EdgeKind {
+    /// Not set, always invalid.
+    Unspecified = 0,
+    /// See `types::EdgeType::ProjectToUserContribution`.
+    ProjectToUserContribution = 1,
+    /// See `types::EdgeType::UserToProjectContribution`.
+    UserToProjectContribution = 2,
+    /// See `types::EdgeType::ProjectToUserMembership`.
+    ProjectToUserMembership = 3,
+    /// See `types::EdgeType::UserToProjectMembership`.
+    UserToProjectMembership = 4,
+    /// See `types::EdgeType::Dependency`.
+    Dependency = 5,
+    /// See `types::EdgeType::OrgToProjectOwnership`.
+    OrgToProjectOwnership = 6,
+    /// See `types::EdgeType::ProjectToOrgOwnership`.
+    ProjectToOrgOwnership = 7,
+    /// See `types::EdgeType::Donation`.
+    Donation = 8,
+    /// See `types::EdgeType::Custom`.
+    Custom = 9,
+}
+
+/// The type of an edge.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct EdgeType {
+    /// The edge kind.
+    #[prost(enumeration = "EdgeKind", tag = "1")]
+    pub kind: i32,
+    /// The contributions, or the amount of donations, for kinds which have
+    /// some.
+    #[prost(uint32, tag = "2")]
+    pub contributions: u32,
+    /// The tag of custom edges.
+    #[prost(string, tag = "3")]
+    pub custom_tag: String,
+}
+
+/// The rank of a node.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct NodeRank {
+    /// The rank.
+    #[prost(double, tag = "1")]
+    pub rank: f64,
+}
+
+/// See `types::DampingFactors`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct DampingFactors {
+    /// Continuation probability on project nodes.
+    #[prost(double, tag = "1")]
+    pub project: f64,
+    /// Continuation probability on user nodes.
+    #[prost(double, tag = "2")]
+    pub account: f64,
+}
+
+/// The weight of an edge kind.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct EdgeWeight {
+    /// The edge kind.
+    #[prost(enumeration = "EdgeKind", tag = "1")]
+    pub kind: i32,
+    /// Its weight.
+    #[prost(double, tag = "2")]
+    pub weight: f64,
+    /// The tag of custom edges.
+    #[prost(string, tag = "3")]
+    pub custom_tag: String,
+}
+
+/// See `types::HyperParameters`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct HyperParameters {
+    /// The pruning threshold.
+    #[prost(double, tag = "1")]
+    pub pruning_threshold: f64,
+    /// The damping factors.
+    #[prost(message, optional, tag = "2")]
+    pub damping_factors: Option<DampingFactors>,
+    /// The 'R' value.
+    #[prost(uint32, tag = "3")]
+    pub r_value: u32,
+    /// Weights by edge kind.
+    #[prost(message, repeated, tag = "4")]
+    pub edge_weights: Vec<EdgeWeight>,
+    /// The maximum share of outgoing weight of an edge, if capped.
+    #[prost(double, optional, tag = "5")]
+    pub max_edge_weight_share: Option<f64>,
+    /// The maximum rank of a user, if capped.
+    #[prost(double, optional, tag = "6")]
+    pub max_user_rank: Option<f64>,
+    /// The factor ranks decay by every epoch, if they decay.
+    #[prost(double, optional, tag = "7")]
+    pub rank_decay: Option<f64>,
+}
+
+/// A node, with `u64` id.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Node {
+    /// The node id.
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    /// The node type.
+    #[prost(message, optional, tag = "2")]
+    pub node_type: Option<NodeType>,
+    /// The node rank.
+    #[prost(message, optional, tag = "3")]
+    pub rank: Option<NodeRank>,
+}
+
+/// An edge, with `u64` ids.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Edge {
+    /// The edge id.
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    /// The source node.
+    #[prost(uint64, tag = "2")]
+    pub from: u64,
+    /// The target node.
+    #[prost(uint64, tag = "3")]
+    pub to: u64,
+    /// The edge type.
+    #[prost(message, optional, tag = "4")]
+    pub edge_type: Option<EdgeType>,
+    /// The edge weight.
+    #[prost(double, tag = "5")]
+    pub weight: f64,
+}
+
+/// A fragment of a graph. Large graphs are sent as a sequence of chunks with
+/// increasing indices, the last one having `last` set.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GraphChunk {
+    /// The schema version, `VERSION`.
+    #[prost(uint32, tag = "1")]
+    pub version: u32,
+    /// The layer of the graph.
+    #[prost(string, tag = "2")]
+    pub layer: String,
+    /// The position of the chunk in its sequence.
+    #[prost(uint64, tag = "3")]
+    pub index: u64,
+    /// Whether this is the last chunk of its sequence.
+    #[prost(bool, tag = "4")]
+    pub last: bool,
+    /// Nodes.
+    #[prost(message, repeated, tag = "5")]
+    pub nodes: Vec<Node>,
+    /// Edges.
+    #[prost(message, repeated, tag = "6")]
+    pub edges: Vec<Edge>,
+}
+
+/// A message which can't be converted to the crate types.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProtoError {
+    /// A required field is not set.
+    MissingField(&'static str),
+    /// An edge kind is unknown, or unspecified.
+    UnknownEdgeKind(i32),
+}
+
+impl fmt::Display for ProtoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProtoError::MissingField(field) => write!(f, "missing field `{}`", field),
+            ProtoError::UnknownEdgeKind(kind) => write!(f, "unknown edge kind {}", kind),
+        }
+    }
+}
+
+impl std::error::Error for ProtoError {}
+
+impl From<&types::NodeType> for NodeType {
+    fn from(node_type: &types::NodeType) -> Self {
+        let kind = match node_type {
+            types::NodeType::User {
+                contributions_to_all_projects,
+            } => NodeKind::User(User {
+                contributions: *contributions_to_all_projects,
+            }),
+            types::NodeType::Project {
+                contributions_from_all_users,
+            } => NodeKind::Project(Project {
+                contributions: *contributions_from_all_users,
+            }),
+            types::NodeType::Org {
+                contributions_to_owned_projects,
+            } => NodeKind::Org(Org {
+                contributions: *contributions_to_owned_projects,
+            }),
+            types::NodeType::Checkpoint { contributions } => NodeKind::Checkpoint(Checkpoint {
+                contributions: *contributions,
+            }),
+        };
+        NodeType { kind: Some(kind) }
+    }
+}
+
+impl TryFrom<NodeType> for types::NodeType {
+    type Error = ProtoError;
+
+    fn try_from(node_type: NodeType) -> Result<Self, ProtoError> {
+        match node_type.kind {
+            Some(NodeKind::User(u)) => Ok(types::NodeType::User {
+                contributions_to_all_projects: u.contributions,
+            }),
+            Some(NodeKind::Project(p)) => Ok(types::NodeType::Project {
+                contributions_from_all_users: p.contributions,
+            }),
+            Some(NodeKind::Org(o)) => Ok(types::NodeType::Org {
+                contributions_to_owned_projects: o.contributions,
+            }),
+            Some(NodeKind::Checkpoint(c)) => Ok(types::NodeType::Checkpoint {
+                contributions: c.contributions,
+            }),
+            None => Err(ProtoError::MissingField("NodeType.kind")),
+        }
+    }
+}
+
+impl From<&EdgeTypeTag> for EdgeKind {
+    fn from(tag: &EdgeTypeTag) -> Self {
+        match tag {
+            EdgeTypeTag::ProjectToUserContribution => EdgeKind::ProjectToUserContribution,
+            EdgeTypeTag::UserToProjectContribution => EdgeKind::UserToProjectContribution,
+            EdgeTypeTag::ProjectToUserMembership => EdgeKind::ProjectToUserMembership,
+            EdgeTypeTag::UserToProjectMembership => EdgeKind::UserToProjectMembership,
+            EdgeTypeTag::Dependency => EdgeKind::Dependency,
+            EdgeTypeTag::OrgToProjectOwnership => EdgeKind::OrgToProjectOwnership,
+            EdgeTypeTag::ProjectToOrgOwnership => EdgeKind::ProjectToOrgOwnership,
+            EdgeTypeTag::Donation => EdgeKind::Donation,
+            EdgeTypeTag::Custom(_) => EdgeKind::Custom,
+        }
+    }
+}
+
+/// The tag of custom edge types, empty for the others.
+fn custom_tag(tag: &EdgeTypeTag) -> String {
+    match tag {
+        EdgeTypeTag::Custom(tag) => tag.clone(),
+        _ => String::new(),
+    }
+}
+
+/// Decode an edge kind, along with the tag of custom kinds, rejecting
+/// unknown and unspecified kinds.
+fn edge_type_tag(kind: i32, custom_tag: String) -> Result<EdgeTypeTag, ProtoError> {
+    match EdgeKind::try_from(kind) {
+        Ok(EdgeKind::ProjectToUserContribution) => Ok(EdgeTypeTag::ProjectToUserContribution),
+        Ok(EdgeKind::UserToProjectContribution) => Ok(EdgeTypeTag::UserToProjectContribution),
+        Ok(EdgeKind::ProjectToUserMembership) => Ok(EdgeTypeTag::ProjectToUserMembership),
+        Ok(EdgeKind::UserToProjectMembership) => Ok(EdgeTypeTag::UserToProjectMembership),
+        Ok(EdgeKind::Dependency) => Ok(EdgeTypeTag::Dependency),
+        Ok(EdgeKind::OrgToProjectOwnership) => Ok(EdgeTypeTag::OrgToProjectOwnership),
+        Ok(EdgeKind::ProjectToOrgOwnership) => Ok(EdgeTypeTag::ProjectToOrgOwnership),
+        Ok(EdgeKind::Donation) => Ok(EdgeTypeTag::Donation),
+        Ok(EdgeKind::Custom) => Ok(EdgeTypeTag::Custom(custom_tag)),
+        Ok(EdgeKind::Unspecified) | Err(_) => Err(ProtoError::UnknownEdgeKind(kind)),
+    }
+}
+
+impl From<&types::EdgeType> for EdgeType {
+    fn from(edge_type: &types::EdgeType) -> Self {
+        let tag = edge_type.to_tag();
+
+        EdgeType {
+            kind: EdgeKind::from(&tag) as i32,
+            contributions: edge_type.value(),
+            custom_tag: custom_tag(&tag),
+        }
+    }
+}
+
+impl TryFrom<EdgeType> for types::EdgeType {
+    type Error = ProtoError;
+
+    fn try_from(edge_type: EdgeType) -> Result<Self, ProtoError> {
+        let tag = edge_type_tag(edge_type.kind, edge_type.custom_tag)?;
+
+        Ok(types::EdgeType::from_tag(tag, edge_type.contributions))
+    }
+}
+
+impl<W: Clone + Into<f64>> From<&types::NodeRank<W>> for NodeRank {
+    fn from(rank: &types::NodeRank<W>) -> Self {
+        NodeRank {
+            rank: rank.rank.clone().into(),
+        }
+    }
+}
+
+impl From<NodeRank> for types::NodeRank<f64> {
+    fn from(rank: NodeRank) -> Self {
+        types::NodeRank { rank: rank.rank }
+    }
+}
+
+impl<W: Clone + Into<f64>> From<&types::HyperParameters<W>> for HyperParameters {
+    fn from(params: &types::HyperParameters<W>) -> Self {
+        let mut edge_weights: Vec<EdgeWeight> = params
+            .edge_weights
+            .iter()
+            .map(|(tag, w)| EdgeWeight {
+                kind: EdgeKind::from(tag) as i32,
+                weight: w.clone().into(),
+                custom_tag: custom_tag(tag),
+            })
+            .collect();
+        // Sort for a deterministic encoding.
+        edge_weights.sort_by(|a, b| (a.kind, &a.custom_tag).cmp(&(b.kind, &b.custom_tag)));
+
+        HyperParameters {
+            pruning_threshold: params.pruning_threshold.clone().into(),
+            damping_factors: Some(DampingFactors {
+                project: params.damping_factors.project,
+                account: params.damping_factors.account,
+            }),
+            r_value: params.r_value,
+            edge_weights,
+            max_edge_weight_share: params.max_edge_weight_share.clone().map(Into::into),
+            max_user_rank: params.max_user_rank.clone().map(Into::into),
+            rank_decay: params.rank_decay.clone().map(Into::into),
+        }
+    }
+}
+
+impl TryFrom<HyperParameters> for types::HyperParameters<f64> {
+    type Error = ProtoError;
+
+    fn try_from(params: HyperParameters) -> Result<Self, ProtoError> {
+        let damping = params
+            .damping_factors
+            .ok_or(ProtoError::MissingField("HyperParameters.damping_factors"))?;
+        let edge_weights = params
+            .edge_weights
+            .into_iter()
+            .map(|w| Ok((edge_type_tag(w.kind, w.custom_tag)?, w.weight)))
+            .collect::<Result<BTreeMap<_, _>, ProtoError>>()?;
+
+        Ok(types::HyperParameters {
+            pruning_threshold: params.pruning_threshold,
+            damping_factors: types::DampingFactors {
+                project: damping.project,
+                account: damping.account,
+            },
+            r_value: params.r_value,
+            edge_weights,
+            max_edge_weight_share: params.max_edge_weight_share,
+            max_user_rank: params.max_user_rank,
+            rank_decay: params.rank_decay,
+        })
+    }
+}
+
+/// Split a graph into chunks of at most `chunk_size` nodes each, along with
+/// their outgoing edges. An empty graph yields a single, empty chunk.
+pub fn graph_chunks<G, W>(graph: &G, layer: &Layer, chunk_size: usize) -> Vec<GraphChunk>
+where
+    G: Graph<NodeData = types::NodeData<W>, Weight = W>,
+    G::Node: GraphObject<Id = u64>,
+    G::Edge: GraphObject<Id = u64>,
+    W: Clone + Into<f64>,
+{
+    let nodes: Vec<&G::Node> = graph.nodes().collect();
+    let mut chunks: Vec<GraphChunk> = nodes
+        .chunks(chunk_size.max(1))
+        .enumerate()
+        .map(|(i, nodes)| GraphChunk {
+            version: VERSION,
+            layer: layer.to_string(),
+            index: i as u64,
+            last: false,
+            nodes: nodes
+                .iter()
+                .map(|n| Node {
+                    id: *n.id(),
+                    node_type: Some(n.node_type().into()),
+                    rank: Some((&n.data().rank).into()),
+                })
+                .collect(),
+            edges: nodes
+                .iter()
+                .flat_map(|n| graph.edges_directed(n.id(), Direction::Outgoing))
+                .filter_map(|e| graph.get_edge(e.id))
+                .map(|e| Edge {
+                    id: *e.id(),
+                    from: *e.source(),
+                    to: *e.target(),
+                    edge_type: Some(e.edge_type().into()),
+                    weight: e.weight().into(),
+                })
+                .collect(),
+        })
+        .collect();
+
+    if chunks.is_empty() {
+        chunks.push(GraphChunk {
+            version: VERSION,
+            layer: layer.to_string(),
+            ..GraphChunk::default()
+        });
+    }
+    if let Some(last) = chunks.last_mut() {
+        last.last = true;
+    }
+    chunks
+}
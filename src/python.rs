@@ -0,0 +1,415 @@
+//! Python bindings, to explore ranks and tune hyperparameters from notebooks.
+//!
+//! The module exposes the reference graph implementation as `Graph`, the
+//! git importers as methods of the graph, and the rank algorithms as
+//! functions returning dictionaries of scores by node id. Node and edge
+//! types are passed in their `Display` format, eg. `"user(3)"` or
+//! `"dependency"`.
+//!
+//! The extension module is built with eg. `maturin build --features python`.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::marker::PhantomData;
+
+use pyo3::exceptions::{PyIOError, PyKeyError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+
+use super::algorithms::centrality::{Betweenness, DegreeCentrality};
+use super::algorithms::hits::Hits;
+use super::algorithms::prune::{Prune, PruneContext};
+use super::importers::git::{
+    import_contributions, read_contributions, read_git_log, Contributions,
+};
+use super::importers::{ImportError, SipMapping};
+use super::mem::EdgeListGraph;
+use super::types::{
+    AlgorithmError, ContributionLedger, DampingFactors, EdgeData, EdgeType, EdgeTypeTag,
+    HyperParameters, NodeData, NodeRank, NodeType,
+};
+use super::weights::{
+    assign_weights, cap_edge_weight_shares, normalize_outgoing_weights, ContributionWeight,
+};
+use super::{
+    Direction, Edge, Graph, GraphAlgorithm, GraphAnnotator, GraphDataWriter, GraphObject,
+    GraphWriter,
+};
+
+/// Ignores annotations: scores are returned as dictionaries instead.
+struct Discard<T>(PhantomData<T>);
+
+impl<T> GraphAnnotator for Discard<T> {
+    type Annotation = (u64, T);
+    type Error = std::convert::Infallible;
+
+    fn annotate_graph(&mut self, _note: Self::Annotation) {}
+}
+
+fn discard<T>() -> Discard<T> {
+    Discard(PhantomData)
+}
+
+fn value_error<E: ToString>(err: E) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+fn algorithm_error(err: AlgorithmError) -> PyErr {
+    PyRuntimeError::new_err(err.to_string())
+}
+
+fn import_error(err: ImportError) -> PyErr {
+    match err {
+        ImportError::Io(err) => PyIOError::new_err(err.to_string()),
+        other => value_error(other),
+    }
+}
+
+/// The hyperparameters of the rank algorithms.
+#[pyclass(name = "HyperParameters")]
+pub struct PyHyperParameters {
+    params: HyperParameters<f64>,
+}
+
+#[pymethods]
+impl PyHyperParameters {
+    /// Create hyperparameters. `edge_weights` maps edge type tags, eg.
+    /// `"dependency"`, to their weight.
+    #[new]
+    #[pyo3(signature = (
+        edge_weights = None,
+        pruning_threshold = 0.0,
+        project_damping = 0.85,
+        account_damping = 0.85,
+        r_value = 10,
+        max_edge_weight_share = None,
+        max_user_rank = None,
+        rank_decay = None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        edge_weights: Option<HashMap<String, f64>>,
+        pruning_threshold: f64,
+        project_damping: f64,
+        account_damping: f64,
+        r_value: u32,
+        max_edge_weight_share: Option<f64>,
+        max_user_rank: Option<f64>,
+        rank_decay: Option<f64>,
+    ) -> PyResult<Self> {
+        let edge_weights = edge_weights
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(tag, weight)| Ok((tag.parse::<EdgeTypeTag>().map_err(value_error)?, weight)))
+            .collect::<PyResult<_>>()?;
+
+        Ok(PyHyperParameters {
+            params: HyperParameters {
+                pruning_threshold,
+                damping_factors: DampingFactors {
+                    project: project_damping,
+                    account: account_damping,
+                },
+                r_value,
+                edge_weights,
+                max_edge_weight_share,
+                max_user_rank,
+                rank_decay,
+            },
+        })
+    }
+
+    /// The weights of the edge types, by tag.
+    #[getter]
+    fn edge_weights(&self) -> HashMap<String, f64> {
+        self.params
+            .edge_weights
+            .iter()
+            .map(|(tag, weight)| (tag.to_string(), *weight))
+            .collect()
+    }
+
+    #[getter]
+    fn pruning_threshold(&self) -> f64 {
+        self.params.pruning_threshold
+    }
+
+    #[getter]
+    fn project_damping(&self) -> f64 {
+        self.params.damping_factors.project
+    }
+
+    #[getter]
+    fn account_damping(&self) -> f64 {
+        self.params.damping_factors.account
+    }
+
+    #[getter]
+    fn r_value(&self) -> u32 {
+        self.params.r_value
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.params)
+    }
+}
+
+/// A graph, with `int` node and edge ids.
+#[pyclass(name = "Graph")]
+#[derive(Default)]
+pub struct PyGraph {
+    graph: EdgeListGraph<u64, f64>,
+}
+
+#[pymethods]
+impl PyGraph {
+    /// Create an empty graph.
+    #[new]
+    fn new() -> Self {
+        PyGraph::default()
+    }
+
+    /// The number of nodes.
+    fn node_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    /// The number of edges.
+    fn edge_count(&self) -> usize {
+        self.graph.edge_count()
+    }
+
+    fn __len__(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    /// The ids of the nodes.
+    fn nodes(&self) -> Vec<u64> {
+        self.graph.nodes().map(|n| *n.id()).collect()
+    }
+
+    /// The edges, as `(id, from, to, type, weight)` tuples.
+    fn edges(&self) -> Vec<(u64, u64, u64, String, f64)> {
+        self.graph
+            .nodes()
+            .flat_map(|n| self.graph.edges_directed(n.id(), Direction::Outgoing))
+            .filter_map(|e| self.graph.get_edge(e.id))
+            .map(|e| {
+                let data = e.data();
+                (
+                    *e.id(),
+                    *e.source(),
+                    *e.target(),
+                    data.edge_type.to_string(),
+                    data.weight,
+                )
+            })
+            .collect()
+    }
+
+    /// Add a node of the given type, replacing any node with the same id.
+    fn add_node(&mut self, id: u64, node_type: &str) -> PyResult<()> {
+        let node_type: NodeType = node_type.parse().map_err(value_error)?;
+        self.graph.add_node(
+            id,
+            NodeData {
+                node_type,
+                rank: NodeRank { rank: 0.0 },
+                contributions: ContributionLedger::new(),
+                timestamps: Default::default(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Add an edge of the given type between two existing nodes, replacing
+    /// any edge with the same id.
+    #[pyo3(signature = (id, source, target, edge_type, weight = 0.0))]
+    fn add_edge(
+        &mut self,
+        id: u64,
+        source: u64,
+        target: u64,
+        edge_type: &str,
+        weight: f64,
+    ) -> PyResult<()> {
+        let edge_type: EdgeType = edge_type.parse().map_err(value_error)?;
+        for node in [source, target].iter() {
+            if self.graph.get_node(node).is_none() {
+                return Err(PyKeyError::new_err(*node));
+            }
+        }
+        self.graph.add_edge(
+            id,
+            &source,
+            &target,
+            EdgeData {
+                edge_type,
+                weight,
+                history: Default::default(),
+                timestamps: Default::default(),
+            },
+        );
+        Ok(())
+    }
+
+    /// The rank stored on a node.
+    fn rank(&self, id: u64) -> PyResult<f64> {
+        self.graph
+            .node_data(&id)
+            .map(|data| data.rank.rank)
+            .ok_or_else(|| PyKeyError::new_err(id))
+    }
+
+    /// Import the log of a single project, a file with one contributor per
+    /// line, eg. the output of `git log --format=%ae`. Returns the number of
+    /// nodes and edges added.
+    fn import_git_log(&mut self, project: &str, path: &str) -> PyResult<(usize, usize)> {
+        let mut contributions = Contributions::new();
+        let file = File::open(path)?;
+        read_git_log(project, file, &mut contributions).map_err(import_error)?;
+
+        self.import(&contributions)
+    }
+
+    /// Import a contribution list, a file with one
+    /// `project,contributor,commits` record per line. Returns the number of
+    /// nodes and edges added.
+    fn import_contributions(&mut self, path: &str) -> PyResult<(usize, usize)> {
+        let mut contributions = Contributions::new();
+        let file = File::open(path)?;
+        read_contributions(file, &mut contributions).map_err(import_error)?;
+
+        self.import(&contributions)
+    }
+
+    /// Weight every edge with the default weight function, normalize the
+    /// outgoing weights of every node and apply the weight share cap, if
+    /// any.
+    fn assign_weights(&mut self, hyperparams: &PyHyperParameters) -> PyResult<()> {
+        let params = &hyperparams.params;
+        for node in self.graph.nodes() {
+            for e in self.graph.edges_directed(node.id(), Direction::Outgoing) {
+                params
+                    .try_get_param(&e.data.edge_type.to_tag())
+                    .map_err(algorithm_error)?;
+            }
+        }
+        assign_weights(&mut self.graph, &ContributionWeight, params);
+        normalize_outgoing_weights(&mut self.graph, params);
+        cap_edge_weight_shares(&mut self.graph, params);
+        Ok(())
+    }
+}
+
+impl PyGraph {
+    fn import(&mut self, contributions: &Contributions) -> PyResult<(usize, usize)> {
+        let summary =
+            import_contributions(&mut self.graph, &mut SipMapping::default(), contributions)
+                .map_err(import_error)?;
+
+        Ok((summary.nodes, summary.edges))
+    }
+}
+
+/// Rank every node with the approximate osrank of the pruning phase, over
+/// walks of up to `steps` steps. The ranks are stored on the nodes and
+/// returned.
+#[pyfunction]
+#[pyo3(signature = (graph, hyperparams, steps = 10))]
+fn osrank(
+    graph: &mut PyGraph,
+    hyperparams: &PyHyperParameters,
+    steps: usize,
+) -> PyResult<HashMap<u64, f64>> {
+    let mut prune = Prune::new(hyperparams.params.clone());
+    prune.hyperparams.pruning_threshold = 0.0;
+    prune.steps = steps;
+
+    let mut context = PruneContext::default();
+    prune
+        .execute(&mut context, &graph.graph, &mut discard(), 0)
+        .map_err(algorithm_error)?;
+    for (id, rank) in context.ranks.iter() {
+        if let Some(data) = graph.graph.node_data_mut(id) {
+            data.rank.rank = *rank;
+        }
+    }
+    Ok(context.ranks)
+}
+
+/// The HITS hub and authority scores of every node, as `(hub, authority)`
+/// tuples.
+#[pyfunction]
+#[pyo3(signature = (graph, hyperparams, max_iterations = 100, tolerance = 1e-8))]
+fn hits(
+    graph: &PyGraph,
+    hyperparams: &PyHyperParameters,
+    max_iterations: usize,
+    tolerance: f64,
+) -> PyResult<HashMap<u64, (f64, f64)>> {
+    let mut hits = Hits::new(hyperparams.params.clone());
+    hits.max_iterations = max_iterations;
+    hits.tolerance = tolerance;
+
+    let scores = hits
+        .execute(&mut (), &graph.graph, &mut discard(), 0)
+        .map_err(algorithm_error)?;
+    Ok(scores
+        .into_iter()
+        .map(|(id, s)| (id, (s.hub, s.authority)))
+        .collect())
+}
+
+/// The betweenness centrality of every node.
+#[pyfunction]
+#[pyo3(signature = (graph, normalized = false))]
+fn betweenness(graph: &PyGraph, normalized: bool) -> PyResult<HashMap<u64, f64>> {
+    let mut annotator = discard::<NodeRank<f64>>();
+    Betweenness { normalized }
+        .execute(&mut (), &graph.graph, &mut annotator, 0)
+        .map_err(algorithm_error)
+}
+
+/// The degree centrality of every node, counting edges in the given
+/// direction, `"outgoing"`, `"incoming"` or `"both"`, or all the edges of
+/// the node if `None`.
+#[pyfunction]
+#[pyo3(signature = (graph, direction = None, normalized = false))]
+fn degree_centrality(
+    graph: &PyGraph,
+    direction: Option<&str>,
+    normalized: bool,
+) -> PyResult<HashMap<u64, f64>> {
+    let direction = match direction {
+        None => None,
+        Some("outgoing") => Some(Direction::Outgoing),
+        Some("incoming") => Some(Direction::Incoming),
+        Some("both") => Some(Direction::Both),
+        Some(other) => {
+            return Err(PyValueError::new_err(format!(
+                "unknown direction {:?}",
+                other
+            )))
+        }
+    };
+    let mut annotator = discard::<NodeRank<f64>>();
+    DegreeCentrality {
+        direction,
+        normalized,
+    }
+    .execute(&mut (), &graph.graph, &mut annotator, 0)
+    .map_err(algorithm_error)
+}
+
+/// The `oscoin_graph` extension module. It's public so it can be embedded,
+/// eg. with `pyo3::wrap_pymodule!`.
+#[pymodule]
+pub fn oscoin_graph(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyGraph>()?;
+    m.add_class::<PyHyperParameters>()?;
+    m.add_function(wrap_pyfunction!(osrank, m)?)?;
+    m.add_function(wrap_pyfunction!(hits, m)?)?;
+    m.add_function(wrap_pyfunction!(betweenness, m)?)?;
+    m.add_function(wrap_pyfunction!(degree_centrality, m)?)?;
+    Ok(())
+}
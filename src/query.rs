@@ -0,0 +1,330 @@
+//! Typed pattern matching over nodes and edges.
+//!
+//! A `Query` describes a path: a first node pattern, followed by steps
+//! along edges of a given type, each to another node pattern. For instance,
+//! the projects maintained by users who also maintain `x`:
+//!
+//! ```ignore
+//! let query = Query::nodes()
+//!     .with_id(x)
+//!     .with_incoming_edge(EdgeTypeTag::UserToProjectMembership)
+//!     .of_type(NodeKind::User)
+//!     .with_edge(EdgeTypeTag::UserToProjectMembership)
+//!     .to(NodePattern::of_type(NodeKind::Project));
+//! ```
+//!
+//! Queries are compiled to a `Plan`, which starts from the most selective
+//! node pattern and expands along the path in both directions. Every match
+//! binds one node per pattern and one edge per step, and uses an edge at most
+//! once.
+
+use super::stats::NodeKind;
+use super::types::EdgeTypeTag;
+use super::{Direction, Edge, Graph, GraphObject, Id, Node};
+
+/// A constraint on a node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodePattern<NodeId> {
+    /// The type of the node, if constrained.
+    pub kind: Option<NodeKind>,
+    /// The id of the node, if constrained.
+    pub id: Option<NodeId>,
+}
+
+impl<NodeId> NodePattern<NodeId> {
+    /// Match any node.
+    pub fn any() -> Self {
+        NodePattern {
+            kind: None,
+            id: None,
+        }
+    }
+
+    /// Match nodes of the given type.
+    pub fn of_type(kind: NodeKind) -> Self {
+        NodePattern {
+            kind: Some(kind),
+            id: None,
+        }
+    }
+
+    /// Match the node with the given id.
+    pub fn with_id(id: NodeId) -> Self {
+        NodePattern {
+            kind: None,
+            id: Some(id),
+        }
+    }
+
+    fn matches<N, D>(&self, node: &N) -> bool
+    where
+        N: Node<D> + GraphObject<Id = NodeId>,
+        NodeId: PartialEq,
+    {
+        self.kind
+            .is_none_or(|kind| NodeKind::from(node.node_type()) == kind)
+            && self.id.as_ref().is_none_or(|id| node.id() == id)
+    }
+}
+
+impl<NodeId> Default for NodePattern<NodeId> {
+    fn default() -> Self {
+        NodePattern::any()
+    }
+}
+
+/// A step of a path, along an edge to a node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step<NodeId> {
+    /// The type of the edge, if constrained.
+    pub edge: Option<EdgeTypeTag>,
+    /// `Outgoing` to follow edges in their direction, `Both` to ignore it.
+    pub direction: Direction,
+    /// The node at the other end of the edge.
+    pub target: NodePattern<NodeId>,
+}
+
+/// A path pattern, built with `Query::nodes` and the builder methods.
+#[derive(Debug, PartialEq)]
+pub struct Query<NodeId> {
+    start: NodePattern<NodeId>,
+    steps: Vec<Step<NodeId>>,
+}
+
+impl<NodeId> Query<NodeId> {
+    /// A query matching every node.
+    pub fn nodes() -> Self {
+        Query {
+            start: NodePattern::any(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// The first node pattern.
+    pub fn start(&self) -> &NodePattern<NodeId> {
+        &self.start
+    }
+
+    /// The steps of the path.
+    pub fn steps(&self) -> &[Step<NodeId>] {
+        &self.steps
+    }
+
+    fn last_mut(&mut self) -> &mut NodePattern<NodeId> {
+        match self.steps.last_mut() {
+            Some(step) => &mut step.target,
+            None => &mut self.start,
+        }
+    }
+
+    /// Constrain the type of the last node of the path.
+    pub fn of_type(mut self, kind: NodeKind) -> Self {
+        self.last_mut().kind = Some(kind);
+        self
+    }
+
+    /// Constrain the id of the last node of the path.
+    pub fn with_id(mut self, id: NodeId) -> Self {
+        self.last_mut().id = Some(id);
+        self
+    }
+
+    /// Follow an outgoing edge of the given type, to any node until
+    /// constrained.
+    pub fn with_edge(self, edge: EdgeTypeTag) -> Self {
+        self.step(Some(edge), Direction::Outgoing)
+    }
+
+    /// Follow an incoming edge of the given type, to any node until
+    /// constrained.
+    pub fn with_incoming_edge(self, edge: EdgeTypeTag) -> Self {
+        self.step(Some(edge), Direction::Incoming)
+    }
+
+    /// Follow an edge of any type in the given direction.
+    pub fn with_any_edge(self, direction: Direction) -> Self {
+        self.step(None, direction)
+    }
+
+    fn step(mut self, edge: Option<EdgeTypeTag>, direction: Direction) -> Self {
+        self.steps.push(Step {
+            edge,
+            direction,
+            target: NodePattern::any(),
+        });
+        self
+    }
+
+    /// Replace the last node pattern of the path.
+    pub fn to(mut self, target: NodePattern<NodeId>) -> Self {
+        *self.last_mut() = target;
+        self
+    }
+
+    /// Compile the query to an execution plan.
+    pub fn plan(&self) -> Plan<'_, NodeId> {
+        let patterns: Vec<&NodePattern<NodeId>> = std::iter::once(&self.start)
+            .chain(self.steps.iter().map(|s| &s.target))
+            .collect();
+        // Start from a node bound by id, or else from a typed node.
+        let anchor = patterns
+            .iter()
+            .position(|p| p.id.is_some())
+            .or_else(|| patterns.iter().position(|p| p.kind.is_some()))
+            .unwrap_or(0);
+
+        let forward = (anchor..self.steps.len()).map(|i| Expansion {
+            from: i,
+            to: i + 1,
+            edge: i,
+            direction: self.steps[i].direction,
+        });
+        let backward = (0..anchor).rev().map(|i| Expansion {
+            from: i + 1,
+            to: i,
+            edge: i,
+            direction: self.steps[i].direction.opposite(),
+        });
+
+        Plan {
+            anchor,
+            expansions: forward.chain(backward).collect(),
+            patterns,
+            steps: &self.steps,
+        }
+    }
+
+    /// Run the query over a graph.
+    pub fn execute<G>(&self, graph: &G) -> Vec<Match<NodeId, Id<G::Edge>>>
+    where
+        G: Graph,
+        G::Node: GraphObject<Id = NodeId>,
+        NodeId: Clone + PartialEq,
+        Id<G::Edge>: Clone + PartialEq,
+    {
+        self.plan().execute(graph)
+    }
+}
+
+/// The nodes and edges bound by a match, in the order of the path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match<NodeId, EdgeId> {
+    /// One node per node pattern.
+    pub nodes: Vec<NodeId>,
+    /// One edge per step.
+    pub edges: Vec<EdgeId>,
+}
+
+/// An expansion from a bound node to the next one along the path.
+#[derive(Debug, PartialEq)]
+pub struct Expansion {
+    /// The position of the bound node.
+    pub from: usize,
+    /// The position of the node to bind.
+    pub to: usize,
+    /// The step followed.
+    pub edge: usize,
+    /// The direction in which edges are followed from the bound node.
+    pub direction: Direction,
+}
+
+/// An execution plan: bind the anchor node pattern by scanning the graph,
+/// or by lookup if it has an id, then perform the expansions in order.
+#[derive(Debug)]
+pub struct Plan<'q, NodeId> {
+    /// The position of the first node pattern bound.
+    pub anchor: usize,
+    /// The expansions, each from a node already bound.
+    pub expansions: Vec<Expansion>,
+    patterns: Vec<&'q NodePattern<NodeId>>,
+    steps: &'q [Step<NodeId>],
+}
+
+/// Partial bindings, by position.
+type Bindings<N, E> = (Vec<Option<N>>, Vec<Option<E>>);
+
+impl<'q, NodeId> Plan<'q, NodeId> {
+    /// Run the plan over a graph.
+    pub fn execute<G>(&self, graph: &G) -> Vec<Match<NodeId, Id<G::Edge>>>
+    where
+        G: Graph,
+        G::Node: GraphObject<Id = NodeId>,
+        NodeId: Clone + PartialEq,
+        Id<G::Edge>: Clone + PartialEq,
+    {
+        let anchor = self.patterns[self.anchor];
+        let candidates: Vec<&G::Node> = match &anchor.id {
+            Some(id) => graph
+                .get_node(id)
+                .filter(|n| anchor.matches(*n))
+                .into_iter()
+                .collect(),
+            None => graph.nodes().filter(|n| anchor.matches(*n)).collect(),
+        };
+
+        let mut matches = Vec::new();
+        let mut bindings: Bindings<NodeId, Id<G::Edge>> = (
+            vec![None; self.patterns.len()],
+            vec![None; self.steps.len()],
+        );
+        for node in candidates {
+            bindings.0[self.anchor] = Some(node.id().clone());
+            self.expand(graph, 0, &mut bindings, &mut matches);
+        }
+        matches
+    }
+
+    fn expand<G>(
+        &self,
+        graph: &G,
+        depth: usize,
+        bindings: &mut Bindings<NodeId, Id<G::Edge>>,
+        matches: &mut Vec<Match<NodeId, Id<G::Edge>>>,
+    ) where
+        G: Graph,
+        G::Node: GraphObject<Id = NodeId>,
+        NodeId: Clone + PartialEq,
+        Id<G::Edge>: Clone + PartialEq,
+    {
+        let expansion = match self.expansions.get(depth) {
+            Some(e) => e,
+            None => {
+                matches.push(Match {
+                    nodes: bindings.0.iter().flatten().cloned().collect(),
+                    edges: bindings.1.iter().flatten().cloned().collect(),
+                });
+                return;
+            }
+        };
+        let step = &self.steps[expansion.edge];
+        let pattern = self.patterns[expansion.to];
+        let from = match &bindings.0[expansion.from] {
+            Some(id) => id.clone(),
+            None => return,
+        };
+
+        for edge in graph.edges_directed(&from, expansion.direction) {
+            if step.edge.as_ref().is_some_and(|t| {
+                graph
+                    .get_edge(edge.id)
+                    .is_none_or(|e| e.edge_type().to_tag() != *t)
+            }) || bindings.1.iter().flatten().any(|e| e == edge.id)
+            {
+                continue;
+            }
+            let other = if *edge.from == from {
+                edge.to
+            } else {
+                edge.from
+            };
+            if !graph.get_node(other).is_some_and(|n| pattern.matches(n)) {
+                continue;
+            }
+            bindings.0[expansion.to] = Some(other.clone());
+            bindings.1[expansion.edge] = Some(edge.id.clone());
+            self.expand(graph, depth + 1, bindings, matches);
+            bindings.0[expansion.to] = None;
+            bindings.1[expansion.edge] = None;
+        }
+    }
+}
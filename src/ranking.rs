@@ -0,0 +1,327 @@
+//! Utilities over rank assignments, ie. maps of node ids to `NodeRank`s.
+
+use num_traits::Float;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use super::types::{HyperParameters, NodeRank, NodeType, TotalOrd};
+use super::{Graph, Id, Node};
+
+/// How much two rank assignments differ.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankComparison<Id> {
+    /// The sum of the absolute rank differences.
+    pub l1: f64,
+    /// The largest absolute rank difference.
+    pub linf: f64,
+    /// The Kendall rank correlation (tau-b) between the two assignments,
+    /// between `-1.0` (reversed orderings) and `1.0` (identical orderings).
+    pub kendall_tau: f64,
+    /// The nodes whose position in the ordering by descending rank changed.
+    pub reordered: BTreeSet<Id>,
+}
+
+/// Compare two rank assignments, eg. the result of an incremental run against
+/// a full recomputation. Nodes missing from one of the assignments are
+/// considered to have a rank of zero in it.
+pub fn compare<'a, Id, W, I, J>(a: I, b: J) -> RankComparison<Id>
+where
+    Id: Ord + Clone + 'a,
+    W: Clone + Into<f64> + 'a,
+    I: IntoIterator<Item = (&'a Id, &'a NodeRank<W>)>,
+    J: IntoIterator<Item = (&'a Id, &'a NodeRank<W>)>,
+{
+    let mut pairs: BTreeMap<&Id, (f64, f64)> = BTreeMap::new();
+    for (id, r) in a {
+        pairs.entry(id).or_insert((0.0, 0.0)).0 = r.rank.clone().into();
+    }
+    for (id, r) in b {
+        pairs.entry(id).or_insert((0.0, 0.0)).1 = r.rank.clone().into();
+    }
+
+    let mut l1 = 0.0;
+    let mut linf: f64 = 0.0;
+    for (x, y) in pairs.values() {
+        let d = (x - y).abs();
+        l1 += d;
+        linf = linf.max(d);
+    }
+
+    // Positions in the orderings by descending rank, ties broken by id.
+    let order = |pick: fn(&(f64, f64)) -> f64| {
+        let mut ids: Vec<(&Id, f64)> = pairs.iter().map(|(id, p)| (*id, pick(p))).collect();
+        ids.sort_by(|(i, x), (j, y)| y.partial_cmp(x).unwrap_or(Ordering::Equal).then(i.cmp(j)));
+        ids.into_iter().map(|(id, _)| id).collect::<Vec<_>>()
+    };
+    let reordered = order(|p| p.0)
+        .into_iter()
+        .zip(order(|p| p.1))
+        .filter(|(x, y)| x != y)
+        .flat_map(|(x, y)| vec![x.clone(), y.clone()])
+        .collect();
+
+    let values: Vec<(f64, f64)> = pairs.values().cloned().collect();
+
+    RankComparison {
+        l1,
+        linf,
+        kendall_tau: kendall_tau(values),
+        reordered,
+    }
+}
+
+/// The `k` nodes with the highest rank, by descending rank, ties broken by
+/// id. Ranks that aren't numbers come last.
+pub fn top_k<'a, Id, W, I>(ranks: I, k: usize) -> Vec<(&'a Id, &'a NodeRank<W>)>
+where
+    Id: Ord + 'a,
+    W: TotalOrd + 'a,
+    I: IntoIterator<Item = (&'a Id, &'a NodeRank<W>)>,
+{
+    let by_rank = |(i, x): &(&Id, &NodeRank<W>), (j, y): &(&Id, &NodeRank<W>)| {
+        y.rank_cmp(x).then_with(|| i.cmp(j))
+    };
+    let mut ranked: Vec<(&Id, &NodeRank<W>)> = ranks.into_iter().collect();
+
+    if k < ranked.len() {
+        ranked.select_nth_unstable_by(k, by_rank);
+        ranked.truncate(k);
+    }
+    ranked.sort_by(by_rank);
+    ranked
+}
+
+/// How far the sum of normalized ranks may be from `1.0`, to allow for
+/// rounding errors.
+pub const NORMALIZATION_TOLERANCE: f64 = 1e-6;
+
+/// A way a rank assignment breaks the invariants of `NodeRank`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RankViolation<Id> {
+    /// The rank of a node is negative.
+    Negative {
+        /// The node.
+        node: Id,
+        /// Its rank.
+        rank: f64,
+    },
+    /// The rank of a node is infinite, or not a number.
+    NotFinite(Id),
+    /// The ranks don't sum to `1.0`, within `NORMALIZATION_TOLERANCE`.
+    NotNormalized {
+        /// The sum of the finite ranks.
+        sum: f64,
+    },
+}
+
+impl<Id: fmt::Debug> fmt::Display for RankViolation<Id> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RankViolation::Negative { node, rank } => {
+                write!(f, "node {:?} has negative rank {}", node, rank)
+            }
+            RankViolation::NotFinite(node) => write!(f, "node {:?} has no finite rank", node),
+            RankViolation::NotNormalized { sum } => write!(f, "ranks sum to {}, not 1", sum),
+        }
+    }
+}
+
+/// Rescale the ranks so that they sum to `1.0`. They are left untouched if
+/// their sum is zero, or isn't finite.
+pub fn normalize<Id, W: Float>(ranks: &mut [(Id, NodeRank<W>)]) {
+    let sum = ranks.iter().fold(W::zero(), |sum, (_, r)| sum + r.rank);
+
+    if sum == W::zero() || !sum.is_finite() {
+        return;
+    }
+    for (_, rank) in ranks.iter_mut() {
+        rank.rank = rank.rank / sum;
+    }
+}
+
+/// Check that all ranks are finite and non-negative, and that they sum to
+/// `1.0`, as the output of a ranking should. An empty assignment is valid.
+/// Returns all the violations found, those of the nodes in iteration order.
+pub fn check_invariants<'a, Id, W, I>(ranks: I) -> Result<(), Vec<RankViolation<Id>>>
+where
+    Id: Clone + 'a,
+    W: Clone + Into<f64> + 'a,
+    I: IntoIterator<Item = (&'a Id, &'a NodeRank<W>)>,
+{
+    let mut violations = Vec::new();
+    let mut sum = 0.0;
+    let mut empty = true;
+
+    for (id, r) in ranks {
+        let rank: f64 = r.rank.clone().into();
+        empty = false;
+        if !rank.is_finite() {
+            violations.push(RankViolation::NotFinite(id.clone()));
+            continue;
+        }
+        if rank < 0.0 {
+            violations.push(RankViolation::Negative {
+                node: id.clone(),
+                rank,
+            });
+        }
+        sum += rank;
+    }
+    if !empty && (sum - 1.0).abs() > NORMALIZATION_TOLERANCE {
+        violations.push(RankViolation::NotNormalized { sum });
+    }
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
+
+/// Cap the values for which `cappable` holds at `max`, and redistribute the
+/// excess to the values below the cap in proportion to their size, capping
+/// again the values pushed above `max`, until the excess is spent. The total
+/// is preserved, unless no positive value is left below the cap to receive
+/// the excess, in which case the excess is dropped.
+pub fn cap_and_redistribute<W, F>(values: &mut [W], max: W, cappable: F)
+where
+    W: Float,
+    F: Fn(usize) -> bool,
+{
+    let mut fixed = vec![false; values.len()];
+
+    loop {
+        let mut excess = W::zero();
+        for (i, v) in values.iter_mut().enumerate() {
+            if !fixed[i] && cappable(i) && *v >= max {
+                excess = excess + (*v - max);
+                *v = max;
+                fixed[i] = true;
+            }
+        }
+        let receivers = values
+            .iter()
+            .zip(fixed.iter())
+            .filter(|(v, f)| !**f && **v > W::zero())
+            .fold(W::zero(), |total, (v, _)| total + *v);
+
+        if excess <= W::zero() || receivers <= W::zero() {
+            return;
+        }
+        for (v, f) in values.iter_mut().zip(fixed.iter()) {
+            if !*f && *v > W::zero() {
+                *v = *v + excess * *v / receivers;
+            }
+        }
+    }
+}
+
+/// Cap the rank of user nodes at `HyperParameters::max_user_rank`, if set,
+/// redistributing the excess to the other nodes as `cap_and_redistribute`
+/// does. This keeps a user from amplifying their rank by splitting their
+/// contributions across fake accounts that all point back to them.
+///
+/// Meant to post-process the annotations of a ranking algorithm, before
+/// they're written to the graph. Nodes missing from the graph aren't capped.
+pub fn cap_user_ranks<G, W>(
+    graph: &G,
+    ranks: &mut [(Id<G::Node>, NodeRank<W>)],
+    hyperparams: &HyperParameters<W>,
+) where
+    G: Graph,
+    W: Float,
+{
+    let max = match hyperparams.max_user_rank {
+        Some(max) => max,
+        None => return,
+    };
+    let users: Vec<bool> = ranks
+        .iter()
+        .map(|(id, _)| {
+            graph
+                .get_node(id)
+                .is_some_and(|n| matches!(n.node_type(), NodeType::User { .. }))
+        })
+        .collect();
+    let mut values: Vec<W> = ranks.iter().map(|(_, r)| r.rank).collect();
+
+    cap_and_redistribute(&mut values, max, |i| users[i]);
+
+    for ((_, rank), value) in ranks.iter_mut().zip(values) {
+        rank.rank = value;
+    }
+}
+
+/// Compute Kendall's tau-b in `O(n log n)`, using Knight's algorithm.
+fn kendall_tau(mut pairs: Vec<(f64, f64)>) -> f64 {
+    let cmp = |a: &f64, b: &f64| a.partial_cmp(b).unwrap_or(Ordering::Equal);
+    let n = pairs.len() as u64;
+    let total = n * n.saturating_sub(1) / 2;
+
+    pairs.sort_by(|(x1, y1), (x2, y2)| cmp(x1, x2).then(cmp(y1, y2)));
+
+    // Pairs tied on the first value, and on both values.
+    let tied_x = tied_pairs(&pairs, |a, b| a.0 == b.0);
+    let tied_xy = tied_pairs(&pairs, |a, b| a == b);
+
+    // Sort by the second value, counting the swaps needed: these are the
+    // discordant pairs.
+    let mut ys: Vec<f64> = pairs.iter().map(|p| p.1).collect();
+    let swaps = merge_sort_swaps(&mut ys);
+    let tied_y = tied_pairs(&ys, |a, b| a == b);
+
+    let denominator = (((total - tied_x) as f64) * ((total - tied_y) as f64)).sqrt();
+    if denominator == 0.0 {
+        // Without any untied pair, the orderings can't disagree.
+        return 1.0;
+    }
+    let numerator =
+        total as f64 - tied_x as f64 - tied_y as f64 + tied_xy as f64 - 2.0 * swaps as f64;
+
+    numerator / denominator
+}
+
+/// Count the pairs of equal elements in a sorted slice.
+fn tied_pairs<T, F: Fn(&T, &T) -> bool>(sorted: &[T], eq: F) -> u64 {
+    let mut count = 0;
+    let mut run = 1u64;
+
+    for i in 1..=sorted.len() {
+        if i < sorted.len() && eq(&sorted[i - 1], &sorted[i]) {
+            run += 1;
+        } else {
+            count += run * (run - 1) / 2;
+            run = 1;
+        }
+    }
+    count
+}
+
+/// Sort in place, returning the number of inversions.
+fn merge_sort_swaps(xs: &mut [f64]) -> u64 {
+    let n = xs.len();
+    if n < 2 {
+        return 0;
+    }
+    let mid = n / 2;
+    let mut swaps = merge_sort_swaps(&mut xs[..mid]) + merge_sort_swaps(&mut xs[mid..]);
+
+    let mut merged = Vec::with_capacity(n);
+    let (mut i, mut j) = (0, mid);
+    while i < mid && j < n {
+        if xs[j] < xs[i] {
+            merged.push(xs[j]);
+            swaps += (mid - i) as u64;
+            j += 1;
+        } else {
+            merged.push(xs[i]);
+            i += 1;
+        }
+    }
+    merged.extend_from_slice(&xs[i..mid]);
+    merged.extend_from_slice(&xs[j..]);
+    xs.copy_from_slice(&merged);
+
+    swaps
+}
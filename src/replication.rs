@@ -0,0 +1,596 @@
+//! Streaming graph mutations from a leader to followers.
+//!
+//! The leader wraps its graph in a `Leader`, which records every mutation as
+//! a `WalEntry`. Committing groups the pending entries in a `Frame`, with a
+//! sequence number and a hash of the resulting graph state. Followers apply
+//! frames in order with `Follower::apply_stream`, which detects missing
+//! frames and diverging states, and resume from their next sequence number
+//! with `Leader::frames_since`.
+//!
+//! Both sides maintain the state hash incrementally: every mutation only
+//! rehashes the nodes it modifies, along with their outgoing edges.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt;
+use std::hash::Hasher;
+
+use serde::{Deserialize, Serialize};
+use siphasher::sip::SipHasher24;
+
+use super::ledger::{Checkpoint, LedgerGraph};
+use super::limits::LimitError;
+use super::types::EdgeTypeTag;
+use super::wal::{self, Entry, WalEntry};
+use super::{
+    Data, Direction, Edge, EdgeRefsOf, Edges, EdgesMut, Graph, GraphDataWriter, GraphObject,
+    GraphWriter, Id, Nodes, NodesMut,
+};
+
+/// A batch of mutations, in order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Frame<NodeId, EdgeId, NodeData, EdgeData> {
+    /// The sequence number of the frame. Frames are numbered from 1.
+    pub seq: u64,
+    /// The mutations.
+    pub entries: Vec<WalEntry<NodeId, EdgeId, NodeData, EdgeData>>,
+    /// The `state_hash` of the graph once the mutations are applied.
+    pub state_hash: u64,
+}
+
+/// The frames of a graph.
+pub type GraphFrame<G> = Frame<
+    Id<<G as Graph>::Node>,
+    Id<<G as Graph>::Edge>,
+    Data<<G as Graph>::Node>,
+    Data<<G as Graph>::Edge>,
+>;
+
+/// A failure to replicate a graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplicationError {
+    /// A frame is missing from the stream.
+    Gap {
+        /// The sequence number of the expected frame.
+        expected: u64,
+        /// The sequence number of the received frame.
+        found: u64,
+    },
+    /// The state of the follower differs from the leader's after a frame.
+    HashMismatch {
+        /// The sequence number of the frame.
+        seq: u64,
+        /// The hash of the leader's state.
+        expected: u64,
+        /// The hash of the follower's state.
+        actual: u64,
+    },
+    /// The frames requested to resume aren't retained anymore: the follower
+    /// must start over from a snapshot.
+    Truncated {
+        /// The first requested frame.
+        requested: u64,
+        /// The oldest retained frame.
+        oldest: u64,
+    },
+    /// A mutation couldn't be encoded for hashing.
+    Encoding(String),
+}
+
+impl fmt::Display for ReplicationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplicationError::Gap { expected, found } => {
+                write!(f, "expected frame {}, found frame {}", expected, found)
+            }
+            ReplicationError::HashMismatch {
+                seq,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "state diverged at frame {}: expected hash {:016x}, found {:016x}",
+                seq, expected, actual
+            ),
+            ReplicationError::Truncated { requested, oldest } => write!(
+                f,
+                "frame {} is not retained, the oldest is {}",
+                requested, oldest
+            ),
+            ReplicationError::Encoding(msg) => write!(f, "encoding error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ReplicationError {}
+
+/// A hash of the nodes and edges of a graph, independent of iteration order.
+///
+/// Every node and edge is hashed with SipHash-2-4 over its JSON encoding, and
+/// the hashes are summed. Computing it takes time linear in the graph size,
+/// which `Leader` and `Follower` avoid by updating it after every mutation.
+pub fn state_hash<G>(graph: &G) -> Result<u64, ReplicationError>
+where
+    G: Graph,
+    Id<G::Node>: Serialize,
+    Id<G::Edge>: Serialize,
+    Data<G::Node>: Serialize,
+    Data<G::Edge>: Serialize,
+{
+    graph.nodes().try_fold(0u64, |sum, node| {
+        Ok(sum.wrapping_add(node_hash(graph, node.id())?))
+    })
+}
+
+/// The part of `state_hash` of a node: the hash of its record and of its
+/// outgoing edges, or zero if it doesn't exist.
+fn node_hash<G>(graph: &G, id: &Id<G::Node>) -> Result<u64, ReplicationError>
+where
+    G: Graph,
+    Id<G::Node>: Serialize,
+    Id<G::Edge>: Serialize,
+    Data<G::Node>: Serialize,
+    Data<G::Edge>: Serialize,
+{
+    let node = match graph.get_node(id) {
+        Some(node) => node,
+        None => return Ok(0),
+    };
+    let mut sum = record_hash(&(0u8, node.id(), node.data()))?;
+
+    for e in graph.edges_directed(id, Direction::Outgoing) {
+        if let Some(edge) = graph.get_edge(e.id) {
+            let record = (1u8, edge.id(), edge.source(), edge.target(), edge.data());
+            sum = sum.wrapping_add(record_hash(&record)?);
+        }
+    }
+    Ok(sum)
+}
+
+/// The nodes whose `node_hash` applying `entry` to `graph` may change.
+fn touched<G>(graph: &G, entry: &Entry<G>) -> Vec<Id<G::Node>>
+where
+    G: Graph,
+    Id<G::Node>: Clone + PartialEq,
+{
+    let source = |id| graph.get_edge(id).map(|e| e.source().clone());
+    let mut nodes = Vec::new();
+    match entry {
+        WalEntry::NodeAdded { id, .. } | WalEntry::NodeUpdated { id, .. } => {
+            nodes.push(id.clone());
+        }
+        // The incoming edges of the node are removed with it.
+        WalEntry::NodeDeleted(id) => {
+            nodes.push(id.clone());
+            for e in graph.edges_directed(id, Direction::Incoming) {
+                nodes.push(e.from.clone());
+            }
+        }
+        // An existing edge is replaced.
+        WalEntry::EdgeAdded { id, from, .. } => {
+            nodes.push(from.clone());
+            nodes.extend(source(id));
+        }
+        WalEntry::EdgeDeleted(id) | WalEntry::EdgeUpdated { id, .. } => {
+            nodes.extend(source(id));
+        }
+    }
+    let mut distinct = Vec::with_capacity(nodes.len());
+    for id in nodes {
+        if !distinct.contains(&id) {
+            distinct.push(id);
+        }
+    }
+    distinct
+}
+
+/// Apply `entry` to `graph`, and update `hash`, the sum of the `node_hash`
+/// of its nodes but the `unhashed` ones. The entry is applied even if the
+/// hash can't be updated.
+fn apply_hashed<G, U>(
+    graph: &mut G,
+    entry: Entry<G>,
+    hash: &mut u64,
+    unhashed: U,
+) -> Result<(), ReplicationError>
+where
+    G: GraphWriter,
+    U: Fn(&Id<G::Node>) -> bool,
+    Id<G::Node>: Clone + PartialEq + Serialize,
+    Id<G::Edge>: Serialize,
+    Data<G::Node>: Serialize,
+    Data<G::Edge>: Serialize,
+{
+    let mut nodes = touched(graph, &entry);
+    nodes.retain(|id| !unhashed(id));
+
+    let sum = |graph: &G| {
+        nodes
+            .iter()
+            .try_fold(0u64, |sum, id| Ok(sum.wrapping_add(node_hash(graph, id)?)))
+    };
+    let before = sum(graph);
+    wal::apply(graph, entry);
+    let after = sum(graph);
+
+    *hash = hash.wrapping_sub(before?).wrapping_add(after?);
+    Ok(())
+}
+
+fn record_hash<T: Serialize>(record: &T) -> Result<u64, ReplicationError> {
+    let bytes =
+        serde_json::to_vec(record).map_err(|e| ReplicationError::Encoding(e.to_string()))?;
+    let mut hasher = SipHasher24::new();
+
+    hasher.write(&bytes);
+    Ok(hasher.finish())
+}
+
+/// The replication state of a follower: the next frame to apply, and the
+/// state hash of its graph, which must only be modified by `apply_stream`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Follower {
+    next_seq: u64,
+    state_hash: u64,
+}
+
+impl Follower {
+    /// Follow a leader from `graph`, a copy of the leader's graph before
+    /// frame `next_seq`. The graph is hashed once.
+    pub fn new<G>(graph: &G, next_seq: u64) -> Result<Self, ReplicationError>
+    where
+        G: Graph,
+        Id<G::Node>: Serialize,
+        Id<G::Edge>: Serialize,
+        Data<G::Node>: Serialize,
+        Data<G::Edge>: Serialize,
+    {
+        Ok(Follower {
+            next_seq,
+            state_hash: state_hash(graph)?,
+        })
+    }
+
+    /// The sequence number of the next frame to apply.
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq
+    }
+
+    /// The state hash of the graph.
+    pub fn state_hash(&self) -> u64 {
+        self.state_hash
+    }
+
+    /// Apply frames to `graph` in order, starting with frame `next_seq`,
+    /// which is advanced past every applied frame. Frames already applied
+    /// are skipped, so that a stream can be replayed after a reconnection.
+    ///
+    /// On a gap or a hash mismatch, the frames before the failing one remain
+    /// applied. After a hash mismatch, the follower should start over from a
+    /// snapshot.
+    pub fn apply_stream<G, I>(&mut self, graph: &mut G, frames: I) -> Result<(), ReplicationError>
+    where
+        G: GraphWriter,
+        I: IntoIterator<Item = GraphFrame<G>>,
+        Id<G::Node>: Clone + PartialEq + Serialize,
+        Id<G::Edge>: Serialize,
+        Data<G::Node>: Serialize,
+        Data<G::Edge>: Serialize,
+    {
+        for frame in frames {
+            if frame.seq < self.next_seq {
+                continue;
+            }
+            if frame.seq > self.next_seq {
+                return Err(ReplicationError::Gap {
+                    expected: self.next_seq,
+                    found: frame.seq,
+                });
+            }
+            for entry in frame.entries {
+                apply_hashed(graph, entry, &mut self.state_hash, |_| false)?;
+            }
+            if self.state_hash != frame.state_hash {
+                return Err(ReplicationError::HashMismatch {
+                    seq: frame.seq,
+                    expected: frame.state_hash,
+                    actual: self.state_hash,
+                });
+            }
+            self.next_seq += 1;
+        }
+        Ok(())
+    }
+}
+
+/// A graph wrapper recording mutations into frames, for followers.
+///
+/// Data modified through `GraphDataWriter` or `nodes_mut` is recorded as
+/// `NodeUpdated` and `EdgeUpdated` entries when the frame is committed, once
+/// per node or edge, in id order.
+///
+/// The graph is hashed entirely by the first commit, and after `nodes_mut`
+/// or `edges_mut`. Otherwise, the hash is updated after every mutation, and
+/// nodes modified through `GraphDataWriter` are rehashed when committing.
+pub struct Leader<G: GraphWriter> {
+    graph: G,
+    pending: Vec<Entry<G>>,
+    dirty_nodes: BTreeSet<Id<G::Node>>,
+    dirty_edges: BTreeSet<Id<G::Edge>>,
+    frames: VecDeque<GraphFrame<G>>,
+    retention: usize,
+    next_seq: u64,
+    /// The state hash, less the hashes of the `unhashed` nodes, unless the
+    /// graph must be `rehash`ed.
+    state_hash: u64,
+    unhashed: BTreeSet<Id<G::Node>>,
+    rehash: bool,
+    error: Option<ReplicationError>,
+}
+
+impl<G> Leader<G>
+where
+    G: GraphWriter,
+    Id<G::Node>: Clone + Ord + Serialize,
+    Id<G::Edge>: Clone + Ord + Serialize,
+    Data<G::Node>: Clone + Serialize,
+    Data<G::Edge>: Clone + Serialize,
+{
+    /// Start recording the mutations of `graph`, retaining the last
+    /// `retention` frames for followers to resume from. Followers must start
+    /// from a copy of `graph`.
+    pub fn new(graph: G, retention: usize) -> Self {
+        Leader {
+            graph,
+            pending: Vec::new(),
+            dirty_nodes: BTreeSet::new(),
+            dirty_edges: BTreeSet::new(),
+            frames: VecDeque::new(),
+            retention,
+            next_seq: 1,
+            state_hash: 0,
+            unhashed: BTreeSet::new(),
+            rehash: true,
+            error: None,
+        }
+    }
+
+    /// The wrapped graph.
+    pub fn graph(&self) -> &G {
+        &self.graph
+    }
+
+    /// The sequence number of the next frame.
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq
+    }
+
+    /// Group the pending mutations in a new frame. Fails if a mutation
+    /// couldn't be hashed, keeping the mutations pending.
+    pub fn commit(&mut self) -> Result<&GraphFrame<G>, ReplicationError> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        let state_hash = if self.rehash {
+            state_hash(&self.graph)?
+        } else {
+            self.unhashed.iter().try_fold(self.state_hash, |sum, id| {
+                Ok(sum.wrapping_add(node_hash(&self.graph, id)?))
+            })?
+        };
+        self.state_hash = state_hash;
+        self.unhashed.clear();
+        self.rehash = false;
+
+        for id in std::mem::take(&mut self.dirty_nodes) {
+            if let Some(data) = self.graph.node_data(&id) {
+                let data = data.clone();
+                self.pending.push(WalEntry::NodeUpdated { id, data });
+            }
+        }
+        for id in std::mem::take(&mut self.dirty_edges) {
+            if let Some(data) = self.graph.edge_data(&id) {
+                let data = data.clone();
+                self.pending.push(WalEntry::EdgeUpdated { id, data });
+            }
+        }
+        let frame = Frame {
+            seq: self.next_seq,
+            entries: std::mem::take(&mut self.pending),
+            state_hash,
+        };
+        self.next_seq += 1;
+
+        self.frames.push_back(frame);
+        while self.frames.len() > self.retention.max(1) {
+            self.frames.pop_front();
+        }
+        Ok(self.frames.back().expect("a frame was just pushed"))
+    }
+
+    /// The retained frames, starting with frame `seq`, to resume a follower.
+    pub fn frames_since(
+        &self,
+        seq: u64,
+    ) -> Result<impl Iterator<Item = &GraphFrame<G>>, ReplicationError> {
+        let oldest = self.frames.front().map_or(self.next_seq, |f| f.seq);
+        if seq < oldest {
+            return Err(ReplicationError::Truncated {
+                requested: seq,
+                oldest,
+            });
+        }
+        Ok(self.frames.iter().filter(move |f| f.seq >= seq))
+    }
+}
+
+impl<G> Leader<G>
+where
+    G: GraphWriter,
+    Id<G::Node>: Clone + Ord + Serialize,
+    Id<G::Edge>: Clone + Ord + Serialize,
+    Data<G::Node>: Clone + Serialize,
+    Data<G::Edge>: Clone + Serialize,
+{
+    /// Apply a mutation, and record it.
+    fn apply(&mut self, entry: Entry<G>) {
+        self.pending.push(entry.clone());
+        if self.rehash {
+            wal::apply(&mut self.graph, entry);
+            return;
+        }
+        let unhashed = &self.unhashed;
+        let result = apply_hashed(&mut self.graph, entry, &mut self.state_hash, |id| {
+            unhashed.contains(id)
+        });
+        self.record(result);
+    }
+
+    /// Leave the hash of a node out of the state hash until the next
+    /// commit, as its data is about to be modified.
+    fn unhash(&mut self, id: &Id<G::Node>) {
+        if self.rehash || self.unhashed.contains(id) {
+            return;
+        }
+        let result = node_hash(&self.graph, id).map(|hash| {
+            self.state_hash = self.state_hash.wrapping_sub(hash);
+        });
+        self.unhashed.insert(id.clone());
+        self.record(result);
+    }
+
+    /// Keep the first failure, to be returned by `commit`, after which the
+    /// graph is rehashed.
+    fn record(&mut self, result: Result<(), ReplicationError>) {
+        if let Err(err) = result {
+            self.error.get_or_insert(err);
+            self.rehash = true;
+        }
+    }
+}
+
+impl<G: GraphWriter> Graph for Leader<G> {
+    type Node = G::Node;
+    type Edge = G::Edge;
+    type NodeData = G::NodeData;
+    type EdgeData = G::EdgeData;
+    type Weight = G::Weight;
+
+    fn get_node(&self, id: &Id<G::Node>) -> Option<&G::Node> {
+        self.graph.get_node(id)
+    }
+
+    fn get_edge(&self, id: &Id<G::Edge>) -> Option<&G::Edge> {
+        self.graph.get_edge(id)
+    }
+
+    fn nodes(&self) -> Nodes<'_, G::Node> {
+        self.graph.nodes()
+    }
+
+    fn neighbors(&self, node: &Id<G::Node>) -> Nodes<'_, G::Node> {
+        self.graph.neighbors(node)
+    }
+
+    fn edges(&self, node: &Id<G::Node>) -> Edges<'_, G::Edge> {
+        self.graph.edges(node)
+    }
+
+    fn edges_directed(&self, node: &Id<G::Node>, dir: Direction) -> EdgeRefsOf<'_, G> {
+        self.graph.edges_directed(node, dir)
+    }
+
+    fn degree_by_edge_type(
+        &self,
+        node: &Id<G::Node>,
+        dir: Direction,
+    ) -> BTreeMap<EdgeTypeTag, usize> {
+        self.graph.degree_by_edge_type(node, dir)
+    }
+}
+
+impl<G> GraphWriter for Leader<G>
+where
+    G: GraphWriter,
+    Id<G::Node>: Clone + Ord + Serialize,
+    Id<G::Edge>: Clone + Ord + Serialize,
+    Data<G::Node>: Clone + Serialize,
+    Data<G::Edge>: Clone + Serialize,
+{
+    fn add_node(&mut self, id: Id<G::Node>, data: Data<G::Node>) {
+        self.apply(WalEntry::NodeAdded { id, data });
+    }
+
+    fn remove_node(&mut self, id: Id<G::Node>) {
+        self.apply(WalEntry::NodeDeleted(id));
+    }
+
+    fn add_edge(
+        &mut self,
+        id: Id<G::Edge>,
+        from: &Id<G::Node>,
+        to: &Id<G::Node>,
+        data: Data<G::Edge>,
+    ) {
+        self.apply(WalEntry::EdgeAdded {
+            id,
+            from: from.clone(),
+            to: to.clone(),
+            data,
+        });
+    }
+
+    fn remove_edge(&mut self, id: Id<G::Edge>) {
+        self.apply(WalEntry::EdgeDeleted(id));
+    }
+
+    /// The graph is rehashed entirely by the next commit.
+    fn nodes_mut(&mut self) -> NodesMut<'_, G::Node> {
+        self.dirty_nodes
+            .extend(self.graph.nodes().map(|n| n.id().clone()));
+        self.rehash = true;
+        self.graph.nodes_mut()
+    }
+
+    /// The graph is rehashed entirely by the next commit.
+    fn edges_mut(&mut self) -> EdgesMut<'_, G::Edge> {
+        let ids: Vec<_> = self
+            .graph
+            .nodes()
+            .flat_map(|n| self.graph.edges_directed(n.id(), Direction::Outgoing))
+            .map(|e| e.id.clone())
+            .collect();
+        self.dirty_edges.extend(ids);
+        self.rehash = true;
+        self.graph.edges_mut()
+    }
+}
+
+impl<G> GraphDataWriter for Leader<G>
+where
+    G: GraphWriter,
+    Id<G::Node>: Clone + Ord + Serialize,
+    Id<G::Edge>: Clone + Ord + Serialize,
+    Data<G::Node>: Clone + Serialize,
+    Data<G::Edge>: Clone + Serialize,
+{
+    fn edge_data_mut(&mut self, id: &Id<G::Edge>) -> Option<&mut Data<G::Edge>> {
+        let source = self.graph.get_edge(id)?.source().clone();
+        self.unhash(&source);
+        self.dirty_edges.insert(id.clone());
+        self.graph.edge_data_mut(id)
+    }
+
+    fn node_data_mut(&mut self, id: &Id<G::Node>) -> Option<&mut Data<G::Node>> {
+        self.graph.get_node(id)?;
+        self.unhash(id);
+        self.dirty_nodes.insert(id.clone());
+        self.graph.node_data_mut(id)
+    }
+}
+
+/// Checkpoints are checked against the limits of the inner graph.
+impl<G: GraphWriter + LedgerGraph> LedgerGraph for Leader<G> {
+    fn check_limits(&self, checkpoint: &Checkpoint<Id<G::Node>>) -> Result<(), LimitError> {
+        self.graph.check_limits(checkpoint)
+    }
+}
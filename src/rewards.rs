@@ -0,0 +1,124 @@
+//! Turning ranks into integer reward payouts.
+
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+
+use super::types::NodeRank;
+use super::GraphAnnotator;
+
+/// The precision of the shares of ranks in the budget: the largest rank is
+/// `1 << SHARE_BITS`, and ranks smaller by more than this factor get nothing.
+pub const SHARE_BITS: u32 = 62;
+
+/// How fractional payouts are rounded to integers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rounding {
+    /// Round every payout down. Up to one unit per node of the budget may be
+    /// left undistributed.
+    Floor,
+    /// Round every payout down, then hand out the units left over one by one
+    /// to the nodes with the largest fractional parts, so that the whole
+    /// budget is distributed. Ties go to the smallest node id.
+    #[default]
+    LargestRemainder,
+}
+
+/// An annotator collecting ranks, to split a reward budget between nodes in
+/// proportion to their rank.
+///
+/// Annotating a node again replaces its rank. Ranks that aren't positive
+/// get nothing. Payouts only depend on the ranks and the budget, not on the
+/// order of annotations, so every node computes the same payouts.
+///
+/// Ranks are converted to fixed point numbers, relative to the largest rank
+/// and with `SHARE_BITS` bits, so that the budget is split in integer
+/// arithmetic, exactly, whatever its size.
+#[derive(Debug, Clone)]
+pub struct RewardAnnotator<NodeId, W = f64> {
+    budget: u64,
+    rounding: Rounding,
+    ranks: BTreeMap<NodeId, f64>,
+    weight: PhantomData<W>,
+}
+
+impl<NodeId: Ord, W> RewardAnnotator<NodeId, W> {
+    /// Split the given budget, rounding with largest remainders.
+    pub fn new(budget: u64) -> Self {
+        Self::with_rounding(budget, Rounding::default())
+    }
+
+    /// Split the given budget, with the given rounding policy.
+    pub fn with_rounding(budget: u64, rounding: Rounding) -> Self {
+        RewardAnnotator {
+            budget,
+            rounding,
+            ranks: BTreeMap::new(),
+            weight: PhantomData,
+        }
+    }
+
+    /// The ranks collected so far.
+    pub fn ranks(&self) -> &BTreeMap<NodeId, f64> {
+        &self.ranks
+    }
+
+    /// The payout of every node with a positive rank.
+    pub fn payouts(&self) -> BTreeMap<&NodeId, u64> {
+        let ranked = || {
+            self.ranks
+                .iter()
+                .filter(|(_, r)| **r > 0.0 && r.is_finite())
+        };
+        let max = ranked().map(|(_, r)| *r).fold(0.0, f64::max);
+        if max <= 0.0 {
+            return BTreeMap::new();
+        }
+        // At most `1 << SHARE_BITS` each, so that the sum of the shares of
+        // up to `u64::MAX` nodes, and their products with the budget, fit.
+        let scale = (1u64 << SHARE_BITS) as f64;
+        let shares: Vec<(&NodeId, u128)> = ranked()
+            .map(|(id, r)| (id, (r / max * scale) as u128))
+            .collect();
+        let total: u128 = shares.iter().map(|(_, s)| s).sum();
+
+        let budget = u128::from(self.budget);
+        let mut payouts = BTreeMap::new();
+        let mut remainders = Vec::with_capacity(shares.len());
+        let mut distributed = 0u64;
+
+        for (id, share) in shares {
+            let exact = budget * share;
+            // At most the budget, as the shares sum to `total`.
+            let units = (exact / total) as u64;
+
+            distributed += units;
+            payouts.insert(id, units);
+            remainders.push((id, exact % total));
+        }
+
+        if self.rounding == Rounding::LargestRemainder {
+            // Sort by decreasing remainder, then by id. The sort is stable
+            // and `remainders` is sorted by id.
+            remainders.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+            let left = (self.budget - distributed) as usize;
+            for (id, _) in remainders.into_iter().take(left) {
+                *payouts.entry(id).or_insert(0) += 1;
+            }
+        }
+        payouts
+    }
+}
+
+impl<NodeId, W> GraphAnnotator for RewardAnnotator<NodeId, W>
+where
+    NodeId: Ord,
+    W: Into<f64>,
+{
+    type Annotation = (NodeId, NodeRank<W>);
+    type Error = std::convert::Infallible;
+
+    fn annotate_graph(&mut self, (id, rank): Self::Annotation) {
+        self.ranks.insert(id, rank.rank.into());
+    }
+}
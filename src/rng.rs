@@ -0,0 +1,133 @@
+//! Deterministic random number generation for graph algorithms.
+//!
+//! Algorithms are run by every node of the network and must produce the same
+//! results given the same seed. The generators defined here are portable and
+//! reproducible, and support splitting into independent streams so that
+//! walks can be simulated in parallel without changing the outcome.
+
+extern crate rand_chacha;
+
+use rand_chacha::rand_core::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+/// A random number generator suitable for graph algorithms.
+pub trait SeedableAlgorithmRng: Sized {
+    /// The seed the generator is created from.
+    type Seed: Clone;
+
+    /// Create a generator from a seed.
+    fn from_seed(seed: Self::Seed) -> Self;
+
+    /// Derive the independent stream number `stream` from this generator.
+    /// The derived generator only depends on the seed and the stream of this
+    /// generator and on `stream`, not on how many numbers were generated so
+    /// far. Streams derived from different generators are independent, of
+    /// each other and of their parents.
+    fn split(&self, stream: u64) -> Self;
+
+    /// Advance the generator as if `n` numbers had been drawn with `next_u64`.
+    fn jump(&mut self, n: u64);
+
+    /// Return the next random `u32`.
+    fn next_u32(&mut self) -> u32;
+
+    /// Return the next random `u64`.
+    fn next_u64(&mut self) -> u64;
+
+    /// Return a random `f64` uniformly distributed in `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        // Keep the 53 most significant bits, ie. the precision of an `f64`.
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+}
+
+/// The offset, in 32-bit words, of the last block of a ChaCha20 stream, which
+/// is reserved to derive the keys of split generators.
+const SPLIT_KEY_POS: u128 = (1 << 68) - 16;
+
+/// The default algorithm RNG, based on ChaCha20.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlgorithmRng(ChaCha20Rng);
+
+impl SeedableAlgorithmRng for AlgorithmRng {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: [u8; 32]) -> Self {
+        AlgorithmRng(ChaCha20Rng::from_seed(seed))
+    }
+
+    /// The key of the derived generator is drawn from the last block of the
+    /// stream of this one, which is out of reach of its draws.
+    fn split(&self, stream: u64) -> Self {
+        let mut parent = ChaCha20Rng::from_seed(self.0.get_seed());
+        parent.set_stream(self.0.get_stream());
+        parent.set_word_pos(SPLIT_KEY_POS);
+
+        let mut key = [0; 32];
+        for chunk in key.chunks_mut(8) {
+            chunk.copy_from_slice(&parent.next_u64().to_le_bytes());
+        }
+        let mut rng = ChaCha20Rng::from_seed(key);
+        rng.set_stream(stream);
+
+        AlgorithmRng(rng)
+    }
+
+    fn jump(&mut self, n: u64) {
+        // A `u64` is made of two 32-bit words.
+        let pos = self.0.get_word_pos();
+        self.0.set_word_pos(pos + 2 * u128::from(n));
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+}
+
+/// A seed that can be turned into a random number generator. Used to bound
+/// `GraphAlgorithm::RngSeed`.
+pub trait AlgorithmSeed: Clone {
+    /// The generator this seed creates.
+    type Rng: SeedableAlgorithmRng;
+
+    /// Create a generator from this seed.
+    fn into_rng(self) -> Self::Rng;
+
+    /// Derive the seed of the independent stream number `stream`, eg. for
+    /// a stage of a pipeline of algorithms.
+    fn split(&self, stream: u64) -> Self;
+}
+
+impl AlgorithmSeed for [u8; 32] {
+    type Rng = AlgorithmRng;
+
+    fn into_rng(self) -> AlgorithmRng {
+        AlgorithmRng::from_seed(self)
+    }
+
+    fn split(&self, stream: u64) -> Self {
+        let mut rng = self.into_rng().split(stream);
+        let mut seed = [0; 32];
+        for chunk in seed.chunks_mut(8) {
+            chunk.copy_from_slice(&rng.next_u64().to_le_bytes());
+        }
+        seed
+    }
+}
+
+impl AlgorithmSeed for u64 {
+    type Rng = AlgorithmRng;
+
+    /// Expand the `u64` into a full seed, in a platform-independent way.
+    fn into_rng(self) -> AlgorithmRng {
+        AlgorithmRng(ChaCha20Rng::seed_from_u64(self))
+    }
+
+    fn split(&self, stream: u64) -> Self {
+        self.into_rng().split(stream).next_u64()
+    }
+}
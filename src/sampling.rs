@@ -0,0 +1,234 @@
+//! Random sampling over graphs.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::rng::{AlgorithmSeed, SeedableAlgorithmRng};
+use super::types::GraphDiff;
+use super::{Direction, Edge, Graph, GraphObject, Id};
+
+/// A table for sampling from a discrete distribution in constant time, using
+/// Vose's alias method.
+#[derive(Debug, Clone)]
+pub struct AliasTable<T> {
+    items: Vec<T>,
+    prob: Vec<f64>,
+    alias: Vec<usize>,
+}
+
+impl<T> AliasTable<T> {
+    /// Build a table from weighted items. Returns `None` if there are no items
+    /// with a positive weight. Negative and non-finite weights are treated as
+    /// zero.
+    pub fn new(weighted: Vec<(T, f64)>) -> Option<Self> {
+        let (items, weights): (Vec<T>, Vec<f64>) = weighted
+            .into_iter()
+            .map(|(item, w)| (item, if w.is_finite() && w > 0.0 { w } else { 0.0 }))
+            .unzip();
+
+        let n = items.len();
+        let total: f64 = weights.iter().sum();
+        if n == 0 || total <= 0.0 {
+            return None;
+        }
+
+        let mut prob: Vec<f64> = weights.iter().map(|w| w * n as f64 / total).collect();
+        let mut alias: Vec<usize> = (0..n).collect();
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) = (0..n).partition(|&i| prob[i] < 1.0);
+
+        while let (Some(s), Some(&l)) = (small.pop(), large.last()) {
+            alias[s] = l;
+            prob[l] -= 1.0 - prob[s];
+
+            if prob[l] < 1.0 {
+                large.pop();
+                small.push(l);
+            }
+        }
+        // Whatever is left over only differs from `1.0` by rounding errors.
+        for i in small.into_iter().chain(large) {
+            prob[i] = 1.0;
+        }
+
+        Some(AliasTable { items, prob, alias })
+    }
+
+    /// Sample an item given a uniformly distributed number in `[0, 1)`.
+    pub fn sample(&self, u: f64) -> &T {
+        let n = self.items.len();
+        let x = u.max(0.0) * n as f64;
+        let i = (x as usize).min(n - 1);
+
+        if x - (i as f64) < self.prob[i] {
+            &self.items[i]
+        } else {
+            &self.items[self.alias[i]]
+        }
+    }
+
+    /// The number of items in the table.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Whether the table is empty. Tables are never empty.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+/// A hop of a random walk: the neighbor reached and the edge followed.
+pub type Hop<G> = (Id<<G as Graph>::Node>, Id<<G as Graph>::Edge>);
+
+/// Weighted selection of the next hop of a random walk.
+///
+/// Alias tables are built lazily for each node the first time it is sampled
+/// from, and are kept until the node is invalidated, eg. by feeding the
+/// changes made to the graph to `apply_diff`. Edges are ordered by id in the
+/// tables, so that samples don't depend on the order in which the graph
+/// lists them.
+pub struct WeightedNeighbors<G: Graph> {
+    direction: Direction,
+    tables: HashMap<Id<G::Node>, Option<AliasTable<Hop<G>>>>,
+}
+
+impl<G> WeightedNeighbors<G>
+where
+    G: Graph,
+    G::Weight: Into<f64>,
+    Id<G::Node>: Clone + Eq + Hash,
+    Id<G::Edge>: Clone + Ord,
+{
+    /// Sample neighbors by following edges in the given direction.
+    pub fn new(direction: Direction) -> Self {
+        WeightedNeighbors {
+            direction,
+            tables: HashMap::new(),
+        }
+    }
+
+    /// Pick a neighbor of `node` with a probability proportional to the
+    /// weight of the edge leading to it, given a uniformly distributed number
+    /// in `[0, 1)`. Returns the neighbor along with the edge that was
+    /// followed, or `None` if the node has no edge with a positive weight.
+    pub fn sample(&mut self, graph: &G, node: &Id<G::Node>, u: f64) -> Option<&Hop<G>> {
+        let direction = &self.direction;
+
+        self.tables
+            .entry(node.clone())
+            .or_insert_with(|| Self::build(graph, node, direction))
+            .as_ref()
+            .map(|table| table.sample(u))
+    }
+
+    /// Like `sample`, drawing the uniform number from the given generator.
+    pub fn sample_with<R: SeedableAlgorithmRng>(
+        &mut self,
+        graph: &G,
+        node: &Id<G::Node>,
+        rng: &mut R,
+    ) -> Option<&Hop<G>> {
+        let u = rng.next_f64();
+        self.sample(graph, node, u)
+    }
+
+    /// Drop the table of the given node, to be rebuilt on next use.
+    pub fn invalidate(&mut self, node: &Id<G::Node>) {
+        self.tables.remove(node);
+    }
+
+    /// Drop all the tables.
+    pub fn invalidate_all(&mut self) {
+        self.tables.clear();
+    }
+
+    /// Invalidate the tables affected by a change to the graph.
+    pub fn apply_diff(&mut self, diff: &GraphDiff<G>) {
+        match diff {
+            GraphDiff::NodeAdded(_) | GraphDiff::NodeUpdated(_) => {}
+            GraphDiff::NodeDeleted(_) => {
+                // The edges of a deleted node are gone as well, and we can't
+                // know which nodes they were connecting it to.
+                self.invalidate_all();
+            }
+            GraphDiff::EdgeAdded { source, target, .. }
+            | GraphDiff::EdgeUpdated { source, target, .. } => {
+                self.invalidate(source);
+                self.invalidate(target);
+            }
+            GraphDiff::EdgeDeleted(edge) => {
+                self.invalidate(edge.source());
+                self.invalidate(edge.target());
+            }
+        }
+    }
+
+    fn build(graph: &G, node: &Id<G::Node>, direction: &Direction) -> Option<AliasTable<Hop<G>>> {
+        let mut weighted: Vec<(Hop<G>, f64)> = graph
+            .edges_directed(node, *direction)
+            .into_iter()
+            .map(|e| {
+                let neighbor = if *e.from == *node { e.to } else { e.from };
+
+                ((neighbor.clone(), e.id.clone()), e.weight().into())
+            })
+            .collect();
+        weighted.sort_by(|((_, a), _), ((_, b), _)| a.cmp(b));
+
+        AliasTable::new(weighted)
+    }
+}
+
+/// Pick `n` items uniformly at random, in a single pass over `items`, with
+/// reservoir sampling. Returns all items if there are fewer than `n`.
+pub fn reservoir<T, I, R>(items: I, n: usize, rng: &mut R) -> Vec<T>
+where
+    I: IntoIterator<Item = T>,
+    R: SeedableAlgorithmRng,
+{
+    let items = items.into_iter();
+    let mut sample = Vec::with_capacity(n.min(items.size_hint().0));
+
+    for (i, item) in items.enumerate() {
+        if i < n {
+            sample.push(item);
+            continue;
+        }
+        let j = (rng.next_f64() * (i + 1) as f64) as usize;
+        if j < n {
+            sample[j] = item;
+        }
+    }
+    sample
+}
+
+/// Pick `n` nodes of the graph uniformly at random, without collecting
+/// them. The same seed always picks the same nodes of the same graph, as
+/// long as its backend lists them in the same order: `EdgeListGraph` does,
+/// by id, but `ArenaGraph` lists them by storage slot, which depends on the
+/// history of the graph.
+pub fn sample_nodes<G, S>(graph: &G, n: usize, seed: S) -> Vec<&G::Node>
+where
+    G: Graph,
+    S: AlgorithmSeed,
+{
+    reservoir(graph.nodes(), n, &mut seed.into_rng())
+}
+
+/// Pick `n` edges of the graph uniformly at random, without collecting
+/// them. As with `sample_nodes`, the same seed picks the same edges for the
+/// same order of the nodes of the graph and of their edges.
+pub fn sample_edges<G, S>(graph: &G, n: usize, seed: S) -> Vec<&G::Edge>
+where
+    G: Graph,
+    S: AlgorithmSeed,
+{
+    // Every edge is outgoing from exactly one node.
+    let edges = graph.nodes().flat_map(|node| {
+        graph
+            .edges_directed(node.id(), Direction::Outgoing)
+            .into_iter()
+            .filter_map(|e| graph.get_edge(e.id))
+    });
+    reservoir(edges, n, &mut seed.into_rng())
+}
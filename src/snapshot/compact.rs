@@ -0,0 +1,1216 @@
+//! A compact on-disk graph format, usable in place through the `Graph` trait.
+//!
+//! The format stores fixed-width node and edge records along with CSR
+//! (compressed sparse row) adjacency, so that a snapshot can be memory-mapped
+//! and queried without being loaded first: lookups are binary searches over
+//! the records, and node and edge objects are decoded the first time they're
+//! accessed. Only the header and the bounds of the sections are checked when
+//! a snapshot is opened, and records as they're decoded, see
+//! `CompactGraph::validate`. Nodes and edges must have `u64` ids. Only the total
+//! contributions of nodes are stored, not their breakdown, nor the history
+//! of edges.
+//!
+//! All integers are little-endian, and all sections are 8-byte aligned.
+//! Version 1 of the format, written by `write`, is laid out as:
+//!
+//! * a 32 byte header: the `MAGIC` bytes, the `VERSION` as a `u32`, the
+//!   `types::SCHEMA_VERSION` of the graph as a `u32` (`0` for snapshots
+//!   written before it was recorded), then the number of nodes `n` and of edges `m` as `u64`s;
+//! * `n` node records, sorted by id: id `u64`, type `u32`, contributions
+//!   `u32`, rank `f64`;
+//! * `m` edge records, sorted by source node, then id: id `u64`, source and
+//!   target node indices `u64`, type `u32`, contributions `u32`, weight `f64`;
+//! * `n + 1` offsets into the edge records of every node's outgoing edges;
+//! * `n + 1` offsets into the incoming edge list of every node's incoming
+//!   edges, followed by the `m` edge indices of this list;
+//! * the `m` edge indices, sorted by edge id.
+//!
+//! Version 2, written by `write_with` and `Encoding::Compressed`, trades
+//! lookup time for size, for graphs that don't fit in memory otherwise.
+//! Edges are sorted by source node, then target node, then id, and
+//! adjacency lists are delta and varint (LEB128) encoded:
+//!
+//! * a 48 byte header: the version 1 header with `COMPRESSED_VERSION`, then
+//!   flags as a `u64`, whose bit 0 is set if edge data is stored in a
+//!   dictionary, and the number of dictionary entries `d` as a `u64`;
+//! * `n` node records, as in version 1;
+//! * the `m` edge ids, as `u64`s;
+//! * with a dictionary, the index of the data of every edge as a `u32`,
+//!   followed by the `d` distinct data: type `u32`, contributions `u32`,
+//!   weight `f64`. Otherwise, the `m` data of edges in this format;
+//! * `n + 1` offsets into the edges of every node's outgoing edges, then
+//!   `n + 1` byte offsets of their lists of targets;
+//! * `n + 1` offsets into the incoming edges of every node, then `n + 1`
+//!   byte offsets of their lists of edge indices;
+//! * the `m` edge indices, sorted by edge id;
+//! * the lists of targets: the sorted node indices of the targets of every
+//!   node's outgoing edges, as the first index then the differences between
+//!   consecutive ones, as varints;
+//! * the lists of edge indices of every node's incoming edges, sorted and
+//!   encoded the same way.
+//!
+//! The source of an edge is found by binary search of the outgoing offsets,
+//! and its target by decoding the list of targets of its source.
+//!
+//! Decoded nodes and edges are kept in a cache, as `Graph` hands out
+//! references to them. The cache grows while the graph is borrowed
+//! immutably, eg. up to the whole graph after `Graph::nodes` or the execution
+//! of an algorithm, and is only emptied by `CompactGraph::clear_cache`, when
+//! no reference to a cached object can be alive.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{self, Write};
+use std::ops::Range;
+
+use crate::epochs::EdgeHistory;
+use crate::mem::{MemEdge, MemNode};
+use crate::types::{
+    ContributionLedger, EdgeData, EdgeType, NodeData, NodeRank, NodeType, Timestamps,
+    SCHEMA_VERSION,
+};
+use crate::{Direction, Edge, EdgeRef, EdgeRefs, Edges, Graph, GraphObject, Node, Nodes};
+
+/// The first bytes of a compact snapshot.
+pub const MAGIC: [u8; 8] = *b"OSCGRAPH";
+
+/// The version of the format.
+pub const VERSION: u32 = 1;
+
+/// The version of the format with compressed adjacency.
+pub const COMPRESSED_VERSION: u32 = 2;
+
+const HEADER_LEN: usize = 32;
+const COMPRESSED_HEADER_LEN: usize = 48;
+const NODE_LEN: usize = 24;
+const EDGE_LEN: usize = 40;
+const EDGE_DATA_LEN: usize = 16;
+const DICTIONARY_FLAG: u64 = 1;
+
+/// An invalid compact snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompactError {
+    /// The data doesn't start with `MAGIC`.
+    BadMagic,
+    /// The snapshot was written with an unsupported version of the format.
+    UnsupportedVersion(u32),
+    /// The data is shorter or longer than its header implies.
+    BadLength {
+        /// The length implied by the header.
+        expected: u64,
+        /// The actual length.
+        actual: u64,
+    },
+    /// A record or index is invalid.
+    Corrupt(String),
+}
+
+impl fmt::Display for CompactError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompactError::BadMagic => write!(f, "not a compact graph snapshot"),
+            CompactError::UnsupportedVersion(v) => write!(f, "unsupported version {}", v),
+            CompactError::BadLength { expected, actual } => {
+                write!(f, "expected {} bytes, found {}", expected, actual)
+            }
+            CompactError::Corrupt(msg) => write!(f, "corrupt snapshot: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for CompactError {}
+
+/// The encoding of a snapshot written by `write_with`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub enum Encoding {
+    /// Version 1 of the format, with fixed-width adjacency.
+    Plain,
+    /// Version 2 of the format, with delta and varint encoded adjacency.
+    Compressed {
+        /// Whether to store the distinct data of edges once, in a
+        /// dictionary. Worth it when most edges share their type and weight,
+        /// as dependencies do.
+        edge_dictionary: bool,
+    },
+}
+
+/// A node, as written.
+struct NodeRecord {
+    id: u64,
+    kind: u32,
+    contributions: u32,
+    rank: f64,
+}
+
+/// An edge between node indices, as written.
+struct EdgeRecord {
+    id: u64,
+    from: u64,
+    to: u64,
+    kind: u32,
+    contributions: u32,
+    weight: f64,
+}
+
+impl EdgeRecord {
+    /// The data of the edge: type, contributions and the bits of the weight.
+    fn data(&self) -> (u32, u32, u64) {
+        (self.kind, self.contributions, self.weight.to_bits())
+    }
+}
+
+/// Write `graph` in the compact format. Fails with `InvalidInput` if the
+/// graph has edges of a custom type, which the format can't store.
+pub fn write<G, W, O>(graph: &G, out: O) -> io::Result<()>
+where
+    G: Graph<NodeData = NodeData<W>, Weight = W>,
+    G::Node: GraphObject<Id = u64>,
+    G::Edge: GraphObject<Id = u64>,
+    W: Clone + Into<f64>,
+    O: Write,
+{
+    write_with(graph, out, Encoding::Plain)
+}
+
+/// Write `graph` in the compact format, with the given encoding. Fails as
+/// `write` does, or if there are more than `u32::MAX` distinct edge data
+/// for the dictionary.
+pub fn write_with<G, W, O>(graph: &G, mut out: O, encoding: Encoding) -> io::Result<()>
+where
+    G: Graph<NodeData = NodeData<W>, Weight = W>,
+    G::Node: GraphObject<Id = u64>,
+    G::Edge: GraphObject<Id = u64>,
+    W: Clone + Into<f64>,
+    O: Write,
+{
+    let mut nodes: Vec<&G::Node> = graph.nodes().collect();
+    nodes.sort_by_key(|n| *n.id());
+    let index = |id: &u64| nodes.binary_search_by_key(id, |n| *n.id()).ok();
+
+    // Edges by source index, then id. Edges to unknown nodes are skipped.
+    let mut edges: Vec<EdgeRecord> = Vec::new();
+    for (i, node) in nodes.iter().enumerate() {
+        let start = edges.len();
+        for e in graph.edges_directed(node.id(), Direction::Outgoing) {
+            let (to, edge) = match (index(e.to), graph.get_edge(e.id)) {
+                (Some(to), Some(edge)) => (to, edge),
+                _ => continue,
+            };
+            let (kind, contributions) = edge_type_code(edge.edge_type()).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "custom edge types can't be stored in compact snapshots",
+                )
+            })?;
+            edges.push(EdgeRecord {
+                id: *edge.id(),
+                from: i as u64,
+                to: to as u64,
+                kind,
+                contributions,
+                weight: edge.weight().into(),
+            });
+        }
+        edges[start..].sort_by_key(|e| e.id);
+    }
+    let nodes: Vec<NodeRecord> = nodes
+        .iter()
+        .map(|node| {
+            let (kind, contributions) = node_type_code(node.node_type());
+            NodeRecord {
+                id: *node.id(),
+                kind,
+                contributions,
+                rank: node.data().rank.rank.clone().into(),
+            }
+        })
+        .collect();
+
+    match encoding {
+        Encoding::Plain => write_plain(&nodes, &edges, &mut out)?,
+        Encoding::Compressed { edge_dictionary } => {
+            edges.sort_by_key(|e| (e.from, e.to, e.id));
+            write_compressed(&nodes, &edges, edge_dictionary, &mut out)?;
+        }
+    }
+    out.flush()
+}
+
+fn write_plain<O: Write>(
+    nodes: &[NodeRecord],
+    edges: &[EdgeRecord],
+    out: &mut O,
+) -> io::Result<()> {
+    let (n, m) = (nodes.len(), edges.len());
+    write_header(out, VERSION, n, m)?;
+    write_nodes(out, nodes)?;
+    for edge in edges {
+        write_u64s(out, [edge.id, edge.from, edge.to])?;
+        write_edge_data(out, edge.data())?;
+    }
+
+    write_u64s(out, run_offsets(n, m, |e| edges[e].from))?;
+    let incoming = incoming(edges);
+    write_u64s(out, run_offsets(n, m, |j| incoming[j].0))?;
+    write_u64s(out, incoming.iter().map(|(_, e)| *e))?;
+    write_u64s(out, by_id(edges))
+}
+
+/// Write edges sorted by source, then target, in version 2 of the format.
+fn write_compressed<O: Write>(
+    nodes: &[NodeRecord],
+    edges: &[EdgeRecord],
+    edge_dictionary: bool,
+    out: &mut O,
+) -> io::Result<()> {
+    let (n, m) = (nodes.len(), edges.len());
+
+    // Distinct edge data, by first use, and the index of every edge's.
+    let mut dictionary: Vec<(u32, u32, u64)> = Vec::new();
+    let mut entries: Vec<u32> = Vec::new();
+    if edge_dictionary {
+        let mut known = BTreeMap::new();
+        for edge in edges {
+            let entry = match known.get(&edge.data()) {
+                Some(entry) => *entry,
+                None => {
+                    let entry = u32::try_from(dictionary.len()).map_err(|_| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "too many distinct edge data for a dictionary",
+                        )
+                    })?;
+                    known.insert(edge.data(), entry);
+                    dictionary.push(edge.data());
+                    entry
+                }
+            };
+            entries.push(entry);
+        }
+    }
+
+    let out_offsets = run_offsets(n, m, |e| edges[e].from);
+    let (out_bytes, out_lists) = encode_lists(&out_offsets, |e| edges[e].to);
+    let incoming = incoming(edges);
+    let in_offsets = run_offsets(n, m, |j| incoming[j].0);
+    let (in_bytes, in_lists) = encode_lists(&in_offsets, |j| incoming[j].1);
+
+    write_header(out, COMPRESSED_VERSION, n, m)?;
+    let flags = if edge_dictionary { DICTIONARY_FLAG } else { 0 };
+    write_u64s(out, [flags, dictionary.len() as u64])?;
+    write_nodes(out, nodes)?;
+    write_u64s(out, edges.iter().map(|e| e.id))?;
+    if edge_dictionary {
+        for entry in &entries {
+            out.write_all(&entry.to_le_bytes())?;
+        }
+        write_padding(out, entries.len() * 4)?;
+        for data in dictionary {
+            write_edge_data(out, data)?;
+        }
+    } else {
+        for edge in edges {
+            write_edge_data(out, edge.data())?;
+        }
+    }
+    write_u64s(out, out_offsets)?;
+    write_u64s(out, out_bytes)?;
+    write_u64s(out, in_offsets)?;
+    write_u64s(out, in_bytes)?;
+    write_u64s(out, by_id(edges))?;
+    out.write_all(&out_lists)?;
+    write_padding(out, out_lists.len())?;
+    out.write_all(&in_lists)?;
+    write_padding(out, in_lists.len())
+}
+
+fn write_header<O: Write>(out: &mut O, version: u32, n: usize, m: usize) -> io::Result<()> {
+    out.write_all(&MAGIC)?;
+    out.write_all(&version.to_le_bytes())?;
+    out.write_all(&SCHEMA_VERSION.to_le_bytes())?;
+    write_u64s(out, [n as u64, m as u64])
+}
+
+fn write_nodes<O: Write>(out: &mut O, nodes: &[NodeRecord]) -> io::Result<()> {
+    for node in nodes {
+        out.write_all(&node.id.to_le_bytes())?;
+        out.write_all(&node.kind.to_le_bytes())?;
+        out.write_all(&node.contributions.to_le_bytes())?;
+        out.write_all(&node.rank.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn write_edge_data<O: Write>(
+    out: &mut O,
+    (kind, contributions, weight): (u32, u32, u64),
+) -> io::Result<()> {
+    out.write_all(&kind.to_le_bytes())?;
+    out.write_all(&contributions.to_le_bytes())?;
+    out.write_all(&weight.to_le_bytes())
+}
+
+fn write_u64s<O: Write, I: IntoIterator<Item = u64>>(out: &mut O, values: I) -> io::Result<()> {
+    for value in values {
+        out.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Pad a section of `len` bytes to 8-byte alignment.
+fn write_padding<O: Write>(out: &mut O, len: usize) -> io::Result<()> {
+    out.write_all(&[0; 8][..(8 - len % 8) % 8])
+}
+
+/// The `n + 1` offsets of the runs of every node in a list of `m` items
+/// sorted by the node index given by `node`.
+fn run_offsets<F: Fn(usize) -> u64>(n: usize, m: usize, node: F) -> Vec<u64> {
+    let mut offsets = Vec::with_capacity(n + 1);
+    let mut offset = 0usize;
+    offsets.push(0);
+    for i in 0..n as u64 {
+        while offset < m && node(offset) == i {
+            offset += 1;
+        }
+        offsets.push(offset as u64);
+    }
+    offsets
+}
+
+/// The `(target, index)` of the edges, sorted by target then index.
+fn incoming(edges: &[EdgeRecord]) -> Vec<(u64, u64)> {
+    let mut incoming: Vec<(u64, u64)> = edges
+        .iter()
+        .enumerate()
+        .map(|(i, e)| (e.to, i as u64))
+        .collect();
+    incoming.sort_unstable();
+    incoming
+}
+
+/// The indices of the edges, sorted by edge id.
+fn by_id(edges: &[EdgeRecord]) -> Vec<u64> {
+    let mut by_id: Vec<(u64, u64)> = edges
+        .iter()
+        .enumerate()
+        .map(|(i, e)| (e.id, i as u64))
+        .collect();
+    by_id.sort_unstable();
+    by_id.into_iter().map(|(_, i)| i).collect()
+}
+
+/// Delta and varint encode the sorted values of every run given by
+/// `offsets`, returning the byte offsets of the encoded runs and their bytes.
+fn encode_lists<F: Fn(usize) -> u64>(offsets: &[u64], value: F) -> (Vec<u64>, Vec<u8>) {
+    let mut bytes = Vec::new();
+    let mut byte_offsets = Vec::with_capacity(offsets.len());
+    byte_offsets.push(0);
+    for run in offsets.windows(2) {
+        let mut previous = 0;
+        for j in run[0]..run[1] {
+            let v = value(j as usize);
+            write_varint(&mut bytes, v - previous);
+            previous = v;
+        }
+        byte_offsets.push(bytes.len() as u64);
+    }
+    (byte_offsets, bytes)
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Read a varint from `data` at `at`, advancing it, unless it's truncated or
+/// overflows.
+fn read_varint(data: &[u8], at: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *data.get(*at)?;
+        *at += 1;
+        let bits = u64::from(byte & 0x7f);
+        if shift == 63 && bits > 1 {
+            return None;
+        }
+        value |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// A graph read in place from a compact snapshot, stored in `B`, eg. a
+/// `Vec<u8>` or a memory map.
+///
+/// Reading a corrupt snapshot never panics: the nodes and edges whose
+/// records are invalid, or refer to records out of the snapshot, are
+/// missing. The rest may still be inconsistent, eg. an edge listed among the
+/// edges of a node it doesn't link, unless the snapshot passed `validate`.
+pub struct CompactGraph<B> {
+    bytes: B,
+    n: usize,
+    m: usize,
+    layout: Layout,
+    cache: RefCell<Cache>,
+}
+
+/// The nodes and edges decoded so far, by index. They're boxed so that
+/// references to them stay valid when the maps grow.
+#[derive(Default)]
+struct Cache {
+    nodes: BTreeMap<usize, Box<MemNode<u64, f64>>>,
+    edges: BTreeMap<usize, Box<MemEdge<u64, f64>>>,
+}
+
+/// The layout of a snapshot, by version of the format.
+#[derive(Debug, Clone, Copy)]
+enum Layout {
+    Plain,
+    Compressed(Sections),
+}
+
+/// The byte offsets of the sections of a compressed snapshot.
+#[derive(Debug, Clone, Copy)]
+struct Sections {
+    edge_ids: usize,
+    /// The dictionary entries of the edges, or their data.
+    edge_data: usize,
+    /// The offset and length of the dictionary, if any.
+    dictionary: Option<(usize, usize)>,
+    out_offsets: usize,
+    out_bytes: usize,
+    in_offsets: usize,
+    in_bytes: usize,
+    by_id: usize,
+    out_lists: usize,
+    in_lists: usize,
+}
+
+impl Sections {
+    /// Locate the sections, checking that they fit the data exactly.
+    fn new(data: &[u8], n: u64, m: u64) -> Result<Self, CompactError> {
+        let bad_length = |expected: u128| CompactError::BadLength {
+            expected: expected.min(u64::MAX as u128) as u64,
+            actual: data.len() as u64,
+        };
+        let padded = |len: u128| len.div_ceil(8) * 8;
+
+        if data.len() < COMPRESSED_HEADER_LEN {
+            return Err(bad_length(COMPRESSED_HEADER_LEN as u128));
+        }
+        let flags = read_u64(data, 32);
+        let d = read_u64(data, 40);
+        if flags & !DICTIONARY_FLAG != 0 {
+            return Err(CompactError::Corrupt(format!("unknown flags {:#x}", flags)));
+        }
+        let has_dictionary = flags & DICTIONARY_FLAG != 0;
+        if !has_dictionary && d != 0 {
+            return Err(CompactError::Corrupt(
+                "dictionary entries without a dictionary".to_owned(),
+            ));
+        }
+
+        let (n, m, d) = (n as u128, m as u128, d as u128);
+        let edge_ids = COMPRESSED_HEADER_LEN as u128 + n * NODE_LEN as u128;
+        let edge_data = edge_ids + m * 8;
+        let (dictionary, out_offsets) = if has_dictionary {
+            let dictionary = edge_data + padded(m * 4);
+            (Some(dictionary), dictionary + d * EDGE_DATA_LEN as u128)
+        } else {
+            (None, edge_data + m * EDGE_DATA_LEN as u128)
+        };
+        let out_bytes = out_offsets + (n + 1) * 8;
+        let in_offsets = out_bytes + (n + 1) * 8;
+        let in_bytes = in_offsets + (n + 1) * 8;
+        let by_id = in_bytes + (n + 1) * 8;
+        let out_lists = by_id + m * 8;
+        if out_lists > data.len() as u128 {
+            return Err(bad_length(out_lists));
+        }
+
+        // The lengths of the lists are their last byte offsets.
+        let out_len = read_u64(data, (out_bytes + n * 8) as usize) as u128;
+        let in_lists = out_lists + padded(out_len);
+        let in_len = read_u64(data, (in_bytes + n * 8) as usize) as u128;
+        let expected = in_lists + padded(in_len);
+        if expected != data.len() as u128 {
+            return Err(bad_length(expected));
+        }
+
+        // Every offset is now within the data.
+        Ok(Sections {
+            edge_ids: edge_ids as usize,
+            edge_data: edge_data as usize,
+            dictionary: dictionary.map(|at| (at as usize, d as usize)),
+            out_offsets: out_offsets as usize,
+            out_bytes: out_bytes as usize,
+            in_offsets: in_offsets as usize,
+            in_bytes: in_bytes as usize,
+            by_id: by_id as usize,
+            out_lists: out_lists as usize,
+            in_lists: in_lists as usize,
+        })
+    }
+}
+
+impl<B: AsRef<[u8]>> CompactGraph<B> {
+    /// Use the given bytes as a compact snapshot, of either version. Only
+    /// the header and the bounds of the sections are checked, see
+    /// `validate`.
+    pub fn from_bytes(bytes: B) -> Result<Self, CompactError> {
+        let data = bytes.as_ref();
+        if data.len() < HEADER_LEN || data[..8] != MAGIC {
+            return Err(CompactError::BadMagic);
+        }
+        let version = u32::from_le_bytes([data[8], data[9], data[10], data[11]]);
+        let n = read_u64(data, 16);
+        let m = read_u64(data, 24);
+        let layout = match version {
+            VERSION => {
+                let expected = (n as u128 * NODE_LEN as u128)
+                    + (m as u128 * EDGE_LEN as u128)
+                    + ((n as u128 + 1) * 16)
+                    + (m as u128 * 16)
+                    + HEADER_LEN as u128;
+                if expected != data.len() as u128 {
+                    return Err(CompactError::BadLength {
+                        expected: expected.min(u64::MAX as u128) as u64,
+                        actual: data.len() as u64,
+                    });
+                }
+                Layout::Plain
+            }
+            COMPRESSED_VERSION => Layout::Compressed(Sections::new(data, n, m)?),
+            _ => return Err(CompactError::UnsupportedVersion(version)),
+        };
+
+        Ok(CompactGraph {
+            n: n as usize,
+            m: m as usize,
+            layout,
+            cache: RefCell::new(Cache::default()),
+            bytes,
+        })
+    }
+
+    /// The version of the format of the snapshot, `VERSION` or
+    /// `COMPRESSED_VERSION`.
+    pub fn version(&self) -> u32 {
+        read_u32(self.data(), 8)
+    }
+
+    /// The `types::SCHEMA_VERSION` the snapshot was written with, or `0` if
+    /// it predates schema versions. Snapshots of an older schema should be
+    /// loaded and upgraded with `migrate::Migrator`.
+    pub fn schema_version(&self) -> u32 {
+        read_u32(self.data(), 12)
+    }
+
+    /// The number of nodes.
+    pub fn node_count(&self) -> usize {
+        self.n
+    }
+
+    /// The number of edges.
+    pub fn edge_count(&self) -> usize {
+        self.m
+    }
+
+    /// The number of nodes and edges decoded and cached so far.
+    pub fn cached_objects(&self) -> usize {
+        let cache = self.cache.borrow();
+        cache.nodes.len() + cache.edges.len()
+    }
+
+    /// Drop every cached node and edge, to be decoded again on their next
+    /// access.
+    pub fn clear_cache(&mut self) {
+        *self.cache.get_mut() = Cache::default();
+    }
+
+    fn data(&self) -> &[u8] {
+        self.bytes.as_ref()
+    }
+
+    fn node_offset(&self, i: usize) -> usize {
+        let header = match self.layout {
+            Layout::Plain => HEADER_LEN,
+            Layout::Compressed(_) => COMPRESSED_HEADER_LEN,
+        };
+        header + i * NODE_LEN
+    }
+
+    fn edge_offset(&self, i: usize) -> usize {
+        HEADER_LEN + self.n * NODE_LEN + i * EDGE_LEN
+    }
+
+    fn out_offsets(&self) -> usize {
+        match self.layout {
+            Layout::Plain => self.edge_offset(self.m),
+            Layout::Compressed(s) => s.out_offsets,
+        }
+    }
+
+    fn in_offsets(&self) -> usize {
+        match self.layout {
+            Layout::Plain => self.out_offsets() + (self.n + 1) * 8,
+            Layout::Compressed(s) => s.in_offsets,
+        }
+    }
+
+    fn in_edges(&self) -> usize {
+        self.in_offsets() + (self.n + 1) * 8
+    }
+
+    fn edges_by_id(&self) -> usize {
+        match self.layout {
+            Layout::Plain => self.in_edges() + self.m * 8,
+            Layout::Compressed(s) => s.by_id,
+        }
+    }
+
+    fn word(&self, offset: usize, i: usize) -> usize {
+        read_u64(self.data(), offset + i * 8) as usize
+    }
+
+    fn node_id(&self, i: usize) -> u64 {
+        read_u64(self.data(), self.node_offset(i))
+    }
+
+    fn edge_id(&self, i: usize) -> u64 {
+        match self.layout {
+            Layout::Plain => read_u64(self.data(), self.edge_offset(i)),
+            Layout::Compressed(s) => read_u64(self.data(), s.edge_ids + i * 8),
+        }
+    }
+
+    /// The offset of the type, contributions and weight of edge `i`.
+    fn edge_data_offset(&self, i: usize) -> Option<usize> {
+        match self.layout {
+            Layout::Plain => Some(self.edge_offset(i) + 24),
+            Layout::Compressed(s) => match s.dictionary {
+                Some((at, len)) => {
+                    let entry = read_u32(self.data(), s.edge_data + i * 4) as usize;
+                    (entry < len).then(|| at + entry * EDGE_DATA_LEN)
+                }
+                None => Some(s.edge_data + i * EDGE_DATA_LEN),
+            },
+        }
+    }
+
+    /// The range of the edges of node `i` in `offsets`, if valid.
+    fn run(&self, offsets: usize, i: usize) -> Option<Range<usize>> {
+        let (start, end) = (self.word(offsets, i), self.word(offsets, i + 1));
+        (start <= end && end <= self.m).then_some(start..end)
+    }
+
+    fn decode_edge_type(&self, at: usize) -> Option<EdgeType> {
+        let data = self.data();
+        decode_edge_type(read_u32(data, at), read_u32(data, at + 4))
+    }
+
+    /// Check every record and index of the snapshot, so that it's read
+    /// consistently. Opening a snapshot doesn't, as reading it is safe
+    /// anyway, and this takes time linear in its size.
+    pub fn validate(&self) -> Result<(), CompactError> {
+        let data = self.data();
+
+        for i in 0..self.n {
+            let at = self.node_offset(i);
+            if i > 0 && self.node_id(i - 1) >= self.node_id(i) {
+                return corrupt("nodes are not sorted by id");
+            }
+            if decode_node_type(read_u32(data, at + 8), read_u32(data, at + 12)).is_none() {
+                return corrupt("invalid node type");
+            }
+        }
+        for offsets in [self.out_offsets(), self.in_offsets()] {
+            if self.word(offsets, 0) != 0 || self.word(offsets, self.n) != self.m {
+                return corrupt("invalid adjacency offsets");
+            }
+            for i in 0..self.n {
+                if self.word(offsets, i) > self.word(offsets, i + 1) {
+                    return corrupt("adjacency offsets are not sorted");
+                }
+            }
+        }
+        match self.layout {
+            Layout::Plain => self.validate_plain()?,
+            Layout::Compressed(s) => self.validate_compressed(&s)?,
+        }
+        for i in 0..self.m {
+            let e = self.word(self.edges_by_id(), i);
+            if e >= self.m {
+                return corrupt("edge index out of range");
+            }
+            if i > 0 && self.edge_id(self.word(self.edges_by_id(), i - 1)) >= self.edge_id(e) {
+                return corrupt("edge index is not sorted by id");
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_plain(&self) -> Result<(), CompactError> {
+        let data = self.data();
+
+        for i in 0..self.m {
+            let at = self.edge_offset(i);
+            if read_u64(data, at + 8) >= self.n as u64 || read_u64(data, at + 16) >= self.n as u64 {
+                return corrupt("edge endpoint out of range");
+            }
+            if self.decode_edge_type(at + 24).is_none() {
+                return corrupt("invalid edge type");
+            }
+        }
+        for i in 0..self.n {
+            let (start, end) = (
+                self.word(self.out_offsets(), i),
+                self.word(self.out_offsets(), i + 1),
+            );
+            for e in start..end {
+                if read_u64(data, self.edge_offset(e) + 8) != i as u64 {
+                    return corrupt("outgoing edge with another source");
+                }
+            }
+            let (start, end) = (
+                self.word(self.in_offsets(), i),
+                self.word(self.in_offsets(), i + 1),
+            );
+            for j in start..end {
+                let e = self.word(self.in_edges(), j);
+                if e >= self.m || read_u64(data, self.edge_offset(e) + 16) != i as u64 {
+                    return corrupt("incoming edge with another target");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_compressed(&self, s: &Sections) -> Result<(), CompactError> {
+        match s.dictionary {
+            Some((at, len)) => {
+                for i in 0..self.m {
+                    if read_u32(self.data(), s.edge_data + i * 4) as usize >= len {
+                        return corrupt("dictionary entry out of range");
+                    }
+                }
+                for i in 0..len {
+                    if self.decode_edge_type(at + i * EDGE_DATA_LEN).is_none() {
+                        return corrupt("invalid edge type");
+                    }
+                }
+            }
+            None => {
+                for i in 0..self.m {
+                    if self
+                        .decode_edge_type(s.edge_data + i * EDGE_DATA_LEN)
+                        .is_none()
+                    {
+                        return corrupt("invalid edge type");
+                    }
+                }
+            }
+        }
+        for bytes in [s.out_bytes, s.in_bytes] {
+            for i in 0..self.n {
+                if self.word(bytes, i) > self.word(bytes, i + 1) {
+                    return corrupt("list offsets are not sorted");
+                }
+            }
+            if self.word(bytes, 0) != 0 {
+                return corrupt("invalid list offsets");
+            }
+        }
+
+        // Every edge must be listed once as outgoing to some target, and once
+        // as incoming to the same node: their fingerprints must match.
+        let (mut outgoing, mut incoming) = (0u64, 0u64);
+        for i in 0..self.n {
+            let targets = self
+                .decode_list(s.out_lists, s.out_bytes, s.out_offsets, i)
+                .ok_or_else(|| CompactError::Corrupt("invalid list of targets".to_owned()))?;
+            let start = self.word(s.out_offsets, i);
+            for (j, to) in targets.into_iter().enumerate() {
+                if to >= self.n as u64 {
+                    return corrupt("edge target out of range");
+                }
+                outgoing = outgoing.wrapping_add(fingerprint((start + j) as u64, to));
+            }
+
+            let edges = self
+                .decode_list(s.in_lists, s.in_bytes, s.in_offsets, i)
+                .ok_or_else(|| CompactError::Corrupt("invalid list of edges".to_owned()))?;
+            for (j, e) in edges.iter().enumerate() {
+                if *e >= self.m as u64 || (j > 0 && edges[j - 1] == *e) {
+                    return corrupt("invalid incoming edge");
+                }
+                incoming = incoming.wrapping_add(fingerprint(*e, i as u64));
+            }
+        }
+        if outgoing != incoming {
+            return corrupt("incoming edges don't match outgoing edges");
+        }
+        Ok(())
+    }
+
+    /// Decode the list of node `i` in a compressed snapshot, given the
+    /// sections of the lists, of their byte offsets, and of the offsets of
+    /// the runs they encode. `None` if the list isn't exactly its bytes.
+    fn decode_list(
+        &self,
+        lists: usize,
+        bytes: usize,
+        offsets: usize,
+        i: usize,
+    ) -> Option<Vec<u64>> {
+        let data = self.data();
+        let at = lists.checked_add(self.word(bytes, i))?;
+        let end = lists.checked_add(self.word(bytes, i + 1))?;
+        if at > end || end > data.len() {
+            return None;
+        }
+        let len = self.run(offsets, i)?.len();
+
+        let mut at = at;
+
+        let mut values = Vec::with_capacity(len);
+        let mut value = 0u64;
+        for _ in 0..len {
+            value = value.checked_add(read_varint(&data[..end], &mut at)?)?;
+            values.push(value);
+        }
+        if at == end {
+            Some(values)
+        } else {
+            None
+        }
+    }
+
+    /// The node with the run of edge `e` in `offsets`.
+    fn run_of(&self, offsets: usize, e: usize) -> usize {
+        let (mut lo, mut hi) = (0, self.n);
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            if self.word(offsets, mid) <= e {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+
+    /// The `(edge, target)` indices of the outgoing edges of node `i`.
+    fn outgoing(&self, i: usize) -> Vec<(usize, usize)> {
+        let edges = match self.run(self.out_offsets(), i) {
+            Some(edges) => edges,
+            None => return Vec::new(),
+        };
+        match self.layout {
+            Layout::Plain => edges
+                .map(|e| (e, read_u64(self.data(), self.edge_offset(e) + 16) as usize))
+                .collect(),
+            Layout::Compressed(s) => edges.zip(self.targets(&s, i).unwrap_or_default()).collect(),
+        }
+    }
+
+    /// The `(edge, source)` indices of the incoming edges of node `i`.
+    fn incoming(&self, i: usize) -> Vec<(usize, usize)> {
+        match self.layout {
+            Layout::Plain => self
+                .run(self.in_offsets(), i)
+                .unwrap_or_default()
+                .map(|j| self.word(self.in_edges(), j))
+                .filter(|&e| e < self.m)
+                .map(|e| (e, read_u64(self.data(), self.edge_offset(e) + 8) as usize))
+                .collect(),
+            Layout::Compressed(s) => self
+                .decode_list(s.in_lists, s.in_bytes, s.in_offsets, i)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|&e| e < self.m as u64)
+                .map(|e| (e as usize, self.run_of(s.out_offsets, e as usize)))
+                .collect(),
+        }
+    }
+
+    fn targets(&self, s: &Sections, i: usize) -> Option<Vec<usize>> {
+        let targets = self.decode_list(s.out_lists, s.out_bytes, s.out_offsets, i)?;
+        Some(targets.into_iter().map(|to| to as usize).collect())
+    }
+
+    /// The `(source, target)` indices of edge `e`, if they're nodes.
+    fn endpoints(&self, e: usize) -> Option<(usize, usize)> {
+        let (from, to) = match self.layout {
+            Layout::Plain => {
+                let at = self.edge_offset(e);
+                (
+                    read_u64(self.data(), at + 8) as usize,
+                    read_u64(self.data(), at + 16) as usize,
+                )
+            }
+            Layout::Compressed(s) => {
+                let from = self.run_of(s.out_offsets, e);
+                let nth = e.checked_sub(self.word(s.out_offsets, from))?;
+                (from, *self.targets(&s, from)?.get(nth)?)
+            }
+        };
+        (from < self.n && to < self.n).then_some((from, to))
+    }
+
+    fn find_node(&self, id: u64) -> Option<usize> {
+        binary_search(self.n, id, |i| self.node_id(i))
+    }
+
+    fn find_edge(&self, id: u64) -> Option<usize> {
+        let by_id = self.edges_by_id();
+        let edge = |i| Some(self.word(by_id, i)).filter(|&e| e < self.m);
+
+        binary_search(self.m, id, |i| {
+            edge(i).map_or(u64::MAX, |e| self.edge_id(e))
+        })
+        .and_then(edge)
+    }
+
+    /// Node `i`, decoded on first access. `None` if it's out of range, or
+    /// its record is invalid.
+    fn node(&self, i: usize) -> Option<&MemNode<u64, f64>> {
+        let mut cache = self.cache.borrow_mut();
+        let node: *const MemNode<u64, f64> = match cache.nodes.get(&i) {
+            Some(node) => &**node,
+            None => &**cache
+                .nodes
+                .entry(i)
+                .or_insert(Box::new(self.decode_node(i)?)),
+        };
+        // The node is boxed, and only dropped by `clear_cache`, which borrows
+        // the graph mutably.
+        Some(unsafe { &*node })
+    }
+
+    /// Edge `i`, decoded on first access. `None` if it's out of range, or
+    /// its record is invalid.
+    fn edge(&self, i: usize) -> Option<&MemEdge<u64, f64>> {
+        let mut cache = self.cache.borrow_mut();
+        let edge: *const MemEdge<u64, f64> = match cache.edges.get(&i) {
+            Some(edge) => &**edge,
+            None => &**cache
+                .edges
+                .entry(i)
+                .or_insert(Box::new(self.decode_edge(i)?)),
+        };
+        // The edge is boxed, and only dropped by `clear_cache`, which borrows
+        // the graph mutably.
+        Some(unsafe { &*edge })
+    }
+
+    fn decode_node(&self, i: usize) -> Option<MemNode<u64, f64>> {
+        if i >= self.n {
+            return None;
+        }
+        let data = self.data();
+        let at = self.node_offset(i);
+        let node_type = decode_node_type(read_u32(data, at + 8), read_u32(data, at + 12))?;
+        let rank = f64::from_bits(read_u64(data, at + 16));
+
+        Some(MemNode::new(
+            self.node_id(i),
+            NodeData {
+                node_type,
+                rank: NodeRank { rank },
+                contributions: ContributionLedger::new(),
+                timestamps: Timestamps::default(),
+            },
+        ))
+    }
+
+    fn decode_edge(&self, i: usize) -> Option<MemEdge<u64, f64>> {
+        if i >= self.m {
+            return None;
+        }
+        let at = self.edge_data_offset(i)?;
+        let edge_type = self.decode_edge_type(at)?;
+        let (from, to) = self.endpoints(i)?;
+        let weight = f64::from_bits(read_u64(self.data(), at + 8));
+
+        Some(MemEdge::new(
+            self.edge_id(i),
+            self.node_id(from),
+            self.node_id(to),
+            EdgeData {
+                edge_type,
+                weight,
+                history: EdgeHistory::new(),
+                timestamps: Timestamps::default(),
+            },
+        ))
+    }
+
+    /// The indices of the edges of node `i` in the given direction.
+    fn adjacent(&self, i: usize, dir: Direction) -> Vec<usize> {
+        let edges = |adjacent: Vec<(usize, usize)>| adjacent.into_iter().map(|(e, _)| e);
+        match dir {
+            Direction::Outgoing => edges(self.outgoing(i)).collect(),
+            Direction::Incoming => edges(self.incoming(i)).collect(),
+            Direction::Both => {
+                let mut es: Vec<usize> = edges(self.outgoing(i))
+                    .chain(edges(self.incoming(i)))
+                    .collect();
+                es.sort_unstable();
+                es.dedup();
+                es
+            }
+        }
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl CompactGraph<memmap2::Mmap> {
+    /// Memory-map the snapshot at `path`.
+    ///
+    /// The file must not be modified while it is mapped.
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        // Safety: the mapping is read-only, and the file is required not to
+        // change while mapped. Its contents are checked as they're read.
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+
+        Self::from_bytes(map).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl<B: AsRef<[u8]>> Graph for CompactGraph<B> {
+    type Node = MemNode<u64, f64>;
+    type Edge = MemEdge<u64, f64>;
+    type NodeData = NodeData<f64>;
+    type EdgeData = EdgeData<f64>;
+    type Weight = f64;
+
+    fn get_node(&self, id: &u64) -> Option<&Self::Node> {
+        self.find_node(*id).and_then(|i| self.node(i))
+    }
+
+    fn get_edge(&self, id: &u64) -> Option<&Self::Edge> {
+        self.find_edge(*id).and_then(|i| self.edge(i))
+    }
+
+    fn nodes(&self) -> Nodes<'_, Self::Node> {
+        Nodes {
+            range: (0..self.n)
+                .filter_map(|i| self.node(i))
+                .collect::<Vec<_>>()
+                .into_iter(),
+        }
+    }
+
+    fn neighbors(&self, node: &u64) -> Nodes<'_, Self::Node> {
+        let ns = match self.find_node(*node) {
+            Some(i) => self
+                .outgoing(i)
+                .into_iter()
+                .chain(self.incoming(i))
+                .filter_map(|(_, j)| self.node(j))
+                .collect(),
+            None => Vec::new(),
+        };
+        Nodes {
+            range: ns.into_iter(),
+        }
+    }
+
+    fn edges(&self, node: &u64) -> Edges<'_, Self::Edge> {
+        let es = match self.find_node(*node) {
+            Some(i) => self
+                .adjacent(i, Direction::Both)
+                .into_iter()
+                .filter_map(|e| self.edge(e))
+                .collect(),
+            None => Vec::new(),
+        };
+        Edges {
+            range: es.into_iter(),
+        }
+    }
+
+    fn edges_directed(&self, node: &u64, dir: Direction) -> EdgeRefs<'_, u64, u64, EdgeData<f64>> {
+        let i = match self.find_node(*node) {
+            Some(i) => i,
+            None => return Vec::new(),
+        };
+        self.adjacent(i, dir)
+            .into_iter()
+            .filter_map(|e| self.edge(e))
+            .map(|e| EdgeRef {
+                from: e.source(),
+                to: e.target(),
+                id: e.id(),
+                data: e.data(),
+            })
+            .collect()
+    }
+}
+
+fn corrupt<T>(msg: &str) -> Result<T, CompactError> {
+    Err(CompactError::Corrupt(msg.to_owned()))
+}
+
+/// A hash of an edge and one of its nodes.
+fn fingerprint(edge: u64, node: u64) -> u64 {
+    let mut x = edge.wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ node;
+    x = (x ^ (x >> 31)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x ^ (x >> 29)
+}
+
+fn read_u64(data: &[u8], at: usize) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&data[at..at + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+fn read_u32(data: &[u8], at: usize) -> u32 {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&data[at..at + 4]);
+    u32::from_le_bytes(bytes)
+}
+
+/// Find `key` among `len` sorted keys.
+fn binary_search<F: Fn(usize) -> u64>(len: usize, key: u64, get: F) -> Option<usize> {
+    let (mut lo, mut hi) = (0, len);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        match get(mid).cmp(&key) {
+            std::cmp::Ordering::Less => lo = mid + 1,
+            std::cmp::Ordering::Greater => hi = mid,
+            std::cmp::Ordering::Equal => return Some(mid),
+        }
+    }
+    None
+}
+
+fn node_type_code(node_type: &NodeType) -> (u32, u32) {
+    (u32::from(node_type.code()), node_type.total_contributions())
+}
+
+fn decode_node_type(kind: u32, contributions: u32) -> Option<NodeType> {
+    NodeType::try_from((u8::try_from(kind).ok()?, contributions)).ok()
+}
+
+/// The code of an edge type, unless it's a custom one.
+fn edge_type_code(edge_type: &EdgeType) -> Option<(u32, u32)> {
+    match edge_type {
+        EdgeType::Custom { .. } => None,
+        _ => Some((u32::from(edge_type.code()), edge_type.value())),
+    }
+}
+
+fn decode_edge_type(kind: u32, value: u32) -> Option<EdgeType> {
+    EdgeType::try_from((u8::try_from(kind).ok()?, value)).ok()
+}
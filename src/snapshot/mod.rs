@@ -0,0 +1,3 @@
+//! Read-only graph snapshots.
+
+pub mod compact;
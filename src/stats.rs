@@ -0,0 +1,163 @@
+//! Graph statistics, to monitor the health of the graph.
+
+use std::collections::{BTreeMap, HashSet};
+use std::hash::Hash;
+
+use super::types::{EdgeTypeTag, NodeType};
+use super::{Direction, Edge, Graph, GraphObject, Id, Node};
+
+/// Node type without its payload, used to count nodes by type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum NodeKind {
+    /// A user node.
+    User,
+    /// A project node.
+    Project,
+    /// An organization node.
+    Org,
+    /// A checkpoint node.
+    Checkpoint,
+}
+
+impl From<&NodeType> for NodeKind {
+    fn from(node_type: &NodeType) -> Self {
+        match node_type {
+            NodeType::User { .. } => NodeKind::User,
+            NodeType::Project { .. } => NodeKind::Project,
+            NodeType::Org { .. } => NodeKind::Org,
+            NodeType::Checkpoint { .. } => NodeKind::Checkpoint,
+        }
+    }
+}
+
+/// A summary of the shape of a graph.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphSummary {
+    /// Number of nodes.
+    pub nodes: usize,
+    /// Number of edges.
+    pub edges: usize,
+    /// Number of nodes, by type.
+    pub nodes_by_type: BTreeMap<NodeKind, usize>,
+    /// Number of edges, by type.
+    pub edges_by_type: BTreeMap<EdgeTypeTag, usize>,
+    /// Ratio of the number of edges to the maximum possible number of edges
+    /// between distinct nodes, `n * (n - 1)`.
+    pub density: f64,
+    /// Average in-degree, which is also the average out-degree.
+    pub avg_degree: f64,
+    /// Largest in-degree.
+    pub max_in_degree: usize,
+    /// Largest out-degree.
+    pub max_out_degree: usize,
+    /// Number of nodes by total degree (in + out).
+    pub degree_histogram: BTreeMap<usize, usize>,
+    /// Average local clustering coefficient, ignoring edge directions, over at
+    /// most `CLUSTERING_SAMPLE` nodes.
+    pub clustering: f64,
+}
+
+/// The maximum number of nodes considered for the clustering coefficient.
+/// Nodes are taken in iteration order, so the estimate is deterministic for a
+/// given graph implementation.
+pub const CLUSTERING_SAMPLE: usize = 1000;
+
+/// Compute a summary of the graph.
+pub fn summarize<G>(graph: &G) -> GraphSummary
+where
+    G: Graph,
+    Id<G::Node>: Eq + Hash,
+{
+    let mut summary = GraphSummary {
+        nodes: 0,
+        edges: 0,
+        nodes_by_type: BTreeMap::new(),
+        edges_by_type: BTreeMap::new(),
+        density: 0.0,
+        avg_degree: 0.0,
+        max_in_degree: 0,
+        max_out_degree: 0,
+        degree_histogram: BTreeMap::new(),
+        clustering: 0.0,
+    };
+    let mut clustering = Vec::new();
+
+    for node in graph.nodes() {
+        let outgoing = graph.edges_directed(node.id(), Direction::Outgoing);
+        let incoming = graph.edges_directed(node.id(), Direction::Incoming);
+
+        summary.nodes += 1;
+        summary.edges += outgoing.len();
+        *summary
+            .nodes_by_type
+            .entry(node.node_type().into())
+            .or_insert(0) += 1;
+        for edge in outgoing.iter().filter_map(|e| graph.get_edge(e.id)) {
+            *summary
+                .edges_by_type
+                .entry(edge.edge_type().to_tag())
+                .or_insert(0) += 1;
+        }
+        summary.max_in_degree = summary.max_in_degree.max(incoming.len());
+        summary.max_out_degree = summary.max_out_degree.max(outgoing.len());
+        *summary
+            .degree_histogram
+            .entry(incoming.len() + outgoing.len())
+            .or_insert(0) += 1;
+
+        if clustering.len() < CLUSTERING_SAMPLE {
+            clustering.push(local_clustering(graph, node.id()));
+        }
+    }
+
+    if summary.nodes > 0 {
+        let n = summary.nodes as f64;
+
+        summary.avg_degree = summary.edges as f64 / n;
+        summary.clustering = clustering.iter().sum::<f64>() / clustering.len() as f64;
+        if summary.nodes > 1 {
+            summary.density = summary.edges as f64 / (n * (n - 1.0));
+        }
+    }
+    summary
+}
+
+/// The fraction of pairs of distinct neighbors of `node` which are linked
+/// together, ignoring edge directions and parallel edges.
+fn local_clustering<G>(graph: &G, node: &Id<G::Node>) -> f64
+where
+    G: Graph,
+    Id<G::Node>: Eq + Hash,
+{
+    let neighbors: HashSet<&Id<G::Node>> = graph
+        .edges(node)
+        .map(|e| {
+            if e.source() == node {
+                e.target()
+            } else {
+                e.source()
+            }
+        })
+        .filter(|n| *n != node)
+        .collect();
+
+    let k = neighbors.len();
+    if k < 2 {
+        return 0.0;
+    }
+
+    // Unordered pairs of linked neighbors.
+    let mut links = HashSet::new();
+    for n in neighbors.iter() {
+        for e in graph.edges(n) {
+            let (s, t) = (e.source(), e.target());
+
+            if s != t && neighbors.contains(s) && neighbors.contains(t) && !links.contains(&(t, s))
+            {
+                links.insert((s, t));
+            }
+        }
+    }
+
+    links.len() as f64 / (k * (k - 1) / 2) as f64
+}
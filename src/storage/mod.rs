@@ -0,0 +1,26 @@
+//! Graphs persisted to disk.
+
+#[cfg(feature = "sled-storage")]
+pub mod sled;
+
+#[cfg(feature = "sled-storage")]
+pub use self::sled::SledGraph;
+
+use super::Graph;
+
+/// A graph backed by durable storage.
+pub trait PersistentGraph: Graph + Sized {
+    /// How to open the storage, eg. a path.
+    type Config;
+    /// Storage failures.
+    type Error: std::error::Error;
+
+    /// Open the graph, creating the storage if it doesn't exist.
+    fn open(config: Self::Config) -> Result<Self, Self::Error>;
+
+    /// Write all pending changes to disk.
+    fn flush(&mut self) -> Result<(), Self::Error>;
+
+    /// Flush and close the graph.
+    fn close(self) -> Result<(), Self::Error>;
+}
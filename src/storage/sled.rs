@@ -0,0 +1,650 @@
+//! A `PersistentGraph` stored in a sled database.
+//!
+//! Nodes and edges are stored in two trees, with ids and data encoded as JSON
+//! through serde, and the edges of every node are indexed by two more trees,
+//! of outgoing and incoming edges. Nothing is loaded when the graph is
+//! opened: nodes and edges are read when they're first accessed, then kept
+//! in a cache, as `Graph` hands out references to them.
+//!
+//! The cache is trimmed down to `SledConfig::object_cache_capacity` objects
+//! whenever the graph is borrowed mutably, as no reference to a cached object
+//! can be alive then. It grows past this capacity while the graph is borrowed
+//! immutably, eg. by `Graph::nodes` or during the execution of an algorithm.
+//! Every mutation through `GraphWriter` is written through to the database,
+//! while data modified through `GraphDataWriter` is written on the next
+//! `flush`, and kept in the cache until then.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::PersistentGraph;
+use crate::ledger::LedgerGraph;
+use crate::mem::{EdgeId, MemEdge, MemNode};
+use crate::types::{EdgeData, EdgeTypeTag, NodeData};
+use crate::{
+    Direction, Edge, EdgeRef, EdgeRefs, Edges, EdgesMut, Graph, GraphDataWriter, GraphObject,
+    GraphWriter, Nodes, NodesMut,
+};
+
+/// The default number of nodes and edges kept in the cache of a `SledGraph`.
+pub const DEFAULT_OBJECT_CACHE_CAPACITY: usize = 16_384;
+
+/// Where and how to open a `SledGraph`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SledConfig {
+    /// The database directory.
+    pub path: PathBuf,
+    /// The size of the sled page cache, in bytes.
+    pub cache_capacity: Option<u64>,
+    /// The number of decoded nodes and edges kept in memory, see `SledGraph`.
+    pub object_cache_capacity: usize,
+    /// Whether to delete the database when it's closed.
+    pub temporary: bool,
+}
+
+impl SledConfig {
+    /// Open the database at the given path.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        SledConfig {
+            path: path.into(),
+            cache_capacity: None,
+            object_cache_capacity: DEFAULT_OBJECT_CACHE_CAPACITY,
+            temporary: false,
+        }
+    }
+}
+
+/// A failure of a `SledGraph`.
+#[derive(Debug)]
+pub enum SledError {
+    /// The database failed.
+    Sled(::sled::Error),
+    /// A record couldn't be encoded or decoded.
+    Encoding(serde_json::Error),
+}
+
+impl fmt::Display for SledError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SledError::Sled(err) => write!(f, "storage error: {}", err),
+            SledError::Encoding(err) => write!(f, "encoding error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SledError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SledError::Sled(err) => Some(err),
+            SledError::Encoding(err) => Some(err),
+        }
+    }
+}
+
+impl From<::sled::Error> for SledError {
+    fn from(err: ::sled::Error) -> Self {
+        SledError::Sled(err)
+    }
+}
+
+impl From<serde_json::Error> for SledError {
+    fn from(err: serde_json::Error) -> Self {
+        SledError::Encoding(err)
+    }
+}
+
+/// A graph stored in a sled database.
+///
+/// `GraphWriter` methods can't fail, and neither can `Graph` methods, so
+/// storage failures are kept, and returned by the next `flush`: a node or
+/// edge that can't be read is missing. Changes not yet flushed are lost if
+/// the graph is dropped without being closed.
+pub struct SledGraph<NodeId = u64, W = f64> {
+    db: ::sled::Db,
+    nodes: ::sled::Tree,
+    edges: ::sled::Tree,
+    outgoing: ::sled::Tree,
+    incoming: ::sled::Tree,
+    cache: Mutex<Cache<NodeId, W>>,
+    capacity: usize,
+    dirty_nodes: BTreeSet<NodeId>,
+    dirty_edges: BTreeSet<EdgeId>,
+}
+
+/// The nodes and edges read so far. They're boxed so that references to
+/// them stay valid when the maps are modified.
+struct Cache<NodeId, W> {
+    nodes: BTreeMap<NodeId, Box<MemNode<NodeId, W>>>,
+    edges: BTreeMap<EdgeId, Box<MemEdge<NodeId, W>>>,
+    error: Option<SledError>,
+}
+
+/// The record of an edge: its source, target and data.
+type EdgeRecord<NodeId, W> = (NodeId, NodeId, EdgeData<W>);
+
+/// The prefix of the keys of a node's edges in the adjacency trees. JSON
+/// never contains a nul byte, so the prefixes of different nodes are
+/// distinct.
+fn adjacency_prefix(node: &[u8]) -> Vec<u8> {
+    let mut key = Vec::with_capacity(node.len() + 9);
+    key.extend_from_slice(node);
+    key.push(0);
+    key
+}
+
+fn adjacency_key(node: &[u8], edge: EdgeId) -> Vec<u8> {
+    let mut key = adjacency_prefix(node);
+    key.extend_from_slice(&edge.to_be_bytes());
+    key
+}
+
+fn edge_id(key: &[u8]) -> EdgeId {
+    let mut id = [0; 8];
+    id.copy_from_slice(&key[key.len() - 8..]);
+    EdgeId::from_be_bytes(id)
+}
+
+impl<NodeId, W> SledGraph<NodeId, W>
+where
+    NodeId: Ord + Clone + Serialize + DeserializeOwned,
+    W: Clone + Serialize + DeserializeOwned,
+{
+    /// The number of nodes and edges in the cache.
+    pub fn cached_objects(&self) -> usize {
+        let cache = self.lock();
+        cache.nodes.len() + cache.edges.len()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Cache<NodeId, W>> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn cache_mut(&mut self) -> &mut Cache<NodeId, W> {
+        self.cache.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Get a cached node, or read it with `read` and cache it.
+    fn node_or<F>(&self, id: &NodeId, read: F) -> Option<&MemNode<NodeId, W>>
+    where
+        F: FnOnce() -> Result<Option<NodeData<W>>, SledError>,
+    {
+        let mut cache = self.lock();
+        let node: *const MemNode<NodeId, W> = match cache.nodes.get(id) {
+            Some(node) => &**node,
+            None => match read() {
+                Ok(Some(data)) => &**cache
+                    .nodes
+                    .entry(id.clone())
+                    .or_insert_with(|| Box::new(MemNode::new(id.clone(), data))),
+                Ok(None) => return None,
+                Err(err) => {
+                    cache.error.get_or_insert(err);
+                    return None;
+                }
+            },
+        };
+        // The node is boxed, and only dropped or borrowed mutably through
+        // `&mut self`, so it outlives the borrow of `self`.
+        Some(unsafe { &*node })
+    }
+
+    /// Get a cached edge, or read it with `read` and cache it.
+    fn edge_or<F>(&self, id: EdgeId, read: F) -> Option<&MemEdge<NodeId, W>>
+    where
+        F: FnOnce() -> Result<Option<EdgeRecord<NodeId, W>>, SledError>,
+    {
+        let mut cache = self.lock();
+        let edge: *const MemEdge<NodeId, W> = match cache.edges.get(&id) {
+            Some(edge) => &**edge,
+            None => match read() {
+                Ok(Some((from, to, data))) => &**cache
+                    .edges
+                    .entry(id)
+                    .or_insert_with(|| Box::new(MemEdge::new(id, from, to, data))),
+                Ok(None) => return None,
+                Err(err) => {
+                    cache.error.get_or_insert(err);
+                    return None;
+                }
+            },
+        };
+        // See `node_or`.
+        Some(unsafe { &*edge })
+    }
+
+    fn read_node(&self, id: &NodeId) -> Result<Option<NodeData<W>>, SledError> {
+        match self.nodes.get(serde_json::to_vec(id)?)? {
+            Some(value) => Ok(Some(serde_json::from_slice(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Whether both nodes exist.
+    fn contains_nodes(&self, from: &NodeId, to: &NodeId) -> Result<bool, SledError> {
+        Ok(self.nodes.contains_key(serde_json::to_vec(from)?)?
+            && self.nodes.contains_key(serde_json::to_vec(to)?)?)
+    }
+
+    fn read_edge(&self, id: EdgeId) -> Result<Option<EdgeRecord<NodeId, W>>, SledError> {
+        match self.edges.get(id.to_be_bytes())? {
+            Some(value) => Ok(Some(serde_json::from_slice(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The ids of the edges of a node, by ascending id.
+    fn adjacent(&self, node: &NodeId, dir: Direction) -> Result<BTreeSet<EdgeId>, SledError> {
+        let prefix = adjacency_prefix(&serde_json::to_vec(node)?);
+        let trees = match dir {
+            Direction::Outgoing => vec![&self.outgoing],
+            Direction::Incoming => vec![&self.incoming],
+            Direction::Both => vec![&self.outgoing, &self.incoming],
+        };
+        let mut ids = BTreeSet::new();
+        for tree in trees {
+            for key in tree.scan_prefix(&prefix).keys() {
+                ids.insert(edge_id(&key?));
+            }
+        }
+        Ok(ids)
+    }
+
+    /// The edges of a node, by ascending id.
+    fn adjacent_edges(&self, node: &NodeId, dir: Direction) -> Vec<&MemEdge<NodeId, W>> {
+        let ids = match self.adjacent(node, dir) {
+            Ok(ids) => ids,
+            Err(err) => {
+                self.record_read(err);
+                return Vec::new();
+            }
+        };
+        ids.into_iter()
+            .filter_map(|id| self.edge_or(id, || self.read_edge(id)))
+            .collect()
+    }
+
+    /// Write the data of a node modified in the cache.
+    fn put_node(&self, id: &NodeId) -> Result<(), SledError> {
+        if let Some(node) = self.lock().nodes.get(id) {
+            self.nodes
+                .insert(serde_json::to_vec(id)?, serde_json::to_vec(node.data())?)?;
+        }
+        Ok(())
+    }
+
+    /// Write the data of an edge modified in the cache.
+    fn put_edge(&self, id: EdgeId) -> Result<(), SledError> {
+        if let Some(e) = self.lock().edges.get(&id) {
+            let value = serde_json::to_vec(&(e.source(), e.target(), e.data()))?;
+            self.edges.insert(id.to_be_bytes(), value)?;
+        }
+        Ok(())
+    }
+
+    fn insert_edge(
+        &self,
+        id: EdgeId,
+        from: &NodeId,
+        to: &NodeId,
+        data: &EdgeData<W>,
+    ) -> Result<(), SledError> {
+        let value = serde_json::to_vec(&(from, to, data))?;
+        self.edges.insert(id.to_be_bytes(), value)?;
+        self.outgoing
+            .insert(adjacency_key(&serde_json::to_vec(from)?, id), &[])?;
+        self.incoming
+            .insert(adjacency_key(&serde_json::to_vec(to)?, id), &[])?;
+        Ok(())
+    }
+
+    fn delete_edge(&mut self, id: EdgeId) -> Result<(), SledError> {
+        self.cache_mut().edges.remove(&id);
+        self.dirty_edges.remove(&id);
+
+        if let Some(value) = self.edges.remove(id.to_be_bytes())? {
+            let (from, to, _): EdgeRecord<NodeId, W> = serde_json::from_slice(&value)?;
+            self.outgoing
+                .remove(adjacency_key(&serde_json::to_vec(&from)?, id))?;
+            self.incoming
+                .remove(adjacency_key(&serde_json::to_vec(&to)?, id))?;
+        }
+        Ok(())
+    }
+
+    fn delete_node(&mut self, id: NodeId) -> Result<(), SledError> {
+        if self.nodes.remove(serde_json::to_vec(&id)?)?.is_none() {
+            return Ok(());
+        }
+        self.cache_mut().nodes.remove(&id);
+        self.dirty_nodes.remove(&id);
+
+        for e in self.adjacent(&id, Direction::Both)? {
+            self.delete_edge(e)?;
+        }
+        Ok(())
+    }
+
+    /// Read every node into the cache, and list them by ascending id.
+    fn load_nodes(&self) -> Vec<&MemNode<NodeId, W>> {
+        let mut nodes = Vec::new();
+        for entry in self.nodes.iter() {
+            let node = entry.map_err(SledError::from).and_then(|(key, value)| {
+                let id: NodeId = serde_json::from_slice(&key)?;
+                Ok(self.node_or(&id, || Ok(Some(serde_json::from_slice(&value)?))))
+            });
+            match node {
+                Ok(node) => nodes.extend(node),
+                Err(err) => self.record_read(err),
+            }
+        }
+        // Keys are ordered as encoded, not as ids.
+        nodes.sort_by(|a, b| a.id().cmp(b.id()));
+        nodes
+    }
+
+    /// Read every edge into the cache.
+    fn load_edges(&self) {
+        for entry in self.edges.iter() {
+            match entry {
+                Ok((key, value)) => {
+                    self.edge_or(edge_id(&key), || Ok(Some(serde_json::from_slice(&value)?)));
+                }
+                Err(err) => self.record_read(err.into()),
+            }
+        }
+    }
+
+    /// Keep the first failure of a read, to be returned by `flush`.
+    fn record_read(&self, err: SledError) {
+        self.lock().error.get_or_insert(err);
+    }
+
+    /// Keep the first failure, to be returned by `flush`.
+    fn record(&mut self, result: Result<(), SledError>) {
+        if let Err(err) = result {
+            self.cache_mut().error.get_or_insert(err);
+        }
+    }
+
+    /// Evict cached objects until at most `capacity` are left, or only
+    /// modified ones, that are yet to be written.
+    fn trim(&mut self) {
+        let capacity = self.capacity;
+        let cache = self.cache.get_mut().unwrap_or_else(PoisonError::into_inner);
+        let mut excess = (cache.nodes.len() + cache.edges.len()).saturating_sub(capacity);
+        if excess == 0 {
+            return;
+        }
+        let (dirty_nodes, dirty_edges) = (&self.dirty_nodes, &self.dirty_edges);
+
+        cache.edges.retain(|id, _| {
+            if excess == 0 || dirty_edges.contains(id) {
+                return true;
+            }
+            excess -= 1;
+            false
+        });
+        cache.nodes.retain(|id, _| {
+            if excess == 0 || dirty_nodes.contains(id) {
+                return true;
+            }
+            excess -= 1;
+            false
+        });
+    }
+}
+
+impl<NodeId, W> PersistentGraph for SledGraph<NodeId, W>
+where
+    NodeId: Ord + Clone + Serialize + DeserializeOwned,
+    W: Clone + Serialize + DeserializeOwned,
+{
+    type Config = SledConfig;
+    type Error = SledError;
+
+    fn open(config: SledConfig) -> Result<Self, SledError> {
+        let mut sled_config = ::sled::Config::new()
+            .path(&config.path)
+            .temporary(config.temporary);
+        if let Some(capacity) = config.cache_capacity {
+            sled_config = sled_config.cache_capacity(capacity);
+        }
+        let db = sled_config.open()?;
+
+        Ok(SledGraph {
+            nodes: db.open_tree("nodes")?,
+            edges: db.open_tree("edges")?,
+            outgoing: db.open_tree("outgoing")?,
+            incoming: db.open_tree("incoming")?,
+            db,
+            cache: Mutex::new(Cache {
+                nodes: BTreeMap::new(),
+                edges: BTreeMap::new(),
+                error: None,
+            }),
+            capacity: config.object_cache_capacity,
+            dirty_nodes: BTreeSet::new(),
+            dirty_edges: BTreeSet::new(),
+        })
+    }
+
+    fn flush(&mut self) -> Result<(), SledError> {
+        if let Some(err) = self.cache_mut().error.take() {
+            return Err(err);
+        }
+        for id in std::mem::take(&mut self.dirty_nodes) {
+            self.put_node(&id)?;
+        }
+        for id in std::mem::take(&mut self.dirty_edges) {
+            self.put_edge(id)?;
+        }
+        self.db.flush()?;
+        self.trim();
+        Ok(())
+    }
+
+    fn close(mut self) -> Result<(), SledError> {
+        self.flush()
+    }
+}
+
+impl<NodeId, W> Graph for SledGraph<NodeId, W>
+where
+    NodeId: Ord + Clone + Serialize + DeserializeOwned,
+    W: Clone + Serialize + DeserializeOwned,
+{
+    type Node = MemNode<NodeId, W>;
+    type Edge = MemEdge<NodeId, W>;
+    type NodeData = NodeData<W>;
+    type EdgeData = EdgeData<W>;
+    type Weight = W;
+
+    fn get_node(&self, id: &NodeId) -> Option<&Self::Node> {
+        self.node_or(id, || self.read_node(id))
+    }
+
+    fn get_edge(&self, id: &EdgeId) -> Option<&Self::Edge> {
+        self.edge_or(*id, || self.read_edge(*id))
+    }
+
+    /// Reads every node into the cache.
+    fn nodes(&self) -> Nodes<'_, Self::Node> {
+        Nodes {
+            range: self.load_nodes().into_iter(),
+        }
+    }
+
+    fn neighbors(&self, node: &NodeId) -> Nodes<'_, Self::Node> {
+        let ns: Vec<&Self::Node> = self
+            .adjacent_edges(node, Direction::Outgoing)
+            .into_iter()
+            .filter_map(|e| self.get_node(e.target()))
+            .chain(
+                self.adjacent_edges(node, Direction::Incoming)
+                    .into_iter()
+                    .filter_map(|e| self.get_node(e.source())),
+            )
+            .collect();
+
+        Nodes {
+            range: ns.into_iter(),
+        }
+    }
+
+    fn edges(&self, node: &NodeId) -> Edges<'_, Self::Edge> {
+        Edges {
+            range: self.adjacent_edges(node, Direction::Both).into_iter(),
+        }
+    }
+
+    fn edges_directed(
+        &self,
+        node: &NodeId,
+        dir: Direction,
+    ) -> EdgeRefs<'_, NodeId, EdgeId, EdgeData<W>> {
+        self.adjacent_edges(node, dir)
+            .into_iter()
+            .map(|e| EdgeRef {
+                from: e.source(),
+                to: e.target(),
+                id: e.id(),
+                data: e.data(),
+            })
+            .collect()
+    }
+
+    fn degree_by_edge_type(&self, node: &NodeId, dir: Direction) -> BTreeMap<EdgeTypeTag, usize> {
+        let mut degrees = BTreeMap::new();
+        for e in self.adjacent_edges(node, dir) {
+            *degrees.entry(e.edge_type().to_tag()).or_insert(0) += 1;
+        }
+        degrees
+    }
+}
+
+impl<NodeId, W> GraphWriter for SledGraph<NodeId, W>
+where
+    NodeId: Ord + Clone + Serialize + DeserializeOwned,
+    W: Clone + Serialize + DeserializeOwned,
+{
+    /// Add a node, replacing the data of any existing node with the same id.
+    fn add_node(&mut self, id: NodeId, data: NodeData<W>) {
+        self.trim();
+        self.cache_mut().nodes.remove(&id);
+        self.dirty_nodes.remove(&id);
+
+        let result = serde_json::to_vec(&id)
+            .and_then(|key| Ok((key, serde_json::to_vec(&data)?)))
+            .map_err(SledError::from)
+            .and_then(|(key, value)| Ok(self.nodes.insert(key, value).map(|_| ())?));
+        self.record(result);
+    }
+
+    /// Remove a node along with all its edges.
+    fn remove_node(&mut self, id: NodeId) {
+        self.trim();
+        let result = self.delete_node(id);
+        self.record(result);
+    }
+
+    /// Link two nodes, replacing any existing edge with the same id. Edges to
+    /// missing nodes are ignored.
+    fn add_edge(&mut self, id: EdgeId, from: &NodeId, to: &NodeId, data: EdgeData<W>) {
+        self.trim();
+        let result = match self.contains_nodes(from, to) {
+            Ok(true) => self
+                .delete_edge(id)
+                .and_then(|_| self.insert_edge(id, from, to, &data)),
+            Ok(false) => Ok(()),
+            Err(err) => Err(err),
+        };
+        self.record(result);
+    }
+
+    fn remove_edge(&mut self, id: EdgeId) {
+        self.trim();
+        let result = self.delete_edge(id);
+        self.record(result);
+    }
+
+    /// Iterate over all nodes mutably. Every node is read into the cache,
+    /// and written on the next `flush`.
+    fn nodes_mut(&mut self) -> NodesMut<'_, Self::Node> {
+        self.load_nodes();
+        let cache = self.cache.get_mut().unwrap_or_else(PoisonError::into_inner);
+        self.dirty_nodes.extend(cache.nodes.keys().cloned());
+
+        NodesMut {
+            range: cache
+                .nodes
+                .values_mut()
+                .map(|n| &mut **n)
+                .collect::<Vec<_>>()
+                .into_iter(),
+        }
+    }
+
+    /// Clears the trees at once.
+    fn clear(&mut self) {
+        let cache = self.cache_mut();
+        cache.nodes.clear();
+        cache.edges.clear();
+        self.dirty_nodes.clear();
+        self.dirty_edges.clear();
+
+        let result = [&self.nodes, &self.edges, &self.outgoing, &self.incoming]
+            .iter()
+            .try_for_each(|tree| tree.clear())
+            .map_err(SledError::from);
+        self.record(result);
+    }
+
+    /// Iterate over all edges mutably. Every edge is read into the cache,
+    /// and written on the next `flush`.
+    fn edges_mut(&mut self) -> EdgesMut<'_, Self::Edge> {
+        self.load_edges();
+        let cache = self.cache.get_mut().unwrap_or_else(PoisonError::into_inner);
+        self.dirty_edges.extend(cache.edges.keys().copied());
+
+        EdgesMut {
+            range: cache
+                .edges
+                .values_mut()
+                .map(|e| &mut **e)
+                .collect::<Vec<_>>()
+                .into_iter(),
+        }
+    }
+}
+
+impl<NodeId, W> GraphDataWriter for SledGraph<NodeId, W>
+where
+    NodeId: Ord + Clone + Serialize + DeserializeOwned,
+    W: Clone + Serialize + DeserializeOwned,
+{
+    fn edge_data_mut(&mut self, id: &EdgeId) -> Option<&mut EdgeData<W>> {
+        self.trim();
+        self.get_edge(id)?;
+        self.dirty_edges.insert(*id);
+        self.cache_mut().edges.get_mut(id).map(|e| e.data_mut())
+    }
+
+    fn node_data_mut(&mut self, id: &NodeId) -> Option<&mut NodeData<W>> {
+        self.trim();
+        self.get_node(id)?;
+        self.dirty_nodes.insert(id.clone());
+        self.cache_mut().nodes.get_mut(id).map(|n| n.data_mut())
+    }
+}
+
+impl<NodeId, W> LedgerGraph for SledGraph<NodeId, W>
+where
+    NodeId: Ord + Clone + Serialize + DeserializeOwned,
+    W: Clone + Serialize + DeserializeOwned,
+{
+}
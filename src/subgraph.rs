@@ -0,0 +1,188 @@
+//! Views restricting a graph to some of its nodes and edges.
+
+use std::collections::{HashSet, VecDeque};
+use std::hash::Hash;
+
+use super::{Direction, EdgeRefsOf, Edges, Graph, GraphObject, Id, Nodes};
+
+/// A read-only view of some nodes of a graph, and of some of the edges
+/// between them.
+pub struct SubgraphView<'a, G: Graph> {
+    graph: &'a G,
+    nodes: HashSet<&'a Id<G::Node>>,
+    edges: HashSet<&'a Id<G::Edge>>,
+}
+
+impl<'a, G> SubgraphView<'a, G>
+where
+    G: Graph,
+    Id<G::Node>: Eq + Hash,
+    Id<G::Edge>: Eq + Hash,
+{
+    /// The subgraph induced by the given nodes: all the edges between them
+    /// for which `edge_filter` returns `true` are included. Ids of nodes not
+    /// in `graph` are ignored.
+    pub fn induced<I, F>(graph: &'a G, nodes: I, mut edge_filter: F) -> Self
+    where
+        I: IntoIterator<Item = &'a Id<G::Node>>,
+        F: FnMut(&G::Edge) -> bool,
+    {
+        let nodes: HashSet<&'a Id<G::Node>> = nodes
+            .into_iter()
+            .filter_map(|id| graph.get_node(id).map(|n| n.id()))
+            .collect();
+        let edges = nodes
+            .iter()
+            .flat_map(|id| graph.edges_directed(id, Direction::Outgoing))
+            .filter(|e| nodes.contains(e.to))
+            .filter_map(|e| graph.get_edge(e.id))
+            .filter(|e| edge_filter(e))
+            .map(|e| e.id())
+            .collect();
+
+        SubgraphView {
+            graph,
+            nodes,
+            edges,
+        }
+    }
+
+    /// The underlying graph.
+    pub fn graph(&self) -> &'a G {
+        self.graph
+    }
+
+    /// The number of nodes in the view.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// The number of edges in the view.
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Whether the view contains the given node.
+    pub fn contains_node(&self, id: &Id<G::Node>) -> bool {
+        self.nodes.contains(id)
+    }
+
+    /// Whether the view contains the given edge.
+    pub fn contains_edge(&self, id: &Id<G::Edge>) -> bool {
+        self.edges.contains(id)
+    }
+}
+
+impl<'a, G> Graph for SubgraphView<'a, G>
+where
+    G: Graph,
+    Id<G::Node>: Eq + Hash,
+    Id<G::Edge>: Eq + Hash,
+{
+    type Node = G::Node;
+    type Edge = G::Edge;
+    type NodeData = G::NodeData;
+    type EdgeData = G::EdgeData;
+    type Weight = G::Weight;
+
+    fn get_node(&self, id: &Id<G::Node>) -> Option<&G::Node> {
+        self.nodes.get(id).and_then(|id| self.graph.get_node(id))
+    }
+
+    fn get_edge(&self, id: &Id<G::Edge>) -> Option<&G::Edge> {
+        self.edges.get(id).and_then(|id| self.graph.get_edge(id))
+    }
+
+    fn nodes(&self) -> Nodes<'_, G::Node> {
+        let nodes: Vec<&G::Node> = self
+            .graph
+            .nodes()
+            .filter(|n| self.nodes.contains(n.id()))
+            .collect();
+
+        Nodes {
+            range: nodes.into_iter(),
+        }
+    }
+
+    fn neighbors(&self, node: &Id<G::Node>) -> Nodes<'_, G::Node> {
+        let outgoing = self.edges_directed(node, Direction::Outgoing);
+        let incoming = self.edges_directed(node, Direction::Incoming);
+        let nodes: Vec<&G::Node> = outgoing
+            .into_iter()
+            .map(|e| e.to)
+            .chain(incoming.into_iter().map(|e| e.from))
+            .filter_map(|id| self.graph.get_node(id))
+            .collect();
+
+        Nodes {
+            range: nodes.into_iter(),
+        }
+    }
+
+    fn edges(&self, node: &Id<G::Node>) -> Edges<'_, G::Edge> {
+        let edges: Vec<&G::Edge> = if self.nodes.contains(node) {
+            self.graph
+                .edges(node)
+                .filter(|e| self.edges.contains(e.id()))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Edges {
+            range: edges.into_iter(),
+        }
+    }
+
+    fn edges_directed(&self, node: &Id<G::Node>, dir: Direction) -> EdgeRefsOf<'_, G> {
+        if !self.nodes.contains(node) {
+            return Vec::new();
+        }
+        self.graph
+            .edges_directed(node, dir)
+            .into_iter()
+            .filter(|e| self.edges.contains(e.id))
+            .collect()
+    }
+}
+
+/// The k-hop ego network of `root`: the nodes reachable from `root` in at
+/// most `k` hops, following edges in the given direction for which
+/// `edge_filter` returns `true`, along with the edges between them that pass
+/// the filter. The view is empty if `root` isn't in the graph.
+pub fn neighborhood<'a, G, F>(
+    graph: &'a G,
+    root: &Id<G::Node>,
+    k: usize,
+    direction: Direction,
+    mut edge_filter: F,
+) -> SubgraphView<'a, G>
+where
+    G: Graph,
+    F: FnMut(&G::Edge) -> bool,
+    Id<G::Node>: Eq + Hash,
+    Id<G::Edge>: Eq + Hash,
+{
+    let mut seen: HashSet<&'a Id<G::Node>> = HashSet::new();
+    let mut queue: VecDeque<(&'a Id<G::Node>, usize)> = VecDeque::new();
+
+    if let Some(node) = graph.get_node(root) {
+        seen.insert(node.id());
+        queue.push_back((node.id(), 0));
+    }
+    while let Some((id, depth)) = queue.pop_front() {
+        if depth == k {
+            continue;
+        }
+        for e in graph.edges_directed(id, direction) {
+            let next = if e.from == id { e.to } else { e.from };
+            if seen.contains(next) || !graph.get_edge(e.id).is_some_and(&mut edge_filter) {
+                continue;
+            }
+            seen.insert(next);
+            queue.push_back((next, depth + 1));
+        }
+    }
+    SubgraphView::induced(graph, seen, edge_filter)
+}
@@ -0,0 +1,4 @@
+//! Utilities to test graph implementations against the contracts of the
+//! traits.
+
+pub mod model;
@@ -0,0 +1,440 @@
+//! Model-based differential testing of `GraphWriter` implementations.
+//!
+//! A sequence of mutations is applied both to the implementation under test
+//! and to `Model`, a reference implementation too simple to be wrong. After
+//! every step, the queries observable through `Graph` must agree: the nodes
+//! and their data, the edges and their endpoints, and the edges of every
+//! node in each direction.
+//!
+//! Read-only graphs built from a writable one, eg. snapshots, are checked
+//! the same way with `check_snapshots`.
+//!
+//! Sequences are generated from a seed by `random_ops`, so that a property
+//! testing library can drive the check and shrink failures to their seed:
+//!
+//! ```ignore
+//! quickcheck::quickcheck(|seed: u64| {
+//!     check(&mut EdgeListGraph::new(), &random_ops(seed, 64)).is_ok()
+//! });
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::epochs::EdgeHistory;
+use crate::rng::{AlgorithmSeed, SeedableAlgorithmRng};
+use crate::types::{
+    ContributionLedger, EdgeData, EdgeType, NodeData, NodeRank, NodeType, Timestamps,
+};
+use crate::{Data, Direction, Edge, Graph, GraphObject, GraphWriter, HasWeight, Id};
+
+/// A mutation of a graph.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Op<NodeId, EdgeId, N, E> {
+    /// Add a node, or replace the data of an existing one.
+    AddNode(NodeId, N),
+    /// Remove a node, along with its edges.
+    RemoveNode(NodeId),
+    /// Add an edge, replacing any edge with the same id. Skipped if either
+    /// endpoint is missing.
+    AddEdge {
+        id: EdgeId,
+        from: NodeId,
+        to: NodeId,
+        data: E,
+    },
+    /// Remove an edge.
+    RemoveEdge(EdgeId),
+    /// Insert or update an edge, with `GraphWriter::upsert_edge`. Skipped if
+    /// either endpoint is missing.
+    UpsertEdge {
+        id: EdgeId,
+        from: NodeId,
+        to: NodeId,
+        data: E,
+    },
+    /// Remove every node and edge.
+    Clear,
+}
+
+/// The operations generated by `random_ops`, on `EdgeListGraph`-like graphs.
+pub type GraphOp = Op<u64, u64, NodeData<f64>, EdgeData<f64>>;
+
+/// The reference implementation: nodes and edges in maps, by id.
+#[derive(Debug, Clone)]
+pub struct Model<NodeId, EdgeId, N, E> {
+    nodes: BTreeMap<NodeId, N>,
+    edges: BTreeMap<EdgeId, (NodeId, NodeId, E)>,
+}
+
+impl<NodeId: Ord, EdgeId: Ord, N, E> Default for Model<NodeId, EdgeId, N, E> {
+    fn default() -> Self {
+        Model {
+            nodes: BTreeMap::new(),
+            edges: BTreeMap::new(),
+        }
+    }
+}
+
+impl<NodeId, EdgeId, N, E> Model<NodeId, EdgeId, N, E>
+where
+    NodeId: Ord + Clone,
+    EdgeId: Ord + Clone,
+    N: Clone,
+    E: Clone,
+{
+    /// An empty model.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The data of the nodes, by id.
+    pub fn nodes(&self) -> &BTreeMap<NodeId, N> {
+        &self.nodes
+    }
+
+    /// The endpoints and data of the edges, by id.
+    pub fn edges(&self) -> &BTreeMap<EdgeId, (NodeId, NodeId, E)> {
+        &self.edges
+    }
+
+    /// The ids of the edges of `node` in the given direction, sorted.
+    pub fn edges_directed(&self, node: &NodeId, dir: Direction) -> Vec<EdgeId> {
+        self.edges
+            .iter()
+            .filter(|(_, (from, to, _))| match dir {
+                Direction::Outgoing => from == node,
+                Direction::Incoming => to == node,
+                Direction::Both => from == node || to == node,
+            })
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    /// Whether the operation applies, ie. isn't skipped.
+    pub fn applies(&self, op: &Op<NodeId, EdgeId, N, E>) -> bool {
+        match op {
+            Op::AddEdge { from, to, .. } | Op::UpsertEdge { from, to, .. } => {
+                self.nodes.contains_key(from) && self.nodes.contains_key(to)
+            }
+            _ => true,
+        }
+    }
+
+    /// Apply the operation, unless it is skipped.
+    pub fn apply(&mut self, op: &Op<NodeId, EdgeId, N, E>) {
+        if !self.applies(op) {
+            return;
+        }
+        match op {
+            Op::AddNode(id, data) => {
+                self.nodes.insert(id.clone(), data.clone());
+            }
+            Op::RemoveNode(id) => {
+                if self.nodes.remove(id).is_some() {
+                    self.edges.retain(|_, (from, to, _)| from != id && to != id);
+                }
+            }
+            Op::AddEdge { id, from, to, data } | Op::UpsertEdge { id, from, to, data } => {
+                self.edges
+                    .insert(id.clone(), (from.clone(), to.clone(), data.clone()));
+            }
+            Op::RemoveEdge(id) => {
+                self.edges.remove(id);
+            }
+            Op::Clear => {
+                self.nodes.clear();
+                self.edges.clear();
+            }
+        }
+    }
+}
+
+/// A query whose answer differs between the graph and the model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query<NodeId, EdgeId> {
+    /// The number of nodes.
+    NodeCount,
+    /// The data of a node, or whether it exists.
+    NodeData(NodeId),
+    /// The number of edges.
+    EdgeCount,
+    /// The endpoints and data of an edge, or whether it exists.
+    Edge(EdgeId),
+    /// The edges of a node in a direction.
+    EdgesDirected(NodeId, Direction),
+}
+
+/// The graph diverged from the model after applying the operation at index
+/// `step`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch<NodeId, EdgeId> {
+    pub step: usize,
+    pub query: Query<NodeId, EdgeId>,
+}
+
+impl<NodeId: fmt::Debug, EdgeId: fmt::Debug> fmt::Display for Mismatch<NodeId, EdgeId> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "graph and model differ after step {}: ", self.step)?;
+        match &self.query {
+            Query::NodeCount => write!(f, "node count"),
+            Query::NodeData(id) => write!(f, "data of node {:?}", id),
+            Query::EdgeCount => write!(f, "edge count"),
+            Query::Edge(id) => write!(f, "edge {:?}", id),
+            Query::EdgesDirected(id, dir) => write!(f, "{:?} edges of node {:?}", dir, id),
+        }
+    }
+}
+
+impl<NodeId: fmt::Debug, EdgeId: fmt::Debug> std::error::Error for Mismatch<NodeId, EdgeId> {}
+
+/// A mismatch between a graph and the model.
+pub type GraphMismatch<G> = Mismatch<Id<<G as Graph>::Node>, Id<<G as Graph>::Edge>>;
+
+/// The operations of a graph.
+pub type GraphOps<G> = [Op<
+    Id<<G as Graph>::Node>,
+    Id<<G as Graph>::Edge>,
+    Data<<G as Graph>::Node>,
+    Data<<G as Graph>::Edge>,
+>];
+
+/// Apply `ops` to `graph`, which is expected to be empty, and to a `Model`,
+/// comparing their queries after every step. Returns the first mismatch.
+pub fn check<G>(graph: &mut G, ops: &GraphOps<G>) -> Result<(), GraphMismatch<G>>
+where
+    G: GraphWriter,
+    Id<G::Node>: Ord + Clone,
+    Id<G::Edge>: Ord + Clone,
+    G::NodeData: Clone + PartialEq,
+    G::EdgeData: Clone + PartialEq,
+{
+    drive(graph, ops, |graph, model, node_ids, edge_ids| {
+        compare(graph, model, node_ids, edge_ids)
+    })
+}
+
+/// Apply `ops` to `graph` as `check` does, but compare the model to the
+/// snapshot of `graph` taken by `snapshot` after every step. This checks
+/// graphs which can't be mutated, eg. `snapshot::compact::CompactGraph`.
+pub fn check_snapshots<G, S, F>(
+    graph: &mut G,
+    ops: &GraphOps<G>,
+    mut snapshot: F,
+) -> Result<(), GraphMismatch<G>>
+where
+    G: GraphWriter,
+    S: Graph<NodeData = G::NodeData, EdgeData = G::EdgeData, Weight = G::Weight>,
+    S::Node: GraphObject<Id = Id<G::Node>>,
+    S::Edge: GraphObject<Id = Id<G::Edge>>,
+    F: FnMut(&G) -> S,
+    Id<G::Node>: Ord + Clone,
+    Id<G::Edge>: Ord + Clone,
+    G::NodeData: Clone + PartialEq,
+    G::EdgeData: Clone + PartialEq,
+{
+    drive(graph, ops, |graph, model, node_ids, edge_ids| {
+        compare(&snapshot(graph), model, node_ids, edge_ids)
+    })
+}
+
+/// Apply `ops` to `graph` and to a `Model`, calling `compare` after every
+/// step with the nodes and edges ever mentioned.
+fn drive<G, F>(graph: &mut G, ops: &GraphOps<G>, mut compare: F) -> Result<(), GraphMismatch<G>>
+where
+    G: GraphWriter,
+    F: FnMut(
+        &G,
+        &GraphModel<G>,
+        &BTreeSet<Id<G::Node>>,
+        &BTreeSet<Id<G::Edge>>,
+    ) -> Result<(), GraphQuery<G>>,
+    Id<G::Node>: Ord + Clone,
+    Id<G::Edge>: Ord + Clone,
+    G::NodeData: Clone,
+    G::EdgeData: Clone,
+{
+    let mut model = Model::new();
+    // The nodes and edges ever mentioned, to check that removed ones are
+    // gone.
+    let mut node_ids = BTreeSet::new();
+    let mut edge_ids = BTreeSet::new();
+
+    for (step, op) in ops.iter().enumerate() {
+        // Skipped operations are applied too, as the graph must ignore them.
+        match op.clone() {
+            Op::AddNode(id, data) => graph.add_node(id, data),
+            Op::RemoveNode(id) => graph.remove_node(id),
+            Op::AddEdge { id, from, to, data } => graph.add_edge(id, &from, &to, data),
+            Op::RemoveEdge(id) => graph.remove_edge(id),
+            Op::UpsertEdge { id, from, to, data } => {
+                graph.upsert_edge(id, &from, &to, data);
+            }
+            Op::Clear => graph.clear(),
+        }
+        model.apply(op);
+
+        match op {
+            Op::AddNode(id, _) | Op::RemoveNode(id) => {
+                node_ids.insert(id.clone());
+            }
+            Op::AddEdge { id, .. } | Op::UpsertEdge { id, .. } | Op::RemoveEdge(id) => {
+                edge_ids.insert(id.clone());
+            }
+            Op::Clear => {}
+        }
+        compare(graph, &model, &node_ids, &edge_ids).map_err(|query| Mismatch { step, query })?;
+    }
+    Ok(())
+}
+
+type GraphModel<G> = Model<
+    Id<<G as Graph>::Node>,
+    Id<<G as Graph>::Edge>,
+    Data<<G as Graph>::Node>,
+    Data<<G as Graph>::Edge>,
+>;
+
+type GraphQuery<G> = Query<Id<<G as Graph>::Node>, Id<<G as Graph>::Edge>>;
+
+fn compare<G, NodeId, EdgeId, N, E>(
+    graph: &G,
+    model: &Model<NodeId, EdgeId, N, E>,
+    node_ids: &BTreeSet<NodeId>,
+    edge_ids: &BTreeSet<EdgeId>,
+) -> Result<(), Query<NodeId, EdgeId>>
+where
+    G: Graph<NodeData = N, EdgeData = E>,
+    G::Node: GraphObject<Id = NodeId>,
+    G::Edge: GraphObject<Id = EdgeId>,
+    NodeId: Ord + Clone,
+    EdgeId: Ord + Clone,
+    N: Clone + PartialEq,
+    E: Clone + PartialEq + HasWeight<G::Weight>,
+{
+    if graph.nodes().count() != model.nodes().len() {
+        return Err(Query::NodeCount);
+    }
+    for id in node_ids {
+        if graph.node_data(id) != model.nodes().get(id) {
+            return Err(Query::NodeData(id.clone()));
+        }
+    }
+
+    let mut edges = 0;
+    for id in node_ids {
+        for dir in [Direction::Outgoing, Direction::Incoming] {
+            let mut actual: Vec<EdgeId> = if graph.get_node(id).is_some() {
+                graph
+                    .edges_directed(id, dir)
+                    .into_iter()
+                    .map(|e| e.id.clone())
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            actual.sort();
+            if actual != model.edges_directed(id, dir) {
+                return Err(Query::EdgesDirected(id.clone(), dir));
+            }
+            if dir == Direction::Outgoing {
+                edges += actual.len();
+            }
+        }
+    }
+    if edges != model.edges().len() {
+        return Err(Query::EdgeCount);
+    }
+
+    for id in edge_ids {
+        let actual = graph
+            .get_edge(id)
+            .map(|e| (e.source(), e.target(), e.data()));
+        let expected = model
+            .edges()
+            .get(id)
+            .map(|(from, to, data)| (from, to, data));
+        if actual != expected {
+            return Err(Query::Edge(id.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// The number of node ids `random_ops` picks from, few enough for
+/// operations to collide.
+const NODE_IDS: u64 = 8;
+
+/// The number of edge ids `random_ops` picks from.
+const EDGE_IDS: u64 = 16;
+
+/// `len` random operations on a graph with `u64` ids, generated from `seed`.
+/// Ids are drawn from small ranges, so that operations replace and remove
+/// existing nodes and edges, and most edges have existing endpoints.
+pub fn random_ops(seed: u64, len: usize) -> Vec<GraphOp> {
+    let mut rng = seed.into_rng();
+    let mut below = |n: u64| rng.next_u64() % n;
+
+    (0..len)
+        .map(|_| {
+            let op = below(100);
+            let node = below(NODE_IDS);
+            let edge = below(EDGE_IDS);
+            let from = below(NODE_IDS);
+            let to = below(NODE_IDS);
+            let value = below(10);
+
+            match op {
+                0..=29 => Op::AddNode(node, node_data(value)),
+                30..=39 => Op::RemoveNode(node),
+                40..=69 => Op::AddEdge {
+                    id: edge,
+                    from,
+                    to,
+                    data: edge_data(value),
+                },
+                70..=79 => Op::RemoveEdge(edge),
+                80..=98 => Op::UpsertEdge {
+                    id: edge,
+                    from,
+                    to,
+                    data: edge_data(value),
+                },
+                _ => Op::Clear,
+            }
+        })
+        .collect()
+}
+
+fn node_data(value: u64) -> NodeData<f64> {
+    let contributions = value as u32;
+    NodeData {
+        node_type: if value.is_multiple_of(2) {
+            NodeType::Project {
+                contributions_from_all_users: contributions,
+            }
+        } else {
+            NodeType::User {
+                contributions_to_all_projects: contributions,
+            }
+        },
+        rank: NodeRank {
+            rank: value as f64 / 10.0,
+        },
+        contributions: ContributionLedger::new(),
+        timestamps: Timestamps::default(),
+    }
+}
+
+fn edge_data(value: u64) -> EdgeData<f64> {
+    EdgeData {
+        edge_type: if value.is_multiple_of(2) {
+            EdgeType::Dependency
+        } else {
+            EdgeType::UserToProjectContribution(value as u32)
+        },
+        weight: value as f64 / 10.0,
+        history: EdgeHistory::new(),
+        timestamps: Timestamps::default(),
+    }
+}
@@ -0,0 +1,46 @@
+//! Internal instrumentation macros, emitting `tracing` spans and events when
+//! the `tracing` feature is enabled, and compiling to nothing otherwise.
+//!
+//! Arguments are dropped when the feature is disabled, so they must not be
+//! computed solely for the sake of tracing.
+
+/// Enter a debug-level span until the end of the enclosing scope.
+#[cfg(feature = "tracing")]
+macro_rules! trace_span {
+    ($($arg:tt)*) => {
+        let _span = ::tracing::debug_span!($($arg)*).entered();
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! trace_span {
+    ($($arg:tt)*) => {};
+}
+
+/// Emit a debug-level event, eg. for a layer operation.
+#[cfg(feature = "tracing")]
+macro_rules! trace_event {
+    ($($arg:tt)*) => {
+        ::tracing::debug!($($arg)*)
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+#[allow(unused_macros)]
+macro_rules! trace_event {
+    ($($arg:tt)*) => {};
+}
+
+/// Emit a trace-level event, for the steps of an algorithm.
+#[cfg(feature = "tracing")]
+macro_rules! trace_step {
+    ($($arg:tt)*) => {
+        ::tracing::trace!($($arg)*)
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+#[allow(unused_macros)]
+macro_rules! trace_step {
+    ($($arg:tt)*) => {};
+}
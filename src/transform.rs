@@ -0,0 +1,183 @@
+//! Copying graphs while transforming their data, eg. to convert a graph of
+//! the registry layer into the representation used by the rank layer, and
+//! restructuring graphs in place.
+
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::hash::Hash;
+#[cfg(feature = "std")]
+use std::collections::HashSet;
+
+#[cfg(feature = "std")]
+use super::GraphBuilder;
+use super::{Data, Direction, Edge, Graph, GraphObject, GraphWriter, Id};
+
+/// Copy `source` into the graph built by `builder`, transforming the data of
+/// every node and edge. Ids and topology are preserved.
+#[cfg(feature = "std")]
+pub fn map_graph<S, T, B, NF, EF>(source: &S, builder: B, mut node_fn: NF, mut edge_fn: EF) -> T
+where
+    S: Graph,
+    T: GraphWriter,
+    T::Node: GraphObject<Id = Id<S::Node>>,
+    T::Edge: GraphObject<Id = Id<S::Edge>>,
+    B: GraphBuilder<Graph = T>,
+    NF: FnMut(&S::Node) -> Data<T::Node>,
+    EF: FnMut(&S::Edge) -> Data<T::Edge>,
+    Id<S::Node>: Clone + Eq + Hash,
+    Id<S::Edge>: Clone,
+{
+    filter_map_graph(source, builder, |n| Some(node_fn(n)), |e| Some(edge_fn(e)))
+}
+
+/// Like `map_graph`, but nodes and edges for which the transformation returns
+/// `None` are dropped. Edges connected to a dropped node are dropped as well.
+#[cfg(feature = "std")]
+pub fn filter_map_graph<S, T, B, NF, EF>(
+    source: &S,
+    builder: B,
+    mut node_fn: NF,
+    mut edge_fn: EF,
+) -> T
+where
+    S: Graph,
+    T: GraphWriter,
+    T::Node: GraphObject<Id = Id<S::Node>>,
+    T::Edge: GraphObject<Id = Id<S::Edge>>,
+    B: GraphBuilder<Graph = T>,
+    NF: FnMut(&S::Node) -> Option<Data<T::Node>>,
+    EF: FnMut(&S::Edge) -> Option<Data<T::Edge>>,
+    Id<S::Node>: Clone + Eq + Hash,
+    Id<S::Edge>: Clone,
+{
+    let mut target = builder.build();
+    let mut kept = HashSet::new();
+
+    for node in source.nodes() {
+        if let Some(data) = node_fn(node) {
+            target.add_node(node.id().clone(), data);
+            kept.insert(node.id());
+        }
+    }
+
+    for node in source.nodes() {
+        if !kept.contains(node.id()) {
+            continue;
+        }
+        for e in source.edges_directed(node.id(), Direction::Outgoing) {
+            if !kept.contains(e.to) {
+                continue;
+            }
+            let data = match source.get_edge(e.id).and_then(&mut edge_fn) {
+                Some(data) => data,
+                None => continue,
+            };
+            target.add_edge(e.id.clone(), e.from, e.to, data);
+        }
+    }
+    target
+}
+
+/// An edge taken out of a graph: its id, endpoints and data.
+type TakenEdge<G> = (
+    Id<<G as Graph>::Edge>,
+    Id<<G as Graph>::Node>,
+    Id<<G as Graph>::Node>,
+    Data<<G as Graph>::Edge>,
+);
+
+/// A node taken out of a graph: its data and edges.
+type TakenNode<G> = (Data<<G as Graph>::Node>, Vec<TakenEdge<G>>);
+
+/// Remove a node and its edges, returning their data.
+pub(crate) fn take_node<G>(graph: &mut G, id: &Id<G::Node>) -> Option<TakenNode<G>>
+where
+    G: GraphWriter,
+    Id<G::Node>: Clone + PartialEq,
+    Id<G::Edge>: Clone,
+    Data<G::Node>: Clone,
+    Data<G::Edge>: Clone,
+{
+    let data = graph.node_data(id)?.clone();
+
+    let ids: Vec<Id<G::Edge>> = graph
+        .edges_directed(id, Direction::Both)
+        .into_iter()
+        .map(|e| e.id.clone())
+        .collect();
+    let edges: Vec<TakenEdge<G>> = ids
+        .iter()
+        .filter_map(|e| graph.get_edge(e))
+        .map(|e| {
+            let (from, to) = (e.source().clone(), e.target().clone());
+            (e.id().clone(), from, to, e.data().clone())
+        })
+        .collect();
+
+    for (e, _, _, _) in &edges {
+        graph.remove_edge(e.clone());
+    }
+    graph.remove_node(id.clone());
+
+    Some((data, edges))
+}
+
+/// Merge the nodes `ids` into the node `into`, eg. when a user consolidates
+/// several identities. Returns the number of nodes merged.
+///
+/// The data of every merged node is combined into the data of `into` with
+/// `merge`, eg. to add up contribution counts, and their edges are rewired to
+/// `into`, keeping their ids. Edges between distinct contracted nodes are
+/// dropped, since they would become self-loops. If `into` doesn't exist, the first
+/// existing node of `ids` is relabeled to `into` first.
+#[cfg(feature = "std")]
+pub fn contract_nodes<G, I, F>(graph: &mut G, ids: I, into: Id<G::Node>, mut merge: F) -> usize
+where
+    G: GraphWriter,
+    I: IntoIterator<Item = Id<G::Node>>,
+    F: FnMut(&mut Data<G::Node>, Data<G::Node>),
+    Id<G::Node>: Clone + Eq + Hash,
+    Id<G::Edge>: Clone,
+    Data<G::Node>: Clone,
+    Data<G::Edge>: Clone,
+{
+    let mut ids: Vec<Id<G::Node>> = ids
+        .into_iter()
+        .filter(|id| *id != into && graph.get_node(id).is_some())
+        .collect();
+    let contracted: HashSet<Id<G::Node>> = ids.iter().cloned().collect();
+    let mut merged = 0;
+
+    if graph.get_node(&into).is_none() {
+        if ids.is_empty() {
+            return 0;
+        }
+        let first = ids.remove(0);
+        graph.relabel_node(&first, into.clone());
+        merged += 1;
+
+        // The edges between `first` and the other contracted nodes are now
+        // attached to `into`, and are dropped once the others are merged.
+    }
+
+    for id in ids {
+        let (data, edges) = match take_node(graph, &id) {
+            Some(taken) => taken,
+            None => continue,
+        };
+        if let Some(target) = graph.node_data_mut(&into) {
+            merge(target, data);
+        }
+        for (e, from, to, data) in edges {
+            let inner = |n: &Id<G::Node>| *n == into || *n == id || contracted.contains(n);
+            if from != to && inner(&from) && inner(&to) {
+                continue;
+            }
+            let from = if from == id { into.clone() } else { from };
+            let to = if to == id { into.clone() } else { to };
+            graph.add_edge(e, &from, &to, data);
+        }
+        merged += 1;
+    }
+    merged
+}
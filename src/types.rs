@@ -3,41 +3,380 @@
 extern crate num_traits;
 #[cfg(feature = "quickcheck")]
 extern crate quickcheck;
+extern crate siphasher;
 
+use alloc::borrow::ToOwned;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::ops::{Add, Mul};
+use core::str::FromStr;
 use num_traits::Zero;
-use std::collections::HashMap;
-use std::hash::Hash;
-use std::ops::Add;
+use siphasher::sip::SipHasher24;
 
-use super::{Graph, Id};
+use super::cancel::Cancelled;
+use super::epochs::{EdgeHistory, EpochId};
+use super::{Data, Edge, Graph, GraphObject, HasWeight, Id};
 
 #[cfg(feature = "quickcheck")]
 use quickcheck::{Arbitrary, Gen};
 
+/// The version of the shape of node and edge types and data. It is bumped
+/// whenever a change to these types breaks graphs persisted by earlier
+/// releases; see the `migrate` module to upgrade them.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A node identifier tagged with the kind of node it refers to.
+///
+/// The tag only exists at the type level: a `TypedId` has the same
+/// representation as its `Raw` id, but a `ProjectId` can't be used where a
+/// `UserId` is expected.
+pub struct TypedId<Tag, Raw = u64> {
+    raw: Raw,
+    tag: PhantomData<fn() -> Tag>,
+}
+
+/// Marker for project node ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ProjectTag {}
+
+/// Marker for user node ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum UserTag {}
+
+/// The id of a project node.
+pub type ProjectId<Raw = u64> = TypedId<ProjectTag, Raw>;
+
+/// The id of a user node.
+pub type UserId<Raw = u64> = TypedId<UserTag, Raw>;
+
+impl<Tag, Raw> TypedId<Tag, Raw> {
+    /// Tag a raw id.
+    pub fn new(raw: Raw) -> Self {
+        TypedId {
+            raw,
+            tag: PhantomData,
+        }
+    }
+
+    /// Get a reference to the underlying raw id.
+    pub fn raw(&self) -> &Raw {
+        &self.raw
+    }
+
+    /// Drop the tag, returning the underlying raw id.
+    pub fn into_raw(self) -> Raw {
+        self.raw
+    }
+}
+
+impl<Tag, Raw> From<Raw> for TypedId<Tag, Raw> {
+    fn from(raw: Raw) -> Self {
+        Self::new(raw)
+    }
+}
+
+impl<Tag, Raw> Borrow<Raw> for TypedId<Tag, Raw> {
+    fn borrow(&self) -> &Raw {
+        &self.raw
+    }
+}
+
+impl<Tag, Raw> AsRef<Raw> for TypedId<Tag, Raw> {
+    fn as_ref(&self) -> &Raw {
+        &self.raw
+    }
+}
+
+// The following impls are written by hand rather than derived, so that they
+// don't require the (uninhabited) tag to implement the traits.
+
+impl<Tag, Raw: Clone> Clone for TypedId<Tag, Raw> {
+    fn clone(&self) -> Self {
+        Self::new(self.raw.clone())
+    }
+}
+
+impl<Tag, Raw: Copy> Copy for TypedId<Tag, Raw> {}
+
+impl<Tag, Raw: PartialEq> PartialEq for TypedId<Tag, Raw> {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+
+impl<Tag, Raw: Eq> Eq for TypedId<Tag, Raw> {}
+
+impl<Tag, Raw: PartialOrd> PartialOrd for TypedId<Tag, Raw> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.raw.partial_cmp(&other.raw)
+    }
+}
+
+impl<Tag, Raw: Ord> Ord for TypedId<Tag, Raw> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.raw.cmp(&other.raw)
+    }
+}
+
+impl<Tag, Raw: Hash> Hash for TypedId<Tag, Raw> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.raw.hash(state)
+    }
+}
+
+impl<Tag, Raw: fmt::Debug> fmt::Debug for TypedId<Tag, Raw> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = core::any::type_name::<Tag>();
+        let name = name.rsplit("::").next().unwrap_or(name);
+
+        write!(f, "{}({:?})", name.trim_end_matches("Tag"), self.raw)
+    }
+}
+
+#[cfg(feature = "quickcheck")]
+impl<Tag: 'static, Raw: Arbitrary> Arbitrary for TypedId<Tag, Raw> {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self::new(Arbitrary::arbitrary(g))
+    }
+}
+
+/// A 32-byte hash, eg. the hash identifying a project on the registry.
+///
+/// Hashes are formatted and parsed as 64 lowercase hexadecimal digits, which
+/// is also how they are serialized.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub struct Hash32(pub [u8; 32]);
+
+impl Hash32 {
+    /// The bytes of the hash.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl From<[u8; 32]> for Hash32 {
+    fn from(bytes: [u8; 32]) -> Self {
+        Hash32(bytes)
+    }
+}
+
+impl From<Hash32> for [u8; 32] {
+    fn from(hash: Hash32) -> Self {
+        hash.0
+    }
+}
+
+impl AsRef<[u8]> for Hash32 {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Hash32 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_hex(f, &self.0)
+    }
+}
+
+impl fmt::Debug for Hash32 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Hash32({})", self)
+    }
+}
+
+impl FromStr for Hash32 {
+    type Err = ParseHashError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.strip_prefix("0x").unwrap_or(s);
+        check_hex(digits)?;
+        if digits.len() != 64 {
+            return Err(ParseHashError::InvalidLength(digits.len()));
+        }
+        let mut bytes = [0; 32];
+        for (byte, pair) in bytes.iter_mut().zip(digits.as_bytes().chunks(2)) {
+            *byte = hex_digit(pair[0])? << 4 | hex_digit(pair[1])?;
+        }
+        Ok(Hash32(bytes))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Hash32 {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Hash32 {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "quickcheck")]
+impl Arbitrary for Hash32 {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let mut bytes = [0; 32];
+        for b in bytes.iter_mut() {
+            *b = Arbitrary::arbitrary(g);
+        }
+        Hash32(bytes)
+    }
+}
+
+/// A failure to parse a `Hash32` or a `NodeId`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseHashError {
+    /// The number of hexadecimal digits isn't 64 for a hash, or isn't even
+    /// for a version.
+    InvalidLength(usize),
+    /// A character isn't a hexadecimal digit.
+    InvalidChar(char),
+    /// A `NodeId` lacks the `@` separating the hash from the version.
+    MissingVersion,
+}
+
+impl fmt::Display for ParseHashError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseHashError::InvalidLength(len) => {
+                write!(f, "invalid number of hexadecimal digits: {}", len)
+            }
+            ParseHashError::InvalidChar(c) => write!(f, "invalid hexadecimal digit {:?}", c),
+            ParseHashError::MissingVersion => write!(f, "missing version"),
+        }
+    }
+}
+
+impl core::error::Error for ParseHashError {}
+
+/// Write bytes as lowercase hexadecimal digits.
+fn write_hex(f: &mut fmt::Formatter, bytes: &[u8]) -> fmt::Result {
+    for b in bytes {
+        write!(f, "{:02x}", b)?;
+    }
+    Ok(())
+}
+
+/// Check that a string only holds hexadecimal digits, so that its length
+/// counts digits and multi-byte characters are reported whole.
+fn check_hex(digits: &str) -> Result<(), ParseHashError> {
+    match digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+        Some(c) => Err(ParseHashError::InvalidChar(c)),
+        None => Ok(()),
+    }
+}
+
+/// Parse a hexadecimal digit, in either case.
+fn hex_digit(c: u8) -> Result<u8, ParseHashError> {
+    match c {
+        b'0'..=b'9' => Ok(c - b'0'),
+        b'a'..=b'f' => Ok(c - b'a' + 10),
+        b'A'..=b'F' => Ok(c - b'A' + 10),
+        _ => Err(ParseHashError::InvalidChar(c as char)),
+    }
+}
+
+/// The id of a version of a project: the hash of the project, and the
+/// version.
+///
+/// Its canonical representation, used to derive edge ids and to serialize
+/// it, is the hash and the version in hexadecimal, separated by `@`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId {
+    /// The hash of the project.
+    pub project: Hash32,
+    /// The version.
+    pub version: Vec<u8>,
+}
+
+impl NodeId {
+    /// The id of the given version of a project.
+    pub fn new<V: Into<Vec<u8>>>(project: Hash32, version: V) -> Self {
+        NodeId {
+            project,
+            version: version.into(),
+        }
+    }
+}
+
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}@", self.project)?;
+        write_hex(f, &self.version)
+    }
+}
+
+impl FromStr for NodeId {
+    type Err = ParseHashError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (project, version) = s.split_once('@').ok_or(ParseHashError::MissingVersion)?;
+        check_hex(version)?;
+        if version.len() % 2 != 0 {
+            return Err(ParseHashError::InvalidLength(version.len()));
+        }
+        let version = version
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| Ok(hex_digit(pair[0])? << 4 | hex_digit(pair[1])?))
+            .collect::<Result<Vec<u8>, ParseHashError>>()?;
+
+        Ok(NodeId {
+            project: project.parse()?,
+            version,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for NodeId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for NodeId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 /// The type of a node.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NodeType {
     /// A user, eg. contributor, project member etc.
     User { contributions_to_all_projects: u32 },
     /// A project with users as members and contributors.
     Project { contributions_from_all_users: u32 },
+    /// An organization owning projects.
+    Org {
+        contributions_to_owned_projects: u32,
+    },
+    /// A version of a project, as recorded by a checkpoint.
+    Checkpoint { contributions: u32 },
 }
 
 impl NodeType {
-    /// Increments the current contributions for this `NodeType` by 'c'.
+    /// Increments the current contributions for this `NodeType` by 'c',
+    /// saturating at `u32::MAX`.
     pub fn add_contributions(&mut self, c: u32) {
-        match self {
-            NodeType::User {
-                contributions_to_all_projects,
-            } => {
-                *contributions_to_all_projects += c;
-            }
-            NodeType::Project {
-                contributions_from_all_users,
-            } => {
-                *contributions_from_all_users += c;
-            }
-        }
+        let total = self.total_contributions().saturating_add(c);
+        self.set_contributions(total);
     }
 
     /// Set the contributions to the given value.
@@ -53,6 +392,14 @@ impl NodeType {
             } => {
                 *contributions_from_all_users = c;
             }
+            NodeType::Org {
+                contributions_to_owned_projects,
+            } => {
+                *contributions_to_owned_projects = c;
+            }
+            NodeType::Checkpoint { contributions } => {
+                *contributions = c;
+            }
         }
     }
 
@@ -64,32 +411,157 @@ impl NodeType {
             NodeType::Project {
                 contributions_from_all_users,
             } => *contributions_from_all_users,
+            NodeType::Org {
+                contributions_to_owned_projects,
+            } => *contributions_to_owned_projects,
+            NodeType::Checkpoint { contributions } => *contributions,
         }
     }
 }
 
 #[cfg(feature = "quickcheck")]
 impl Arbitrary for NodeType {
-    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+    fn arbitrary(g: &mut Gen) -> Self {
         let contribs = Arbitrary::arbitrary(g);
-        if g.next_u32() % 2 == 0 {
-            Self::User {
+        match u8::arbitrary(g) % 4 {
+            0 => Self::User {
                 contributions_to_all_projects: contribs,
-            }
-        } else {
-            Self::Project {
+            },
+            1 => Self::Project {
                 contributions_from_all_users: contribs,
-            }
+            },
+            2 => Self::Org {
+                contributions_to_owned_projects: contribs,
+            },
+            _ => Self::Checkpoint {
+                contributions: contribs,
+            },
+        }
+    }
+}
+
+/// The contributions of a node, broken down by counterpart: by project for
+/// users, and by user for projects.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(deserialize = "NodeId: Ord + serde::Deserialize<'de>"))
+)]
+pub struct ContributionLedger<NodeId = u64> {
+    contributions: BTreeMap<NodeId, u32>,
+}
+
+impl<NodeId> Default for ContributionLedger<NodeId> {
+    fn default() -> Self {
+        ContributionLedger {
+            contributions: BTreeMap::new(),
         }
     }
 }
 
+impl<NodeId: Ord> ContributionLedger<NodeId> {
+    /// An empty ledger.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The contributions with the given counterpart.
+    pub fn get(&self, counterpart: &NodeId) -> u32 {
+        self.contributions.get(counterpart).copied().unwrap_or(0)
+    }
+
+    /// Record contributions with the given counterpart, saturating at
+    /// `u32::MAX`.
+    pub fn add(&mut self, counterpart: NodeId, contributions: u32) {
+        if contributions > 0 {
+            let c = self.contributions.entry(counterpart).or_insert(0);
+            *c = c.saturating_add(contributions);
+        }
+    }
+
+    /// Add the contributions of another ledger to this one.
+    pub fn merge(&mut self, other: &Self)
+    where
+        NodeId: Clone,
+    {
+        for (counterpart, c) in other.iter() {
+            self.add(counterpart.clone(), c);
+        }
+    }
+
+    /// The counterparts and their contributions, by counterpart.
+    pub fn iter(&self) -> impl Iterator<Item = (&NodeId, u32)> {
+        self.contributions.iter().map(|(id, c)| (id, *c))
+    }
+
+    /// The sum of all contributions, saturating at `u32::MAX`.
+    pub fn total(&self) -> u32 {
+        self.contributions
+            .values()
+            .fold(0, |total, c| total.saturating_add(*c))
+    }
+
+    /// The number of counterparts.
+    pub fn len(&self) -> usize {
+        self.contributions.len()
+    }
+
+    /// Whether there are no contributions.
+    pub fn is_empty(&self) -> bool {
+        self.contributions.is_empty()
+    }
+
+    /// Apply `f` to every count, dropping the counterparts left without
+    /// contributions.
+    pub fn scale<F: FnMut(u32) -> u32>(&mut self, mut f: F) {
+        self.contributions.retain(|_, c| {
+            *c = f(*c);
+            *c > 0
+        });
+    }
+}
+
+/// When a graph object was added and last modified, in epochs or block
+/// heights, if tracked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Timestamps {
+    /// When the object entered the graph.
+    pub created_at: Option<EpochId>,
+    /// When the object was last replaced or modified.
+    pub updated_at: Option<EpochId>,
+}
+
+impl Timestamps {
+    /// Timestamps of an object created at `now`.
+    pub fn created(now: EpochId) -> Self {
+        Timestamps {
+            created_at: Some(now),
+            updated_at: Some(now),
+        }
+    }
+
+    /// Record a modification at `now`.
+    pub fn touch(&mut self, now: EpochId) {
+        self.updated_at = Some(now);
+    }
+}
+
 /// Node data.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NodeData<W> {
     /// The type for this node.
     pub node_type: NodeType,
     pub rank: NodeRank<W>,
+    /// The breakdown of the contributions of `node_type`. It may be empty, or
+    /// partial, when contributions are only known in aggregate.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub contributions: ContributionLedger,
+    /// When the node was added and last modified.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub timestamps: Timestamps,
 }
 
 #[cfg(feature = "quickcheck")]
@@ -97,10 +569,12 @@ impl<W> Arbitrary for NodeData<W>
 where
     W: Arbitrary,
 {
-    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+    fn arbitrary(g: &mut Gen) -> Self {
         NodeData {
             node_type: Arbitrary::arbitrary(g),
             rank: Arbitrary::arbitrary(g),
+            contributions: ContributionLedger::new(),
+            timestamps: Timestamps::default(),
         }
     }
 }
@@ -108,6 +582,7 @@ where
 /// The type of an edge. When allowed, it bundles together the number of
 /// contributions.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EdgeType {
     /// Contribution from a project to a user. Corresponds to `contrib` from the paper.
     ProjectToUserContribution(u32),
@@ -119,17 +594,32 @@ pub enum EdgeType {
     UserToProjectMembership(u32),
     /// One-way dependency between two projects. Correspond to `depend` from the paper.
     Dependency,
+    /// Ownership relation from an organization to a project.
+    OrgToProjectOwnership,
+    /// Ownership relation from a project to its organization.
+    ProjectToOrgOwnership,
+    /// Funds donated by a user or an organization to a project.
+    Donation(u32),
+    /// A relationship this crate doesn't know about, eg. code reviews,
+    /// identified by its tag. Custom edges are weighted by the
+    /// hyperparameter of `EdgeTypeTag::Custom(tag)`.
+    Custom { tag: String, contributions: u32 },
 }
 
 /// A companion tag for an `EdgeType`, to allow the former to be used as a key
-/// in a `HashMap`.
+/// in a map.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EdgeTypeTag {
     ProjectToUserContribution,
     UserToProjectContribution,
     ProjectToUserMembership,
     UserToProjectMembership,
     Dependency,
+    OrgToProjectOwnership,
+    ProjectToOrgOwnership,
+    Donation,
+    Custom(String),
 }
 
 impl EdgeType {
@@ -140,6 +630,10 @@ impl EdgeType {
             EdgeType::ProjectToUserMembership(_) => EdgeTypeTag::ProjectToUserMembership,
             EdgeType::UserToProjectMembership(_) => EdgeTypeTag::UserToProjectMembership,
             EdgeType::Dependency => EdgeTypeTag::Dependency,
+            EdgeType::OrgToProjectOwnership => EdgeTypeTag::OrgToProjectOwnership,
+            EdgeType::ProjectToOrgOwnership => EdgeTypeTag::ProjectToOrgOwnership,
+            EdgeType::Donation(_) => EdgeTypeTag::Donation,
+            EdgeType::Custom { tag, .. } => EdgeTypeTag::Custom(tag.clone()),
         }
     }
 
@@ -149,13 +643,292 @@ impl EdgeType {
             EdgeType::UserToProjectContribution(c) => *c,
             EdgeType::ProjectToUserMembership(c) => *c,
             EdgeType::UserToProjectMembership(c) => *c,
-            EdgeType::Dependency => 0,
+            EdgeType::Custom { contributions, .. } => *contributions,
+            EdgeType::Dependency
+            | EdgeType::OrgToProjectOwnership
+            | EdgeType::ProjectToOrgOwnership
+            | EdgeType::Donation(_) => 0,
+        }
+    }
+
+    /// The number carried by the edge: its contributions, or the amount
+    /// donated.
+    pub fn value(&self) -> u32 {
+        match self {
+            EdgeType::Donation(amount) => *amount,
+            other => other.total_contributions(),
+        }
+    }
+}
+
+/// A failure to decode a `NodeType`, an `EdgeType` or an `EdgeTypeTag` from
+/// its string or numeric encoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseTypeError {
+    /// The numeric code isn't known, or can't be decoded on its own, like
+    /// the code of custom edge types.
+    UnknownCode(u8),
+    /// The name isn't known.
+    UnknownName(String),
+    /// The value between parentheses is missing or invalid.
+    InvalidValue(String),
+}
+
+impl fmt::Display for ParseTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseTypeError::UnknownCode(code) => write!(f, "unknown type code {}", code),
+            ParseTypeError::UnknownName(name) => write!(f, "unknown type {:?}", name),
+            ParseTypeError::InvalidValue(s) => write!(f, "invalid value in {:?}", s),
+        }
+    }
+}
+
+impl core::error::Error for ParseTypeError {}
+
+/// Split `name(value)` into its name and value, or return `name` alone.
+fn split_value(s: &str) -> Result<(&str, Option<u32>), ParseTypeError> {
+    let invalid = || ParseTypeError::InvalidValue(s.to_owned());
+
+    match s.strip_suffix(')') {
+        None => Ok((s, None)),
+        Some(rest) => {
+            let open = rest.rfind('(').ok_or_else(invalid)?;
+            let value = rest[open + 1..].parse().map_err(|_| invalid())?;
+            Ok((&rest[..open], Some(value)))
+        }
+    }
+}
+
+impl NodeType {
+    /// The stable numeric code of the variant.
+    pub fn code(&self) -> u8 {
+        match self {
+            NodeType::User { .. } => 0,
+            NodeType::Project { .. } => 1,
+            NodeType::Org { .. } => 2,
+            NodeType::Checkpoint { .. } => 3,
+        }
+    }
+
+    /// The name of the variant, as used by `Display`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            NodeType::User { .. } => "user",
+            NodeType::Project { .. } => "project",
+            NodeType::Org { .. } => "org",
+            NodeType::Checkpoint { .. } => "checkpoint",
+        }
+    }
+}
+
+/// Decode a node type from its code and its contributions.
+impl TryFrom<(u8, u32)> for NodeType {
+    type Error = ParseTypeError;
+
+    fn try_from((code, contributions): (u8, u32)) -> Result<Self, Self::Error> {
+        let mut node_type = match code {
+            0 => NodeType::User {
+                contributions_to_all_projects: 0,
+            },
+            1 => NodeType::Project {
+                contributions_from_all_users: 0,
+            },
+            2 => NodeType::Org {
+                contributions_to_owned_projects: 0,
+            },
+            3 => NodeType::Checkpoint { contributions: 0 },
+            _ => return Err(ParseTypeError::UnknownCode(code)),
+        };
+        node_type.set_contributions(contributions);
+        Ok(node_type)
+    }
+}
+
+/// Formats as the name of the variant followed by the contributions between
+/// parentheses, eg. `user(3)`.
+impl fmt::Display for NodeType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}({})", self.name(), self.total_contributions())
+    }
+}
+
+/// Parses the `Display` format. The contributions may be omitted, and
+/// default to zero.
+impl FromStr for NodeType {
+    type Err = ParseTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, contributions) = split_value(s)?;
+        let code = match name {
+            "user" => 0,
+            "project" => 1,
+            "org" => 2,
+            "checkpoint" => 3,
+            _ => return Err(ParseTypeError::UnknownName(name.to_owned())),
+        };
+        NodeType::try_from((code, contributions.unwrap_or(0)))
+    }
+}
+
+impl EdgeTypeTag {
+    /// All the tags, except custom ones.
+    pub const ALL: [EdgeTypeTag; 8] = [
+        EdgeTypeTag::ProjectToUserContribution,
+        EdgeTypeTag::UserToProjectContribution,
+        EdgeTypeTag::ProjectToUserMembership,
+        EdgeTypeTag::UserToProjectMembership,
+        EdgeTypeTag::Dependency,
+        EdgeTypeTag::OrgToProjectOwnership,
+        EdgeTypeTag::ProjectToOrgOwnership,
+        EdgeTypeTag::Donation,
+    ];
+
+    /// The prefix of the names of custom tags, eg. `custom:code_review`.
+    pub const CUSTOM_PREFIX: &'static str = "custom:";
+
+    /// The stable numeric code of the tag. All custom tags share the same
+    /// code.
+    pub fn code(&self) -> u8 {
+        match self {
+            EdgeTypeTag::ProjectToUserContribution => 0,
+            EdgeTypeTag::UserToProjectContribution => 1,
+            EdgeTypeTag::ProjectToUserMembership => 2,
+            EdgeTypeTag::UserToProjectMembership => 3,
+            EdgeTypeTag::Dependency => 4,
+            EdgeTypeTag::OrgToProjectOwnership => 5,
+            EdgeTypeTag::ProjectToOrgOwnership => 6,
+            EdgeTypeTag::Donation => 7,
+            EdgeTypeTag::Custom(_) => 8,
         }
     }
+
+    /// Whether edges of this type carry a value: contributions, or an
+    /// amount.
+    pub fn has_value(&self) -> bool {
+        !matches!(
+            self,
+            EdgeTypeTag::Dependency
+                | EdgeTypeTag::OrgToProjectOwnership
+                | EdgeTypeTag::ProjectToOrgOwnership
+        )
+    }
+}
+
+/// Decode a tag from its code. Custom tags can't be decoded.
+impl TryFrom<u8> for EdgeTypeTag {
+    type Error = ParseTypeError;
+
+    fn try_from(code: u8) -> Result<Self, Self::Error> {
+        EdgeTypeTag::ALL
+            .iter()
+            .find(|tag| tag.code() == code)
+            .cloned()
+            .ok_or(ParseTypeError::UnknownCode(code))
+    }
+}
+
+/// Formats in snake case, eg. `user_to_project_contribution`, and custom
+/// tags with the `custom:` prefix.
+impl fmt::Display for EdgeTypeTag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            EdgeTypeTag::ProjectToUserContribution => "project_to_user_contribution",
+            EdgeTypeTag::UserToProjectContribution => "user_to_project_contribution",
+            EdgeTypeTag::ProjectToUserMembership => "project_to_user_membership",
+            EdgeTypeTag::UserToProjectMembership => "user_to_project_membership",
+            EdgeTypeTag::Dependency => "dependency",
+            EdgeTypeTag::OrgToProjectOwnership => "org_to_project_ownership",
+            EdgeTypeTag::ProjectToOrgOwnership => "project_to_org_ownership",
+            EdgeTypeTag::Donation => "donation",
+            EdgeTypeTag::Custom(tag) => return write!(f, "{}{}", Self::CUSTOM_PREFIX, tag),
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for EdgeTypeTag {
+    type Err = ParseTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix(Self::CUSTOM_PREFIX) {
+            Some(tag) if !tag.is_empty() => Ok(EdgeTypeTag::Custom(tag.to_owned())),
+            _ => EdgeTypeTag::ALL
+                .iter()
+                .find(|tag| tag.to_string() == s)
+                .cloned()
+                .ok_or_else(|| ParseTypeError::UnknownName(s.to_owned())),
+        }
+    }
+}
+
+impl EdgeType {
+    /// The edge type with the given tag and value. The value is ignored for
+    /// tags without one.
+    pub fn from_tag(tag: EdgeTypeTag, value: u32) -> Self {
+        match tag {
+            EdgeTypeTag::ProjectToUserContribution => EdgeType::ProjectToUserContribution(value),
+            EdgeTypeTag::UserToProjectContribution => EdgeType::UserToProjectContribution(value),
+            EdgeTypeTag::ProjectToUserMembership => EdgeType::ProjectToUserMembership(value),
+            EdgeTypeTag::UserToProjectMembership => EdgeType::UserToProjectMembership(value),
+            EdgeTypeTag::Dependency => EdgeType::Dependency,
+            EdgeTypeTag::OrgToProjectOwnership => EdgeType::OrgToProjectOwnership,
+            EdgeTypeTag::ProjectToOrgOwnership => EdgeType::ProjectToOrgOwnership,
+            EdgeTypeTag::Donation => EdgeType::Donation(value),
+            EdgeTypeTag::Custom(tag) => EdgeType::Custom {
+                tag,
+                contributions: value,
+            },
+        }
+    }
+
+    /// The stable numeric code of the variant, see `EdgeTypeTag::code`.
+    pub fn code(&self) -> u8 {
+        self.to_tag().code()
+    }
+}
+
+/// Decode an edge type from its code and its value. Custom edge types can't
+/// be decoded.
+impl TryFrom<(u8, u32)> for EdgeType {
+    type Error = ParseTypeError;
+
+    fn try_from((code, value): (u8, u32)) -> Result<Self, Self::Error> {
+        Ok(EdgeType::from_tag(EdgeTypeTag::try_from(code)?, value))
+    }
+}
+
+/// Formats as the tag followed by the value between parentheses, for types
+/// with a value, eg. `dependency` or `user_to_project_contribution(3)`.
+impl fmt::Display for EdgeType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let tag = self.to_tag();
+        if tag.has_value() {
+            write!(f, "{}({})", tag, self.value())
+        } else {
+            write!(f, "{}", tag)
+        }
+    }
+}
+
+/// Parses the `Display` format. The value may be omitted, and defaults to
+/// zero.
+impl FromStr for EdgeType {
+    type Err = ParseTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (tag, value) = split_value(s)?;
+        let tag: EdgeTypeTag = tag.parse()?;
+        if value.is_some() && !tag.has_value() {
+            return Err(ParseTypeError::InvalidValue(s.to_owned()));
+        }
+        Ok(EdgeType::from_tag(tag, value.unwrap_or(0)))
+    }
 }
 
 /// Edge data.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EdgeData<W> {
     /// The type for this edge.
     pub edge_type: EdgeType,
@@ -163,14 +936,257 @@ pub struct EdgeData<W> {
     /// edges with more contributions higher, or weigh certain dependencies
     /// higher than others.
     pub weight: W,
+    /// The contributions made along the edge, by epoch, if tracked.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub history: EdgeHistory,
+    /// When the edge was added and last modified.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub timestamps: Timestamps,
 }
 
-/// The rank or "osrank" of a node, normalized to `1.0`.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+impl<W: Clone> HasWeight<W> for EdgeData<W> {
+    fn weight(&self) -> W {
+        self.weight.clone()
+    }
+
+    fn set_weight(&mut self, weight: W) {
+        self.weight = weight;
+    }
+}
+
+/// A generator of deterministic graph object ids.
+///
+/// Ids derived by an `IdGenerator` must only depend on its inputs, so that
+/// every node replaying the same checkpoints ends up with the same graph.
+pub trait IdGenerator<NodeId> {
+    /// The type of the generated ids.
+    type Id;
+
+    /// Derive the id of an edge of type `edge_type` between `from` and `to`,
+    /// introduced by the given project `version`. The value of the edge
+    /// type, eg. its contributions, doesn't change the id, so that an edge
+    /// keeps its id as its value changes.
+    fn edge_id(&self, from: &NodeId, to: &NodeId, edge_type: &EdgeType, version: &[u8])
+        -> Self::Id;
+}
+
+/// An `IdGenerator` based on SipHash-2-4 with fixed keys.
+///
+/// Unlike `std::collections::hash_map::DefaultHasher`, the output of this
+/// generator is guaranteed to be stable across Rust versions and platforms:
+/// all inputs are fed to the hasher as little-endian bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SipIdGenerator {
+    key0: u64,
+    key1: u64,
+}
+
+impl SipIdGenerator {
+    /// The first key used by the default generator.
+    pub const DEFAULT_KEY0: u64 = 0x6f73_636f_696e_0001;
+    /// The second key used by the default generator.
+    pub const DEFAULT_KEY1: u64 = 0x6f73_636f_696e_0002;
+
+    /// Create a generator with custom keys. Generators with different keys
+    /// produce unrelated ids for the same inputs.
+    pub fn with_keys(key0: u64, key1: u64) -> Self {
+        SipIdGenerator { key0, key1 }
+    }
+
+    /// Derive the id of a node from an external name, eg. a crate name or an
+    /// email address. `namespace` separates names of different kinds, so that
+    /// equal names in different namespaces get unrelated ids.
+    pub fn name_id(&self, namespace: &str, name: &str) -> u64 {
+        let mut hasher = self.hasher();
+
+        for s in [namespace, name] {
+            hasher.write(&(s.len() as u64).to_le_bytes());
+            hasher.write(s.as_bytes());
+        }
+        hasher.finish()
+    }
+
+    fn hasher(&self) -> SipHasher24 {
+        SipHasher24::new_with_keys(self.key0, self.key1)
+    }
+}
+
+impl Default for SipIdGenerator {
+    fn default() -> Self {
+        Self::with_keys(Self::DEFAULT_KEY0, Self::DEFAULT_KEY1)
+    }
+}
+
+impl IdGenerator<u64> for SipIdGenerator {
+    type Id = u64;
+
+    fn edge_id(&self, from: &u64, to: &u64, edge_type: &EdgeType, version: &[u8]) -> u64 {
+        let mut hasher = self.hasher();
+
+        hasher.write(&from.to_le_bytes());
+        hasher.write(&to.to_le_bytes());
+        write_edge_type(&mut hasher, edge_type);
+        // Length-prefix the version so that it can't be confused with
+        // any of the other fields.
+        hasher.write(&(version.len() as u64).to_le_bytes());
+        hasher.write(version);
+        hasher.finish()
+    }
+}
+
+/// Edge ids between versions of projects are derived from the canonical
+/// encoding of the versions: the hash, followed by the length-prefixed
+/// version.
+impl IdGenerator<NodeId> for SipIdGenerator {
+    type Id = u64;
+
+    fn edge_id(&self, from: &NodeId, to: &NodeId, edge_type: &EdgeType, version: &[u8]) -> u64 {
+        let mut hasher = self.hasher();
+
+        for id in [from, to] {
+            hasher.write(id.project.as_bytes());
+            hasher.write(&(id.version.len() as u64).to_le_bytes());
+            hasher.write(&id.version);
+        }
+        write_edge_type(&mut hasher, edge_type);
+        hasher.write(&(version.len() as u64).to_le_bytes());
+        hasher.write(version);
+        hasher.finish()
+    }
+}
+
+/// Feed the type of an `EdgeType` to a hasher using a stable encoding: a
+/// tag byte, followed by the length-prefixed tag of custom types. Its value
+/// is left out.
+fn write_edge_type<H: Hasher>(hasher: &mut H, edge_type: &EdgeType) {
+    hasher.write(&[edge_type.code()]);
+    if let EdgeType::Custom { tag, .. } = edge_type {
+        hasher.write(&(tag.len() as u64).to_le_bytes());
+        hasher.write(tag.as_bytes());
+    }
+}
+
+/// Weights with a total order, to sort and compare ranks.
+pub trait TotalOrd {
+    /// Compare `self` to `other`.
+    fn total_cmp(&self, other: &Self) -> Ordering;
+}
+
+macro_rules! total_ord_float {
+    ($($t:ty),*) => {$(
+        /// NaN is lower than every number, and equal to itself. Zeroes are
+        /// equal whatever their sign.
+        impl TotalOrd for $t {
+            fn total_cmp(&self, other: &Self) -> Ordering {
+                match (self.is_nan(), other.is_nan()) {
+                    (false, false) => self.partial_cmp(other).unwrap_or(Ordering::Equal),
+                    (nan, other_nan) => other_nan.cmp(&nan),
+                }
+            }
+        }
+    )*};
+}
+
+macro_rules! total_ord_int {
+    ($($t:ty),*) => {$(
+        impl TotalOrd for $t {
+            fn total_cmp(&self, other: &Self) -> Ordering {
+                self.cmp(other)
+            }
+        }
+    )*};
+}
+
+total_ord_float!(f32, f64);
+total_ord_int!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// A float ordered by its `TotalOrd` implementation, so that it can be used
+/// where `Ord` is required, eg. as `NodeRank<OrderedFloat<f64>>`.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrderedFloat<F>(pub F);
+
+impl<F> OrderedFloat<F> {
+    /// The wrapped float.
+    pub fn into_inner(self) -> F {
+        self.0
+    }
+}
+
+impl<F> From<F> for OrderedFloat<F> {
+    fn from(f: F) -> Self {
+        OrderedFloat(f)
+    }
+}
+
+impl<F: TotalOrd> TotalOrd for OrderedFloat<F> {
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl<F: TotalOrd> PartialEq for OrderedFloat<F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<F: TotalOrd> Eq for OrderedFloat<F> {}
+
+impl<F: TotalOrd> PartialOrd for OrderedFloat<F> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<F: TotalOrd> Ord for OrderedFloat<F> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// The rank or "osrank" of a node, normalized to `1.0`. See
+/// `ranking::normalize` and `ranking::check_invariants`.
+///
+/// Ranks compare by their value. Float ranks have no total order: use
+/// `rank_cmp` to sort them, or wrap them in `OrderedFloat`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NodeRank<W> {
     pub rank: W,
 }
 
+impl<W: TotalOrd> NodeRank<W> {
+    /// Compare ranks by `TotalOrd`, where NaN is lower than every float.
+    /// Sorting by decreasing rank is `ranks.sort_by(|a, b| b.rank_cmp(a))`.
+    pub fn rank_cmp(&self, other: &Self) -> Ordering {
+        self.rank.total_cmp(&other.rank)
+    }
+
+    /// The highest of the two ranks, `other` if they are equal.
+    pub fn max(self, other: Self) -> Self {
+        match self.rank_cmp(&other) {
+            Ordering::Greater => self,
+            _ => other,
+        }
+    }
+
+    /// The rank, totally ordered.
+    pub fn ordered(self) -> NodeRank<OrderedFloat<W>> {
+        NodeRank {
+            rank: OrderedFloat(self.rank),
+        }
+    }
+}
+
+impl<W: Copy + Mul<Output = W>> NodeRank<W> {
+    /// Multiply the rank by `factor`, in `[0, 1]`, eg. `0.9` for a rank
+    /// losing a tenth of its value.
+    pub fn decay(&mut self, factor: W) {
+        self.rank = self.rank * factor;
+    }
+}
+
 impl<W: Add<Output = W>> Add for NodeRank<W> {
     type Output = Self;
 
@@ -201,7 +1217,7 @@ impl<W> Arbitrary for NodeRank<W>
 where
     W: Arbitrary,
 {
-    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+    fn arbitrary(g: &mut Gen) -> Self {
         NodeRank {
             rank: Arbitrary::arbitrary(g),
         }
@@ -210,6 +1226,7 @@ where
 
 /// Global DampingFactors used by the graph algorithm.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DampingFactors {
     /// Probability that a random walk on a project node continues.
     pub project: f64,
@@ -227,19 +1244,149 @@ pub struct HyperParameters<W> {
     /// 'R' value.
     pub r_value: u32,
     /// Weights for the different edge types.
-    pub edge_weights: HashMap<EdgeTypeTag, W>,
+    pub edge_weights: BTreeMap<EdgeTypeTag, W>,
+    /// The largest share of a node's outgoing weight a single edge may
+    /// carry, if capped. See `weights::cap_edge_weight_shares`.
+    pub max_edge_weight_share: Option<W>,
+    /// The largest rank a user node may receive, if capped. See
+    /// `ranking::cap_user_ranks`.
+    pub max_user_rank: Option<W>,
+    /// The factor, in `[0, 1]`, stored ranks are multiplied by every epoch,
+    /// if they decay. See `algorithms::DecayRanks`.
+    pub rank_decay: Option<W>,
+}
+
+impl<W: From<f64>> HyperParameters<W> {
+    /// The parameters of the osrank paper: walks continue with probability
+    /// `0.85`, `R = 10` walks start from every node, nothing is pruned or
+    /// capped, and edges are weighted as
+    ///
+    /// | edge type                      | weight |
+    /// |--------------------------------|--------|
+    /// | `project_to_user_contribution` | 1/7    |
+    /// | `user_to_project_contribution` | 2/5    |
+    /// | `project_to_user_membership`   | 2/7    |
+    /// | `user_to_project_membership`   | 3/5    |
+    /// | `dependency`                   | 4/7    |
+    ///
+    /// Ownership and donation edges aren't part of the paper, and have no
+    /// weight.
+    pub fn osrank_paper_defaults() -> Self {
+        let edge_weights = [
+            (EdgeTypeTag::ProjectToUserContribution, 1.0 / 7.0),
+            (EdgeTypeTag::UserToProjectContribution, 2.0 / 5.0),
+            (EdgeTypeTag::ProjectToUserMembership, 2.0 / 7.0),
+            (EdgeTypeTag::UserToProjectMembership, 3.0 / 5.0),
+            (EdgeTypeTag::Dependency, 4.0 / 7.0),
+        ];
+
+        HyperParameters {
+            pruning_threshold: W::from(0.0),
+            damping_factors: DampingFactors {
+                project: 0.85,
+                account: 0.85,
+            },
+            r_value: 10,
+            edge_weights: edge_weights
+                .iter()
+                .map(|(tag, weight)| (tag.clone(), W::from(*weight)))
+                .collect(),
+            max_edge_weight_share: None,
+            max_user_rank: None,
+            rank_decay: None,
+        }
+    }
+
+    /// The edge weights of the paper, with parameters trading some accuracy
+    /// for resistance to gaming: walks are shorter, with damping factors of
+    /// `0.75`, and ten times as many (`R = 100`) to reduce variance, no edge
+    /// carries more than half of the outgoing weight of its node, and no
+    /// user gets more than 1% of the total rank.
+    pub fn conservative() -> Self {
+        HyperParameters {
+            damping_factors: DampingFactors {
+                project: 0.75,
+                account: 0.75,
+            },
+            r_value: 100,
+            max_edge_weight_share: Some(W::from(0.5)),
+            max_user_rank: Some(W::from(0.01)),
+            ..Self::osrank_paper_defaults()
+        }
+    }
 }
 
 impl<W> HyperParameters<W> {
+    /// Get the hyper value associated to the input `EdgeTypeTag`, or an
+    /// `AlgorithmError::MissingHyperParameter` if it cannot be found.
+    pub fn try_get_param(&self, edge_type_tag: &EdgeTypeTag) -> Result<&W, AlgorithmError> {
+        self.edge_weights
+            .get(edge_type_tag)
+            .ok_or_else(|| AlgorithmError::MissingHyperParameter(edge_type_tag.clone()))
+    }
+
     /// Get the hyper value associated to the input `EdgeTypeTag`. It panics at
     /// runtime if the value cannot be found.
     pub fn get_param(&self, edge_type_tag: &EdgeTypeTag) -> &W {
         self.edge_weights
-            .get(&edge_type_tag)
+            .get(edge_type_tag)
             .unwrap_or_else(|| panic!("hyperparam value for {:#?} not found.", edge_type_tag))
     }
 }
 
+/// A standard error for graph algorithms. Implementations are encouraged to
+/// use it as their `GraphAlgorithm::Error`, or to wrap it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlgorithmError {
+    /// No hyperparameter was provided for the given edge type.
+    MissingHyperParameter(EdgeTypeTag),
+    /// An iterative algorithm didn't converge within its maximum number of
+    /// iterations.
+    NotConverged {
+        /// Number of iterations performed.
+        iterations: u64,
+        /// The remaining difference between the last two iterations.
+        residual: f64,
+    },
+    /// The graph doesn't satisfy an invariant the algorithm relies on.
+    InvariantViolated(String),
+    /// The execution was cancelled.
+    Cancelled,
+    /// The annotator failed to record an annotation.
+    Annotator(String),
+}
+
+impl fmt::Display for AlgorithmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AlgorithmError::MissingHyperParameter(tag) => {
+                write!(f, "hyperparam value for {:?} not found", tag)
+            }
+            AlgorithmError::NotConverged {
+                iterations,
+                residual,
+            } => write!(
+                f,
+                "did not converge after {} iterations (residual {})",
+                iterations, residual
+            ),
+            AlgorithmError::InvariantViolated(msg) => {
+                write!(f, "graph invariant violated: {}", msg)
+            }
+            AlgorithmError::Cancelled => write!(f, "execution was cancelled"),
+            AlgorithmError::Annotator(msg) => write!(f, "annotator failure: {}", msg),
+        }
+    }
+}
+
+impl core::error::Error for AlgorithmError {}
+
+impl From<Cancelled> for AlgorithmError {
+    fn from(_: Cancelled) -> Self {
+        AlgorithmError::Cancelled
+    }
+}
+
 /* Concrete types for the incremental MonteCarlo algorithm. */
 
 /// This is an enumeration of all the possible ways in which a `Graph` can be
@@ -267,29 +1414,330 @@ where
     /// An existing edge has been deleted from the network. We require full
     /// ownership over the `G::Edge` for the same reasons of `NodeDeleted`.
     EdgeDeleted(G::Edge),
-    // NOTE: There is no `EdgeUpdated` by design: this is because the only
-    // reason why an edge might be updated is either to change "Direction"
-    // (which seems unlikely and wrong to begin with) or to bump the number
-    // of contributions. But in a "multi-version" world like this one, this is
-    // *not* what happens. Rather, every time a new contributions contributes
-    // you do *not* update an existing node but rather the next checkpoint a brand
-    // new edge is added (with the new contributions) and a new *project version*
-    // is released.
-}
-
-/// An Iterator over a collection of `GraphDiff`.
-pub struct GraphDiffs<'a, G: 'a>
+    /// The data of an existing edge has been updated in place, eg. its weight
+    /// was renormalized or its contributions decayed at the end of an epoch.
+    /// The edge keeps its id and endpoints: a new contribution, on the other
+    /// hand, is represented by a brand new edge added at the next checkpoint,
+    /// along with a new *project version*.
+    EdgeUpdated {
+        id: &'a Id<G::Edge>,
+        source: &'a Id<G::Node>,
+        target: &'a Id<G::Node>,
+    },
+}
+
+/// An owned `GraphDiff`, holding ids and data by value, so that it can
+/// outlive the graph that produced it, eg. to be journaled or sent over the
+/// network.
+pub enum GraphDiffOwned<G>
+where
+    G: Graph,
+{
+    /// A new node has been added to the network.
+    NodeAdded {
+        id: Id<G::Node>,
+        data: Data<G::Node>,
+    },
+    /// An existing node has been deleted from the network.
+    NodeDeleted {
+        id: Id<G::Node>,
+        data: Data<G::Node>,
+    },
+    /// The data of a node has been updated.
+    NodeUpdated {
+        id: Id<G::Node>,
+        data: Data<G::Node>,
+    },
+    /// A new edge has been added to the network.
+    EdgeAdded {
+        id: Id<G::Edge>,
+        source: Id<G::Node>,
+        target: Id<G::Node>,
+        data: Data<G::Edge>,
+    },
+    /// An existing edge has been deleted from the network.
+    EdgeDeleted {
+        id: Id<G::Edge>,
+        source: Id<G::Node>,
+        target: Id<G::Node>,
+        data: Data<G::Edge>,
+    },
+    /// The data of an edge has been updated.
+    EdgeUpdated {
+        id: Id<G::Edge>,
+        source: Id<G::Node>,
+        target: Id<G::Node>,
+        data: Data<G::Edge>,
+    },
+}
+
+impl<'a, G> GraphDiff<'a, G>
+where
+    G: Graph,
+    Id<G::Node>: Clone,
+    Id<G::Edge>: Clone,
+    Data<G::Node>: Clone,
+    Data<G::Edge>: Clone,
+{
+    /// Convert to an owned diff, looking up the data of added and updated
+    /// nodes and edges in `graph`. Returns `None` if they aren't in `graph`
+    /// anymore.
+    pub fn into_owned(self, graph: &G) -> Option<GraphDiffOwned<G>> {
+        Some(match self {
+            GraphDiff::NodeAdded(id) => GraphDiffOwned::NodeAdded {
+                id: id.clone(),
+                data: graph.node_data(id)?.clone(),
+            },
+            GraphDiff::NodeDeleted(node) => GraphDiffOwned::NodeDeleted {
+                id: node.id().clone(),
+                data: node.data().clone(),
+            },
+            GraphDiff::NodeUpdated(id) => GraphDiffOwned::NodeUpdated {
+                id: id.clone(),
+                data: graph.node_data(id)?.clone(),
+            },
+            GraphDiff::EdgeAdded { id, source, target } => GraphDiffOwned::EdgeAdded {
+                id: id.clone(),
+                source: source.clone(),
+                target: target.clone(),
+                data: graph.edge_data(id)?.clone(),
+            },
+            GraphDiff::EdgeDeleted(edge) => GraphDiffOwned::EdgeDeleted {
+                id: edge.id().clone(),
+                source: edge.source().clone(),
+                target: edge.target().clone(),
+                data: edge.data().clone(),
+            },
+            GraphDiff::EdgeUpdated { id, source, target } => GraphDiffOwned::EdgeUpdated {
+                id: id.clone(),
+                source: source.clone(),
+                target: target.clone(),
+                data: graph.edge_data(id)?.clone(),
+            },
+        })
+    }
+}
+
+impl<G> GraphDiffOwned<G>
+where
+    G: Graph,
+{
+    /// Borrow as a `GraphDiff`. Deleted nodes and edges are looked up in
+    /// `graph`, as `GraphDiff` holds them whole: returns `None` if they
+    /// aren't in `graph`, eg. because the diff was already applied.
+    pub fn as_diff<'a>(&'a self, graph: &G) -> Option<GraphDiff<'a, G>>
+    where
+        G::Node: Clone,
+        G::Edge: Clone,
+    {
+        Some(match self {
+            GraphDiffOwned::NodeAdded { id, .. } => GraphDiff::NodeAdded(id),
+            GraphDiffOwned::NodeDeleted { id, .. } => {
+                GraphDiff::NodeDeleted(graph.get_node(id)?.clone())
+            }
+            GraphDiffOwned::NodeUpdated { id, .. } => GraphDiff::NodeUpdated(id),
+            GraphDiffOwned::EdgeAdded {
+                id, source, target, ..
+            } => GraphDiff::EdgeAdded { id, source, target },
+            GraphDiffOwned::EdgeDeleted { id, .. } => {
+                GraphDiff::EdgeDeleted(graph.get_edge(id)?.clone())
+            }
+            GraphDiffOwned::EdgeUpdated {
+                id, source, target, ..
+            } => GraphDiff::EdgeUpdated { id, source, target },
+        })
+    }
+}
+
+impl<G> Clone for GraphDiffOwned<G>
+where
+    G: Graph,
+    Id<G::Node>: Clone,
+    Id<G::Edge>: Clone,
+    Data<G::Node>: Clone,
+    Data<G::Edge>: Clone,
+{
+    fn clone(&self) -> Self {
+        match self {
+            GraphDiffOwned::NodeAdded { id, data } => GraphDiffOwned::NodeAdded {
+                id: id.clone(),
+                data: data.clone(),
+            },
+            GraphDiffOwned::NodeDeleted { id, data } => GraphDiffOwned::NodeDeleted {
+                id: id.clone(),
+                data: data.clone(),
+            },
+            GraphDiffOwned::NodeUpdated { id, data } => GraphDiffOwned::NodeUpdated {
+                id: id.clone(),
+                data: data.clone(),
+            },
+            GraphDiffOwned::EdgeAdded {
+                id,
+                source,
+                target,
+                data,
+            } => GraphDiffOwned::EdgeAdded {
+                id: id.clone(),
+                source: source.clone(),
+                target: target.clone(),
+                data: data.clone(),
+            },
+            GraphDiffOwned::EdgeDeleted {
+                id,
+                source,
+                target,
+                data,
+            } => GraphDiffOwned::EdgeDeleted {
+                id: id.clone(),
+                source: source.clone(),
+                target: target.clone(),
+                data: data.clone(),
+            },
+            GraphDiffOwned::EdgeUpdated {
+                id,
+                source,
+                target,
+                data,
+            } => GraphDiffOwned::EdgeUpdated {
+                id: id.clone(),
+                source: source.clone(),
+                target: target.clone(),
+                data: data.clone(),
+            },
+        }
+    }
+}
+
+impl<G> GraphDiffOwned<G>
+where
+    G: Graph,
+{
+    /// The name of the variant.
+    fn kind(&self) -> &'static str {
+        match self {
+            GraphDiffOwned::NodeAdded { .. } => "NodeAdded",
+            GraphDiffOwned::NodeDeleted { .. } => "NodeDeleted",
+            GraphDiffOwned::NodeUpdated { .. } => "NodeUpdated",
+            GraphDiffOwned::EdgeAdded { .. } => "EdgeAdded",
+            GraphDiffOwned::EdgeDeleted { .. } => "EdgeDeleted",
+            GraphDiffOwned::EdgeUpdated { .. } => "EdgeUpdated",
+        }
+    }
+
+    /// The fields of an edge diff.
+    fn edge_fields(&self) -> Option<EdgeFields<'_, G>> {
+        match self {
+            GraphDiffOwned::EdgeAdded {
+                id,
+                source,
+                target,
+                data,
+            }
+            | GraphDiffOwned::EdgeDeleted {
+                id,
+                source,
+                target,
+                data,
+            }
+            | GraphDiffOwned::EdgeUpdated {
+                id,
+                source,
+                target,
+                data,
+            } => Some((id, source, target, data)),
+            _ => None,
+        }
+    }
+
+    /// The fields of a node diff.
+    fn node_fields(&self) -> Option<NodeFields<'_, G>> {
+        match self {
+            GraphDiffOwned::NodeAdded { id, data }
+            | GraphDiffOwned::NodeDeleted { id, data }
+            | GraphDiffOwned::NodeUpdated { id, data } => Some((id, data)),
+            _ => None,
+        }
+    }
+}
+
+/// The id and data of a node diff.
+type NodeFields<'a, G> = (&'a Id<<G as Graph>::Node>, &'a Data<<G as Graph>::Node>);
+
+/// The id, endpoints and data of an edge diff.
+type EdgeFields<'a, G> = (
+    &'a Id<<G as Graph>::Edge>,
+    &'a Id<<G as Graph>::Node>,
+    &'a Id<<G as Graph>::Node>,
+    &'a Data<<G as Graph>::Edge>,
+);
+
+impl<G> PartialEq for GraphDiffOwned<G>
+where
+    G: Graph,
+    Id<G::Node>: PartialEq,
+    Id<G::Edge>: PartialEq,
+    Data<G::Node>: PartialEq,
+    Data<G::Edge>: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.kind() == other.kind()
+            && self.node_fields() == other.node_fields()
+            && self.edge_fields() == other.edge_fields()
+    }
+}
+
+impl<G> fmt::Debug for GraphDiffOwned<G>
+where
+    G: Graph,
+    Id<G::Node>: fmt::Debug,
+    Id<G::Edge>: fmt::Debug,
+    Data<G::Node>: fmt::Debug,
+    Data<G::Edge>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut s = f.debug_struct(self.kind());
+
+        if let Some((id, data)) = self.node_fields() {
+            s.field("id", id).field("data", data);
+        }
+        if let Some((id, source, target, data)) = self.edge_fields() {
+            s.field("id", id)
+                .field("source", source)
+                .field("target", target)
+                .field("data", data);
+        }
+        s.finish()
+    }
+}
+
+/// An Iterator over a collection of diffs, either borrowed `GraphDiff`s (the
+/// default) or `GraphDiffOwned`s.
+pub struct GraphDiffs<'a, G: 'a, D = &'a GraphDiff<'a, G>>
 where
     G: Graph,
 {
-    pub range: std::vec::IntoIter<&'a GraphDiff<'a, G>>,
+    pub range: alloc::vec::IntoIter<D>,
+    pub graph: PhantomData<&'a G>,
+}
+
+impl<'a, G, D> GraphDiffs<'a, G, D>
+where
+    G: Graph,
+{
+    /// Iterate over the given diffs.
+    pub fn new(diffs: Vec<D>) -> Self {
+        GraphDiffs {
+            range: diffs.into_iter(),
+            graph: PhantomData,
+        }
+    }
 }
 
-impl<'a, G> Iterator for GraphDiffs<'a, G>
+impl<'a, G, D> Iterator for GraphDiffs<'a, G, D>
 where
     G: Graph,
 {
-    type Item = &'a GraphDiff<'a, G>;
+    type Item = D;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.range.next()
@@ -0,0 +1,525 @@
+//! A write-ahead log of graph mutations, to recover graphs after a crash.
+//!
+//! A log directory holds a snapshot of the graph and a log of the mutations
+//! applied since. Both are files of JSON-encoded `WalEntry`s, one per line.
+//! Taking a snapshot replaces the snapshot file atomically, then truncates
+//! the log. Replaying entries is idempotent, so a crash between the two steps
+//! is harmless.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use super::ledger::{Checkpoint, LedgerGraph};
+use super::limits::LimitError;
+use super::types::{EdgeTypeTag, GraphDiffOwned};
+use super::{
+    Data, Direction, Edge, EdgeRefsOf, Edges, EdgesMut, Graph, GraphBuilder, GraphDataWriter,
+    GraphObject, GraphWriter, Id, Nodes, NodesMut,
+};
+
+const SNAPSHOT_FILE: &str = "snapshot";
+const LOG_FILE: &str = "log";
+
+/// A logged graph mutation: the encodable form of a `GraphDiffOwned`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WalEntry<NodeId, EdgeId, NodeData, EdgeData> {
+    /// A node was added, or its data replaced.
+    NodeAdded {
+        /// The node id.
+        id: NodeId,
+        /// The node data.
+        data: NodeData,
+    },
+    /// A node was removed.
+    NodeDeleted(NodeId),
+    /// The data of a node was modified.
+    NodeUpdated {
+        /// The node id.
+        id: NodeId,
+        /// The new node data.
+        data: NodeData,
+    },
+    /// An edge was added.
+    EdgeAdded {
+        /// The edge id.
+        id: EdgeId,
+        /// The source node.
+        from: NodeId,
+        /// The target node.
+        to: NodeId,
+        /// The edge data.
+        data: EdgeData,
+    },
+    /// An edge was removed.
+    EdgeDeleted(EdgeId),
+    /// The data of an edge was modified.
+    EdgeUpdated {
+        /// The edge id.
+        id: EdgeId,
+        /// The new edge data.
+        data: EdgeData,
+    },
+}
+
+/// The log entries of a graph.
+pub(crate) type Entry<G> = WalEntry<
+    Id<<G as Graph>::Node>,
+    Id<<G as Graph>::Edge>,
+    Data<<G as Graph>::Node>,
+    Data<<G as Graph>::Edge>,
+>;
+
+/// A log entry borrowing from a graph.
+type EntryRef<'a, G> = WalEntry<
+    &'a Id<<G as Graph>::Node>,
+    &'a Id<<G as Graph>::Edge>,
+    &'a Data<<G as Graph>::Node>,
+    &'a Data<<G as Graph>::Edge>,
+>;
+
+impl<G: Graph> From<GraphDiffOwned<G>> for Entry<G> {
+    fn from(diff: GraphDiffOwned<G>) -> Self {
+        match diff {
+            GraphDiffOwned::NodeAdded { id, data } => WalEntry::NodeAdded { id, data },
+            GraphDiffOwned::NodeDeleted { id, .. } => WalEntry::NodeDeleted(id),
+            GraphDiffOwned::NodeUpdated { id, data } => WalEntry::NodeUpdated { id, data },
+            GraphDiffOwned::EdgeAdded {
+                id,
+                source,
+                target,
+                data,
+            } => WalEntry::EdgeAdded {
+                id,
+                from: source,
+                to: target,
+                data,
+            },
+            GraphDiffOwned::EdgeDeleted { id, .. } => WalEntry::EdgeDeleted(id),
+            GraphDiffOwned::EdgeUpdated { id, data, .. } => WalEntry::EdgeUpdated { id, data },
+        }
+    }
+}
+
+/// A failure to write or replay a log.
+#[derive(Debug)]
+pub enum WalError {
+    /// Reading or writing a file failed.
+    Io(io::Error),
+    /// An entry couldn't be encoded.
+    Encoding(serde_json::Error),
+    /// An entry couldn't be decoded.
+    Corrupt {
+        /// The file containing the entry.
+        file: PathBuf,
+        /// The line of the entry, starting from 1.
+        line: usize,
+    },
+}
+
+impl fmt::Display for WalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WalError::Io(err) => write!(f, "i/o error: {}", err),
+            WalError::Encoding(err) => write!(f, "encoding error: {}", err),
+            WalError::Corrupt { file, line } => {
+                write!(f, "corrupt entry in {} at line {}", file.display(), line)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WalError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WalError::Io(err) => Some(err),
+            WalError::Encoding(err) => Some(err),
+            WalError::Corrupt { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for WalError {
+    fn from(err: io::Error) -> Self {
+        WalError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for WalError {
+    fn from(err: serde_json::Error) -> Self {
+        WalError::Encoding(err)
+    }
+}
+
+/// A graph wrapper logging every mutation before it is acknowledged.
+///
+/// `GraphWriter` methods can't fail, so failures to append to the log are
+/// kept, and returned by the next `sync`. Data modified through
+/// `GraphDataWriter` is logged on the next `sync`, and nodes modified through
+/// `nodes_mut` are captured by a snapshot on the next `sync`.
+pub struct WalWriter<G: GraphWriter> {
+    graph: G,
+    dir: PathBuf,
+    log: BufWriter<File>,
+    entries: u64,
+    snapshot_interval: Option<u64>,
+    dirty_nodes: Vec<Id<G::Node>>,
+    dirty_edges: Vec<Id<G::Edge>>,
+    needs_snapshot: bool,
+    error: Option<WalError>,
+}
+
+impl<G> WalWriter<G>
+where
+    G: GraphWriter,
+    Id<G::Node>: Clone + Serialize,
+    Id<G::Edge>: Clone + Serialize,
+    Data<G::Node>: Serialize,
+    Data<G::Edge>: Serialize,
+{
+    /// Start logging the mutations of `graph` in `dir`, which is created if
+    /// needed. The graph is snapshotted first, replacing any existing log.
+    pub fn new<P: AsRef<Path>>(graph: G, dir: P) -> Result<Self, WalError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        write_snapshot(&graph, &dir)?;
+        let log = BufWriter::new(File::create(dir.join(LOG_FILE))?);
+
+        Ok(WalWriter {
+            graph,
+            dir,
+            log,
+            entries: 0,
+            snapshot_interval: None,
+            dirty_nodes: Vec::new(),
+            dirty_edges: Vec::new(),
+            needs_snapshot: false,
+            error: None,
+        })
+    }
+
+    /// Take a snapshot automatically every `entries` log entries.
+    pub fn with_snapshot_interval(mut self, entries: u64) -> Self {
+        self.snapshot_interval = Some(entries);
+        self
+    }
+
+    /// The wrapped graph.
+    pub fn graph(&self) -> &G {
+        &self.graph
+    }
+
+    /// Sync the log, and return the wrapped graph.
+    pub fn into_inner(mut self) -> Result<G, WalError> {
+        self.sync()?;
+        Ok(self.graph)
+    }
+
+    /// Make all mutations so far durable.
+    pub fn sync(&mut self) -> Result<(), WalError> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        if self.needs_snapshot {
+            return self.snapshot();
+        }
+        for id in std::mem::take(&mut self.dirty_nodes) {
+            if let Some(data) = self.graph.node_data(&id) {
+                write_entry::<G, _>(&mut self.log, &WalEntry::NodeUpdated { id: &id, data })?;
+            }
+        }
+        for id in std::mem::take(&mut self.dirty_edges) {
+            if let Some(data) = self.graph.edge_data(&id) {
+                write_entry::<G, _>(&mut self.log, &WalEntry::EdgeUpdated { id: &id, data })?;
+            }
+        }
+        self.log.flush()?;
+        self.log.get_ref().sync_data()?;
+
+        Ok(())
+    }
+
+    /// Snapshot the graph and truncate the log.
+    pub fn snapshot(&mut self) -> Result<(), WalError> {
+        // Flush buffered entries before truncating, so they aren't written
+        // past the end of the truncated log later.
+        trace_span!("wal_snapshot", entries = self.entries);
+        self.log.flush()?;
+        write_snapshot(&self.graph, &self.dir)?;
+        self.log = BufWriter::new(File::create(self.dir.join(LOG_FILE))?);
+        self.entries = 0;
+        self.dirty_nodes.clear();
+        self.dirty_edges.clear();
+        self.needs_snapshot = false;
+
+        Ok(())
+    }
+
+    fn append(&mut self, entry: &EntryRef<'_, G>) {
+        if let Err(err) = write_entry::<G, _>(&mut self.log, entry) {
+            trace_event!(error = %err, "failed to append to the log");
+            self.error.get_or_insert(err);
+        }
+        self.entries += 1;
+    }
+
+    /// Snapshot if the interval is reached. Called once a logged mutation is
+    /// applied, so that the snapshot includes it.
+    fn applied(&mut self) {
+        if self.error.is_some() || self.snapshot_interval.is_none_or(|n| self.entries < n) {
+            return;
+        }
+        if let Err(err) = self.snapshot() {
+            self.error = Some(err);
+        }
+    }
+}
+
+/// Rebuild the graph logged in `dir`, replaying its snapshot and log into the
+/// graph built by `builder`. A truncated last entry, left by a crash during a
+/// write, is ignored.
+pub fn recover<B, P>(builder: B, dir: P) -> Result<B::Graph, WalError>
+where
+    B: GraphBuilder,
+    B::Graph: GraphWriter,
+    P: AsRef<Path>,
+    Id<<B::Graph as Graph>::Node>: DeserializeOwned,
+    Id<<B::Graph as Graph>::Edge>: DeserializeOwned,
+    Data<<B::Graph as Graph>::Node>: DeserializeOwned,
+    Data<<B::Graph as Graph>::Edge>: DeserializeOwned,
+{
+    let mut graph = builder.build();
+
+    for file in [SNAPSHOT_FILE, LOG_FILE] {
+        let path = dir.as_ref().join(file);
+        if !path.exists() {
+            continue;
+        }
+        let mut lines = BufReader::new(File::open(&path)?)
+            .lines()
+            .enumerate()
+            .peekable();
+
+        while let Some((i, line)) = lines.next() {
+            let line = line?;
+            match serde_json::from_str::<Entry<B::Graph>>(&line) {
+                Ok(entry) => apply(&mut graph, entry),
+                Err(_) if lines.peek().is_none() => break,
+                Err(_) => {
+                    return Err(WalError::Corrupt {
+                        file: path,
+                        line: i + 1,
+                    })
+                }
+            }
+        }
+    }
+    Ok(graph)
+}
+
+pub(crate) fn apply<G: GraphWriter>(graph: &mut G, entry: Entry<G>) {
+    match entry {
+        WalEntry::NodeAdded { id, data } => graph.add_node(id, data),
+        WalEntry::NodeDeleted(id) => graph.remove_node(id),
+        WalEntry::NodeUpdated { id, data } => {
+            if let Some(d) = graph.node_data_mut(&id) {
+                *d = data;
+            }
+        }
+        WalEntry::EdgeAdded { id, from, to, data } => graph.add_edge(id, &from, &to, data),
+        WalEntry::EdgeDeleted(id) => graph.remove_edge(id),
+        WalEntry::EdgeUpdated { id, data } => {
+            if let Some(d) = graph.edge_data_mut(&id) {
+                *d = data;
+            }
+        }
+    }
+}
+
+fn write_entry<G, W>(writer: &mut W, entry: &EntryRef<'_, G>) -> Result<(), WalError>
+where
+    G: Graph,
+    W: Write,
+    Id<G::Node>: Serialize,
+    Id<G::Edge>: Serialize,
+    Data<G::Node>: Serialize,
+    Data<G::Edge>: Serialize,
+{
+    serde_json::to_writer(&mut *writer, entry)?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Write all nodes and edges to a temporary file, then move it in place.
+fn write_snapshot<G>(graph: &G, dir: &Path) -> Result<(), WalError>
+where
+    G: Graph,
+    Id<G::Node>: Serialize,
+    Id<G::Edge>: Serialize,
+    Data<G::Node>: Serialize,
+    Data<G::Edge>: Serialize,
+{
+    let tmp = dir.join(format!("{}.tmp", SNAPSHOT_FILE));
+    let mut out = BufWriter::new(File::create(&tmp)?);
+
+    for node in graph.nodes() {
+        let entry = WalEntry::NodeAdded {
+            id: node.id(),
+            data: node.data(),
+        };
+        write_entry::<G, _>(&mut out, &entry)?;
+    }
+    for node in graph.nodes() {
+        for e in graph.edges_directed(node.id(), Direction::Outgoing) {
+            if let Some(edge) = graph.get_edge(e.id) {
+                let entry = WalEntry::EdgeAdded {
+                    id: edge.id(),
+                    from: edge.source(),
+                    to: edge.target(),
+                    data: edge.data(),
+                };
+                write_entry::<G, _>(&mut out, &entry)?;
+            }
+        }
+    }
+    out.flush()?;
+    out.get_ref().sync_all()?;
+    fs::rename(&tmp, dir.join(SNAPSHOT_FILE))?;
+
+    // Make the rename durable.
+    if let Ok(d) = OpenOptions::new().read(true).open(dir) {
+        d.sync_all().ok();
+    }
+    Ok(())
+}
+
+impl<G: GraphWriter> Graph for WalWriter<G> {
+    type Node = G::Node;
+    type Edge = G::Edge;
+    type NodeData = G::NodeData;
+    type EdgeData = G::EdgeData;
+    type Weight = G::Weight;
+
+    fn get_node(&self, id: &Id<G::Node>) -> Option<&G::Node> {
+        self.graph.get_node(id)
+    }
+
+    fn get_edge(&self, id: &Id<G::Edge>) -> Option<&G::Edge> {
+        self.graph.get_edge(id)
+    }
+
+    fn nodes(&self) -> Nodes<'_, G::Node> {
+        self.graph.nodes()
+    }
+
+    fn neighbors(&self, node: &Id<G::Node>) -> Nodes<'_, G::Node> {
+        self.graph.neighbors(node)
+    }
+
+    fn edges(&self, node: &Id<G::Node>) -> Edges<'_, G::Edge> {
+        self.graph.edges(node)
+    }
+
+    fn edges_directed(&self, node: &Id<G::Node>, dir: Direction) -> EdgeRefsOf<'_, G> {
+        self.graph.edges_directed(node, dir)
+    }
+
+    fn degree_by_edge_type(
+        &self,
+        node: &Id<G::Node>,
+        dir: Direction,
+    ) -> BTreeMap<EdgeTypeTag, usize> {
+        self.graph.degree_by_edge_type(node, dir)
+    }
+}
+
+impl<G> GraphWriter for WalWriter<G>
+where
+    G: GraphWriter,
+    Id<G::Node>: Clone + Serialize,
+    Id<G::Edge>: Clone + Serialize,
+    Data<G::Node>: Serialize,
+    Data<G::Edge>: Serialize,
+{
+    fn add_node(&mut self, id: Id<G::Node>, data: Data<G::Node>) {
+        self.append(&WalEntry::NodeAdded {
+            id: &id,
+            data: &data,
+        });
+        self.graph.add_node(id, data);
+        self.applied();
+    }
+
+    fn remove_node(&mut self, id: Id<G::Node>) {
+        self.append(&WalEntry::NodeDeleted(&id));
+        self.graph.remove_node(id);
+        self.applied();
+    }
+
+    fn add_edge(
+        &mut self,
+        id: Id<G::Edge>,
+        from: &Id<G::Node>,
+        to: &Id<G::Node>,
+        data: Data<G::Edge>,
+    ) {
+        self.append(&WalEntry::EdgeAdded {
+            id: &id,
+            from,
+            to,
+            data: &data,
+        });
+        self.graph.add_edge(id, from, to, data);
+        self.applied();
+    }
+
+    fn remove_edge(&mut self, id: Id<G::Edge>) {
+        self.append(&WalEntry::EdgeDeleted(&id));
+        self.graph.remove_edge(id);
+        self.applied();
+    }
+
+    fn nodes_mut(&mut self) -> NodesMut<'_, G::Node> {
+        self.needs_snapshot = true;
+        self.graph.nodes_mut()
+    }
+
+    fn edges_mut(&mut self) -> EdgesMut<'_, G::Edge> {
+        self.needs_snapshot = true;
+        self.graph.edges_mut()
+    }
+}
+
+impl<G> GraphDataWriter for WalWriter<G>
+where
+    G: GraphWriter,
+    Id<G::Node>: Clone + Serialize,
+    Id<G::Edge>: Clone + Serialize,
+    Data<G::Node>: Serialize,
+    Data<G::Edge>: Serialize,
+{
+    fn edge_data_mut(&mut self, id: &Id<G::Edge>) -> Option<&mut Data<G::Edge>> {
+        let data = self.graph.edge_data_mut(id)?;
+        self.dirty_edges.push(id.clone());
+        Some(data)
+    }
+
+    fn node_data_mut(&mut self, id: &Id<G::Node>) -> Option<&mut Data<G::Node>> {
+        let data = self.graph.node_data_mut(id)?;
+        self.dirty_nodes.push(id.clone());
+        Some(data)
+    }
+}
+
+/// Checkpoints are checked against the limits of the inner graph.
+impl<G: GraphWriter + LedgerGraph> LedgerGraph for WalWriter<G> {
+    fn check_limits(&self, checkpoint: &Checkpoint<Id<G::Node>>) -> Result<(), LimitError> {
+        self.graph.check_limits(checkpoint)
+    }
+}
@@ -0,0 +1,202 @@
+//! Persisted random walks, for incremental Monte Carlo osrank.
+//!
+//! Osrank is estimated from random walks started from every node. When the
+//! graph changes, only the walks going through the changed part of the graph
+//! need to be simulated again: a `WalkStore` keeps the walks indexed by the
+//! nodes and edges they go through, and finds them from a `GraphDiff`.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use super::types::GraphDiff;
+use super::{Edge, Graph, GraphObject, Id};
+
+/// A random walk: the nodes visited, starting with its source, and the edges
+/// followed between them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Walk<NodeId, EdgeId> {
+    /// The nodes visited, starting with the source. Never empty.
+    pub nodes: Vec<NodeId>,
+    /// The edges followed, one less than the nodes.
+    pub edges: Vec<EdgeId>,
+}
+
+impl<NodeId, EdgeId> Walk<NodeId, EdgeId> {
+    /// A walk which hasn't left its source yet.
+    pub fn new(source: NodeId) -> Self {
+        Walk {
+            nodes: vec![source],
+            edges: Vec::new(),
+        }
+    }
+
+    /// Follow an edge to the next node.
+    pub fn push(&mut self, edge: EdgeId, node: NodeId) {
+        self.edges.push(edge);
+        self.nodes.push(node);
+    }
+
+    /// The node the walk started from.
+    pub fn source(&self) -> &NodeId {
+        &self.nodes[0]
+    }
+}
+
+/// The walks of a graph.
+pub type GraphWalk<G> = Walk<Id<<G as Graph>::Node>, Id<<G as Graph>::Edge>>;
+
+/// The identifier of a walk in a `WalkStore`.
+pub type WalkId = usize;
+
+/// A set of walks, indexed by the nodes and edges they go through.
+pub struct WalkStore<G: Graph> {
+    walks: Vec<Option<GraphWalk<G>>>,
+    free: Vec<WalkId>,
+    by_source: HashMap<Id<G::Node>, HashSet<WalkId>>,
+    by_node: HashMap<Id<G::Node>, HashSet<WalkId>>,
+    by_edge: HashMap<Id<G::Edge>, HashSet<WalkId>>,
+    len: usize,
+}
+
+impl<G> Default for WalkStore<G>
+where
+    G: Graph,
+    Id<G::Node>: Clone + Eq + Hash,
+    Id<G::Edge>: Clone + Eq + Hash,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<G> WalkStore<G>
+where
+    G: Graph,
+    Id<G::Node>: Clone + Eq + Hash,
+    Id<G::Edge>: Clone + Eq + Hash,
+{
+    /// Create an empty store.
+    pub fn new() -> Self {
+        WalkStore {
+            walks: Vec::new(),
+            free: Vec::new(),
+            by_source: HashMap::new(),
+            by_node: HashMap::new(),
+            by_edge: HashMap::new(),
+            len: 0,
+        }
+    }
+
+    /// The number of walks.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no walks.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Record a walk.
+    pub fn insert(&mut self, walk: GraphWalk<G>) -> WalkId {
+        let id = self.free.pop().unwrap_or(self.walks.len());
+
+        self.by_source
+            .entry(walk.source().clone())
+            .or_default()
+            .insert(id);
+        for node in &walk.nodes {
+            self.by_node.entry(node.clone()).or_default().insert(id);
+        }
+        for edge in &walk.edges {
+            self.by_edge.entry(edge.clone()).or_default().insert(id);
+        }
+
+        if id == self.walks.len() {
+            self.walks.push(Some(walk));
+        } else {
+            self.walks[id] = Some(walk);
+        }
+        self.len += 1;
+        id
+    }
+
+    /// Get a walk.
+    pub fn get(&self, id: WalkId) -> Option<&GraphWalk<G>> {
+        self.walks.get(id).and_then(|w| w.as_ref())
+    }
+
+    /// Remove a walk.
+    pub fn remove(&mut self, id: WalkId) -> Option<GraphWalk<G>> {
+        let walk = self.walks.get_mut(id)?.take()?;
+
+        unindex(&mut self.by_source, walk.source(), id);
+        for node in &walk.nodes {
+            unindex(&mut self.by_node, node, id);
+        }
+        for edge in &walk.edges {
+            unindex(&mut self.by_edge, edge, id);
+        }
+        self.free.push(id);
+        self.len -= 1;
+
+        Some(walk)
+    }
+
+    /// The walks started from the given node.
+    pub fn walks_from(&self, source: &Id<G::Node>) -> impl Iterator<Item = WalkId> + '_ {
+        self.by_source.get(source).into_iter().flatten().copied()
+    }
+
+    /// The walks visiting the given node, including the ones started from it.
+    pub fn walks_through_node(&self, node: &Id<G::Node>) -> impl Iterator<Item = WalkId> + '_ {
+        self.by_node.get(node).into_iter().flatten().copied()
+    }
+
+    /// The walks following the given edge.
+    pub fn walks_through_edge(&self, edge: &Id<G::Edge>) -> impl Iterator<Item = WalkId> + '_ {
+        self.by_edge.get(edge).into_iter().flatten().copied()
+    }
+
+    /// The number of walks visiting the given node. Visiting a node several
+    /// times counts once.
+    pub fn visits(&self, node: &Id<G::Node>) -> usize {
+        self.by_node.get(node).map_or(0, |walks| walks.len())
+    }
+
+    /// Remove the walks affected by a change to the graph, and return them
+    /// to be simulated again from their source.
+    ///
+    /// The walks visiting a node whose outgoing edges changed are affected,
+    /// since they may have taken another path, as well as the walks visiting
+    /// an updated or deleted node. Walks from a deleted node are removed but
+    /// not returned, as they can't be simulated anymore. An added node
+    /// affects no walk: walks from it must be simulated by the caller.
+    pub fn invalidate(&mut self, diff: &GraphDiff<G>) -> Vec<GraphWalk<G>> {
+        let (node, deleted) = match diff {
+            GraphDiff::NodeAdded(_) => return Vec::new(),
+            GraphDiff::NodeDeleted(node) => (node.id(), true),
+            GraphDiff::NodeUpdated(id) => (*id, false),
+            GraphDiff::EdgeAdded { source, .. } | GraphDiff::EdgeUpdated { source, .. } => {
+                (*source, false)
+            }
+            GraphDiff::EdgeDeleted(edge) => (edge.source(), false),
+        };
+        let ids: Vec<WalkId> = self.walks_through_node(node).collect();
+
+        ids.into_iter()
+            .filter_map(|id| self.remove(id))
+            .filter(|walk| !deleted || walk.source() != node)
+            .collect()
+    }
+}
+
+/// Drop a walk from an index.
+fn unindex<K: Eq + Hash>(index: &mut HashMap<K, HashSet<WalkId>>, key: &K, id: WalkId) {
+    if let Some(walks) = index.get_mut(key) {
+        walks.remove(&id);
+        if walks.is_empty() {
+            index.remove(key);
+        }
+    }
+}
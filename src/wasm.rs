@@ -0,0 +1,70 @@
+//! JavaScript bindings, to query rank snapshots client-side, eg. from the
+//! web explorer.
+//!
+//! Snapshots are loaded from the bytes of a compact snapshot, see
+//! `snapshot::compact`. Node ids are `u64`s, ie. `BigInt`s on the JavaScript
+//! side.
+
+use wasm_bindgen::prelude::*;
+
+use super::ranking;
+use super::snapshot::compact::CompactGraph;
+use super::{Graph, GraphObject};
+
+/// A node along with its rank.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RankedNode {
+    /// The node id.
+    pub id: u64,
+    /// The rank of the node.
+    pub rank: f64,
+}
+
+/// A compact graph snapshot, loaded in memory.
+#[wasm_bindgen]
+pub struct Snapshot {
+    graph: CompactGraph<Vec<u8>>,
+}
+
+#[wasm_bindgen]
+impl Snapshot {
+    /// Load a snapshot from its bytes. Fails if the snapshot is invalid.
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: Vec<u8>) -> Result<Snapshot, JsError> {
+        let graph = CompactGraph::from_bytes(bytes)?;
+
+        Ok(Snapshot { graph })
+    }
+
+    /// The number of nodes.
+    #[wasm_bindgen(js_name = nodeCount)]
+    pub fn node_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    /// The number of edges.
+    #[wasm_bindgen(js_name = edgeCount)]
+    pub fn edge_count(&self) -> usize {
+        self.graph.edge_count()
+    }
+
+    /// The rank of a node, if it exists.
+    pub fn rank(&self, id: u64) -> Option<f64> {
+        self.graph.get_node(&id).map(|n| n.data().rank.rank)
+    }
+
+    /// The `k` nodes with the highest rank, by descending rank.
+    #[wasm_bindgen(js_name = topK)]
+    pub fn top_k(&self, k: usize) -> Vec<RankedNode> {
+        let ranks = self.graph.nodes().map(|n| (n.id(), &n.data().rank));
+
+        ranking::top_k(ranks, k)
+            .into_iter()
+            .map(|(id, rank)| RankedNode {
+                id: *id,
+                rank: rank.rank,
+            })
+            .collect()
+    }
+}
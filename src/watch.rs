@@ -0,0 +1,278 @@
+//! Subscriptions to graph mutations.
+//!
+//! A graph wrapped in `Watched` sends a `GraphEvent` to every subscriber on
+//! each mutation, so that external components, eg. a rank scheduler or a
+//! cache, can react to changes instead of polling the graph.
+
+use std::collections::BTreeMap;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Mutex;
+
+use super::ledger::{Checkpoint, LedgerGraph};
+use super::limits::LimitError;
+use super::types::EdgeTypeTag;
+use super::{
+    Data, Direction, EdgeRefsOf, Edges, EdgesMut, Graph, GraphDataWriter, GraphObject, GraphWriter,
+    Id, Nodes, NodesMut,
+};
+
+/// A mutation of a graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphEvent<NodeId, EdgeId> {
+    /// A node was added.
+    NodeAdded(NodeId),
+    /// A node was removed, along with its edges if the graph removes them.
+    NodeRemoved(NodeId),
+    /// The data of a node was handed out for modification.
+    NodeUpdated(NodeId),
+    /// An edge was added.
+    EdgeAdded {
+        /// The edge.
+        id: EdgeId,
+        /// The source node.
+        from: NodeId,
+        /// The target node.
+        to: NodeId,
+    },
+    /// An edge was removed.
+    EdgeRemoved(EdgeId),
+    /// The data of an edge was handed out for modification.
+    EdgeUpdated(EdgeId),
+}
+
+/// The events of a graph.
+pub type Event<G> = GraphEvent<Id<<G as Graph>::Node>, Id<<G as Graph>::Edge>>;
+
+/// The subscriptions to a graph.
+pub type GraphSubscription<G> = Subscription<Id<<G as Graph>::Node>, Id<<G as Graph>::Edge>>;
+
+/// A graph whose mutations can be subscribed to.
+pub trait Observable: Graph {
+    /// Subscribe to the mutations of the graph made from now on.
+    fn subscribe(&self) -> GraphSubscription<Self>;
+}
+
+/// The receiving end of a subscription.
+///
+/// Iterating blocks until the next event, and ends once the graph is
+/// dropped. Dropping the subscription unsubscribes.
+#[derive(Debug)]
+pub struct Subscription<NodeId, EdgeId> {
+    events: Receiver<GraphEvent<NodeId, EdgeId>>,
+}
+
+impl<NodeId, EdgeId> Subscription<NodeId, EdgeId> {
+    /// Return the next event if one is pending, without blocking.
+    pub fn try_next(&self) -> Option<GraphEvent<NodeId, EdgeId>> {
+        match self.events.try_recv() {
+            Ok(event) => Some(event),
+            Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => None,
+        }
+    }
+
+    /// Iterate over the pending events, without blocking.
+    pub fn pending(&self) -> impl Iterator<Item = GraphEvent<NodeId, EdgeId>> + '_ {
+        self.events.try_iter()
+    }
+}
+
+impl<NodeId, EdgeId> Iterator for Subscription<NodeId, EdgeId> {
+    type Item = GraphEvent<NodeId, EdgeId>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.events.recv().ok()
+    }
+}
+
+/// A graph wrapper sending its mutations to subscribers.
+///
+/// Data modified through `GraphDataWriter` or `nodes_mut` is reported when
+/// it is handed out, so the modification may not be visible yet when the
+/// event is received.
+pub struct Watched<G: Graph> {
+    graph: G,
+    subscribers: Mutex<Vec<Sender<Event<G>>>>,
+}
+
+impl<G: Graph> Watched<G> {
+    /// Watch the mutations of `graph`.
+    pub fn new(graph: G) -> Self {
+        Watched {
+            graph,
+            subscribers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// The wrapped graph.
+    pub fn graph(&self) -> &G {
+        &self.graph
+    }
+
+    /// Unwrap the graph, ending all subscriptions.
+    pub fn into_inner(self) -> G {
+        self.graph
+    }
+
+    /// The number of live subscriptions. Dropped subscriptions are only
+    /// noticed on the next mutation.
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.lock().map_or(0, |s| s.len())
+    }
+}
+
+impl<G> Watched<G>
+where
+    G: Graph,
+    Id<G::Node>: Clone,
+    Id<G::Edge>: Clone,
+{
+    /// Send an event to every subscriber, dropping the ones that are gone.
+    fn emit(&mut self, event: Event<G>) {
+        let subscribers = match self.subscribers.get_mut() {
+            Ok(subscribers) => subscribers,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        subscribers.retain(|s| s.send(event.clone()).is_ok());
+    }
+}
+
+impl<G: Graph> Observable for Watched<G> {
+    fn subscribe(&self) -> GraphSubscription<G> {
+        let (sender, events) = mpsc::channel();
+        match self.subscribers.lock() {
+            Ok(mut subscribers) => subscribers.push(sender),
+            Err(poisoned) => poisoned.into_inner().push(sender),
+        }
+        Subscription { events }
+    }
+}
+
+impl<G: Graph + Default> Default for Watched<G> {
+    fn default() -> Self {
+        Self::new(G::default())
+    }
+}
+
+impl<G: Graph> Graph for Watched<G> {
+    type Node = G::Node;
+    type Edge = G::Edge;
+    type NodeData = G::NodeData;
+    type EdgeData = G::EdgeData;
+    type Weight = G::Weight;
+
+    fn get_node(&self, id: &Id<G::Node>) -> Option<&G::Node> {
+        self.graph.get_node(id)
+    }
+
+    fn get_edge(&self, id: &Id<G::Edge>) -> Option<&G::Edge> {
+        self.graph.get_edge(id)
+    }
+
+    fn nodes(&self) -> Nodes<'_, G::Node> {
+        self.graph.nodes()
+    }
+
+    fn neighbors(&self, node: &Id<G::Node>) -> Nodes<'_, G::Node> {
+        self.graph.neighbors(node)
+    }
+
+    fn edges(&self, node: &Id<G::Node>) -> Edges<'_, G::Edge> {
+        self.graph.edges(node)
+    }
+
+    fn edges_directed(&self, node: &Id<G::Node>, dir: Direction) -> EdgeRefsOf<'_, G> {
+        self.graph.edges_directed(node, dir)
+    }
+
+    fn degree_by_edge_type(
+        &self,
+        node: &Id<G::Node>,
+        dir: Direction,
+    ) -> BTreeMap<EdgeTypeTag, usize> {
+        self.graph.degree_by_edge_type(node, dir)
+    }
+}
+
+impl<G> GraphWriter for Watched<G>
+where
+    G: GraphWriter,
+    Id<G::Node>: Clone,
+    Id<G::Edge>: Clone,
+{
+    fn add_node(&mut self, id: Id<G::Node>, data: Data<G::Node>) {
+        self.graph.add_node(id.clone(), data);
+        self.emit(GraphEvent::NodeAdded(id));
+    }
+
+    fn remove_node(&mut self, id: Id<G::Node>) {
+        self.graph.remove_node(id.clone());
+        self.emit(GraphEvent::NodeRemoved(id));
+    }
+
+    fn add_edge(
+        &mut self,
+        id: Id<G::Edge>,
+        from: &Id<G::Node>,
+        to: &Id<G::Node>,
+        data: Data<G::Edge>,
+    ) {
+        self.graph.add_edge(id.clone(), from, to, data);
+        self.emit(GraphEvent::EdgeAdded {
+            id,
+            from: from.clone(),
+            to: to.clone(),
+        });
+    }
+
+    fn remove_edge(&mut self, id: Id<G::Edge>) {
+        self.graph.remove_edge(id.clone());
+        self.emit(GraphEvent::EdgeRemoved(id));
+    }
+
+    fn nodes_mut(&mut self) -> NodesMut<'_, G::Node> {
+        let ids: Vec<_> = self.graph.nodes().map(|n| n.id().clone()).collect();
+        for id in ids {
+            self.emit(GraphEvent::NodeUpdated(id));
+        }
+        self.graph.nodes_mut()
+    }
+
+    fn edges_mut(&mut self) -> EdgesMut<'_, G::Edge> {
+        let ids: Vec<_> = self
+            .graph
+            .nodes()
+            .flat_map(|n| self.graph.edges_directed(n.id(), Direction::Outgoing))
+            .map(|e| e.id.clone())
+            .collect();
+        for id in ids {
+            self.emit(GraphEvent::EdgeUpdated(id));
+        }
+        self.graph.edges_mut()
+    }
+}
+
+impl<G> GraphDataWriter for Watched<G>
+where
+    G: GraphDataWriter,
+    Id<G::Node>: Clone,
+    Id<G::Edge>: Clone,
+{
+    fn edge_data_mut(&mut self, id: &Id<G::Edge>) -> Option<&mut Data<G::Edge>> {
+        self.graph.get_edge(id)?;
+        self.emit(GraphEvent::EdgeUpdated(id.clone()));
+        self.graph.edge_data_mut(id)
+    }
+
+    fn node_data_mut(&mut self, id: &Id<G::Node>) -> Option<&mut Data<G::Node>> {
+        self.graph.get_node(id)?;
+        self.emit(GraphEvent::NodeUpdated(id.clone()));
+        self.graph.node_data_mut(id)
+    }
+}
+
+/// Checkpoints are checked against the limits of the inner graph.
+impl<G: LedgerGraph> LedgerGraph for Watched<G> {
+    fn check_limits(&self, checkpoint: &Checkpoint<Id<G::Node>>) -> Result<(), LimitError> {
+        self.graph.check_limits(checkpoint)
+    }
+}
@@ -0,0 +1,178 @@
+//! Edge weights.
+
+use num_traits::Float;
+use std::collections::BTreeMap;
+
+use super::ranking::cap_and_redistribute;
+use super::types::{EdgeData, EdgeType, EdgeTypeTag, HyperParameters};
+use super::{Direction, GraphDataWriter, GraphObject, Id};
+
+/// A function computing the weight of an edge from its type.
+pub trait WeightFunction<W> {
+    /// The weight of an edge of the given type.
+    fn weight(&self, edge_type: &EdgeType, hyper: &HyperParameters<W>) -> W;
+}
+
+/// The default `WeightFunction`: contribution and membership edges are
+/// weighted by their number of contributions, and donations by their
+/// amount, multiplied by the hyperparameter of their type, while
+/// dependencies and ownership get the hyperparameter as is.
+///
+/// The resulting weights are relative: they are meant to be rescaled with
+/// `normalize_outgoing_weights` once all the edges of a node are known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ContributionWeight;
+
+impl<W: Float> WeightFunction<W> for ContributionWeight {
+    fn weight(&self, edge_type: &EdgeType, hyper: &HyperParameters<W>) -> W {
+        let param = *hyper.get_param(&edge_type.to_tag());
+
+        match edge_type {
+            EdgeType::Dependency
+            | EdgeType::OrgToProjectOwnership
+            | EdgeType::ProjectToOrgOwnership => param,
+            other => param * W::from(other.value()).unwrap_or_else(W::zero),
+        }
+    }
+}
+
+impl<W, F> WeightFunction<W> for F
+where
+    F: Fn(&EdgeType, &HyperParameters<W>) -> W,
+{
+    fn weight(&self, edge_type: &EdgeType, hyper: &HyperParameters<W>) -> W {
+        self(edge_type, hyper)
+    }
+}
+
+/// Set the weight of every edge of the graph using the given weight function.
+pub fn assign_weights<G, W, F>(graph: &mut G, function: &F, hyperparams: &HyperParameters<W>)
+where
+    G: GraphDataWriter<EdgeData = EdgeData<W>>,
+    F: WeightFunction<W>,
+    Id<G::Edge>: Clone,
+{
+    let edges: Vec<Id<G::Edge>> = graph
+        .nodes()
+        .flat_map(|n| {
+            graph
+                .edges_directed(n.id(), Direction::Outgoing)
+                .into_iter()
+                .map(|e| e.id.clone())
+        })
+        .collect();
+
+    for id in edges.iter() {
+        if let Some(data) = graph.edge_data_mut(id) {
+            data.weight = function.weight(&data.edge_type, hyperparams);
+        }
+    }
+}
+
+/// Rescale the outgoing edge weights of every node so that, for each edge
+/// type, they sum to the fraction given by `HyperParameters::edge_weights`,
+/// as required by the osrank paper. The relative weights of edges of the same
+/// type are preserved; if they are all zero, the fraction is split equally.
+///
+/// Panics if a hyperparameter is missing for one of the edge types in the
+/// graph.
+pub fn normalize_outgoing_weights<G, W>(graph: &mut G, hyperparams: &HyperParameters<W>)
+where
+    G: GraphDataWriter<EdgeData = EdgeData<W>>,
+    W: Float,
+    Id<G::Edge>: Clone,
+{
+    let nodes: Vec<Vec<Id<G::Edge>>> = graph
+        .nodes()
+        .map(|n| {
+            graph
+                .edges_directed(n.id(), Direction::Outgoing)
+                .into_iter()
+                .map(|e| e.id.clone())
+                .collect()
+        })
+        .collect();
+
+    for edges in nodes {
+        let mut groups: BTreeMap<EdgeTypeTag, (W, usize)> = BTreeMap::new();
+
+        for id in edges.iter() {
+            if let Some(data) = graph.edge_data(id) {
+                let group = groups
+                    .entry(data.edge_type.to_tag())
+                    .or_insert((W::zero(), 0));
+                group.0 = group.0 + data.weight;
+                group.1 += 1;
+            }
+        }
+
+        for id in edges.iter() {
+            if let Some(data) = graph.edge_data_mut(id) {
+                let tag = data.edge_type.to_tag();
+                let (total, count) = groups[&tag];
+                let fraction = *hyperparams.get_param(&tag);
+
+                data.weight = if total.is_zero() {
+                    fraction / W::from(count).unwrap_or_else(W::one)
+                } else {
+                    fraction * data.weight / total
+                };
+            }
+        }
+    }
+}
+
+/// Cap the weight of every outgoing edge of a node at
+/// `HyperParameters::max_edge_weight_share` of the node's total outgoing
+/// weight, if set, redistributing the excess to the node's other edges as
+/// `ranking::cap_and_redistribute` does. This keeps a single edge, eg. to a
+/// fake account, from capturing most of the rank flowing out of a node.
+///
+/// Nodes with too few edges of positive weight to meet the cap, fewer than
+/// `1 / max_edge_weight_share`, are skipped and keep their weights: the
+/// excess would have nowhere to go, and capping them would lose some of
+/// their outgoing weight. With a share of `0.5`, a node with a single
+/// outgoing edge keeps all of its weight on it.
+///
+/// Meant to run after `normalize_outgoing_weights`.
+pub fn cap_edge_weight_shares<G, W>(graph: &mut G, hyperparams: &HyperParameters<W>)
+where
+    G: GraphDataWriter<EdgeData = EdgeData<W>>,
+    W: Float,
+    Id<G::Edge>: Clone,
+{
+    let share = match hyperparams.max_edge_weight_share {
+        Some(share) => share,
+        None => return,
+    };
+    let nodes: Vec<Vec<Id<G::Edge>>> = graph
+        .nodes()
+        .map(|n| {
+            graph
+                .edges_directed(n.id(), Direction::Outgoing)
+                .into_iter()
+                .map(|e| e.id.clone())
+                .collect()
+        })
+        .collect();
+
+    for edges in nodes {
+        let mut weights: Vec<W> = edges
+            .iter()
+            .map(|id| graph.edge_data(id).map_or_else(W::zero, |d| d.weight))
+            .collect();
+        let total = weights.iter().fold(W::zero(), |total, w| total + *w);
+        let receivers = weights.iter().filter(|w| **w > W::zero()).count();
+        if share * W::from(receivers).unwrap_or_else(W::zero) < W::one() {
+            continue;
+        }
+
+        cap_and_redistribute(&mut weights, share * total, |_| true);
+
+        for (id, weight) in edges.iter().zip(weights) {
+            if let Some(data) = graph.edge_data_mut(id) {
+                data.weight = weight;
+            }
+        }
+    }
+}
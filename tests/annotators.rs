@@ -0,0 +1,285 @@
+use std::collections::BTreeMap;
+
+use oscoin_graph_api::annotators::{
+    BufferedAnnotator, MergeAnnotations, ShardedAnnotator, Transaction,
+};
+use oscoin_graph_api::bench::Workload;
+use oscoin_graph_api::cancel::CancellationToken;
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::types::AlgorithmError;
+use oscoin_graph_api::{
+    Graph, GraphAlgorithm, GraphAnnotator, GraphObject, TransactionalAnnotator,
+};
+
+/// An algorithm annotating every node with its id, then failing if there are
+/// more than `max_nodes` of them, and giving up once cancelled.
+struct AnnotateIds {
+    max_nodes: usize,
+}
+
+impl<A> GraphAlgorithm<EdgeListGraph, A> for AnnotateIds
+where
+    A: GraphAnnotator<Annotation = u64>,
+{
+    type Context = ();
+    type Output = usize;
+    type Error = AlgorithmError;
+    type RngSeed = u64;
+    type Annotation = u64;
+
+    fn execute(
+        &self,
+        context: &mut (),
+        graph: &EdgeListGraph,
+        annotator: &mut A,
+        seed: u64,
+    ) -> Result<usize, AlgorithmError> {
+        self.execute_with(context, graph, annotator, seed, &CancellationToken::new())
+    }
+
+    fn execute_with(
+        &self,
+        _context: &mut (),
+        graph: &EdgeListGraph,
+        annotator: &mut A,
+        _seed: u64,
+        token: &CancellationToken,
+    ) -> Result<usize, AlgorithmError> {
+        let mut annotated = 0;
+        for node in graph.nodes() {
+            annotator.annotate_graph(*node.id());
+            annotated += 1;
+            if token.is_cancelled() {
+                return Err(AlgorithmError::Cancelled);
+            }
+        }
+        if annotated > self.max_nodes {
+            return Err(AlgorithmError::InvariantViolated("too many nodes".into()));
+        }
+        Ok(annotated)
+    }
+}
+
+#[test]
+fn transactions_buffer_annotations_until_committed() {
+    let mut annotator = Transaction::new(Vec::new());
+    assert!(!annotator.is_active());
+    annotator.annotate_graph(1);
+    assert_eq!(annotator.inner(), &vec![1]);
+
+    annotator.begin();
+    assert!(annotator.is_active());
+    annotator.annotate_graph(2);
+    annotator.annotate_graph(3);
+    assert_eq!(annotator.pending(), 2);
+    assert_eq!(annotator.inner(), &vec![1]);
+
+    // Transactions don't nest.
+    annotator.begin();
+    assert_eq!(annotator.pending(), 2);
+
+    annotator.commit();
+    assert!(!annotator.is_active());
+    assert_eq!(annotator.pending(), 0);
+    assert_eq!(annotator.inner(), &vec![1, 2, 3]);
+
+    annotator.begin();
+    annotator.annotate_graph(4);
+    annotator.rollback();
+    assert!(!annotator.is_active());
+    assert_eq!(annotator.pending(), 0);
+    annotator.annotate_graph(5);
+    assert_eq!(annotator.into_inner(), vec![1, 2, 3, 5]);
+
+    // Pending annotations aren't flushed, nor returned.
+    let mut annotator: Transaction<Vec<u64>> = Transaction::default();
+    annotator.begin();
+    annotator.annotate_graph(6);
+    assert_eq!(annotator.flush(), Ok(()));
+    assert!(annotator.into_inner().is_empty());
+}
+
+#[test]
+fn atomic_executions_leave_no_partial_annotations() {
+    let graph: EdgeListGraph = Workload::random(10, 2, 3).build();
+    let token = CancellationToken::new();
+    let mut annotator = Transaction::new(vec![99]);
+
+    let result =
+        AnnotateIds { max_nodes: 5 }.execute_atomically(&mut (), &graph, &mut annotator, 0, &token);
+    assert_eq!(
+        result,
+        Err(AlgorithmError::InvariantViolated("too many nodes".into()))
+    );
+    assert!(!annotator.is_active());
+    assert_eq!(annotator.inner(), &vec![99]);
+
+    let result = AnnotateIds { max_nodes: 10 }.execute_atomically(
+        &mut (),
+        &graph,
+        &mut annotator,
+        0,
+        &token,
+    );
+    assert_eq!(result, Ok(10));
+    assert!(!annotator.is_active());
+    let mut annotations = annotator.inner().clone();
+    annotations.sort_unstable();
+    assert_eq!(annotations, vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 99]);
+
+    token.cancel();
+    let result = AnnotateIds { max_nodes: 10 }.execute_atomically(
+        &mut (),
+        &graph,
+        &mut annotator,
+        0,
+        &token,
+    );
+    assert_eq!(result, Err(AlgorithmError::Cancelled));
+    assert_eq!(annotator.inner().len(), 11);
+    assert_eq!(annotator.pending(), 0);
+}
+
+/// An annotator taking annotations in batches, counting its flushes, whose
+/// flushes fail once `failing`.
+#[derive(Debug, Default)]
+struct Batches {
+    batches: Vec<Vec<u64>>,
+    flushes: usize,
+    failing: bool,
+}
+
+impl GraphAnnotator for Batches {
+    type Annotation = Vec<u64>;
+    type Error = &'static str;
+
+    fn annotate_graph(&mut self, batch: Vec<u64>) {
+        self.batches.push(batch);
+    }
+
+    fn flush(&mut self) -> Result<(), &'static str> {
+        if self.failing {
+            return Err("disk full");
+        }
+        self.flushes += 1;
+        Ok(())
+    }
+}
+
+#[test]
+fn buffered_annotations_are_written_in_chunks() {
+    let mut annotator = BufferedAnnotator::with_chunk_size(Batches::default(), 3);
+    for id in 0..7 {
+        annotator.annotate_graph(id);
+    }
+    assert_eq!(
+        annotator.inner().batches,
+        vec![vec![0, 1, 2], vec![3, 4, 5]]
+    );
+    assert_eq!(annotator.buffered(), 1);
+    assert_eq!(annotator.inner().flushes, 0);
+
+    assert_eq!(annotator.flush(), Ok(()));
+    assert_eq!(annotator.buffered(), 0);
+    assert_eq!(annotator.inner().batches.len(), 3);
+    assert_eq!(annotator.inner().flushes, 1);
+
+    // Nothing buffered, no empty batch.
+    assert_eq!(annotator.flush(), Ok(()));
+    assert_eq!(annotator.inner().batches.len(), 3);
+    assert_eq!(annotator.inner().flushes, 2);
+
+    annotator.annotate_graph(7);
+    let inner = annotator.into_inner().unwrap();
+    assert_eq!(inner.batches.concat(), (0..8).collect::<Vec<_>>());
+    assert_eq!(inner.flushes, 3);
+}
+
+#[test]
+fn buffered_chunks_hold_at_least_one_annotation() {
+    let mut annotator = BufferedAnnotator::with_chunk_size(Batches::default(), 0);
+    annotator.annotate_graph(1);
+    annotator.annotate_graph(2);
+    assert_eq!(annotator.inner().batches, vec![vec![1], vec![2]]);
+    assert_eq!(annotator.buffered(), 0);
+
+    let mut annotator = BufferedAnnotator::new(Batches::default());
+    for id in 0..BufferedAnnotator::<Batches, u64>::DEFAULT_CHUNK_SIZE as u64 - 1 {
+        annotator.annotate_graph(id);
+    }
+    assert!(annotator.inner().batches.is_empty());
+    annotator.annotate_graph(0);
+    assert_eq!(annotator.inner().batches.len(), 1);
+}
+
+#[test]
+fn flush_failures_are_reported() {
+    let failing = Batches {
+        failing: true,
+        ..Batches::default()
+    };
+    let mut annotator = BufferedAnnotator::with_chunk_size(failing, 4);
+    annotator.annotate_graph(1);
+    assert_eq!(annotator.flush(), Err("disk full"));
+    // The batch was handed over before the inner annotator failed.
+    assert_eq!(annotator.inner().batches, vec![vec![1]]);
+    assert_eq!(annotator.into_inner().unwrap_err(), "disk full");
+
+    // Transactions flush their inner annotator.
+    let mut transaction = Transaction::new(Batches {
+        failing: true,
+        ..Batches::default()
+    });
+    assert_eq!(transaction.flush(), Err("disk full"));
+}
+
+#[test]
+fn annotations_are_merged() {
+    let mut visits: BTreeMap<u64, u64> = vec![(1, 2), (2, 1)].into_iter().collect();
+    // Maps may get a `merge` method of their own in `std`.
+    MergeAnnotations::merge(&mut visits, vec![(2, 3), (5, 1)].into_iter().collect());
+    assert_eq!(
+        visits.into_iter().collect::<Vec<_>>(),
+        vec![(1, 2), (2, 4), (5, 1)]
+    );
+
+    let mut paths: BTreeMap<u64, Vec<u64>> = BTreeMap::new();
+    paths.insert(1, vec![1, 2]);
+    let mut other = BTreeMap::new();
+    other.insert(1, vec![3]);
+    other.insert(4, vec![4]);
+    MergeAnnotations::merge(&mut paths, other);
+    assert_eq!(paths[&1], vec![1, 2, 3]);
+    assert_eq!(paths[&4], vec![4]);
+
+    let mut total = 0.5f64;
+    total.merge(0.25);
+    assert_eq!(total, 0.75);
+}
+
+#[test]
+fn shards_are_merged_in_order() {
+    let mut sharded = ShardedAnnotator::<Vec<u64>>::new(4);
+    assert_eq!(sharded.shards().len(), 4);
+    std::thread::scope(|s| {
+        for (i, shard) in sharded.shards_mut().iter_mut().enumerate() {
+            s.spawn(move || {
+                // Later shards finish first.
+                std::thread::sleep(std::time::Duration::from_millis(10 * (4 - i as u64)));
+                for n in 0..3 {
+                    shard.annotate_graph(10 * i as u64 + n);
+                }
+            });
+        }
+    });
+    let mut annotator: Vec<Vec<u64>> = Vec::new();
+    sharded.finish(&mut annotator);
+    assert_eq!(
+        annotator,
+        vec![vec![0, 1, 2, 10, 11, 12, 20, 21, 22, 30, 31, 32]]
+    );
+
+    let sharded = ShardedAnnotator::<BTreeMap<u64, u64>>::new(0);
+    assert_eq!(sharded.shards().len(), 1);
+    assert!(sharded.merge().is_empty());
+}
@@ -0,0 +1,100 @@
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+use oscoin_graph_api::algorithms::prune::PruneContext;
+use oscoin_graph_api::algorithms::Prune;
+use oscoin_graph_api::asynchronous::{AsyncGraphAlgorithm, BlockingAnnotator};
+use oscoin_graph_api::bench::Workload;
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::types::{HyperParameters, NodeRank};
+use oscoin_graph_api::GraphAlgorithm;
+
+/// Poll a future to completion, returning its output and the number of
+/// times it yielded.
+fn block_on<F: Future>(future: F) -> (F::Output, usize) {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut yields = 0;
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return (output, yields),
+            Poll::Pending => yields += 1,
+        }
+    }
+}
+
+/// Check that the same nodes are annotated with the same ranks, up to
+/// rounding: ranks are summed in hash map order.
+fn assert_same_ranks(mut a: Vec<(u64, NodeRank<f64>)>, mut b: Vec<(u64, NodeRank<f64>)>) {
+    a.sort_by_key(|(id, _)| *id);
+    b.sort_by_key(|(id, _)| *id);
+    assert_eq!(a.len(), b.len());
+    for ((id, x), (other, y)) in a.iter().zip(&b) {
+        assert_eq!(id, other);
+        assert!(
+            (x.rank - y.rank).abs() < 1e-12,
+            "{}: {:?} != {:?}",
+            id,
+            x,
+            y
+        );
+    }
+}
+
+#[test]
+fn prune_yields_between_steps() {
+    let graph: EdgeListGraph = Workload::random(200, 3, 1).build();
+    let mut hyperparams = HyperParameters::osrank_paper_defaults();
+    hyperparams.pruning_threshold = 0.004;
+    let prune = Prune::new(hyperparams).emit_subgraph();
+
+    let mut expected = Vec::new();
+    let output = GraphAlgorithm::execute(
+        &prune,
+        &mut PruneContext::default(),
+        &graph,
+        &mut expected,
+        0,
+    )
+    .unwrap();
+
+    let mut annotator = BlockingAnnotator(Vec::new());
+    let mut context = PruneContext::default();
+    let (result, yields) = block_on(AsyncGraphAlgorithm::execute(
+        &prune,
+        &mut context,
+        &graph,
+        &mut annotator,
+        0,
+    ));
+    assert_eq!(result.unwrap(), output);
+    assert_eq!(yields, prune.steps);
+    assert!(!annotator.0.is_empty());
+    assert_same_ranks(annotator.0, expected);
+    assert_eq!(context.ranks.len(), 200);
+}
+
+#[cfg(feature = "linalg")]
+#[test]
+fn power_iteration_yields_between_iterations() {
+    use oscoin_graph_api::linalg::PowerIteration;
+
+    let graph: EdgeListGraph = Workload::random(200, 3, 2).build();
+    let power = PowerIteration::new(HyperParameters::osrank_paper_defaults());
+
+    let mut expected = Vec::new();
+    let output = GraphAlgorithm::execute(&power, &mut (), &graph, &mut expected, 0).unwrap();
+
+    let mut annotator = BlockingAnnotator(Vec::new());
+    let (result, yields) = block_on(AsyncGraphAlgorithm::execute(
+        &power,
+        &mut (),
+        &graph,
+        &mut annotator,
+        0,
+    ));
+    assert_eq!(result.unwrap(), output);
+    assert!(yields > 0 && yields < power.max_iterations);
+    assert_same_ranks(annotator.0, expected);
+}
@@ -0,0 +1,96 @@
+use oscoin_graph_api::bench::{Workload, LOOKUPS, SIZES};
+use oscoin_graph_api::mem::{ArenaGraph, EdgeListGraph};
+use oscoin_graph_api::snapshot::compact::{self, CompactGraph};
+use oscoin_graph_api::types::{EdgeType, NodeType};
+use oscoin_graph_api::{Graph, GraphObject};
+
+/// The results of every workload over `graph`.
+fn run<G>(workload: &Workload, graph: &G) -> [usize; 4]
+where
+    G: Graph<Weight = f64>,
+    G::Node: GraphObject<Id = u64>,
+    G::Edge: GraphObject<Id = u64>,
+{
+    [
+        workload.neighbors(graph),
+        workload.edges_directed(graph),
+        workload.subgraphs(graph),
+        workload.rank(graph),
+    ]
+}
+
+#[test]
+fn workloads_are_generated_from_their_seed() {
+    let workload = Workload::random(50, 3, 7);
+    assert_eq!(workload.nodes.len(), 50);
+    assert_eq!(workload.edges.len(), 150);
+    assert_eq!(workload.lookups.len(), 50.min(LOOKUPS));
+    assert!(workload.lookups.iter().all(|id| *id < 50));
+    for (i, (id, data)) in workload.nodes.iter().enumerate() {
+        assert_eq!(*id, i as u64);
+        let is_user = matches!(data.node_type, NodeType::User { .. });
+        assert_eq!(is_user, id % 10 == 0);
+    }
+    for (i, (id, from, to, data)) in workload.edges.iter().enumerate() {
+        assert_eq!(*id, i as u64);
+        assert_eq!(*from, i as u64 / 3);
+        assert!(*to < 50);
+        assert!((0.0..1.0).contains(&data.weight));
+        match data.edge_type {
+            EdgeType::UserToProjectContribution(n) => {
+                assert_eq!(from % 10, 0);
+                assert!((1..=10).contains(&n));
+            }
+            EdgeType::Dependency => assert_ne!(from % 10, 0),
+            ref other => panic!("unexpected edge type {:?}", other),
+        }
+    }
+
+    let again = Workload::random(50, 3, 7);
+    assert_eq!(again.edges, workload.edges);
+    assert_eq!(again.lookups, workload.lookups);
+    assert_ne!(Workload::random(50, 3, 8).edges, workload.edges);
+
+    let large = Workload::random(SIZES[1], Workload::DEFAULT_DEGREE, 0);
+    assert_eq!(large.lookups.len(), LOOKUPS);
+    assert_eq!(large.edges.len(), SIZES[1] * Workload::DEFAULT_DEGREE);
+
+    let empty = Workload::random(0, 3, 7);
+    assert!(empty.nodes.is_empty() && empty.edges.is_empty() && empty.lookups.is_empty());
+}
+
+#[test]
+fn backends_give_the_same_results() {
+    let workload = Workload::random(200, 4, 11);
+    let graph: EdgeListGraph = workload.build();
+    let results = run(&workload, &graph);
+    assert!(results.iter().all(|r| *r > 0));
+    assert_eq!(results[3], 200);
+
+    let arena: ArenaGraph = workload.build();
+    assert_eq!(run(&workload, &arena), results);
+
+    let mut bytes = Vec::new();
+    compact::write(&graph, &mut bytes).unwrap();
+    let snapshot = CompactGraph::from_bytes(bytes).unwrap();
+    assert_eq!(run(&workload, &snapshot), results);
+
+    // Every edge is listed from both of its endpoints.
+    let expected: usize = workload
+        .lookups
+        .iter()
+        .map(|id| {
+            workload
+                .edges
+                .iter()
+                .filter(|(_, from, _, _)| from == id)
+                .count()
+                + workload
+                    .edges
+                    .iter()
+                    .filter(|(_, _, to, _)| to == id)
+                    .count()
+        })
+        .sum();
+    assert_eq!(results[1], expected);
+}
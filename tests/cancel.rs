@@ -0,0 +1,88 @@
+use oscoin_graph_api::algorithms::prune::PruneContext;
+use oscoin_graph_api::algorithms::Prune;
+use oscoin_graph_api::bench::Workload;
+use oscoin_graph_api::cancel::{CancellationToken, Cancelled};
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::types::{AlgorithmError, HyperParameters, NodeRank};
+use oscoin_graph_api::{Graph, GraphAlgorithm, GraphAnnotator, GraphObject};
+
+/// An algorithm annotating every node with its id, unaware of cancellation.
+struct CountNodes;
+
+impl<A> GraphAlgorithm<EdgeListGraph, A> for CountNodes
+where
+    A: GraphAnnotator<Annotation = u64>,
+{
+    type Context = ();
+    type Output = usize;
+    type Error = AlgorithmError;
+    type RngSeed = u64;
+    type Annotation = u64;
+
+    fn execute(
+        &self,
+        _context: &mut (),
+        graph: &EdgeListGraph,
+        annotator: &mut A,
+        _seed: u64,
+    ) -> Result<usize, AlgorithmError> {
+        let ids: Vec<u64> = graph.nodes().map(|n| *n.id()).collect();
+        for id in &ids {
+            annotator.annotate_graph(*id);
+        }
+        Ok(ids.len())
+    }
+}
+
+#[test]
+fn clones_of_a_token_share_its_state() {
+    let token = CancellationToken::new();
+    let clone = token.clone();
+    assert!(!clone.is_cancelled());
+    assert_eq!(clone.check(), Ok(()));
+
+    token.cancel();
+    assert!(clone.is_cancelled());
+    assert_eq!(clone.check(), Err(Cancelled));
+    assert_eq!(
+        AlgorithmError::from(Cancelled).to_string(),
+        "execution was cancelled"
+    );
+}
+
+#[test]
+fn cancelled_executions_stop_without_annotating() {
+    let graph: EdgeListGraph = Workload::random(100, 3, 2).build();
+    let mut hyperparams = HyperParameters::osrank_paper_defaults();
+    hyperparams.pruning_threshold = 0.01;
+    let prune = Prune::new(hyperparams);
+
+    let token = CancellationToken::new();
+    let mut context = PruneContext::default();
+    let mut annotations: Vec<(u64, NodeRank<f64>)> = Vec::new();
+    let output = prune
+        .execute_with(&mut context, &graph, &mut annotations, 0, &token)
+        .unwrap();
+    assert!(!output.pruned.is_empty());
+    assert_eq!(annotations.len(), output.pruned.len());
+
+    token.cancel();
+    let mut context = PruneContext::default();
+    let mut annotations: Vec<(u64, NodeRank<f64>)> = Vec::new();
+    let result = prune.execute_with(&mut context, &graph, &mut annotations, 0, &token);
+    assert_eq!(result, Err(AlgorithmError::Cancelled));
+    assert!(annotations.is_empty());
+    assert!(context.ranks.is_empty());
+}
+
+#[test]
+fn algorithms_ignore_the_token_by_default() {
+    let graph: EdgeListGraph = Workload::random(20, 2, 2).build();
+    let token = CancellationToken::new();
+    token.cancel();
+
+    let mut annotations = Vec::new();
+    let result = CountNodes.execute_with(&mut (), &graph, &mut annotations, 0, &token);
+    assert_eq!(result, Ok(20));
+    assert_eq!(annotations.len(), 20);
+}
@@ -0,0 +1,106 @@
+use oscoin_graph_api::bench::Workload;
+use oscoin_graph_api::canonical::{canonicalize, Canonical, CanonicalEdge};
+use oscoin_graph_api::ledger::LedgerEdgeData;
+use oscoin_graph_api::mem::{ArenaGraph, EdgeListGraph};
+use oscoin_graph_api::snapshot::compact::{self, CompactGraph};
+use oscoin_graph_api::types::{EdgeData, EdgeType, Timestamps};
+use oscoin_graph_api::{GraphDataWriter, GraphWriter};
+
+mod common;
+
+use common::{dependency, project};
+
+#[test]
+fn floats_have_one_zero_and_one_nan() {
+    assert_eq!((-0.0f64).canonical().to_bits(), 0.0f64.to_bits());
+    assert_eq!((-0.0f32).canonical().to_bits(), 0.0f32.to_bits());
+    let nan = f64::from_bits(f64::NAN.to_bits() | 1);
+    assert_ne!(nan.to_bits(), f64::NAN.to_bits());
+    assert_eq!(nan.canonical().to_bits(), f64::NAN.to_bits());
+    assert_eq!((-f64::NAN).canonical().to_bits(), f64::NAN.to_bits());
+    assert_eq!(1.5f64.canonical(), 1.5);
+    assert_eq!((-3i32).canonical(), -3);
+}
+
+#[test]
+fn canonical_data_has_no_timestamps() {
+    let mut node = project();
+    node.rank.rank = -0.0;
+    node.timestamps = Timestamps::created(3);
+    let node = node.canonical();
+    assert_eq!(node.timestamps, Timestamps::default());
+    assert_eq!(node.rank.rank.to_bits(), 0.0f64.to_bits());
+    assert_eq!(node.node_type, project().node_type);
+
+    let mut edge = EdgeData::from_edge_type(EdgeType::Donation(5));
+    edge.weight = 0.25;
+    edge.timestamps = Timestamps::created(3);
+    edge.history.append(2, 5);
+    let canonical = edge.canonical();
+    assert_eq!(canonical.timestamps, Timestamps::default());
+    assert_eq!(canonical.edge_type, EdgeType::Donation(5));
+    assert_eq!(canonical.weight, 0.25);
+    assert_eq!(canonical.history, edge.history);
+}
+
+#[test]
+fn backends_have_the_same_canonical_form() {
+    let workload = Workload::random(40, 3, 8);
+    let graph: EdgeListGraph = workload.build();
+    let form = canonicalize(&graph);
+
+    let ids: Vec<u64> = form.nodes.iter().map(|(id, _)| *id).collect();
+    assert!(ids.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(ids.len(), graph.node_count());
+    let ids: Vec<u64> = form.edges.iter().map(|e| e.id).collect();
+    assert!(ids.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(ids.len(), graph.edge_count());
+
+    let arena: ArenaGraph = workload.build();
+    assert_eq!(canonicalize(&arena), form);
+
+    let mut bytes = Vec::new();
+    compact::write(&graph, &mut bytes).unwrap();
+    assert_eq!(
+        canonicalize(&CompactGraph::from_bytes(bytes).unwrap()),
+        form
+    );
+
+    // Timestamps and signed zeros don't matter.
+    let mut stamped: EdgeListGraph = EdgeListGraph::new();
+    stamped.set_epoch(9);
+    workload.insert(&mut stamped);
+    let (node, data) = &form.nodes[0];
+    assert_eq!(data.rank.rank.to_bits(), 0.0f64.to_bits());
+    stamped.node_data_mut(node).unwrap().rank.rank = -0.0;
+    let canonical = canonicalize(&stamped);
+    assert_eq!(canonical, form);
+    assert_eq!(canonical.nodes[0].1.rank.rank.to_bits(), 0.0f64.to_bits());
+
+    // Anything else does.
+    stamped.edge_data_mut(&0).unwrap().weight += 1.0;
+    assert_ne!(canonicalize(&stamped), form);
+}
+
+#[test]
+fn edges_keep_their_endpoints() {
+    let mut graph = EdgeListGraph::new();
+    for id in [3, 1, 2] {
+        graph.add_node(id, project());
+    }
+    graph.add_edge(5, &3, &1, dependency());
+    graph.add_edge(2, &1, &2, dependency());
+    graph.add_edge(7, &2, &2, dependency());
+
+    let form = canonicalize(&graph);
+    let edges: Vec<(u64, u64, u64)> = form
+        .edges
+        .iter()
+        .map(|e: &CanonicalEdge<_, _, _>| (e.id, e.source, e.target))
+        .collect();
+    assert_eq!(edges, vec![(2, 1, 2), (5, 3, 1), (7, 2, 2)]);
+    assert_eq!(
+        form.nodes.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![1, 2, 3]
+    );
+}
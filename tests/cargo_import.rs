@@ -0,0 +1,125 @@
+#![cfg(feature = "cargo-importer")]
+
+use oscoin_graph_api::importers::cargo::{import_crates_io_dump, import_metadata, CargoOptions};
+use oscoin_graph_api::importers::{ImportError, ImportMapping, SipMapping};
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::stats::NodeKind;
+use oscoin_graph_api::GraphExt;
+
+const METADATA: &str = r#"{
+    "packages": [
+        {"name": "app", "dependencies": [
+            {"name": "serde", "kind": null},
+            {"name": "rand", "kind": "dev"},
+            {"name": "cc", "kind": "build"},
+            {"name": "openssl", "kind": null, "optional": true}
+        ]},
+        {"name": "serde", "dependencies": []},
+        {"name": "serde", "dependencies": [{"name": "cc", "kind": "normal"}]},
+        {"name": "rand", "dependencies": []},
+        {"name": "cc", "dependencies": []}
+    ]
+}"#;
+
+fn id(name: &str) -> u64 {
+    ImportMapping::<EdgeListGraph>::node_id(&mut SipMapping::default(), NodeKind::Project, name)
+}
+
+fn depends(graph: &EdgeListGraph, from: &str, to: &str) -> bool {
+    graph.contains_edge_between(&id(from), &id(to))
+}
+
+#[test]
+fn metadata_is_imported_per_crate() {
+    let mut graph = EdgeListGraph::new();
+    let summary = import_metadata(
+        &mut graph,
+        &mut SipMapping::default(),
+        METADATA.as_bytes(),
+        &CargoOptions::default(),
+    )
+    .unwrap();
+
+    // Both versions of serde are merged, and openssl isn't a package.
+    assert_eq!(summary.nodes, 4);
+    assert_eq!(summary.edges, 2);
+    assert!(depends(&graph, "app", "serde"));
+    assert!(depends(&graph, "serde", "cc"));
+    assert!(!depends(&graph, "app", "rand"));
+    assert!(!depends(&graph, "app", "cc"));
+}
+
+#[test]
+fn dev_and_build_dependencies_are_optional() {
+    let mut graph = EdgeListGraph::new();
+    let options = CargoOptions {
+        dev_dependencies: true,
+        build_dependencies: true,
+    };
+    let summary = import_metadata(
+        &mut graph,
+        &mut SipMapping::default(),
+        METADATA.as_bytes(),
+        &options,
+    )
+    .unwrap();
+
+    assert_eq!(summary.edges, 4);
+    assert!(depends(&graph, "app", "rand"));
+    assert!(depends(&graph, "app", "cc"));
+}
+
+#[test]
+fn crates_io_dumps_are_imported() {
+    let crates = "id,name,description\n1,app,\"An app,\nover two lines\"\n2,serde,\n3,cc,\n";
+    let versions = "id,crate_id,num\n10,1,0.1.0\n11,1,0.2.0\n20,2,1.0.0\n";
+    let dependencies = "id,version_id,crate_id,kind\n1,10,2,0\n2,11,2,0\n3,11,3,1\n4,20,3,2\n";
+
+    let mut graph = EdgeListGraph::new();
+    let summary = import_crates_io_dump(
+        &mut graph,
+        &mut SipMapping::default(),
+        crates.as_bytes(),
+        versions.as_bytes(),
+        dependencies.as_bytes(),
+        &CargoOptions::default(),
+    )
+    .unwrap();
+
+    // The dependency of both versions of app on serde is imported once.
+    assert_eq!(summary.nodes, 3);
+    assert_eq!(summary.edges, 1);
+    assert!(depends(&graph, "app", "serde"));
+}
+
+#[test]
+fn malformed_dumps_are_rejected() {
+    let import = |dependencies: &str| {
+        import_crates_io_dump(
+            &mut EdgeListGraph::<u64, f64>::new(),
+            &mut SipMapping::default(),
+            "id,name\n1,app\n".as_bytes(),
+            "id,crate_id\n10,1\n".as_bytes(),
+            dependencies.as_bytes(),
+            &CargoOptions::default(),
+        )
+        .unwrap_err()
+    };
+
+    let parse_error = |e: ImportError| match e {
+        ImportError::Parse(msg) => msg,
+        other => panic!("unexpected error: {}", other),
+    };
+    assert_eq!(
+        parse_error(import("version_id,crate_id\n10,1\n")),
+        "missing `kind` column"
+    );
+    assert_eq!(
+        parse_error(import("version_id,crate_id,kind\n10,1,7\n")),
+        "unknown dependency kind `7`"
+    );
+    assert_eq!(
+        parse_error(import("version_id,crate_id,kind\n99,1,0\n")),
+        "unknown version `99`"
+    );
+}
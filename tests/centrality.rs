@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+
+use oscoin_graph_api::algorithms::{Betweenness, DegreeCentrality};
+use oscoin_graph_api::cancel::CancellationToken;
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::types::{AlgorithmError, NodeRank};
+use oscoin_graph_api::{Direction, GraphAlgorithm};
+
+mod common;
+
+use common::{dependencies, project};
+
+/// A diamond `1 -> {2, 3} -> 4`, followed by `4 -> 5`.
+fn graph() -> EdgeListGraph {
+    let edges = dependencies(&[(1, 2), (1, 3), (2, 4), (3, 4), (4, 5)]);
+    EdgeListGraph::from_edges(edges, project())
+}
+
+/// Run the algorithm, checking that it annotates every node with its score,
+/// in id order.
+fn scores<C>(centrality: C) -> Vec<(u64, f64)>
+where
+    C: GraphAlgorithm<
+        EdgeListGraph,
+        Vec<(u64, NodeRank<f64>)>,
+        Context = (),
+        Output = HashMap<u64, f64>,
+        Error = AlgorithmError,
+        RngSeed = u64,
+        Annotation = (u64, NodeRank<f64>),
+    >,
+{
+    let mut annotations = Vec::new();
+    let scores = centrality
+        .execute(&mut (), &graph(), &mut annotations, 0)
+        .unwrap();
+    assert_eq!(annotations.len(), scores.len());
+    let order: Vec<u64> = annotations.iter().map(|(id, _)| *id).collect();
+    assert_eq!(order, vec![1, 2, 3, 4, 5]);
+    for (id, rank) in &annotations {
+        assert_eq!(rank.rank, scores[id]);
+    }
+    let mut scores: Vec<(u64, f64)> = scores.into_iter().collect();
+    scores.sort_by_key(|(id, _)| *id);
+    scores
+}
+
+fn assert_scores(actual: Vec<(u64, f64)>, expected: &[f64]) {
+    let ids: Vec<u64> = actual.iter().map(|(id, _)| *id).collect();
+    assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+    for ((id, score), expected) in actual.iter().zip(expected) {
+        assert!((score - expected).abs() < 1e-9, "{}: {}", id, score);
+    }
+}
+
+#[test]
+fn betweenness_splits_between_shortest_paths() {
+    assert_scores(scores(Betweenness::default()), &[0.0, 1.0, 1.0, 3.0, 0.0]);
+    assert_scores(
+        scores(Betweenness { normalized: true }),
+        &[0.0, 1.0 / 12.0, 1.0 / 12.0, 3.0 / 12.0, 0.0],
+    );
+}
+
+#[test]
+fn betweenness_can_be_cancelled() {
+    let token = CancellationToken::new();
+    token.cancel();
+    let mut annotations: Vec<(u64, NodeRank<f64>)> = Vec::new();
+    let result =
+        Betweenness::default().execute_with(&mut (), &graph(), &mut annotations, 0, &token);
+    assert_eq!(result, Err(AlgorithmError::Cancelled));
+    assert!(annotations.is_empty());
+}
+
+#[test]
+fn degrees_count_edges_in_the_given_direction() {
+    let degree = |direction, normalized| DegreeCentrality {
+        direction,
+        normalized,
+    };
+    assert_scores(
+        scores(degree(Some(Direction::Outgoing), false)),
+        &[2.0, 1.0, 1.0, 1.0, 0.0],
+    );
+    assert_scores(
+        scores(degree(Some(Direction::Incoming), false)),
+        &[0.0, 1.0, 1.0, 2.0, 1.0],
+    );
+    assert_scores(scores(degree(None, false)), &[2.0, 2.0, 2.0, 3.0, 1.0]);
+    assert_scores(scores(degree(None, true)), &[0.5, 0.5, 0.5, 0.75, 0.25]);
+}
@@ -0,0 +1,52 @@
+//! Fixtures shared by the integration tests.
+#![allow(dead_code)]
+
+use num_traits::Zero;
+use oscoin_graph_api::ledger::{LedgerEdgeData, LedgerNodeData};
+use oscoin_graph_api::mem::{ArenaGraph, EdgeListGraph};
+use oscoin_graph_api::types::{EdgeData, EdgeType, NodeData, NodeType};
+use oscoin_graph_api::{Direction, Graph, GraphObject, GraphWriter};
+
+/// The data of a node of the given type, with any weight.
+pub fn node<W: Zero>(node_type: NodeType) -> NodeData<W> {
+    NodeData::from_node_type(node_type)
+}
+
+/// The data of a project without contributions.
+pub fn project() -> NodeData<f64> {
+    node(NodeType::Project {
+        contributions_from_all_users: 0,
+    })
+}
+
+/// The data of a dependency.
+pub fn dependency() -> EdgeData<f64> {
+    EdgeData::from_edge_type(EdgeType::Dependency)
+}
+
+/// Dependencies between pairs of nodes, as edge lists.
+pub fn dependencies(pairs: &[(u64, u64)]) -> Vec<(u64, u64, EdgeData<f64>)> {
+    pairs
+        .iter()
+        .map(|&(from, to)| (from, to, dependency()))
+        .collect()
+}
+
+/// A copy of a graph as an `ArenaGraph`, whose nodes and edges are inserted
+/// in reverse order, so that it lists them in another order.
+pub fn reversed_arena(graph: &EdgeListGraph) -> ArenaGraph {
+    let mut arena = ArenaGraph::new();
+    let mut nodes: Vec<_> = graph.nodes().collect();
+    nodes.reverse();
+    for node in &nodes {
+        arena.add_node(*node.id(), node.data().clone());
+    }
+    for node in &nodes {
+        let mut edges = graph.edges_directed(node.id(), Direction::Outgoing);
+        edges.reverse();
+        for e in edges {
+            arena.add_edge(*e.id, e.from, e.to, e.data.clone());
+        }
+    }
+    arena
+}
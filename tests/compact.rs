@@ -0,0 +1,272 @@
+use oscoin_graph_api::bench::Workload;
+use oscoin_graph_api::ledger::{LedgerEdgeData, LedgerNodeData};
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::snapshot::compact::{self, CompactError, CompactGraph, Encoding};
+use oscoin_graph_api::types::{EdgeData, EdgeType, NodeData, NodeType};
+use oscoin_graph_api::{Direction, Edge, Graph, GraphDataWriter, GraphObject, GraphWriter, Node};
+
+mod common;
+
+use common::{dependency, project};
+
+fn snapshot(graph: &EdgeListGraph, encoding: Encoding) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    compact::write_with(graph, &mut bytes, encoding).unwrap();
+    bytes
+}
+
+/// The ids of the edges of a node, sorted.
+fn edge_ids<G>(graph: &G, node: &u64, dir: Direction) -> Vec<u64>
+where
+    G: Graph,
+    G::Node: GraphObject<Id = u64>,
+    G::Edge: GraphObject<Id = u64>,
+{
+    let mut ids: Vec<u64> = graph
+        .edges_directed(node, dir)
+        .iter()
+        .map(|e| *e.id)
+        .collect();
+    ids.sort_unstable();
+    ids
+}
+
+const ENCODINGS: [Encoding; 3] = [
+    Encoding::Plain,
+    Encoding::Compressed {
+        edge_dictionary: false,
+    },
+    Encoding::Compressed {
+        edge_dictionary: true,
+    },
+];
+
+#[test]
+fn snapshots_read_like_their_graph() {
+    let graph: EdgeListGraph = Workload::random(50, 4, 3).build();
+
+    for encoding in ENCODINGS {
+        let compact = CompactGraph::from_bytes(snapshot(&graph, encoding)).unwrap();
+        assert_eq!(compact.validate(), Ok(()));
+
+        for node in graph.nodes() {
+            let id = node.id();
+            assert_eq!(compact.get_node(id).map(|n| n.id()), Some(id));
+            for dir in [Direction::Outgoing, Direction::Incoming, Direction::Both] {
+                assert_eq!(edge_ids(&compact, id, dir), edge_ids(&graph, id, dir));
+            }
+        }
+        assert_eq!(compact.nodes().count(), graph.node_count());
+    }
+}
+
+#[test]
+fn cached_objects_can_be_cleared() {
+    let graph: EdgeListGraph = Workload::random(50, 4, 3).build();
+    let mut compact = CompactGraph::from_bytes(snapshot(&graph, Encoding::Plain)).unwrap();
+    assert_eq!(compact.cached_objects(), 0);
+
+    assert_eq!(compact.nodes().count(), graph.node_count());
+    assert_eq!(compact.cached_objects(), graph.node_count());
+
+    compact.clear_cache();
+    assert_eq!(compact.cached_objects(), 0);
+    assert_eq!(compact.nodes().count(), graph.node_count());
+}
+
+#[test]
+fn snapshots_keep_edge_weights() {
+    let graph: EdgeListGraph = Workload::random(30, 3, 4).build();
+    for encoding in ENCODINGS {
+        let compact = CompactGraph::from_bytes(snapshot(&graph, encoding)).unwrap();
+        for node in graph.nodes() {
+            for e in graph.edges_directed(node.id(), Direction::Outgoing) {
+                let weight = graph.get_edge(e.id).unwrap().weight();
+                assert_eq!(compact.get_edge(e.id).unwrap().weight(), weight);
+            }
+        }
+    }
+}
+
+#[test]
+fn corrupt_records_are_missing() {
+    let graph: EdgeListGraph = Workload::random(20, 3, 5).build();
+    let mut bytes = snapshot(&graph, Encoding::Plain);
+    // The type of the first node.
+    bytes[32 + 8] = 0xff;
+
+    let compact = CompactGraph::from_bytes(bytes).unwrap();
+    assert!(matches!(compact.validate(), Err(CompactError::Corrupt(_))));
+
+    let first = *graph.nodes().next().unwrap().id();
+    assert!(compact.get_node(&first).is_none());
+    assert_eq!(compact.nodes().count(), graph.node_count() - 1);
+}
+
+#[test]
+fn corrupt_snapshots_never_panic() {
+    let graph: EdgeListGraph = Workload::random(12, 2, 5).build();
+
+    for encoding in ENCODINGS {
+        let valid = snapshot(&graph, encoding);
+        for at in 32..valid.len() {
+            for byte in [0x07, 0xff] {
+                let mut bytes = valid.clone();
+                bytes[at] = byte;
+                let compact = match CompactGraph::from_bytes(bytes) {
+                    Ok(compact) => compact,
+                    Err(_) => continue,
+                };
+                let _ = compact.validate();
+                for id in 0..16 {
+                    compact.neighbors(&id).count();
+                    compact.edges(&id).count();
+                    compact.edges_directed(&id, Direction::Both);
+                    compact.get_edge(&(id * 3));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn snapshots_keep_node_and_edge_types() {
+    let node = |node_type| NodeData::from_node_type(node_type);
+    let mut graph = EdgeListGraph::new();
+    graph.add_node(
+        1,
+        node(NodeType::Org {
+            contributions_to_owned_projects: 3,
+        }),
+    );
+    graph.add_node(2, node(NodeType::Checkpoint { contributions: 2 }));
+    let edge_types = [
+        EdgeType::OrgToProjectOwnership,
+        EdgeType::ProjectToOrgOwnership,
+        EdgeType::Donation(40),
+    ];
+    for (id, edge_type) in edge_types.iter().enumerate() {
+        graph.add_edge(
+            id as u64,
+            &1,
+            &2,
+            EdgeData::from_edge_type(edge_type.clone()),
+        );
+    }
+
+    for encoding in ENCODINGS {
+        let compact = CompactGraph::from_bytes(snapshot(&graph, encoding)).unwrap();
+        for id in [1, 2] {
+            assert_eq!(
+                compact.get_node(&id).unwrap().node_type(),
+                graph.get_node(&id).unwrap().node_type()
+            );
+        }
+        for (id, edge_type) in edge_types.iter().enumerate() {
+            assert_eq!(
+                compact.get_edge(&(id as u64)).unwrap().edge_type(),
+                edge_type
+            );
+        }
+    }
+}
+
+#[test]
+fn custom_edge_types_cannot_be_snapshotted() {
+    let mut graph: EdgeListGraph = EdgeListGraph::new();
+    graph.add_node(1, project());
+    graph.add_node(2, project());
+    let review = EdgeType::Custom {
+        tag: "review".to_owned(),
+        contributions: 1,
+    };
+    graph.add_edge(0, &1, &2, EdgeData::from_edge_type(review));
+
+    let mut bytes = Vec::new();
+    let err = compact::write_with(&graph, &mut bytes, Encoding::Plain).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(bytes.is_empty());
+}
+
+#[test]
+fn compressed_snapshots_are_smaller() {
+    // Mostly dependencies of the same weight, for the dictionary.
+    let mut graph: EdgeListGraph = Workload::random(200, 8, 6).build();
+    let ids: Vec<u64> = (0..graph.edge_count() as u64).collect();
+    for id in ids {
+        graph.set_edge_weight(&id, 0.5);
+    }
+
+    let sizes: Vec<usize> = ENCODINGS
+        .iter()
+        .map(|encoding| snapshot(&graph, *encoding).len())
+        .collect();
+    assert!(sizes[1] < sizes[0], "{:?}", sizes);
+    assert!(sizes[2] < sizes[1], "{:?}", sizes);
+
+    let versions: Vec<u32> = ENCODINGS
+        .iter()
+        .map(|encoding| {
+            CompactGraph::from_bytes(snapshot(&graph, *encoding))
+                .unwrap()
+                .version()
+        })
+        .collect();
+    assert_eq!(
+        versions,
+        vec![
+            compact::VERSION,
+            compact::COMPRESSED_VERSION,
+            compact::COMPRESSED_VERSION
+        ]
+    );
+    // `write` writes the plain format.
+    let mut bytes = Vec::new();
+    compact::write(&graph, &mut bytes).unwrap();
+    assert_eq!(bytes, snapshot(&graph, Encoding::Plain));
+}
+
+#[test]
+fn compressed_adjacency_keeps_parallel_edges_and_loops() {
+    let dependency = |weight| {
+        let mut data = dependency();
+        data.weight = weight;
+        data
+    };
+    let mut graph = EdgeListGraph::new();
+    // Sparse ids, with large gaps between node indices' ids.
+    for id in [1, 1 << 40, u64::MAX] {
+        graph.add_node(id, project());
+    }
+    graph.add_edge(9, &u64::MAX, &1, dependency(0.25));
+    graph.add_edge(3, &1, &u64::MAX, dependency(0.5));
+    graph.add_edge(7, &1, &u64::MAX, dependency(0.75));
+    graph.add_edge(1 << 50, &1, &1, dependency(1.0));
+    graph.add_edge(0, &(1 << 40), &(1 << 40), dependency(0.25));
+    graph.add_edge(5, &1, &(1 << 40), dependency(0.5));
+
+    for encoding in ENCODINGS {
+        let compact = CompactGraph::from_bytes(snapshot(&graph, encoding)).unwrap();
+        assert_eq!(compact.validate(), Ok(()));
+        assert_eq!(compact.edge_count(), 6);
+        for node in graph.nodes() {
+            for dir in [Direction::Outgoing, Direction::Incoming, Direction::Both] {
+                assert_eq!(
+                    edge_ids(&compact, node.id(), dir),
+                    edge_ids(&graph, node.id(), dir),
+                    "{:?} {} {:?}",
+                    encoding,
+                    node.id(),
+                    dir
+                );
+            }
+        }
+        for id in [9, 3, 7, 1 << 50, 0, 5] {
+            let (edge, expected) = (compact.get_edge(&id).unwrap(), graph.get_edge(&id).unwrap());
+            assert_eq!(
+                (edge.source(), edge.target(), edge.weight()),
+                (expected.source(), expected.target(), expected.weight())
+            );
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use oscoin_graph_api::bench::Workload;
+use oscoin_graph_api::compare::{
+    eq_by_ids, is_isomorphic, is_isomorphic_matching, TooLarge, MAX_ISOMORPHISM_NODES,
+};
+use oscoin_graph_api::mem::{ArenaGraph, EdgeListGraph};
+use oscoin_graph_api::types::{EdgeData, NodeData, NodeType};
+use oscoin_graph_api::{Direction, Edge, Graph, GraphDataWriter, GraphObject, GraphWriter};
+
+mod common;
+
+use common::{dependency, node, project};
+
+fn user() -> NodeData<f64> {
+    node(NodeType::User {
+        contributions_to_all_projects: 0,
+    })
+}
+
+/// A copy of a graph with every node `id` renamed `ids(id)`, and edges
+/// renumbered from `100`.
+fn relabel(graph: &EdgeListGraph, ids: impl Fn(u64) -> u64) -> EdgeListGraph {
+    let mut copy = EdgeListGraph::new();
+    for n in graph.nodes() {
+        copy.add_node(ids(*n.id()), n.data().clone());
+    }
+    let mut next = 100;
+    for n in graph.nodes() {
+        for e in graph.edges_directed(n.id(), Direction::Outgoing) {
+            copy.add_edge(next, &ids(*e.from), &ids(*e.to), e.data.clone());
+            next += 1;
+        }
+    }
+    copy
+}
+
+#[test]
+fn graphs_are_equal_by_ids_across_backends() {
+    let workload = Workload::random(30, 3, 5);
+    let graph: EdgeListGraph = workload.build();
+    let arena: ArenaGraph = workload.build();
+    assert!(eq_by_ids(&graph, &arena));
+    assert!(eq_by_ids(&arena, &graph));
+    assert!(eq_by_ids(
+        &EdgeListGraph::<u64, f64>::new(),
+        &ArenaGraph::new()
+    ));
+
+    let mut other: EdgeListGraph = workload.build();
+    other.node_data_mut(&3).unwrap().rank.rank = 0.5;
+    assert!(!eq_by_ids(&graph, &other));
+
+    let mut other: EdgeListGraph = workload.build();
+    other.edge_data_mut(&0).unwrap().weight += 1.0;
+    assert!(!eq_by_ids(&graph, &other));
+
+    // An extra edge, in either graph.
+    let mut other: EdgeListGraph = workload.build();
+    other.add_edge(1000, &0, &1, dependency());
+    assert!(!eq_by_ids(&graph, &other));
+    assert!(!eq_by_ids(&other, &graph));
+
+    // The same edge between other nodes.
+    let mut other: EdgeListGraph = workload.build();
+    let data = other.edge_data(&0).unwrap().clone();
+    let edge = other.get_edge(&0).unwrap();
+    let (from, to) = (*edge.source(), *edge.target());
+    other.add_edge(0, &to, &from, data);
+    assert!(!eq_by_ids(&graph, &other));
+
+    // Relabeled graphs aren't equal by ids, but are isomorphic.
+    let relabeled = relabel(&graph, |id| 29 - id);
+    assert!(!eq_by_ids(&graph, &relabeled));
+    assert_eq!(is_isomorphic(&graph, &relabeled), Ok(true));
+}
+
+#[test]
+fn isomorphisms_preserve_edges() {
+    // A path `1 -> 2 -> 3` and a star out of `1`.
+    let path =
+        EdgeListGraph::from_edges(vec![(1, 2, dependency()), (2, 3, dependency())], project());
+    let star =
+        EdgeListGraph::from_edges(vec![(1, 2, dependency()), (1, 3, dependency())], project());
+    assert_eq!(is_isomorphic(&path, &star), Ok(false));
+    assert_eq!(
+        is_isomorphic(&path, &relabel(&path, |id| 10 * id)),
+        Ok(true)
+    );
+
+    // Reversing the path gives another path.
+    let reversed =
+        EdgeListGraph::from_edges(vec![(3, 2, dependency()), (2, 1, dependency())], project());
+    assert_eq!(is_isomorphic(&path, &reversed), Ok(true));
+
+    // Parallel edges are counted.
+    let double = EdgeListGraph::from_edges(
+        vec![
+            (1, 2, dependency()),
+            (1, 2, dependency()),
+            (2, 3, dependency()),
+        ],
+        project(),
+    );
+    let split = EdgeListGraph::from_edges(
+        vec![
+            (1, 2, dependency()),
+            (2, 3, dependency()),
+            (2, 3, dependency()),
+        ],
+        project(),
+    );
+    assert_eq!(is_isomorphic(&double, &path), Ok(false));
+    assert_eq!(is_isomorphic(&double, &split), Ok(false));
+    assert_eq!(
+        is_isomorphic(&double, &relabel(&double, |id| id + 1)),
+        Ok(true)
+    );
+
+    // Self-loops only map to self-loops.
+    let looped =
+        EdgeListGraph::from_edges(vec![(1, 1, dependency()), (2, 3, dependency())], project());
+    let chained =
+        EdgeListGraph::from_edges(vec![(1, 2, dependency()), (3, 3, dependency())], project());
+    assert_eq!(is_isomorphic(&looped, &chained), Ok(true));
+    assert_eq!(is_isomorphic(&looped, &path), Ok(false));
+}
+
+#[test]
+fn isomorphisms_can_match_data() {
+    // A user and two projects, with the user in the middle of the path or
+    // at its start.
+    let path = |first: NodeData<f64>, second: NodeData<f64>| {
+        let mut graph = EdgeListGraph::new();
+        graph.add_node(1, first);
+        graph.add_node(2, second);
+        graph.add_node(3, project());
+        graph.add_edge(0, &1, &2, dependency());
+        graph.add_edge(1, &2, &3, dependency());
+        graph
+    };
+    let same_type = |a: &NodeData<f64>, b: &NodeData<f64>| a.node_type == b.node_type;
+    let any = |_: &EdgeData<f64>, _: &EdgeData<f64>| true;
+
+    let middle = path(project(), user());
+    let start = path(user(), project());
+    assert_eq!(is_isomorphic(&middle, &start), Ok(true));
+    assert_eq!(
+        is_isomorphic_matching(&middle, &start, same_type, any),
+        Ok(false)
+    );
+    assert_eq!(
+        is_isomorphic_matching(&middle, &path(project(), user()), same_type, any),
+        Ok(true)
+    );
+
+    let mut heavy = path(project(), user());
+    heavy.edge_data_mut(&0).unwrap().weight = 2.0;
+    let same_weight = |a: &EdgeData<f64>, b: &EdgeData<f64>| a.weight == b.weight;
+    assert_eq!(
+        is_isomorphic_matching(&middle, &heavy, same_type, same_weight),
+        Ok(false)
+    );
+    assert_eq!(
+        is_isomorphic_matching(&middle, &heavy, same_type, any),
+        Ok(true)
+    );
+}
+
+#[test]
+fn large_graphs_are_not_searched() {
+    let nodes = MAX_ISOMORPHISM_NODES + 1;
+    let large: EdgeListGraph = Workload::random(nodes, 2, 1).build();
+    let small: EdgeListGraph = Workload::random(MAX_ISOMORPHISM_NODES, 2, 1).build();
+    let error = TooLarge { nodes };
+    assert_eq!(is_isomorphic(&large, &small), Err(error));
+    assert_eq!(is_isomorphic(&small, &large), Err(error));
+    assert_eq!(is_isomorphic(&small, &small), Ok(true));
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "graph has {} nodes, isomorphism is checked for at most 64",
+            nodes
+        )
+    );
+}
@@ -0,0 +1,176 @@
+#![cfg(feature = "config")]
+
+use std::fs;
+use std::path::PathBuf;
+
+use oscoin_graph_api::config::{self, Config, ConfigError, PRESETS};
+use oscoin_graph_api::types::{EdgeTypeTag, HyperParameters};
+
+/// Hyperparameters, compared by their debug output.
+fn debug(params: &HyperParameters<f64>) -> String {
+    format!("{:?}", params)
+}
+
+/// A path in the temporary directory, unique to the test and process.
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("oscoin-config-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn configurations_override_their_preset() {
+    let params = config::from_toml(
+        r#"
+        preset = "paper"
+        r_value = 100
+
+        [damping_factors]
+        account = 0.9
+
+        [edge_weights]
+        dependency = 0.5
+        "custom:code_review" = 0.25
+        "#,
+    )
+    .unwrap();
+
+    let paper: HyperParameters<f64> = HyperParameters::osrank_paper_defaults();
+    assert_eq!(params.r_value, 100);
+    assert_eq!(params.damping_factors.account, 0.9);
+    assert_eq!(
+        params.damping_factors.project,
+        paper.damping_factors.project
+    );
+    assert_eq!(params.pruning_threshold, paper.pruning_threshold);
+    assert_eq!(params.edge_weights[&EdgeTypeTag::Dependency], 0.5);
+    assert_eq!(
+        params.edge_weights[&EdgeTypeTag::Custom("code_review".to_owned())],
+        0.25
+    );
+    // Weights not set are the preset's.
+    assert_eq!(params.edge_weights.len(), paper.edge_weights.len() + 1);
+    assert_eq!(
+        params.edge_weights[&EdgeTypeTag::UserToProjectContribution],
+        paper.edge_weights[&EdgeTypeTag::UserToProjectContribution]
+    );
+
+    for name in PRESETS.iter() {
+        let params = config::from_json(&format!(r#"{{"preset": "{}"}}"#, name)).unwrap();
+        assert_eq!(debug(&params), debug(&config::preset(name).unwrap()));
+    }
+    assert_eq!(
+        debug(&config::from_toml(r#"preset = "conservative""#).unwrap()),
+        debug(&HyperParameters::conservative())
+    );
+}
+
+#[test]
+fn configurations_without_a_preset_set_the_required_fields() {
+    let params = config::from_json(
+        r#"{
+            "damping_factors": {"project": 0.8, "account": 0.5},
+            "r_value": 10,
+            "rank_decay": 0.75
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(params.damping_factors.project, 0.8);
+    assert_eq!(params.damping_factors.account, 0.5);
+    assert_eq!(params.r_value, 10);
+    assert_eq!(params.rank_decay, Some(0.75));
+    assert_eq!(params.pruning_threshold, 0.0);
+    assert!(params.edge_weights.is_empty());
+
+    let missing = |s: &str| match config::from_json(s) {
+        Err(ConfigError::MissingField(field)) => field,
+        other => panic!("unexpected {:?}", other),
+    };
+    assert_eq!(missing("{}"), "damping_factors.project");
+    assert_eq!(
+        missing(r#"{"damping_factors": {"project": 0.8}}"#),
+        "damping_factors.account"
+    );
+    assert_eq!(
+        missing(r#"{"damping_factors": {"project": 0.8, "account": 0.5}}"#),
+        "r_value"
+    );
+}
+
+#[test]
+fn invalid_configurations_are_rejected() {
+    let error = |s: &str| config::from_toml(s).unwrap_err().to_string();
+
+    assert_eq!(error(r#"preset = "bold""#), r#"unknown preset "bold""#);
+    assert!(error("preset = \"paper\"\nr_valu = 3").starts_with("parse error: "));
+    assert!(
+        error("preset = \"paper\"\n[damping_factors]\nusers = 0.5").starts_with("parse error: ")
+    );
+    assert!(error("preset = \"paper\"\n[edge_weights]\nfollows = 1.0")
+        .starts_with("invalid edge weight: "));
+    assert_eq!(
+        error("preset = \"paper\"\n[damping_factors]\nproject = 1.5"),
+        "invalid value 1.5 for damping_factors.project"
+    );
+    assert_eq!(
+        error("preset = \"paper\"\npruning_threshold = -1.0"),
+        "invalid value -1 for pruning_threshold"
+    );
+    assert_eq!(
+        error("preset = \"paper\"\nrank_decay = nan"),
+        "invalid value NaN for rank_decay"
+    );
+    assert_eq!(
+        error("preset = \"paper\"\n[edge_weights]\n\"custom:review\" = -0.5"),
+        "invalid value -0.5 for edge_weights.custom:review"
+    );
+    assert_eq!(
+        error("preset = \"paper\"\nmax_user_rank = 2.0"),
+        "invalid value 2 for max_user_rank"
+    );
+
+    let mut params = HyperParameters::osrank_paper_defaults();
+    assert!(config::validate(&params).is_ok());
+    params.max_edge_weight_share = Some(f64::INFINITY);
+    assert!(matches!(
+        config::validate(&params),
+        Err(ConfigError::Invalid { ref field, .. }) if field == "max_edge_weight_share"
+    ));
+}
+
+#[test]
+fn configurations_are_loaded_by_extension() {
+    let toml = temp_path("params.toml");
+    fs::write(&toml, "preset = \"paper\"\nr_value = 7\n").unwrap();
+    assert_eq!(config::load(&toml).unwrap().r_value, 7);
+    fs::remove_file(&toml).unwrap();
+
+    let json = temp_path("params.json");
+    fs::write(&json, r#"{"preset": "conservative", "r_value": 8}"#).unwrap();
+    assert_eq!(config::load(&json).unwrap().r_value, 8);
+    fs::remove_file(&json).unwrap();
+
+    assert!(matches!(
+        config::load(temp_path("params.yaml")),
+        Err(ConfigError::UnknownFormat(ref ext)) if ext == "yaml"
+    ));
+    assert!(matches!(
+        config::load(temp_path("params")),
+        Err(ConfigError::UnknownFormat(ref ext)) if ext.is_empty()
+    ));
+    let error = config::load(temp_path("missing.toml")).unwrap_err();
+    assert!(matches!(error, ConfigError::Io(_)));
+    assert!(std::error::Error::source(&error).is_some());
+}
+
+#[test]
+fn configurations_round_trip() {
+    let config = Config {
+        preset: Some("paper".to_owned()),
+        r_value: Some(12),
+        edge_weights: vec![("dependency".to_owned(), 0.5)].into_iter().collect(),
+        ..Config::default()
+    };
+    let toml = toml::to_string(&config).unwrap();
+    assert_eq!(Config::from_toml(&toml).unwrap(), config);
+    let json = serde_json::to_string(&config).unwrap();
+    assert_eq!(Config::from_json(&json).unwrap(), config);
+}
@@ -0,0 +1,207 @@
+use oscoin_graph_api::io::csv::{
+    read_edges, read_nodes, write_edges, write_nodes, CsvError, CsvFormat,
+};
+use oscoin_graph_api::ledger::{LedgerEdgeData, LedgerNodeData};
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::types::{EdgeData, EdgeType, NodeData, NodeType};
+use oscoin_graph_api::{Edge, Graph, GraphObject, GraphWriter, Node};
+
+mod common;
+
+use common::dependency;
+
+type Strings = EdgeListGraph<String, f64>;
+
+fn graph() -> Strings {
+    let mut graph = Strings::new();
+    for (id, contributions) in [
+        ("plain", 1),
+        ("two\nlines", 2),
+        ("\"quoted\", comma", 3),
+        ("crlf\r\nend", 4),
+    ] {
+        graph.add_node(
+            id.to_owned(),
+            NodeData::from_node_type(NodeType::User {
+                contributions_to_all_projects: contributions,
+            }),
+        );
+    }
+    let mut data = dependency();
+    data.weight = 0.5;
+    graph.add_edge(
+        7,
+        &"plain".to_owned(),
+        &"two\nlines".to_owned(),
+        data.clone(),
+    );
+    graph.add_edge(
+        8,
+        &"two\nlines".to_owned(),
+        &"\"quoted\", comma".to_owned(),
+        data,
+    );
+    graph
+}
+
+fn round_trip(graph: &Strings, format: &CsvFormat) -> Strings {
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    write_nodes(graph, &mut nodes, format).unwrap();
+    write_edges(graph, &mut edges, format).unwrap();
+
+    let mut read = Strings::new();
+    let n = read_nodes(&mut read, &nodes[..], format, |r| {
+        NodeData::from_node_type(r.node_type.clone())
+    })
+    .unwrap();
+    let m = read_edges(&mut read, &edges[..], format, |r| {
+        let mut data = EdgeData::from_edge_type(r.edge_type.clone());
+        data.weight = r.weight;
+        data
+    })
+    .unwrap();
+    assert_eq!((n, m), (graph.node_count(), graph.edge_count()));
+    read
+}
+
+#[test]
+fn multiline_fields_round_trip() {
+    let graph = graph();
+    for header in [true, false] {
+        let format = CsvFormat {
+            header,
+            ..CsvFormat::default()
+        };
+        let read = round_trip(&graph, &format);
+        for node in graph.nodes() {
+            // Line breaks are normalized to `\n`.
+            let id = node.id().replace("\r\n", "\n");
+            assert_eq!(read.get_node(&id).unwrap().node_type(), node.node_type());
+        }
+        for id in [7, 8] {
+            let (a, b) = (graph.get_edge(&id).unwrap(), read.get_edge(&id).unwrap());
+            assert_eq!(
+                (a.source(), a.target(), a.weight()),
+                (b.source(), b.target(), b.weight())
+            );
+        }
+    }
+}
+
+#[test]
+fn unterminated_quotes_are_rejected() {
+    let mut graph = Strings::new();
+    let input = "id,type,contributions\na,user,1\n\"b\nuser,2\n";
+    let err = read_nodes(&mut graph, input.as_bytes(), &CsvFormat::default(), |r| {
+        NodeData::from_node_type(r.node_type.clone())
+    })
+    .unwrap_err();
+    assert!(matches!(err, CsvError::UnterminatedQuote { line: 3 }));
+}
+
+#[test]
+fn formats_with_shared_columns_are_rejected() {
+    let mut format = CsvFormat::default();
+    format.edges.weight = format.edges.from;
+    assert!(matches!(
+        format.validate(),
+        Err(CsvError::DuplicateColumn(1))
+    ));
+    let mut out = Vec::new();
+    assert!(write_edges(&graph(), &mut out, &format).is_err());
+    assert!(out.is_empty());
+
+    let mut format = CsvFormat::default();
+    format.nodes.contributions = 0;
+    assert!(matches!(
+        format.validate(),
+        Err(CsvError::DuplicateColumn(0))
+    ));
+
+    let format = CsvFormat {
+        delimiter: '"',
+        ..CsvFormat::default()
+    };
+    assert!(matches!(
+        format.validate(),
+        Err(CsvError::InvalidDelimiter('"'))
+    ));
+    assert!(CsvFormat::default().validate().is_ok());
+}
+
+#[test]
+fn orgs_checkpoints_donations_and_ownership_round_trip() {
+    let mut graph = Strings::new();
+    for (id, node_type) in [
+        (
+            "org",
+            NodeType::Org {
+                contributions_to_owned_projects: 3,
+            },
+        ),
+        (
+            "project",
+            NodeType::Project {
+                contributions_from_all_users: 0,
+            },
+        ),
+        ("v1", NodeType::Checkpoint { contributions: 2 }),
+    ] {
+        graph.add_node(id.to_owned(), NodeData::from_node_type(node_type));
+    }
+    let edges = [
+        (1, "org", "project", EdgeType::OrgToProjectOwnership),
+        (2, "project", "org", EdgeType::ProjectToOrgOwnership),
+        (3, "org", "v1", EdgeType::Donation(40)),
+    ];
+    for (id, from, to, edge_type) in edges.iter() {
+        let data = EdgeData::from_edge_type(edge_type.clone());
+        graph.add_edge(*id, &from.to_string(), &to.to_string(), data);
+    }
+
+    let read = round_trip(&graph, &CsvFormat::default());
+    for node in graph.nodes() {
+        assert_eq!(
+            read.get_node(node.id()).unwrap().node_type(),
+            node.node_type()
+        );
+    }
+    for (id, _, _, edge_type) in edges.iter() {
+        assert_eq!(read.get_edge(id).unwrap().edge_type(), edge_type);
+    }
+}
+
+#[test]
+fn custom_edge_types_are_prefixed() {
+    let mut graph = Strings::new();
+    for id in ["a", "b"] {
+        graph.add_node(
+            id.to_owned(),
+            NodeData::from_node_type(NodeType::User {
+                contributions_to_all_projects: 0,
+            }),
+        );
+    }
+    let review = EdgeType::Custom {
+        tag: "code_review".to_owned(),
+        contributions: 6,
+    };
+    let data = EdgeData::from_edge_type(review.clone());
+    graph.add_edge(1, &"a".to_owned(), &"b".to_owned(), data);
+
+    let format = CsvFormat::default();
+    let mut out = Vec::new();
+    write_edges(&graph, &mut out, &format).unwrap();
+    let written = String::from_utf8(out).unwrap();
+    assert!(written.contains(",custom:code_review,6,"));
+    let read = round_trip(&graph, &format);
+    assert_eq!(read.get_edge(&1).unwrap().edge_type(), &review);
+
+    let input = written.replace("custom:code_review", "custom:");
+    let err = read_edges(&mut Strings::new(), input.as_bytes(), &format, |r| {
+        EdgeData::from_edge_type(r.edge_type.clone())
+    })
+    .unwrap_err();
+    assert!(matches!(err, CsvError::InvalidValue { value, .. } if value == "custom:"));
+}
@@ -0,0 +1,85 @@
+use oscoin_graph_api::debug::{dump, dump_neighborhood};
+use oscoin_graph_api::ledger::{LedgerEdgeData, LedgerNodeData};
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::types::{EdgeData, EdgeType, NodeData, NodeType};
+use oscoin_graph_api::GraphWriter;
+
+/// A user contributing to a project, which depends on another one, which
+/// depends on a third one. Ids are added out of order.
+fn graph() -> EdgeListGraph {
+    let mut graph = EdgeListGraph::new();
+    let project = || {
+        NodeData::from_node_type(NodeType::Project {
+            contributions_from_all_users: 3,
+        })
+    };
+    graph.add_node(4, project());
+    graph.add_node(3, project());
+    graph.add_node(2, project());
+    graph.add_node(
+        1,
+        NodeData::from_node_type(NodeType::User {
+            contributions_to_all_projects: 3,
+        }),
+    );
+    let edge = |edge_type, weight| {
+        let mut data = EdgeData::from_edge_type(edge_type);
+        data.weight = weight;
+        data
+    };
+    graph.add_edge(
+        10,
+        &1,
+        &2,
+        edge(EdgeType::UserToProjectContribution(3), 0.75),
+    );
+    graph.add_edge(12, &2, &4, edge(EdgeType::Dependency, 0.5));
+    graph.add_edge(11, &2, &3, edge(EdgeType::Dependency, 0.5));
+    graph.add_edge(13, &3, &4, edge(EdgeType::Dependency, 1.0));
+    graph
+}
+
+/// The text written by `write`.
+fn listing(write: impl FnOnce(&mut String) -> std::fmt::Result) -> String {
+    let mut out = String::new();
+    write(&mut out).unwrap();
+    out
+}
+
+#[test]
+fn dumps_list_nodes_and_edges_by_id() {
+    let out = listing(|out| dump(&graph(), out));
+    assert_eq!(
+        out,
+        "1 user(3)\n\
+         \x20 -> 2 user_to_project_contribution(3) weight=0.75 #10\n\
+         2 project(3)\n\
+         \x20 -> 3 dependency weight=0.5 #11\n\
+         \x20 -> 4 dependency weight=0.5 #12\n\
+         3 project(3)\n\
+         \x20 -> 4 dependency weight=1.0 #13\n\
+         4 project(3)\n"
+    );
+
+    let empty: EdgeListGraph = EdgeListGraph::new();
+    assert_eq!(listing(|out| dump(&empty, out)), "");
+}
+
+#[test]
+fn neighborhoods_are_dumped_in_both_directions() {
+    let graph = graph();
+    let out = listing(|out| dump_neighborhood(&graph, &3, 1, out));
+    assert_eq!(
+        out,
+        "2 project(3)\n\
+         \x20 -> 3 dependency weight=0.5 #11\n\
+         \x20 -> 4 dependency weight=0.5 #12\n\
+         3 project(3)\n\
+         \x20 -> 4 dependency weight=1.0 #13\n\
+         4 project(3)\n"
+    );
+
+    let out = listing(|out| dump_neighborhood(&graph, &1, 0, out));
+    assert_eq!(out, "1 user(3)\n");
+    assert_eq!(listing(|out| dump_neighborhood(&graph, &9, 2, out)), "");
+}
@@ -0,0 +1,86 @@
+use oscoin_graph_api::algorithms::DecayRanks;
+use oscoin_graph_api::bench::Workload;
+use oscoin_graph_api::cancel::CancellationToken;
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::types::{AlgorithmError, HyperParameters, NodeRank};
+use oscoin_graph_api::{Graph, GraphAlgorithm, GraphDataWriter, GraphObject};
+
+/// The default hyperparameters, with ranks decaying by `rank_decay`.
+fn hyperparams(rank_decay: Option<f64>) -> HyperParameters<f64> {
+    let mut hyperparams = HyperParameters::osrank_paper_defaults();
+    hyperparams.rank_decay = rank_decay;
+    hyperparams
+}
+
+/// A graph whose node `id` has rank `id + 1`.
+fn ranked_graph() -> EdgeListGraph {
+    let mut graph: EdgeListGraph = Workload::random(20, 2, 3).build();
+    let ids: Vec<u64> = graph.nodes().map(|n| *n.id()).collect();
+    for id in ids {
+        graph.node_data_mut(&id).unwrap().rank.rank = id as f64 + 1.0;
+    }
+    graph
+}
+
+#[test]
+fn ranks_decay_by_a_factor() {
+    let mut rank = NodeRank { rank: 2.0 };
+    rank.decay(0.25);
+    assert_eq!(rank, NodeRank { rank: 0.5 });
+
+    let decay = DecayRanks::new(hyperparams(Some(0.5)));
+    assert_eq!(decay.epochs, 1);
+    assert_eq!(decay.factor(), Some(0.5));
+    assert_eq!(decay.clone().epochs(3).factor(), Some(0.125));
+    assert_eq!(decay.clone().epochs(0).factor(), Some(1.0));
+    // Very many epochs don't overflow the exponent.
+    assert_eq!(decay.epochs(u32::MAX).factor(), Some(0.0));
+    assert_eq!(DecayRanks::new(hyperparams(None)).factor(), None);
+}
+
+#[test]
+fn every_node_is_annotated_with_its_decayed_rank() {
+    let graph = ranked_graph();
+    let mut annotations = Vec::new();
+    let annotated = DecayRanks::new(hyperparams(Some(0.5)))
+        .epochs(2)
+        .execute(&mut (), &graph, &mut annotations, 0)
+        .unwrap();
+    assert_eq!(annotated, 20);
+    assert_eq!(annotations.len(), 20);
+    for (id, rank) in annotations {
+        assert_eq!(rank.rank, (id as f64 + 1.0) * 0.25);
+        // The graph is left untouched.
+        assert_eq!(
+            graph.get_node(&id).unwrap().data().rank.rank,
+            id as f64 + 1.0
+        );
+    }
+}
+
+#[test]
+fn nothing_is_annotated_without_decay() {
+    let graph = ranked_graph();
+    let mut annotations = Vec::new();
+    let annotated = DecayRanks::new(hyperparams(None))
+        .execute(&mut (), &graph, &mut annotations, 0)
+        .unwrap();
+    assert_eq!(annotated, 0);
+    assert!(annotations.is_empty());
+}
+
+#[test]
+fn cancelled_decays_stop() {
+    let token = CancellationToken::new();
+    token.cancel();
+    let mut annotations = Vec::new();
+    let result = DecayRanks::new(hyperparams(Some(0.5))).execute_with(
+        &mut (),
+        &ranked_graph(),
+        &mut annotations,
+        0,
+        &token,
+    );
+    assert_eq!(result, Err(AlgorithmError::Cancelled));
+    assert!(annotations.is_empty());
+}
@@ -0,0 +1,82 @@
+use std::collections::BTreeMap;
+
+use oscoin_graph_api::bench::Workload;
+use oscoin_graph_api::mem::{ArenaGraph, EdgeListGraph};
+use oscoin_graph_api::snapshot::compact::{self, CompactGraph};
+use oscoin_graph_api::types::{EdgeData, EdgeTypeTag, NodeData};
+use oscoin_graph_api::watch::Watched;
+use oscoin_graph_api::{Direction, Edge, Graph, GraphObject, GraphWriter};
+
+mod common;
+
+use common::dependency;
+
+const DIRECTIONS: [Direction; 3] = [Direction::Outgoing, Direction::Incoming, Direction::Both];
+
+/// A random workload, with a self-loop on every node with an id multiple of
+/// five.
+fn workload_graph<G>() -> G
+where
+    G: GraphWriter<NodeData = NodeData<f64>, EdgeData = EdgeData<f64>> + Default,
+    G::Node: GraphObject<Id = u64>,
+    G::Edge: GraphObject<Id = u64>,
+{
+    let mut graph: G = Workload::random(40, 4, 12).build();
+    for id in (0..40).step_by(5) {
+        graph.add_edge(1000 + id, &id, &id, dependency());
+    }
+    graph
+}
+
+/// Check that the degrees of every node match the edges listed, and return
+/// them.
+fn degrees<G>(graph: &G) -> Vec<BTreeMap<EdgeTypeTag, usize>>
+where
+    G: Graph,
+    G::Node: GraphObject<Id = u64>,
+{
+    let mut ids: Vec<u64> = graph.nodes().map(|n| *n.id()).collect();
+    ids.sort_unstable();
+
+    let mut all = Vec::new();
+    for id in ids {
+        for dir in DIRECTIONS.iter() {
+            let mut listed = BTreeMap::new();
+            for e in graph.edges_directed(&id, *dir) {
+                let edge_type = graph.get_edge(e.id).unwrap().edge_type().to_tag();
+                *listed.entry(edge_type).or_insert(0) += 1;
+            }
+            let counted = graph.degree_by_edge_type(&id, *dir);
+            assert_eq!(counted, listed, "node {} {:?}", id, dir);
+            all.push(counted);
+        }
+    }
+    all
+}
+
+#[test]
+fn degrees_count_the_edges_listed() {
+    let graph: EdgeListGraph = workload_graph();
+    let expected = degrees(&graph);
+    assert!(expected.iter().any(|d| d.len() > 1));
+
+    assert_eq!(degrees(&workload_graph::<ArenaGraph>()), expected);
+    assert_eq!(
+        degrees(&Watched::new(workload_graph::<EdgeListGraph>())),
+        expected
+    );
+    let mut bytes = Vec::new();
+    compact::write(&graph, &mut bytes).unwrap();
+    assert_eq!(degrees(&CompactGraph::from_bytes(bytes).unwrap()), expected);
+
+    assert!(graph.degree_by_edge_type(&999, Direction::Both).is_empty());
+}
+
+#[test]
+fn self_loops_count_once_in_both_directions() {
+    let graph: EdgeListGraph = workload_graph();
+    let loops = |dir| graph.degree_by_edge_type(&5, dir)[&EdgeTypeTag::Dependency];
+    let out = loops(Direction::Outgoing);
+    let inc = loops(Direction::Incoming);
+    assert_eq!(loops(Direction::Both), out + inc - 1);
+}
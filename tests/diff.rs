@@ -0,0 +1,202 @@
+use oscoin_graph_api::compare::eq_by_ids;
+use oscoin_graph_api::diff::{apply, compact, invert, DiffError};
+use oscoin_graph_api::ledger::{LedgerEdgeData, LedgerNodeData};
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::types::{
+    EdgeData, EdgeType, GraphDiff, GraphDiffOwned, GraphDiffs, NodeData, NodeType,
+};
+use oscoin_graph_api::{Graph, GraphWriter};
+
+type Diff = GraphDiffOwned<EdgeListGraph>;
+
+fn project(contributions: u32) -> NodeData<f64> {
+    NodeData::from_node_type(NodeType::Project {
+        contributions_from_all_users: contributions,
+    })
+}
+
+fn dependency(weight: f64) -> EdgeData<f64> {
+    let mut data = EdgeData::from_edge_type(EdgeType::Dependency);
+    data.weight = weight;
+    data
+}
+
+/// Nodes `1` and `2`, and the edge `0` between them.
+fn graph() -> EdgeListGraph {
+    EdgeListGraph::from_edges(vec![(1, 2, dependency(0.5))], project(0))
+}
+
+#[test]
+fn owned_diffs_hold_their_data() {
+    let mut graph = graph();
+    let node = graph.get_node(&1).unwrap().clone();
+    let edge = graph.get_edge(&0).unwrap().clone();
+
+    assert_eq!(
+        GraphDiff::NodeAdded(&2).into_owned(&graph),
+        Some(Diff::NodeAdded {
+            id: 2,
+            data: project(0)
+        })
+    );
+    assert_eq!(
+        GraphDiff::EdgeAdded {
+            id: &0,
+            source: &1,
+            target: &2
+        }
+        .into_owned(&graph),
+        Some(Diff::EdgeAdded {
+            id: 0,
+            source: 1,
+            target: 2,
+            data: dependency(0.5),
+        })
+    );
+
+    // Deleted objects are carried by the diff, added ones looked up.
+    graph.remove_node(1);
+    graph.remove_edge(0);
+    assert_eq!(
+        GraphDiff::NodeDeleted(node).into_owned(&graph),
+        Some(Diff::NodeDeleted {
+            id: 1,
+            data: project(0)
+        })
+    );
+    assert!(GraphDiff::EdgeDeleted(edge).into_owned(&graph).is_some());
+    assert!(GraphDiff::NodeUpdated(&1).into_owned(&graph).is_none());
+}
+
+#[test]
+fn owned_diffs_are_borrowed_back() {
+    let graph = graph();
+    let deleted = Diff::EdgeDeleted {
+        id: 0,
+        source: 1,
+        target: 2,
+        data: dependency(0.5),
+    };
+    match deleted.as_diff(&graph) {
+        Some(GraphDiff::EdgeDeleted(edge)) => assert_eq!(&edge, graph.get_edge(&0).unwrap()),
+        _ => panic!("expected an edge deletion"),
+    }
+    let missing = Diff::NodeDeleted {
+        id: 3,
+        data: project(0),
+    };
+    assert!(missing.as_diff(&graph).is_none());
+
+    let diffs: GraphDiffs<EdgeListGraph, Diff> = GraphDiffs::new(vec![deleted.clone(), missing]);
+    let debug: Vec<_> = diffs.map(|d| format!("{:?}", d)).collect();
+    assert_eq!(debug.len(), 2);
+    assert!(debug[0].starts_with("EdgeDeleted { id: 0, source: 1, target: 2"));
+    assert_ne!(
+        deleted,
+        Diff::EdgeUpdated {
+            id: 0,
+            source: 1,
+            target: 2,
+            data: dependency(0.5),
+        }
+    );
+}
+
+/// A log adding node `3` and linking it, updating the existing nodes and
+/// edge, then removing node `3` and its edge.
+fn log() -> Vec<Diff> {
+    let edge = |id, data| Diff::EdgeAdded {
+        id,
+        source: 1,
+        target: 3,
+        data,
+    };
+    vec![
+        Diff::NodeAdded {
+            id: 3,
+            data: project(0),
+        },
+        Diff::NodeUpdated {
+            id: 3,
+            data: project(1),
+        },
+        Diff::NodeUpdated {
+            id: 1,
+            data: project(2),
+        },
+        edge(1, dependency(1.0)),
+        Diff::EdgeUpdated {
+            id: 0,
+            source: 1,
+            target: 2,
+            data: dependency(0.2),
+        },
+        Diff::NodeUpdated {
+            id: 1,
+            data: project(3),
+        },
+        Diff::EdgeUpdated {
+            id: 0,
+            source: 1,
+            target: 2,
+            data: dependency(0.3),
+        },
+        Diff::EdgeDeleted {
+            id: 1,
+            source: 1,
+            target: 3,
+            data: dependency(1.0),
+        },
+        Diff::NodeDeleted {
+            id: 3,
+            data: project(1),
+        },
+    ]
+}
+
+#[test]
+fn logs_are_compacted_without_changing_their_effect() {
+    let compacted = compact(log());
+    assert_eq!(
+        compacted,
+        vec![
+            Diff::NodeUpdated {
+                id: 1,
+                data: project(3),
+            },
+            Diff::EdgeUpdated {
+                id: 0,
+                source: 1,
+                target: 2,
+                data: dependency(0.3),
+            },
+        ]
+    );
+
+    let (mut full, mut short) = (graph(), graph());
+    apply(&mut full, log());
+    apply(&mut short, compacted);
+    assert!(eq_by_ids(&full, &short));
+}
+
+#[test]
+fn inverted_logs_roll_back() {
+    let base = graph();
+    let undo = invert(&log(), &base).unwrap();
+    assert_eq!(undo.len(), log().len());
+
+    let mut graph = graph();
+    apply(&mut graph, log());
+    assert!(!eq_by_ids(&graph, &base));
+    apply(&mut graph, undo);
+    assert!(eq_by_ids(&graph, &base));
+
+    let unknown = vec![Diff::NodeUpdated {
+        id: 9,
+        data: project(0),
+    }];
+    assert_eq!(
+        invert(&unknown, &base).err(),
+        Some(DiffError::UnknownNode(0))
+    );
+}
@@ -0,0 +1,84 @@
+use oscoin_graph_api::mem::{self, ArenaGraph, EdgeListGraph};
+use oscoin_graph_api::snapshot::compact::{self, CompactGraph};
+use oscoin_graph_api::subgraph::SubgraphView;
+use oscoin_graph_api::types::EdgeData;
+use oscoin_graph_api::{Direction, Graph, GraphObject};
+
+mod common;
+
+use common::{dependencies, project};
+
+const DIRECTIONS: [Direction; 3] = [Direction::Outgoing, Direction::Incoming, Direction::Both];
+
+/// Edges `1 -> 2`, `2 -> 1`, and a self-loop on `1`.
+fn edges() -> Vec<(u64, u64, EdgeData<f64>)> {
+    dependencies(&[(1, 2), (2, 1), (1, 1)])
+}
+
+/// The ids of the edges of node `1` in every direction, sorted but not
+/// deduplicated.
+fn edge_ids<G>(graph: &G) -> Vec<Vec<u64>>
+where
+    G: Graph,
+    G::Node: GraphObject<Id = u64>,
+    G::Edge: GraphObject<Id = u64>,
+{
+    DIRECTIONS
+        .iter()
+        .map(|dir| {
+            let mut ids: Vec<u64> = graph
+                .edges_directed(&1, *dir)
+                .iter()
+                .map(|e| *e.id)
+                .collect();
+            ids.sort_unstable();
+            ids
+        })
+        .collect()
+}
+
+#[test]
+fn directions_have_opposites() {
+    assert_eq!(Direction::Outgoing.opposite(), Direction::Incoming);
+    assert_eq!(Direction::Incoming.opposite(), Direction::Outgoing);
+    assert_eq!(Direction::Both.opposite(), Direction::Both);
+    for dir in DIRECTIONS.iter() {
+        assert_eq!(dir.opposite().opposite(), *dir);
+    }
+}
+
+#[test]
+fn directions_match_edge_endpoints() {
+    let matches = |dir: Direction| {
+        (
+            dir.matches(&1, &1, &2),
+            dir.matches(&2, &1, &2),
+            dir.matches(&3, &1, &2),
+        )
+    };
+    assert_eq!(matches(Direction::Outgoing), (true, false, false));
+    assert_eq!(matches(Direction::Incoming), (false, true, false));
+    assert_eq!(matches(Direction::Both), (true, true, false));
+    for dir in DIRECTIONS.iter() {
+        assert!(dir.matches(&1, &1, &1));
+    }
+}
+
+#[test]
+fn self_loops_are_listed_once_in_both_directions() {
+    let expected = vec![vec![0, 2], vec![1, 2], vec![0, 1, 2]];
+
+    let graph = EdgeListGraph::from_edges(edges(), project());
+    assert_eq!(edge_ids(&graph), expected);
+
+    let arena: ArenaGraph = mem::from_edges(ArenaGraph::new(), edges(), project(), |i| i as u64);
+    assert_eq!(edge_ids(&arena), expected);
+
+    let mut bytes = Vec::new();
+    compact::write(&graph, &mut bytes).unwrap();
+    let snapshot = CompactGraph::from_bytes(bytes).unwrap();
+    assert_eq!(edge_ids(&snapshot), expected);
+
+    let view = SubgraphView::induced(&graph, &[1, 2], |_| true);
+    assert_eq!(edge_ids(&view), expected);
+}
@@ -0,0 +1,222 @@
+use std::fmt;
+
+use oscoin_graph_api::algorithms::MonteCarlo;
+use oscoin_graph_api::bench::Workload;
+use oscoin_graph_api::cancel::CancellationToken;
+use oscoin_graph_api::distributed::{
+    LocalWalkExecutor, RemoteWalkExecutor, WalkBatch, WalkDriver, WalkError, WalkRequest,
+    WalkResults,
+};
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::partition::{self, Assignment, Partition, ShardedGraph, Strategy};
+use oscoin_graph_api::types::{AlgorithmError, HyperParameters, NodeRank};
+use oscoin_graph_api::{Graph, GraphAlgorithm, GraphObject};
+
+fn monte_carlo() -> MonteCarlo<f64> {
+    let mut hyperparams = HyperParameters::osrank_paper_defaults();
+    hyperparams.r_value = 4;
+    MonteCarlo::new(hyperparams).max_steps(20)
+}
+
+/// The ranks of a local execution.
+fn local_ranks(graph: &EdgeListGraph, seed: u64) -> Vec<(u64, NodeRank<f64>)> {
+    let mut ranks = Vec::new();
+    monte_carlo()
+        .execute(&mut (), graph, &mut ranks, seed)
+        .unwrap();
+    ranks
+}
+
+/// An executor failing on its first batch.
+struct Failing;
+
+#[derive(Debug, PartialEq)]
+struct Unreachable;
+
+impl fmt::Display for Unreachable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("unreachable")
+    }
+}
+
+impl std::error::Error for Unreachable {}
+
+impl RemoteWalkExecutor<u64> for Failing {
+    type Error = Unreachable;
+
+    fn submit(&mut self, _batch: WalkBatch<u64>) -> Result<(), Unreachable> {
+        Err(Unreachable)
+    }
+
+    fn receive(&mut self) -> Result<WalkResults<u64>, Unreachable> {
+        Err(Unreachable)
+    }
+}
+
+#[test]
+fn sharded_walks_give_the_local_ranks() {
+    let graph: EdgeListGraph = Workload::random(80, 3, 4).build();
+    let ranks = local_ranks(&graph, 7);
+    assert_eq!(ranks.len(), 80);
+    let total: f64 = ranks.iter().map(|(_, r)| r.rank).sum();
+    assert!((total - 1.0).abs() < 1e-9);
+    assert!(ranks.windows(2).all(|w| w[0].0 < w[1].0));
+    assert_ne!(ranks, local_ranks(&graph, 8));
+
+    let token = CancellationToken::new();
+    for strategy in [Strategy::Hash, Strategy::balanced()] {
+        for batch_size in [1, 7, 10_000] {
+            let assignment = partition::assign(&graph, 3, strategy);
+            let mut executors: Vec<_> = (0..3)
+                .map(|s| LocalWalkExecutor::for_shard(&graph, &assignment, s))
+                .collect();
+            let sharded = monte_carlo()
+                .batch_size(batch_size)
+                .rank_on(&assignment, &mut executors, 7, &token)
+                .unwrap();
+            assert_eq!(sharded, ranks, "{:?} {}", strategy, batch_size);
+        }
+    }
+
+    // Executors over the sharded graph.
+    let assignment = partition::assign(&graph, 2, Strategy::balanced());
+    let parts: Partition<EdgeListGraph> = partition::split(&graph, &assignment, EdgeListGraph::new);
+    let view = ShardedGraph::new(parts);
+    let mut executors: Vec<_> = (0..2)
+        .map(|s| LocalWalkExecutor::for_shard(&view, &assignment, s))
+        .collect();
+    let sharded = monte_carlo()
+        .rank_on(&assignment, &mut executors, 7, &token)
+        .unwrap();
+    assert_eq!(sharded, ranks);
+}
+
+#[test]
+fn walks_are_handed_off_between_shards() {
+    let graph: EdgeListGraph = Workload::random(30, 3, 9).build();
+    let assignment = partition::assign(&graph, 2, Strategy::Hash);
+    let driver = WalkDriver {
+        seed: 3,
+        damping_factors: HyperParameters::<f64>::osrank_paper_defaults().damping_factors,
+        max_steps: 10,
+        batch_size: 100,
+    };
+
+    let ids: Vec<u64> = graph.nodes().map(|n| *n.id()).collect();
+    let source = ids[0];
+    let shard = assignment.shard_of(&source).unwrap();
+    let mut executor = LocalWalkExecutor::for_shard(&graph, &assignment, shard);
+    let results = executor.simulate(WalkBatch {
+        seed: driver.seed,
+        damping_factors: driver.damping_factors.clone(),
+        max_steps: driver.max_steps,
+        walks: (0..50)
+            .map(|walk| WalkRequest::start(walk, source))
+            .collect(),
+    });
+    assert!(!results.handoffs.is_empty());
+    for handoff in &results.handoffs {
+        assert_ne!(assignment.shard_of(&handoff.node), Some(shard));
+        assert!(handoff.steps > 0 && handoff.draws >= 2 * u64::from(handoff.steps));
+    }
+    // Only nodes of the shard are visited.
+    assert!(results
+        .visits
+        .iter()
+        .all(|(id, _)| assignment.shard_of(id) == Some(shard)));
+
+    // Resuming a walk where it was handed off continues it as if it hadn't
+    // been.
+    let walks: Vec<WalkRequest<u64>> = (0..50).map(|w| WalkRequest::start(w, source)).collect();
+    let mut whole = vec![LocalWalkExecutor::new(&graph)];
+    let single = Assignment::new(1, ids.iter().map(|id| (*id, 0)));
+    let expected = driver
+        .run(
+            &mut whole,
+            &single,
+            walks.clone(),
+            &CancellationToken::new(),
+        )
+        .unwrap();
+    let mut executors: Vec<_> = (0..2)
+        .map(|s| LocalWalkExecutor::for_shard(&graph, &assignment, s))
+        .collect();
+    let visits = driver
+        .run(
+            &mut executors,
+            &assignment,
+            walks,
+            &CancellationToken::new(),
+        )
+        .unwrap();
+    assert_eq!(visits, expected);
+    assert!(visits.values().sum::<u64>() >= 50);
+}
+
+#[test]
+fn walks_without_steps_visit_their_source() {
+    let graph: EdgeListGraph = Workload::random(10, 2, 1).build();
+    let mut ranks = Vec::new();
+    monte_carlo()
+        .max_steps(0)
+        .execute(&mut (), &graph, &mut ranks, 0)
+        .unwrap();
+    assert!(ranks.iter().all(|(_, r)| r.rank == 0.1));
+
+    // Walks reaching unassigned nodes end there.
+    let assignment = Assignment::new(1, vec![(0, 0)]);
+    let mut executors = [LocalWalkExecutor::for_shard(&graph, &assignment, 0)];
+    let ranks = monte_carlo()
+        .rank_on(&assignment, &mut executors, 0, &CancellationToken::new())
+        .unwrap();
+    assert_eq!(ranks, vec![(0, NodeRank { rank: 1.0 })]);
+}
+
+#[test]
+fn driver_failures_are_reported() {
+    let graph: EdgeListGraph = Workload::random(10, 2, 1).build();
+    let assignment = partition::assign(&graph, 2, Strategy::Hash);
+    let token = CancellationToken::new();
+
+    let mut one = [LocalWalkExecutor::new(&graph)];
+    let error = monte_carlo()
+        .rank_on(&assignment, &mut one, 0, &token)
+        .unwrap_err();
+    assert_eq!(
+        error,
+        WalkError::ShardCount {
+            shards: 2,
+            executors: 1
+        }
+    );
+    assert_eq!(error.to_string(), "1 executors for 2 shards");
+
+    let mut failing = [Failing, Failing];
+    let error = monte_carlo()
+        .rank_on(&assignment, &mut failing, 0, &token)
+        .unwrap_err();
+    assert_eq!(
+        error,
+        WalkError::Executor {
+            shard: 0,
+            error: Unreachable
+        }
+    );
+    assert_eq!(error.to_string(), "executor of shard 0 failed: unreachable");
+    assert!(std::error::Error::source(&error).is_some());
+
+    token.cancel();
+    let mut executors: Vec<_> = (0..2)
+        .map(|s| LocalWalkExecutor::for_shard(&graph, &assignment, s))
+        .collect();
+    assert_eq!(
+        monte_carlo().rank_on(&assignment, &mut executors, 0, &token),
+        Err(WalkError::Cancelled)
+    );
+    let mut ranks = Vec::new();
+    assert_eq!(
+        monte_carlo().execute_with(&mut (), &graph, &mut ranks, 0, &token),
+        Err(AlgorithmError::Cancelled)
+    );
+    assert!(ranks.is_empty());
+}
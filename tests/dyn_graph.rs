@@ -0,0 +1,80 @@
+use oscoin_graph_api::dyn_graph::{DynEdge, DynGraph, ErasableId, ErasedId};
+use oscoin_graph_api::mem::{self, ArenaGraph, EdgeListGraph};
+use oscoin_graph_api::types::{EdgeData, EdgeType, NodeData};
+use oscoin_graph_api::Direction;
+
+mod common;
+
+use common::{dependency, project};
+
+fn edges() -> Vec<(u64, u64, EdgeData<f64>)> {
+    let mut dependency = dependency();
+    dependency.weight = 0.5;
+    vec![(1, 2, dependency.clone()), (1, 3, dependency)]
+}
+
+/// The same graph, with different backends.
+fn graphs() -> Vec<Box<dyn DynGraph>> {
+    let list = EdgeListGraph::from_edges(edges(), project());
+    let arena: ArenaGraph = mem::from_edges(ArenaGraph::new(), edges(), project(), |i| i as u64);
+    vec![Box::new(list), Box::new(arena)]
+}
+
+#[test]
+fn graphs_of_different_backends_are_read_alike() {
+    let ids: Vec<ErasedId> = vec![1.into(), 2.into(), 3.into()];
+
+    for graph in graphs() {
+        assert_eq!(graph.node_count(), 3);
+        let mut node_ids = graph.node_ids();
+        node_ids.sort();
+        assert_eq!(node_ids, ids);
+
+        assert!(graph.contains_node(&1.into()));
+        assert!(!graph.contains_node(&4.into()));
+        assert_eq!(graph.node_type(&2.into()), Some(&project().node_type));
+
+        let data = graph.node_data(&3.into()).unwrap();
+        assert_eq!(data.downcast_ref::<NodeData<f64>>(), Some(&project()));
+
+        let edge = DynEdge {
+            id: 1.into(),
+            from: 1.into(),
+            to: 3.into(),
+            edge_type: EdgeType::Dependency,
+            weight: 0.5,
+        };
+        assert_eq!(graph.edge(&1.into()), Some(edge.clone()));
+        assert_eq!(
+            graph.edges_directed(&3.into(), Direction::Incoming),
+            vec![edge]
+        );
+        let data = graph.edge_data(&0.into()).unwrap();
+        assert_eq!(data.downcast_ref::<EdgeData<f64>>(), Some(&edges()[0].2));
+
+        let mut neighbors = graph.neighbors(&1.into());
+        neighbors.sort();
+        assert_eq!(neighbors, ids[1..].to_vec());
+    }
+}
+
+#[test]
+fn ids_of_another_type_are_not_found() {
+    let id = ErasedId(vec![1]);
+
+    for graph in graphs() {
+        assert!(!graph.contains_node(&id));
+        assert!(graph.node_data(&id).is_none());
+        assert!(graph.edge(&id).is_none());
+        assert!(graph.neighbors(&id).is_empty());
+        assert!(graph.edges_directed(&id, Direction::Outgoing).is_empty());
+    }
+}
+
+#[test]
+fn erased_ids_round_trip() {
+    assert_eq!(u64::unerase(&42u64.erase()), Some(42));
+    assert_eq!(u32::unerase(&7u32.erase()), Some(7));
+    assert_eq!(<[u8; 32]>::unerase(&[9; 32].erase()), Some([9; 32]));
+    assert_eq!(u32::unerase(&42u64.erase()), None);
+}
@@ -0,0 +1,68 @@
+use oscoin_graph_api::bench::Workload;
+use oscoin_graph_api::mem::{ArenaGraph, EdgeListGraph};
+use oscoin_graph_api::snapshot::compact::{self, CompactGraph};
+use oscoin_graph_api::subgraph::SubgraphView;
+use oscoin_graph_api::types::EdgeData;
+use oscoin_graph_api::watch::Watched;
+use oscoin_graph_api::{Direction, Edge, Graph, GraphDataWriter, GraphObject};
+
+/// Check that the edge refs of every node, in every direction, carry the
+/// endpoints, data and weight of the edges they refer to. Returns the number
+/// of refs checked.
+fn assert_edge_refs_carry_data<G>(graph: &G) -> usize
+where
+    G: Graph<EdgeData = EdgeData<f64>, Weight = f64>,
+    G::Node: GraphObject<Id = u64>,
+    G::Edge: GraphObject<Id = u64>,
+{
+    let mut refs = 0;
+    for node in graph.nodes() {
+        for dir in [Direction::Outgoing, Direction::Incoming, Direction::Both] {
+            for e in graph.edges_directed(node.id(), dir) {
+                let edge = graph.get_edge(e.id).unwrap();
+                assert_eq!((e.from, e.to), (edge.source(), edge.target()));
+                assert_eq!(e.data, edge.data());
+                assert_eq!(e.weight::<f64>(), edge.weight());
+                refs += 1;
+            }
+        }
+    }
+    refs
+}
+
+#[test]
+fn edge_refs_carry_edge_data() {
+    let workload = Workload::random(30, 3, 4);
+    let graph: EdgeListGraph = workload.build();
+    let refs = assert_edge_refs_carry_data(&graph);
+    assert!(refs > 0);
+
+    let arena: ArenaGraph = workload.build();
+    assert_eq!(assert_edge_refs_carry_data(&arena), refs);
+
+    let mut bytes = Vec::new();
+    compact::write(&graph, &mut bytes).unwrap();
+    let snapshot = CompactGraph::from_bytes(bytes).unwrap();
+    assert_eq!(assert_edge_refs_carry_data(&snapshot), refs);
+
+    let watched = Watched::new(workload.build::<EdgeListGraph>());
+    assert_eq!(assert_edge_refs_carry_data(&watched), refs);
+
+    let ids: Vec<u64> = graph.nodes().map(|n| *n.id()).step_by(2).collect();
+    let view = SubgraphView::induced(&graph, &ids, |_| true);
+    assert_edge_refs_carry_data(&view);
+}
+
+#[test]
+fn edge_refs_read_the_current_weight() {
+    let mut graph: EdgeListGraph = Workload::random(10, 2, 6).build();
+    graph.edge_data_mut(&0).unwrap().weight = 0.125;
+    let source = *graph.get_edge(&0).unwrap().source();
+    let weights: Vec<f64> = graph
+        .edges_directed(&source, Direction::Outgoing)
+        .iter()
+        .filter(|e| *e.id == 0)
+        .map(|e| e.weight())
+        .collect();
+    assert_eq!(weights, vec![0.125]);
+}
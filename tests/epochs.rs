@@ -0,0 +1,167 @@
+use oscoin_graph_api::epochs::{DecayError, EdgeHistory, EpochDecay};
+use oscoin_graph_api::ledger::{Checkpoint, CheckpointApplier, Contrib};
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::types::{GraphDiff, Hash32, SipIdGenerator};
+use oscoin_graph_api::{Direction, Edge, Graph, GraphAPI, Layer, LayerInfo, LayeredGraphs};
+
+fn checkpoint(version: &str, user: u64, contributions: u32) -> Checkpoint<u64> {
+    Checkpoint {
+        project: 1,
+        version: version.as_bytes().to_vec(),
+        hash: Hash32::default(),
+        deps: Vec::new(),
+        contributions: vec![Contrib {
+            node_id: user,
+            contributions,
+        }],
+    }
+}
+
+/// The contributions of the outgoing contribution edges of a node.
+fn edge_total(graph: &EdgeListGraph, node: u64) -> u32 {
+    graph
+        .edges_directed(&node, Direction::Outgoing)
+        .into_iter()
+        .map(|e| {
+            graph
+                .get_edge(e.id)
+                .unwrap()
+                .edge_type()
+                .total_contributions()
+        })
+        .sum()
+}
+
+/// Layered graphs whose `osrank` layer holds two checkpoints of project `1`
+/// with contributions from user `2`.
+fn layered(layer: &Layer) -> LayeredGraphs<EdgeListGraph> {
+    let mut api = LayeredGraphs::new();
+    api.add_layer(
+        layer.clone(),
+        LayerInfo::new(0, "osrank"),
+        EdgeListGraph::new(),
+    );
+    let ids = SipIdGenerator::default();
+    api.apply_checkpoint(layer, &checkpoint("0.1.0", 2, 3), &ids)
+        .unwrap();
+    api.apply_checkpoint(layer, &checkpoint("0.2.0", 2, 4), &ids)
+        .unwrap();
+    api
+}
+
+#[test]
+fn decayed_totals_match_edges() {
+    let layer = Layer::from_static("osrank");
+    let mut api = layered(&layer);
+
+    let decay = EpochDecay {
+        contributions: 0.9,
+        ranks: 1.0,
+    };
+    let diffs = api.advance_epoch(&layer, &decay).unwrap();
+    assert!(!diffs.is_empty());
+
+    let graph = api.graph(&layer).unwrap();
+    for node in [1, 2] {
+        let data = graph.node_data(&node).unwrap();
+        // Each edge rounds down on its own: 2 + 3, rather than 6.
+        assert_eq!(data.node_type.total_contributions(), 5);
+        assert_eq!(
+            data.node_type.total_contributions(),
+            edge_total(graph, node)
+        );
+        assert_eq!(data.contributions.total(), 5);
+    }
+}
+
+#[test]
+fn exponential_decay_needs_a_positive_half_life() {
+    let decay = EpochDecay::exponential(1.0).unwrap();
+    assert_eq!(decay.contributions, 0.5);
+
+    for half_life in [0.0, -1.0, f64::NAN] {
+        assert!(matches!(
+            EpochDecay::exponential(half_life),
+            Err(DecayError::InvalidHalfLife(_))
+        ));
+    }
+}
+
+#[test]
+fn decayed_edges_are_reported_after_nodes() {
+    let layer = Layer::from_static("osrank");
+    let mut api = layered(&layer);
+    let edges = api.graph(&layer).unwrap().edge_count();
+
+    let decay = EpochDecay {
+        contributions: 0.5,
+        ranks: 1.0,
+    };
+    let diffs = api.advance_epoch(&layer, &decay).unwrap();
+    let kinds: Vec<&str> = diffs
+        .iter()
+        .map(|diff| match diff {
+            GraphDiff::NodeUpdated(_) => "node",
+            GraphDiff::EdgeUpdated { source, target, .. } => {
+                assert!([(&1, &2), (&2, &1)].contains(&(*source, *target)));
+                "edge"
+            }
+            _ => panic!("unexpected diff"),
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        vec!["node", "node"]
+            .into_iter()
+            .chain(vec!["edge"; edges])
+            .collect::<Vec<_>>()
+    );
+
+    // Nothing changes without decay.
+    let decay = EpochDecay {
+        contributions: 1.0,
+        ranks: 1.0,
+    };
+    assert!(api.advance_epoch(&layer, &decay).unwrap().is_empty());
+}
+
+#[test]
+fn histories_record_contributions_by_epoch() {
+    let mut history = EdgeHistory::new();
+    assert!(history.is_empty());
+    history.append(5, 2);
+    history.append(1, 4);
+    history.append(5, 3);
+    history.append(3, 0);
+    assert_eq!(history.entries(), &[(1, 4), (5, 5)]);
+
+    assert_eq!(history.window(0, 10), 9);
+    assert_eq!(history.window(2, 5), 5);
+    assert_eq!(history.window(2, 4), 0);
+    assert_eq!(history.window(5, 1), 0);
+
+    history.append(7, u32::MAX);
+    assert_eq!(history.window(0, 10), u32::MAX);
+    history.append(7, 1);
+    assert_eq!(history.entries()[2], (7, u32::MAX));
+
+    history.truncate_before(5);
+    assert_eq!(history.entries(), &[(5, 5), (7, u32::MAX)]);
+}
+
+#[test]
+fn recent_contributions_weigh_more() {
+    let mut history = EdgeHistory::new();
+    history.append(1, 8);
+    history.append(3, 4);
+    history.append(4, 100);
+
+    let decay = EpochDecay {
+        contributions: 0.5,
+        ranks: 1.0,
+    };
+    // The contributions of epoch 4 are in the future.
+    assert!((history.decayed(3, &decay) - (8.0 * 0.25 + 4.0)).abs() < 1e-12);
+    assert!((history.decayed(3, &EpochDecay::none()) - 12.0).abs() < 1e-12);
+    assert_eq!(history.decayed(0, &decay), 0.0);
+}
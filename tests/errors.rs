@@ -0,0 +1,62 @@
+use std::error::Error;
+
+use oscoin_graph_api::cancel::Cancelled;
+use oscoin_graph_api::types::{AlgorithmError, EdgeTypeTag, HyperParameters};
+
+fn cancellable(cancelled: bool) -> Result<(), AlgorithmError> {
+    if cancelled {
+        Err(Cancelled)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn missing_hyperparameters_are_reported() {
+    let mut hyperparams: HyperParameters<f64> = HyperParameters::osrank_paper_defaults();
+    assert!(hyperparams.try_get_param(&EdgeTypeTag::Dependency).is_ok());
+
+    hyperparams.edge_weights.remove(&EdgeTypeTag::Dependency);
+    assert_eq!(
+        hyperparams.try_get_param(&EdgeTypeTag::Dependency),
+        Err(AlgorithmError::MissingHyperParameter(
+            EdgeTypeTag::Dependency
+        ))
+    );
+}
+
+#[test]
+fn errors_are_described() {
+    let errors = vec![
+        (
+            AlgorithmError::MissingHyperParameter(EdgeTypeTag::Donation),
+            "hyperparam value for Donation not found",
+        ),
+        (
+            AlgorithmError::NotConverged {
+                iterations: 100,
+                residual: 0.5,
+            },
+            "did not converge after 100 iterations (residual 0.5)",
+        ),
+        (
+            AlgorithmError::InvariantViolated("dangling edge 3".into()),
+            "graph invariant violated: dangling edge 3",
+        ),
+        (AlgorithmError::Cancelled, "execution was cancelled"),
+        (
+            AlgorithmError::Annotator("disk full".into()),
+            "annotator failure: disk full",
+        ),
+    ];
+    for (error, message) in errors {
+        let boxed: Box<dyn Error> = Box::new(error);
+        assert_eq!(boxed.to_string(), message);
+        assert!(boxed.source().is_none());
+    }
+}
+
+#[test]
+fn cancellation_converts_to_an_algorithm_error() {
+    assert_eq!(cancellable(false), Ok(()));
+    assert_eq!(cancellable(true), Err(AlgorithmError::Cancelled));
+}
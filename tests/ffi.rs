@@ -0,0 +1,115 @@
+#![cfg(feature = "ffi")]
+
+use std::ptr;
+
+use oscoin_graph_api::ffi::*;
+
+/// A user contributing to a project, and the project's contributor edge
+/// back.
+unsafe fn contribution() -> *mut OscGraph {
+    let graph = osc_graph_new();
+    assert_eq!(osc_graph_add_node(graph, 1, 0, 0), OSC_OK);
+    assert_eq!(osc_graph_add_node(graph, 2, 1, 3), OSC_OK);
+    assert_eq!(osc_graph_add_edge(graph, 0, 1, 2, 1, 3, 1.0), OSC_OK);
+    assert_eq!(osc_graph_add_edge(graph, 1, 2, 1, 0, 3, 1.0), OSC_OK);
+    graph
+}
+
+#[test]
+fn graphs_count_nodes_and_edges() {
+    unsafe {
+        let graph = osc_graph_new();
+        assert_eq!(osc_graph_node_count(graph), 0);
+        assert_eq!(osc_graph_edge_count(graph), 0);
+        osc_graph_free(graph);
+
+        let graph = contribution();
+        assert_eq!(osc_graph_node_count(graph), 2);
+        assert_eq!(osc_graph_edge_count(graph), 2);
+
+        // Replacing a node or an edge doesn't add one.
+        assert_eq!(osc_graph_add_node(graph, 1, 0, 0), OSC_OK);
+        assert_eq!(osc_graph_add_edge(graph, 0, 1, 2, 1, 5, 1.0), OSC_OK);
+        assert_eq!(osc_graph_node_count(graph), 2);
+        assert_eq!(osc_graph_edge_count(graph), 2);
+        osc_graph_free(graph);
+    }
+}
+
+#[test]
+fn unknown_type_codes_are_rejected() {
+    unsafe {
+        let graph = contribution();
+        assert_eq!(osc_graph_add_node(graph, 3, 42, 0), OSC_INVALID_TYPE);
+        assert_eq!(
+            osc_graph_add_edge(graph, 2, 1, 2, 42, 0, 1.0),
+            OSC_INVALID_TYPE
+        );
+        // Custom edge types have no code to decode.
+        assert_eq!(
+            osc_graph_add_edge(graph, 2, 1, 2, 8, 0, 1.0),
+            OSC_INVALID_TYPE
+        );
+        assert_eq!(osc_graph_node_count(graph), 2);
+        assert_eq!(osc_graph_edge_count(graph), 2);
+        osc_graph_free(graph);
+    }
+}
+
+#[test]
+fn edges_need_both_endpoints() {
+    unsafe {
+        let graph = contribution();
+        assert_eq!(osc_graph_add_edge(graph, 2, 1, 9, 4, 0, 1.0), OSC_NOT_FOUND);
+        assert_eq!(osc_graph_add_edge(graph, 2, 9, 1, 4, 0, 1.0), OSC_NOT_FOUND);
+        assert_eq!(osc_graph_edge_count(graph), 2);
+        osc_graph_free(graph);
+    }
+}
+
+#[test]
+fn null_pointers_are_reported() {
+    unsafe {
+        let null = ptr::null_mut();
+        let mut rank = 0.0;
+        assert_eq!(osc_graph_node_count(null), 0);
+        assert_eq!(osc_graph_edge_count(null), 0);
+        assert_eq!(osc_graph_add_node(null, 1, 0, 0), OSC_NULL_POINTER);
+        assert_eq!(
+            osc_graph_add_edge(null, 0, 1, 2, 4, 0, 1.0),
+            OSC_NULL_POINTER
+        );
+        assert_eq!(osc_graph_rank(null, 0.85, 0.85, 10), OSC_NULL_POINTER);
+        assert_eq!(osc_graph_node_rank(null, 1, &mut rank), OSC_NULL_POINTER);
+        osc_graph_free(null);
+
+        let graph = contribution();
+        assert_eq!(
+            osc_graph_node_rank(graph, 1, ptr::null_mut()),
+            OSC_NULL_POINTER
+        );
+        osc_graph_free(graph);
+    }
+}
+
+#[test]
+fn ranks_are_stored_on_the_nodes() {
+    unsafe {
+        let graph = contribution();
+        let mut user = -1.0;
+        let mut project = -1.0;
+        assert_eq!(osc_graph_node_rank(graph, 1, &mut user), OSC_OK);
+        assert_eq!(user, 0.0);
+
+        assert_eq!(osc_graph_rank(graph, 0.85, 0.85, 10), OSC_OK);
+        assert_eq!(osc_graph_node_rank(graph, 1, &mut user), OSC_OK);
+        assert_eq!(osc_graph_node_rank(graph, 2, &mut project), OSC_OK);
+        assert!(user > 0.0 && project > 0.0);
+        assert!((user + project - 1.0).abs() < 1e-9);
+
+        let mut missing = -1.0;
+        assert_eq!(osc_graph_node_rank(graph, 9, &mut missing), OSC_NOT_FOUND);
+        assert_eq!(missing, -1.0);
+        osc_graph_free(graph);
+    }
+}
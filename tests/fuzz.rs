@@ -0,0 +1,31 @@
+#![cfg(feature = "fuzz")]
+
+use oscoin_graph_api::fuzz::{fuzz_apply_checkpoint, fuzz_parse_snapshot};
+use oscoin_graph_api::rng::{AlgorithmSeed, SeedableAlgorithmRng};
+
+/// Runs over random inputs, to keep the entry points passing between
+/// fuzzing campaigns with `cargo fuzz`.
+const RUNS: u64 = 2_000;
+
+/// Random inputs of up to 512 bytes.
+fn inputs() -> impl Iterator<Item = Vec<u8>> {
+    (0..RUNS).map(|seed| {
+        let mut rng = seed.into_rng();
+        let len = (rng.next_u64() % 512) as usize;
+        (0..len).map(|_| rng.next_u64() as u8).collect()
+    })
+}
+
+#[test]
+fn random_checkpoints_keep_the_invariants() {
+    for bytes in inputs() {
+        fuzz_apply_checkpoint(&bytes);
+    }
+}
+
+#[test]
+fn random_snapshots_are_read_safely() {
+    for bytes in inputs() {
+        fuzz_parse_snapshot(&bytes);
+    }
+}
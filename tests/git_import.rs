@@ -0,0 +1,102 @@
+use oscoin_graph_api::importers::git::{
+    import_contributions, read_contributions, read_git_log, Contributions,
+};
+use oscoin_graph_api::importers::{ImportError, ImportMapping, SipMapping};
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::stats::NodeKind;
+use oscoin_graph_api::{Direction, Edge, Graph, GraphObject};
+
+fn import(graph: &mut EdgeListGraph, csv: &str) -> (usize, usize, usize) {
+    let mut contributions = Contributions::new();
+    read_contributions(csv.as_bytes(), &mut contributions).unwrap();
+    let summary = import_contributions(graph, &mut SipMapping::default(), &contributions).unwrap();
+    (summary.nodes, summary.edges, summary.updated_edges)
+}
+
+fn id(kind: NodeKind, name: &str) -> u64 {
+    ImportMapping::<EdgeListGraph>::node_id(&mut SipMapping::default(), kind, name)
+}
+
+/// Check that the contributions of every node are those of its edges, and
+/// return the contributions of a node.
+fn total(graph: &EdgeListGraph, kind: NodeKind, name: &str) -> u32 {
+    for node in graph.nodes() {
+        let edges: u32 = graph
+            .edges_directed(node.id(), Direction::Outgoing)
+            .into_iter()
+            .map(|e| {
+                graph
+                    .get_edge(e.id)
+                    .unwrap()
+                    .edge_type()
+                    .total_contributions()
+            })
+            .sum();
+        assert_eq!(node.data().node_type.total_contributions(), edges);
+    }
+    graph
+        .node_data(&id(kind, name))
+        .unwrap()
+        .node_type
+        .total_contributions()
+}
+
+#[test]
+fn reimporting_changes_nothing() {
+    let mut graph = EdgeListGraph::new();
+    let csv = "radicle,alice,3\nradicle,bob,2\noscoin,alice,1\n";
+    assert_eq!(import(&mut graph, csv), (4, 6, 0));
+    assert_eq!(import(&mut graph, csv), (0, 0, 0));
+
+    assert_eq!(graph.edge_count(), 6);
+    assert_eq!(total(&graph, NodeKind::User, "alice"), 4);
+    assert_eq!(total(&graph, NodeKind::Project, "radicle"), 5);
+}
+
+#[test]
+fn changed_counts_update_edges() {
+    let mut graph = EdgeListGraph::new();
+    import(&mut graph, "radicle,alice,3\noscoin,alice,1\n");
+    assert_eq!(import(&mut graph, "radicle,alice,5\n"), (0, 0, 2));
+
+    assert_eq!(graph.edge_count(), 4);
+    assert_eq!(total(&graph, NodeKind::User, "alice"), 6);
+    assert_eq!(total(&graph, NodeKind::Project, "radicle"), 5);
+    assert_eq!(total(&graph, NodeKind::Project, "oscoin"), 1);
+}
+
+#[test]
+fn overflowing_counts_are_rejected() {
+    let mut contributions = Contributions::new();
+    let csv = format!("radicle,alice,{}\nradicle,alice,1\n", u32::MAX);
+    assert!(matches!(
+        read_contributions(csv.as_bytes(), &mut contributions),
+        Err(ImportError::Parse(_))
+    ));
+
+    let mut contributions = Contributions::new();
+    let csv = format!("radicle,alice,{}\noscoin,alice,1\n", u32::MAX);
+    read_contributions(csv.as_bytes(), &mut contributions).unwrap();
+    let mut graph: EdgeListGraph = EdgeListGraph::new();
+    assert!(matches!(
+        import_contributions(&mut graph, &mut SipMapping::default(), &contributions),
+        Err(ImportError::Parse(_))
+    ));
+    assert_eq!(graph.node_count(), 0);
+}
+
+#[test]
+fn logs_count_commits() {
+    let mut contributions = Contributions::new();
+    read_git_log(
+        "radicle",
+        "alice\nbob\n\nalice\n".as_bytes(),
+        &mut contributions,
+    )
+    .unwrap();
+    assert_eq!(
+        contributions[&("radicle".to_owned(), "alice".to_owned())],
+        2
+    );
+    assert_eq!(contributions[&("radicle".to_owned(), "bob".to_owned())], 1);
+}
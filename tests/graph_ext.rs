@@ -0,0 +1,109 @@
+use oscoin_graph_api::dyn_graph::{DynGraph, ErasedId};
+use oscoin_graph_api::mem::{EdgeListGraph, MemNode};
+use oscoin_graph_api::{Direction, GraphExt, GraphObject};
+
+mod common;
+
+use common::{dependency, project};
+
+fn graph() -> EdgeListGraph {
+    EdgeListGraph::from_edges(vec![(1, 2, dependency()), (2, 3, dependency())], project())
+}
+
+#[test]
+fn graph_ext_and_dyn_graph_can_be_imported_together() {
+    let graph = graph();
+    assert_eq!(graph.all_node_ids(), vec![&1, &2, &3]);
+    assert_eq!(
+        graph.node_ids(),
+        vec![ErasedId::from(1), ErasedId::from(2), ErasedId::from(3)]
+    );
+    assert!(graph.contains_edge_between(&1, &2));
+    assert!(!graph.is_empty());
+}
+
+/// Parallel edges from `1` to `2`, edges both ways between `1` and `3`, and
+/// a self-loop on `1`.
+fn multigraph() -> EdgeListGraph {
+    EdgeListGraph::from_edges(
+        vec![
+            (1, 3, dependency()),
+            (1, 2, dependency()),
+            (1, 2, dependency()),
+            (3, 1, dependency()),
+            (1, 1, dependency()),
+        ],
+        project(),
+    )
+}
+
+/// The ids of a list of nodes.
+fn ids<'a>(nodes: impl Iterator<Item = &'a MemNode<u64, f64>>) -> Vec<u64> {
+    nodes.map(|n| *n.id()).collect()
+}
+
+#[test]
+fn neighbors_are_listed_once_per_edge_or_distinct() {
+    let graph = multigraph();
+    assert_eq!(ids(graph.out_neighbors(&1)), vec![3, 2, 2, 1]);
+    assert_eq!(ids(graph.in_neighbors(&1)), vec![3, 1]);
+    assert_eq!(
+        ids(graph.directed_neighbors(&1, Direction::Both)),
+        vec![3, 2, 2, 3, 1]
+    );
+
+    assert_eq!(
+        ids(graph.distinct_neighbors(&1, Direction::Outgoing)),
+        vec![3, 2, 1]
+    );
+    assert_eq!(
+        ids(graph.distinct_neighbors(&1, Direction::Incoming)),
+        vec![3, 1]
+    );
+    assert_eq!(
+        ids(graph.distinct_neighbors(&1, Direction::Both)),
+        vec![3, 2, 1]
+    );
+    assert_eq!(ids(graph.distinct_neighbors(&2, Direction::Both)), vec![1]);
+    assert!(ids(graph.distinct_neighbors(&9, Direction::Both)).is_empty());
+}
+
+#[test]
+fn neighbors_are_ordered_by_weight() {
+    let weighted = |to, weight| {
+        let mut data = dependency();
+        data.weight = weight;
+        (1, to, data)
+    };
+    let graph = EdgeListGraph::from_edges(
+        vec![
+            weighted(5, 0.5),
+            weighted(6, f64::NAN),
+            weighted(2, 2.0),
+            weighted(4, f64::NAN),
+            weighted(3, 0.5),
+            weighted(3, 1.0),
+        ],
+        project(),
+    );
+
+    let order = |dir| -> Vec<(u64, String)> {
+        graph
+            .neighbors_by_weight(&1, dir)
+            .into_iter()
+            .map(|(n, w)| (*n.id(), w.to_string()))
+            .collect()
+    };
+    let expected: Vec<(u64, String)> = vec![
+        (2, "2".to_owned()),
+        (3, "1".to_owned()),
+        (3, "0.5".to_owned()),
+        (5, "0.5".to_owned()),
+        (4, "NaN".to_owned()),
+        (6, "NaN".to_owned()),
+    ];
+    assert_eq!(order(Direction::Outgoing), expected);
+    assert_eq!(order(Direction::Both), expected);
+    assert!(order(Direction::Incoming).is_empty());
+    assert_eq!(graph.neighbors_by_weight(&3, Direction::Incoming).len(), 2);
+}
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use oscoin_graph_api::algorithms::{Hits, HitsScore};
+use oscoin_graph_api::cancel::CancellationToken;
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::types::{AlgorithmError, EdgeTypeTag, HyperParameters};
+use oscoin_graph_api::GraphAlgorithm;
+
+mod common;
+
+use common::{dependencies, project};
+
+type Annotations = Vec<(u64, HitsScore<f64>)>;
+
+/// Hubs `1` and `2` pointing to authorities `3` and `4`, with `2` pointing
+/// to both.
+fn graph() -> EdgeListGraph {
+    let edges = dependencies(&[(1, 3), (2, 3), (2, 4)]);
+    EdgeListGraph::from_edges(edges, project())
+}
+
+fn hits() -> Hits<f64> {
+    Hits::new(HyperParameters::osrank_paper_defaults())
+}
+
+#[test]
+fn scores_follow_the_principal_eigenvectors() {
+    let mut annotations = Annotations::new();
+    let scores = hits()
+        .execute(&mut (), &graph(), &mut annotations, 0)
+        .unwrap();
+
+    // The authorities are the eigenvector of `[[2, 1], [1, 1]]`, in golden
+    // ratio, and so are the hubs.
+    let phi = (1.0 + 5f64.sqrt()) / 2.0;
+    let expected = vec![
+        (1, 0.0, 1.0 / (phi * phi)),
+        (2, 0.0, 1.0 / phi),
+        (3, 1.0 / phi, 0.0),
+        (4, 1.0 / (phi * phi), 0.0),
+    ];
+    assert_eq!(scores.len(), 4);
+    for (id, authority, hub) in expected {
+        assert!((scores[&id].authority - authority).abs() < 1e-6, "{}", id);
+        assert!((scores[&id].hub - hub).abs() < 1e-6, "{}", id);
+    }
+
+    let order: Vec<u64> = annotations.iter().map(|(id, _)| *id).collect();
+    assert_eq!(order, vec![1, 2, 3, 4]);
+    let annotated: HashMap<u64, HitsScore<f64>> = annotations.into_iter().collect();
+    assert_eq!(annotated, scores);
+}
+
+#[test]
+fn iterations_are_bounded() {
+    let mut hits = hits();
+    hits.max_iterations = 1;
+    let mut annotations = Annotations::new();
+    match hits.execute(&mut (), &graph(), &mut annotations, 0) {
+        Err(AlgorithmError::NotConverged {
+            iterations,
+            residual,
+        }) => {
+            assert_eq!(iterations, 1);
+            assert!(residual > hits.tolerance);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert!(annotations.is_empty());
+}
+
+#[test]
+fn edge_types_need_a_weight() {
+    let mut hits = hits();
+    hits.hyperparams
+        .edge_weights
+        .remove(&EdgeTypeTag::Dependency);
+    let result = hits.execute(&mut (), &graph(), &mut Annotations::new(), 0);
+    assert_eq!(
+        result,
+        Err(AlgorithmError::MissingHyperParameter(
+            EdgeTypeTag::Dependency
+        ))
+    );
+}
+
+#[test]
+fn executions_can_be_cancelled() {
+    let token = CancellationToken::new();
+    token.cancel();
+    let mut annotations = Annotations::new();
+    let result = hits().execute_with(&mut (), &graph(), &mut annotations, 0, &token);
+    assert_eq!(result, Err(AlgorithmError::Cancelled));
+    assert!(annotations.is_empty());
+}
@@ -0,0 +1,140 @@
+use oscoin_graph_api::types::{
+    EdgeType, Hash32, IdGenerator, NodeId, ParseHashError, SipIdGenerator,
+};
+
+#[test]
+fn edge_ids_are_stable() {
+    let ids = SipIdGenerator::default();
+    // Pinned, as ids are persisted and must not change across versions.
+    assert_eq!(
+        ids.edge_id(&1u64, &2u64, &EdgeType::Dependency, b"v1"),
+        11602938295247130822
+    );
+    assert_eq!(ids.name_id("crate", "serde"), 11623205249316657646);
+}
+
+#[test]
+fn edge_ids_ignore_edge_values() {
+    let ids = SipIdGenerator::default();
+    let id = |from: u64, to: u64, edge_type: EdgeType, version: &[u8]| {
+        ids.edge_id(&from, &to, &edge_type, version)
+    };
+    let contribution = EdgeType::UserToProjectContribution;
+
+    assert_eq!(
+        id(1, 2, contribution(3), b"v1"),
+        id(1, 2, contribution(7), b"v1")
+    );
+    assert_ne!(
+        id(1, 2, contribution(3), b"v1"),
+        id(2, 1, contribution(3), b"v1")
+    );
+    assert_ne!(
+        id(1, 2, contribution(3), b"v1"),
+        id(1, 2, contribution(3), b"v2")
+    );
+    assert_ne!(
+        id(1, 2, contribution(3), b"v1"),
+        id(1, 2, EdgeType::ProjectToUserContribution(3), b"v1")
+    );
+}
+
+#[test]
+fn keys_and_namespaces_separate_ids() {
+    let ids = SipIdGenerator::default();
+    let other = SipIdGenerator::with_keys(1, 2);
+    let dependency = EdgeType::Dependency;
+
+    assert_ne!(
+        ids.edge_id(&1u64, &2u64, &dependency, b"v1"),
+        other.edge_id(&1u64, &2u64, &dependency, b"v1")
+    );
+    assert_ne!(ids.name_id("crate", "serde"), ids.name_id("user", "serde"));
+    // Names are length-prefixed, so they can't run into the namespace.
+    assert_ne!(ids.name_id("ab", "c"), ids.name_id("a", "bc"));
+}
+
+fn hash(byte: u8) -> Hash32 {
+    Hash32([byte; 32])
+}
+
+#[test]
+fn hashes_are_written_in_hexadecimal() {
+    let digits = "ab".repeat(32);
+    assert_eq!(hash(0xab).to_string(), digits);
+    assert_eq!(format!("{:?}", hash(0xab)), format!("Hash32({})", digits));
+
+    assert_eq!(digits.parse(), Ok(hash(0xab)));
+    assert_eq!(digits.to_uppercase().parse(), Ok(hash(0xab)));
+    assert_eq!(format!("0x{}", digits).parse(), Ok(hash(0xab)));
+
+    assert_eq!(
+        "ab".parse::<Hash32>(),
+        Err(ParseHashError::InvalidLength(2))
+    );
+    assert_eq!(
+        format!("{}g", "a".repeat(63)).parse::<Hash32>(),
+        Err(ParseHashError::InvalidChar('g'))
+    );
+    assert_eq!(
+        format!("{}é", "a".repeat(62)).parse::<Hash32>(),
+        Err(ParseHashError::InvalidChar('é'))
+    );
+}
+
+#[test]
+fn node_ids_are_written_with_their_version() {
+    let id = NodeId::new(hash(1), &b"v1"[..]);
+    let written = format!("{}@7631", "01".repeat(32));
+    assert_eq!(id.to_string(), written);
+    assert_eq!(written.parse(), Ok(id));
+    assert_eq!(
+        format!("{}@", "01".repeat(32)).parse(),
+        Ok(NodeId::new(hash(1), Vec::new()))
+    );
+
+    assert_eq!(
+        "01".repeat(32).parse::<NodeId>(),
+        Err(ParseHashError::MissingVersion)
+    );
+    assert_eq!(
+        format!("{}@763", "01".repeat(32)).parse::<NodeId>(),
+        Err(ParseHashError::InvalidLength(3))
+    );
+    assert_eq!(
+        "01@76".parse::<NodeId>(),
+        Err(ParseHashError::InvalidLength(2))
+    );
+    assert_eq!(
+        format!("{}@7é", "01".repeat(32)).parse::<NodeId>(),
+        Err(ParseHashError::InvalidChar('é'))
+    );
+}
+
+#[test]
+fn node_id_versions_are_delimited_in_edge_ids() {
+    let ids = SipIdGenerator::default();
+    let id = |from: &[u8], to: &[u8]| {
+        let from = NodeId::new(hash(1), from);
+        let to = NodeId::new(hash(1), to);
+        ids.edge_id(&from, &to, &EdgeType::Dependency, b"v1")
+    };
+    assert_eq!(id(b"a", b"bc"), id(b"a", b"bc"));
+    assert_ne!(id(b"a", b"bc"), id(b"ab", b"c"));
+    assert_ne!(id(b"a", b"b"), id(b"b", b"a"));
+}
+
+#[test]
+fn custom_tags_separate_ids() {
+    let ids = SipIdGenerator::default();
+    let id = |tag: &str, version: &[u8]| {
+        let custom = EdgeType::Custom {
+            tag: tag.to_owned(),
+            contributions: 1,
+        };
+        ids.edge_id(&1u64, &2u64, &custom, version)
+    };
+    assert_eq!(id("review", b"v1"), id("review", b"v1"));
+    assert_ne!(id("review", b"v1"), id("audit", b"v1"));
+    assert_ne!(id("a", b"bv1"), id("ab", b"v1"));
+}
@@ -0,0 +1,218 @@
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::{
+    DefaultBuilder, Graph, GraphAPI, GraphBuilder, GraphWriter, Layer, LayerError, LayerInfo,
+    LayerNameError, LayerRemoval, LayeredGraphs,
+};
+
+mod common;
+
+use common::{dependency, project};
+
+const OSRANK: Layer = Layer::from_static("osrank");
+
+/// Add a chain of `n` nodes to the layer.
+fn add_chain(api: &mut LayeredGraphs<EdgeListGraph>, layer: &Layer, n: u64) {
+    let graph = api.graph_mut(layer).unwrap();
+    for id in 0..n {
+        graph.add_node(id, project());
+    }
+    for id in 1..n {
+        let data = dependency();
+        graph.add_edge(id, &(id - 1), &id, data);
+    }
+}
+
+/// Builds graphs stamping their objects with the given epoch.
+struct AtEpoch(u64);
+
+impl GraphBuilder for AtEpoch {
+    type Graph = EdgeListGraph;
+
+    fn build(self) -> EdgeListGraph {
+        let mut graph = EdgeListGraph::new();
+        graph.set_epoch(self.0);
+        graph
+    }
+}
+
+/// Layered graphs with an empty `osrank` layer.
+fn layered() -> LayeredGraphs<EdgeListGraph> {
+    let mut api = LayeredGraphs::new();
+    api.add_layer(OSRANK, LayerInfo::new(7, "osrank"), EdgeListGraph::new());
+    api
+}
+
+#[test]
+fn layer_names_are_validated() {
+    let epoch = 42;
+    let layer = Layer::new(format!("osrank-epoch-{}", epoch)).unwrap();
+    assert_eq!(layer.as_str(), "osrank-epoch-42");
+    assert_eq!(layer, "osrank-epoch-42".parse().unwrap());
+    assert_eq!(layer.to_string(), "osrank-epoch-42");
+
+    assert_eq!(Layer::new(""), Err(LayerNameError::Empty));
+    assert_eq!(
+        Layer::new("a".repeat(Layer::MAX_LEN + 1)),
+        Err(LayerNameError::TooLong(Layer::MAX_LEN + 1))
+    );
+    assert_eq!(
+        "Osrank".parse::<Layer>(),
+        Err(LayerNameError::InvalidChar('O'))
+    );
+    assert!(Layer::new("a".repeat(Layer::MAX_LEN)).is_ok());
+}
+
+#[test]
+fn frozen_layers_are_read_only() {
+    let mut api = layered();
+    api.graph_mut(&OSRANK).unwrap().add_node(1, project());
+
+    // Adding a layer again keeps the existing one.
+    api.add_layer(OSRANK, LayerInfo::new(9, "other"), EdgeListGraph::new());
+    let info = api.layer_info(&OSRANK).unwrap();
+    assert_eq!((info.created_at, info.read_only), (7, false));
+    assert_eq!(info.description, "osrank");
+
+    api.freeze_layer(&OSRANK).unwrap();
+    assert!(api.layer_info(&OSRANK).unwrap().read_only);
+    assert!(api.graph_mut(&OSRANK).is_none());
+    assert!(api.graph(&OSRANK).unwrap().get_node(&1).is_some());
+
+    let missing = Layer::from_static("missing");
+    assert_eq!(
+        api.freeze_layer(&missing),
+        Err(LayerError::NotFound(missing))
+    );
+}
+
+#[test]
+fn only_empty_layers_are_removed_without_cascading() {
+    let mut api = layered();
+    add_chain(&mut api, &OSRANK, 3);
+
+    assert_eq!(
+        api.remove_layer(&OSRANK),
+        Err(LayerError::NotEmpty {
+            layer: OSRANK,
+            nodes: 3
+        })
+    );
+    assert!(api.contains_layer(&OSRANK));
+    assert_eq!(
+        api.remove_layer_cascade(&OSRANK),
+        Ok(LayerRemoval { nodes: 3, edges: 2 })
+    );
+    assert!(!api.contains_layer(&OSRANK));
+    assert_eq!(api.remove_layer(&OSRANK), Err(LayerError::NotFound(OSRANK)));
+
+    let mut api = layered();
+    assert_eq!(api.remove_layer(&OSRANK), Ok(()));
+    assert_eq!(api.layers().count(), 0);
+}
+
+#[test]
+fn cleared_layers_are_kept_empty() {
+    let mut api = layered();
+    let epoch = Layer::from_static("epoch");
+    api.add_layer(
+        epoch.clone(),
+        LayerInfo::new(8, "epoch"),
+        EdgeListGraph::new(),
+    );
+    add_chain(&mut api, &OSRANK, 3);
+    add_chain(&mut api, &epoch, 2);
+    api.link_across(&OSRANK, &0, &epoch, &1).unwrap();
+
+    assert_eq!(
+        api.clear_layer(&epoch),
+        Ok(LayerRemoval { nodes: 2, edges: 1 })
+    );
+    assert!(api.contains_layer(&epoch));
+    assert_eq!(api.graph(&epoch).unwrap().node_count(), 0);
+    assert_eq!(api.graph(&OSRANK).unwrap().node_count(), 3);
+
+    // Links are dropped in both directions, even once nodes come back.
+    add_chain(&mut api, &epoch, 2);
+    assert_eq!(api.linked(&OSRANK, &0, &epoch), None);
+    assert_eq!(api.linked(&epoch, &1, &OSRANK), None);
+
+    let missing = Layer::from_static("missing");
+    assert_eq!(
+        api.clear_layer(&missing),
+        Err(LayerError::NotFound(missing))
+    );
+}
+
+#[test]
+fn frozen_layers_are_not_removed_or_cleared() {
+    let mut api = layered();
+    add_chain(&mut api, &OSRANK, 2);
+    let empty = Layer::from_static("empty");
+    api.add_layer(
+        empty.clone(),
+        LayerInfo::new(8, "empty"),
+        EdgeListGraph::new(),
+    );
+    api.freeze_layer(&OSRANK).unwrap();
+    api.freeze_layer(&empty).unwrap();
+
+    let read_only = Err(LayerError::ReadOnly(OSRANK));
+    assert_eq!(api.remove_layer_cascade(&OSRANK), read_only);
+    assert_eq!(api.clear_layer(&OSRANK), read_only);
+    assert_eq!(api.remove_layer(&empty), Err(LayerError::ReadOnly(empty)));
+    assert_eq!(api.graph(&OSRANK).unwrap().nodes().count(), 2);
+    assert_eq!(api.layers().count(), 2);
+}
+
+#[test]
+fn nodes_are_projected_through_links() {
+    let mut api = layered();
+    let epoch = Layer::from_static("epoch");
+    api.add_layer(
+        epoch.clone(),
+        LayerInfo::new(8, "epoch"),
+        EdgeListGraph::new(),
+    );
+    add_chain(&mut api, &OSRANK, 4);
+    add_chain(&mut api, &epoch, 4);
+
+    api.link_across(&OSRANK, &0, &epoch, &2).unwrap();
+    api.link_across(&OSRANK, &1, &epoch, &3).unwrap();
+    assert_eq!(api.linked(&epoch, &2, &OSRANK), Some(&0));
+
+    // Relinking replaces the previous link, in both directions.
+    api.link_across(&OSRANK, &0, &epoch, &1).unwrap();
+    assert_eq!(api.linked(&OSRANK, &0, &epoch), Some(&1));
+    assert_eq!(api.linked(&epoch, &2, &OSRANK), None);
+
+    // Nodes without a counterpart, or whose counterpart is gone, are skipped.
+    api.graph_mut(&epoch).unwrap().remove_node(3);
+    assert_eq!(api.project(&OSRANK, &[2, 1, 0], &epoch), vec![&1]);
+
+    assert_eq!(
+        api.link_across(&OSRANK, &0, &epoch, &9),
+        Err(LayerError::NodeNotFound(epoch.clone()))
+    );
+    api.remove_layer_cascade(&epoch).unwrap();
+    assert_eq!(api.linked(&OSRANK, &0, &epoch), None);
+}
+
+#[test]
+fn layers_are_created_by_builders() {
+    let mut api = layered();
+    let (epoch, default) = (Layer::from_static("epoch"), Layer::from_static("default"));
+    api.add_layer(
+        epoch.clone(),
+        LayerInfo::new(8, "at epoch 3"),
+        AtEpoch(3).with_capacity(10, 20),
+    );
+    api.add_layer(
+        default.clone(),
+        LayerInfo::new(9, "default"),
+        DefaultBuilder::new(),
+    );
+
+    assert_eq!(api.graph(&epoch).unwrap().epoch(), Some(3));
+    assert_eq!(api.graph(&default).unwrap().epoch(), None);
+    assert_eq!(api.graph(&default).unwrap().nodes().count(), 0);
+}
@@ -0,0 +1,254 @@
+use oscoin_graph_api::compare::eq_by_ids;
+use oscoin_graph_api::ledger::{
+    validate_checkpoint, Checkpoint, CheckpointApplier, CheckpointError, CheckpointViolation,
+    Contrib, Dep,
+};
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::types::{ContributionLedger, EdgeType, Hash32, NodeType, SipIdGenerator};
+use oscoin_graph_api::{
+    Direction, Edge, Graph, GraphAPI, Layer, LayerError, LayerInfo, LayeredGraphs,
+};
+
+const OSRANK: Layer = Layer::from_static("osrank");
+
+fn layered() -> LayeredGraphs<EdgeListGraph> {
+    let mut api = LayeredGraphs::new();
+    api.add_layer(OSRANK, LayerInfo::new(0, "osrank"), EdgeListGraph::new());
+    api
+}
+
+fn checkpoint(version: &str, contributions: &[(u64, u32)]) -> Checkpoint<u64> {
+    Checkpoint {
+        project: 1,
+        version: version.as_bytes().to_vec(),
+        hash: Hash32::default(),
+        deps: Vec::new(),
+        contributions: contributions
+            .iter()
+            .map(|&(node_id, contributions)| Contrib {
+                node_id,
+                contributions,
+            })
+            .collect(),
+    }
+}
+
+/// A checkpoint of a project adding and removing dependencies, without
+/// contributions.
+fn dependencies(project: u64, version: &str, deps: &[(u64, bool)]) -> Checkpoint<u64> {
+    let mut checkpoint = checkpoint(version, &[]);
+    checkpoint.project = project;
+    checkpoint.deps = deps
+        .iter()
+        .map(|&(node_id, is_added)| Dep { node_id, is_added })
+        .collect();
+    checkpoint
+}
+
+/// The types of the edges from `from` to `to`.
+fn edge_types(api: &LayeredGraphs<EdgeListGraph>, from: u64, to: u64) -> Vec<EdgeType> {
+    let graph = api.graph(&OSRANK).unwrap();
+    graph
+        .edges_directed(&from, Direction::Outgoing)
+        .into_iter()
+        .filter(|e| *e.to == to)
+        .map(|e| graph.get_edge(e.id).unwrap().edge_type().clone())
+        .collect()
+}
+
+fn total(api: &LayeredGraphs<EdgeListGraph>, layer: &Layer, node: u64) -> u32 {
+    let graph = api.graph(layer).unwrap();
+    graph
+        .node_data(&node)
+        .unwrap()
+        .node_type
+        .total_contributions()
+}
+
+#[test]
+fn checkpoints_add_projects_dependencies_and_contributions() {
+    let ids = SipIdGenerator::default();
+    let mut api = layered();
+    api.apply_checkpoint(&OSRANK, &dependencies(5, "v1", &[]), &ids)
+        .unwrap();
+    api.apply_checkpoint(&OSRANK, &dependencies(1, "v1", &[(5, true)]), &ids)
+        .unwrap();
+    api.apply_checkpoint(&OSRANK, &checkpoint("v2", &[(2, 3), (3, 1)]), &ids)
+        .unwrap();
+    api.apply_checkpoint(&OSRANK, &checkpoint("v3", &[(2, 4)]), &ids)
+        .unwrap();
+
+    let graph = api.graph(&OSRANK).unwrap();
+    assert_eq!((graph.node_count(), graph.edge_count()), (4, 7));
+    assert_eq!(edge_types(&api, 1, 5), vec![EdgeType::Dependency]);
+    let mut to_2 = edge_types(&api, 1, 2);
+    to_2.sort_by_key(|t| format!("{:?}", t));
+    assert_eq!(
+        to_2,
+        vec![
+            EdgeType::ProjectToUserContribution(3),
+            EdgeType::ProjectToUserContribution(4),
+        ]
+    );
+    assert_eq!(
+        edge_types(&api, 3, 1),
+        vec![EdgeType::UserToProjectContribution(1)]
+    );
+
+    assert_eq!(total(&api, &OSRANK, 1), 8);
+    assert_eq!(total(&api, &OSRANK, 2), 7);
+    assert_eq!(total(&api, &OSRANK, 5), 0);
+    let project = graph.node_data(&1).unwrap();
+    assert_eq!(
+        (project.contributions.get(&2), project.contributions.get(&3)),
+        (7, 1)
+    );
+    assert!(matches!(
+        graph.node_data(&2).unwrap().node_type,
+        NodeType::User { .. }
+    ));
+}
+
+#[test]
+fn removed_dependencies_drop_every_edge_to_them() {
+    let ids = SipIdGenerator::default();
+    let mut api = layered();
+    api.apply_checkpoint(&OSRANK, &dependencies(5, "v1", &[]), &ids)
+        .unwrap();
+    api.apply_checkpoint(&OSRANK, &dependencies(1, "v1", &[(5, true)]), &ids)
+        .unwrap();
+    api.apply_checkpoint(&OSRANK, &dependencies(1, "v2", &[(5, true)]), &ids)
+        .unwrap();
+    assert_eq!(edge_types(&api, 1, 5).len(), 2);
+
+    api.apply_checkpoint(&OSRANK, &dependencies(1, "v3", &[(5, false)]), &ids)
+        .unwrap();
+    assert!(edge_types(&api, 1, 5).is_empty());
+    assert!(api.graph(&OSRANK).unwrap().get_node(&5).is_some());
+}
+
+#[test]
+fn replaying_checkpoints_yields_the_same_graph() {
+    let ids = SipIdGenerator::default();
+    let checkpoints = vec![
+        dependencies(5, "v1", &[]),
+        dependencies(1, "v1", &[(5, true)]),
+        checkpoint("v2", &[(2, 3), (3, 1)]),
+        dependencies(1, "v3", &[(5, false)]),
+    ];
+    let replay = || {
+        let mut api = layered();
+        for checkpoint in &checkpoints {
+            api.apply_checkpoint(&OSRANK, checkpoint, &ids).unwrap();
+        }
+        api
+    };
+    assert!(eq_by_ids(
+        replay().graph(&OSRANK).unwrap(),
+        replay().graph(&OSRANK).unwrap()
+    ));
+}
+
+#[test]
+fn checkpoints_need_a_writable_layer() {
+    let ids = SipIdGenerator::default();
+    let mut api = layered();
+    let missing = Layer::from_static("missing");
+    assert_eq!(
+        api.apply_checkpoint(&missing, &checkpoint("v1", &[(2, 1)]), &ids),
+        Err(CheckpointError::Layer(LayerError::NotFound(missing)))
+    );
+
+    api.freeze_layer(&OSRANK).unwrap();
+    let result = api.apply_checkpoint(&OSRANK, &checkpoint("v1", &[(2, 1)]), &ids);
+    assert_eq!(
+        result,
+        Err(CheckpointError::Layer(LayerError::ReadOnly(OSRANK)))
+    );
+    assert_eq!(api.graph(&OSRANK).unwrap().node_count(), 0);
+}
+
+#[test]
+fn invalid_checkpoints_report_every_violation() {
+    let ids = SipIdGenerator::default();
+    let mut api = layered();
+    api.apply_checkpoint(&OSRANK, &checkpoint("v1", &[(2, 1)]), &ids)
+        .unwrap();
+    let graph = api.graph(&OSRANK).unwrap();
+
+    let mut invalid = dependencies(1, "v1", &[(5, true), (2, false)]);
+    invalid.contributions = checkpoint("v1", &[(2, 1), (3, 0)]).contributions;
+    let violations = validate_checkpoint(graph, &invalid, &ids).unwrap_err();
+    assert_eq!(violations.len(), 5);
+    assert_eq!(violations[0], CheckpointViolation::UnknownDependency(5));
+    assert_eq!(violations[1], CheckpointViolation::UnknownRemoval(2));
+    assert!(matches!(
+        violations[2..4],
+        [
+            CheckpointViolation::DuplicateEdge(_),
+            CheckpointViolation::DuplicateEdge(_)
+        ]
+    ));
+    assert_eq!(violations[4], CheckpointViolation::ZeroContributions(3));
+    assert!(validate_checkpoint(graph, &checkpoint("v2", &[(2, 1)]), &ids).is_ok());
+
+    let before = graph.clone();
+    let error = api.apply_checkpoint(&OSRANK, &invalid, &ids).unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("invalid checkpoint: dependency 5 not found, removed dependency 2 is not"));
+    assert!(eq_by_ids(&before, api.graph(&OSRANK).unwrap()));
+}
+
+#[test]
+fn checkpoints_may_not_add_an_edge_twice() {
+    let ids = SipIdGenerator::default();
+    let graph: EdgeListGraph = EdgeListGraph::new();
+    let violations = validate_checkpoint(&graph, &checkpoint("v1", &[(2, 1), (2, 1)]), &ids);
+    assert_eq!(violations.map_err(|v| v.len()), Err(2));
+}
+
+#[test]
+fn overflowing_checkpoints_are_rejected() {
+    let layer = OSRANK;
+    let ids = SipIdGenerator::default();
+    let mut api = layered();
+
+    let near = u32::MAX - 10;
+    api.apply_checkpoint(&layer, &checkpoint("v1", &[(2, near)]), &ids)
+        .unwrap();
+    let before = api.graph(&layer).unwrap().clone();
+
+    // The project would overflow from two users, and user 2 by itself.
+    let result = api.apply_checkpoint(&layer, &checkpoint("v2", &[(2, 11), (3, 5)]), &ids);
+    match result {
+        Err(CheckpointError::Invalid(violations)) => assert_eq!(
+            violations,
+            vec![
+                CheckpointViolation::ContributionOverflow(1),
+                CheckpointViolation::ContributionOverflow(2),
+            ]
+        ),
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert!(eq_by_ids(&before, api.graph(&layer).unwrap()));
+
+    api.apply_checkpoint(&layer, &checkpoint("v2", &[(2, 10)]), &ids)
+        .unwrap();
+    assert_eq!(total(&api, &layer, 1), u32::MAX);
+    assert_eq!(total(&api, &layer, 2), u32::MAX);
+}
+
+#[test]
+fn contribution_counts_saturate() {
+    let mut node_type = NodeType::User {
+        contributions_to_all_projects: u32::MAX - 1,
+    };
+    node_type.add_contributions(5);
+    assert_eq!(node_type.total_contributions(), u32::MAX);
+
+    let mut ledger = ContributionLedger::new();
+    ledger.add(1u64, u32::MAX);
+    ledger.add(1, 1);
+    assert_eq!(ledger.get(&1), u32::MAX);
+}
@@ -0,0 +1,97 @@
+use oscoin_graph_api::compare::eq_by_ids;
+use oscoin_graph_api::ledger::{Checkpoint, CheckpointApplier, CheckpointError, Contrib, Dep};
+use oscoin_graph_api::limits::{LimitError, Limited, Limits};
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::types::{Hash32, SipIdGenerator};
+use oscoin_graph_api::{Graph, GraphAPI, Layer, LayerInfo, LayeredGraphs};
+
+fn checkpoint(project: u64, deps: &[u64], contributions: &[(u64, u32)]) -> Checkpoint<u64> {
+    Checkpoint {
+        project,
+        version: b"v1".to_vec(),
+        hash: Hash32::default(),
+        deps: deps
+            .iter()
+            .map(|&node_id| Dep {
+                node_id,
+                is_added: true,
+            })
+            .collect(),
+        contributions: contributions
+            .iter()
+            .map(|&(node_id, contributions)| Contrib {
+                node_id,
+                contributions,
+            })
+            .collect(),
+    }
+}
+
+fn limited(max_edges_per_node: usize) -> (LayeredGraphs<Limited<EdgeListGraph>>, Layer) {
+    let layer = Layer::from_static("osrank");
+    let limits = Limits {
+        max_edges_per_node: Some(max_edges_per_node),
+        ..Limits::default()
+    };
+    let mut api = LayeredGraphs::new();
+    api.add_layer(
+        layer.clone(),
+        LayerInfo::new(0, "osrank"),
+        Limited::new(EdgeListGraph::new(), limits),
+    );
+    (api, layer)
+}
+
+/// Apply a checkpoint the limits must refuse, checking nothing was applied.
+fn assert_refused(
+    api: &mut LayeredGraphs<Limited<EdgeListGraph>>,
+    layer: &Layer,
+    checkpoint: &Checkpoint<u64>,
+    max: usize,
+) {
+    let ids = SipIdGenerator::default();
+    let before = api.graph(layer).unwrap().graph().clone();
+    match api.apply_checkpoint(layer, checkpoint, &ids) {
+        Err(CheckpointError::Limit(err)) => assert_eq!(err, LimitError::TooManyEdges { max }),
+        other => panic!("unexpected result {:?}", other),
+    }
+    let graph = api.graph_mut(layer).unwrap();
+    assert!(eq_by_ids(&before, graph.graph()));
+    assert_eq!(graph.take_error(), None);
+}
+
+#[test]
+fn checkpoints_filling_dependencies_are_refused() {
+    let (mut api, layer) = limited(4);
+    let ids = SipIdGenerator::default();
+
+    // Project 5 reaches the limit with two contributors.
+    api.apply_checkpoint(&layer, &checkpoint(5, &[], &[(6, 1), (7, 1)]), &ids)
+        .unwrap();
+    assert_eq!(api.graph(&layer).unwrap().edges(&5).count(), 4);
+
+    assert_refused(&mut api, &layer, &checkpoint(1, &[5], &[(2, 1)]), 4);
+    assert!(api.graph(&layer).unwrap().get_node(&1).is_none());
+
+    api.apply_checkpoint(&layer, &checkpoint(1, &[6], &[(2, 1)]), &ids)
+        .unwrap();
+    assert_eq!(api.graph(&layer).unwrap().edges(&1).count(), 3);
+}
+
+#[test]
+fn nodes_added_as_dependency_and_contributor_count_all_their_edges() {
+    let (mut api, layer) = limited(4);
+    let ids = SipIdGenerator::default();
+
+    api.apply_checkpoint(&layer, &checkpoint(2, &[], &[(6, 1)]), &ids)
+        .unwrap();
+
+    // Node 2 would gain a dependency edge and two contribution edges.
+    assert_refused(&mut api, &layer, &checkpoint(1, &[2], &[(2, 1)]), 4);
+
+    api.apply_checkpoint(&layer, &checkpoint(1, &[6], &[(2, 1)]), &ids)
+        .unwrap();
+    let graph = api.graph_mut(&layer).unwrap();
+    assert_eq!(graph.edges(&2).count(), 4);
+    assert_eq!(graph.take_error(), None);
+}
@@ -0,0 +1,279 @@
+#![cfg(feature = "linalg")]
+
+use std::cell::Cell;
+use std::collections::HashMap;
+
+use oscoin_graph_api::bench::Workload;
+use oscoin_graph_api::cancel::CancellationToken;
+use oscoin_graph_api::linalg::{
+    transition_matrix, AcceleratedRankBackend, CpuBackend, PowerIteration, RankProblem, RankVector,
+};
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::progress::{NoProgress, ProgressSink};
+use oscoin_graph_api::types::{AlgorithmError, HyperParameters, NodeRank};
+use oscoin_graph_api::GraphAlgorithm;
+
+mod common;
+
+use common::{dependency, project, reversed_arena};
+
+const DAMPING: f64 = 0.8;
+
+/// `1` points to `2` and `3` with weights 1 and 3, and to `4` with a zero
+/// weight, while `2` and `3` point back to `1`.
+fn graph() -> EdgeListGraph {
+    let edges = [
+        (1, 2, 1.0),
+        (1, 3, 3.0),
+        (1, 4, 0.0),
+        (2, 1, 1.0),
+        (3, 1, 1.0),
+    ]
+    .iter()
+    .map(|&(from, to, weight)| {
+        let mut data = dependency();
+        data.weight = weight;
+        (from, to, data)
+    })
+    .collect::<Vec<_>>();
+    EdgeListGraph::from_edges(edges, project())
+}
+
+fn power_iteration() -> PowerIteration<f64> {
+    let mut hyperparams = HyperParameters::osrank_paper_defaults();
+    hyperparams.damping_factors.project = DAMPING;
+    PowerIteration::new(hyperparams)
+}
+
+fn rank<B: AcceleratedRankBackend>(
+    power: &PowerIteration<f64, B>,
+) -> Result<HashMap<u64, f64>, AlgorithmError> {
+    let mut annotations: Vec<(u64, NodeRank<f64>)> = Vec::new();
+    let ranks = power.execute(&mut (), &graph(), &mut annotations, 0)?;
+    assert_eq!(annotations.len(), ranks.len());
+    for (id, rank) in &annotations {
+        assert_eq!(rank.rank, ranks[id]);
+    }
+    Ok(ranks)
+}
+
+/// A backend checking the problem it's handed, before delegating to the CPU
+/// or returning a given number of ranks.
+struct Checked {
+    calls: Cell<usize>,
+    ranks: Option<usize>,
+}
+
+impl AcceleratedRankBackend for Checked {
+    fn rank(
+        &self,
+        problem: &RankProblem<'_>,
+        progress: &mut dyn ProgressSink,
+        token: &CancellationToken,
+    ) -> Result<RankVector, AlgorithmError> {
+        self.calls.set(self.calls.get() + 1);
+        assert_eq!(problem.nodes(), 4);
+        assert_eq!(problem.targets.len(), 4);
+        assert_eq!(problem.damping, &[DAMPING; 4]);
+        match self.ranks {
+            Some(n) => Ok(RankVector {
+                ranks: vec![0.0; n],
+                iterations: 1,
+            }),
+            None => CpuBackend.rank(problem, progress, token),
+        }
+    }
+}
+
+#[test]
+fn transitions_are_proportional_to_positive_weights() {
+    let graph = graph();
+    let transitions = transition_matrix(&graph);
+    assert_eq!(transitions.nodes, vec![&1, &2, &3, &4]);
+
+    let matrix = transitions.matrix.to_dense();
+    let expected = [
+        [0.0, 0.25, 0.75, 0.0],
+        [1.0, 0.0, 0.0, 0.0],
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 0.0, 0.0, 0.0],
+    ];
+    for (i, row) in expected.iter().enumerate() {
+        for (j, p) in row.iter().enumerate() {
+            assert_eq!(matrix[[i, j]], *p, "{} -> {}", i, j);
+        }
+    }
+}
+
+#[test]
+fn ranks_are_the_stationary_distribution() {
+    let r = rank(&power_iteration()).unwrap();
+    assert!((r.values().sum::<f64>() - 1.0).abs() < 1e-9);
+
+    // Walks on `4` always restart.
+    let restart = (1.0 - DAMPING * (r[&1] + r[&2] + r[&3])) / 4.0;
+    assert!((r[&1] - restart - DAMPING * (r[&2] + r[&3])).abs() < 1e-9);
+    assert!((r[&2] - restart - DAMPING * r[&1] * 0.25).abs() < 1e-9);
+    assert!((r[&3] - restart - DAMPING * r[&1] * 0.75).abs() < 1e-9);
+    assert!((r[&4] - restart).abs() < 1e-9);
+}
+
+#[test]
+fn explanations_break_ranks_down_by_source() {
+    let power = power_iteration();
+    let ranks = rank(&power).unwrap();
+    let explanations = power.explain(&graph(), &ranks, 1);
+    assert_eq!(explanations.len(), 4);
+
+    let one = &explanations[0];
+    assert_eq!((one.node, one.rank), (1, ranks[&1]));
+    assert_eq!(one.sources.len(), 1);
+    assert_eq!(one.sources[0].source, 3);
+    let from_2 = DAMPING * ranks[&2] / ranks[&1];
+    assert!((one.own + one.sources[0].share + from_2 - 1.0).abs() < 1e-9);
+
+    assert!(explanations[3].sources.is_empty());
+    assert!((explanations[3].own - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn iterations_can_be_delegated() {
+    let backend = Checked {
+        calls: Cell::new(0),
+        ranks: None,
+    };
+    let power = power_iteration().backend(backend);
+    let delegated = rank(&power).unwrap();
+    assert_eq!(power.backend.calls.get(), 1);
+
+    let ranks = rank(&power_iteration()).unwrap();
+    for (id, r) in &ranks {
+        assert!((delegated[id] - r).abs() < 1e-12);
+    }
+
+    let power = power_iteration().backend(Checked {
+        calls: Cell::new(0),
+        ranks: Some(3),
+    });
+    assert_eq!(
+        rank(&power),
+        Err(AlgorithmError::InvariantViolated(
+            "the backend returned 3 ranks for 4 nodes".to_string()
+        ))
+    );
+}
+
+#[test]
+fn ranks_do_not_depend_on_the_backend() {
+    let graph: EdgeListGraph = Workload::random(200, 3, 7).build();
+    let arena = reversed_arena(&graph);
+    let power = power_iteration();
+
+    let mut annotations: Vec<(u64, NodeRank<f64>)> = Vec::new();
+    power.execute(&mut (), &graph, &mut annotations, 0).unwrap();
+    let mut arena_annotations: Vec<(u64, NodeRank<f64>)> = Vec::new();
+    power
+        .execute(&mut (), &arena, &mut arena_annotations, 0)
+        .unwrap();
+
+    let bits = |annotations: Vec<(u64, NodeRank<f64>)>| -> Vec<(u64, u64)> {
+        annotations
+            .into_iter()
+            .map(|(id, rank)| (id, rank.rank.to_bits()))
+            .collect()
+    };
+    assert_eq!(bits(annotations), bits(arena_annotations));
+}
+
+#[test]
+fn iterations_are_bounded() {
+    let mut power = power_iteration();
+    power.max_iterations = 2;
+    match rank(&power) {
+        Err(AlgorithmError::NotConverged { iterations, .. }) => assert_eq!(iterations, 2),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    let token = CancellationToken::new();
+    token.cancel();
+    let mut annotations: Vec<(u64, NodeRank<f64>)> = Vec::new();
+    let result = power_iteration().execute_with(&mut (), &graph(), &mut annotations, 0, &token);
+    assert_eq!(result, Err(AlgorithmError::Cancelled));
+    assert!(annotations.is_empty());
+}
+
+#[test]
+fn empty_graphs_have_no_ranks() {
+    let mut annotations: Vec<(u64, NodeRank<f64>)> = Vec::new();
+    let ranks = power_iteration()
+        .execute(
+            &mut (),
+            &EdgeListGraph::<u64, f64>::new(),
+            &mut annotations,
+            0,
+        )
+        .unwrap();
+    assert!(ranks.is_empty());
+    assert!(annotations.is_empty());
+}
+
+/// Records the steps reported.
+#[derive(Default)]
+struct Steps(Vec<(u64, Option<u64>)>);
+
+impl ProgressSink for Steps {
+    fn on_progress(&mut self, step: u64, total: Option<u64>) {
+        self.0.push((step, total));
+    }
+}
+
+#[test]
+fn the_cpu_backend_iterates_over_csr_arrays() {
+    // `0 -> 1`, with `1` dangling, walks continuing half of the time: at
+    // the stationary distribution, `r0 = (1 - r0 / 2) / 2`.
+    let problem = RankProblem {
+        offsets: &[0, 1, 1],
+        targets: &[1],
+        weights: &[1.0],
+        damping: &[0.5, 0.5],
+        max_iterations: 100,
+        tolerance: 1e-12,
+    };
+    assert_eq!(problem.nodes(), 2);
+    let mut steps = Steps::default();
+    let RankVector { ranks, iterations } = CpuBackend
+        .rank(&problem, &mut steps, &CancellationToken::new())
+        .unwrap();
+    assert!((ranks[0] - 0.4).abs() < 1e-9);
+    assert!((ranks[1] - 0.6).abs() < 1e-9);
+    assert!(iterations < 100);
+    let expected: Vec<_> = (1..=iterations as u64).map(|i| (i, Some(100))).collect();
+    assert_eq!(steps.0, expected);
+
+    // A single iteration from the uniform ranks.
+    let once = RankProblem {
+        max_iterations: 1,
+        tolerance: 1.0,
+        ..problem
+    };
+    let RankVector { ranks, iterations } = CpuBackend
+        .rank(&once, &mut NoProgress, &CancellationToken::new())
+        .unwrap();
+    assert_eq!(iterations, 1);
+    assert_eq!(ranks, vec![0.375, 0.625]);
+
+    let token = CancellationToken::new();
+    token.cancel();
+    assert_eq!(
+        CpuBackend.rank(&problem, &mut NoProgress, &token),
+        Err(AlgorithmError::Cancelled)
+    );
+    let empty = RankProblem {
+        offsets: &[],
+        targets: &[],
+        weights: &[],
+        damping: &[],
+        ..problem
+    };
+    assert_eq!(empty.nodes(), 0);
+}
@@ -0,0 +1,436 @@
+use std::ptr;
+
+use oscoin_graph_api::bench::Workload;
+use oscoin_graph_api::compare::eq_by_ids;
+use oscoin_graph_api::mem::{self, ArenaGraph, EdgeListGraph};
+use oscoin_graph_api::properties::{HasProperties, PropertyKey, PropertyStore};
+use oscoin_graph_api::types::{EdgeData, NodeData, Timestamps};
+use oscoin_graph_api::{
+    Direction, Edge, Graph, GraphDataReader, GraphDataWriter, GraphObject, GraphWriter, HasWeight,
+    IntoGraphParts,
+};
+
+mod common;
+
+use common::{dependencies, dependency, project};
+
+fn edges() -> Vec<(u64, u64, EdgeData<f64>)> {
+    dependencies(&[(1, 2), (2, 3), (3, 1)])
+}
+
+/// Check that data accessors give the data of the objects themselves.
+fn assert_data_is_readable<G>(graph: &G, missing: &u64)
+where
+    G: GraphDataReader,
+    G::Node: GraphObject<Id = u64>,
+    G::Edge: GraphObject<Id = u64>,
+{
+    for node in graph.nodes() {
+        let data = graph.node_data(node.id()).unwrap();
+        assert!(ptr::eq(data, node.data()));
+
+        for e in graph.edges_directed(node.id(), Direction::Outgoing) {
+            let edge = graph.get_edge(e.id).unwrap();
+            assert!(ptr::eq(graph.edge_data(e.id).unwrap(), edge.data()));
+        }
+    }
+    assert!(graph.node_data(missing).is_none());
+    assert!(graph.edge_data(missing).is_none());
+}
+
+#[test]
+fn every_graph_is_a_data_reader() {
+    let graph = EdgeListGraph::from_edges(edges(), project());
+    assert_data_is_readable(&graph, &42);
+
+    let graph: ArenaGraph = mem::from_edges(ArenaGraph::new(), edges(), project(), |i| i as u64);
+    assert_data_is_readable(&graph, &42);
+}
+
+/// Consume the graph, returning its sorted node ids and its edges as sorted
+/// `(id, from, to)` triples.
+fn into_ids<G>(graph: G) -> (Vec<u64>, Vec<(u64, u64, u64)>)
+where
+    G: IntoGraphParts,
+    G::Node: GraphObject<Id = u64>,
+    G::Edge: GraphObject<Id = u64>,
+{
+    let (nodes, edges) = graph.into_parts();
+    let mut nodes: Vec<u64> = nodes.map(|n| *n.id()).collect();
+    let mut edges: Vec<_> = edges.map(|e| (*e.id(), *e.source(), *e.target())).collect();
+    nodes.sort_unstable();
+    edges.sort_unstable();
+    (nodes, edges)
+}
+
+#[test]
+fn graphs_are_consumed_into_their_parts() {
+    let expected = (vec![1, 2, 3], vec![(0, 1, 2), (1, 2, 3), (2, 3, 1)]);
+
+    let graph = EdgeListGraph::from_edges(edges(), project());
+    assert_eq!(graph.clone().into_nodes().count(), 3);
+    assert_eq!(graph.clone().into_edges().count(), 3);
+    assert_eq!(into_ids(graph), expected);
+
+    let graph: ArenaGraph = mem::from_edges(ArenaGraph::new(), edges(), project(), |i| i as u64);
+    assert_eq!(into_ids(graph), expected);
+}
+
+#[test]
+fn graphs_are_collected_from_edge_lists() {
+    let graph: EdgeListGraph = edges().into_iter().collect();
+    assert_eq!(graph.node_count(), 3);
+    assert_eq!(graph.edge_count(), 3);
+    assert_eq!(graph.node_data(&2), Some(&project()));
+
+    let mut graph = graph;
+    let dependency = dependency();
+    graph.extend_edges(vec![(3, 4, dependency)], project());
+    assert_eq!(graph.node_count(), 4);
+    // New edges get the next ids.
+    let (_, edges) = into_ids(graph);
+    assert_eq!(edges.last(), Some(&(3, 3, 4)));
+}
+
+/// Check that objects are stamped with the epoch set by `set_epoch`.
+fn assert_objects_are_stamped<G>(mut graph: G, set_epoch: fn(&mut G, u64))
+where
+    G: GraphWriter<Node = mem::MemNode<u64, f64>, Edge = mem::MemEdge<u64, f64>> + GraphDataWriter,
+{
+    let node = |graph: &G, id| graph.node_data(&id).unwrap().timestamps;
+    let edge = |graph: &G, id| graph.edge_data(&id).unwrap().timestamps;
+
+    // Nothing is tracked until an epoch is set.
+    graph.add_node(1, project());
+    assert_eq!(node(&graph, 1), Timestamps::default());
+
+    set_epoch(&mut graph, 3);
+    graph.add_node(2, project());
+    graph.add_node(3, project());
+    graph.add_edge(0, &2, &3, dependency());
+    assert_eq!(node(&graph, 2), Timestamps::created(3));
+    assert_eq!(edge(&graph, 0), Timestamps::created(3));
+
+    // Replacements and writes keep the creation time.
+    set_epoch(&mut graph, 5);
+    graph.add_node(2, project());
+    graph.add_edge(0, &2, &3, dependency());
+    graph.node_data_mut(&3).unwrap();
+    let updated = Timestamps {
+        created_at: Some(3),
+        updated_at: Some(5),
+    };
+    assert_eq!(node(&graph, 2), updated);
+    assert_eq!(node(&graph, 3), updated);
+    assert_eq!(edge(&graph, 0), updated);
+
+    // Objects added before tracking started have no known creation time,
+    // and new ones may carry their own.
+    graph.add_node(1, project());
+    assert_eq!(
+        node(&graph, 1),
+        Timestamps {
+            created_at: None,
+            updated_at: Some(5),
+        }
+    );
+    let mut data = project();
+    data.timestamps.created_at = Some(1);
+    graph.add_node(4, data);
+    assert_eq!(
+        node(&graph, 4),
+        Timestamps {
+            created_at: Some(1),
+            updated_at: Some(5),
+        }
+    );
+
+    // Bulk writes through the mutable iterators are stamped as well.
+    set_epoch(&mut graph, 8);
+    assert_eq!(graph.nodes_mut().count(), 4);
+    assert_eq!(graph.edges_mut().count(), 1);
+    let updated = Timestamps {
+        created_at: Some(3),
+        updated_at: Some(8),
+    };
+    assert_eq!(node(&graph, 3), updated);
+    assert_eq!(edge(&graph, 0), updated);
+}
+
+#[test]
+fn graphs_track_timestamps() {
+    assert_objects_are_stamped(EdgeListGraph::new(), EdgeListGraph::set_epoch);
+    assert_objects_are_stamped(ArenaGraph::new(), ArenaGraph::set_epoch);
+
+    let mut graph: EdgeListGraph = EdgeListGraph::new();
+    assert_eq!(graph.epoch(), None);
+    graph.set_epoch(7);
+    assert_eq!(graph.epoch(), Some(7));
+
+    let mut timestamps = Timestamps::created(2);
+    timestamps.touch(4);
+    assert_eq!(timestamps.created_at, Some(2));
+    assert_eq!(timestamps.updated_at, Some(4));
+}
+
+#[test]
+fn edges_are_upserted() {
+    const NAME: PropertyKey<&str> = PropertyKey::new("name");
+    let mut graph = EdgeListGraph::from_edges(edges(), project());
+    graph.edge_properties_mut(&0).unwrap().set(&NAME, "1 -> 2");
+
+    let mut data = dependency();
+    data.weight = 0.5;
+    assert!(!graph.upsert_edge(3, &1, &3, data.clone()));
+    assert_eq!(graph.edge_data(&3), Some(&data));
+
+    // Between the same nodes, the edge is updated in place.
+    assert!(graph.upsert_edge(0, &1, &2, data.clone()));
+    assert_eq!(graph.edge_data(&0), Some(&data));
+    assert_eq!(
+        graph.get_edge(&0).unwrap().properties().get(&NAME),
+        Some(&"1 -> 2")
+    );
+
+    // Otherwise it's moved.
+    assert!(graph.upsert_edge(0, &2, &1, data));
+    let edge = graph.get_edge(&0).unwrap();
+    assert_eq!((*edge.source(), *edge.target()), (2, 1));
+    assert!(edge.properties().is_empty());
+    let incoming: Vec<u64> = graph
+        .edges_directed(&2, Direction::Incoming)
+        .into_iter()
+        .map(|e| *e.id)
+        .collect();
+    assert!(!incoming.contains(&0));
+    assert_eq!(graph.edge_count(), 4);
+}
+
+fn assert_missing_nodes_are_not_linked<G>(mut graph: G)
+where
+    G: GraphWriter<NodeData = NodeData<f64>, EdgeData = EdgeData<f64>>,
+    G::Node: GraphObject<Id = u64>,
+    G::Edge: GraphObject<Id = u64>,
+{
+    graph.add_node(1, project());
+    graph.add_edge(0, &1, &2, dependency());
+    graph.add_edge(1, &2, &1, dependency());
+    assert!(graph.get_edge(&0).is_none());
+    assert!(graph.get_edge(&1).is_none());
+    assert!(graph.edges_directed(&1, Direction::Both).is_empty());
+
+    // An existing edge isn't moved to a missing node either.
+    graph.add_edge(0, &1, &1, dependency());
+    assert!(graph.upsert_edge(0, &1, &2, dependency()));
+    graph.add_edge(0, &2, &1, dependency());
+    let links: Vec<(u64, u64, u64)> = graph
+        .edges_directed(&1, Direction::Outgoing)
+        .iter()
+        .map(|e| (*e.id, *e.from, *e.to))
+        .collect();
+    assert_eq!(links, vec![(0, 1, 1)]);
+}
+
+#[test]
+fn edges_to_missing_nodes_are_ignored() {
+    assert_missing_nodes_are_not_linked(EdgeListGraph::new());
+    assert_missing_nodes_are_not_linked(ArenaGraph::new());
+}
+
+#[test]
+fn edge_weights_are_set() {
+    let mut graph = EdgeListGraph::from_edges(edges(), project());
+    assert!(graph.set_edge_weight(&1, 0.25));
+    assert!(!graph.set_edge_weight(&7, 0.25));
+    let data = graph.edge_data(&1).unwrap();
+    assert_eq!(data.weight, 0.25);
+    assert_eq!(HasWeight::weight(data), 0.25);
+    assert_eq!(graph.get_edge(&1).unwrap().weight(), 0.25);
+}
+
+#[test]
+fn edge_weights_read_back_what_was_written() {
+    let mut edges = edges();
+    for (i, (_, _, data)) in edges.iter_mut().enumerate() {
+        data.weight = i as f64 / 4.0;
+    }
+    let graph = EdgeListGraph::from_edges(edges.clone(), project());
+    let arena: ArenaGraph = mem::from_edges(ArenaGraph::new(), edges, project(), |i| i as u64);
+    for id in 0..3 {
+        let weight = id as f64 / 4.0;
+        assert_eq!(graph.get_edge(&id).unwrap().weight(), weight);
+        assert_eq!(arena.get_edge(&id).unwrap().weight(), weight);
+    }
+}
+
+/// Halve the weight of every edge in place, checking that the edges are
+/// listed once each and keep their endpoints.
+fn halve_weights<G>(graph: &mut G)
+where
+    G: GraphWriter<Node = mem::MemNode<u64, f64>, Edge = mem::MemEdge<u64, f64>>,
+{
+    let before = edge_triples(graph);
+    let mut seen = 0;
+    for edge in graph.edges_mut() {
+        edge.data_mut().weight /= 2.0;
+        seen += 1;
+    }
+    assert_eq!(seen, before.len());
+    assert_eq!(edge_triples(graph), before);
+}
+
+/// The sorted `(id, from, to)` triples of the edges of a graph.
+fn edge_triples<G>(graph: &G) -> Vec<(u64, u64, u64)>
+where
+    G: Graph<Node = mem::MemNode<u64, f64>, Edge = mem::MemEdge<u64, f64>>,
+{
+    let mut edges: Vec<_> = graph
+        .nodes()
+        .flat_map(|n| graph.edges_directed(n.id(), Direction::Outgoing))
+        .map(|e| (*e.id, *e.from, *e.to))
+        .collect();
+    edges.sort_unstable();
+    edges
+}
+
+#[test]
+fn edges_are_modified_in_place() {
+    let mut edges = edges();
+    for (_, _, data) in edges.iter_mut() {
+        data.weight = 1.0;
+    }
+    let mut graph = EdgeListGraph::from_edges(edges.clone(), project());
+    let mut arena: ArenaGraph = mem::from_edges(ArenaGraph::new(), edges, project(), |i| i as u64);
+    halve_weights(&mut graph);
+    halve_weights(&mut arena);
+    for id in 0..3 {
+        assert_eq!(graph.edge_data(&id).unwrap().weight, 0.5);
+        assert_eq!(arena.edge_data(&id).unwrap().weight, 0.5);
+    }
+
+    assert_eq!(EdgeListGraph::<u64, f64>::new().edges_mut().count(), 0);
+}
+
+/// Check that every edge listed in either direction exists, and is listed
+/// once in each.
+fn assert_adjacency_is_consistent<G>(graph: &G)
+where
+    G: Graph<Node = mem::MemNode<u64, f64>, Edge = mem::MemEdge<u64, f64>>,
+{
+    let listed = |dir| {
+        let mut ids: Vec<u64> = graph
+            .nodes()
+            .flat_map(|n| graph.edges_directed(n.id(), dir))
+            .map(|e| *e.id)
+            .collect();
+        ids.sort_unstable();
+        ids
+    };
+    let outgoing = listed(Direction::Outgoing);
+    assert_eq!(outgoing, listed(Direction::Incoming));
+    assert!(outgoing.iter().all(|id| graph.get_edge(id).is_some()));
+}
+
+#[test]
+fn nodes_and_edges_are_retained() {
+    let workload = Workload::random(40, 3, 11);
+    let keep_node = |id: &u64| !id.is_multiple_of(3);
+    let keep_edge = |id: &u64| id.is_multiple_of(2);
+
+    let mut expected = EdgeListGraph::new();
+    let source: EdgeListGraph = workload.build();
+    let nodes: Vec<u64> = source.nodes().map(|n| *n.id()).collect();
+    for id in nodes.iter().filter(|id| keep_node(id)) {
+        expected.add_node(*id, source.node_data(id).unwrap().clone());
+    }
+    for id in nodes.iter() {
+        for e in source.edges_directed(id, Direction::Outgoing) {
+            if keep_node(e.from) && keep_node(e.to) && keep_edge(e.id) {
+                expected.add_edge(*e.id, e.from, e.to, e.data.clone());
+            }
+        }
+    }
+
+    assert!(0 < expected.edge_count() && expected.edge_count() < source.edge_count());
+
+    let mut graph: EdgeListGraph = workload.build();
+    graph.retain_nodes(|n| keep_node(n.id()));
+    graph.retain_edges(|e| keep_edge(e.id()));
+    assert_adjacency_is_consistent(&graph);
+    assert!(eq_by_ids(&graph, &expected));
+
+    // With the default implementations.
+    let mut arena: ArenaGraph = workload.build();
+    arena.retain_nodes(|n| keep_node(n.id()));
+    arena.retain_edges(|e| keep_edge(e.id()));
+    assert_adjacency_is_consistent(&arena);
+    assert!(eq_by_ids(&arena, &expected));
+
+    graph.retain_nodes(|_| true);
+    graph.retain_edges(|_| true);
+    assert!(eq_by_ids(&graph, &expected));
+}
+
+#[test]
+fn graphs_are_cleared() {
+    let mut graph = EdgeListGraph::from_edges(edges(), project());
+    graph.clear();
+    assert_eq!((graph.node_count(), graph.edge_count()), (0, 0));
+    assert!(graph.edges_directed(&1, Direction::Both).is_empty());
+
+    // Edge ids allocated before aren't reused.
+    graph.extend_edges(edges(), project());
+    assert_eq!(edge_triples(&graph).first(), Some(&(3, 1, 2)));
+
+    let mut arena: ArenaGraph =
+        mem::from_edges(ArenaGraph::new(), edges(), project(), |i| i as u64);
+    arena.clear();
+    assert_eq!((arena.node_count(), arena.edge_count()), (0, 0));
+    assert!(arena.get_node(&1).is_none());
+    arena.add_node(1, project());
+    arena.add_edge(0, &1, &1, dependency());
+    assert_eq!(edge_triples(&arena), vec![(0, 1, 1)]);
+}
+
+#[test]
+fn arena_slots_are_reused() {
+    let mut arena: ArenaGraph = ArenaGraph::new();
+    for id in [5, 1, 3, 2] {
+        arena.add_node(id, project());
+    }
+    arena.add_edge(10, &5, &1, dependency());
+    arena.add_edge(11, &1, &3, dependency());
+    arena.add_edge(12, &3, &5, dependency());
+    let node_ids = |arena: &ArenaGraph| arena.nodes().map(|n| *n.id()).collect::<Vec<_>>();
+    let edge_ids = |arena: &ArenaGraph| arena.all_edges().map(|e| *e.id()).collect::<Vec<_>>();
+    assert_eq!(node_ids(&arena), vec![5, 1, 3, 2]);
+    assert_eq!(edge_ids(&arena), vec![10, 11, 12]);
+
+    // Removing node `1` frees its slot and those of its edges, which new
+    // objects take.
+    arena.remove_node(1);
+    assert_eq!((arena.node_count(), arena.edge_count()), (3, 1));
+    arena.add_node(7, project());
+    arena.add_edge(13, &7, &2, dependency());
+    assert_eq!(node_ids(&arena), vec![5, 7, 3, 2]);
+    assert_eq!(edge_ids(&arena).len(), 2);
+    assert!(edge_ids(&arena).contains(&13));
+
+    // The adjacency of the new objects doesn't include the removed edges.
+    assert_adjacency_is_consistent(&arena);
+    let neighbors = |arena: &ArenaGraph, id| {
+        arena
+            .edges_directed(&id, Direction::Both)
+            .iter()
+            .map(|e| *e.id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(neighbors(&arena, 7), vec![13]);
+    assert_eq!(neighbors(&arena, 5), vec![12]);
+    assert!(arena.get_edge(&10).is_none());
+
+    // Re-adding a removed node gives it no edges back.
+    arena.add_node(1, project());
+    assert!(neighbors(&arena, 1).is_empty());
+    assert_eq!(arena.node_count(), 5);
+}
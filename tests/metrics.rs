@@ -0,0 +1,170 @@
+#![cfg(feature = "metrics")]
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use metrics::{
+    Counter, CounterFn, Gauge, GaugeFn, Histogram, HistogramFn, Key, KeyName, Metadata, Recorder,
+    SharedString, Unit,
+};
+
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::metrics::{self as graph_metrics, MetricsObserver};
+use oscoin_graph_api::progress::{ExecutionStats, ProgressSink};
+use oscoin_graph_api::watch::{Observable, Watched};
+use oscoin_graph_api::{GraphWriter, Layer};
+
+mod common;
+
+use common::{dependency, project};
+
+/// The values of the metrics, by name and labels, eg.
+/// `oscoin_graph_nodes{layer=osrank}`. Counters and gauges hold their
+/// value, histograms the sum of their samples.
+type Values = Arc<Mutex<BTreeMap<String, f64>>>;
+
+/// A recorder keeping the values of the metrics.
+#[derive(Default)]
+struct TestRecorder(Values);
+
+/// The handle of one metric.
+struct Handle(String, Values);
+
+impl Handle {
+    fn update(&self, f: impl FnOnce(f64) -> f64) {
+        let mut values = self.1.lock().unwrap();
+        let value = values.entry(self.0.clone()).or_insert(0.0);
+        *value = f(*value);
+    }
+}
+
+impl CounterFn for Handle {
+    fn increment(&self, value: u64) {
+        self.update(|v| v + value as f64);
+    }
+
+    fn absolute(&self, value: u64) {
+        self.update(|v| v.max(value as f64));
+    }
+}
+
+impl GaugeFn for Handle {
+    fn increment(&self, value: f64) {
+        self.update(|v| v + value);
+    }
+
+    fn decrement(&self, value: f64) {
+        self.update(|v| v - value);
+    }
+
+    fn set(&self, value: f64) {
+        self.update(|_| value);
+    }
+}
+
+impl HistogramFn for Handle {
+    fn record(&self, value: f64) {
+        self.update(|v| v + value);
+    }
+}
+
+impl TestRecorder {
+    fn handle(&self, key: &Key) -> Arc<Handle> {
+        let labels: Vec<String> = key
+            .labels()
+            .map(|l| format!("{}={}", l.key(), l.value()))
+            .collect();
+        let name = format!("{}{{{}}}", key.name(), labels.join(","));
+        Arc::new(Handle(name, self.0.clone()))
+    }
+
+    fn get(&self, name: &str) -> Option<f64> {
+        self.0.lock().unwrap().get(name).copied()
+    }
+}
+
+impl Recorder for TestRecorder {
+    fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+    fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+    fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+    fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+        Counter::from_arc(self.handle(key))
+    }
+
+    fn register_gauge(&self, key: &Key, _: &Metadata<'_>) -> Gauge {
+        Gauge::from_arc(self.handle(key))
+    }
+
+    fn register_histogram(&self, key: &Key, _: &Metadata<'_>) -> Histogram {
+        Histogram::from_arc(self.handle(key))
+    }
+}
+
+const OSRANK: Layer = Layer::from_static("osrank");
+
+#[test]
+fn mutations_are_counted_by_kind() {
+    let recorder = TestRecorder::default();
+    let observer = MetricsObserver::new(&OSRANK);
+    let mut graph = Watched::new(EdgeListGraph::new());
+    let subscription = graph.subscribe();
+
+    graph.add_node(1, project());
+    graph.add_node(2, project());
+    graph.add_edge(7, &1, &2, dependency());
+    graph.remove_node(2);
+
+    let drained = metrics::with_local_recorder(&recorder, || observer.drain(&subscription));
+    assert_eq!(drained, 4);
+    let mutations = |kind: &str| {
+        recorder.get(&format!(
+            "{}{{layer=osrank,kind={}}}",
+            graph_metrics::MUTATIONS,
+            kind
+        ))
+    };
+    assert_eq!(mutations("node_added"), Some(2.0));
+    assert_eq!(mutations("edge_added"), Some(1.0));
+    assert_eq!(mutations("node_removed"), Some(1.0));
+    assert_eq!(mutations("edge_removed"), None);
+
+    let drained = metrics::with_local_recorder(&recorder, || observer.drain(&subscription));
+    assert_eq!(drained, 0);
+}
+
+#[test]
+fn graph_sizes_are_sampled() {
+    let recorder = TestRecorder::default();
+    let observer = MetricsObserver::new(&OSRANK);
+    let graph =
+        EdgeListGraph::from_edges(vec![(1, 2, dependency()), (2, 3, dependency())], project());
+
+    metrics::with_local_recorder(&recorder, || observer.record_graph(&graph));
+    assert_eq!(recorder.get("oscoin_graph_nodes{layer=osrank}"), Some(3.0));
+    assert_eq!(recorder.get("oscoin_graph_edges{layer=osrank}"), Some(2.0));
+    assert_eq!(graph_metrics::NODES, "oscoin_graph_nodes");
+    assert_eq!(graph_metrics::EDGES, "oscoin_graph_edges");
+}
+
+#[test]
+fn executions_are_recorded() {
+    let recorder = TestRecorder::default();
+    let mut observer = MetricsObserver::new(&OSRANK);
+    let stats = ExecutionStats {
+        walks: 100,
+        iterations: 3,
+        elapsed: Duration::from_millis(1500),
+        ..Default::default()
+    };
+    metrics::with_local_recorder(&recorder, || {
+        observer.on_stats(&stats);
+        observer.on_stats(&stats);
+    });
+
+    let metric = |name: &str| recorder.get(&format!("{}{{layer=osrank}}", name));
+    assert_eq!(metric(graph_metrics::WALKS), Some(200.0));
+    assert_eq!(metric(graph_metrics::ITERATIONS), Some(6.0));
+    assert_eq!(metric(graph_metrics::ALGORITHM_DURATION), Some(3.0));
+}
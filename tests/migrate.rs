@@ -0,0 +1,124 @@
+use oscoin_graph_api::bench::Workload;
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::migrate::{FnMigration, Migration, MigrationError, Migrator};
+use oscoin_graph_api::snapshot::compact::{self, CompactGraph};
+use oscoin_graph_api::types::SCHEMA_VERSION;
+use oscoin_graph_api::{Graph, GraphObject, GraphWriter};
+
+mod common;
+
+use common::project;
+
+/// A migration adding node `to`, and requiring node `from` unless upgrading
+/// from version 0.
+fn migration(from: u32, to: u32) -> impl Migration<EdgeListGraph> {
+    FnMigration::new(from, to, move |graph: &mut EdgeListGraph| {
+        if from > 0 && graph.get_node(&u64::from(from)).is_none() {
+            return Err(format!("node {} is missing", from));
+        }
+        graph.add_node(u64::from(to), project());
+        Ok(())
+    })
+}
+
+fn nodes(graph: &EdgeListGraph) -> Vec<u64> {
+    let mut ids: Vec<u64> = graph.nodes().map(|n| *n.id()).collect();
+    ids.sort_unstable();
+    ids
+}
+
+#[test]
+fn migrations_are_chained_in_order() {
+    let migrator = Migrator::new()
+        .with(migration(2, 3))
+        .with(migration(1, 2))
+        .with(migration(3, 4));
+    let mut graph = EdgeListGraph::new();
+    graph.add_node(1, project());
+
+    assert_eq!(migrator.migrate_to(&mut graph, 1, 3), Ok(2));
+    assert_eq!(nodes(&graph), vec![1, 2, 3]);
+    assert_eq!(migrator.migrate_to(&mut graph, 3, 3), Ok(0));
+    assert_eq!(migrator.migrate_to(&mut graph, 3, 4), Ok(1));
+    assert_eq!(nodes(&graph), vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn the_furthest_migration_is_picked() {
+    let migrator = Migrator::new()
+        .with(migration(1, 2))
+        .with(migration(1, 3))
+        .with(migration(2, 4))
+        .with(migration(3, 4));
+
+    let plan = migrator.plan(1, 4).unwrap();
+    let steps: Vec<(u32, u32)> = plan
+        .iter()
+        .map(|m| (m.from_version(), m.to_version()))
+        .collect();
+    assert_eq!(steps, vec![(1, 3), (3, 4)]);
+
+    // Migrations don't go past the target.
+    let plan = migrator.plan(1, 2).unwrap();
+    assert_eq!(plan.len(), 1);
+    assert_eq!(plan[0].to_version(), 2);
+}
+
+#[test]
+fn graphs_are_left_untouched_without_a_full_chain() {
+    let migrator = Migrator::new().with(migration(0, 1)).with(migration(2, 3));
+    let mut graph = EdgeListGraph::new();
+
+    assert_eq!(
+        migrator.migrate_to(&mut graph, 0, 3),
+        Err(MigrationError::NoPath {
+            version: 1,
+            target: 3
+        })
+    );
+    assert!(nodes(&graph).is_empty());
+    assert_eq!(
+        migrator.migrate_to(&mut graph, 3, 1),
+        Err(MigrationError::Newer {
+            version: 3,
+            target: 1
+        })
+    );
+
+    // Downgrades are never registered.
+    let migrator = Migrator::new().with(FnMigration::new(3, 3, |_: &mut EdgeListGraph| Ok(())));
+    assert!(migrator.plan(3, 4).is_err());
+}
+
+#[test]
+fn failed_migrations_are_reported() {
+    let migrator = Migrator::new().with(migration(1, 2)).with(migration(2, 3));
+    let mut graph = EdgeListGraph::new();
+    let err = migrator.migrate_to(&mut graph, 1, 3).unwrap_err();
+    assert_eq!(
+        err,
+        MigrationError::Failed {
+            from: 1,
+            to: 2,
+            reason: "node 1 is missing".to_owned()
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "migration from schema version 1 to 2 failed: node 1 is missing"
+    );
+}
+
+#[test]
+fn snapshots_record_the_current_schema() {
+    let graph: EdgeListGraph = Workload::random(10, 2, 1).build();
+    let mut bytes = Vec::new();
+    compact::write(&graph, &mut bytes).unwrap();
+    let snapshot = CompactGraph::from_bytes(bytes).unwrap();
+    assert_eq!(snapshot.schema_version(), SCHEMA_VERSION);
+
+    let mut graph: EdgeListGraph = Workload::random(10, 2, 1).build();
+    let migrator = Migrator::new().with(migration(0, SCHEMA_VERSION));
+    assert_eq!(migrator.migrate(&mut graph, SCHEMA_VERSION), Ok(0));
+    assert_eq!(migrator.migrate(&mut graph, 0), Ok(1));
+}
@@ -0,0 +1,41 @@
+use oscoin_graph_api::mem::{ArenaGraph, EdgeListGraph};
+use oscoin_graph_api::snapshot::compact::{self, CompactGraph};
+use oscoin_graph_api::testing::model::{check, check_snapshots, random_ops};
+
+const SEEDS: u64 = 200;
+const OPS: usize = 64;
+
+#[test]
+fn edge_list_graphs_match_the_model() {
+    for seed in 0..SEEDS {
+        let mut graph: EdgeListGraph = EdgeListGraph::new();
+        if let Err(mismatch) = check(&mut graph, &random_ops(seed, OPS)) {
+            panic!("seed {}: {}", seed, mismatch);
+        }
+    }
+}
+
+#[test]
+fn arena_graphs_match_the_model() {
+    for seed in 0..SEEDS {
+        let mut graph: ArenaGraph = ArenaGraph::new();
+        if let Err(mismatch) = check(&mut graph, &random_ops(seed, OPS)) {
+            panic!("seed {}: {}", seed, mismatch);
+        }
+    }
+}
+
+#[test]
+fn compact_snapshots_match_the_model() {
+    let snapshot = |graph: &EdgeListGraph| {
+        let mut bytes = Vec::new();
+        compact::write(graph, &mut bytes).unwrap();
+        CompactGraph::from_bytes(bytes).unwrap()
+    };
+    for seed in 0..SEEDS / 4 {
+        let mut graph: EdgeListGraph = EdgeListGraph::new();
+        if let Err(mismatch) = check_snapshots(&mut graph, &random_ops(seed, OPS), snapshot) {
+            panic!("seed {}: {}", seed, mismatch);
+        }
+    }
+}
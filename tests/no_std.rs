@@ -0,0 +1,50 @@
+//! Only uses what the crate offers without its `std` feature, so that it
+//! also runs against a `no_std` build of the crate, with
+//! `cargo test --no-default-features --test no_std`.
+
+use oscoin_graph_api::cancel::CancellationToken;
+use oscoin_graph_api::epochs::EdgeHistory;
+use oscoin_graph_api::rng::{AlgorithmSeed, SeedableAlgorithmRng};
+use oscoin_graph_api::types::{EdgeType, Hash32, HyperParameters, NodeType};
+
+#[test]
+fn types_are_available() {
+    let hash: Hash32 = "01".repeat(32).parse().unwrap();
+    assert_eq!(hash, Hash32([1; 32]));
+    assert_eq!(
+        "user(3)".parse::<NodeType>().unwrap().total_contributions(),
+        3
+    );
+    assert_eq!(
+        "donation(7)".parse::<EdgeType>().unwrap(),
+        EdgeType::Donation(7)
+    );
+
+    let hyperparams = HyperParameters::<f64>::osrank_paper_defaults();
+    let dependency = EdgeType::Dependency.to_tag();
+    assert!((hyperparams.get_param(&dependency) - 4.0 / 7.0).abs() < 1e-12);
+}
+
+#[test]
+fn seeded_generators_are_reproducible() {
+    let draws = |seed: u64| {
+        let mut rng = seed.into_rng();
+        [rng.next_u64(), rng.next_u64()]
+    };
+    assert_eq!(draws(7), draws(7));
+    assert_ne!(draws(7), draws(8));
+
+    let rng = 7u64.into_rng();
+    assert_ne!(rng.split(0).next_u64(), rng.split(1).next_u64());
+}
+
+#[test]
+fn cancellation_and_histories_are_available() {
+    let token = CancellationToken::new();
+    token.clone().cancel();
+    assert!(token.is_cancelled());
+
+    let mut history = EdgeHistory::new();
+    history.append(2, 3);
+    assert_eq!(history.window(0, 2), 3);
+}
@@ -0,0 +1,176 @@
+use oscoin_graph_api::bench::Workload;
+use oscoin_graph_api::compare::eq_by_ids;
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::partition::{
+    self, hash_shard, Assignment, Partition, ShardedGraph, Strategy,
+};
+use oscoin_graph_api::{Direction, Edge, Graph, GraphObject, GraphWriter};
+
+mod common;
+
+use common::{dependency, project};
+
+/// Two cliques of `size` nodes, `0..size` and `size..2 * size`, joined by a
+/// single edge.
+fn two_cliques(size: u64) -> EdgeListGraph {
+    let mut graph = EdgeListGraph::new();
+    for id in 0..2 * size {
+        graph.add_node(id, project());
+    }
+    let mut next = 0;
+    for clique in [0, size] {
+        for from in clique..clique + size {
+            for to in clique..clique + size {
+                if from != to {
+                    graph.add_edge(next, &from, &to, dependency());
+                    next += 1;
+                }
+            }
+        }
+    }
+    graph.add_edge(next, &0, &size, dependency());
+    graph
+}
+
+/// The ids of the edges of a node, and of its neighbors, sorted.
+fn adjacency<G>(graph: &G, id: &u64, dir: Direction) -> (Vec<u64>, Vec<u64>)
+where
+    G: Graph,
+    G::Node: GraphObject<Id = u64>,
+    G::Edge: GraphObject<Id = u64>,
+{
+    let mut edges: Vec<u64> = graph
+        .edges_directed(id, dir)
+        .iter()
+        .map(|e| *e.id)
+        .collect();
+    edges.sort_unstable();
+    let mut neighbors: Vec<u64> = graph.neighbors(id).map(|n| *n.id()).collect();
+    neighbors.sort_unstable();
+    (edges, neighbors)
+}
+
+#[test]
+fn nodes_are_hashed_to_stable_shards() {
+    let graph: EdgeListGraph = Workload::random(200, 3, 1).build();
+    let assignment = partition::assign(&graph, 4, Strategy::Hash);
+    assert_eq!(assignment.shards(), 4);
+    for (id, shard) in assignment.iter() {
+        assert_eq!(shard, hash_shard(id, 4));
+    }
+    let sizes = assignment.sizes();
+    assert_eq!(sizes.iter().sum::<usize>(), 200);
+    assert!(sizes.iter().all(|s| *s > 25), "{:?}", sizes);
+
+    // Shards don't depend on the run, nor on the platform.
+    let shards: Vec<usize> = (0..8u64).map(|id| hash_shard(&id, 4)).collect();
+    assert_eq!(shards, vec![3, 2, 1, 2, 0, 0, 1, 0]);
+    assert_eq!(hash_shard(&"project", 1), 0);
+}
+
+#[test]
+fn balanced_shards_follow_clusters() {
+    let graph = two_cliques(6);
+    let hashed = partition::assign(&graph, 2, Strategy::Hash);
+    let balanced = partition::assign(&graph, 2, Strategy::balanced());
+    assert_eq!(balanced.sizes(), vec![6, 6]);
+    assert_eq!(balanced.cut_size(&graph), 1);
+    assert!(hashed.cut_size(&graph) > 1);
+    let first: Vec<u64> = balanced.nodes_of(0).copied().collect();
+    assert_eq!(first, (0..6).collect::<Vec<_>>());
+
+    // Refinement keeps shards within the imbalance allowed.
+    let graph: EdgeListGraph = Workload::random(300, 4, 2).build();
+    let strategy = Strategy::Balanced {
+        imbalance: 0.1,
+        passes: 8,
+    };
+    let balanced = partition::assign(&graph, 3, strategy);
+    assert!(
+        balanced.sizes().iter().all(|s| *s <= 110),
+        "{:?}",
+        balanced.sizes()
+    );
+    assert_eq!(balanced.sizes().iter().sum::<usize>(), 300);
+    let unrefined = partition::assign(
+        &graph,
+        3,
+        Strategy::Balanced {
+            imbalance: 0.1,
+            passes: 0,
+        },
+    );
+    assert_eq!(unrefined.sizes(), vec![100, 100, 100]);
+    assert!(balanced.cut_size(&graph) <= unrefined.cut_size(&graph));
+
+    // More shards than nodes.
+    let assignment = partition::assign(&two_cliques(1), 3, Strategy::balanced());
+    assert_eq!(assignment.sizes(), vec![1, 1, 0]);
+}
+
+#[test]
+fn sharded_graphs_read_like_the_graph_split() {
+    let graph: EdgeListGraph = Workload::random(100, 3, 5).build();
+    for strategy in [Strategy::Hash, Strategy::balanced()] {
+        let assignment = partition::assign(&graph, 4, strategy);
+        let parts: Partition<EdgeListGraph> =
+            partition::split(&graph, &assignment, EdgeListGraph::new);
+
+        for (s, shard) in parts.shards.iter().enumerate() {
+            let ids: Vec<u64> = shard.nodes().map(|n| *n.id()).collect();
+            assert_eq!(ids, assignment.nodes_of(s).copied().collect::<Vec<_>>());
+            for id in &ids {
+                for e in shard.edges_directed(id, Direction::Both) {
+                    assert_eq!(assignment.shard_of(e.from), Some(s));
+                    assert_eq!(assignment.shard_of(e.to), Some(s));
+                }
+            }
+        }
+        let cut: Vec<&u64> = parts.cut_edges().map(|e| e.id()).collect();
+        assert_eq!(cut.len(), assignment.cut_size(&graph));
+        for e in parts.cut_edges() {
+            assert_ne!(
+                assignment.shard_of(e.source()),
+                assignment.shard_of(e.target())
+            );
+        }
+        let edges: usize = parts.shards.iter().map(|s| s.edge_count()).sum();
+        assert_eq!(edges + cut.len(), graph.edge_count());
+
+        let view = ShardedGraph::new(parts);
+        assert!(eq_by_ids(&view, &graph));
+        assert!(eq_by_ids(&graph, &view));
+        for node in graph.nodes() {
+            for dir in [Direction::Outgoing, Direction::Incoming, Direction::Both] {
+                assert_eq!(
+                    adjacency(&view, node.id(), dir),
+                    adjacency(&graph, node.id(), dir)
+                );
+            }
+            assert_eq!(
+                view.edges(node.id()).count(),
+                graph.edges(node.id()).count()
+            );
+        }
+        assert_eq!(view.into_partition().assignment, assignment);
+    }
+}
+
+#[test]
+fn unassigned_nodes_are_dropped() {
+    let graph = two_cliques(3);
+    let assignment = Assignment::new(2, vec![(0, 0), (1, 0), (3, 1), (4, 1)]);
+    assert_eq!(assignment.shard_of(&2), None);
+    let parts: Partition<EdgeListGraph> = partition::split(&graph, &assignment, EdgeListGraph::new);
+    let view = ShardedGraph::new(parts);
+    assert!(view.get_node(&2).is_none());
+    assert!(view.edges_directed(&2, Direction::Both).is_empty());
+    assert_eq!(view.nodes().count(), 4);
+    assert!(view
+        .nodes()
+        .flat_map(|n| view.edges_directed(n.id(), Direction::Both))
+        .all(|e| *e.from != 2 && *e.to != 2));
+    // `0 -> 3` is the only cut edge.
+    assert_eq!(view.partition().cut.edge_count(), 1);
+    assert_eq!(view.partition().cut.node_count(), 2);
+}
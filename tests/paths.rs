@@ -0,0 +1,95 @@
+use oscoin_graph_api::algorithms::{is_reachable, shortest_path, Path};
+use oscoin_graph_api::ledger::LedgerEdgeData;
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::types::{EdgeData, EdgeType, NodeType};
+use oscoin_graph_api::{Edge, Graph};
+
+mod common;
+
+use common::node;
+
+/// A graph with integer costs, as Dijkstra needs totally ordered weights.
+type CostGraph = EdgeListGraph<u64, u64>;
+
+/// Three routes from `1` to `4`: through `2` at a cost of 2, the first hop
+/// being a contribution, through `3` at a cost of 6, and directly at a cost
+/// of 10. Node `5` only points to `1`.
+fn graph() -> CostGraph {
+    let edge = |from, to, weight, edge_type| {
+        let mut data = EdgeData::from_edge_type(edge_type);
+        data.weight = weight;
+        (from, to, data)
+    };
+    EdgeListGraph::from_edges(
+        vec![
+            edge(1, 2, 1, EdgeType::ProjectToUserContribution(1)),
+            edge(2, 4, 1, EdgeType::Dependency),
+            edge(1, 3, 5, EdgeType::Dependency),
+            edge(3, 4, 1, EdgeType::Dependency),
+            edge(1, 4, 10, EdgeType::Dependency),
+            edge(5, 1, 1, EdgeType::Dependency),
+        ],
+        node(NodeType::Project {
+            contributions_from_all_users: 0,
+        }),
+    )
+}
+
+fn dependencies(edge: &<CostGraph as Graph>::Edge) -> bool {
+    *edge.edge_type() == EdgeType::Dependency
+}
+
+#[test]
+fn reachability_follows_edges_forward() {
+    let graph = graph();
+    assert!(is_reachable(&graph, &5, &4, |_| true));
+    assert!(is_reachable(&graph, &3, &3, |_| true));
+    assert!(!is_reachable(&graph, &4, &1, |_| true));
+    assert!(!is_reachable(&graph, &1, &5, |_| true));
+    assert!(!is_reachable(&graph, &1, &9, |_| true));
+    assert!(!is_reachable(&graph, &9, &9, |_| true));
+
+    assert!(is_reachable(&graph, &1, &2, |_| true));
+    assert!(!is_reachable(&graph, &1, &2, dependencies));
+    assert!(!is_reachable(&graph, &1, &4, |_| false));
+}
+
+#[test]
+fn shortest_paths_are_the_cheapest() {
+    let graph = graph();
+    assert_eq!(
+        shortest_path(&graph, &5, &4, |_| true),
+        Some(Path {
+            nodes: vec![5, 1, 2, 4],
+            edges: vec![5, 0, 1],
+            cost: 3,
+        })
+    );
+    assert_eq!(
+        shortest_path(&graph, &1, &4, dependencies),
+        Some(Path {
+            nodes: vec![1, 3, 4],
+            edges: vec![2, 3],
+            cost: 6,
+        })
+    );
+    assert_eq!(
+        shortest_path(&graph, &1, &4, |e| e.weight() < 5 && dependencies(e)),
+        None
+    );
+}
+
+#[test]
+fn a_node_is_its_own_shortest_path() {
+    let graph = graph();
+    assert_eq!(
+        shortest_path(&graph, &3, &3, |_| true),
+        Some(Path {
+            nodes: vec![3],
+            edges: vec![],
+            cost: 0,
+        })
+    );
+    assert_eq!(shortest_path(&graph, &4, &1, |_| true), None);
+    assert_eq!(shortest_path(&graph, &9, &9, |_| true), None);
+}
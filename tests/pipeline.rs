@@ -0,0 +1,41 @@
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::pipeline::AlgorithmExt;
+use oscoin_graph_api::rng::AlgorithmSeed;
+use oscoin_graph_api::types::{AlgorithmError, NodeRank};
+use oscoin_graph_api::{Graph, GraphAlgorithm, GraphAnnotator};
+
+/// Returns the seed it was run with.
+struct Seed;
+
+impl<G, A> GraphAlgorithm<G, A> for Seed
+where
+    G: Graph,
+    A: GraphAnnotator<Annotation = (u64, NodeRank<f64>)>,
+{
+    type Context = ();
+    type Output = u64;
+    type Error = AlgorithmError;
+    type RngSeed = u64;
+    type Annotation = (u64, NodeRank<f64>);
+
+    fn execute(&self, _: &mut (), _: &G, _: &mut A, seed: u64) -> Result<u64, AlgorithmError> {
+        Ok(seed)
+    }
+}
+
+#[test]
+fn stages_get_their_own_seed() {
+    let graph = EdgeListGraph::<u64, f64>::new();
+    let mut ranks: Vec<(u64, NodeRank<f64>)> = Vec::new();
+    let pipeline = Seed.then(Seed);
+    let (first, second) = pipeline
+        .execute(&mut ((), ()), &graph, &mut ranks, 42)
+        .unwrap();
+
+    assert_ne!(first, second);
+    assert_eq!((first, second), (42.split(0), 42.split(1)));
+    let again = pipeline
+        .execute(&mut ((), ()), &graph, &mut ranks, 42)
+        .unwrap();
+    assert_eq!(again, (first, second));
+}
@@ -0,0 +1,43 @@
+use oscoin_graph_api::algorithms::prune::PruneContext;
+use oscoin_graph_api::algorithms::Prune;
+use oscoin_graph_api::bench::Workload;
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::progress::{ExecutionStats, NoProgress, ProgressSink};
+use oscoin_graph_api::types::{HyperParameters, NodeRank};
+use oscoin_graph_api::GraphAlgorithm;
+
+#[derive(Default)]
+struct Stats(Vec<ExecutionStats>);
+
+impl ProgressSink for Stats {
+    fn on_stats(&mut self, stats: &ExecutionStats) {
+        self.0.push(stats.clone());
+    }
+}
+
+#[test]
+fn only_interested_sinks_want_stats() {
+    assert!(!NoProgress.wants_stats());
+    assert!(!(|_: u64, _: Option<u64>| {}).wants_stats());
+    assert!(Stats::default().wants_stats());
+}
+
+#[test]
+fn stats_are_reported_once() {
+    let graph: EdgeListGraph = Workload::random(200, 3, 1).build();
+    let prune = Prune::new(HyperParameters::osrank_paper_defaults());
+    let mut ranks: Vec<(u64, NodeRank<f64>)> = Vec::new();
+    let mut stats = Stats::default();
+    prune
+        .execute_with_progress(
+            &mut PruneContext::default(),
+            &graph,
+            &mut ranks,
+            0,
+            &mut stats,
+        )
+        .unwrap();
+
+    assert_eq!(stats.0.len(), 1);
+    assert!(stats.0[0].iterations > 0);
+}
@@ -0,0 +1,106 @@
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::properties::{HasProperties, Properties, PropertyKey, PropertyStore};
+use oscoin_graph_api::{Graph, GraphWriter};
+
+mod common;
+
+use common::{dependency, project};
+
+const LAST_SEEN: PropertyKey<u64> = PropertyKey::new("last_seen");
+const NAME: PropertyKey<String> = PropertyKey::new("name");
+/// Shares its name with `LAST_SEEN`, with another type.
+const LAST_SEEN_TEXT: PropertyKey<String> = PropertyKey::new("last_seen");
+
+#[test]
+fn properties_are_typed_by_key() {
+    let mut props = Properties::new();
+    assert!(props.is_empty());
+    assert_eq!(props.get(&LAST_SEEN), None);
+
+    assert_eq!(props.set(&LAST_SEEN, 3), None);
+    assert_eq!(props.set(&NAME, "oscoin".to_owned()), None);
+    assert_eq!(props.len(), 2);
+    assert_eq!(props.names().collect::<Vec<_>>(), vec!["last_seen", "name"]);
+
+    assert_eq!(props.get(&LAST_SEEN), Some(&3));
+    assert_eq!(props.get(&LAST_SEEN_TEXT), None);
+    *props.get_mut(&LAST_SEEN).unwrap() += 1;
+    assert_eq!(props.set(&LAST_SEEN, 5), Some(4));
+
+    assert_eq!(props.remove(&NAME), Some("oscoin".to_owned()));
+    assert_eq!(props.remove(&NAME), None);
+    assert_eq!(props.len(), 1);
+    assert_eq!(LAST_SEEN.name(), "last_seen");
+}
+
+#[test]
+fn keys_of_the_same_name_replace_each_other() {
+    let mut props = Properties::new();
+    props.set(&LAST_SEEN, 3);
+
+    // The previous value has another type, so it's dropped.
+    assert_eq!(props.set(&LAST_SEEN_TEXT, "yesterday".to_owned()), None);
+    assert_eq!(props.get(&LAST_SEEN), None);
+    assert_eq!(props.get_mut(&LAST_SEEN), None);
+    assert_eq!(props.len(), 1);
+
+    assert_eq!(props.remove(&LAST_SEEN), None);
+    assert!(props.is_empty());
+}
+
+#[test]
+fn stores_compare_by_value() {
+    let mut props = Properties::new();
+    props.set(&LAST_SEEN, 3);
+    props.set(&NAME, "oscoin".to_owned());
+
+    let mut copy = props.clone();
+    assert_eq!(copy, props);
+    copy.set(&LAST_SEEN, 4);
+    assert_ne!(copy, props);
+    copy.set(&LAST_SEEN_TEXT, "3".to_owned());
+    assert_ne!(copy, props);
+    copy.remove(&LAST_SEEN_TEXT);
+    assert_ne!(copy, props);
+
+    assert_eq!(format!("{:?}", copy), r#"{"name": "oscoin"}"#);
+}
+
+#[test]
+fn graph_objects_carry_properties() {
+    let mut graph: EdgeListGraph = EdgeListGraph::new();
+    graph.add_node(1, project());
+    graph.add_node(2, project());
+    graph.add_edge(0, &1, &2, dependency());
+    graph.node_properties_mut(&1).unwrap().set(&LAST_SEEN, 3);
+    graph
+        .edge_properties_mut(&0)
+        .unwrap()
+        .set(&NAME, "dep".to_owned());
+    assert!(graph.node_properties_mut(&3).is_none());
+    assert!(graph.edge_properties_mut(&1).is_none());
+
+    assert_eq!(
+        graph.get_node(&1).unwrap().properties().get(&LAST_SEEN),
+        Some(&3)
+    );
+    assert!(graph.get_node(&2).unwrap().properties().is_empty());
+    assert_eq!(
+        graph.get_edge(&0).unwrap().properties().get(&NAME),
+        Some(&"dep".to_owned())
+    );
+
+    // Replacing a node keeps its properties, replacing an edge drops them.
+    graph.add_node(1, project());
+    graph.add_edge(0, &1, &2, dependency());
+    assert_eq!(
+        graph.get_node(&1).unwrap().properties().get(&LAST_SEEN),
+        Some(&3)
+    );
+    assert!(graph.get_edge(&0).unwrap().properties().is_empty());
+
+    // So does removing a node.
+    graph.remove_node(1);
+    graph.add_node(1, project());
+    assert!(graph.get_node(&1).unwrap().properties().is_empty());
+}
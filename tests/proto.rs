@@ -0,0 +1,131 @@
+#![cfg(feature = "proto")]
+
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+
+use prost::Message;
+
+use oscoin_graph_api::bench::Workload;
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::proto::{self, ProtoError};
+use oscoin_graph_api::types::{EdgeType, EdgeTypeTag, HyperParameters, NodeType};
+use oscoin_graph_api::{Edge, Graph, Layer};
+
+/// Encode a message to bytes and decode it back.
+fn round_trip<M: Message + Default>(message: &M) -> M {
+    M::decode(message.encode_to_vec().as_slice()).unwrap()
+}
+
+#[test]
+fn types_round_trip() {
+    let node_types = vec![
+        NodeType::User {
+            contributions_to_all_projects: 3,
+        },
+        NodeType::Project {
+            contributions_from_all_users: 4,
+        },
+        NodeType::Org {
+            contributions_to_owned_projects: 5,
+        },
+        NodeType::Checkpoint { contributions: 6 },
+    ];
+    for node_type in node_types {
+        let decoded = round_trip(&proto::NodeType::from(&node_type));
+        assert_eq!(NodeType::try_from(decoded), Ok(node_type));
+    }
+
+    let edge_types = vec![
+        EdgeType::ProjectToUserContribution(1),
+        EdgeType::UserToProjectMembership(2),
+        EdgeType::Dependency,
+        EdgeType::OrgToProjectOwnership,
+        EdgeType::Donation(100),
+        EdgeType::Custom {
+            tag: "review".to_owned(),
+            contributions: 7,
+        },
+    ];
+    for edge_type in edge_types {
+        let decoded = round_trip(&proto::EdgeType::from(&edge_type));
+        assert_eq!(EdgeType::try_from(decoded), Ok(edge_type));
+    }
+}
+
+#[test]
+fn hyperparameters_round_trip() {
+    let mut params: HyperParameters<f64> = HyperParameters::osrank_paper_defaults();
+    params
+        .edge_weights
+        .insert(EdgeTypeTag::Custom("review".to_owned()), 0.25);
+    params.max_user_rank = Some(0.1);
+    params.rank_decay = Some(0.9);
+
+    let encoded = proto::HyperParameters::from(&params);
+    let decoded = HyperParameters::try_from(round_trip(&encoded)).unwrap();
+    assert_eq!(decoded.edge_weights, params.edge_weights);
+    assert_eq!(decoded.max_user_rank, Some(0.1));
+    assert_eq!(decoded.rank_decay, Some(0.9));
+    assert_eq!(proto::HyperParameters::from(&decoded), encoded);
+}
+
+#[test]
+fn invalid_messages_are_rejected() {
+    assert_eq!(
+        NodeType::try_from(proto::NodeType::default()),
+        Err(ProtoError::MissingField("NodeType.kind"))
+    );
+    assert_eq!(
+        EdgeType::try_from(proto::EdgeType::default()),
+        Err(ProtoError::UnknownEdgeKind(0))
+    );
+    let unknown = proto::EdgeType {
+        kind: 42,
+        ..Default::default()
+    };
+    assert_eq!(
+        EdgeType::try_from(unknown),
+        Err(ProtoError::UnknownEdgeKind(42))
+    );
+
+    let params = proto::HyperParameters::from(&HyperParameters::<f64>::osrank_paper_defaults());
+    let no_damping = proto::HyperParameters {
+        damping_factors: None,
+        ..params
+    };
+    assert_eq!(
+        HyperParameters::try_from(no_damping).err(),
+        Some(ProtoError::MissingField("HyperParameters.damping_factors"))
+    );
+}
+
+#[test]
+fn graphs_are_sent_in_chunks() {
+    let graph: EdgeListGraph = Workload::random(25, 2, 4).build();
+    let layer = Layer::from_static("osrank");
+    let chunks = proto::graph_chunks(&graph, &layer, 10);
+
+    assert_eq!(chunks.len(), 3);
+    let mut nodes = BTreeSet::new();
+    let mut edges = BTreeSet::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let chunk = round_trip(chunk);
+        assert_eq!(chunk.version, proto::VERSION);
+        assert_eq!(chunk.layer, "osrank");
+        assert_eq!(chunk.index, i as u64);
+        assert_eq!(chunk.last, i == 2);
+        assert!(chunk.nodes.len() <= 10);
+
+        nodes.extend(chunk.nodes.iter().map(|n| n.id));
+        for e in chunk.edges {
+            assert!(edges.insert(e.id));
+            assert_eq!(graph.get_edge(&e.id).map(|x| *x.source()), Some(e.from));
+        }
+    }
+    assert_eq!(nodes.len(), 25);
+    assert_eq!(edges.len(), graph.edge_count());
+
+    let empty = proto::graph_chunks(&EdgeListGraph::<u64, f64>::new(), &layer, 10);
+    assert_eq!(empty.len(), 1);
+    assert!(empty[0].last && empty[0].nodes.is_empty());
+}
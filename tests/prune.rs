@@ -0,0 +1,186 @@
+use oscoin_graph_api::algorithms::prune::{PruneContext, PruneOutput};
+use oscoin_graph_api::algorithms::Prune;
+use oscoin_graph_api::bench::Workload;
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::types::{HyperParameters, NodeRank};
+use oscoin_graph_api::{Direction, DryRun, Graph, GraphAlgorithm};
+
+mod common;
+
+use common::reversed_arena;
+
+/// The output of a pruning pass, its annotations and its final context.
+type Pruned = (
+    PruneOutput<u64, u64>,
+    Vec<(u64, NodeRank<f64>)>,
+    PruneContext<u64>,
+);
+
+fn prune(graph: &EdgeListGraph, threshold: f64) -> Pruned {
+    let mut hyperparams = HyperParameters::osrank_paper_defaults();
+    hyperparams.pruning_threshold = threshold;
+
+    let mut context = PruneContext::default();
+    let mut annotations = Vec::new();
+    let output = Prune::new(hyperparams)
+        .emit_subgraph()
+        .execute(&mut context, graph, &mut annotations, 0)
+        .unwrap();
+    (output, annotations, context)
+}
+
+#[test]
+fn nodes_below_the_threshold_are_pruned_and_annotated() {
+    let graph: EdgeListGraph = Workload::random(200, 3, 7).build();
+    let (output, mut annotations, context) = prune(&graph, 0.004);
+
+    assert!(!output.kept.is_empty());
+    assert!(!output.pruned.is_empty());
+    assert_eq!(output.kept.len() + output.pruned.len(), 200);
+    assert_eq!(context.ranks.len(), 200);
+    for id in &output.kept {
+        assert!(context.ranks[id] >= 0.004);
+    }
+
+    annotations.sort_by_key(|(id, _)| *id);
+    let annotated: Vec<u64> = annotations.iter().map(|(id, _)| *id).collect();
+    assert_eq!(annotated, output.pruned);
+    for (id, rank) in &annotations {
+        assert!(rank.rank < 0.004);
+        assert_eq!(rank.rank, context.ranks[id]);
+    }
+}
+
+#[test]
+fn the_subgraph_holds_the_edges_between_kept_nodes() {
+    let graph: EdgeListGraph = Workload::random(200, 3, 7).build();
+    let (output, _, _) = prune(&graph, 0.004);
+    let subgraph = output.subgraph.unwrap();
+    assert_eq!(subgraph.nodes, output.kept);
+
+    let mut expected = Vec::new();
+    for id in &output.kept {
+        for e in graph.edges_directed(id, Direction::Outgoing) {
+            if output.kept.contains(e.to) {
+                expected.push(*e.id);
+            }
+        }
+    }
+    let mut edges = subgraph.edges.clone();
+    edges.sort_unstable();
+    expected.sort_unstable();
+    assert!(!edges.is_empty());
+    assert_eq!(edges, expected);
+}
+
+#[test]
+fn ranks_do_not_depend_on_the_backend() {
+    let graph: EdgeListGraph = Workload::random(200, 3, 7).build();
+    let arena = reversed_arena(&graph);
+    let prune: Prune<f64> = Prune::new(HyperParameters::osrank_paper_defaults());
+
+    let ranks = |context: PruneContext<u64>| {
+        let mut ranks: Vec<(u64, u64)> = context
+            .ranks
+            .into_iter()
+            .map(|(id, rank)| (id, rank.to_bits()))
+            .collect();
+        ranks.sort_unstable();
+        ranks
+    };
+    let (mut list, mut annotated) = (PruneContext::default(), Vec::new());
+    prune.execute(&mut list, &graph, &mut annotated, 0).unwrap();
+    let (mut slots, mut arena_annotated) = (PruneContext::default(), Vec::new());
+    prune
+        .execute(&mut slots, &arena, &mut arena_annotated, 0)
+        .unwrap();
+
+    assert_eq!(ranks(list), ranks(slots));
+    assert_eq!(annotated, arena_annotated);
+}
+
+#[test]
+fn a_zero_threshold_prunes_nothing() {
+    let graph: EdgeListGraph = Workload::random(50, 2, 3).build();
+    let (output, annotations, _) = prune(&graph, 0.0);
+
+    assert_eq!(output.kept.len(), 50);
+    assert!(output.pruned.is_empty());
+    assert!(annotations.is_empty());
+    assert!(output.explanations.is_none());
+}
+
+#[test]
+fn dry_runs_preview_the_annotations() {
+    let graph: EdgeListGraph = Workload::random(100, 3, 9).build();
+    let (output, annotations, context) = prune(&graph, 0.01);
+
+    let mut hyperparams = HyperParameters::osrank_paper_defaults();
+    hyperparams.pruning_threshold = 0.01;
+    let mut previous = PruneContext::default();
+    previous.ranks.insert(1000, 0.5);
+    let dry_run = Prune::new(hyperparams)
+        .emit_subgraph()
+        .execute_dry_run(&previous, &graph, 0)
+        .unwrap();
+    assert_eq!(dry_run.output, output);
+    assert_eq!(dry_run.annotations, annotations);
+
+    // The context given isn't updated.
+    assert_eq!(previous.ranks.len(), 1);
+    assert_eq!(context.ranks.len(), 100);
+}
+
+#[test]
+fn explanations_break_approximate_ranks_down_by_source() {
+    let graph: EdgeListGraph = Workload::random(60, 3, 4).build();
+    let mut context = PruneContext::default();
+    let mut annotations = Vec::new();
+    let prune: Prune<f64> = Prune::new(HyperParameters::osrank_paper_defaults());
+    let output = prune
+        .clone()
+        .execute(&mut context, &graph, &mut annotations, 0)
+        .unwrap();
+    assert_eq!(output.explanations, None);
+
+    let explained = prune
+        .clone()
+        .explain(usize::MAX)
+        .execute(&mut PruneContext::default(), &graph, &mut Vec::new(), 0)
+        .unwrap();
+    assert_eq!(explained.kept, output.kept);
+    let explanations = explained.explanations.unwrap();
+    assert_eq!(explanations.len(), 60);
+    assert!(explanations.windows(2).all(|w| w[0].node < w[1].node));
+
+    for explanation in &explanations {
+        assert_eq!(explanation.rank, context.ranks[&explanation.node]);
+        let shares: f64 = explanation.sources.iter().map(|s| s.share).sum();
+        assert!((explanation.own + shares - 1.0).abs() < 1e-9);
+        assert!(explanation
+            .sources
+            .windows(2)
+            .all(|w| w[0].share > w[1].share
+                || (w[0].share == w[1].share && w[0].source < w[1].source)));
+        // Walks only come along incoming edges.
+        for source in &explanation.sources {
+            assert!(graph
+                .edges_directed(&explanation.node, Direction::Incoming)
+                .iter()
+                .any(|e| *e.from == source.source));
+        }
+    }
+    assert!(explanations.iter().any(|e| e.sources.len() > 1));
+
+    // Only the top sources are kept.
+    let top = prune
+        .explain(1)
+        .execute(&mut PruneContext::default(), &graph, &mut Vec::new(), 0)
+        .unwrap()
+        .explanations
+        .unwrap();
+    for (top, all) in top.iter().zip(&explanations) {
+        assert_eq!(top.own, all.own);
+        assert_eq!(top.sources[..], all.sources[..all.sources.len().min(1)]);
+    }
+}
@@ -0,0 +1,166 @@
+#![cfg(feature = "python")]
+//! The bindings are exercised from Python, through an embedded interpreter
+//! with the extension module bound to `og`.
+
+use std::ffi::CString;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use pyo3::wrap_pymodule;
+
+use oscoin_graph_api::python::oscoin_graph;
+
+/// Run a Python script, failing on any exception it raises.
+fn run(script: &str) {
+    let script = CString::new(script).unwrap();
+    Python::initialize();
+    Python::attach(|py| {
+        let globals = PyDict::new(py);
+        globals
+            .set_item("og", wrap_pymodule!(oscoin_graph)(py))
+            .unwrap();
+        if let Err(err) = py.run(&script, Some(&globals), None) {
+            err.print(py);
+            panic!("{}", err);
+        }
+    })
+}
+
+#[test]
+fn graphs_are_built_from_python() {
+    run(r#"
+g = og.Graph()
+g.add_node(1, "user(3)")
+g.add_node(2, "project(3)")
+g.add_edge(0, 1, 2, "user_to_project_contribution(3)", 0.5)
+g.add_edge(1, 2, 1, "project_to_user_contribution(3)")
+
+assert g.node_count() == 2 and len(g) == 2
+assert g.edge_count() == 2
+assert sorted(g.nodes()) == [1, 2]
+assert sorted(g.edges()) == [
+    (0, 1, 2, "user_to_project_contribution(3)", 0.5),
+    (1, 2, 1, "project_to_user_contribution(3)", 0.0),
+]
+assert g.rank(1) == 0.0
+"#);
+}
+
+#[test]
+fn invalid_arguments_raise() {
+    run(r#"
+g = og.Graph()
+g.add_node(1, "user")
+
+def raises(error, f, *args):
+    try:
+        f(*args)
+    except error:
+        return
+    raise AssertionError(f"{f.__name__}{args} didn't raise {error.__name__}")
+
+raises(ValueError, g.add_node, 2, "robot")
+raises(ValueError, g.add_edge, 0, 1, 1, "friendship")
+raises(KeyError, g.add_edge, 0, 1, 2, "dependency")
+raises(KeyError, g.rank, 2)
+raises(ValueError, og.HyperParameters, {"friendship": 1.0})
+raises(ValueError, og.degree_centrality, g, "sideways")
+raises(IOError, g.import_git_log, "oscoin", "/nonexistent/git.log")
+assert g.edge_count() == 0
+"#);
+}
+
+#[test]
+fn hyperparameters_have_defaults() {
+    run(r#"
+params = og.HyperParameters()
+assert params.edge_weights == {}
+assert params.pruning_threshold == 0.0
+assert params.project_damping == 0.85
+assert params.account_damping == 0.85
+assert params.r_value == 10
+
+params = og.HyperParameters({"dependency": 4.0}, project_damping=0.5, r_value=3)
+assert params.edge_weights == {"dependency": 4.0}
+assert params.project_damping == 0.5
+assert params.r_value == 3
+assert "pruning_threshold" in repr(params)
+"#);
+}
+
+#[test]
+fn algorithms_return_scores_by_node() {
+    run(r#"
+g = og.Graph()
+for id, node_type in [(1, "user(2)"), (2, "project(2)"), (3, "project")]:
+    g.add_node(id, node_type)
+g.add_edge(0, 1, 2, "user_to_project_contribution(2)")
+g.add_edge(1, 2, 1, "project_to_user_contribution(2)")
+g.add_edge(2, 3, 2, "dependency")
+
+params = og.HyperParameters({
+    "user_to_project_contribution": 1.0,
+    "project_to_user_contribution": 1.0,
+    "dependency": 1.0,
+})
+g.assign_weights(params)
+
+ranks = og.osrank(g, params)
+assert sorted(ranks) == [1, 2, 3]
+assert abs(sum(ranks.values()) - 1.0) < 1e-9
+assert all(g.rank(id) == rank for id, rank in ranks.items())
+
+scores = og.hits(g, params)
+assert sorted(scores) == [1, 2, 3]
+assert all(len(s) == 2 for s in scores.values())
+
+# Only the path from 3 to 1 goes through another node.
+assert og.betweenness(g) == {1: 0.0, 2: 1.0, 3: 0.0}
+assert og.degree_centrality(g, "incoming") == {1: 1.0, 2: 2.0, 3: 0.0}
+assert og.degree_centrality(g, "outgoing") == {1: 1.0, 2: 1.0, 3: 1.0}
+"#);
+}
+
+#[test]
+fn assigning_weights_needs_every_edge_weight() {
+    run(r#"
+g = og.Graph()
+g.add_node(1, "project")
+g.add_node(2, "project")
+g.add_edge(0, 1, 2, "dependency")
+try:
+    g.assign_weights(og.HyperParameters())
+except RuntimeError:
+    pass
+else:
+    raise AssertionError("missing weights were accepted")
+"#);
+}
+
+#[test]
+fn git_logs_are_imported() {
+    let path = std::env::temp_dir().join(format!("oscoin-python-{}.log", std::process::id()));
+    std::fs::write(
+        &path,
+        "alice@example.com\nbob@example.com\n\nalice@example.com\n",
+    )
+    .unwrap();
+
+    let script = format!(
+        r#"
+g = og.Graph()
+nodes, edges = g.import_git_log("oscoin", {:?})
+assert (nodes, edges) == (g.node_count(), g.edge_count())
+assert nodes == 3
+assert sorted(e[3] for e in g.edges()) == [
+    "project_to_user_contribution(1)",
+    "project_to_user_contribution(2)",
+    "user_to_project_contribution(1)",
+    "user_to_project_contribution(2)",
+]
+"#,
+        path.to_str().unwrap()
+    );
+    run(&script);
+    std::fs::remove_file(&path).unwrap();
+}
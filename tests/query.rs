@@ -0,0 +1,158 @@
+use oscoin_graph_api::ledger::{LedgerEdgeData, LedgerNodeData};
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::query::{Expansion, Match, NodePattern, Query};
+use oscoin_graph_api::stats::NodeKind;
+use oscoin_graph_api::types::{EdgeData, EdgeType, EdgeTypeTag, NodeData, NodeType};
+use oscoin_graph_api::{Direction, GraphWriter};
+
+mod common;
+
+use common::project;
+
+const MEMBER: EdgeTypeTag = EdgeTypeTag::UserToProjectMembership;
+
+/// Users `1` and `2` both maintain project `10`, and respectively `11` and
+/// `12`, which `12` depends on. Edges are numbered in that order.
+fn graph() -> EdgeListGraph {
+    let mut graph = EdgeListGraph::new();
+    for id in [1, 2].iter() {
+        graph.add_node(
+            *id,
+            NodeData::from_node_type(NodeType::User {
+                contributions_to_all_projects: 0,
+            }),
+        );
+    }
+    for id in [10, 11, 12].iter() {
+        graph.add_node(*id, project());
+    }
+    let edges = vec![
+        (1, 10, EdgeType::UserToProjectMembership(1)),
+        (1, 11, EdgeType::UserToProjectMembership(1)),
+        (2, 10, EdgeType::UserToProjectMembership(1)),
+        (2, 12, EdgeType::UserToProjectMembership(1)),
+        (12, 11, EdgeType::Dependency),
+    ];
+    for (id, (from, to, edge_type)) in edges.into_iter().enumerate() {
+        graph.add_edge(id as u64, &from, &to, EdgeData::from_edge_type(edge_type));
+    }
+    graph
+}
+
+/// The matches of a query, sorted.
+fn matches(query: &Query<u64>, graph: &EdgeListGraph) -> Vec<(Vec<u64>, Vec<u64>)> {
+    let mut matches: Vec<_> = query
+        .execute(graph)
+        .into_iter()
+        .map(|Match { nodes, edges }| (nodes, edges))
+        .collect();
+    matches.sort();
+    matches
+}
+
+#[test]
+fn node_patterns_filter_nodes() {
+    let graph = graph();
+    let all = matches(&Query::nodes(), &graph);
+    assert_eq!(all.len(), 5);
+    assert!(all.iter().all(|(_, edges)| edges.is_empty()));
+
+    let users = Query::nodes().of_type(NodeKind::User);
+    assert_eq!(
+        matches(&users, &graph),
+        vec![(vec![1], vec![]), (vec![2], vec![])]
+    );
+    assert_eq!(
+        matches(&users.with_id(10), &graph),
+        Vec::<(Vec<u64>, Vec<u64>)>::new()
+    );
+    assert_eq!(
+        matches(&Query::nodes().with_id(11), &graph),
+        vec![(vec![11], vec![])]
+    );
+    assert!(matches(&Query::nodes().with_id(99), &graph).is_empty());
+}
+
+#[test]
+fn paths_use_each_edge_once() {
+    // The projects maintained by users who also maintain `10`.
+    let query = Query::nodes()
+        .with_id(10)
+        .with_incoming_edge(MEMBER)
+        .of_type(NodeKind::User)
+        .with_edge(MEMBER)
+        .to(NodePattern::of_type(NodeKind::Project));
+
+    assert_eq!(
+        matches(&query, &graph()),
+        vec![(vec![10, 1, 11], vec![0, 1]), (vec![10, 2, 12], vec![2, 3])]
+    );
+}
+
+#[test]
+fn edges_are_filtered_by_type_and_direction() {
+    let graph = graph();
+    let dependencies = Query::nodes().with_edge(EdgeTypeTag::Dependency);
+    assert_eq!(
+        matches(&dependencies, &graph),
+        vec![(vec![12, 11], vec![4])]
+    );
+
+    let into_11 = Query::nodes()
+        .with_any_edge(Direction::Outgoing)
+        .with_id(11);
+    assert_eq!(
+        matches(&into_11, &graph),
+        vec![(vec![1, 11], vec![1]), (vec![12, 11], vec![4])]
+    );
+
+    let around_12 = Query::nodes().with_id(12).with_any_edge(Direction::Both);
+    assert_eq!(
+        matches(&around_12, &graph),
+        vec![(vec![12, 2], vec![3]), (vec![12, 11], vec![4])]
+    );
+}
+
+#[test]
+fn plans_start_from_the_most_selective_pattern() {
+    // By id first, expanding backward against the direction of the step.
+    let query = Query::nodes()
+        .of_type(NodeKind::User)
+        .with_edge(MEMBER)
+        .with_id(12);
+    let plan = query.plan();
+    assert_eq!(plan.anchor, 1);
+    assert_eq!(
+        plan.expansions,
+        vec![Expansion {
+            from: 1,
+            to: 0,
+            edge: 0,
+            direction: Direction::Incoming,
+        }]
+    );
+    assert_eq!(plan.execute(&graph()), query.execute(&graph()));
+    assert_eq!(matches(&query, &graph()), vec![(vec![2, 12], vec![3])]);
+
+    // By type otherwise, then forward and backward from there.
+    let query = Query::nodes()
+        .with_edge(MEMBER)
+        .of_type(NodeKind::Project)
+        .with_edge(EdgeTypeTag::Dependency);
+    let plan = query.plan();
+    assert_eq!(plan.anchor, 1);
+    let order: Vec<(usize, usize)> = plan.expansions.iter().map(|e| (e.from, e.to)).collect();
+    assert_eq!(order, vec![(1, 2), (1, 0)]);
+    assert_eq!(
+        matches(&query, &graph()),
+        vec![(vec![2, 12, 11], vec![3, 4])]
+    );
+
+    assert_eq!(
+        Query::<u64>::nodes()
+            .with_any_edge(Direction::Both)
+            .plan()
+            .anchor,
+        0
+    );
+}
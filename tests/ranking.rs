@@ -0,0 +1,202 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+
+use oscoin_graph_api::ranking::{
+    check_invariants, compare, normalize, top_k, RankViolation, NORMALIZATION_TOLERANCE,
+};
+use oscoin_graph_api::types::{NodeRank, OrderedFloat, TotalOrd};
+
+fn ranks(ranks: &[(u64, f64)]) -> BTreeMap<u64, NodeRank<f64>> {
+    ranks
+        .iter()
+        .map(|&(id, rank)| (id, NodeRank { rank }))
+        .collect()
+}
+
+#[test]
+fn identical_assignments_do_not_differ() {
+    let a = ranks(&[(1, 0.5), (2, 0.3), (3, 0.2)]);
+    let cmp = compare(&a, &a);
+
+    assert_eq!(cmp.l1, 0.0);
+    assert_eq!(cmp.linf, 0.0);
+    assert!((cmp.kendall_tau - 1.0).abs() < 1e-12);
+    assert!(cmp.reordered.is_empty());
+}
+
+#[test]
+fn distances_and_reordered_nodes_are_reported() {
+    let a = ranks(&[(1, 0.5), (2, 0.3), (3, 0.2)]);
+    let b = ranks(&[(1, 0.5), (2, 0.1), (3, 0.4)]);
+    let cmp = compare(&a, &b);
+
+    assert!((cmp.l1 - 0.4).abs() < 1e-12);
+    assert!((cmp.linf - 0.2).abs() < 1e-12);
+    // One discordant pair out of three.
+    assert!((cmp.kendall_tau - 1.0 / 3.0).abs() < 1e-12);
+    assert_eq!(
+        cmp.reordered,
+        vec![2, 3].into_iter().collect::<BTreeSet<_>>()
+    );
+}
+
+#[test]
+fn reversed_orderings_are_anticorrelated() {
+    let a = ranks(&[(1, 0.1), (2, 0.2), (3, 0.3), (4, 0.4)]);
+    let b = ranks(&[(1, 0.4), (2, 0.3), (3, 0.2), (4, 0.1)]);
+    let cmp = compare(&a, &b);
+
+    assert!((cmp.kendall_tau + 1.0).abs() < 1e-12);
+    assert_eq!(cmp.reordered.len(), 4);
+}
+
+#[test]
+fn missing_nodes_have_a_zero_rank() {
+    let a = ranks(&[(1, 0.6), (2, 0.4)]);
+    let b = ranks(&[(1, 0.6), (2, 0.3), (3, 0.1)]);
+    let cmp = compare(&a, &b);
+
+    assert!((cmp.l1 - 0.2).abs() < 1e-12);
+    assert!((cmp.linf - 0.1).abs() < 1e-12);
+    assert!(cmp.reordered.is_empty());
+}
+
+#[test]
+fn floats_are_totally_ordered() {
+    // Called through the trait, floats having an inherent `total_cmp`.
+    assert_eq!(TotalOrd::total_cmp(&1.0f64, &2.0), Ordering::Less);
+    assert_eq!(TotalOrd::total_cmp(&-0.0f64, &0.0), Ordering::Equal);
+    assert_eq!(TotalOrd::total_cmp(&f64::NAN, &f64::NAN), Ordering::Equal);
+    assert_eq!(
+        TotalOrd::total_cmp(&f64::NAN, &f64::NEG_INFINITY),
+        Ordering::Less
+    );
+    assert_eq!(TotalOrd::total_cmp(&0.0f32, &f32::NAN), Ordering::Greater);
+    assert_eq!(TotalOrd::total_cmp(&3u64, &2), Ordering::Greater);
+
+    let mut floats: Vec<OrderedFloat<f64>> = vec![2.0, f64::NAN, -1.0, 0.5]
+        .into_iter()
+        .map(OrderedFloat::from)
+        .collect();
+    floats.sort();
+    assert!(floats[0].into_inner().is_nan());
+    assert_eq!(
+        floats[1..].iter().map(|f| f.0).collect::<Vec<_>>(),
+        vec![-1.0, 0.5, 2.0]
+    );
+    assert_eq!(OrderedFloat(f64::NAN), OrderedFloat(f64::NAN));
+    assert_eq!(OrderedFloat(-0.0), OrderedFloat(0.0));
+    let set: BTreeSet<OrderedFloat<f64>> = floats.into_iter().collect();
+    assert_eq!(set.len(), 4);
+}
+
+#[test]
+fn node_ranks_are_ordered_by_value() {
+    let rank = |rank| NodeRank { rank };
+    assert_eq!(rank(0.25).rank_cmp(&rank(0.5)), Ordering::Less);
+    assert_eq!(rank(f64::NAN).rank_cmp(&rank(0.0)), Ordering::Less);
+    assert_eq!(rank(0.25).max(rank(0.5)), rank(0.5));
+    assert_eq!(rank(0.5).max(rank(f64::NAN)), rank(0.5));
+    assert_eq!(rank(f64::NAN).max(rank(0.5)), rank(0.5));
+
+    let mut ranks = [rank(0.25), rank(f64::NAN), rank(0.75)];
+    ranks.sort_by(|a, b| b.rank_cmp(a));
+    assert_eq!(&ranks[..2], &[rank(0.75), rank(0.25)]);
+    assert!(ranks[2].rank.is_nan());
+
+    let mut ordered: Vec<_> = vec![rank(0.5), rank(f64::NAN), rank(0.1)]
+        .into_iter()
+        .map(NodeRank::ordered)
+        .collect();
+    ordered.sort();
+    assert_eq!(ordered[1], rank(0.1).ordered());
+    assert_eq!(ordered.iter().max(), Some(&rank(0.5).ordered()));
+
+    // Integer ranks are ordered, and compare as such.
+    assert!(NodeRank { rank: 2u64 } > NodeRank { rank: 1 });
+}
+
+#[test]
+fn top_ranks_come_first_and_nan_last() {
+    let ranks = ranks(&[(1, 0.25), (2, f64::NAN), (3, 0.5), (4, 0.25), (5, 0.0)]);
+    let ids = |k| -> Vec<u64> { top_k(&ranks, k).into_iter().map(|(id, _)| *id).collect() };
+    assert_eq!(ids(10), vec![3, 1, 4, 5, 2]);
+    assert_eq!(ids(3), vec![3, 1, 4]);
+    assert_eq!(ids(0), Vec::<u64>::new());
+}
+
+#[test]
+fn ranks_are_normalized_to_sum_to_one() {
+    let mut ranks: Vec<(u64, NodeRank<f64>)> = vec![
+        (1, NodeRank { rank: 1.0 }),
+        (2, NodeRank { rank: 3.0 }),
+        (3, NodeRank { rank: 0.0 }),
+    ];
+    normalize(&mut ranks);
+    let values: Vec<f64> = ranks.iter().map(|(_, r)| r.rank).collect();
+    assert_eq!(values, vec![0.25, 0.75, 0.0]);
+    assert_eq!(
+        check_invariants(ranks.iter().map(|(id, r)| (id, r))),
+        Ok(())
+    );
+
+    // Ranks which can't be normalized are left untouched.
+    let mut zeros = vec![(1, NodeRank { rank: 0.0 }), (2, NodeRank { rank: 0.0 })];
+    normalize(&mut zeros);
+    assert_eq!(zeros[1].1.rank, 0.0);
+    let mut infinite = vec![
+        (
+            1,
+            NodeRank {
+                rank: f64::INFINITY,
+            },
+        ),
+        (2, NodeRank { rank: 1.0 }),
+    ];
+    normalize(&mut infinite);
+    assert_eq!(infinite[1].1.rank, 1.0);
+    let mut empty: Vec<(u64, NodeRank<f64>)> = Vec::new();
+    normalize(&mut empty);
+}
+
+#[test]
+fn rank_violations_are_all_reported() {
+    assert_eq!(check_invariants(&ranks(&[])), Ok(()));
+    assert_eq!(
+        check_invariants(&ranks(&[
+            (1, 0.5),
+            (2, 0.5 + NORMALIZATION_TOLERANCE / 2.0)
+        ])),
+        Ok(())
+    );
+
+    let violations = check_invariants(&ranks(&[
+        (1, -0.25),
+        (2, f64::NAN),
+        (3, 1.0),
+        (4, f64::INFINITY),
+    ]))
+    .unwrap_err();
+    assert_eq!(
+        violations,
+        vec![
+            RankViolation::Negative {
+                node: 1,
+                rank: -0.25
+            },
+            RankViolation::NotFinite(2),
+            RankViolation::NotFinite(4),
+            RankViolation::NotNormalized { sum: 0.75 },
+        ]
+    );
+    let messages: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "node 1 has negative rank -0.25",
+            "node 2 has no finite rank",
+            "node 4 has no finite rank",
+            "ranks sum to 0.75, not 1",
+        ]
+    );
+}
@@ -0,0 +1,98 @@
+#![cfg(feature = "replication")]
+
+use oscoin_graph_api::compare::eq_by_ids;
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::replication::{state_hash, Follower, Leader, ReplicationError};
+use oscoin_graph_api::testing::model::{random_ops, Op};
+use oscoin_graph_api::wal::WalEntry;
+use oscoin_graph_api::{Graph, GraphDataWriter, GraphObject, GraphWriter};
+
+/// Apply random mutations to a leader, modifying data in place now and
+/// then, and commit every few of them.
+fn run(leader: &mut Leader<EdgeListGraph>, seed: u64) {
+    for (i, op) in random_ops(seed, 120).into_iter().enumerate() {
+        match op {
+            Op::AddNode(id, data) => leader.add_node(id, data),
+            Op::RemoveNode(id) => leader.remove_node(id),
+            Op::AddEdge { id, from, to, data } => leader.add_edge(id, &from, &to, data),
+            Op::RemoveEdge(id) => leader.remove_edge(id),
+            Op::UpsertEdge { id, from, to, data } => {
+                leader.upsert_edge(id, &from, &to, data);
+            }
+            Op::Clear => leader.clear(),
+        }
+        if let Some(data) = leader.node_data_mut(&(i as u64 % 8)) {
+            data.rank.rank += 1.0;
+        }
+        if let Some(data) = leader.edge_data_mut(&(i as u64 % 16)) {
+            data.weight *= 0.5;
+        }
+        if i % 40 == 39 {
+            for node in leader.nodes_mut() {
+                node.data_mut().rank.rank = 0.0;
+            }
+        }
+        if i % 7 == 0 {
+            let hash = leader.commit().unwrap().state_hash;
+            assert_eq!(hash, state_hash(leader.graph()).unwrap());
+        }
+    }
+    leader.commit().unwrap();
+}
+
+#[test]
+fn followers_track_the_leader() {
+    for seed in 0..20 {
+        let mut leader = Leader::new(EdgeListGraph::new(), 100);
+        run(&mut leader, seed);
+
+        let mut graph = EdgeListGraph::new();
+        let mut follower = Follower::new(&graph, 1).unwrap();
+        let frames = leader.frames_since(1).unwrap().cloned();
+        follower.apply_stream(&mut graph, frames).unwrap();
+
+        assert_eq!(follower.next_seq(), leader.next_seq());
+        assert_eq!(follower.state_hash(), state_hash(&graph).unwrap());
+        assert!(eq_by_ids(&graph, leader.graph()));
+    }
+}
+
+#[test]
+fn diverging_followers_are_detected() {
+    let mut leader = Leader::new(EdgeListGraph::new(), 100);
+    run(&mut leader, 1);
+
+    let mut graph = EdgeListGraph::new();
+    let mut follower = Follower::new(&graph, 1).unwrap();
+    let mut frames: Vec<_> = leader.frames_since(1).unwrap().cloned().collect();
+    frames[2].entries.clear();
+
+    assert!(matches!(
+        follower.apply_stream(&mut graph, frames),
+        Err(ReplicationError::HashMismatch { seq: 3, .. })
+    ));
+    assert_eq!(follower.next_seq(), 3);
+}
+
+#[test]
+fn data_updates_are_recorded_once_per_node() {
+    let mut leader = Leader::new(EdgeListGraph::new(), 100);
+    run(&mut leader, 2);
+    let mut ids: Vec<u64> = leader.graph().nodes().map(|n| *n.id()).collect();
+    ids.sort_unstable();
+
+    for id in ids.iter().rev().chain(&ids) {
+        leader.node_data_mut(id).unwrap().rank.rank += 1.0;
+    }
+    let updated: Vec<u64> = leader
+        .commit()
+        .unwrap()
+        .entries
+        .iter()
+        .map(|entry| match entry {
+            WalEntry::NodeUpdated { id, .. } => *id,
+            _ => panic!("unexpected entry"),
+        })
+        .collect();
+    assert_eq!(updated, ids);
+}
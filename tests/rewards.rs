@@ -0,0 +1,50 @@
+use oscoin_graph_api::rewards::{RewardAnnotator, Rounding};
+use oscoin_graph_api::types::NodeRank;
+use oscoin_graph_api::GraphAnnotator;
+
+fn annotator(budget: u64, rounding: Rounding, ranks: &[f64]) -> RewardAnnotator<u64> {
+    let mut annotator = RewardAnnotator::with_rounding(budget, rounding);
+    for (id, rank) in ranks.iter().enumerate() {
+        annotator.annotate_graph((id as u64, NodeRank { rank: *rank }));
+    }
+    annotator
+}
+
+#[test]
+fn large_budgets_are_split_exactly() {
+    let budget = 1_000_000_000_000_000_000;
+    let payouts = annotator(budget, Rounding::LargestRemainder, &[1.0 / 3.0, 2.0 / 3.0]);
+    let payouts: Vec<u64> = payouts.payouts().into_values().collect();
+    assert_eq!(
+        payouts,
+        vec![333_333_333_333_333_333, 666_666_666_666_666_667]
+    );
+
+    let payouts = annotator(budget, Rounding::Floor, &[1.0 / 3.0, 2.0 / 3.0]);
+    let payouts: Vec<u64> = payouts.payouts().into_values().collect();
+    assert_eq!(
+        payouts,
+        vec![333_333_333_333_333_333, 666_666_666_666_666_666]
+    );
+}
+
+#[test]
+fn the_whole_budget_is_paid() {
+    let ranks: Vec<f64> = (1..100).map(|i| 1.0 / f64::from(i)).collect();
+    for budget in [0, 1, 7, 1 << 53, (1 << 53) + 1, u64::MAX] {
+        let annotator = annotator(budget, Rounding::LargestRemainder, &ranks);
+        let payouts = annotator.payouts();
+        assert_eq!(payouts.values().sum::<u64>(), budget);
+        // Larger ranks never get less.
+        let units: Vec<u64> = payouts.into_values().collect();
+        assert!(units.windows(2).all(|w| w[0] >= w[1]));
+    }
+}
+
+#[test]
+fn ranks_that_are_not_positive_get_nothing() {
+    let annotator = annotator(10, Rounding::LargestRemainder, &[0.0, -1.0, f64::NAN, 2.0]);
+    let payouts = annotator.payouts();
+    assert_eq!(payouts.len(), 1);
+    assert_eq!(payouts[&3], 10);
+}
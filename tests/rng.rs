@@ -0,0 +1,40 @@
+use oscoin_graph_api::rng::{AlgorithmSeed, SeedableAlgorithmRng};
+
+fn draws<R: SeedableAlgorithmRng>(rng: &mut R) -> Vec<u64> {
+    (0..8).map(|_| rng.next_u64()).collect()
+}
+
+#[test]
+fn splits_depend_on_the_parent_stream() {
+    let root = 7u64.into_rng();
+    let a = root.split(1);
+    let b = root.split(2);
+
+    assert_ne!(draws(&mut a.split(2)), draws(&mut root.split(2)));
+    assert_ne!(draws(&mut a.split(2)), draws(&mut b.split(2)));
+    assert_ne!(draws(&mut a.split(1)), draws(&mut a.clone()));
+    assert_ne!(draws(&mut root.split(0)), draws(&mut root.clone()));
+}
+
+#[test]
+fn splits_are_deterministic() {
+    let mut rng = 7u64.into_rng();
+    let before = rng.split(3);
+    rng.next_u64();
+    assert_eq!(draws(&mut rng.split(3)), draws(&mut before.clone()));
+    assert_eq!(
+        draws(&mut 7u64.into_rng().split(3).split(4)),
+        draws(&mut before.split(4))
+    );
+}
+
+#[test]
+fn jumps_skip_draws() {
+    let mut rng = 7u64.into_rng().split(5);
+    let mut jumped = rng.clone();
+    jumped.jump(3);
+    for _ in 0..3 {
+        rng.next_u64();
+    }
+    assert_eq!(draws(&mut rng), draws(&mut jumped));
+}
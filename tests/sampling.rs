@@ -0,0 +1,182 @@
+use std::cell::Cell;
+use std::collections::{BTreeSet, HashMap};
+use std::rc::Rc;
+
+use oscoin_graph_api::bench::Workload;
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::rng::AlgorithmSeed;
+use oscoin_graph_api::sampling::{
+    reservoir, sample_edges, sample_nodes, AliasTable, WeightedNeighbors,
+};
+use oscoin_graph_api::types::GraphDiff;
+use oscoin_graph_api::{Direction, Graph, GraphDataWriter, GraphObject};
+
+mod common;
+
+use common::{dependency, project};
+
+fn graph(edges: &[(u64, u64, f64)]) -> EdgeListGraph {
+    let edges = edges.iter().map(|&(from, to, weight)| {
+        let mut data = dependency();
+        data.weight = weight;
+        (from, to, data)
+    });
+    EdgeListGraph::from_edges(edges.collect::<Vec<_>>(), project())
+}
+
+/// Sample the table over an evenly spaced grid of `[0, 1)`, and return how
+/// often each item was picked.
+fn frequencies(table: &AliasTable<&'static str>, draws: usize) -> HashMap<&'static str, f64> {
+    let mut counts = HashMap::new();
+    for i in 0..draws {
+        let u = i as f64 / draws as f64;
+        *counts.entry(*table.sample(u)).or_insert(0.0) += 1.0 / draws as f64;
+    }
+    counts
+}
+
+#[test]
+fn alias_tables_sample_in_proportion_to_the_weights() {
+    let table = AliasTable::new(vec![("a", 1.0), ("b", 3.0), ("c", 6.0), ("d", 0.0)]).unwrap();
+    assert_eq!(table.len(), 4);
+
+    let freq = frequencies(&table, 100_000);
+    assert!((freq["a"] - 0.1).abs() < 1e-3);
+    assert!((freq["b"] - 0.3).abs() < 1e-3);
+    assert!((freq["c"] - 0.6).abs() < 1e-3);
+    assert!(!freq.contains_key("d"));
+}
+
+#[test]
+fn alias_tables_need_a_positive_weight() {
+    assert!(AliasTable::<&str>::new(vec![]).is_none());
+    assert!(AliasTable::new(vec![("a", 0.0), ("b", -1.0), ("c", f64::NAN)]).is_none());
+
+    let table = AliasTable::new(vec![("a", f64::INFINITY), ("b", 2.0)]).unwrap();
+    assert_eq!(frequencies(&table, 1000).get("a"), None);
+}
+
+#[test]
+fn neighbors_are_sampled_along_the_given_direction() {
+    let g = graph(&[(1, 2, 1.0), (1, 3, 3.0), (4, 1, 1.0)]);
+
+    let mut outgoing = WeightedNeighbors::new(Direction::Outgoing);
+    let mut picked = HashMap::new();
+    for i in 0..1000 {
+        let (to, _) = *outgoing.sample(&g, &1, i as f64 / 1000.0).unwrap();
+        *picked.entry(to).or_insert(0) += 1;
+    }
+    assert_eq!(picked.len(), 2);
+    assert_eq!(picked[&2], 250);
+    assert_eq!(picked[&3], 750);
+
+    let mut incoming = WeightedNeighbors::new(Direction::Incoming);
+    assert_eq!(incoming.sample(&g, &1, 0.5).map(|hop| hop.0), Some(4));
+    assert!(incoming.sample(&g, &4, 0.5).is_none());
+}
+
+#[test]
+fn tables_are_rebuilt_once_invalidated() {
+    let mut g = graph(&[(1, 2, 1.0), (1, 3, 0.0)]);
+    let mut neighbors = WeightedNeighbors::new(Direction::Outgoing);
+    assert_eq!(neighbors.sample(&g, &1, 0.9).map(|hop| hop.0), Some(2));
+
+    let edges = g.edges_directed(&1, Direction::Outgoing);
+    let (to_2, to_3) = if *edges[0].to == 2 {
+        (*edges[0].id, *edges[1].id)
+    } else {
+        (*edges[1].id, *edges[0].id)
+    };
+    g.edge_data_mut(&to_2).unwrap().weight = 0.0;
+    g.edge_data_mut(&to_3).unwrap().weight = 1.0;
+
+    // The stale table is kept until it's invalidated.
+    assert_eq!(neighbors.sample(&g, &1, 0.9).map(|hop| hop.0), Some(2));
+
+    neighbors.apply_diff(&GraphDiff::EdgeUpdated {
+        id: &to_3,
+        source: &1,
+        target: &3,
+    });
+    assert_eq!(neighbors.sample(&g, &1, 0.9).map(|hop| hop.0), Some(3));
+    assert_eq!(neighbors.sample(&g, &1, 0.9).map(|hop| hop.1), Some(to_3));
+}
+
+#[test]
+fn reservoirs_pick_items_uniformly() {
+    let mut rng = 0u64.into_rng();
+    assert_eq!(reservoir(0..3, 5, &mut rng), vec![0, 1, 2]);
+    assert!(reservoir(0..3, 0, &mut rng).is_empty());
+
+    let mut counts = [0; 10];
+    for seed in 0..2000u64 {
+        let picked = reservoir(0..10, 2, &mut seed.into_rng());
+        assert_eq!(picked.len(), 2);
+        assert_ne!(picked[0], picked[1]);
+        for i in picked {
+            counts[i] += 1;
+        }
+    }
+    // Every item is expected to be picked 400 times.
+    for count in counts.iter() {
+        assert!((320..480).contains(count), "{:?}", counts);
+    }
+}
+
+/// An item counting how many items are alive at once.
+struct Counted(Rc<Cell<(usize, usize)>>);
+
+impl Counted {
+    fn new(live: &Rc<Cell<(usize, usize)>>) -> Self {
+        let (count, max) = live.get();
+        live.set((count + 1, max.max(count + 1)));
+        Counted(live.clone())
+    }
+}
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        let (count, max) = self.0.get();
+        self.0.set((count - 1, max));
+    }
+}
+
+#[test]
+fn reservoirs_only_keep_the_sample() {
+    let live = Rc::new(Cell::new((0, 0)));
+    let items = (0..10_000).map(|_| Counted::new(&live));
+
+    let sample = reservoir(items, 5, &mut 1u64.into_rng());
+    assert_eq!(sample.len(), 5);
+    // The sample, and the item being considered.
+    assert_eq!(live.get(), (5, 6));
+}
+
+#[test]
+fn samples_of_a_graph_only_depend_on_the_seed() {
+    let graph: EdgeListGraph = Workload::random(100, 3, 4).build();
+
+    let nodes = |seed: u64| -> Vec<u64> {
+        sample_nodes(&graph, 10, seed)
+            .into_iter()
+            .map(|n| *n.id())
+            .collect()
+    };
+    assert_eq!(nodes(1), nodes(1));
+    assert_ne!(nodes(1), nodes(2));
+    assert_eq!(nodes(1).into_iter().collect::<BTreeSet<_>>().len(), 10);
+    assert_eq!(sample_nodes(&graph, 1000, 1).len(), 100);
+
+    let edges = |n: usize, seed: u64| -> BTreeSet<u64> {
+        sample_edges(&graph, n, seed)
+            .into_iter()
+            .map(|e| *e.id())
+            .collect()
+    };
+    assert_eq!(edges(10, 1), edges(10, 1));
+    assert_ne!(edges(10, 1), edges(10, 2));
+    assert_eq!(edges(10, 1).len(), 10);
+
+    let all = edges(usize::MAX, 1);
+    assert_eq!(all.len(), graph.edge_count());
+}
@@ -0,0 +1,146 @@
+#![cfg(feature = "sled-storage")]
+
+use std::path::PathBuf;
+
+use oscoin_graph_api::bench::Workload;
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::storage::sled::{SledConfig, SledGraph};
+use oscoin_graph_api::storage::PersistentGraph;
+use oscoin_graph_api::testing::model::{check, random_ops};
+use oscoin_graph_api::{Direction, Graph, GraphDataWriter, GraphObject, GraphWriter};
+
+fn path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("oscoin-sled-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&path);
+    path
+}
+
+/// The edges of a node, in every direction.
+fn adjacency<G>(graph: &G, node: u64) -> [Vec<u64>; 3]
+where
+    G: Graph,
+    G::Node: GraphObject<Id = u64>,
+    G::Edge: GraphObject<Id = u64>,
+{
+    let directed = |dir| {
+        graph
+            .edges_directed(&node, dir)
+            .iter()
+            .map(|e| *e.id)
+            .collect()
+    };
+    [
+        graph.edges(&node).map(|e| *e.id()).collect(),
+        directed(Direction::Outgoing),
+        directed(Direction::Incoming),
+    ]
+}
+
+#[test]
+fn reopened_graphs_match() {
+    let path = path("reopen");
+    let workload = Workload::random(64, 4, 7);
+    let expected: EdgeListGraph = workload.build();
+
+    let mut graph: SledGraph = SledGraph::open(SledConfig::new(&path)).unwrap();
+    workload.insert(&mut graph);
+    graph.node_data_mut(&3).unwrap().rank.rank = 0.5;
+    graph.close().unwrap();
+
+    let graph: SledGraph = SledGraph::open(SledConfig::new(&path)).unwrap();
+    assert_eq!(graph.cached_objects(), 0);
+    assert_eq!(graph.node_data(&3).unwrap().rank.rank, 0.5);
+    for node in expected.nodes() {
+        let id = *node.id();
+        if id != 3 {
+            assert_eq!(graph.node_data(&id), Some(node.data()));
+        }
+        assert_eq!(adjacency(&graph, id), adjacency(&expected, id));
+    }
+    let ids: Vec<u64> = graph.nodes().map(|n| *n.id()).collect();
+    assert_eq!(ids, expected.nodes().map(|n| *n.id()).collect::<Vec<_>>());
+    drop(graph);
+    std::fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn the_cache_is_trimmed() {
+    let mut config = SledConfig::new(path("cache"));
+    config.object_cache_capacity = 10;
+    config.temporary = true;
+    let workload = Workload::random(64, 4, 7);
+
+    let mut graph: SledGraph = SledGraph::open(config).unwrap();
+    workload.insert(&mut graph);
+    assert_eq!(graph.nodes().count(), 64);
+    assert!(graph.cached_objects() >= 64);
+
+    graph.node_data_mut(&1).unwrap().rank.rank = 0.25;
+    graph.flush().unwrap();
+    assert!(graph.cached_objects() <= 10);
+    graph.remove_edge(0);
+    assert!(graph.cached_objects() <= 10);
+    assert_eq!(graph.node_data(&1).unwrap().rank.rank, 0.25);
+}
+
+#[test]
+fn removed_nodes_lose_their_edges() {
+    let mut config = SledConfig::new(path("remove"));
+    config.temporary = true;
+    let workload = Workload::random(16, 4, 1);
+    let mut expected: EdgeListGraph = workload.build();
+    let mut graph: SledGraph = SledGraph::open(config).unwrap();
+    workload.insert(&mut graph);
+
+    for id in [0, 5, 9] {
+        graph.remove_node(id);
+        expected.remove_node(id);
+    }
+    for id in 0..16 {
+        for dir in [Direction::Outgoing, Direction::Incoming, Direction::Both] {
+            assert_eq!(
+                graph.degree_by_edge_type(&id, dir),
+                expected.degree_by_edge_type(&id, dir)
+            );
+        }
+    }
+    assert!(graph.get_node(&5).is_none());
+    assert_eq!(graph.nodes().count(), expected.nodes().count());
+    for id in 0..16 {
+        assert_eq!(adjacency(&graph, id), adjacency(&expected, id));
+    }
+}
+
+#[test]
+fn cleared_graphs_stay_empty() {
+    let path = path("clear");
+    let mut graph: SledGraph = SledGraph::open(SledConfig::new(&path)).unwrap();
+    Workload::random(16, 4, 2).insert(&mut graph);
+    graph.flush().unwrap();
+    graph.node_data_mut(&1).unwrap().rank.rank = 0.5;
+    graph.clear();
+    assert_eq!(graph.nodes().count(), 0);
+    assert!(adjacency(&graph, 1).iter().all(Vec::is_empty));
+    graph.close().unwrap();
+
+    let graph: SledGraph = SledGraph::open(SledConfig::new(&path)).unwrap();
+    assert_eq!(graph.nodes().count(), 0);
+    assert!(graph.get_node(&1).is_none());
+    drop(graph);
+    std::fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn sled_graphs_match_the_model() {
+    for seed in 0..50 {
+        let mut config = SledConfig::new(path(&format!("model-{}", seed)));
+        // A small cache, so that objects are evicted and read back.
+        config.object_cache_capacity = 3;
+        config.temporary = true;
+        let mut graph: SledGraph = SledGraph::open(config).unwrap();
+
+        if let Err(mismatch) = check(&mut graph, &random_ops(seed, 64)) {
+            panic!("seed {}: {}", seed, mismatch);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::BTreeMap;
+
+use oscoin_graph_api::ledger::LedgerNodeData;
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::stats::{summarize, NodeKind};
+use oscoin_graph_api::types::{EdgeTypeTag, NodeData, NodeType};
+use oscoin_graph_api::GraphWriter;
+
+mod common;
+
+use common::{dependencies, project};
+
+/// A triangle `1 -> 2 -> 3 -> 1`, with a dependency of `3` on `4` hanging off
+/// it, and an isolated user.
+fn graph() -> EdgeListGraph {
+    let edges = dependencies(&[(1, 2), (2, 3), (3, 1), (3, 4)]);
+    let mut graph = EdgeListGraph::from_edges(edges, project());
+    graph.add_node(
+        5,
+        NodeData::from_node_type(NodeType::User {
+            contributions_to_all_projects: 0,
+        }),
+    );
+    graph
+}
+
+#[test]
+fn graphs_are_summarized() {
+    let summary = summarize(&graph());
+
+    assert_eq!(summary.nodes, 5);
+    assert_eq!(summary.edges, 4);
+    assert_eq!(
+        summary.nodes_by_type,
+        vec![(NodeKind::User, 1), (NodeKind::Project, 4)]
+            .into_iter()
+            .collect::<BTreeMap<_, _>>()
+    );
+    assert_eq!(
+        summary.edges_by_type,
+        vec![(EdgeTypeTag::Dependency, 4)]
+            .into_iter()
+            .collect::<BTreeMap<_, _>>()
+    );
+    assert!((summary.density - 4.0 / 20.0).abs() < 1e-12);
+    assert!((summary.avg_degree - 0.8).abs() < 1e-12);
+    assert_eq!(summary.max_in_degree, 1);
+    assert_eq!(summary.max_out_degree, 2);
+    assert_eq!(
+        summary.degree_histogram,
+        vec![(0, 1), (1, 1), (2, 2), (3, 1)]
+            .into_iter()
+            .collect::<BTreeMap<_, _>>()
+    );
+    // Nodes 1 and 2 are fully clustered, and 3 has one linked pair out of
+    // three.
+    assert!((summary.clustering - (1.0 + 1.0 + 1.0 / 3.0) / 5.0).abs() < 1e-12);
+}
+
+#[test]
+fn empty_graphs_have_a_zero_summary() {
+    let graph: EdgeListGraph = EdgeListGraph::from_edges(Vec::new(), project());
+    let summary = summarize(&graph);
+
+    assert_eq!(summary.nodes, 0);
+    assert_eq!(summary.edges, 0);
+    assert_eq!(summary.density, 0.0);
+    assert_eq!(summary.avg_degree, 0.0);
+    assert_eq!(summary.clustering, 0.0);
+    assert!(summary.degree_histogram.is_empty());
+}
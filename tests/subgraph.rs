@@ -0,0 +1,89 @@
+use oscoin_graph_api::ledger::LedgerEdgeData;
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::stats::summarize;
+use oscoin_graph_api::subgraph::{neighborhood, SubgraphView};
+use oscoin_graph_api::types::{EdgeData, EdgeType};
+use oscoin_graph_api::{Direction, Edge, Graph, GraphObject};
+
+mod common;
+
+use common::project;
+
+/// A chain `5 -> 1 -> 2 -> 3 -> 4`, with a contribution from `2` to `6`.
+fn graph() -> EdgeListGraph {
+    let edge = |from, to, edge_type| (from, to, EdgeData::from_edge_type(edge_type));
+    EdgeListGraph::from_edges(
+        vec![
+            edge(5, 1, EdgeType::Dependency),
+            edge(1, 2, EdgeType::Dependency),
+            edge(2, 3, EdgeType::Dependency),
+            edge(3, 4, EdgeType::Dependency),
+            edge(2, 6, EdgeType::ProjectToUserContribution(1)),
+        ],
+        project(),
+    )
+}
+
+/// The sorted node and edge ids of a view.
+fn ids(view: &SubgraphView<'_, EdgeListGraph>) -> (Vec<u64>, Vec<u64>) {
+    let mut nodes: Vec<u64> = view.nodes().map(|n| *n.id()).collect();
+    let mut edges: Vec<u64> = view
+        .nodes()
+        .flat_map(|n| view.edges_directed(n.id(), Direction::Outgoing))
+        .map(|e| *e.id)
+        .collect();
+    nodes.sort_unstable();
+    edges.sort_unstable();
+    assert_eq!(
+        (nodes.len(), edges.len()),
+        (view.node_count(), view.edge_count())
+    );
+    (nodes, edges)
+}
+
+#[test]
+fn neighborhoods_span_k_hops() {
+    let graph = graph();
+    let all = |_: &_| true;
+
+    let view = neighborhood(&graph, &1, 2, Direction::Outgoing, all);
+    assert_eq!(ids(&view), (vec![1, 2, 3, 6], vec![1, 2, 4]));
+
+    let view = neighborhood(&graph, &1, 1, Direction::Both, all);
+    assert_eq!(ids(&view), (vec![1, 2, 5], vec![0, 1]));
+
+    let view = neighborhood(&graph, &1, 0, Direction::Outgoing, all);
+    assert_eq!(ids(&view), (vec![1], vec![]));
+
+    let view = neighborhood(&graph, &7, 3, Direction::Outgoing, all);
+    assert_eq!(ids(&view), (vec![], vec![]));
+}
+
+#[test]
+fn neighborhoods_only_follow_filtered_edges() {
+    let graph = graph();
+    let dependencies = |e: &<EdgeListGraph as Graph>::Edge| *e.edge_type() == EdgeType::Dependency;
+
+    let view = neighborhood(&graph, &1, 5, Direction::Outgoing, dependencies);
+    assert_eq!(ids(&view), (vec![1, 2, 3, 4], vec![1, 2, 3]));
+}
+
+#[test]
+fn views_are_graphs() {
+    let graph = graph();
+    let view = SubgraphView::induced(&graph, &[2, 3, 6, 9], |_| true);
+
+    assert!(view.contains_node(&2) && !view.contains_node(&1));
+    assert!(view.contains_edge(&2) && !view.contains_edge(&1));
+    assert!(view.get_node(&1).is_none());
+    assert!(view.get_edge(&1).is_none());
+    assert!(view.edges_directed(&1, Direction::Outgoing).is_empty());
+    assert_eq!(view.edges(&2).count(), 2);
+
+    let mut neighbors: Vec<u64> = view.neighbors(&2).map(|n| *n.id()).collect();
+    neighbors.sort_unstable();
+    assert_eq!(neighbors, vec![3, 6]);
+
+    let summary = summarize(&view);
+    assert_eq!((summary.nodes, summary.edges), (3, 2));
+}
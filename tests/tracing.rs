@@ -0,0 +1,137 @@
+#![cfg(feature = "tracing")]
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+use oscoin_graph_api::algorithms::Hits;
+use oscoin_graph_api::ledger::{Checkpoint, CheckpointApplier, Contrib, Dep};
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::types::{Hash32, HyperParameters, SipIdGenerator};
+use oscoin_graph_api::{GraphAPI, GraphAlgorithm, Layer, LayerInfo, LayeredGraphs};
+
+mod common;
+
+use common::{dependency, project};
+
+/// What was traced, in order: `span <name>` and `<level> <message>` lines.
+type Lines = Arc<Mutex<Vec<String>>>;
+
+/// A subscriber recording spans and events.
+#[derive(Default)]
+struct Recorder {
+    lines: Lines,
+    next_id: AtomicU64,
+}
+
+/// Extracts the message of an event.
+struct Message(String);
+
+impl Visit for Message {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{:?}", value);
+        }
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let line = format!("span {}", span.metadata().name());
+        self.lines.lock().unwrap().push(line);
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut message = Message(String::new());
+        event.record(&mut message);
+        let level = *event.metadata().level();
+        let line = format!("{} {}", level, message.0);
+        self.lines.lock().unwrap().push(line);
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+/// Run `f`, returning what it traced.
+fn traced(f: impl FnOnce()) -> Vec<String> {
+    let recorder = Recorder::default();
+    let lines = recorder.lines.clone();
+    tracing::subscriber::with_default(recorder, f);
+    let lines = lines.lock().unwrap().clone();
+    lines
+}
+
+const OSRANK: Layer = Layer::from_static("osrank");
+
+fn checkpoint(deps: &[u64]) -> Checkpoint<u64> {
+    Checkpoint {
+        project: 1,
+        version: b"v1".to_vec(),
+        hash: Hash32::default(),
+        deps: deps
+            .iter()
+            .map(|&node_id| Dep {
+                node_id,
+                is_added: true,
+            })
+            .collect(),
+        contributions: vec![Contrib {
+            node_id: 2,
+            contributions: 1,
+        }],
+    }
+}
+
+#[test]
+fn checkpoints_are_traced() {
+    let ids = SipIdGenerator::default();
+    let lines = traced(|| {
+        let mut api: LayeredGraphs<EdgeListGraph> = LayeredGraphs::new();
+        api.add_layer(OSRANK, LayerInfo::new(0, "osrank"), EdgeListGraph::new());
+        api.apply_checkpoint(&OSRANK, &checkpoint(&[9]), &ids)
+            .unwrap_err();
+        api.apply_checkpoint(&OSRANK, &checkpoint(&[]), &ids)
+            .unwrap();
+    });
+    assert_eq!(
+        lines,
+        vec![
+            "DEBUG adding layer",
+            "span apply_checkpoint",
+            "DEBUG rejected invalid checkpoint",
+            "span apply_checkpoint",
+            "DEBUG applied checkpoint",
+        ]
+    );
+}
+
+#[test]
+fn algorithm_steps_are_traced() {
+    let graph: EdgeListGraph = EdgeListGraph::from_edges(vec![(1, 2, dependency())], project());
+    let hits: Hits<f64> = Hits::new(HyperParameters::osrank_paper_defaults());
+
+    let lines = traced(|| {
+        let mut annotations = Vec::new();
+        hits.execute(&mut (), &graph, &mut annotations, 0).unwrap();
+    });
+    assert_eq!(lines[0], "span hits");
+    assert_eq!(lines.last().unwrap(), "DEBUG hits converged");
+    let steps = &lines[1..lines.len() - 1];
+    assert!(!steps.is_empty());
+    assert!(steps.iter().all(|line| line == "TRACE hits iteration"));
+}
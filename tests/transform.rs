@@ -0,0 +1,154 @@
+use oscoin_graph_api::mem::{ArenaGraph, EdgeListGraph};
+use oscoin_graph_api::transform::{contract_nodes, filter_map_graph, map_graph};
+use oscoin_graph_api::types::{EdgeData, NodeData};
+use oscoin_graph_api::{Direction, Graph, GraphDataWriter, GraphObject, GraphWriter};
+
+mod common;
+
+use common::{dependency, project};
+
+/// A chain `1 -> 2 -> 3`, and `1 -> 3`, with weights `1`, `2` and `3`.
+fn graph() -> EdgeListGraph {
+    let edges = [(1, 2, 1.0), (2, 3, 2.0), (1, 3, 3.0)]
+        .iter()
+        .map(|&(from, to, weight)| {
+            let mut data = dependency();
+            data.weight = weight;
+            (from, to, data)
+        });
+    EdgeListGraph::from_edges(edges.collect::<Vec<_>>(), project())
+}
+
+/// The `(id, from, to, weight)` of every edge, by id.
+fn edges<G>(graph: &G) -> Vec<(u64, u64, u64, f64)>
+where
+    G: Graph<EdgeData = EdgeData<f64>>,
+    G::Node: GraphObject<Id = u64>,
+    G::Edge: GraphObject<Id = u64>,
+{
+    let mut edges: Vec<_> = graph
+        .nodes()
+        .flat_map(|n| graph.edges_directed(n.id(), Direction::Outgoing))
+        .map(|e| (*e.id, *e.from, *e.to, graph.edge_data(e.id).unwrap().weight))
+        .collect();
+    edges.sort_by_key(|(id, _, _, _)| *id);
+    edges
+}
+
+#[test]
+fn graphs_are_mapped_into_other_backends() {
+    let source = graph();
+    let mapped: ArenaGraph = map_graph(
+        &source,
+        ArenaGraph::new(),
+        |n| {
+            let mut data = n.data().clone();
+            data.rank.rank = *n.id() as f64;
+            data
+        },
+        |e| {
+            let mut data = e.data().clone();
+            data.weight *= 10.0;
+            data
+        },
+    );
+
+    assert_eq!(mapped.nodes().count(), 3);
+    assert_eq!(mapped.node_data(&3).unwrap().rank.rank, 3.0);
+    assert_eq!(
+        edges(&mapped),
+        vec![(0, 1, 2, 10.0), (1, 2, 3, 20.0), (2, 1, 3, 30.0)]
+    );
+}
+
+#[test]
+fn dropped_nodes_take_their_edges_with_them() {
+    let source = graph();
+    let filtered: EdgeListGraph = filter_map_graph(
+        &source,
+        EdgeListGraph::new(),
+        |n| Some(n.data().clone()).filter(|_| *n.id() != 2),
+        |e| Some(e.data().clone()),
+    );
+    assert_eq!(filtered.node_count(), 2);
+    assert_eq!(edges(&filtered), vec![(2, 1, 3, 3.0)]);
+
+    let filtered: EdgeListGraph = filter_map_graph(
+        &source,
+        EdgeListGraph::new(),
+        |n| Some(n.data().clone()),
+        |e| Some(e.data().clone()).filter(|d| d.weight < 2.5),
+    );
+    assert_eq!(filtered.node_count(), 3);
+    assert_eq!(edges(&filtered), vec![(0, 1, 2, 1.0), (1, 2, 3, 2.0)]);
+}
+
+/// The graph, with node `4` in between `2` and `3`, and every node ranked
+/// after its id.
+fn ranked_graph() -> EdgeListGraph {
+    let mut graph = graph();
+    graph.add_node(4, project());
+    graph.add_edge(3, &2, &4, dependency());
+    graph.add_edge(4, &4, &3, dependency());
+    for id in 1..=4 {
+        graph.node_data_mut(&id).unwrap().rank.rank = id as f64;
+    }
+    graph
+}
+
+fn add_ranks(into: &mut NodeData<f64>, other: NodeData<f64>) {
+    into.rank.rank += other.rank.rank;
+}
+
+#[test]
+fn nodes_are_relabeled_with_their_edges() {
+    let mut graph = ranked_graph();
+    assert!(graph.relabel_node(&2, 9));
+
+    assert!(graph.get_node(&2).is_none());
+    assert_eq!(graph.node_data(&9).unwrap().rank.rank, 2.0);
+    assert_eq!(
+        edges(&graph),
+        vec![
+            (0, 1, 9, 1.0),
+            (1, 9, 3, 2.0),
+            (2, 1, 3, 3.0),
+            (3, 9, 4, 0.0),
+            (4, 4, 3, 0.0),
+        ]
+    );
+
+    // Existing targets and missing sources are refused.
+    assert!(!graph.relabel_node(&1, 3));
+    assert!(!graph.relabel_node(&7, 8));
+    assert_eq!(graph.node_count(), 4);
+}
+
+#[test]
+fn contracted_nodes_are_merged_and_rewired() {
+    let mut graph = ranked_graph();
+    assert_eq!(contract_nodes(&mut graph, vec![2, 3], 1, add_ranks), 2);
+
+    assert_eq!(graph.node_count(), 2);
+    assert_eq!(graph.node_data(&1).unwrap().rank.rank, 6.0);
+    // The edges between contracted nodes are gone.
+    assert_eq!(edges(&graph), vec![(3, 1, 4, 0.0), (4, 4, 1, 0.0)]);
+}
+
+#[test]
+fn nodes_are_contracted_into_a_new_node() {
+    let mut graph = ranked_graph();
+    assert_eq!(contract_nodes(&mut graph, vec![2, 3, 5], 10, add_ranks), 2);
+
+    assert_eq!(graph.node_data(&10).unwrap().rank.rank, 5.0);
+    assert_eq!(
+        edges(&graph),
+        vec![
+            (0, 1, 10, 1.0),
+            (2, 1, 10, 3.0),
+            (3, 10, 4, 0.0),
+            (4, 4, 10, 0.0),
+        ]
+    );
+    assert_eq!(contract_nodes(&mut graph, vec![5], 11, add_ranks), 0);
+}
@@ -0,0 +1,33 @@
+use std::collections::BTreeMap;
+
+use oscoin_graph_api::types::{ProjectId, UserId};
+
+#[test]
+fn typed_ids_behave_like_their_raw_ids() {
+    let mut projects: BTreeMap<ProjectId, &str> = BTreeMap::new();
+    projects.insert(ProjectId::new(2), "serde");
+    projects.insert(ProjectId::from(1), "rand");
+
+    // Maps keyed by typed ids can be queried with raw ids.
+    assert_eq!(projects.get(&2), Some(&"serde"));
+    assert_eq!(
+        projects.keys().map(|id| *id.raw()).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    assert_eq!(UserId::new(7u64).into_raw(), 7);
+}
+
+#[test]
+fn typed_ids_show_their_kind() {
+    assert_eq!(format!("{:?}", ProjectId::new(3)), "Project(3)");
+    assert_eq!(format!("{:?}", UserId::new("alice")), "User(\"alice\")");
+}
+
+#[cfg(feature = "quickcheck")]
+#[test]
+fn typed_ids_order_like_their_raw_ids() {
+    fn prop(a: ProjectId, b: ProjectId) -> bool {
+        a.cmp(&b) == a.raw().cmp(b.raw()) && (a == b) == (a.raw() == b.raw())
+    }
+    quickcheck::quickcheck(prop as fn(ProjectId, ProjectId) -> bool);
+}
@@ -0,0 +1,247 @@
+use std::convert::TryFrom;
+
+use oscoin_graph_api::types::{
+    AlgorithmError, ContributionLedger, EdgeType, EdgeTypeTag, HyperParameters, NodeType,
+    ParseTypeError,
+};
+
+#[test]
+fn orgs_and_checkpoints_count_contributions() {
+    let mut org = NodeType::Org {
+        contributions_to_owned_projects: 2,
+    };
+    org.add_contributions(3);
+    assert_eq!(org.total_contributions(), 5);
+    assert_eq!(
+        org,
+        NodeType::Org {
+            contributions_to_owned_projects: 5
+        }
+    );
+
+    let mut checkpoint = NodeType::Checkpoint { contributions: 0 };
+    checkpoint.set_contributions(7);
+    checkpoint.add_contributions(u32::MAX);
+    assert_eq!(
+        checkpoint,
+        NodeType::Checkpoint {
+            contributions: u32::MAX
+        }
+    );
+}
+
+#[test]
+fn donations_carry_an_amount_and_ownership_nothing() {
+    let donation = EdgeType::Donation(250);
+    assert_eq!(donation.value(), 250);
+    assert_eq!(donation.total_contributions(), 0);
+    assert_eq!(donation.to_tag(), EdgeTypeTag::Donation);
+    assert!(donation.to_tag().has_value());
+
+    for (ownership, tag) in [
+        (
+            EdgeType::OrgToProjectOwnership,
+            EdgeTypeTag::OrgToProjectOwnership,
+        ),
+        (
+            EdgeType::ProjectToOrgOwnership,
+            EdgeTypeTag::ProjectToOrgOwnership,
+        ),
+    ] {
+        assert_eq!(ownership.value(), 0);
+        assert!(!tag.has_value());
+        assert_eq!(ownership.to_tag(), tag);
+    }
+
+    let contribution = EdgeType::UserToProjectContribution(4);
+    assert_eq!(contribution.value(), contribution.total_contributions());
+}
+
+#[test]
+fn custom_types_are_tagged() {
+    let review = EdgeType::Custom {
+        tag: "review".to_owned(),
+        contributions: 3,
+    };
+    assert_eq!(review.to_tag(), EdgeTypeTag::Custom("review".to_owned()));
+    assert_eq!((review.total_contributions(), review.value()), (3, 3));
+    assert!(review.to_tag().has_value());
+}
+
+#[test]
+fn ledgers_break_contributions_down_by_counterpart() {
+    let mut ledger = ContributionLedger::new();
+    ledger.add(3u64, 2);
+    ledger.add(1, 5);
+    ledger.add(3, 1);
+    ledger.add(2, 0);
+    assert_eq!(ledger.iter().collect::<Vec<_>>(), vec![(&1, 5), (&3, 3)]);
+    assert_eq!((ledger.get(&3), ledger.get(&2)), (3, 0));
+    assert_eq!((ledger.len(), ledger.total()), (2, 8));
+
+    let mut other = ContributionLedger::new();
+    other.add(2, 4);
+    other.add(3, 1);
+    ledger.merge(&other);
+    assert_eq!(
+        ledger.iter().collect::<Vec<_>>(),
+        vec![(&1, 5), (&2, 4), (&3, 4)]
+    );
+
+    ledger.scale(|c| c / 2);
+    assert_eq!(
+        ledger.iter().collect::<Vec<_>>(),
+        vec![(&1, 2), (&2, 2), (&3, 2)]
+    );
+    ledger.scale(|c| c - 2);
+    assert!(ledger.is_empty());
+}
+
+#[test]
+fn ledger_totals_saturate() {
+    let mut ledger = ContributionLedger::new();
+    ledger.add(1u64, u32::MAX - 1);
+    ledger.add(2, 5);
+    assert_eq!(ledger.total(), u32::MAX);
+}
+
+#[test]
+fn node_types_round_trip_through_strings_and_codes() {
+    let node_types = [
+        NodeType::User {
+            contributions_to_all_projects: 3,
+        },
+        NodeType::Project {
+            contributions_from_all_users: 0,
+        },
+        NodeType::Org {
+            contributions_to_owned_projects: 12,
+        },
+        NodeType::Checkpoint { contributions: 1 },
+    ];
+    let written: Vec<String> = node_types.iter().map(|t| t.to_string()).collect();
+    assert_eq!(
+        written,
+        vec!["user(3)", "project(0)", "org(12)", "checkpoint(1)"]
+    );
+
+    for (code, node_type) in node_types.iter().enumerate() {
+        assert_eq!(node_type.code(), code as u8);
+        assert_eq!(node_type.to_string().parse().as_ref(), Ok(node_type));
+        let encoded = (node_type.code(), node_type.total_contributions());
+        assert_eq!(NodeType::try_from(encoded).as_ref(), Ok(node_type));
+    }
+    assert_eq!("project".parse(), Ok(node_types[1].clone()));
+
+    assert_eq!(
+        NodeType::try_from((4, 0)),
+        Err(ParseTypeError::UnknownCode(4))
+    );
+    assert_eq!(
+        "person(1)".parse::<NodeType>(),
+        Err(ParseTypeError::UnknownName("person".to_owned()))
+    );
+    for invalid in ["user(-1)", "user()", "user(x)"] {
+        assert_eq!(
+            invalid.parse::<NodeType>(),
+            Err(ParseTypeError::InvalidValue(invalid.to_owned()))
+        );
+    }
+}
+
+#[test]
+fn edge_types_round_trip_through_strings_and_codes() {
+    for tag in EdgeTypeTag::ALL.iter() {
+        assert_eq!(tag.to_string().parse().as_ref(), Ok(tag));
+        assert_eq!(EdgeTypeTag::try_from(tag.code()).as_ref(), Ok(tag));
+
+        let edge_type = EdgeType::from_tag(tag.clone(), 5);
+        assert_eq!(edge_type.code(), tag.code());
+        assert_eq!(edge_type.to_string().parse().as_ref(), Ok(&edge_type));
+        let encoded = (edge_type.code(), edge_type.value());
+        assert_eq!(EdgeType::try_from(encoded).as_ref(), Ok(&edge_type));
+    }
+    assert_eq!(EdgeType::Dependency.to_string(), "dependency");
+    assert_eq!(EdgeType::Donation(7).to_string(), "donation(7)");
+    assert_eq!("donation".parse(), Ok(EdgeType::Donation(0)));
+
+    let review = EdgeType::Custom {
+        tag: "review(1)".to_owned(),
+        contributions: 2,
+    };
+    assert_eq!(review.to_string(), "custom:review(1)(2)");
+    assert_eq!(review.to_string().parse(), Ok(review.clone()));
+    assert_eq!(
+        EdgeType::try_from((review.code(), 2)),
+        Err(ParseTypeError::UnknownCode(8))
+    );
+}
+
+#[test]
+fn invalid_edge_types_are_rejected() {
+    assert_eq!(
+        "dependency(3)".parse::<EdgeType>(),
+        Err(ParseTypeError::InvalidValue("dependency(3)".to_owned()))
+    );
+    assert_eq!(
+        "donation(x)".parse::<EdgeType>(),
+        Err(ParseTypeError::InvalidValue("donation(x)".to_owned()))
+    );
+    for unknown in ["funding", "custom:", "Dependency"] {
+        assert_eq!(
+            unknown.parse::<EdgeTypeTag>(),
+            Err(ParseTypeError::UnknownName(unknown.to_owned()))
+        );
+    }
+    assert_eq!(
+        ParseTypeError::UnknownCode(9).to_string(),
+        "unknown type code 9"
+    );
+}
+
+#[test]
+fn paper_defaults_weight_the_edges_of_the_paper() {
+    let paper: HyperParameters<f64> = HyperParameters::osrank_paper_defaults();
+    assert_eq!(paper.pruning_threshold, 0.0);
+    assert_eq!(paper.damping_factors.project, 0.85);
+    assert_eq!(paper.damping_factors.account, 0.85);
+    assert_eq!(paper.r_value, 10);
+    assert_eq!(paper.max_edge_weight_share, None);
+    assert_eq!(paper.max_user_rank, None);
+    assert_eq!(paper.rank_decay, None);
+
+    let weights: Vec<(String, f64)> = paper
+        .edge_weights
+        .iter()
+        .map(|(tag, weight)| (tag.to_string(), *weight))
+        .collect();
+    let mut expected = vec![
+        ("project_to_user_contribution".to_owned(), 1.0 / 7.0),
+        ("user_to_project_contribution".to_owned(), 2.0 / 5.0),
+        ("project_to_user_membership".to_owned(), 2.0 / 7.0),
+        ("user_to_project_membership".to_owned(), 3.0 / 5.0),
+        ("dependency".to_owned(), 4.0 / 7.0),
+    ];
+    expected.sort_by_key(|(tag, _)| tag.parse::<EdgeTypeTag>().unwrap());
+    assert_eq!(weights, expected);
+
+    assert_eq!(paper.get_param(&EdgeTypeTag::Dependency), &(4.0 / 7.0));
+    assert_eq!(
+        paper.try_get_param(&EdgeTypeTag::Donation),
+        Err(AlgorithmError::MissingHyperParameter(EdgeTypeTag::Donation))
+    );
+}
+
+#[test]
+fn conservative_parameters_resist_gaming() {
+    let paper: HyperParameters<f64> = HyperParameters::osrank_paper_defaults();
+    let conservative: HyperParameters<f64> = HyperParameters::conservative();
+    assert_eq!(conservative.damping_factors.project, 0.75);
+    assert_eq!(conservative.damping_factors.account, 0.75);
+    assert_eq!(conservative.r_value, 100);
+    assert_eq!(conservative.max_edge_weight_share, Some(0.5));
+    assert_eq!(conservative.max_user_rank, Some(0.01));
+    assert_eq!(conservative.edge_weights, paper.edge_weights);
+    assert_eq!(conservative.pruning_threshold, paper.pruning_threshold);
+    assert_eq!(conservative.rank_decay, None);
+}
@@ -0,0 +1,101 @@
+#![cfg(feature = "wal")]
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use oscoin_graph_api::compare::eq_by_ids;
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::testing::model::{random_ops, Model, Op};
+use oscoin_graph_api::wal::{recover, WalError, WalWriter};
+use oscoin_graph_api::{GraphDataWriter, GraphObject, GraphWriter};
+
+fn path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("oscoin-wal-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&path);
+    path
+}
+
+/// Apply random mutations to the writer, modifying data in place now and
+/// then. Edges between missing nodes are skipped, like the model does.
+fn run(wal: &mut WalWriter<EdgeListGraph>, seed: u64) {
+    let mut model = Model::new();
+
+    for (i, op) in random_ops(seed, 100).into_iter().enumerate() {
+        if !model.applies(&op) {
+            continue;
+        }
+        model.apply(&op);
+
+        match op {
+            Op::AddNode(id, data) => wal.add_node(id, data),
+            Op::RemoveNode(id) => wal.remove_node(id),
+            Op::AddEdge { id, from, to, data } => wal.add_edge(id, &from, &to, data),
+            Op::RemoveEdge(id) => wal.remove_edge(id),
+            Op::UpsertEdge { id, from, to, data } => {
+                wal.upsert_edge(id, &from, &to, data);
+            }
+            Op::Clear => wal.clear(),
+        }
+        if let Some(data) = wal.edge_data_mut(&(i as u64 % 16)) {
+            data.weight *= 0.5;
+        }
+        if i % 30 == 29 {
+            for node in wal.nodes_mut() {
+                node.data_mut().rank.rank += 1.0;
+            }
+        }
+        if i % 10 == 9 {
+            wal.sync().unwrap();
+        }
+    }
+    wal.sync().unwrap();
+}
+
+fn recovered(dir: &Path) -> Result<EdgeListGraph, WalError> {
+    recover(EdgeListGraph::new(), dir)
+}
+
+#[test]
+fn graphs_are_recovered_from_their_log() {
+    for seed in 0..10 {
+        for interval in [None, Some(7)] {
+            let dir = path(&format!("recover-{}", seed));
+            let mut wal = WalWriter::new(EdgeListGraph::new(), &dir).unwrap();
+            if let Some(entries) = interval {
+                wal = wal.with_snapshot_interval(entries);
+            }
+            run(&mut wal, seed);
+
+            assert!(eq_by_ids(&recovered(&dir).unwrap(), wal.graph()));
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+}
+
+#[test]
+fn truncated_entries_are_ignored_and_corrupt_ones_reported() {
+    let dir = path("corrupt");
+    let mut wal = WalWriter::new(EdgeListGraph::new(), &dir).unwrap();
+    run(&mut wal, 3);
+    let graph = wal.into_inner().unwrap();
+
+    // A crash in the middle of appending an entry.
+    let mut log = OpenOptions::new()
+        .append(true)
+        .open(dir.join("log"))
+        .unwrap();
+    log.write_all(b"{\"NodeAdded\":{\"id\":").unwrap();
+    assert!(eq_by_ids(&recovered(&dir).unwrap(), &graph));
+
+    log.write_all(b"\n{}\n").unwrap();
+    let lines = fs::read_to_string(dir.join("log")).unwrap().lines().count();
+    match recovered(&dir) {
+        Err(WalError::Corrupt { file, line }) => {
+            assert_eq!(file, dir.join("log"));
+            assert_eq!(line, lines - 1);
+        }
+        other => panic!("unexpected recovery: {:?}", other.map(|_| ())),
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
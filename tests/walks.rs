@@ -0,0 +1,128 @@
+use std::collections::BTreeSet;
+
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::types::GraphDiff;
+use oscoin_graph_api::walks::{GraphWalk, Walk, WalkId, WalkStore};
+use oscoin_graph_api::Graph;
+
+mod common;
+
+use common::{dependencies, project};
+
+type Store = WalkStore<EdgeListGraph>;
+
+/// A cycle `1 -> 2 -> 3 -> 1` with edges 0, 1 and 2, and `4 -> 3` with edge
+/// 3.
+fn graph() -> EdgeListGraph {
+    let edges = dependencies(&[(1, 2), (2, 3), (3, 1), (4, 3)]);
+    EdgeListGraph::from_edges(edges, project())
+}
+
+/// A walk from the first node, following the given edges to the given nodes.
+fn walk(source: u64, hops: &[(u64, u64)]) -> GraphWalk<EdgeListGraph> {
+    let mut walk = Walk::new(source);
+    for &(edge, node) in hops {
+        walk.push(edge, node);
+    }
+    walk
+}
+
+/// A store with walks `1 -> 2 -> 3`, `4 -> 3 -> 1`, `2` and `3 -> 1`.
+fn stored() -> (Store, Vec<WalkId>) {
+    let mut store = Store::new();
+    let ids = vec![
+        store.insert(walk(1, &[(0, 2), (1, 3)])),
+        store.insert(walk(4, &[(3, 3), (2, 1)])),
+        store.insert(walk(2, &[])),
+        store.insert(walk(3, &[(2, 1)])),
+    ];
+    (store, ids)
+}
+
+fn set(ids: impl Iterator<Item = WalkId>) -> BTreeSet<WalkId> {
+    ids.collect()
+}
+
+fn sources(walks: Vec<GraphWalk<EdgeListGraph>>) -> BTreeSet<u64> {
+    walks.iter().map(|w| *w.source()).collect()
+}
+
+#[test]
+fn walks_are_indexed_by_what_they_go_through() {
+    let (store, ids) = stored();
+    assert_eq!(store.len(), 4);
+    assert_eq!(store.get(ids[1]), Some(&walk(4, &[(3, 3), (2, 1)])));
+
+    assert_eq!(set(store.walks_from(&1)), set(ids[..1].iter().copied()));
+    assert_eq!(set(store.walks_from(&5)), BTreeSet::new());
+    assert_eq!(
+        set(store.walks_through_node(&3)),
+        set([ids[0], ids[1], ids[3]].iter().copied())
+    );
+    assert_eq!(
+        set(store.walks_through_edge(&2)),
+        set([ids[1], ids[3]].iter().copied())
+    );
+    assert_eq!(store.visits(&1), 3);
+    assert_eq!(store.visits(&5), 0);
+
+    // Visiting a node again counts once.
+    let mut store = Store::new();
+    store.insert(walk(1, &[(0, 2), (1, 3), (2, 1)]));
+    assert_eq!(store.visits(&1), 1);
+}
+
+#[test]
+fn removed_walks_are_unindexed() {
+    let (mut store, ids) = stored();
+    assert_eq!(store.remove(ids[0]), Some(walk(1, &[(0, 2), (1, 3)])));
+    assert_eq!(store.remove(ids[0]), None);
+    assert_eq!(store.get(ids[0]), None);
+    assert_eq!(store.len(), 3);
+    assert_eq!(store.walks_from(&1).count(), 0);
+    assert_eq!(store.walks_through_edge(&0).count(), 0);
+    assert_eq!(store.visits(&2), 1);
+
+    // Identifiers are reused.
+    assert_eq!(store.insert(walk(1, &[])), ids[0]);
+    for id in ids.iter().skip(1) {
+        store.remove(*id);
+    }
+    store.remove(ids[0]);
+    assert!(store.is_empty());
+    assert_eq!(store.visits(&1), 0);
+}
+
+#[test]
+fn changes_invalidate_the_walks_visiting_their_source() {
+    let graph = graph();
+
+    let (mut store, _) = stored();
+    let walks = store.invalidate(&GraphDiff::EdgeAdded {
+        id: &4,
+        source: &2,
+        target: &1,
+    });
+    assert_eq!(sources(walks), vec![1, 2].into_iter().collect());
+    assert_eq!(store.len(), 2);
+    assert_eq!(store.visits(&2), 0);
+
+    let (mut store, _) = stored();
+    let walks = store.invalidate(&GraphDiff::EdgeDeleted(graph.get_edge(&3).unwrap().clone()));
+    assert_eq!(sources(walks), vec![4].into_iter().collect());
+
+    let (mut store, _) = stored();
+    assert!(store.invalidate(&GraphDiff::NodeAdded(&5)).is_empty());
+    assert_eq!(store.len(), 4);
+}
+
+#[test]
+fn walks_from_deleted_nodes_are_dropped() {
+    let graph = graph();
+    let (mut store, _) = stored();
+    let deleted = GraphDiff::NodeDeleted(graph.get_node(&3).unwrap().clone());
+    let walks = store.invalidate(&deleted);
+    assert_eq!(sources(walks), vec![1, 4].into_iter().collect());
+    assert_eq!(store.len(), 1);
+    assert_eq!(store.visits(&3), 0);
+}
@@ -0,0 +1,41 @@
+#![cfg(feature = "wasm")]
+//! Natively, only the paths not calling into JavaScript can run: errors, for
+//! one, are built by the JavaScript side.
+
+use oscoin_graph_api::bench::Workload;
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::snapshot::compact;
+use oscoin_graph_api::wasm::{RankedNode, Snapshot};
+use oscoin_graph_api::{Graph, GraphDataWriter, GraphObject};
+
+#[test]
+fn snapshots_answer_rank_queries() {
+    let mut graph: EdgeListGraph = Workload::random(20, 2, 9).build();
+    let ids: Vec<u64> = graph.nodes().map(|n| *n.id()).collect();
+    for (i, id) in ids.iter().enumerate() {
+        graph.node_data_mut(id).unwrap().rank.rank = i as f64 / 100.0;
+    }
+    let mut bytes = Vec::new();
+    compact::write(&graph, &mut bytes).unwrap();
+
+    let snapshot = Snapshot::new(bytes).unwrap();
+    assert_eq!(snapshot.node_count(), 20);
+    assert_eq!(snapshot.edge_count(), graph.edge_count());
+    assert_eq!(snapshot.rank(ids[3]), Some(0.03));
+    assert_eq!(snapshot.rank(u64::MAX), None);
+
+    assert_eq!(
+        snapshot.top_k(2),
+        vec![
+            RankedNode {
+                id: ids[19],
+                rank: 0.19
+            },
+            RankedNode {
+                id: ids[18],
+                rank: 0.18
+            },
+        ]
+    );
+    assert_eq!(snapshot.top_k(100).len(), 20);
+}
@@ -0,0 +1,115 @@
+use std::thread;
+
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::watch::{GraphEvent, Observable, Watched};
+use oscoin_graph_api::{
+    GraphAPI, GraphDataWriter, GraphObject, GraphWriter, Layer, LayerError, LayerInfo,
+    LayeredGraphs,
+};
+
+mod common;
+
+use common::{dependency, project};
+
+#[test]
+fn subscribers_receive_mutations_from_now_on() {
+    let mut graph = Watched::new(EdgeListGraph::new());
+    graph.add_node(1, project());
+
+    let subscription = graph.subscribe();
+    graph.add_node(2, project());
+    graph.add_edge(7, &1, &2, dependency());
+    graph.edge_data_mut(&7).unwrap().weight = 0.5;
+    graph.node_data_mut(&2).unwrap().rank.rank = 0.1;
+    graph.remove_edge(7);
+    graph.remove_node(1);
+
+    assert_eq!(
+        subscription.pending().collect::<Vec<_>>(),
+        vec![
+            GraphEvent::NodeAdded(2),
+            GraphEvent::EdgeAdded {
+                id: 7,
+                from: 1,
+                to: 2
+            },
+            GraphEvent::EdgeUpdated(7),
+            GraphEvent::NodeUpdated(2),
+            GraphEvent::EdgeRemoved(7),
+            GraphEvent::NodeRemoved(1),
+        ]
+    );
+    assert_eq!(subscription.try_next(), None);
+}
+
+#[test]
+fn edges_modified_in_place_are_updated() {
+    let mut graph = Watched::new(EdgeListGraph::new());
+    graph.add_node(1, project());
+    graph.add_node(2, project());
+    graph.add_edge(7, &1, &2, dependency());
+    graph.add_edge(8, &2, &1, dependency());
+
+    let subscription = graph.subscribe();
+    for edge in graph.edges_mut() {
+        edge.data_mut().weight = 0.5;
+    }
+    assert_eq!(
+        subscription.pending().collect::<Vec<_>>(),
+        vec![GraphEvent::EdgeUpdated(7), GraphEvent::EdgeUpdated(8)]
+    );
+}
+
+#[test]
+fn dropped_subscriptions_are_forgotten() {
+    let mut graph = Watched::new(EdgeListGraph::new());
+    let kept = graph.subscribe();
+    let dropped = graph.subscribe();
+    assert_eq!(graph.subscriber_count(), 2);
+
+    drop(dropped);
+    graph.add_node(1, project());
+    assert_eq!(graph.subscriber_count(), 1);
+    assert_eq!(kept.try_next(), Some(GraphEvent::NodeAdded(1)));
+}
+
+#[test]
+fn subscriptions_end_with_the_graph() {
+    let mut graph = Watched::new(EdgeListGraph::new());
+    let subscription = graph.subscribe();
+
+    let events = thread::spawn(move || subscription.collect::<Vec<_>>());
+    graph.add_node(1, project());
+    graph.add_node(2, project());
+    drop(graph.into_inner());
+
+    assert_eq!(
+        events.join().unwrap(),
+        vec![GraphEvent::NodeAdded(1), GraphEvent::NodeAdded(2)]
+    );
+}
+
+#[test]
+fn layers_can_be_subscribed_to() {
+    let osrank = Layer::from_static("osrank");
+    let mut api = LayeredGraphs::new();
+    api.add_layer(
+        osrank.clone(),
+        LayerInfo::new(1, "osrank"),
+        Watched::new(EdgeListGraph::new()),
+    );
+
+    let subscription = api.subscribe(&osrank).unwrap();
+    api.graph_mut(&osrank).unwrap().add_node(1, project());
+    assert_eq!(subscription.try_next(), Some(GraphEvent::NodeAdded(1)));
+
+    // Removing the layer ends the subscription.
+    api.remove_layer_cascade(&osrank).unwrap();
+    assert_eq!(subscription.collect::<Vec<_>>(), vec![]);
+
+    let missing = Layer::from_static("missing");
+    assert_eq!(
+        api.subscribe(&missing).err(),
+        Some(LayerError::NotFound(missing))
+    );
+}
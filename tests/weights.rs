@@ -0,0 +1,143 @@
+use oscoin_graph_api::ledger::LedgerEdgeData;
+use oscoin_graph_api::mem::EdgeListGraph;
+use oscoin_graph_api::types::{EdgeData, EdgeType, EdgeTypeTag, HyperParameters};
+use oscoin_graph_api::weights::{
+    assign_weights, cap_edge_weight_shares, normalize_outgoing_weights, ContributionWeight,
+};
+use oscoin_graph_api::{Direction, Graph};
+
+mod common;
+
+use common::project;
+
+fn graph(edges: &[(u64, u64, f64)]) -> EdgeListGraph {
+    let edges: Vec<_> = edges
+        .iter()
+        .map(|&(from, to, weight)| (from, to, EdgeType::Dependency, weight))
+        .collect();
+    typed_graph(&edges)
+}
+
+fn typed_graph(edges: &[(u64, u64, EdgeType, f64)]) -> EdgeListGraph {
+    let edges = edges.iter().map(|(from, to, edge_type, weight)| {
+        let mut data = EdgeData::from_edge_type(edge_type.clone());
+        data.weight = *weight;
+        (*from, *to, data)
+    });
+    EdgeListGraph::from_edges(edges.collect::<Vec<_>>(), project())
+}
+
+fn weights(graph: &EdgeListGraph, from: u64) -> Vec<f64> {
+    let mut weights: Vec<f64> = graph
+        .edges_directed(&from, Direction::Outgoing)
+        .into_iter()
+        .filter_map(|e| graph.edge_data(e.id))
+        .map(|d| d.weight)
+        .collect();
+    weights.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    weights
+}
+
+fn capped(share: f64) -> HyperParameters<f64> {
+    HyperParameters {
+        max_edge_weight_share: Some(share),
+        ..HyperParameters::osrank_paper_defaults()
+    }
+}
+
+#[test]
+fn shares_are_capped_and_the_excess_redistributed() {
+    let mut g = graph(&[(1, 2, 0.7), (1, 3, 0.2), (1, 4, 0.1)]);
+    cap_edge_weight_shares(&mut g, &capped(0.5));
+
+    let w = weights(&g, 1);
+    assert!((w[2] - 0.5).abs() < 1e-12);
+    assert!((w[1] - 0.2 * 0.5 / 0.3).abs() < 1e-12);
+    assert!((w.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+}
+
+#[test]
+fn nodes_unable_to_meet_the_cap_keep_their_weights() {
+    // Node 1 has a single edge, node 5 two edges, one of them weightless.
+    let mut g = graph(&[(1, 2, 1.0), (5, 6, 0.9), (5, 7, 0.0)]);
+    cap_edge_weight_shares(&mut g, &capped(0.5));
+
+    assert_eq!(weights(&g, 1), vec![1.0]);
+    assert_eq!(weights(&g, 5), vec![0.0, 0.9]);
+}
+
+#[test]
+fn outgoing_weights_are_normalized_per_edge_type() {
+    let mut g = typed_graph(&[
+        (1, 2, EdgeType::Dependency, 1.0),
+        (1, 3, EdgeType::Dependency, 3.0),
+        (1, 4, EdgeType::ProjectToUserContribution(2), 0.0),
+        (1, 5, EdgeType::ProjectToUserContribution(5), 0.0),
+    ]);
+    normalize_outgoing_weights(&mut g, &HyperParameters::osrank_paper_defaults());
+
+    let by_target = |to: u64| {
+        let edges = g.edges_directed(&1, Direction::Outgoing);
+        let edge = edges.into_iter().find(|e| *e.to == to).unwrap();
+        g.edge_data(edge.id).unwrap().weight
+    };
+    // Relative weights are kept within a type, and zero weights split.
+    assert!((by_target(2) - 1.0 / 7.0).abs() < 1e-12);
+    assert!((by_target(3) - 3.0 / 7.0).abs() < 1e-12);
+    assert!((by_target(4) - 1.0 / 14.0).abs() < 1e-12);
+    assert!((by_target(5) - 1.0 / 14.0).abs() < 1e-12);
+}
+
+#[test]
+fn weights_are_derived_from_contributions() {
+    let mut g = typed_graph(&[
+        (1, 2, EdgeType::Dependency, 0.0),
+        (1, 3, EdgeType::ProjectToUserContribution(5), 0.0),
+    ]);
+    let hyper = HyperParameters::osrank_paper_defaults();
+    assign_weights(&mut g, &ContributionWeight, &hyper);
+    // The contribution edge is weighted by its 5 contributions.
+    let w = weights(&g, 1);
+    assert!((w[0] - 4.0 / 7.0).abs() < 1e-12);
+    assert!((w[1] - 5.0 / 7.0).abs() < 1e-12);
+
+    let constant = |_: &EdgeType, _: &HyperParameters<f64>| 2.0;
+    assign_weights(&mut g, &constant, &hyper);
+    assert_eq!(weights(&g, 1), vec![2.0, 2.0]);
+}
+
+#[test]
+fn donations_are_weighted_by_their_amount() {
+    let mut g = typed_graph(&[
+        (1, 2, EdgeType::Donation(30), 0.0),
+        (1, 3, EdgeType::OrgToProjectOwnership, 0.0),
+    ]);
+    let mut hyper = HyperParameters::osrank_paper_defaults();
+    hyper.edge_weights.insert(EdgeTypeTag::Donation, 0.1);
+    hyper
+        .edge_weights
+        .insert(EdgeTypeTag::OrgToProjectOwnership, 0.5);
+    assign_weights(&mut g, &ContributionWeight, &hyper);
+
+    let w = weights(&g, 1);
+    assert!((w[0] - 0.5).abs() < 1e-12);
+    assert!((w[1] - 3.0).abs() < 1e-12);
+}
+
+#[test]
+fn custom_edges_are_weighted_by_their_tag() {
+    let custom = |tag: &str| EdgeType::Custom {
+        tag: tag.to_owned(),
+        contributions: 4,
+    };
+    let mut g = typed_graph(&[(1, 2, custom("review"), 0.0), (1, 3, custom("audit"), 0.0)]);
+    let mut hyper = HyperParameters::osrank_paper_defaults();
+    hyper
+        .edge_weights
+        .insert(EdgeTypeTag::Custom("review".to_owned()), 0.25);
+    hyper
+        .edge_weights
+        .insert(EdgeTypeTag::Custom("audit".to_owned()), 0.5);
+    assign_weights(&mut g, &ContributionWeight, &hyper);
+    assert_eq!(weights(&g, 1), vec![1.0, 2.0]);
+}